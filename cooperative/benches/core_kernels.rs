@@ -0,0 +1,157 @@
+//! Micro-benchmarks of the performance-critical kernels on small synthetic inputs.
+//!
+//! Run with `cargo bench -p cooperative --bench core_kernels`.
+//! The inputs are generated deterministically, so numbers are comparable across refactorings.
+
+use std::f64::consts::PI;
+
+use cooperative::dijkstra::potentials::multi_metric_potential::customization::{build_metric_entries, extract_metrics};
+use cooperative::dijkstra::potentials::multi_metric_potential::interval_patterns::complete_balanced_interval_pattern;
+use cooperative::graph::capacity_graph::CapacityGraph;
+use cooperative::graph::edge_buckets::CapacityBuckets;
+use cooperative::graph::traffic_functions::BPRTrafficFunction;
+use cooperative::graph::MAX_BUCKETS;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use rust_road_router::algo::customizable_contraction_hierarchy::query::stepped_elimination_tree::EliminationTreeWalk;
+use rust_road_router::algo::customizable_contraction_hierarchy::{customize, CCH, CCHT};
+use rust_road_router::datastr::graph::floating_time_dependent::{FlWeight, PeriodicPiecewiseLinearFunction, TTFPoint, Timestamp};
+use rust_road_router::datastr::graph::time_dependent::Timestamp as IntTimestamp;
+use rust_road_router::datastr::graph::{EdgeId, FirstOutGraph, Graph, NodeId, Weight};
+use rust_road_router::datastr::node_order::NodeOrder;
+use rust_road_router::datastr::timestamped_vector::TimestampedVector;
+
+const GRID_SIZE: usize = 64;
+const NUM_PLF_POINTS: usize = 500;
+
+/// bidirectional grid graph with `size * size` nodes, travel times vary deterministically between 1000 and 2000
+fn grid_graph(size: usize) -> (Vec<EdgeId>, Vec<NodeId>, Vec<Weight>) {
+    let mut first_out = vec![0];
+    let mut head = Vec::new();
+    let mut weight = Vec::new();
+
+    for node in 0..size * size {
+        let (row, col) = (node / size, node % size);
+        let neighbors = [
+            (row > 0).then(|| node - size),
+            (col > 0).then(|| node - 1),
+            (col + 1 < size).then(|| node + 1),
+            (row + 1 < size).then(|| node + size),
+        ];
+
+        for next in neighbors.iter().flatten() {
+            head.push(*next as NodeId);
+            weight.push(1000 + ((node * 31 + next * 17) % 1000) as Weight);
+        }
+        first_out.push(head.len() as EdgeId);
+    }
+
+    (first_out, head, weight)
+}
+
+/// periodic travel time function with `num_points` points and the given number of peaks per day
+fn periodic_ttf(num_points: usize, num_peaks: f64, phase: f64) -> Vec<TTFPoint> {
+    (0..=num_points)
+        .map(|idx| {
+            let at = 86400.0 * idx as f64 / num_points as f64;
+            let val = 600.0 + 200.0 * (2.0 * PI * num_peaks * at / 86400.0 + phase).sin();
+            TTFPoint {
+                at: Timestamp::new(at),
+                val: FlWeight::new(val),
+            }
+        })
+        .collect()
+}
+
+fn plf_link_merge(c: &mut Criterion) {
+    let first = periodic_ttf(NUM_PLF_POINTS, 3.0, 0.0);
+    let second = periodic_ttf(NUM_PLF_POINTS, 5.0, 1.0);
+    let first_plf = PeriodicPiecewiseLinearFunction::new(&first);
+    let second_plf = PeriodicPiecewiseLinearFunction::new(&second);
+
+    c.bench_function("plf_link", |b| b.iter(|| black_box(first_plf.link(&second_plf))));
+
+    let mut buffer = Vec::new();
+    c.bench_function("plf_merge", |b| b.iter(|| black_box(first_plf.merge(&second_plf, &mut buffer))));
+}
+
+fn elimination_tree_walk(c: &mut Criterion) {
+    let (first_out, head, weight) = grid_graph(GRID_SIZE);
+    let graph = FirstOutGraph::new(first_out, head, weight);
+    let cch = CCH::fix_order_and_build(&graph, NodeOrder::identity(GRID_SIZE * GRID_SIZE));
+    let customized = customize(&cch, &graph);
+    let forward = customized.forward_graph();
+
+    let mut distances = TimestampedVector::new(cch.num_nodes());
+    let mut predecessors = vec![0; cch.num_nodes()];
+    let mut source = 0;
+
+    c.bench_function("elimination_tree_walk", |b| {
+        b.iter(|| {
+            source = (source + 7919) % cch.num_nodes() as NodeId;
+            let walk = EliminationTreeWalk::query(&forward, cch.elimination_tree(), &mut distances, &mut predecessors, source);
+            black_box(walk.count())
+        })
+    });
+}
+
+fn interval_minima(c: &mut Criterion) {
+    let num_edges = 1000;
+    let num_points = 96;
+    let departures = (0..num_edges)
+        .map(|_| (0..=num_points).map(|idx| idx * (MAX_BUCKETS / num_points)).collect::<Vec<IntTimestamp>>())
+        .collect::<Vec<Vec<IntTimestamp>>>();
+    let travel_times = (0..num_edges)
+        .map(|edge| {
+            (0..=num_points)
+                .map(|idx| 60_000 + ((edge * 131 + (idx as usize % num_points as usize) * 977) % 30_000) as Weight)
+                .collect::<Vec<Weight>>()
+        })
+        .collect::<Vec<Vec<Weight>>>();
+    let entries = build_metric_entries(&complete_balanced_interval_pattern());
+
+    c.bench_function("interval_minima", |b| {
+        b.iter(|| black_box(extract_metrics(&departures, &travel_times, &entries)))
+    });
+}
+
+fn bucket_updates(c: &mut Criterion) {
+    let timestamps = (0..1000u32).map(|idx| (idx * 7_919_993) % MAX_BUCKETS).collect::<Vec<IntTimestamp>>();
+
+    c.bench_function("capacity_bucket_increase", |b| {
+        b.iter_batched(
+            || CapacityBuckets::Used(Vec::new()),
+            |mut buckets| {
+                for &ts in &timestamps {
+                    buckets.increase(ts, 1);
+                }
+                buckets
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    let (first_out, head, weight) = grid_graph(GRID_SIZE);
+    let num_edges = head.len();
+    let mut graph = CapacityGraph::new(
+        50,
+        first_out.clone(),
+        head,
+        vec![30; num_edges],
+        weight,
+        vec![1000; num_edges],
+        BPRTrafficFunction::default(),
+    );
+
+    // path along the first row of the grid, the second edge of each node points to the right neighbor (except for the first node)
+    let path = (0..GRID_SIZE as NodeId - 1)
+        .map(|node| first_out[node as usize] + if node == 0 { 0 } else { 1 })
+        .collect::<Vec<EdgeId>>();
+    let departures = (0..path.len() as u32).map(|idx| 28_800_000 + idx * 2000).collect::<Vec<IntTimestamp>>();
+
+    c.bench_function("capacity_graph_increase_weights", |b| {
+        b.iter(|| black_box(graph.increase_weights(&path, &departures, 1)))
+    });
+}
+
+criterion_group!(benches, plf_link_merge, elimination_tree_walk, interval_minima, bucket_updates);
+criterion_main!(benches);
@@ -1,12 +1,15 @@
+use cooperative::dijkstra::cached_server::CachedCapacityServer;
 use cooperative::dijkstra::potentials::multi_metric_potential::customization::CustomizedMultiMetrics;
-use cooperative::dijkstra::potentials::multi_metric_potential::interval_patterns::complete_balanced_interval_pattern;
+use cooperative::dijkstra::potentials::multi_metric_potential::interval_patterns::{complete_balanced_interval_pattern, interval_pattern_from_env};
 use cooperative::dijkstra::server::{CapacityServer, CapacityServerOps};
 use cooperative::experiments::queries::permutate_queries;
+use cooperative::graph::speed_blend::SpeedBlend;
 use cooperative::graph::traffic_functions::BPRTrafficFunction;
-use cooperative::io::io_graph::{load_capacity_graph, store_speed_buckets};
-use cooperative::io::io_node_order::load_node_order;
+use cooperative::io::io_graph::{load_capacity_graph, store_learned_speed_profiles};
+use cooperative::io::io_historic_speeds::load_historic_speeds;
+use cooperative::io::io_node_order::load_coordinate_aware_node_order;
 use cooperative::io::io_queries::load_queries;
-use cooperative::util::cli_args::parse_arg_required;
+use cooperative::util::cli_args::{parse_arg_optional, parse_arg_required};
 use rust_road_router::algo::customizable_contraction_hierarchy::CCH;
 use std::env;
 use std::error::Error;
@@ -18,9 +21,16 @@ use std::time::Instant;
 /// Runs a given query set and stores the resulting speed buckets.
 /// The resulting data can be used in the actual query phase to support predictions of future traffic conditions
 ///
-/// Additional parameters: <path_to_graph> <path_to_queries> <output_path> <num_buckets>
+/// Additional parameters: <path_to_graph> <path_to_queries> <output_path> <num_buckets> <history_directory=> <cache_capacity=0>
+///
+/// If <history_directory> is given, the speeds stored by a previous run (or a CSV/JSON file, see `load_historic_speeds`) are used as historic speeds.
+/// The exported speeds then combine both runs, so repeated runs accumulate the observed congestion.
+///
+/// With a <cache_capacity> > 0, the paths of up to that many OD pairs (per departure bucket) are cached and reused, see `CachedCapacityServer`.
+///
+/// Set `INTERVAL_PATTERN` to override the interval pattern of the multi-metric potential, see `parse_interval_pattern`.
 fn main() -> Result<(), Box<dyn Error>> {
-    let (graph_directory, query_directory, output_directory, num_buckets) = parse_args()?;
+    let (graph_directory, query_directory, output_directory, num_buckets, history_directory, cache_capacity) = parse_args()?;
 
     let graph_path = Path::new(&graph_directory);
     let query_path = graph_path.join("queries").join(&query_directory);
@@ -35,16 +45,21 @@ fn main() -> Result<(), Box<dyn Error>> {
     }
 
     // load graph and queries
-    let graph = load_capacity_graph(&graph_path, num_buckets, BPRTrafficFunction::default())?;
+    let mut graph = load_capacity_graph(&graph_path, num_buckets, BPRTrafficFunction::default())?;
+    if !history_directory.is_empty() {
+        println!("Using the speeds of {} as historic speeds", history_directory);
+        let historic_speeds = load_historic_speeds(&speed_path.join(&history_directory), &graph)?;
+        graph.add_historic_speeds(historic_speeds, SpeedBlend::Minimum);
+    }
     let mut queries = load_queries(&query_path)?;
     permutate_queries(&mut queries);
 
     // init potential and server
-    let order = load_node_order(&graph_path)?;
+    let order = load_coordinate_aware_node_order(&graph_path, &graph)?;
     let cch = CCH::fix_order_and_build(&graph, order);
-    let interval_pattern = complete_balanced_interval_pattern();
+    let interval_pattern = interval_pattern_from_env(complete_balanced_interval_pattern)?;
     let customized = CustomizedMultiMetrics::new_from_capacity(cch, &graph, &interval_pattern, 20);
-    let mut server = CapacityServer::new(graph, customized);
+    let mut server = CachedCapacityServer::new(CapacityServer::new(graph, customized), cache_capacity);
 
     let mut time = Instant::now();
 
@@ -64,27 +79,41 @@ fn main() -> Result<(), Box<dyn Error>> {
 
         // customize graph regularly
         if (idx + 1) % 50000 == 0 {
-            server.customize(&interval_pattern, 20);
+            server.borrow_server_mut().customize(&interval_pattern, 20);
         }
 
-        if !server.result_valid() || !server.update_valid() {
-            // re-customization of upper bounds
+        if !server.result_valid() || !server.borrow_server().update_valid() {
+            // re-customization of the potential
             println!("-- {} - potential update after {} steps", num_buckets, idx + 1);
-            server.customize_upper_bound();
+            server.borrow_server_mut().restore_potential(&interval_pattern, 20);
         }
     }
 
+    if cache_capacity > 0 {
+        let (num_hits, num_misses) = server.cache_statistics();
+        println!("Path cache: {} hits, {} misses", num_hits, num_misses);
+    }
+
     println!("Finished queries, starting to extract and store the speed buckets..");
-    store_speed_buckets(&output_path, server.borrow_graph())
+    store_learned_speed_profiles(&output_path, server.borrow_server().borrow_graph())
 }
 
-fn parse_args() -> Result<(String, String, String, u32), Box<dyn Error>> {
+fn parse_args() -> Result<(String, String, String, u32, String, usize), Box<dyn Error>> {
     let mut args = env::args().skip(1);
 
     let graph_directory = parse_arg_required(&mut args, "Graph Directory")?;
     let query_directory = parse_arg_required(&mut args, "Query Directory")?;
     let output_directory = parse_arg_required(&mut args, "Output Directory")?;
     let num_buckets = parse_arg_required(&mut args, "Num Buckets")?;
+    let history_directory = parse_arg_optional(&mut args, String::new());
+    let cache_capacity = parse_arg_optional(&mut args, 0);
 
-    Ok((graph_directory, query_directory, output_directory, num_buckets))
+    Ok((
+        graph_directory,
+        query_directory,
+        output_directory,
+        num_buckets,
+        history_directory,
+        cache_capacity,
+    ))
 }
@@ -0,0 +1,75 @@
+use cooperative::dijkstra::potentials::multi_metric_potential::customization::CustomizedMultiMetrics;
+use cooperative::dijkstra::potentials::multi_metric_potential::interval_patterns::{complete_balanced_interval_pattern, interval_pattern_from_env};
+use cooperative::dijkstra::server::{CapacityServer, CapacityServerOps};
+use cooperative::graph::traffic_functions::BPRTrafficFunction;
+use cooperative::io::io_coordinates::load_coords;
+use cooperative::io::io_graph::load_capacity_graph;
+use cooperative::io::io_node_order::load_coordinate_aware_node_order;
+use cooperative::io::io_queries::load_queries;
+use cooperative::io::io_search_space::store_search_space_geojson;
+use cooperative::util::cli_args::{parse_arg_optional, parse_arg_required};
+use rust_road_router::algo::customizable_contraction_hierarchy::CCH;
+use std::env;
+use std::error::Error;
+use std::path::Path;
+
+/// Dumps the search space of a single query as GeoJSON point layer, e.g. to inspect pathological searches on a map.
+///
+/// The queries before `query_index` are executed first (with updates), so the dumped query runs on the same traffic state
+/// as in a cooperative run over the query set.
+///
+/// Additional parameters: <path_to_graph> <query_directory> <query_index> <output_file> <num_buckets = 50>
+///
+/// Set `INTERVAL_PATTERN` to override the interval pattern of the multi-metric potential, see `parse_interval_pattern`.
+fn main() -> Result<(), Box<dyn Error>> {
+    let (graph_directory, query_directory, query_index, output_file, num_buckets) = parse_args()?;
+    let graph_path = Path::new(&graph_directory);
+
+    let graph = load_capacity_graph(&graph_path, num_buckets, BPRTrafficFunction::default())?;
+    let (lon, lat) = load_coords(&graph_path)?;
+    let queries = load_queries(&graph_path.join("queries").join(&query_directory))?;
+    if query_index >= queries.len() {
+        return Err(format!("invalid query index {} ({} queries)", query_index, queries.len()).into());
+    }
+
+    let order = load_coordinate_aware_node_order(&graph_path, &graph)?;
+    let cch = CCH::fix_order_and_build(&graph, order);
+    let interval_pattern = interval_pattern_from_env(complete_balanced_interval_pattern)?;
+    let customized = CustomizedMultiMetrics::new_from_capacity(cch, &graph, &interval_pattern, 20);
+    let mut server = CapacityServer::new(graph, customized);
+
+    // build up the traffic state of the preceding queries
+    for query in &queries[..query_index] {
+        server.query(query, true);
+        if !server.result_valid() || !server.update_valid() {
+            server.restore_potential(&interval_pattern, 20);
+        }
+    }
+
+    server.set_search_space_recording(true);
+    let result = server.query_measured(&queries[query_index], false);
+    let search_space = server.search_space().unwrap();
+    println!(
+        "Query {}: distance {:?}, potential {:?}, {} settled nodes",
+        query_index,
+        result.distance_result.distance,
+        result.distance_result.potential,
+        search_space.len()
+    );
+
+    store_search_space_geojson(Path::new(&output_file), search_space, &lat, &lon)?;
+    println!("Stored search space in {}", output_file);
+    Ok(())
+}
+
+fn parse_args() -> Result<(String, String, usize, String, u32), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+
+    let graph_directory = parse_arg_required(&mut args, "Graph Directory")?;
+    let query_directory = parse_arg_required(&mut args, "Query Directory")?;
+    let query_index = parse_arg_required(&mut args, "Query Index")?;
+    let output_file = parse_arg_required(&mut args, "Output File")?;
+    let num_buckets = parse_arg_optional(&mut args, 50);
+
+    Ok((graph_directory, query_directory, query_index, output_file, num_buckets))
+}
@@ -0,0 +1,179 @@
+use cooperative::dijkstra::model::PathResult;
+use cooperative::dijkstra::potentials::init_cch_potential::init_cch_potential;
+use cooperative::dijkstra::server::{CapacityServer, CapacityServerOps};
+use cooperative::graph::traffic_functions::BPRTrafficFunction;
+use cooperative::graph::PCE_SCALE;
+use cooperative::io::io_graph::load_capacity_graph;
+use cooperative::io::io_node_order::load_coordinate_aware_node_order;
+use cooperative::io::io_paths::load_assigned_paths;
+use cooperative::util::cli_args::{parse_arg_optional, parse_arg_required};
+use rust_road_router::algo::{GenQuery, TDQuery};
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{EdgeId, NodeId};
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+/// Equilibrium diagnostics of an assignment, e.g. stored by `rolling_horizon`, `path_swapping_assignment` or `logit_route_choice`.
+///
+/// The final graph state is restored by replaying all assigned paths with their loads and departures.
+/// While replaying, the Beckmann objective and the total travel time of the inserted paths are recorded at regular checkpoints
+/// (`equilibrium_convergence.csv`). On the final graph, the excess cost of each OD pair (flow-weighted average travel time
+/// minus the currently shortest travel time) is written to `equilibrium_od.csv`. The delay over free-flow time is attributed
+/// to the traversed edges, the 1000 most delaying edges are written to `equilibrium_edges.csv`.
+///
+/// Additional parameters: <path_to_graph> <path_to_assigned_paths> <num_buckets> <num_checkpoints=20>
+fn main() -> Result<(), Box<dyn Error>> {
+    let (graph_directory, paths_directory, num_buckets, num_checkpoints) = parse_args()?;
+
+    let graph_path = Path::new(&graph_directory);
+    let paths_path = graph_path.join(&paths_directory);
+
+    let paths = load_assigned_paths(&paths_path)?;
+
+    // init graph and server with a free-flow potential, which stays valid for any load
+    let graph = load_capacity_graph(&graph_path, num_buckets, BPRTrafficFunction::default())?;
+    let order = load_coordinate_aware_node_order(&graph_path, &graph)?;
+    let cch_pot_data = init_cch_potential(&graph, order);
+    let mut server = CapacityServer::new(graph, cch_pot_data.forward_potential());
+
+    // 1. replay all paths, record the convergence data at each checkpoint
+    let checkpoint_size = ((paths.len() + num_checkpoints - 1) / num_checkpoints).max(1);
+    let mut convergence_file = File::create(&paths_path.join("equilibrium_convergence.csv"))?;
+    convergence_file.write("num_paths,beckmann_objective,total_travel_time\n".as_bytes())?;
+
+    for (idx, path) in paths.iter().enumerate() {
+        server.update(path);
+
+        if (idx + 1) % checkpoint_size == 0 || idx + 1 == paths.len() {
+            let line = format!(
+                "{},{},{}\n",
+                idx + 1,
+                server.borrow_graph().beckmann_objective(),
+                total_travel_time(&server, &paths[..=idx])
+            );
+            convergence_file.write(line.as_bytes())?;
+        }
+    }
+
+    // 2. group the paths by OD pair, evaluate them on the final graph
+    let mut od_pairs = BTreeMap::<(NodeId, NodeId, Timestamp), Vec<&PathResult>>::new();
+    for path in paths.iter().filter(|path| !path.edge_path.is_empty()) {
+        let key = (path.node_path[0], *path.node_path.last().unwrap(), path.departure[0]);
+        od_pairs.entry(key).or_default().push(path);
+    }
+
+    let mut od_file = File::create(&paths_path.join("equilibrium_od.csv"))?;
+    od_file.write("from,to,departure,flow,num_paths,avg_travel_time,shortest_travel_time,excess_cost\n".as_bytes())?;
+
+    let (mut total_cost, mut shortest_cost, mut total_flow, mut max_excess) = (0.0, 0.0, 0.0, 0.0f64);
+    let mut num_unreachable = 0;
+    for (&(from, to, departure), od_paths) in &od_pairs {
+        // unreachable OD pairs (on the final graph) would add INFINITY to the costs, they are only counted
+        let shortest = match server.query(&TDQuery::new(from, to, departure), false) {
+            Some(result) => result.distance as f64,
+            None => {
+                num_unreachable += 1;
+                continue;
+            }
+        };
+
+        let flow = od_paths.iter().map(|path| path.pce_load as f64 / PCE_SCALE as f64).sum::<f64>();
+        let cost = od_paths
+            .iter()
+            .map(|path| path.pce_load as f64 / PCE_SCALE as f64 * server.path_distance(&path.edge_path, departure) as f64)
+            .sum::<f64>();
+        let avg_cost = cost / flow;
+        let excess = (avg_cost - shortest).max(0.0);
+
+        total_cost += cost;
+        shortest_cost += flow * shortest;
+        total_flow += flow;
+        max_excess = max_excess.max(excess);
+
+        let line = format!(
+            "{},{},{},{},{},{},{},{}\n",
+            from,
+            to,
+            departure,
+            flow,
+            od_paths.len(),
+            avg_cost,
+            shortest,
+            excess
+        );
+        od_file.write(line.as_bytes())?;
+    }
+
+    // 3. attribute the delays of all paths to the traversed edges
+    let mut edge_delays = BTreeMap::<EdgeId, (f64, f64)>::new();
+    for path in paths.iter().filter(|path| !path.departure.is_empty()) {
+        let flow = path.pce_load as f64 / PCE_SCALE as f64;
+        for segment in server.path_breakdown(&path.edge_path, path.departure[0]) {
+            let delay = segment
+                .travel_time
+                .saturating_sub(server.borrow_graph().free_flow_time()[segment.edge_id as usize]);
+            let entry = edge_delays.entry(segment.edge_id).or_default();
+            entry.0 += flow;
+            entry.1 += flow * delay as f64;
+        }
+    }
+
+    let mut edge_delays = edge_delays.into_iter().collect::<Vec<(EdgeId, (f64, f64))>>();
+    edge_delays.sort_by(|(_, (_, a)), (_, (_, b))| b.partial_cmp(a).unwrap());
+
+    let mut edge_file = File::create(&paths_path.join("equilibrium_edges.csv"))?;
+    edge_file.write("edge_id,flow,total_delay\n".as_bytes())?;
+    for &(edge_id, (flow, total_delay)) in edge_delays.iter().take(1000) {
+        edge_file.write(format!("{},{},{}\n", edge_id, flow, total_delay).as_bytes())?;
+    }
+
+    println!("------------------------------------------");
+    println!(
+        "OD pairs: {} ({} unreachable, excluded from the costs), paths: {}",
+        od_pairs.len(),
+        num_unreachable,
+        paths.len()
+    );
+    println!("Beckmann objective: {}", server.borrow_graph().beckmann_objective());
+    println!(
+        "Total travel time: {}, on shortest paths: {}, relative gap: {:.6}",
+        total_cost,
+        shortest_cost,
+        (total_cost - shortest_cost) / shortest_cost.max(1.0)
+    );
+    println!(
+        "Average excess cost per vehicle: {}, max excess cost: {}",
+        (total_cost - shortest_cost) / total_flow.max(1.0),
+        max_excess
+    );
+
+    Ok(())
+}
+
+/// total travel time of the given paths on the current graph, weighted by their load
+fn total_travel_time<Pot>(server: &CapacityServer<Pot>, paths: &[PathResult]) -> f64
+where
+    CapacityServer<Pot>: CapacityServerOps,
+{
+    paths
+        .iter()
+        .filter(|path| !path.departure.is_empty())
+        .map(|path| path.pce_load as f64 / PCE_SCALE as f64 * server.path_distance(&path.edge_path, path.departure[0]) as f64)
+        .sum()
+}
+
+fn parse_args() -> Result<(String, String, u32, usize), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+
+    let graph_directory = parse_arg_required(&mut args, "Graph Directory")?;
+    let paths_directory = parse_arg_required(&mut args, "Assigned Paths Directory")?;
+    let num_buckets = parse_arg_required(&mut args, "Num Buckets")?;
+    let num_checkpoints: usize = parse_arg_optional(&mut args, 20);
+    assert!(num_checkpoints > 0, "At least one checkpoint is required!");
+
+    Ok((graph_directory, paths_directory, num_buckets, num_checkpoints))
+}
@@ -1,10 +1,10 @@
 use cooperative::dijkstra::potentials::multi_metric_potential::customization::CustomizedMultiMetrics;
-use cooperative::dijkstra::potentials::multi_metric_potential::interval_patterns::complete_balanced_interval_pattern;
+use cooperative::dijkstra::potentials::multi_metric_potential::interval_patterns::{complete_balanced_interval_pattern, interval_pattern_from_env};
 use cooperative::dijkstra::server::{CapacityServer, CapacityServerOps};
 use cooperative::experiments::queries::permutate_queries;
 use cooperative::graph::traffic_functions::BPRTrafficFunction;
 use cooperative::io::io_graph::load_capacity_graph;
-use cooperative::io::io_node_order::load_node_order;
+use cooperative::io::io_node_order::load_coordinate_aware_node_order;
 use cooperative::io::io_queries::load_queries;
 use cooperative::util::cli_args::{parse_arg_optional, parse_arg_required};
 use rayon::prelude::*;
@@ -23,6 +23,8 @@ use std::time::Instant;
 /// Queries are accelerated with a default Multi-Metric potential which is updated after 50000 queries each
 ///
 /// Additional parameters: <path_to_graph> <path_to_queries> <query_breakpoints, comma-separated> <buckets = 50,200,600>
+///
+/// Set `INTERVAL_PATTERN` to override the interval pattern of the multi-metric potential, see `parse_interval_pattern`.
 fn main() -> Result<(), Box<dyn Error>> {
     let (graph_directory, query_directory, query_breakpoints, graph_bucket_counts) = parse_args()?;
 
@@ -40,7 +42,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     // bring queries into disorder -> required to enable faster traffic distribution
     permutate_queries(&mut queries);
 
-    let interval_pattern = complete_balanced_interval_pattern();
+    let interval_pattern = interval_pattern_from_env(complete_balanced_interval_pattern)?;
 
     // initialize graphs and evaluate memory consumption
     let usage_statistics = graph_bucket_counts
@@ -50,7 +52,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
             // init graph and cch
             let graph = load_capacity_graph(graph_path, num_buckets, BPRTrafficFunction::default()).unwrap();
-            let order = load_node_order(graph_path).unwrap();
+            let order = load_coordinate_aware_node_order(graph_path, &graph).unwrap();
             let cch = CCH::fix_order_and_build(&graph, order);
 
             // run initial customization, init server
@@ -80,9 +82,9 @@ fn main() -> Result<(), Box<dyn Error>> {
                         // regular re-customization
                         server.customize(&interval_pattern, 20);
                     } else if !server.result_valid() || !server.update_valid() {
-                        // re-customization of upper bounds
+                        // re-customization of the potential
                         println!("-- {} - potential update after {} steps", num_buckets, idx + 1);
-                        server.customize_upper_bound();
+                        server.restore_potential(&interval_pattern, 20);
                     }
                 }
 
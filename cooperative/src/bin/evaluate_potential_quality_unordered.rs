@@ -1,12 +1,12 @@
 use cooperative::dijkstra::potentials::corridor_lowerbound_potential::customization::CustomizedCorridorLowerbound;
 use cooperative::dijkstra::potentials::multi_metric_potential::customization::CustomizedMultiMetrics;
-use cooperative::dijkstra::potentials::multi_metric_potential::interval_patterns::complete_balanced_interval_pattern;
+use cooperative::dijkstra::potentials::multi_metric_potential::interval_patterns::{complete_balanced_interval_pattern, interval_pattern_from_env};
 use cooperative::dijkstra::server::{CapacityServer, CapacityServerOps};
 use cooperative::experiments::queries::permutate_queries;
 use cooperative::experiments::types::PotentialType;
 use cooperative::graph::traffic_functions::BPRTrafficFunction;
 use cooperative::io::io_graph::load_capacity_graph;
-use cooperative::io::io_node_order::load_node_order;
+use cooperative::io::io_node_order::load_coordinate_aware_node_order;
 use cooperative::io::io_queries::load_queries;
 use cooperative::util::cli_args::{parse_arg_optional, parse_arg_required};
 use rayon::prelude::*;
@@ -34,6 +34,8 @@ use std::time::{Duration, Instant};
 ///
 /// Additional parameters: <path_to_graph> <path_to_queries> <num_buckets> <query_evaluation_frequency = 100000> <mm_num_metrics = 20> <mm_update_frequency = 50000> <cl_num_intervals = 72> <cl_update_frequency = 72>
 /// Note that `query_evaluation_frequency` must be divisible by the total number of queries
+///
+/// Set `INTERVAL_PATTERN` to override the interval pattern of the multi-metric potential, see `parse_interval_pattern`.
 fn main() -> Result<(), Box<dyn Error>> {
     let (graph_directory, query_directory, num_buckets, evaluation_frequency, mm_num_metrics, mm_update_frequency, cl_num_intervals, cl_update_frequency) =
         parse_args()?;
@@ -57,8 +59,13 @@ fn main() -> Result<(), Box<dyn Error>> {
         .map(|i| i * evaluation_frequency)
         .collect::<Vec<u32>>();
 
-    let order = load_node_order(&graph_path)?;
-    let interval_pattern = complete_balanced_interval_pattern();
+    // load node order (reordered by coordinates) and build the cch once, all servers share its topology
+    let temp_graph = load_capacity_graph(&graph_path, 1, BPRTrafficFunction::default())?;
+    let order = load_coordinate_aware_node_order(&graph_path, &temp_graph)?;
+    let (cch, time) = measure(|| CCH::fix_order_and_build(&temp_graph, order));
+    println!("CCH created in {} ms", time.as_secs_f64() * 1000.0);
+    drop(temp_graph);
+    let interval_pattern = interval_pattern_from_env(complete_balanced_interval_pattern)?;
 
     let results = [PotentialType::CCHPot, PotentialType::MultiMetrics, PotentialType::CorridorLowerbound]
         .par_iter()
@@ -67,10 +74,6 @@ fn main() -> Result<(), Box<dyn Error>> {
             let graph = load_capacity_graph(&graph_path, num_buckets, BPRTrafficFunction::default()).unwrap();
             println!("{}: Graph initialized!", potential_type.to_string());
 
-            // init cch
-            let (cch, time) = measure(|| CCH::fix_order_and_build(&graph, order.clone()));
-            println!("{}: CCH created in {} ms", potential_type.to_string(), time.as_secs_f64() * 1000.0);
-
             let mut total_time = Duration::ZERO;
             let mut temp_time = Duration::ZERO;
             let mut sum_dist = 0;
@@ -152,7 +155,7 @@ fn main() -> Result<(), Box<dyn Error>> {
                                         println!("Corridor-Lowerbound: Update Bounds in step {}", current_idx);
                                         println!("--------------------------\n\n");
 
-                                        let (_, time) = measure(|| server.customize_upper_bound(&cch));
+                                        let (_, time) = measure(|| server.restore_potential(&cch, cl_num_intervals));
                                         total_time = total_time.add(time);
                                     }
                                 }
@@ -169,7 +172,7 @@ fn main() -> Result<(), Box<dyn Error>> {
                     let mut last_update_step = 0;
                     // init server
                     let init_start = Instant::now();
-                    let customized = CustomizedMultiMetrics::new_from_capacity(cch, &graph, &interval_pattern, mm_num_metrics as usize);
+                    let customized = CustomizedMultiMetrics::new_from_capacity(cch.clone(), &graph, &interval_pattern, mm_num_metrics as usize);
                     let mut server = CapacityServer::new(graph, customized);
                     total_time = total_time.add(init_start.elapsed());
 
@@ -210,7 +213,7 @@ fn main() -> Result<(), Box<dyn Error>> {
                                         println!("Multi-Metric: Update Bounds in step {}", current_idx);
                                         println!("--------------------------\n\n");
 
-                                        let (_, time) = measure(|| server.customize_upper_bound());
+                                        let (_, time) = measure(|| server.restore_potential(&interval_pattern, mm_num_metrics as usize));
                                         total_time = total_time.add(time);
                                     }
                                 }
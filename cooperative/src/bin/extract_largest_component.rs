@@ -0,0 +1,18 @@
+use cooperative::io::modification::extract_scc::{extract_largest_component, ComponentType};
+use cooperative::util::cli_args::{parse_arg_optional, parse_arg_required};
+use std::env;
+use std::error::Error;
+use std::path::Path;
+
+/// Extracts the largest strongly (SCC) or weakly (WCC) connected component of a given graph.
+/// Component size statistics are printed before pruning.
+///
+/// Additional parameters: <path_to_graph> <output_directory> <component_type = SCC>
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let graph_directory: String = parse_arg_required(&mut args, "Graph Directory")?;
+    let output_directory: String = parse_arg_required(&mut args, "Output Graph Directory")?;
+    let component_type = parse_arg_optional(&mut args, ComponentType::Strong);
+
+    extract_largest_component(Path::new(&graph_directory), Path::new(&output_directory), component_type)
+}
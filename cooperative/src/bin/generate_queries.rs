@@ -1,17 +1,18 @@
 use cooperative::experiments::queries::departure_distributions::{
-    ConstantDeparture, DepartureDistribution, NormalDeparture, RushHourDeparture, UniformDeparture,
+    BimodalDeparture, ConstantDeparture, DepartureDistribution, NormalDeparture, RushHourDeparture, UniformDeparture,
 };
 use cooperative::experiments::queries::dijkstra_rank::{generate_dijkstra_rank_queries, generate_population_dijkstra_rank_queries};
 use cooperative::experiments::queries::population_density_based::{
-    generate_geometric_population_density_based_queries, generate_uniform_population_density_based_queries,
+    generate_commuter_population_density_based_queries, generate_commuter_round_trip_queries, generate_geometric_population_density_based_queries,
+    generate_uniform_population_density_based_queries,
 };
 use cooperative::experiments::queries::random_geometric::generate_random_geometric_queries;
 use cooperative::experiments::queries::random_uniform::generate_random_uniform_queries;
-use cooperative::experiments::queries::{GraphType, QueryType};
+use cooperative::experiments::queries::{generate_queries, GraphType, QueryType};
 use cooperative::graph::traffic_functions::BPRTrafficFunction;
-use cooperative::io::io_coordinates::load_coords;
+use cooperative::io::io_coordinates::load_coords_f64;
 use cooperative::io::io_graph::load_capacity_graph;
-use cooperative::io::io_population_grid::load_population_grid;
+use cooperative::io::io_population_grid::load_population_grid_for_nodes;
 use cooperative::io::io_queries::store_queries;
 use cooperative::util::cli_args::parse_arg_required;
 use rust_road_router::datastr::graph::time_dependent::TDGraph;
@@ -25,9 +26,14 @@ use std::path::Path;
 ///
 /// First parameters: <path_to_graph> <type = CAPACITY/PTV> <num_queries> <query_type> <output_directory>
 /// Additional parameters, depending on `query_type`:
-/// uniform/geometric: ---
+/// uniform/geometric/geometric-travel-time: ---
+/// (geometric-travel-time uses the time-dependent travel times on capacity graphs and the lower bounds on PTV graphs)
 /// population-grid-based: <path_to_population_grid_file>
-/// dijkstra-rank: <max_rank_pow> (for each rank power 7 <= i <= max_rank_power), `num_queries` are generated
+/// (several grids, e.g. of neighboring regions or different resolutions, can be given as comma-separated list)
+/// population-commuter(-round-trip): <path_to_residential_grid_file> <path_to_workplace_grid_file>
+/// (round trips emit `num_queries / 2` pairs, the partner trip of each query is stored in `return_trip`)
+/// dijkstra-rank: <max_rank_pow> (for each rank power 8 <= i <= max_rank_power), `num_queries` are generated
+/// (the rank power of each query is stored in `rank`)
 /// population-grid & dijkstra-rank: <path_to_population_grid_file> <max_rank_pow>
 ///
 /// Results will be written to directory <path_to_graph>/queries/<output_directory>/
@@ -60,6 +66,10 @@ fn main() -> Result<(), Box<dyn Error>> {
             let queries = generate_random_uniform_queries(graph.num_nodes() as u32, num_queries, NormalDeparture::new());
             (queries, None)
         }
+        QueryType::UniformBimodalDep => {
+            let queries = generate_random_uniform_queries(graph.num_nodes() as u32, num_queries, BimodalDeparture::new());
+            (queries, None)
+        }
         QueryType::Geometric | QueryType::GeometricRushHourDep => {
             let queries = match graph_type {
                 GraphType::PTV => {
@@ -85,27 +95,48 @@ fn main() -> Result<(), Box<dyn Error>> {
 
             (queries, None)
         }
+        QueryType::GeometricTravelTime | QueryType::GeometricTravelTimeRushHourDep => {
+            let queries = match graph_type {
+                GraphType::PTV => {
+                    // no time-dependent capacity graph available => use the lower bounds instead
+                    if query_type == QueryType::GeometricTravelTime {
+                        generate_random_geometric_queries(&graph, false, num_queries, UniformDeparture::new())
+                    } else {
+                        generate_random_geometric_queries(&graph, false, num_queries, RushHourDeparture::new())
+                    }
+                }
+                GraphType::CAPACITY => {
+                    // travel times are evaluated at the departure of each query
+                    let capacity_graph = load_capacity_graph(graph_directory, 1, BPRTrafficFunction::default()).unwrap();
+                    generate_queries(&capacity_graph, query_type, num_queries)
+                }
+            };
+
+            (queries, None)
+        }
         QueryType::DijkstraRank | QueryType::DijkstraRankRushHourDep => {
             let max_rank_pow: u32 = parse_arg_required(&mut remaining_args, "power of last rank (2^x)")?;
-            let queries = if query_type == QueryType::DijkstraRank {
+            let (queries, ranks) = if query_type == QueryType::DijkstraRank {
                 generate_dijkstra_rank_queries(&graph, num_queries, max_rank_pow, UniformDeparture::new())
             } else {
                 generate_dijkstra_rank_queries(&graph, num_queries, max_rank_pow, RushHourDeparture::new())
             };
 
-            (queries, Some(vec![("num_queries", vec![num_queries]), ("max_rank", vec![max_rank_pow])]))
+            (
+                queries,
+                Some(vec![("num_queries", vec![num_queries]), ("max_rank", vec![max_rank_pow]), ("rank", ranks)]),
+            )
         }
         QueryType::PopulationDijkstraRank | QueryType::PopulationDijkstraRankRushHourDep => {
             // load population data
             let population_path: String = parse_arg_required(&mut remaining_args, "population grid directory")?;
-            let population_directory = Path::new(&population_path);
-            let (longitude, latitude) = load_coords(graph_directory)?;
-            let (grid_tree, grid_population) = load_population_grid(population_directory)?;
+            let (longitude, latitude) = load_coords_f64(graph_directory)?;
+            let (grid_tree, grid_population) = load_population_grid_for_nodes(&population_path, &longitude, &latitude)?;
 
             // retrieve dijkstra-rank data
             let max_rank_pow: u32 = parse_arg_required(&mut remaining_args, "power of last rank (2^x)")?;
 
-            let queries = if query_type == QueryType::PopulationDijkstraRank {
+            let (queries, ranks) = if query_type == QueryType::PopulationDijkstraRank {
                 generate_population_dijkstra_rank_queries(
                     &longitude,
                     &latitude,
@@ -129,62 +160,101 @@ fn main() -> Result<(), Box<dyn Error>> {
                 )
             };
 
-            (queries, Some(vec![("num_queries", vec![num_queries]), ("max_rank", vec![max_rank_pow])]))
+            (
+                queries,
+                Some(vec![("num_queries", vec![num_queries]), ("max_rank", vec![max_rank_pow]), ("rank", ranks)]),
+            )
         }
         _ => {
             // for population queries, we have to use some additional data
             let population_path: String = parse_arg_required(&mut remaining_args, "population grid directory")?;
-            let population_directory = Path::new(&population_path);
 
-            let (longitude, latitude) = load_coords(graph_directory)?;
-            let (grid_tree, grid_population) = load_population_grid(population_directory)?;
+            let (longitude, latitude) = load_coords_f64(graph_directory)?;
+            let (grid_tree, grid_population) = load_population_grid_for_nodes(&population_path, &longitude, &latitude)?;
 
-            let queries = match query_type {
-                QueryType::PopulationUniform => {
-                    generate_uniform_population_density_based_queries(&longitude, &latitude, &grid_tree, &grid_population, num_queries, UniformDeparture::new())
-                }
-                QueryType::PopulationUniformConstantDep => generate_uniform_population_density_based_queries(
+            if query_type == QueryType::PopulationCommuterRoundTrip {
+                let work_population_path: String = parse_arg_required(&mut remaining_args, "workplace population grid directory")?;
+                let (work_grid_tree, work_population) = load_population_grid_for_nodes(&work_population_path, &longitude, &latitude)?;
+
+                let (queries, return_trip) = generate_commuter_round_trip_queries(
                     &longitude,
                     &latitude,
                     &grid_tree,
                     &grid_population,
-                    num_queries,
-                    ConstantDeparture::new(),
-                ),
-                QueryType::PopulationGeometric => {
-                    match graph_type {
-                        GraphType::CAPACITY => {
-                            // capacity graph has its own distance metric => rebuild graph before
-                            let distance = Vec::<u32>::load_from(graph_directory.join("geo_distance"))?;
-                            let distance_graph = FirstOutGraph::new(graph.first_out(), graph.head(), distance);
-
-                            generate_geometric_population_density_based_queries(
-                                &distance_graph,
+                    &work_grid_tree,
+                    &work_population,
+                    num_queries / 2,
+                );
+                (queries, Some(vec![("return_trip", return_trip)]))
+            } else {
+                let queries = match query_type {
+                    QueryType::PopulationUniform => generate_uniform_population_density_based_queries(
+                        &longitude,
+                        &latitude,
+                        &grid_tree,
+                        &grid_population,
+                        num_queries,
+                        UniformDeparture::new(),
+                    ),
+                    QueryType::PopulationUniformConstantDep => generate_uniform_population_density_based_queries(
+                        &longitude,
+                        &latitude,
+                        &grid_tree,
+                        &grid_population,
+                        num_queries,
+                        ConstantDeparture::new(),
+                    ),
+                    QueryType::PopulationGeometric => {
+                        match graph_type {
+                            GraphType::CAPACITY => {
+                                // capacity graph has its own distance metric => rebuild graph before
+                                let distance = Vec::<u32>::load_from(graph_directory.join("geo_distance"))?;
+                                let distance_graph = FirstOutGraph::new(graph.first_out(), graph.head(), distance);
+
+                                generate_geometric_population_density_based_queries(
+                                    &distance_graph,
+                                    &longitude,
+                                    &latitude,
+                                    &grid_tree,
+                                    &grid_population,
+                                    num_queries,
+                                    RushHourDeparture::new(),
+                                    true,
+                                )
+                            }
+                            GraphType::PTV => generate_geometric_population_density_based_queries(
+                                &graph,
                                 &longitude,
                                 &latitude,
                                 &grid_tree,
                                 &grid_population,
                                 num_queries,
                                 RushHourDeparture::new(),
-                                true,
-                            )
+                                false,
+                            ),
                         }
-                        GraphType::PTV => generate_geometric_population_density_based_queries(
-                            &graph,
+                    }
+                    QueryType::PopulationCommuter => {
+                        // residential density is given by the first grid, workplace density by an additional one
+                        let work_population_path: String = parse_arg_required(&mut remaining_args, "workplace population grid directory")?;
+                        let (work_grid_tree, work_population) = load_population_grid_for_nodes(&work_population_path, &longitude, &latitude)?;
+
+                        generate_commuter_population_density_based_queries(
                             &longitude,
                             &latitude,
                             &grid_tree,
                             &grid_population,
+                            &work_grid_tree,
+                            &work_population,
                             num_queries,
                             RushHourDeparture::new(),
-                            false,
-                        ),
+                        )
                     }
-                }
-                _ => unimplemented!(), // won't happen
-            };
+                    _ => unimplemented!(), // won't happen
+                };
 
-            (queries, None)
+                (queries, None)
+            }
         }
     };
 
@@ -210,7 +280,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
     }
 
-    println!("Wrote {} queries to {}", num_queries, output_dir.display());
+    println!("Wrote {} queries to {}", queries.len(), output_dir.display());
 
     Ok(())
 }
@@ -0,0 +1,132 @@
+use cooperative::dijkstra::potentials::init_cch_potential::init_cch_potential;
+use cooperative::dijkstra::potentials::TDPotential;
+use cooperative::dijkstra::route_choice::LogitRouteChoice;
+use cooperative::dijkstra::server::{CapacityServer, CapacityServerOps};
+use cooperative::experiments::types::StaticPotentialType;
+use cooperative::graph::traffic_functions::BPRTrafficFunction;
+use cooperative::graph::{pce_load, PCE_SCALE};
+use cooperative::io::io_graph::load_capacity_graph;
+use cooperative::io::io_node_order::load_coordinate_aware_node_order;
+use cooperative::io::io_paths::store_assigned_paths;
+use cooperative::io::io_queries::{load_pce_factors, load_queries};
+use cooperative::util::cli_args::{parse_arg_optional, parse_arg_required};
+use rust_road_router::algo::a_star::ZeroPotential;
+use rust_road_router::datastr::graph::INFINITY;
+use rust_road_router::report::measure;
+use std::env;
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+/// Cooperative routing with stochastic (multinomial-logit) route choice among `k` candidate paths per query.
+/// A free-flow potential is used, as candidate paths are generated by temporarily penalizing the graph.
+/// <potential> selects a CCH lowerbound potential (CCH_POT) or plain Dijkstra (ZERO).
+///
+/// Per-query choices are written to `logit_route_choice.csv`, the committed paths are evaluated on the final graph
+/// and stored in `paths/logit_route_choice` within the query directory.
+///
+/// Additional parameters: <path_to_graph> <path_to_queries> <num_buckets> <num_candidates=3> <theta=10> <penalty_pce=10> <seed=42> <potential=CCH_POT>
+fn main() -> Result<(), Box<dyn Error>> {
+    let (graph_directory, query_directory, num_buckets, num_candidates, theta, penalty_pce, seed, potential_type) = parse_args()?;
+
+    let graph_path = Path::new(&graph_directory);
+    let query_path = graph_path.join("queries").join(&query_directory);
+
+    let queries = load_queries(&query_path)?;
+    let pce_factors = load_pce_factors(&query_path, queries.len())?;
+
+    // init graph, potential and server
+    let graph = load_capacity_graph(&graph_path, num_buckets, BPRTrafficFunction::default())?;
+    let cch_pot_data = match potential_type {
+        StaticPotentialType::Zero => None,
+        StaticPotentialType::CCHLowerbound => {
+            let order = load_coordinate_aware_node_order(&graph_path, &graph)?;
+            Some(init_cch_potential(&graph, order))
+        }
+    };
+    let potential: Box<dyn TDPotential + '_> = match &cch_pot_data {
+        Some(cch_pot_data) => Box::new(cch_pot_data.forward_potential()),
+        None => Box::new(ZeroPotential()),
+    };
+    println!("Using potential: {}", potential_type.to_string());
+    let mut server = CapacityServer::new(graph, potential);
+
+    let mut route_choice = LogitRouteChoice::new(num_candidates, theta, penalty_pce * PCE_SCALE, seed);
+
+    let mut file = File::create(&query_path.join("logit_route_choice.csv"))?;
+    file.write("query_id,num_candidates,chosen_rank,probability,distance\n".as_bytes())?;
+
+    let mut committed = Vec::with_capacity(queries.len());
+    let (num_alternative_choices, time) = measure(|| -> Result<u32, Box<dyn Error>> {
+        let mut num_alternative_choices = 0;
+
+        for (query_id, (query, &pce_factor)) in queries.iter().zip(pce_factors.iter()).enumerate() {
+            if let Some(choice) = route_choice.choose(&mut server, query, pce_load(pce_factor), true) {
+                if choice.chosen_rank > 0 {
+                    num_alternative_choices += 1;
+                }
+
+                let line = format!(
+                    "{},{},{},{},{}\n",
+                    query_id, choice.num_candidates, choice.chosen_rank, choice.probability, choice.result.distance
+                );
+                file.write(line.as_bytes())?;
+                committed.push(choice.result.path);
+            }
+
+            if (query_id + 1) % 10000 == 0 {
+                println!("Finished {} of {} queries", query_id + 1, queries.len());
+            }
+        }
+
+        Ok(num_alternative_choices)
+    });
+    let num_alternative_choices = num_alternative_choices?;
+
+    // evaluate the committed paths on the final graph
+    let total_dist = committed
+        .iter()
+        .map(|path| server.path_distance(&path.edge_path, path.departure[0]))
+        .filter(|&dist| dist != INFINITY)
+        .fold(0u64, |acc, dist| acc + dist as u64);
+
+    println!("------------------------------------------");
+    println!(
+        "Routed {} of {} queries, {} chose a non-shortest path, total distance: {} (avg: {}), total time: {}s",
+        committed.len(),
+        queries.len(),
+        num_alternative_choices,
+        total_dist,
+        total_dist / (committed.len() as u64).max(1),
+        time.as_secs_f64()
+    );
+
+    let paths_path = query_path.join("paths").join("logit_route_choice");
+    std::fs::create_dir_all(&paths_path)?;
+    store_assigned_paths(&committed, &paths_path)
+}
+
+fn parse_args() -> Result<(String, String, u32, usize, f64, u32, u64, StaticPotentialType), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+
+    let graph_directory = parse_arg_required(&mut args, "Graph Directory")?;
+    let query_directory = parse_arg_required(&mut args, "Query Directory")?;
+    let num_buckets = parse_arg_required(&mut args, "Num Buckets")?;
+    let num_candidates = parse_arg_optional(&mut args, 3);
+    let theta = parse_arg_optional(&mut args, 10.0);
+    let penalty_pce = parse_arg_optional(&mut args, 10);
+    let seed = parse_arg_optional(&mut args, 42);
+    let potential_type = parse_arg_optional(&mut args, StaticPotentialType::CCHLowerbound);
+
+    Ok((
+        graph_directory,
+        query_directory,
+        num_buckets,
+        num_candidates,
+        theta,
+        penalty_pce,
+        seed,
+        potential_type,
+    ))
+}
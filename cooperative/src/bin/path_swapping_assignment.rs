@@ -0,0 +1,137 @@
+use cooperative::dijkstra::path_swapping::{PathSwappingAssignment, PathSwappingStatistics};
+use cooperative::dijkstra::potentials::init_cch_potential::init_cch_potential;
+use cooperative::dijkstra::server::CapacityServer;
+#[cfg(feature = "sqlite")]
+use cooperative::experiments::result_sink::SqliteResultSink;
+use cooperative::graph::traffic_functions::BPRTrafficFunction;
+use cooperative::graph::{pce_load, Capacity};
+use cooperative::io::io_graph::load_capacity_graph;
+use cooperative::io::io_node_order::load_coordinate_aware_node_order;
+use cooperative::io::io_paths::store_assigned_paths;
+use cooperative::io::io_queries::{load_pce_factors, load_queries};
+use cooperative::util::cli_args::{parse_arg_optional, parse_arg_required};
+use rust_road_router::report::measure;
+use std::env;
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+/// Path-swapping dynamic traffic assignment as an alternative to sequential cooperative insertion.
+///
+/// The initial assignment inserts all OD pairs sequentially, afterwards each iteration shifts flow
+/// from costly paths to the currently shortest path of each OD pair.
+/// A free-flow CCH potential is used, as it stays valid while flow is withdrawn.
+/// The final path flows are stored in `paths/path_swapping` within the query directory.
+/// With the `sqlite` feature, the iteration statistics are also appended to the database given by `RESULTS_DB`.
+///
+/// Additional parameters: <path_to_graph> <path_to_queries> <num_buckets> <num_iterations=10>
+fn main() -> Result<(), Box<dyn Error>> {
+    let (graph_directory, query_directory, num_buckets, num_iterations) = parse_args()?;
+
+    let graph_path = Path::new(&graph_directory);
+    let query_path = graph_path.join("queries").join(&query_directory);
+
+    #[cfg(feature = "sqlite")]
+    let mut result_sink = SqliteResultSink::open_from_env("path_swapping_assignment", &graph_directory)?;
+    #[cfg(feature = "sqlite")]
+    if let Some(sink) = result_sink.as_mut() {
+        sink.add_parameter("query_directory", &query_directory)?;
+        sink.add_parameter("num_buckets", num_buckets)?;
+        sink.add_parameter("num_iterations", num_iterations)?;
+    }
+
+    let queries = load_queries(&query_path)?;
+    let pce_loads = load_pce_factors(&query_path, queries.len())?
+        .into_iter()
+        .map(pce_load)
+        .collect::<Vec<Capacity>>();
+
+    // init graph, potential and server
+    let graph = load_capacity_graph(&graph_path, num_buckets, BPRTrafficFunction::default())?;
+    let order = load_coordinate_aware_node_order(&graph_path, &graph)?;
+    let cch_pot_data = init_cch_potential(&graph, order);
+    let mut server = CapacityServer::new(graph, cch_pot_data.forward_potential());
+
+    // initial all-or-nothing assignment
+    let mut assignment = PathSwappingAssignment::new(&queries, &pce_loads);
+    let (num_routed, init_time) = measure(|| assignment.initialize(&mut server));
+    println!(
+        "Initialized {} of {} OD pairs ({} queries) in {}s",
+        num_routed,
+        assignment.num_od_pairs(),
+        queries.len(),
+        init_time.as_secs_f64()
+    );
+
+    let mut results = Vec::with_capacity(num_iterations as usize);
+    for iteration in 1..=num_iterations {
+        let (statistics, time) = measure(|| assignment.iterate(&mut server, iteration));
+        println!(
+            "Iteration {}: relative gap: {:.6}, shifted flow: {}, paths: {} ({} new) - {}s",
+            iteration,
+            statistics.relative_gap,
+            statistics.shifted_flow,
+            statistics.num_paths,
+            statistics.num_new_paths,
+            time.as_secs_f64()
+        );
+
+        #[cfg(feature = "sqlite")]
+        if let Some(sink) = result_sink.as_mut() {
+            sink.add_iteration_statistics(
+                "path_swapping",
+                iteration,
+                &[
+                    ("relative_gap", statistics.relative_gap),
+                    ("shifted_flow", statistics.shifted_flow as f64),
+                    ("num_paths", statistics.num_paths as f64),
+                    ("num_new_paths", statistics.num_new_paths as f64),
+                    ("time", time.as_secs_f64()),
+                ],
+            )?;
+        }
+
+        results.push((statistics, time));
+    }
+
+    write_results(&results, &query_path)?;
+
+    let paths_path = query_path.join("paths").join("path_swapping");
+    std::fs::create_dir_all(&paths_path)?;
+    store_assigned_paths(&assignment.assigned_paths(), &paths_path)
+}
+
+fn write_results(results: &Vec<(PathSwappingStatistics, Duration)>, path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut file = File::create(&path.join("path_swapping.csv"))?;
+
+    let header = "iteration,relative_gap,shifted_flow,num_paths,num_new_paths,time\n";
+    file.write(header.as_bytes())?;
+
+    for (entry, time) in results {
+        let line = format!(
+            "{},{},{},{},{},{}\n",
+            entry.iteration,
+            entry.relative_gap,
+            entry.shifted_flow,
+            entry.num_paths,
+            entry.num_new_paths,
+            time.as_secs_f64()
+        );
+        file.write(line.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+fn parse_args() -> Result<(String, String, u32, u32), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+
+    let graph_directory = parse_arg_required(&mut args, "Graph Directory")?;
+    let query_directory = parse_arg_required(&mut args, "Query Directory")?;
+    let num_buckets = parse_arg_required(&mut args, "Num Buckets")?;
+    let num_iterations = parse_arg_optional(&mut args, 10);
+
+    Ok((graph_directory, query_directory, num_buckets, num_iterations))
+}
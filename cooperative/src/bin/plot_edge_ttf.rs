@@ -0,0 +1,71 @@
+use cooperative::dijkstra::potentials::corridor_lowerbound_potential::customization::CustomizedCorridorLowerbound;
+use cooperative::graph::speed_blend::SpeedBlend;
+use cooperative::graph::traffic_functions::BPRTrafficFunction;
+use cooperative::io::io_graph::load_capacity_graph;
+use cooperative::io::io_historic_speeds::load_historic_speeds;
+use cooperative::io::io_node_order::load_coordinate_aware_node_order;
+use cooperative::util::cli_args::{parse_arg_optional, parse_arg_required};
+use cooperative::util::ttf_plot::edge_ttf_plot;
+use rust_road_router::algo::customizable_contraction_hierarchy::CCH;
+use rust_road_router::datastr::graph::{EdgeId, Graph};
+use std::env;
+use std::error::Error;
+use std::path::Path;
+
+/// Renders the travel time functions of selected edges into SVG files (`edge_<id>.svg`)
+///
+/// Additional parameters: <path_to_graph> <num_buckets> <edge_ids> <output_directory> <history_directory=> <num_intervals=0>
+///
+/// `edge_ids` is a comma-separated list. If <history_directory> is given, its speeds are plotted as historic profile
+/// (see `load_historic_speeds`). If <num_intervals> is positive, a corridor lowerbound potential is customized
+/// with this number of intervals and the interval minima of each edge are added to the plot.
+fn main() -> Result<(), Box<dyn Error>> {
+    let (graph_directory, num_buckets, edge_ids, output_directory, history_directory, num_intervals) = parse_args()?;
+
+    let graph_path = Path::new(&graph_directory);
+    let output_path = Path::new(&output_directory);
+    if !output_path.exists() {
+        std::fs::create_dir_all(&output_path)?;
+    }
+
+    let mut graph = load_capacity_graph(&graph_path, num_buckets, BPRTrafficFunction::default())?;
+    if !history_directory.is_empty() {
+        let historic_speeds = load_historic_speeds(&graph_path.join("speeds").join(&history_directory), &graph)?;
+        graph.add_historic_speeds(historic_speeds, SpeedBlend::Minimum);
+    }
+
+    let edge_ids = edge_ids.split(',').map(|id| id.trim().parse::<EdgeId>()).collect::<Result<Vec<EdgeId>, _>>()?;
+    if let Some(&invalid) = edge_ids.iter().find(|&&edge_id| edge_id as usize >= graph.num_arcs()) {
+        return Err(format!("invalid edge id {} (graph has {} edges)", invalid, graph.num_arcs()).into());
+    }
+
+    let customized = if num_intervals > 0 {
+        let order = load_coordinate_aware_node_order(&graph_path, &graph)?;
+        let cch = CCH::fix_order_and_build(&graph, order);
+        Some(CustomizedCorridorLowerbound::new_from_capacity(&cch, &graph, num_intervals))
+    } else {
+        None
+    };
+
+    for edge_id in edge_ids {
+        let interval_minima = customized.as_ref().and_then(|customized| customized.orig_arc_interval_minima(edge_id));
+        let path = output_path.join(format!("edge_{}.svg", edge_id));
+        edge_ttf_plot(&graph, edge_id, interval_minima).store(&path)?;
+        println!("Stored plot of edge {} in {}", edge_id, path.display());
+    }
+
+    Ok(())
+}
+
+fn parse_args() -> Result<(String, u32, String, String, String, u32), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+
+    let graph_directory = parse_arg_required(&mut args, "Graph Directory")?;
+    let num_buckets = parse_arg_required(&mut args, "Num Buckets")?;
+    let edge_ids = parse_arg_required(&mut args, "Edge Ids")?;
+    let output_directory = parse_arg_required(&mut args, "Output Directory")?;
+    let history_directory = parse_arg_optional(&mut args, String::new());
+    let num_intervals = parse_arg_optional(&mut args, 0);
+
+    Ok((graph_directory, num_buckets, edge_ids, output_directory, history_directory, num_intervals))
+}
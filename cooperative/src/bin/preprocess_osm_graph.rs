@@ -1,29 +1,45 @@
+use cooperative::graph::edge_buckets::SpeedBuckets;
 use cooperative::graph::traffic_functions::BPRTrafficFunction;
 use cooperative::graph::MAX_BUCKETS;
 use cooperative::io::io_graph::load_capacity_graph;
-use cooperative::io::modification::filter_invalid_nodes_and_edges::filter_invalid_nodes_and_edges;
-use cooperative::io::modification::{load_raw_graph_data, store_raw_data};
-use cooperative::util::cli_args::parse_arg_required;
-use rust_road_router::io::Load;
+use cooperative::io::io_graph::{load_used_speed_profiles, store_speed_profiles};
+use cooperative::io::modification::extract_scc::{mark_largest_component, ComponentType};
+use cooperative::io::modification::filter_invalid_nodes_and_edges::{
+    filter_invalid_nodes_and_edges, filter_invalid_nodes_and_edges_with_profiles, find_coordinate_outliers, repair_coordinate_outliers,
+};
+use cooperative::io::modification::remove_degenerate_edges::{remap_edge_data, remove_degenerate_edges};
+use cooperative::io::modification::{load_raw_graph_data, store_raw_data, CapacityGraphContainer};
+use cooperative::util::cli_args::{parse_arg_optional, parse_arg_required};
+use rust_road_router::datastr::graph::EdgeId;
 use std::env;
 use std::error::Error;
 use std::path::Path;
 
 /// Final preprocession for a given OSM graph, generated by `RoutingKit`
 ///
-/// Extracts the largest SCC, removes multi edges etc..
+/// Removes self-loops, zero-length edges, multi edges etc. and extracts the largest SCC of the remaining graph.
 ///
-/// Additional parameters: <path_to_graph> <output_directory>
+/// If a directory with speed profiles is given, multi-edges are merged profile-aware
+/// and the merged profiles are stored in `<output_directory>/speed_profiles`.
+///
+/// Nodes with broken coordinates are either repaired by interpolation from their neighbors or removed.
+///
+/// Additional parameters: <path_to_graph> <output_directory> <speed_profile_directory = ''> <repair_coordinates = true>
 fn main() -> Result<(), Box<dyn Error>> {
-    let (graph_directory, output_directory) = parse_required_args()?;
+    let (graph_directory, output_directory, speed_profile_directory, repair_coordinates) = parse_args()?;
     let path = Path::new(&graph_directory);
     let output_path = Path::new(&output_directory);
 
     // load raw graph
-    let raw_data = load_raw_graph_data(path)?;
+    let mut raw_data = load_raw_graph_data(path)?;
 
-    // load excluded nodes (by largest scc construction)
-    let is_valid_node = Vec::<u32>::load_from(&path.join("largest_scc"))?.iter().map(|&i| i > 0).collect::<Vec<bool>>();
+    // exclude nodes with broken coordinates, unless they can be repaired
+    let is_outlier = find_coordinate_outliers(&raw_data);
+    let is_outlier = if repair_coordinates {
+        repair_coordinate_outliers(&mut raw_data, &is_outlier)
+    } else {
+        is_outlier
+    };
 
     // mark invalid edges (travel time >= 86_400_000 or capacity == 0)
     let is_valid_edge = (0..raw_data.head.len())
@@ -31,12 +47,43 @@ fn main() -> Result<(), Box<dyn Error>> {
         .map(|i| raw_data.travel_time[i] < MAX_BUCKETS && raw_data.max_capacity[i] > 10)
         .collect::<Vec<bool>>();
 
+    // remove self-loops and zero-length edges, keep the edge validity in sync
+    let (raw_data, edge_mapping) = remove_degenerate_edges(&raw_data, &vec![false; raw_data.head.len()]);
+    let is_valid_edge = remap_edge_data(&is_valid_edge, &edge_mapping);
+
+    // removed edges and outliers may disconnect parts of the graph, so the largest scc is computed on the remaining graph
+    let is_valid_node = largest_scc(&raw_data, &is_valid_edge, &is_outlier);
+
+    let speed_profiles = if speed_profile_directory.is_empty() {
+        None
+    } else {
+        let speed_profiles = load_used_speed_profiles(Path::new(&speed_profile_directory))?;
+        Some(remap_edge_data(&speed_profiles, &edge_mapping))
+    };
+
     println!(
         "Retrieved all data, starting to reduce the graph. Original graph has {} nodes and {} edges",
         is_valid_node.len(),
         is_valid_edge.len()
     );
-    let reduced_graph_data = filter_invalid_nodes_and_edges(&raw_data, &is_valid_node, &is_valid_edge);
+    let reduced_graph_data = if let Some(speed_profiles) = speed_profiles {
+        let (reduced_graph_data, merged_profiles) = filter_invalid_nodes_and_edges_with_profiles(&raw_data, &is_valid_node, &is_valid_edge, &speed_profiles);
+
+        let profile_directory = output_path.join("speed_profiles");
+        std::fs::create_dir_all(&profile_directory)?;
+        let merged_profiles = merged_profiles
+            .into_iter()
+            .map(|profile| match profile {
+                SpeedBuckets::Unused => Vec::new(),
+                SpeedBuckets::Used(inner) => inner,
+            })
+            .collect::<Vec<Vec<(u32, u32)>>>();
+        store_speed_profiles(&profile_directory, &merged_profiles)?;
+
+        reduced_graph_data
+    } else {
+        filter_invalid_nodes_and_edges(&raw_data, &is_valid_node, &is_valid_edge)
+    };
 
     println!(
         "Reduced graph to {} nodes and {} edges",
@@ -47,11 +94,38 @@ fn main() -> Result<(), Box<dyn Error>> {
     store_raw_data(&reduced_graph_data, output_path)
 }
 
-fn parse_required_args() -> Result<(String, String), Box<dyn Error>> {
+/// nodes of the largest strongly connected component, only valid edges between non-outlier nodes are considered
+fn largest_scc(raw_data: &CapacityGraphContainer, is_valid_edge: &Vec<bool>, is_outlier: &Vec<bool>) -> Vec<bool> {
+    let mut first_out = Vec::with_capacity(raw_data.first_out.len());
+    let mut head = Vec::with_capacity(raw_data.head.len());
+    first_out.push(0);
+
+    for (node_id, edges) in raw_data.first_out.windows(2).enumerate() {
+        if !is_outlier[node_id] {
+            head.extend(
+                (edges[0] as usize..edges[1] as usize)
+                    .filter(|&edge_id| is_valid_edge[edge_id] && !is_outlier[raw_data.head[edge_id] as usize])
+                    .map(|edge_id| raw_data.head[edge_id]),
+            );
+        }
+        first_out.push(head.len() as EdgeId);
+    }
+
+    // isolated outliers might still form the largest component in degenerated graphs
+    mark_largest_component(&first_out, &head, ComponentType::Strong)
+        .iter()
+        .zip(is_outlier.iter())
+        .map(|(&i, &outlier)| i > 0 && !outlier)
+        .collect()
+}
+
+fn parse_args() -> Result<(String, String, String, bool), Box<dyn Error>> {
     let mut args = env::args().skip(1);
 
     let graph_directory = parse_arg_required(&mut args, "Graph Directory")?;
     let output_directory = parse_arg_required(&mut args, "Output Graph Directory")?;
+    let speed_profile_directory = parse_arg_optional(&mut args, String::new());
+    let repair_coordinates = parse_arg_optional(&mut args, true);
 
-    Ok((graph_directory, output_directory))
+    Ok((graph_directory, output_directory, speed_profile_directory, repair_coordinates))
 }
@@ -6,14 +6,14 @@ use cooperative::io::io_queries::load_queries;
 use cooperative::util::cli_args::parse_arg_required;
 use rand::{thread_rng, Rng};
 use rust_road_router::algo::a_star::ZeroPotential;
-use rust_road_router::algo::ch_potentials::{BorrowedCCHPot, CCHPotData};
+use rust_road_router::algo::ch_potentials::{BorrowedCCHPot, BorrowedLazyRPHASTPot, CCHPotData};
 use rust_road_router::algo::customizable_contraction_hierarchy::CCH;
 use rust_road_router::algo::TDQuery;
 use rust_road_router::datastr::graph::time_dependent::TDGraph;
 use rust_road_router::datastr::graph::{FirstOutGraph, Graph};
 use rust_road_router::datastr::node_order::NodeOrder;
 use rust_road_router::io::{Load, Reconstruct};
-use rust_road_router::report::measure;
+use rust_road_router::report::{benchmark, measure, num_benchmark_repetitions};
 use std::env;
 use std::error::Error;
 use std::ops::Add;
@@ -41,6 +41,9 @@ fn main() -> Result<(), Box<dyn Error>> {
     // load pre-generated queries
     let queries = load_queries(&path.join("queries").join(query_directory))?;
 
+    // with `BENCHMARK_REPETITIONS` set, the median of the warm runs is taken
+    let repetitions = num_benchmark_repetitions()?;
+
     // init cch
     let order = Vec::load_from(path.join("cch_perm"))?;
     let node_order = NodeOrder::from_node_order(order);
@@ -61,7 +64,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let pot_name = format!("Naive Dijkstra ({} queries)", dijkstra_queries.len());
     let query_fn = |s: &mut PTVQueryServer<ZeroPotential>, q: &TDQuery<u32>| s.query(q);
-    execute_queries(&mut server, query_fn, &dijkstra_queries, pot_name.as_str());
+    execute_queries(&mut server, query_fn, &dijkstra_queries, pot_name.as_str(), repetitions);
     let (graph, _) = server.decompose();
 
     // ----------------------------------------------------------------------------- //
@@ -70,10 +73,18 @@ fn main() -> Result<(), Box<dyn Error>> {
     let cch_pot_data = CCHPotData::new(&cch, &lower_bound_graph);
     let mut server = PTVQueryServer::new(graph, cch_pot_data.forward_potential());
     let query_fn = |s: &mut PTVQueryServer<BorrowedCCHPot>, q: &TDQuery<u32>| s.query(q);
-    execute_queries(&mut server, query_fn, &queries, "CCH Lowerbound Potential");
+    execute_queries(&mut server, query_fn, &queries, "CCH Lowerbound Potential", repetitions);
 
     let (graph, cch_lowerbound_pot) = server.decompose();
     drop(cch_lowerbound_pot);
+
+    // same lowerbound, evaluated by lazy RPHAST sweeps instead of elimination tree climbs
+    let mut server = PTVQueryServer::new(graph, cch_pot_data.forward_lazy_rphast_potential());
+    let query_fn = |s: &mut PTVQueryServer<BorrowedLazyRPHASTPot>, q: &TDQuery<u32>| s.query(q);
+    execute_queries(&mut server, query_fn, &queries, "CCH Lowerbound Potential (Lazy RPHAST)", repetitions);
+
+    let (graph, lazy_rphast_pot) = server.decompose();
+    drop(lazy_rphast_pot);
     drop(cch_pot_data);
 
     // ----------------------------------------------------------------------------- //
@@ -83,7 +94,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let mut server = PTVQueryServer::new(graph, customized_multi_metric);
     let query_fn = |s: &mut PTVQueryServer<CustomizedMultiMetrics>, q: &TDQuery<u32>| s.query(q);
-    execute_queries(&mut server, query_fn, &queries, "Multi Metric Pot");
+    execute_queries(&mut server, query_fn, &queries, "Multi Metric Pot", repetitions);
     let (graph, customized) = server.decompose();
     drop(customized);
 
@@ -94,7 +105,13 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let mut server = PTVQueryServer::new(graph, customized_corridor_lowerbound);
     let query_fn = |s: &mut PTVQueryServer<CustomizedCorridorLowerbound>, q: &TDQuery<u32>| s.query(q);
-    execute_queries(&mut server, query_fn, &queries, "Corridor Lowerbound Potential");
+    execute_queries(&mut server, query_fn, &queries, "Corridor Lowerbound Potential", repetitions);
+
+    // same potential, reusing the search spaces of repeated queries within a departure interval
+    let (graph, mut customized_corridor_lowerbound) = server.decompose();
+    customized_corridor_lowerbound.set_search_reuse(true);
+    let mut server = PTVQueryServer::new(graph, customized_corridor_lowerbound);
+    execute_queries(&mut server, query_fn, &queries, "Corridor Lowerbound Potential (reused searches)", repetitions);
     Ok(())
 }
 
@@ -103,8 +120,10 @@ fn execute_queries<Customized>(
     query_fn: fn(&mut PTVQueryServer<Customized>, &TDQuery<u32>) -> PTVQueryResult,
     queries: &Vec<TDQuery<u32>>,
     pot_name: &str,
+    repetitions: usize,
 ) {
     let mut sum_distances = 0u64;
+    let mut sum_potentials = 0u64;
     let mut num_relaxed_arcs = 0u64;
     let mut num_queue_pops = 0u64;
 
@@ -112,14 +131,19 @@ fn execute_queries<Customized>(
     let mut time_queries = Duration::ZERO;
     let mut time_potentials = Duration::ZERO;
 
+    let mut time_cold = Duration::ZERO;
+
     queries.iter().enumerate().for_each(|(idx, query)| {
-        let (result, time) = measure(|| query_fn(server, query));
+        // the server sums up the potentials of all repetitions, so only the representative result is counted here
+        let (result, times) = benchmark(repetitions, || query_fn(server, query));
 
-        time_total = time_total.add(time);
+        time_cold = time_cold.add(times.cold);
+        time_total = time_total.add(times.representative());
         time_queries = time_queries.add(result.time_query);
         time_potentials = time_potentials.add(result.time_potential);
 
         sum_distances += result.distance.unwrap_or(0) as u64;
+        sum_potentials += result.start_potential as u64;
         num_relaxed_arcs += result.num_relaxed_arcs as u64;
         num_queue_pops += result.num_queue_pops as u64;
 
@@ -136,6 +160,13 @@ fn execute_queries<Customized>(
         time_potentials.as_secs_f64() * 1000.0,
         time_queries.as_secs_f64() * 1000.0,
     );
+    if repetitions > 0 {
+        println!(
+            "Cold runtime: {} ms, warm runtimes are medians of {} repetitions",
+            time_cold.as_secs_f64() * 1000.0,
+            repetitions
+        );
+    }
     println!(
         "Query statistics: {} relaxed arcs (avg: {}), {} queue pops (avg: {})",
         num_relaxed_arcs,
@@ -144,11 +175,7 @@ fn execute_queries<Customized>(
         num_queue_pops / queries.len() as u64
     );
     println!("Total distance: {} (avg: {})", sum_distances, sum_distances / queries.len() as u64);
-    println!(
-        "Potential estimation: {} (avg: {})",
-        server.sum_potentials,
-        server.sum_potentials / queries.len() as u64
-    );
+    println!("Potential estimation: {} (avg: {})", sum_potentials, sum_potentials / queries.len() as u64);
     println!("-----------------------------");
 }
 
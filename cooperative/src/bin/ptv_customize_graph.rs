@@ -1,7 +1,7 @@
 use cooperative::dijkstra::potentials::corridor_lowerbound_potential::customization::CustomizedCorridorLowerbound;
 use cooperative::dijkstra::potentials::corridor_lowerbound_potential::customization_catchup::convert_to_td_graph;
 use cooperative::dijkstra::potentials::multi_metric_potential::customization::CustomizedMultiMetrics;
-use cooperative::dijkstra::potentials::multi_metric_potential::interval_patterns::balanced_interval_pattern;
+use cooperative::dijkstra::potentials::multi_metric_potential::interval_patterns::{balanced_interval_pattern, interval_pattern_from_env};
 use cooperative::experiments::types::PotentialType;
 use cooperative::graph::MAX_BUCKETS;
 use cooperative::io::io_ptv_customization::{store_interval_minima, store_multiple_metrics};
@@ -12,7 +12,8 @@ use rust_road_router::datastr::graph::time_dependent::{TDGraph, Timestamp};
 use rust_road_router::datastr::graph::{EdgeId, FirstOutGraph, Weight};
 use rust_road_router::datastr::node_order::NodeOrder;
 use rust_road_router::io::{Load, Reconstruct};
-use rust_road_router::report::measure;
+use rust_road_router::report::progress::ProgressPrinter;
+use rust_road_router::report::{benchmark, measure, num_benchmark_repetitions};
 use std::env;
 use std::error::Error;
 use std::path::{Path, PathBuf};
@@ -24,6 +25,11 @@ use std::path::{Path, PathBuf};
 /// Additional parameters, depending on `potential_type`:
 /// CORRIDOR_LOWERBOUND: <num_intervals = 72>
 /// MULTI_METRICS: <max_num_metrics = 20>
+///
+/// Set `BENCHMARK_REPETITIONS` to repeat the customization (CCH_POT and CORRIDOR_LOWERBOUND only) and report the median of the warm runs.
+/// The multi-metric customization consumes the CCH and is therefore only measured once.
+///
+/// Set `INTERVAL_PATTERN` to override the interval pattern of the multi-metric potential, see `parse_interval_pattern`.
 fn main() -> Result<(), Box<dyn Error>> {
     let (path, potential_type, mut remaining_args) = parse_required_args()?;
     let graph_directory = Path::new(&path);
@@ -34,7 +40,8 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     // init cch
     let order = Vec::load_from(graph_directory.join("cch_perm")).map(NodeOrder::from_node_order)?;
-    let cch = CCH::fix_order_and_build(&graph, order);
+    let contraction_progress = ProgressPrinter::new("CCH contraction");
+    let cch = CCH::fix_order_and_build_with_progress(&graph, order, &|done, total| contraction_progress.update(done, total));
 
     match potential_type {
         PotentialType::CCHPot => {
@@ -42,11 +49,11 @@ fn main() -> Result<(), Box<dyn Error>> {
             // no data is actually stored here. Even on continental-sized graph, loading vs customizing takes similar times
             let lower_bound = Vec::<u32>::load_from(&graph_directory.join("lower_bound"))?;
 
-            let (pot_data, time) = measure(|| {
+            let (pot_data, times) = benchmark(num_benchmark_repetitions()?, || {
                 let lower_bound_graph = FirstOutGraph::new(graph.first_out(), graph.head(), &lower_bound[..]);
                 CCHPotData::new(&cch, &lower_bound_graph)
             });
-            println!("Complete customization took {} ms", time.as_secs_f64() * 1000.0);
+            println!("Complete customization took {}", times);
 
             let customized = pot_data.customized();
             let mem_usage = customized.cch().mem_size()
@@ -62,8 +69,11 @@ fn main() -> Result<(), Box<dyn Error>> {
             let num_intervals = parse_arg_optional(&mut remaining_args, 72);
 
             let graph = convert_to_td_graph(&graph);
-            let (customized, time) = measure(|| CustomizedCorridorLowerbound::new_from_ptv(&cch, &graph, num_intervals));
-            println!("Complete customization took {} ms", time.as_secs_f64() * 1000.0);
+            let customization_progress = ProgressPrinter::new("Interval minima customization");
+            let (customized, times) = benchmark(num_benchmark_repetitions()?, || {
+                CustomizedCorridorLowerbound::new_from_ptv_with_progress(&cch, &graph, num_intervals, &|done, total| customization_progress.update(done, total))
+            });
+            println!("Complete customization took {}", times);
 
             let mem_usage = customized.cch.mem_size()
                 + std::mem::size_of_val(&*customized.downward_intervals)
@@ -83,7 +93,13 @@ fn main() -> Result<(), Box<dyn Error>> {
             let output_path = create_output_directory(&graph_directory, output_directory)?;
 
             let num_metrics = parse_arg_optional(&mut remaining_args, 20);
-            let (customized_multi_metric, time) = measure(|| CustomizedMultiMetrics::new_from_ptv(cch, &graph, &balanced_interval_pattern(), num_metrics));
+            let interval_pattern = interval_pattern_from_env(balanced_interval_pattern)?;
+            let customization_progress = ProgressPrinter::new("Multi-metric customization");
+            let (customized_multi_metric, time) = measure(|| {
+                CustomizedMultiMetrics::new_from_ptv_with_progress(cch, &graph, &interval_pattern, num_metrics, &|done, total| {
+                    customization_progress.update(done, total)
+                })
+            });
             println!("Complete customization took {} ms", time.as_secs_f64() * 1000.0);
 
             let memory_usage = std::mem::size_of_val(&*customized_multi_metric.upward)
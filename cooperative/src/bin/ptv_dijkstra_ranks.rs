@@ -2,7 +2,7 @@ use cooperative::dijkstra::potentials::corridor_lowerbound_potential::customizat
 use cooperative::dijkstra::potentials::multi_metric_potential::customization::CustomizedMultiMetrics;
 use cooperative::dijkstra::ptv_server::{PTVQueryResult, PTVQueryServer};
 use cooperative::io::io_ptv_customization::{load_interval_minima, load_multiple_metrics};
-use cooperative::io::io_queries::load_queries;
+use cooperative::io::io_queries::{load_queries, load_query_ranks};
 use cooperative::util::cli_args::parse_arg_required;
 use rust_road_router::algo::ch_potentials::{BorrowedCCHPot, CCHPotData};
 use rust_road_router::algo::customizable_contraction_hierarchy::CCH;
@@ -11,7 +11,7 @@ use rust_road_router::datastr::graph::time_dependent::{TDGraph, Timestamp};
 use rust_road_router::datastr::graph::{FirstOutGraph, Graph};
 use rust_road_router::datastr::node_order::NodeOrder;
 use rust_road_router::io::{Load, Reconstruct};
-use rust_road_router::report::measure;
+use rust_road_router::report::{benchmark, measure, num_benchmark_repetitions};
 use std::env;
 use std::error::Error;
 use std::fs::File;
@@ -34,12 +34,22 @@ fn main() -> Result<(), Box<dyn Error>> {
     let query_path = path.join("queries").join(&query_directory);
 
     let queries = load_queries(&query_path)?;
-    let num_queries_per_rank = *Vec::<u32>::load_from(&query_path.join("num_queries")).unwrap().first().unwrap();
-    let max_rank = *Vec::<u32>::load_from(&query_path.join("max_rank")).unwrap().first().unwrap();
-
-    let first_rank = max_rank + 1 - (queries.len() as u32 / num_queries_per_rank);
+    let ranks = match load_query_ranks(&query_path, queries.len())? {
+        Some(ranks) => ranks,
+        None => {
+            // older query sets only store the rank range, the queries are grouped by rank
+            let num_queries_per_rank = *Vec::<u32>::load_from(&query_path.join("num_queries")).unwrap().first().unwrap();
+            let max_rank = *Vec::<u32>::load_from(&query_path.join("max_rank")).unwrap().first().unwrap();
+
+            let first_rank = max_rank + 1 - (queries.len() as u32 / num_queries_per_rank);
+            debug_assert_eq!(first_rank, 8u32);
+            (0..queries.len() as u32).map(|idx| first_rank + idx / num_queries_per_rank).collect()
+        }
+    };
     let mut query_results = Vec::with_capacity(queries.len() * 3);
-    debug_assert_eq!(first_rank, 8u32);
+
+    // with `BENCHMARK_REPETITIONS` set, the median of the warm runs is taken
+    let repetitions = num_benchmark_repetitions()?;
 
     // init cch
     let order = Vec::load_from(path.join("cch_perm"))?;
@@ -59,10 +69,10 @@ fn main() -> Result<(), Box<dyn Error>> {
         &mut server,
         |s: &mut PTVQueryServer<BorrowedCCHPot>, q: &TDQuery<u32>| s.query(q),
         &queries,
-        first_rank,
-        num_queries_per_rank,
+        &ranks,
         &mut query_results,
         "cch-pot".to_string(),
+        repetitions,
     );
     let (graph, cch_lowerbound_pot) = server.decompose();
     drop(cch_lowerbound_pot);
@@ -78,10 +88,10 @@ fn main() -> Result<(), Box<dyn Error>> {
         &mut server,
         |s: &mut PTVQueryServer<CustomizedMultiMetrics>, q: &TDQuery<u32>| s.query(q),
         &queries,
-        first_rank,
-        num_queries_per_rank,
+        &ranks,
         &mut query_results,
         "multi-metric".to_string(),
+        repetitions,
     );
     let (graph, customized) = server.decompose();
     drop(customized);
@@ -97,10 +107,10 @@ fn main() -> Result<(), Box<dyn Error>> {
         &mut server,
         |s: &mut PTVQueryServer<CustomizedCorridorLowerbound>, q: &TDQuery<u32>| s.query(q),
         &queries,
-        first_rank,
-        num_queries_per_rank,
+        &ranks,
         &mut query_results,
         "corridor-lowerbound".to_string(),
+        repetitions,
     );
     let (graph, customized) = server.decompose();
     drop(customized);
@@ -114,18 +124,16 @@ fn execute_queries<Customized>(
     server: &mut PTVQueryServer<Customized>,
     query_fn: fn(&mut PTVQueryServer<Customized>, &TDQuery<u32>) -> PTVQueryResult,
     queries: &Vec<TDQuery<Timestamp>>,
-    first_rank: u32,
-    num_queries_per_rank: u32,
+    ranks: &[u32],
     results: &mut Vec<(String, u32, f64, u32)>,
     pot_name: String,
+    repetitions: usize,
 ) {
     queries.iter().enumerate().for_each(|(idx, query)| {
-        let (result, time) = measure(|| query_fn(server, query));
-
-        let time = time.as_secs_f64() * 1000.0;
-        let rank = first_rank + idx as u32 / num_queries_per_rank;
+        let (result, times) = benchmark(repetitions, || query_fn(server, query));
 
-        results.push((pot_name.clone(), rank, time, result.num_queue_pops));
+        let time = times.representative().as_secs_f64() * 1000.0;
+        results.push((pot_name.clone(), ranks[idx], time, result.num_queue_pops));
 
         if (idx + 1) % 1000 == 0 {
             println!("Finished {}/{} queries", idx + 1, queries.len());
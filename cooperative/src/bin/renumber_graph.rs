@@ -0,0 +1,43 @@
+use cooperative::io::io_node_order::load_node_order;
+use cooperative::io::modification::renumber_graph::{bfs_order, renumber_graph, RenumberOrder};
+use cooperative::util::cli_args::{parse_arg_optional, parse_arg_required};
+use rust_road_router::datastr::graph::{EdgeId, NodeId};
+use rust_road_router::datastr::node_order::NodeOrder;
+use rust_road_router::io::Load;
+use std::env;
+use std::error::Error;
+use std::path::Path;
+
+/// Physically renumbers a graph and all of its companion data according to a node order.
+/// The order is either the stored CCH order, a breadth-first order or a seeded random permutation.
+///
+/// Additional parameters: <path_to_graph> <output_directory> <order_type = CCH> <seed = 42>
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let graph_directory: String = parse_arg_required(&mut args, "Graph Directory")?;
+    let output_directory: String = parse_arg_required(&mut args, "Output Graph Directory")?;
+    let order_type = parse_arg_optional(&mut args, RenumberOrder::Stored);
+    let seed = parse_arg_optional(&mut args, 42u64);
+
+    let graph_path = Path::new(&graph_directory);
+    let out_path = Path::new(&output_directory);
+
+    let order = match order_type {
+        RenumberOrder::Stored => load_node_order(graph_path)?,
+        RenumberOrder::Bfs => {
+            let first_out = Vec::<EdgeId>::load_from(graph_path.join("first_out"))?;
+            let head = Vec::<NodeId>::load_from(graph_path.join("head"))?;
+            bfs_order(&first_out, &head)
+        }
+        RenumberOrder::Random => {
+            let first_out = Vec::<EdgeId>::load_from(graph_path.join("first_out"))?;
+            NodeOrder::random(first_out.len() - 1, seed)
+        }
+    };
+
+    std::fs::create_dir_all(out_path)?;
+    renumber_graph(graph_path, out_path, &order)?;
+    println!("Renumbered graph ({:?} order) written to {}", order_type, output_directory);
+
+    Ok(())
+}
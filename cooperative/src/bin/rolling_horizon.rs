@@ -0,0 +1,652 @@
+use cooperative::dijkstra::model::{CapacityQueryResult, PathResult, TripId, TripStatistics, VehicleStatistics};
+use cooperative::dijkstra::potentials::multi_metric_potential::customization::CustomizedMultiMetrics;
+use cooperative::dijkstra::potentials::multi_metric_potential::interval_patterns::{
+    complete_balanced_interval_pattern, interval_pattern_from_env, restrict_to_horizon,
+};
+use cooperative::dijkstra::server::{CapacityServer, CapacityServerOps};
+use cooperative::experiments::scenario::Scenario;
+use cooperative::graph::access_restrictions::{AccessRestrictions, VehicleClass, DEFAULT_VEHICLE_CLASS};
+use cooperative::graph::load_feed::observations_in_range;
+use cooperative::graph::parking::ParkingModel;
+use cooperative::graph::traffic_functions::BPRTrafficFunction;
+use cooperative::graph::{pce_load, Capacity, MAX_BUCKETS};
+use cooperative::io::io_coordinates::load_coords_f64;
+use cooperative::io::io_graph::{load_capacity_graph, load_edge_classes};
+use cooperative::io::io_load_feed::load_load_feed;
+use cooperative::io::io_node_order::load_coordinate_aware_node_order;
+use cooperative::io::io_parking::load_parking_zones;
+use cooperative::io::io_paths::store_assigned_paths;
+use cooperative::io::io_queries::{load_pce_factors, load_queries, load_return_trips, load_trip_ids, load_vehicle_classes};
+use cooperative::io::io_restrictions::load_restriction_zones;
+use cooperative::io::io_scenario::load_scenario;
+use cooperative::util::cli_args::{parse_arg_required, parse_named_arg, parse_named_args};
+use rust_road_router::algo::customizable_contraction_hierarchy::CCH;
+use rust_road_router::algo::TDQuery;
+use rust_road_router::cli::CliErr;
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{EdgeId, INFINITY};
+use rust_road_router::report::measure;
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::ops::Add;
+use std::path::Path;
+use std::time::Duration;
+
+/// Rolling-horizon re-optimization on a cooperative graph
+///
+/// Instead of processing all queries of a day as one batch, the time advances in fixed steps.
+/// In each step, the potential is re-customized for the upcoming horizon only.
+/// Vehicles departing within the current step are routed and added to the graph,
+/// vehicles departing later within the horizon are (re-)planned without updating the graph.
+/// A plan is counted as changed if its path differs from the plan of the previous step.
+/// Per-trip statistics (experienced delay etc.) are written next to the aggregated step statistics,
+/// the committed paths are stored in `paths/rolling_horizon` within the query directory.
+///
+/// If the query directory contains linked round trips (`return_trip`), both trips of a round trip are reported
+/// as one vehicle in the per-vehicle statistics. With `defer_return_trips` set, all return trips are routed in a second pass
+/// over the day, i.e. each return trip sees the traffic state of the full outward assignment.
+/// The `phase` column of the step statistics distinguishes both passes (0: outward trips or all trips, 1: return trips).
+///
+/// If a `load_feed` directory (relative to the graph) is given, the bucket loads are reconciled with the externally observed counts
+/// of the previous step before each re-customization, so the simulated congestion tracks the observed traffic.
+///
+/// Vehicles contribute to the bucket loads according to their passenger car equivalent (`pce`, defaults to 1.0).
+///
+/// If a `scenario` file (relative to the graph) is given, its lane closures and weather are applied at the start of each step
+/// and the loads of all vehicles are scaled by its demand scalings at their departure, see `load_scenario`.
+///
+/// If a `restriction_zones` file (relative to the graph) is given, its zones are enforced for the vehicle class of each query
+/// (`vehicle_class`, defaults to 0), see `load_restriction_zones`.
+/// If a `parking_zones` file (relative to the graph) is given, the parking search time at the destination of each vehicle
+/// is added to its results, depending on the occupancy of the destination zone, see `load_parking_zones`.
+/// Dedicated lanes (e.g. HOV or bus lanes) given by the graph's `edge_classes` are only used by the vehicle classes allowed on them.
+/// Each vehicle class is routed with a potential customized on the edges available to it, see `ClassPotentials`.
+///
+/// Queries that still fail after restoring the potential are skipped and counted, the potential is then fully re-customized.
+///
+/// Additional parameters: <path_to_graph> <path_to_queries> <num_buckets>, followed by optional `name=value` options in any order:
+/// `step_minutes=15` `horizon_minutes=60` `pot_num_metrics=20` `defer_return_trips=false` `load_feed=` `scenario=` `restriction_zones=` `parking_zones=`
+///
+/// Set `INTERVAL_PATTERN` to override the interval pattern of the multi-metric potential, see `parse_interval_pattern`.
+fn main() -> Result<(), Box<dyn Error>> {
+    let RollingHorizonConfig {
+        graph_directory,
+        query_directory,
+        num_buckets,
+        step,
+        horizon,
+        pot_num_metrics,
+        defer_return_trips,
+        load_feed_directory,
+        scenario_file,
+        restriction_file,
+        parking_file,
+    } = RollingHorizonConfig::from_args(env::args().skip(1))?;
+
+    let graph_path = Path::new(&graph_directory);
+    let query_path = graph_path.join("queries").join(&query_directory);
+
+    // load queries, process them in order of their departure
+    let queries = load_queries(&query_path)?;
+    let trip_ids = load_trip_ids(&query_path, queries.len())?;
+    let return_trips = load_return_trips(&query_path, queries.len())?;
+    let vehicle_classes = load_vehicle_classes(&query_path, queries.len())?;
+    // disruptions of the experiment, empty if not provided
+    let mut scenario = if scenario_file.is_empty() {
+        Scenario::default()
+    } else {
+        load_scenario(&graph_path.join(&scenario_file))?
+    };
+
+    let pce_loads = load_pce_factors(&query_path, queries.len())?
+        .into_iter()
+        .zip(queries.iter())
+        .map(|(pce_factor, query)| scenario.scaled_load(pce_load(pce_factor), query.departure))
+        .collect::<Vec<Capacity>>();
+
+    // a trip is a return trip if its partner departs earlier
+    let is_return_trip = return_trips
+        .as_ref()
+        .map(|partner| {
+            (0..queries.len())
+                .map(|idx| (queries[partner[idx] as usize].departure, partner[idx] as usize) < (queries[idx].departure, idx))
+                .collect::<Vec<bool>>()
+        })
+        .unwrap_or_else(|| vec![false; queries.len()]);
+    let linked_trips = return_trips
+        .as_ref()
+        .map(|partner| {
+            (0..queries.len())
+                .filter(|&idx| is_return_trip[idx])
+                .map(|idx| (trip_ids[partner[idx] as usize], trip_ids[idx]))
+                .collect::<Vec<(TripId, TripId)>>()
+        })
+        .unwrap_or_default();
+
+    let (queries, trip_ids, pce_loads, vehicle_classes, is_return_trip): (Vec<TDQuery<Timestamp>>, Vec<TripId>, Vec<Capacity>, Vec<VehicleClass>, Vec<bool>) = {
+        let mut order = (0..queries.len()).collect::<Vec<usize>>();
+        order.sort_by_key(|&idx| queries[idx].departure);
+        (
+            order.iter().map(|&idx| queries[idx].clone()).collect(),
+            order.iter().map(|&idx| trip_ids[idx]).collect(),
+            order.iter().map(|&idx| pce_loads[idx]).collect(),
+            order.iter().map(|&idx| vehicle_classes[idx]).collect(),
+            order.iter().map(|&idx| is_return_trip[idx]).collect(),
+        )
+    };
+
+    // process either all queries at once or outward trips before return trips, each phase sorted by departure
+    let phases = if defer_return_trips {
+        vec![
+            (0..queries.len()).filter(|&idx| !is_return_trip[idx]).collect::<Vec<usize>>(),
+            (0..queries.len()).filter(|&idx| is_return_trip[idx]).collect::<Vec<usize>>(),
+        ]
+    } else {
+        vec![(0..queries.len()).collect::<Vec<usize>>()]
+    };
+
+    // external load feed, empty if not provided
+    let load_feed = if load_feed_directory.is_empty() {
+        Vec::new()
+    } else {
+        load_load_feed(&graph_path.join(&load_feed_directory))?
+    };
+
+    // init graph, potential and server
+    let mut graph = load_capacity_graph(&graph_path, num_buckets, BPRTrafficFunction::default())?;
+    if let Some(edge_classes) = load_edge_classes(&graph_path)? {
+        let num_dedicated = edge_classes.iter().filter(|&&classes| classes != u32::MAX).count();
+        println!("{} edges are restricted to certain vehicle classes", num_dedicated);
+        graph.set_edge_classes(edge_classes);
+    }
+    if scenario.has_weather_regions() {
+        let (longitude, latitude) = load_coords_f64(&graph_path)?;
+        scenario.resolve_weather_regions(&graph, &longitude, &latitude);
+    }
+    scenario.validate(&graph)?;
+    let restrictions = if restriction_file.is_empty() {
+        None
+    } else {
+        let (longitude, latitude) = load_coords_f64(&graph_path)?;
+        let restrictions = AccessRestrictions::new(load_restriction_zones(&graph_path.join(&restriction_file))?, &graph, &longitude, &latitude);
+        println!("Restriction zones contain {} edges", restrictions.num_restricted_edges());
+        Some(restrictions)
+    };
+    let parking = if parking_file.is_empty() {
+        None
+    } else {
+        let (longitude, latitude) = load_coords_f64(&graph_path)?;
+        let parking = ParkingModel::new(load_parking_zones(&graph_path.join(&parking_file))?, &longitude, &latitude);
+        println!("Parking zones contain {} nodes", parking.num_zone_nodes());
+        Some(parking)
+    };
+    let order = load_coordinate_aware_node_order(&graph_path, &graph)?;
+    let mut classes = vehicle_classes.clone();
+    classes.sort_unstable();
+    classes.dedup();
+    if classes.is_empty() {
+        classes.push(DEFAULT_VEHICLE_CLASS);
+    }
+
+    let intervals = interval_pattern_from_env(complete_balanced_interval_pattern)?;
+    let (mut customized, init_time) = measure(|| {
+        let initial_intervals = horizon_intervals(&intervals, 0, horizon.min(MAX_BUCKETS));
+        classes
+            .iter()
+            .map(|&class| {
+                let cch = CCH::fix_order_and_build(&graph, order.clone());
+                (
+                    class,
+                    CustomizedMultiMetrics::new_from_capacity_for_class(cch, &graph, class, &initial_intervals, pot_num_metrics),
+                )
+            })
+            .collect::<Vec<(VehicleClass, CustomizedMultiMetrics)>>()
+    });
+    let (active_class, active) = customized.remove(0);
+    let mut server = CapacityServer::new(graph, active);
+    let mut potentials = ClassPotentials::new(&mut server, active_class, customized);
+    server.set_access_restrictions(restrictions);
+    server.set_parking_model(parking);
+
+    // link both trips of each round trip to the same vehicle
+    for &(outward_trip, return_trip) in &linked_trips {
+        server.link_trips(outward_trip, return_trip);
+    }
+
+    // the current plan of each query, committed as soon as the vehicle departs
+    let mut plans: Vec<Option<Vec<EdgeId>>> = vec![None; queries.len()];
+    let mut committed: Vec<Option<PathResult>> = vec![None; queries.len()];
+    let mut results = Vec::new();
+    let mut num_skipped = 0;
+
+    for (phase_idx, phase) in phases.iter().enumerate() {
+        let phase_queries = phase.iter().map(|&idx| queries[idx].clone()).collect::<Vec<TDQuery<Timestamp>>>();
+
+        let mut step_start = 0;
+        while step_start < MAX_BUCKETS {
+            let step_end = (step_start + step).min(MAX_BUCKETS);
+            let horizon_end = (step_start + horizon).min(MAX_BUCKETS);
+
+            // 1. synchronize with the loads observed during the previous step, then re-customize the potential for the upcoming horizon
+            if step_start > 0 && phase_idx == 0 {
+                let observations = observations_in_range(&load_feed, step_start - step, step_start);
+                if !observations.is_empty() {
+                    let statistics = server.synchronize_loads(observations);
+                    println!(
+                        "Synchronized {} observations on {} edges (simulated load: {}, observed load: {})",
+                        statistics.num_observations, statistics.num_edges, statistics.simulated_load, statistics.observed_load
+                    );
+                }
+            }
+
+            // lane closures and weather change the graph in both directions, the potential is re-customized anyway
+            let num_closures = scenario.apply_lane_closures(&mut server, step_start);
+            if num_closures > 0 {
+                println!("Scenario: changed the closed lanes of {} edges", num_closures);
+            }
+            let num_weather = scenario.apply_weather(&mut server, step_start);
+            if num_weather > 0 {
+                println!("Scenario: changed the weather of {} edges", num_weather);
+            }
+            let num_modified = num_closures + num_weather;
+
+            let step_intervals = horizon_intervals(&intervals, step_start, horizon_end);
+            let (_, cust_time) = if step_start == 0 && phase_idx == 0 && num_modified == 0 {
+                ((), init_time)
+            } else {
+                measure(|| potentials.customize_all(&mut server, &step_intervals, pot_num_metrics))
+            };
+
+            // 2. route all vehicles departing within the current step, grouped by their vehicle class to avoid switching potentials
+            let departing = query_range(&phase_queries, step_start, step_end);
+            let mut departing_by_class = departing.clone().map(|i| phase[i]).collect::<Vec<usize>>();
+            departing_by_class.sort_by_key(|&idx| vehicle_classes[idx]);
+            let mut num_changed_on_departure = 0;
+            let (_, departure_time) = measure(|| {
+                for &idx in &departing_by_class {
+                    potentials.activate(&mut server, vehicle_classes[idx]);
+                    let path = run_query(
+                        &mut server,
+                        &step_intervals,
+                        pot_num_metrics,
+                        &queries[idx],
+                        trip_ids[idx],
+                        pce_loads[idx],
+                        true,
+                        &mut num_skipped,
+                    )
+                    .map(|result| result.path);
+                    potentials.mark_updated();
+
+                    if plans[idx].is_some() && plans[idx].as_ref() != path.as_ref().map(|path| &path.edge_path) {
+                        num_changed_on_departure += 1;
+                    }
+                    committed[idx] = path;
+                }
+            });
+
+            // 3. re-plan all vehicles departing later within the horizon, without updating the graph
+            let planned = query_range(&phase_queries, step_end, horizon_end);
+            let mut num_changed_plans = 0;
+            let (_, planning_time) = measure(|| {
+                for idx in planned.clone().map(|i| phase[i]) {
+                    potentials.activate(&mut server, vehicle_classes[idx]);
+                    let path = run_query(
+                        &mut server,
+                        &step_intervals,
+                        pot_num_metrics,
+                        &queries[idx],
+                        trip_ids[idx],
+                        pce_loads[idx],
+                        false,
+                        &mut num_skipped,
+                    )
+                    .map(|result| result.path.edge_path);
+
+                    if plans[idx].is_some() && plans[idx] != path {
+                        num_changed_plans += 1;
+                    }
+                    plans[idx] = path;
+                }
+            });
+
+            if !departing.is_empty() || !planned.is_empty() {
+                println!(
+                    "Phase {}, step {}-{}: {} departures ({} changed), {} plans ({} changed) - customization: {}s, queries: {}s, planning: {}s",
+                    phase_idx,
+                    step_start,
+                    step_end,
+                    departing.len(),
+                    num_changed_on_departure,
+                    planned.len(),
+                    num_changed_plans,
+                    cust_time.as_secs_f64(),
+                    departure_time.as_secs_f64(),
+                    planning_time.as_secs_f64()
+                );
+            }
+
+            results.push(RollingHorizonStatisticEntry {
+                phase: phase_idx as u32,
+                step_start,
+                num_departures: departing.len() as u32,
+                num_changed_on_departure,
+                num_plans: planned.len() as u32,
+                num_changed_plans,
+                customization_time: cust_time,
+                query_time: departure_time,
+                planning_time,
+            });
+
+            step_start = step_end;
+        }
+    }
+
+    // evaluate the committed paths on the final graph
+    let (total_dist, num_routed) = committed
+        .iter()
+        .zip(queries.iter())
+        .filter_map(|(path, query)| path.as_ref().map(|path| server.path_distance(&path.edge_path, query.departure)))
+        .filter(|&dist| dist != INFINITY)
+        .fold((0u64, 0u64), |(sum, count), dist| (sum + dist as u64, count + 1));
+
+    let trip_statistics = server.trip_statistics();
+    let total_parking_time = trip_statistics.iter().map(|entry| entry.parking_time as u64).sum::<u64>();
+
+    let total_time = results.iter().fold(Duration::ZERO, |acc, entry| {
+        acc.add(entry.customization_time).add(entry.query_time).add(entry.planning_time)
+    });
+
+    println!("------------------------------------------");
+    println!(
+        "Routed {} of {} queries, total distance: {} (avg: {}), total time: {}s",
+        num_routed,
+        queries.len(),
+        total_dist,
+        total_dist / num_routed.max(1),
+        total_time.as_secs_f64()
+    );
+    if num_skipped > 0 {
+        println!("Skipped {} queries which failed twice in the same step", num_skipped);
+    }
+    if server.parking_model().is_some() {
+        println!(
+            "Total parking search time: {} (avg: {}), door-to-door: {}",
+            total_parking_time,
+            total_parking_time / num_routed.max(1),
+            total_dist + total_parking_time
+        );
+    }
+
+    write_results(&results, &query_path)?;
+    let paths_path = query_path.join("paths").join("rolling_horizon");
+    std::fs::create_dir_all(&paths_path)?;
+    store_assigned_paths(&committed.into_iter().flatten().collect(), &paths_path)?;
+    write_trip_statistics(&trip_statistics, &query_path)?;
+    write_vehicle_statistics(&server.vehicle_statistics(), &query_path)
+}
+
+/// restrict the interval pattern to the given horizon, fall back to the horizon itself if no interval remains
+fn horizon_intervals(intervals: &Vec<(Timestamp, Timestamp)>, start: Timestamp, end: Timestamp) -> Vec<(Timestamp, Timestamp)> {
+    let ret = restrict_to_horizon(intervals, start, end);
+
+    if ret.is_empty() {
+        vec![(start, end)]
+    } else {
+        ret
+    }
+}
+
+/// range of query indices departing within `[start, end)`, queries must be sorted by departure
+fn query_range(queries: &Vec<TDQuery<Timestamp>>, start: Timestamp, end: Timestamp) -> std::ops::Range<usize> {
+    let first = queries.partition_point(|query| query.departure < start);
+    let last = queries.partition_point(|query| query.departure < end);
+    first..last.max(first)
+}
+
+/// execute a single query, restore the potential if it became invalid (see `CapacityServer::restore_potential`).
+/// If the query fails again, it is skipped (and counted in `num_skipped`) after a full re-customization.
+fn run_query(
+    server: &mut CapacityServer<CustomizedMultiMetrics>,
+    intervals: &Vec<(Timestamp, Timestamp)>,
+    pot_num_metrics: usize,
+    query: &TDQuery<Timestamp>,
+    trip_id: TripId,
+    pce_load: Capacity,
+    update: bool,
+    num_skipped: &mut u32,
+) -> Option<CapacityQueryResult> {
+    let mut bounds_updated = false;
+
+    let result = loop {
+        let result = server.query_trip_with_pce(query, trip_id, pce_load, false);
+        if server.result_valid() {
+            break result;
+        }
+
+        if bounds_updated {
+            // avoid infinite loops, continue with a fully re-customized potential
+            println!("-- Query {:?} failed twice in the same step, skipping it", query);
+            *num_skipped += 1;
+            server.customize(intervals, pot_num_metrics);
+            return None;
+        }
+
+        // re-customization of the potential
+        bounds_updated = true;
+        server.restore_potential(intervals, pot_num_metrics);
+    };
+
+    // only the final result is committed, so retries are not counted as routes of the trip
+    let result = match result {
+        Some(result) if update => Some(server.commit_trip(trip_id, result)),
+        result => result,
+    };
+
+    // an invalid update was already applied to the graph, only the potential has to be restored
+    if !server.update_valid() {
+        server.restore_potential(intervals, pot_num_metrics);
+    }
+    result
+}
+
+fn write_results(results: &Vec<RollingHorizonStatisticEntry>, path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut file = File::create(&path.join("rolling_horizon.csv"))?;
+
+    let header = "phase,step_start,num_departures,num_changed_on_departure,num_plans,num_changed_plans,cust_time,query_time,planning_time\n";
+    file.write(header.as_bytes())?;
+
+    for entry in results {
+        let line = format!(
+            "{},{},{},{},{},{},{},{},{}\n",
+            entry.phase,
+            entry.step_start,
+            entry.num_departures,
+            entry.num_changed_on_departure,
+            entry.num_plans,
+            entry.num_changed_plans,
+            entry.customization_time.as_secs_f64(),
+            entry.query_time.as_secs_f64(),
+            entry.planning_time.as_secs_f64()
+        );
+        file.write(line.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+fn write_trip_statistics(statistics: &Vec<TripStatistics>, path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut file = File::create(&path.join("rolling_horizon_trips.csv"))?;
+
+    let header = "trip_id,departure,num_routes,num_reroutes,routed_time,experienced_time,free_flow_time,delay,parking_time\n";
+    file.write(header.as_bytes())?;
+
+    for entry in statistics {
+        let line = format!(
+            "{},{},{},{},{},{},{},{},{}\n",
+            entry.trip_id,
+            entry.departure,
+            entry.num_routes,
+            entry.num_reroutes,
+            entry.routed_travel_time,
+            entry.experienced_travel_time,
+            entry.free_flow_travel_time,
+            entry.experienced_delay(),
+            entry.parking_time
+        );
+        file.write(line.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+fn write_vehicle_statistics(statistics: &Vec<VehicleStatistics>, path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut file = File::create(&path.join("rolling_horizon_vehicles.csv"))?;
+
+    let header = "vehicle_id,num_trips,num_routes,num_reroutes,routed_time,experienced_time,free_flow_time,delay,parking_time\n";
+    file.write(header.as_bytes())?;
+
+    for entry in statistics {
+        let line = format!(
+            "{},{},{},{},{},{},{},{},{}\n",
+            entry.vehicle_id,
+            entry.num_trips,
+            entry.num_routes,
+            entry.num_reroutes,
+            entry.routed_travel_time,
+            entry.experienced_travel_time,
+            entry.free_flow_travel_time,
+            entry.experienced_delay(),
+            entry.parking_time
+        );
+        file.write(line.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// command line parameters, see `main`
+struct RollingHorizonConfig {
+    graph_directory: String,
+    query_directory: String,
+    num_buckets: u32,
+    step: Timestamp,
+    horizon: Timestamp,
+    pot_num_metrics: usize,
+    defer_return_trips: bool,
+    load_feed_directory: String,
+    scenario_file: String,
+    restriction_file: String,
+    parking_file: String,
+}
+
+impl RollingHorizonConfig {
+    const OPTIONS: [&'static str; 8] = [
+        "step_minutes",
+        "horizon_minutes",
+        "pot_num_metrics",
+        "defer_return_trips",
+        "load_feed",
+        "scenario",
+        "restriction_zones",
+        "parking_zones",
+    ];
+
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, Box<dyn Error>> {
+        let graph_directory = parse_arg_required(&mut args, "Graph Directory")?;
+        let query_directory = parse_arg_required(&mut args, "Query Directory")?;
+        let num_buckets = parse_arg_required(&mut args, "Num Buckets")?;
+
+        let options = parse_named_args(args, &Self::OPTIONS)?;
+        let step_minutes: u32 = parse_named_arg(&options, "step_minutes", 15)?;
+        let horizon_minutes: u32 = parse_named_arg(&options, "horizon_minutes", 60)?;
+
+        if step_minutes == 0 {
+            return Err(Box::new(CliErr("Step size must be positive!")));
+        }
+        if horizon_minutes < step_minutes {
+            return Err(Box::new(CliErr("Horizon must not be shorter than a single step!")));
+        }
+
+        Ok(Self {
+            graph_directory,
+            query_directory,
+            num_buckets,
+            step: step_minutes * 60_000,
+            horizon: horizon_minutes * 60_000,
+            pot_num_metrics: parse_named_arg(&options, "pot_num_metrics", 20)?,
+            defer_return_trips: parse_named_arg(&options, "defer_return_trips", false)?,
+            load_feed_directory: parse_named_arg(&options, "load_feed", String::new())?,
+            scenario_file: parse_named_arg(&options, "scenario", String::new())?,
+            restriction_file: parse_named_arg(&options, "restriction_zones", String::new())?,
+            parking_file: parse_named_arg(&options, "parking_zones", String::new())?,
+        })
+    }
+}
+
+/// Potentials customized on the edges available to each vehicle class (see `CustomizedMultiMetrics::new_from_capacity_for_class`).
+/// The server holds the potential of the active class, the others are kept here.
+struct ClassPotentials {
+    active: VehicleClass,
+    // flag: loads were added to the graph since the potential was held by the server
+    inactive: HashMap<VehicleClass, (CustomizedMultiMetrics, bool)>,
+}
+
+impl ClassPotentials {
+    fn new(server: &mut CapacityServer<CustomizedMultiMetrics>, active: VehicleClass, inactive: Vec<(VehicleClass, CustomizedMultiMetrics)>) -> Self {
+        server.set_vehicle_class(active);
+        Self {
+            active,
+            inactive: inactive.into_iter().map(|(class, customized)| (class, (customized, false))).collect(),
+        }
+    }
+
+    /// switch the server to the potential of `vehicle_class`, its upper bounds are re-customized if loads were added in the meantime
+    fn activate(&mut self, server: &mut CapacityServer<CustomizedMultiMetrics>, vehicle_class: VehicleClass) {
+        server.set_vehicle_class(vehicle_class);
+        if vehicle_class == self.active {
+            return;
+        }
+
+        let (customized, outdated) = self.inactive.remove(&vehicle_class).unwrap();
+        let previous = server.replace_customized(customized);
+        self.inactive.insert(self.active, (previous, false));
+        self.active = vehicle_class;
+
+        if outdated {
+            server.customize_upper_bound();
+        }
+    }
+
+    /// loads were added to the graph, the upper bounds of the inactive potentials may be exceeded
+    fn mark_updated(&mut self) {
+        self.inactive.values_mut().for_each(|(_, outdated)| *outdated = true);
+    }
+
+    /// re-customize the potentials of all classes, afterwards the previously active class is active again
+    fn customize_all(&mut self, server: &mut CapacityServer<CustomizedMultiMetrics>, intervals: &Vec<(Timestamp, Timestamp)>, num_max_metrics: usize) {
+        let active = self.active;
+        let classes = self.inactive.keys().cloned().collect::<Vec<VehicleClass>>();
+        for class in classes.into_iter().chain(std::iter::once(active)) {
+            // the full customization replaces the upper bounds anyway
+            self.inactive.get_mut(&class).into_iter().for_each(|(_, outdated)| *outdated = false);
+            self.activate(server, class);
+            server.customize(intervals, num_max_metrics);
+        }
+    }
+}
+
+struct RollingHorizonStatisticEntry {
+    pub phase: u32,
+    pub step_start: Timestamp,
+    pub num_departures: u32,
+    pub num_changed_on_departure: u32,
+    pub num_plans: u32,
+    pub num_changed_plans: u32,
+    pub customization_time: Duration,
+    pub query_time: Duration,
+    pub planning_time: Duration,
+}
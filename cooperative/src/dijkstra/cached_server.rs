@@ -0,0 +1,307 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use rust_road_router::algo::TDQuery;
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{EdgeId, Graph, NodeId, Weight};
+
+use crate::dijkstra::model::{CapacityQueryResult, DistanceMeasure, MeasuredCapacityQueryResult, PathResult, PathSegment};
+use crate::dijkstra::server::{CapacityServer, CapacityServerOps};
+use crate::graph::{Capacity, MAX_BUCKETS};
+
+/// (source, target, departure bucket)
+type CacheKey = (NodeId, NodeId, u32);
+
+struct CacheEntry {
+    node_path: Vec<NodeId>,
+    edge_path: Vec<EdgeId>,
+    // departure and travel time the path was computed for
+    departure: Timestamp,
+    travel_time: Weight,
+    // logical time of the last validation
+    validated_at: u64,
+    last_used: u64,
+}
+
+/// LRU cache in front of a `CapacityServer`.
+///
+/// Results are keyed on source, target and the departure bucket of the underlying graph.
+/// A cached path is reused for all departures within the same bucket, its travel time is re-evaluated at the actual departure.
+/// On single-bucket graphs this is exact, otherwise the path is only guaranteed to be optimal for the departure it was computed for.
+///
+/// Capacity updates only increase travel times, so a cached path stays optimal as long as its own travel time doesn't change.
+/// Paths with edges modified by other queries are therefore re-evaluated on lookup and evicted if their travel time changed.
+/// The load a cooperative query (`update == true`) adds to its own path doesn't evict the entry: it keeps serving
+/// the same OD pair with the increased travel time, even if an alternative might now be slightly faster.
+/// Withdrawing a path may decrease travel times anywhere, hence it clears the whole cache.
+///
+/// A capacity of 0 disables the cache, all queries are passed to the server.
+pub struct CachedCapacityServer<PotCustomized> {
+    server: CapacityServer<PotCustomized>,
+    capacity: usize,
+    entries: HashMap<CacheKey, CacheEntry>,
+    // last usage -> key, the first element is the least recently used entry
+    lru: BTreeMap<u64, CacheKey>,
+    // logical time of the last modification of each edge
+    edge_modified: Vec<u64>,
+    clock: u64,
+    num_hits: usize,
+    num_misses: usize,
+}
+
+impl<PotCustomized> CachedCapacityServer<PotCustomized> {
+    pub fn new(server: CapacityServer<PotCustomized>, capacity: usize) -> Self {
+        let m = server.borrow_graph().num_arcs();
+
+        Self {
+            server,
+            capacity,
+            entries: HashMap::with_capacity(capacity),
+            lru: BTreeMap::new(),
+            edge_modified: vec![0; m],
+            clock: 0,
+            num_hits: 0,
+            num_misses: 0,
+        }
+    }
+
+    pub fn borrow_server(&self) -> &CapacityServer<PotCustomized> {
+        &self.server
+    }
+
+    /// direct access to the server, the cache is cleared as the graph may be modified
+    pub fn borrow_server_mut(&mut self) -> &mut CapacityServer<PotCustomized> {
+        self.clear_cache();
+        &mut self.server
+    }
+
+    pub fn decompose(self) -> CapacityServer<PotCustomized> {
+        self.server
+    }
+
+    /// (number of hits, number of misses)
+    pub fn cache_statistics(&self) -> (usize, usize) {
+        (self.num_hits, self.num_misses)
+    }
+
+    pub fn clear_cache(&mut self) {
+        self.entries.clear();
+        self.lru.clear();
+    }
+
+    fn key(&self, query: &TDQuery<Timestamp>) -> CacheKey {
+        let bucket_size = MAX_BUCKETS / self.server.borrow_graph().num_buckets();
+        (query.from, query.to, (query.departure % MAX_BUCKETS) / bucket_size)
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.lru.remove(&entry.last_used);
+        }
+    }
+
+    /// timestamps at each node of the given path, starting at `departure`
+    fn path_departures(&self, edge_path: &[EdgeId], departure: Timestamp) -> Vec<Timestamp> {
+        let graph = self.server.borrow_graph();
+        let mut departures = Vec::with_capacity(edge_path.len() + 1);
+        let mut current_time = departure;
+        departures.push(current_time);
+
+        for &edge_id in edge_path {
+            current_time += graph.travel_time_function(edge_id).eval(current_time);
+            departures.push(current_time);
+        }
+        departures
+    }
+
+    fn lookup(&mut self, query: &TDQuery<Timestamp>) -> Option<CapacityQueryResult> {
+        let key = self.key(query);
+        let entry = self.entries.get(&key)?;
+
+        // 1. re-validate the path if any of its edges was modified since the last validation
+        let modified = entry.edge_path.iter().any(|&edge_id| self.edge_modified[edge_id as usize] > entry.validated_at);
+        if modified {
+            let departures = self.path_departures(&entry.edge_path, entry.departure);
+            if departures.last().unwrap() - entry.departure != entry.travel_time {
+                self.remove(&key);
+                return None;
+            }
+        }
+
+        // 2. evaluate the path at the actual departure
+        let departures = self.path_departures(&entry.edge_path, query.departure);
+        let result = CapacityQueryResult::new(
+            departures.last().unwrap() - query.departure,
+            PathResult::new(entry.node_path.clone(), entry.edge_path.clone(), departures),
+        );
+
+        // 3. mark as most recently used
+        let now = self.tick();
+        let entry = self.entries.get_mut(&key).unwrap();
+        if modified {
+            entry.validated_at = now;
+        }
+        self.lru.remove(&entry.last_used);
+        entry.last_used = now;
+        self.lru.insert(now, key);
+
+        Some(result)
+    }
+
+    fn insert(&mut self, query: &TDQuery<Timestamp>, result: &CapacityQueryResult) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let key = self.key(query);
+        self.remove(&key);
+
+        if self.entries.len() >= self.capacity {
+            if let Some((_, lru_key)) = self.lru.pop_first() {
+                self.entries.remove(&lru_key);
+            }
+        }
+
+        let now = self.tick();
+        self.entries.insert(
+            key,
+            CacheEntry {
+                node_path: result.path.node_path.clone(),
+                edge_path: result.path.edge_path.clone(),
+                departure: query.departure,
+                travel_time: result.distance,
+                validated_at: now,
+                last_used: now,
+            },
+        );
+        self.lru.insert(now, key);
+    }
+
+    /// Add the path of a query to the graph. The entry of the query is re-validated afterwards,
+    /// so it isn't evicted because of its own load.
+    fn update_cached(&mut self, query: &TDQuery<Timestamp>, path: &PathResult)
+    where
+        CapacityServer<PotCustomized>: CapacityServerOps,
+    {
+        self.update(path);
+
+        let key = self.key(query);
+        let travel_time = match self.entries.get(&key) {
+            Some(entry) => self.path_departures(&entry.edge_path, entry.departure).last().unwrap() - entry.departure,
+            None => return,
+        };
+        let now = self.tick();
+        let entry = self.entries.get_mut(&key).unwrap();
+        entry.travel_time = travel_time;
+        entry.validated_at = now;
+    }
+}
+
+impl<PotCustomized> CapacityServerOps for CachedCapacityServer<PotCustomized>
+where
+    CapacityServer<PotCustomized>: CapacityServerOps,
+{
+    // plain distance queries bypass the cache, the path is taken from the underlying server
+    fn distance(&mut self, query: &TDQuery<Timestamp>) -> DistanceMeasure {
+        self.server.distance(query)
+    }
+
+    fn result_valid(&self) -> bool {
+        self.server.result_valid()
+    }
+
+    fn update(&mut self, path: &PathResult) {
+        self.server.update(path);
+
+        let now = self.tick();
+        for &edge_id in &path.edge_path {
+            self.edge_modified[edge_id as usize] = now;
+        }
+    }
+
+    fn withdraw(&mut self, path: &PathResult) {
+        self.server.withdraw(path);
+        self.clear_cache();
+    }
+
+    fn path(&self, query: &TDQuery<Timestamp>) -> PathResult {
+        self.server.path(query)
+    }
+
+    fn path_distance(&self, edge_path: &Vec<EdgeId>, query_start: Timestamp) -> Weight {
+        self.server.path_distance(edge_path, query_start)
+    }
+
+    fn path_breakdown(&self, edge_path: &Vec<EdgeId>, query_start: Timestamp) -> Vec<PathSegment> {
+        self.server.path_breakdown(edge_path, query_start)
+    }
+
+    fn query_with_pce(&mut self, query: &TDQuery<Timestamp>, pce_load: Capacity, update: bool) -> Option<CapacityQueryResult> {
+        let mut result = if let Some(result) = self.lookup(query) {
+            self.num_hits += 1;
+            result
+        } else {
+            self.num_misses += 1;
+            let result = self.server.query(query, false)?;
+            self.insert(query, &result);
+            result
+        };
+        result.path.pce_load = pce_load;
+
+        if update {
+            self.update_cached(query, &result.path);
+        }
+        Some(result)
+    }
+
+    fn query_measured(&mut self, query: &TDQuery<Timestamp>, update: bool) -> MeasuredCapacityQueryResult {
+        let start = Instant::now();
+        if let Some(result) = self.lookup(query) {
+            self.num_hits += 1;
+            let distance_result = DistanceMeasure {
+                distance: Some(result.distance),
+                potential: None,
+                time_potential: Duration::ZERO,
+                time_query: start.elapsed(),
+                num_queue_pushs: 0,
+                num_queue_pops: 0,
+                num_relaxed_arcs: 0,
+                num_pot_computations: None,
+                search_statistics: None,
+            };
+
+            let update_time = if update {
+                let start = Instant::now();
+                self.update_cached(query, &result.path);
+                start.elapsed()
+            } else {
+                Duration::ZERO
+            };
+
+            return MeasuredCapacityQueryResult {
+                query_result: Some(result),
+                distance_result,
+                update_time,
+            };
+        }
+
+        self.num_misses += 1;
+        let mut measured = self.server.query_measured(query, false);
+        if let Some(result) = &measured.query_result {
+            self.insert(query, result);
+
+            if update {
+                let start = Instant::now();
+                let path = result.path.clone();
+                self.update_cached(query, &path);
+                measured.update_time = start.elapsed();
+            }
+        }
+        measured
+    }
+}
@@ -1,24 +1,111 @@
-use rust_road_router::algo::dijkstra::DijkstraOps;
-use rust_road_router::datastr::graph::{EdgeIdT, NodeId, NodeIdT, Weight};
+use std::marker::PhantomData;
+
+use rust_road_router::algo::dijkstra::{DijkstraOps, Label};
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{EdgeIdT, NodeId, NodeIdT, Weight, INFINITY};
+use rust_road_router::datastr::timestamped_vector::Reset;
 
 use crate::graph::capacity_graph::CapacityGraph;
 
-pub struct CapacityDijkstraOps();
+/// Labels of a time-dependent Dijkstra search on a capacity graph.
+///
+/// The arrival time is needed to evaluate the travel time functions and is also the queue key,
+/// additional criteria (e.g. the number of hops) can be carried along and used to break ties.
+pub trait CapacityLabel: Label<Key = Weight> {
+    /// label at the start node of a query
+    fn at_departure(departure: Timestamp) -> Self;
+    fn arrival(&self) -> Timestamp;
+    /// label after traversing `edge` with the given travel time
+    fn extend(&self, edge: EdgeIdT, travel_time: Weight) -> Self;
+    /// whether `self` should replace `other` as the label of a node
+    fn improves(&self, other: &Self) -> bool;
+}
+
+impl CapacityLabel for Weight {
+    fn at_departure(departure: Timestamp) -> Self {
+        departure
+    }
+
+    #[inline(always)]
+    fn arrival(&self) -> Timestamp {
+        *self
+    }
+
+    #[inline(always)]
+    fn extend(&self, _edge: EdgeIdT, travel_time: Weight) -> Self {
+        self + travel_time
+    }
+
+    #[inline(always)]
+    fn improves(&self, other: &Self) -> bool {
+        self < other
+    }
+}
+
+/// arrival time and number of edges on the path, ties in the arrival time are broken by the number of hops
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HopLabel {
+    pub arrival: Timestamp,
+    pub hops: u32,
+}
+
+impl Reset for HopLabel {
+    const DEFAULT: Self = HopLabel { arrival: INFINITY, hops: 0 };
+}
+
+impl Label for HopLabel {
+    type Key = Weight;
+
+    fn neutral() -> Self {
+        Self::DEFAULT
+    }
+
+    #[inline(always)]
+    fn key(&self) -> Self::Key {
+        self.arrival
+    }
+}
+
+impl CapacityLabel for HopLabel {
+    fn at_departure(departure: Timestamp) -> Self {
+        HopLabel { arrival: departure, hops: 0 }
+    }
+
+    #[inline(always)]
+    fn arrival(&self) -> Timestamp {
+        self.arrival
+    }
+
+    #[inline(always)]
+    fn extend(&self, _edge: EdgeIdT, travel_time: Weight) -> Self {
+        HopLabel {
+            arrival: self.arrival + travel_time,
+            hops: self.hops + 1,
+        }
+    }
+
+    #[inline(always)]
+    fn improves(&self, other: &Self) -> bool {
+        (self.arrival, self.hops) < (other.arrival, other.hops)
+    }
+}
+
+pub struct CapacityDijkstraOps<L = Weight>(PhantomData<L>);
 
-impl DijkstraOps<CapacityGraph> for CapacityDijkstraOps {
-    type Label = Weight;
+impl<L: CapacityLabel> DijkstraOps<CapacityGraph> for CapacityDijkstraOps<L> {
+    type Label = L;
     type Arc = (NodeIdT, EdgeIdT);
-    type LinkResult = Weight;
+    type LinkResult = L;
     type PredecessorLink = EdgeIdT;
 
     #[inline(always)]
-    fn link(&mut self, graph: &CapacityGraph, _parents: &[(NodeId, EdgeIdT)], _tail: NodeIdT, label: &Weight, link: &Self::Arc) -> Self::LinkResult {
-        label + graph.travel_time_function(link.1 .0).eval(*label)
+    fn link(&mut self, graph: &CapacityGraph, _parents: &[(NodeId, EdgeIdT)], _tail: NodeIdT, label: &L, link: &Self::Arc) -> Self::LinkResult {
+        label.extend(link.1, graph.travel_time_function(link.1 .0).eval(label.arrival()))
     }
 
     #[inline(always)]
-    fn merge(&mut self, label: &mut Weight, linked: Self::LinkResult) -> bool {
-        if linked < *label {
+    fn merge(&mut self, label: &mut L, linked: Self::LinkResult) -> bool {
+        if linked.improves(label) {
             *label = linked;
             return true;
         }
@@ -30,8 +117,8 @@ impl DijkstraOps<CapacityGraph> for CapacityDijkstraOps {
     }
 }
 
-impl Default for CapacityDijkstraOps {
+impl<L> Default for CapacityDijkstraOps<L> {
     fn default() -> Self {
-        Self {}
+        Self(PhantomData)
     }
 }
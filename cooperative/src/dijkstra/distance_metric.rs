@@ -0,0 +1,99 @@
+use std::error::Error;
+
+use rust_road_router::algo::customizable_contraction_hierarchy::query::Server as CCHServer;
+use rust_road_router::algo::customizable_contraction_hierarchy::{customize, CCH};
+use rust_road_router::algo::{GenQuery, Query, QueryServer, TDQuery};
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{EdgeId, FirstOutGraph, NodeId, Weight};
+
+use crate::dijkstra::model::CapacityQueryResult;
+use crate::dijkstra::server::{CapacityServer, CapacityServerOps};
+use crate::graph::capacity_graph::CapacityGraph;
+
+/// Optimization objective of a query
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Objective {
+    /// minimum (time-dependent, cooperative) travel time
+    #[default]
+    Fastest,
+    /// minimum free-flow travel time, i.e. ignoring the current traffic
+    FreeFlow,
+    /// minimum geo distance
+    Shortest,
+}
+
+impl Objective {
+    pub fn parse(objective: &str) -> Result<Self, Box<dyn Error>> {
+        match objective {
+            "fastest" => Ok(Objective::Fastest),
+            "free_flow" => Ok(Objective::FreeFlow),
+            "shortest" => Ok(Objective::Shortest),
+            _ => Err(format!("unknown objective `{}`, expected `fastest`, `free_flow` or `shortest`", objective).into()),
+        }
+    }
+
+    /// static weights of the objective, `None` for the time-dependent travel time
+    fn static_weights(self, graph: &CapacityGraph) -> Option<&Vec<Weight>> {
+        match self {
+            Objective::Fastest => None,
+            Objective::FreeFlow => Some(graph.free_flow_time()),
+            Objective::Shortest => Some(graph.distance()),
+        }
+    }
+}
+
+/// Static objectives (free-flow time, geo distance), each customized on a CCH that is shared with the travel time potentials.
+/// Together with a `CapacityServer`, the objective can be selected per query. The static metrics don't change,
+/// so a single customization suffices, the travel time of static paths is evaluated on the server's current graph.
+pub struct CustomizedObjectives<'a> {
+    servers: Vec<(Objective, CCHServer<CCH, &'a CCH>)>,
+}
+
+impl<'a> CustomizedObjectives<'a> {
+    /// customize all given static objectives, `Objective::Fastest` is always available and needs no customization
+    pub fn new(cch: &'a CCH, graph: &CapacityGraph, objectives: &[Objective]) -> Self {
+        let servers = objectives
+            .iter()
+            .filter_map(|&objective| {
+                let weights = objective.static_weights(graph)?;
+                let metric = FirstOutGraph::new(graph.first_out(), graph.head(), &weights[..]);
+                Some((objective, CCHServer::new(customize(cch, &metric))))
+            })
+            .collect();
+
+        Self { servers }
+    }
+
+    pub fn supports(&self, objective: Objective) -> bool {
+        objective == Objective::Fastest || self.servers.iter().any(|(customized, _)| *customized == objective)
+    }
+
+    /// optimal path w.r.t. the static `objective` as weight and edge ids of the capacity graph, parallel edges are resolved by their weight.
+    /// Panics if the objective isn't static or wasn't customized.
+    pub fn static_path(&mut self, graph: &CapacityGraph, objective: Objective, from: NodeId, to: NodeId) -> Option<(Weight, Vec<EdgeId>)> {
+        let weights = objective.static_weights(graph).expect("only static objectives have a customization");
+        let (_, server) = self
+            .servers
+            .iter_mut()
+            .find(|(customized, _)| *customized == objective)
+            .expect("objective wasn't customized");
+
+        let metric = FirstOutGraph::new(graph.first_out(), graph.head(), &weights[..]);
+        let mut result = server.query(Query::new(from, to, 0));
+        result.distance().map(|distance| (distance, result.data().orig_edge_path(&metric)))
+    }
+
+    /// Run a query with the given objective. Static objectives are routed as fixed paths on the server, see `CapacityServer::query_path`.
+    pub fn query<P>(&mut self, server: &mut CapacityServer<P>, query: &TDQuery<Timestamp>, objective: Objective, update: bool) -> Option<CapacityQueryResult>
+    where
+        CapacityServer<P>: CapacityServerOps,
+    {
+        match objective {
+            Objective::Fastest => server.query(query, update),
+            _ => {
+                let (_, edge_path) = self.static_path(server.borrow_graph(), objective, query.from, query.to)?;
+                server.query_path(query, edge_path, update)
+            }
+        }
+    }
+}
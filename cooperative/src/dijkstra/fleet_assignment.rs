@@ -0,0 +1,203 @@
+use rust_road_router::algo::TDQuery;
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{NodeId, Weight, INFINITY};
+
+use crate::dijkstra::model::CapacityQueryResult;
+use crate::dijkstra::server::CapacityServerOps;
+
+/// Idle vehicle of a fleet, available at `node` from `available` on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FleetVehicle {
+    pub node: NodeId,
+    pub available: Timestamp,
+}
+
+/// Transport request from `from` to `to`, the customer is ready at `departure`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransportRequest {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub departure: Timestamp,
+}
+
+/// A vehicle serving a request: the empty approach to the pickup and the trip itself
+#[derive(Clone, Debug)]
+pub struct Assignment {
+    pub vehicle: usize,
+    pub request: usize,
+    /// assignment cost, see `assignment_costs`
+    pub cost: Weight,
+    pub approach: CapacityQueryResult,
+    pub trip: CapacityQueryResult,
+}
+
+/// Assign idle vehicles to requests with minimum total cost and route the assigned vehicles on the server.
+///
+/// The cost matrix is computed with a single query batch (see `assignment_costs`), the assignment is solved exactly
+/// with the Hungarian method. Afterwards, the approach and the trip of each assigned vehicle are routed with updates,
+/// in order of their pickup, so their loads are fed back into the capacity graph.
+/// Vehicles and requests that can't be paired remain unassigned.
+pub fn assign_fleet<S: CapacityServerOps>(server: &mut S, vehicles: &[FleetVehicle], requests: &[TransportRequest]) -> Vec<Assignment> {
+    let costs = assignment_costs(server, vehicles, requests);
+    let mut pairs = solve_assignment(&costs)
+        .into_iter()
+        .enumerate()
+        .filter_map(|(vehicle, request)| request.map(|request| (vehicle, request)))
+        .collect::<Vec<(usize, usize)>>();
+    pairs.sort_by_key(|&(vehicle, request)| vehicles[vehicle].available.max(requests[request].departure));
+
+    pairs
+        .into_iter()
+        .filter_map(|(vehicle, request)| {
+            let (vehicle_entry, request_entry) = (&vehicles[vehicle], &requests[request]);
+            let approach = server.query(
+                &TDQuery {
+                    from: vehicle_entry.node,
+                    to: request_entry.from,
+                    departure: vehicle_entry.available,
+                },
+                true,
+            )?;
+            let pickup = (vehicle_entry.available + approach.distance).max(request_entry.departure);
+            let trip = server.query(
+                &TDQuery {
+                    from: request_entry.from,
+                    to: request_entry.to,
+                    departure: pickup,
+                },
+                true,
+            )?;
+
+            Some(Assignment {
+                vehicle,
+                request,
+                cost: costs[vehicle][request],
+                approach,
+                trip,
+            })
+        })
+        .collect()
+}
+
+/// Cost of each vehicle (rows) serving each request (columns): the empty approach time plus the waiting time of the customer.
+/// Pairs without a path to the pickup cost `INFINITY`. All approaches are evaluated as one query batch on the current graph.
+pub fn assignment_costs<S: CapacityServerOps>(server: &mut S, vehicles: &[FleetVehicle], requests: &[TransportRequest]) -> Vec<Vec<Weight>> {
+    let queries = vehicles
+        .iter()
+        .flat_map(|vehicle| {
+            requests.iter().map(move |request| TDQuery {
+                from: vehicle.node,
+                to: request.from,
+                departure: vehicle.available,
+            })
+        })
+        .collect::<Vec<TDQuery<Timestamp>>>();
+    let results = server.query_batch(&queries);
+
+    vehicles
+        .iter()
+        .enumerate()
+        .map(|(vehicle_idx, vehicle)| {
+            requests
+                .iter()
+                .enumerate()
+                .map(|(request_idx, request)| {
+                    let approach = results[vehicle_idx * requests.len() + request_idx].distance_result.distance;
+                    approach.map_or(INFINITY, |approach| {
+                        let waiting = (vehicle.available + approach).saturating_sub(request.departure);
+                        (approach + waiting).min(INFINITY)
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Minimum cost assignment of rows to columns (Hungarian method with potentials, `O(n^2 * m)`).
+/// Returns the assigned column of each row, rows remain unassigned if there are fewer columns or only infinite costs.
+pub fn solve_assignment(costs: &Vec<Vec<Weight>>) -> Vec<Option<usize>> {
+    let num_rows = costs.len();
+    let num_cols = costs.first().map_or(0, |row| row.len());
+    if num_rows == 0 || num_cols == 0 {
+        return vec![None; num_rows];
+    }
+
+    // the method requires at most as many rows as columns
+    if num_rows > num_cols {
+        let transposed = (0..num_cols)
+            .map(|col| (0..num_rows).map(|row| costs[row][col]).collect())
+            .collect::<Vec<Vec<Weight>>>();
+        let mut ret = vec![None; num_rows];
+        for (col, row) in solve_assignment(&transposed).into_iter().enumerate() {
+            if let Some(row) = row {
+                ret[row] = Some(col);
+            }
+        }
+        return ret;
+    }
+
+    // 1-based indices, row/column 0 is a virtual start
+    let mut row_potential = vec![0i64; num_rows + 1];
+    let mut col_potential = vec![0i64; num_cols + 1];
+    let mut col_match = vec![0usize; num_cols + 1];
+    let mut way = vec![0usize; num_cols + 1];
+
+    for row in 1..=num_rows {
+        col_match[0] = row;
+        let mut col = 0;
+        let mut min_slack = vec![i64::MAX; num_cols + 1];
+        let mut used = vec![false; num_cols + 1];
+
+        // grow an alternating tree until a free column is reached
+        loop {
+            used[col] = true;
+            let current_row = col_match[col];
+            let mut delta = i64::MAX;
+            let mut next_col = 0;
+
+            for c in 1..=num_cols {
+                if !used[c] {
+                    let slack = costs[current_row - 1][c - 1] as i64 - row_potential[current_row] - col_potential[c];
+                    if slack < min_slack[c] {
+                        min_slack[c] = slack;
+                        way[c] = col;
+                    }
+                    if min_slack[c] < delta {
+                        delta = min_slack[c];
+                        next_col = c;
+                    }
+                }
+            }
+
+            for c in 0..=num_cols {
+                if used[c] {
+                    row_potential[col_match[c]] += delta;
+                    col_potential[c] -= delta;
+                } else {
+                    min_slack[c] -= delta;
+                }
+            }
+
+            col = next_col;
+            if col_match[col] == 0 {
+                break;
+            }
+        }
+
+        // augment along the alternating path
+        while col != 0 {
+            let prev_col = way[col];
+            col_match[col] = col_match[prev_col];
+            col = prev_col;
+        }
+    }
+
+    let mut ret = vec![None; num_rows];
+    for col in 1..=num_cols {
+        let row = col_match[col];
+        if row != 0 && costs[row - 1][col - 1] < INFINITY {
+            ret[row - 1] = Some(col - 1);
+        }
+    }
+    ret
+}
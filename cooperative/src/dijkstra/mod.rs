@@ -1,5 +1,13 @@
+pub mod cached_server;
 pub mod capacity_dijkstra_ops;
+pub mod distance_metric;
+pub mod fleet_assignment;
 pub mod model;
+pub mod path_swapping;
 pub mod potentials;
 pub mod ptv_server;
+pub mod ride_pooling;
+pub mod route_choice;
+pub mod route_sequencing;
 pub mod server;
+pub mod static_ch_server;
@@ -1,16 +1,46 @@
 use rust_road_router::datastr::graph::time_dependent::Timestamp;
 use rust_road_router::datastr::graph::{EdgeId, NodeId, Weight};
+use std::collections::HashSet;
 use std::time::Duration;
 
+use crate::graph::{Capacity, PCE_SCALE};
+
+/// Identifier of a single vehicle/trip, tracked through all updates of a server
+pub type TripId = u32;
+pub type VehicleId = u32;
+
 #[derive(Clone, Debug)]
 pub struct CapacityQueryResult {
     pub distance: Weight,
     pub path: PathResult,
+    pub trip_id: Option<TripId>,
+    /// time to find a parking space at the destination, see `ParkingModel`
+    pub parking_time: Weight,
 }
 
 impl CapacityQueryResult {
     pub fn new(distance: Weight, path: PathResult) -> Self {
-        Self { distance, path }
+        Self {
+            distance,
+            path,
+            trip_id: None,
+            parking_time: 0,
+        }
+    }
+
+    pub fn with_trip_id(mut self, trip_id: TripId) -> Self {
+        self.trip_id = Some(trip_id);
+        self
+    }
+
+    pub fn with_parking_time(mut self, parking_time: Weight) -> Self {
+        self.parking_time = parking_time;
+        self
+    }
+
+    /// travel time including the parking search at the destination
+    pub fn door_to_door_time(&self) -> Weight {
+        self.distance + self.parking_time
     }
 }
 
@@ -30,6 +60,31 @@ pub struct DistanceMeasure {
     pub num_queue_pushs: u32,
     pub num_queue_pops: u32,
     pub num_relaxed_arcs: u32,
+    pub num_pot_computations: Option<usize>,
+    /// only collected with the `stats` feature
+    pub search_statistics: Option<SearchStatistics>,
+}
+
+/// Detailed search space of a single query, collected by the `CapacityServer` if the `stats` feature is enabled
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SearchStatistics {
+    pub num_settled_nodes: u32,
+    pub num_improved_labels: u32,
+    pub num_decrease_keys: u32,
+    /// number of nodes for which the potential was evaluated
+    pub num_potential_calls: u32,
+    /// number of improved nodes that were pruned because the target is unreachable according to the potential
+    pub num_pruned_nodes: u32,
+}
+
+/// A node settled by a query, recorded by the `CapacityServer` if the search space recording is enabled
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SettledNode {
+    pub node: NodeId,
+    /// travel time from the query's departure
+    pub distance: Weight,
+    /// estimated remaining travel time, 0 for the start node
+    pub potential: Weight,
 }
 
 #[derive(Clone, Debug)]
@@ -37,6 +92,8 @@ pub struct PathResult {
     pub node_path: Vec<NodeId>,
     pub edge_path: Vec<EdgeId>,
     pub departure: Vec<Timestamp>,
+    /// load added to each bucket on the path, in passenger car equivalents scaled by `PCE_SCALE`
+    pub pce_load: Capacity,
 }
 
 impl PathResult {
@@ -45,6 +102,140 @@ impl PathResult {
             node_path,
             edge_path,
             departure,
+            pce_load: PCE_SCALE,
+        }
+    }
+
+    pub fn with_pce_load(mut self, pce_load: Capacity) -> Self {
+        self.pce_load = pce_load;
+        self
+    }
+}
+
+/// Traversal of a single edge of a path, used to attribute delays to specific road segments
+#[derive(Clone, Copy, Debug)]
+pub struct PathSegment {
+    pub edge_id: EdgeId,
+    pub departure: Timestamp,
+    pub travel_time: Weight,
+    /// capacity bucket of the edge that was used at `departure`
+    pub bucket: u32,
+}
+
+/// Difference between a withdrawn path and its replacement
+#[derive(Clone, Debug)]
+pub struct RouteDiff {
+    /// number of leading edges both paths have in common
+    pub shared_prefix_len: usize,
+    /// first node at which the paths diverge, `None` if both paths are equal
+    pub divergence_node: Option<NodeId>,
+    /// edges of the new path which are not part of the previous path
+    pub added_edges: Vec<EdgeId>,
+    /// edges of the previous path which are not part of the new path
+    pub removed_edges: Vec<EdgeId>,
+    /// travel time of the new path minus travel time of the previous path
+    pub delta_travel_time: i64,
+}
+
+impl RouteDiff {
+    pub fn new(previous: &PathResult, current: &PathResult) -> Self {
+        let shared_prefix_len = previous
+            .edge_path
+            .iter()
+            .zip(current.edge_path.iter())
+            .take_while(|(prev_edge, cur_edge)| prev_edge == cur_edge)
+            .count();
+
+        let divergence_node = if previous.edge_path == current.edge_path {
+            None
+        } else {
+            current.node_path.get(shared_prefix_len).or(previous.node_path.get(shared_prefix_len)).cloned()
+        };
+
+        let previous_edges = previous.edge_path.iter().collect::<HashSet<&EdgeId>>();
+        let current_edges = current.edge_path.iter().collect::<HashSet<&EdgeId>>();
+
+        let added_edges = current
+            .edge_path
+            .iter()
+            .filter(|edge| !previous_edges.contains(edge))
+            .cloned()
+            .collect::<Vec<EdgeId>>();
+        let removed_edges = previous
+            .edge_path
+            .iter()
+            .filter(|edge| !current_edges.contains(edge))
+            .cloned()
+            .collect::<Vec<EdgeId>>();
+
+        Self {
+            shared_prefix_len,
+            divergence_node,
+            added_edges,
+            removed_edges,
+            delta_travel_time: path_travel_time(current) as i64 - path_travel_time(previous) as i64,
         }
     }
+
+    pub fn is_changed(&self) -> bool {
+        self.divergence_node.is_some()
+    }
+}
+
+fn path_travel_time(path: &PathResult) -> Weight {
+    match (path.departure.first(), path.departure.last()) {
+        (Some(&first), Some(&last)) => last - first,
+        _ => 0,
+    }
+}
+
+/// Per-vehicle statistics of a single trip
+#[derive(Clone, Debug)]
+pub struct TripStatistics {
+    pub trip_id: TripId,
+    pub departure: Timestamp,
+    /// number of times the trip was routed, including re-routes
+    pub num_routes: u32,
+    pub num_reroutes: u32,
+    /// travel time of the current path at the time it was routed
+    pub routed_travel_time: Weight,
+    /// travel time of the current path, evaluated on the current graph
+    pub experienced_travel_time: Weight,
+    /// travel time of the current path without any traffic
+    pub free_flow_travel_time: Weight,
+    /// parking search time at the destination, not included in the travel times
+    pub parking_time: Weight,
+}
+
+impl TripStatistics {
+    pub fn experienced_delay(&self) -> Weight {
+        self.experienced_travel_time.saturating_sub(self.free_flow_travel_time)
+    }
+
+    pub fn door_to_door_time(&self) -> Weight {
+        self.experienced_travel_time + self.parking_time
+    }
+}
+
+/// Daily statistics of a vehicle, aggregated over all of its (linked) trips
+#[derive(Clone, Debug)]
+pub struct VehicleStatistics {
+    pub vehicle_id: VehicleId,
+    pub num_trips: u32,
+    pub num_routes: u32,
+    pub num_reroutes: u32,
+    pub routed_travel_time: Weight,
+    pub experienced_travel_time: Weight,
+    pub free_flow_travel_time: Weight,
+    pub parking_time: Weight,
+}
+
+impl VehicleStatistics {
+    pub fn experienced_delay(&self) -> Weight {
+        self.experienced_travel_time.saturating_sub(self.free_flow_travel_time)
+    }
+
+    pub fn door_to_door_time(&self) -> Weight {
+        self.experienced_travel_time + self.parking_time
+    }
 }
@@ -0,0 +1,178 @@
+use std::cmp::min;
+use std::collections::HashMap;
+
+use rust_road_router::algo::TDQuery;
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{EdgeId, NodeId, Weight, INFINITY};
+
+use crate::dijkstra::model::PathResult;
+use crate::dijkstra::server::{CapacityServer, CapacityServerOps};
+use crate::graph::Capacity;
+
+/// Demand of a single origin-destination pair and its current path set.
+/// The flow of each path is given by its `pce_load`, the departures of each path are the ones its flow was added with.
+struct ODEntry {
+    query: TDQuery<Timestamp>,
+    demand: Capacity,
+    paths: Vec<PathResult>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PathSwappingStatistics {
+    pub iteration: u32,
+    /// relative gap between the total travel time and the travel time on the currently shortest paths, before shifting
+    pub relative_gap: f64,
+    /// shifted flow, in PCE units
+    pub shifted_flow: u64,
+    pub num_paths: usize,
+    pub num_new_paths: usize,
+}
+
+/// Path-swapping dynamic traffic assignment on top of a `CapacityServer`.
+///
+/// Queries with equal source, target and departure form an OD pair, its demand is the sum of their loads (in PCE units).
+/// After an all-or-nothing initialization (equal to sequential cooperative insertion), each iteration extends
+/// the path set of every OD pair by its currently shortest path and shifts flow from costly to the cheapest path.
+/// The shifted flow of a path is proportional to its relative excess cost, scaled by the step size `1 / (iteration + 1)`.
+///
+/// Flow is withdrawn from the graph during each iteration, so the server's potential must stay valid on decreasing
+/// travel times (e.g. a free-flow lowerbound potential).
+pub struct PathSwappingAssignment {
+    od_pairs: Vec<ODEntry>,
+}
+
+impl PathSwappingAssignment {
+    pub fn new(queries: &[TDQuery<Timestamp>], pce_loads: &[Capacity]) -> Self {
+        debug_assert_eq!(queries.len(), pce_loads.len());
+
+        let mut od_indices = HashMap::<(NodeId, NodeId, Timestamp), usize>::new();
+        let mut od_pairs: Vec<ODEntry> = Vec::new();
+
+        for (query, &pce_load) in queries.iter().zip(pce_loads.iter()) {
+            let idx = *od_indices.entry((query.from, query.to, query.departure)).or_insert_with(|| {
+                od_pairs.push(ODEntry {
+                    query: query.clone(),
+                    demand: 0,
+                    paths: Vec::new(),
+                });
+                od_pairs.len() - 1
+            });
+            od_pairs[idx].demand += pce_load;
+        }
+
+        Self { od_pairs }
+    }
+
+    pub fn num_od_pairs(&self) -> usize {
+        self.od_pairs.len()
+    }
+
+    /// all-or-nothing assignment of each OD pair in the given order, returns the number of routed OD pairs
+    pub fn initialize<Pot>(&mut self, server: &mut CapacityServer<Pot>) -> usize
+    where
+        CapacityServer<Pot>: CapacityServerOps,
+    {
+        for od in self.od_pairs.iter_mut() {
+            if let Some(result) = server.query_with_pce(&od.query, od.demand, true) {
+                od.paths = vec![result.path];
+            }
+        }
+
+        self.od_pairs.iter().filter(|od| !od.paths.is_empty()).count()
+    }
+
+    /// run a single path-swapping iteration, starting with `iteration = 1`
+    pub fn iterate<Pot>(&mut self, server: &mut CapacityServer<Pot>, iteration: u32) -> PathSwappingStatistics
+    where
+        CapacityServer<Pot>: CapacityServerOps,
+    {
+        let step_size = 1.0 / (iteration + 1) as f64;
+
+        let mut total_cost = 0.0;
+        let mut shortest_cost = 0.0;
+        let mut shifted_flow = 0;
+        let mut num_new_paths = 0;
+
+        for od in self.od_pairs.iter_mut().filter(|od| !od.paths.is_empty()) {
+            // 1. extend the path set by the currently shortest path
+            if let Some(result) = server.query(&od.query, false) {
+                if od.paths.iter().all(|path| path.edge_path != result.path.edge_path) {
+                    od.paths.push(result.path.with_pce_load(0));
+                    num_new_paths += 1;
+                }
+            }
+
+            // 2. evaluate all paths on the current graph
+            let costs = od
+                .paths
+                .iter()
+                .map(|path| server.path_distance(&path.edge_path, od.query.departure))
+                .collect::<Vec<Weight>>();
+            let (best_idx, &min_cost) = costs.iter().enumerate().min_by_key(|&(_, &cost)| cost).unwrap();
+
+            total_cost += od
+                .paths
+                .iter()
+                .zip(costs.iter())
+                .map(|(path, &cost)| path.pce_load as f64 * cost as f64)
+                .sum::<f64>();
+            shortest_cost += od.demand as f64 * min_cost as f64;
+
+            // 3. shift flow from costly paths to the cheapest path
+            let mut od_shifted = 0;
+            for (idx, path) in od.paths.iter_mut().enumerate() {
+                if idx == best_idx || path.pce_load == 0 || costs[idx] <= min_cost || costs[idx] >= INFINITY {
+                    continue;
+                }
+
+                let excess = (costs[idx] - min_cost) as f64 / costs[idx] as f64;
+                let amount = min(path.pce_load, (step_size * excess * path.pce_load as f64).ceil() as Capacity);
+
+                server.withdraw(&path.clone().with_pce_load(amount));
+                path.pce_load -= amount;
+                od_shifted += amount;
+            }
+
+            if od_shifted > 0 {
+                // re-add the cheapest path with its full flow, based on the current departures
+                let best = &od.paths[best_idx];
+                if best.pce_load > 0 {
+                    server.withdraw(best);
+                }
+
+                let departure = server.borrow_graph().path_departures(&best.edge_path, od.query.departure);
+                let best = PathResult::new(best.node_path.clone(), best.edge_path.clone(), departure).with_pce_load(best.pce_load + od_shifted);
+                server.update(&best);
+                od.paths[best_idx] = best;
+                shifted_flow += od_shifted as u64;
+            }
+
+            od.paths.retain(|path| path.pce_load > 0);
+        }
+
+        PathSwappingStatistics {
+            iteration,
+            relative_gap: if shortest_cost > 0.0 {
+                (total_cost - shortest_cost) / shortest_cost
+            } else {
+                0.0
+            },
+            shifted_flow,
+            num_paths: self.od_pairs.iter().map(|od| od.paths.len()).sum(),
+            num_new_paths,
+        }
+    }
+
+    /// all paths with a positive flow, the flow is given by their `pce_load`
+    pub fn assigned_paths(&self) -> Vec<PathResult> {
+        self.od_pairs.iter().flat_map(|od| od.paths.iter().cloned()).collect()
+    }
+
+    /// current path set of each OD pair as (query, [(edge path, flow in PCE units)])
+    pub fn path_flows(&self) -> Vec<(TDQuery<Timestamp>, Vec<(Vec<EdgeId>, Capacity)>)> {
+        self.od_pairs
+            .iter()
+            .map(|od| (od.query.clone(), od.paths.iter().map(|path| (path.edge_path.clone(), path.pce_load)).collect()))
+            .collect()
+    }
+}
@@ -1,48 +1,48 @@
 use crate::dijkstra::potentials::cch_lower_upper::elimination_tree_server::CorridorEliminationTreeServer;
 use rust_road_router::algo::customizable_contraction_hierarchy::CCHT;
 use rust_road_router::datastr::graph::{EdgeId, EdgeIdT, LinkIterable, NodeId, NodeIdT, UnweightedFirstOutGraph, Weight, INFINITY};
-use rust_road_router::datastr::timestamped_vector::TimestampedVector;
+use rust_road_router::datastr::timestamped_vector::{Epoch, TimestampedVector};
 use rust_road_router::util::in_range_option::InRangeOption;
 use std::cmp::min;
 
 #[derive(Clone, Debug)]
-pub struct BoundedLowerUpperPotentialContext {
+pub struct BoundedLowerUpperPotentialContext<E: Epoch = u32> {
     stack: Vec<NodeId>,
-    potentials: TimestampedVector<InRangeOption<(Weight, Weight)>>,
-    forward_distances: TimestampedVector<(Weight, Weight)>,
-    backward_distances: TimestampedVector<(Weight, Weight)>,
+    potentials: TimestampedVector<InRangeOption<(Weight, Weight)>, E>,
+    forward_distances: TimestampedVector<(Weight, Weight), E>,
+    backward_distances: TimestampedVector<(Weight, Weight), E>,
     target_bounds: Option<(Weight, Weight)>,
     num_pot_computations: usize,
 }
 
-impl BoundedLowerUpperPotentialContext {
+impl<E: Epoch> BoundedLowerUpperPotentialContext<E> {
     pub fn new(num_nodes: usize) -> Self {
         Self {
             stack: Vec::new(),
-            potentials: TimestampedVector::new(num_nodes),
-            forward_distances: TimestampedVector::new(num_nodes),
-            backward_distances: TimestampedVector::new(num_nodes),
+            potentials: TimestampedVector::with_epochs(num_nodes),
+            forward_distances: TimestampedVector::with_epochs(num_nodes),
+            backward_distances: TimestampedVector::with_epochs(num_nodes),
             target_bounds: None,
             num_pot_computations: 0,
         }
     }
 }
 
-pub struct BoundedLowerUpperPotential<'a, CCH> {
+pub struct BoundedLowerUpperPotential<'a, CCH, E: Epoch = u32> {
     cch: &'a CCH,
     forward_cch_graph: UnweightedFirstOutGraph<&'a [EdgeId], &'a [NodeId]>,
     forward_cch_weights: &'a Vec<(Weight, Weight)>,
     backward_cch_graph: UnweightedFirstOutGraph<&'a [EdgeId], &'a [NodeId]>,
     backward_cch_weights: &'a Vec<(Weight, Weight)>,
-    context: &'a mut BoundedLowerUpperPotentialContext,
+    context: &'a mut BoundedLowerUpperPotentialContext<E>,
 }
 
-impl<'a, CCH: CCHT> BoundedLowerUpperPotential<'a, CCH> {
+impl<'a, CCH: CCHT, E: Epoch> BoundedLowerUpperPotential<'a, CCH, E> {
     pub fn prepare(
         cch: &'a CCH,
         forward_cch_weights: &'a Vec<(Weight, Weight)>,
         backward_cch_weights: &'a Vec<(Weight, Weight)>,
-        context: &'a mut BoundedLowerUpperPotentialContext,
+        context: &'a mut BoundedLowerUpperPotentialContext<E>,
     ) -> Self {
         let forward_cch_graph = UnweightedFirstOutGraph::new(cch.forward_first_out(), cch.forward_head());
         let backward_cch_graph = UnweightedFirstOutGraph::new(cch.backward_first_out(), cch.backward_head());
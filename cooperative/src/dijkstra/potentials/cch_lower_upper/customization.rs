@@ -1,4 +1,6 @@
-use crate::dijkstra::potentials::cch_parallelization_util::{SeparatorBasedParallelCustomization, SeparatorBasedPerfectParallelCustomization};
+use crate::dijkstra::potentials::cch_parallelization_util::{
+    CancellationToken, CustomizationCancelled, SeparatorBasedParallelCustomization, SeparatorBasedPerfectParallelCustomization,
+};
 use rayon::prelude::*;
 use rust_road_router::algo::customizable_contraction_hierarchy::{DirectedCCH, CCH, CCHT};
 use rust_road_router::datastr::graph::{
@@ -26,6 +28,15 @@ pub struct CustomizedLowerUpper {
 
 impl CustomizedLowerUpper {
     pub fn new(cch: &CCH, travel_times: &Vec<Vec<Weight>>) -> Self {
+        Self::new_internal(cch, travel_times, None).unwrap()
+    }
+
+    /// Same as `new`, but aborts as soon as the given token is cancelled.
+    pub fn new_cancellable(cch: &CCH, travel_times: &Vec<Vec<Weight>>, cancellation: &CancellationToken) -> Result<Self, CustomizationCancelled> {
+        Self::new_internal(cch, travel_times, Some(cancellation))
+    }
+
+    fn new_internal(cch: &CCH, travel_times: &Vec<Vec<Weight>>, cancellation: Option<&CancellationToken>) -> Result<Self, CustomizationCancelled> {
         let m = cch.num_arcs();
 
         let (lower_bound, upper_bound): (Vec<Weight>, Vec<Weight>) = travel_times
@@ -43,7 +54,10 @@ impl CustomizedLowerUpper {
         prepare_weights(cch, &mut upward_weights, &mut downward_weights, &lower_bound, &upper_bound);
 
         // run basic customization
-        customize_basic(cch, &mut upward_weights, &mut downward_weights);
+        customize_basic(cch, &mut upward_weights, &mut downward_weights, cancellation);
+        if cancellation.map(|token| token.is_cancelled()).unwrap_or(false) {
+            return Err(CustomizationCancelled);
+        }
 
         println!("Sizes after basic: {} {}", upward_weights.len(), downward_weights.len());
 
@@ -57,13 +71,13 @@ impl CustomizedLowerUpper {
         debug_assert!(!upward_weights.iter().any(|&(lower, upper)| lower > upper));
         debug_assert!(!downward_weights.iter().any(|&(lower, upper)| lower > upper));
 
-        Self {
+        Ok(Self {
             cch: directed_cch,
             upward: upward_weights,
             downward: downward_weights,
             orig_edge_to_forward_shortcut: orig_edge_to_forward,
             orig_edge_to_backward_shortcut: orig_edge_to_backward,
-        }
+        })
     }
 
     pub fn forward_graph(&self) -> (UnweightedFirstOutGraph<&[EdgeId], &[NodeId]>, &Vec<(Weight, Weight)>) {
@@ -106,7 +120,12 @@ fn prepare_weights(cch: &CCH, upward_weights: &mut Vec<(u32, u32)>, downward_wei
     });
 }
 
-fn customize_basic(cch: &CCH, upward_weights: &mut Vec<(Weight, Weight)>, downward_weights: &mut Vec<(Weight, Weight)>) {
+fn customize_basic(
+    cch: &CCH,
+    upward_weights: &mut Vec<(Weight, Weight)>,
+    downward_weights: &mut Vec<(Weight, Weight)>,
+    cancellation: Option<&CancellationToken>,
+) {
     let n = cch.num_nodes() as NodeId;
 
     let customize = |nodes: Range<usize>, offset: usize, upward_weights: &mut [(Weight, Weight)], downward_weights: &mut [(Weight, Weight)]| {
@@ -172,7 +191,10 @@ fn customize_basic(cch: &CCH, upward_weights: &mut Vec<(Weight, Weight)>, downwa
     };
 
     // setup customization for parallelization
-    let customization = SeparatorBasedParallelCustomization::new(cch, customize, customize);
+    let mut customization = SeparatorBasedParallelCustomization::new(cch, customize, customize);
+    if let Some(cancellation) = cancellation {
+        customization = customization.with_cancellation(cancellation);
+    }
 
     // execute customization
     report_time_with_key("CCH Customization", "basic_customization", || {
@@ -1,6 +1,6 @@
 use rust_road_router::algo::customizable_contraction_hierarchy::CCHT;
 use rust_road_router::datastr::graph::{EdgeId, EdgeIdT, LinkIterable, NodeId, NodeIdT, UnweightedFirstOutGraph, Weight, INFINITY};
-use rust_road_router::datastr::timestamped_vector::TimestampedVector;
+use rust_road_router::datastr::timestamped_vector::{Epoch, TimestampedVector};
 use rust_road_router::util::in_range_option::InRangeOption;
 use std::borrow::Borrow;
 use std::cmp::min;
@@ -9,14 +9,14 @@ use std::marker::PhantomData;
 pub struct CorridorEliminationTreeServer<CCH>(PhantomData<CCH>);
 
 impl<CCH: CCHT> CorridorEliminationTreeServer<CCH> {
-    pub fn query(
+    pub fn query<E: Epoch>(
         cch: &CCH,
         forward_graph: &UnweightedFirstOutGraph<&[EdgeId], &[NodeId]>,
         forward_weights: &Vec<(Weight, Weight)>,
         backward_graph: &UnweightedFirstOutGraph<&[EdgeId], &[NodeId]>,
         backward_weights: &Vec<(Weight, Weight)>,
-        fw_distances: &mut TimestampedVector<(Weight, Weight)>,
-        bw_distances: &mut TimestampedVector<(Weight, Weight)>,
+        fw_distances: &mut TimestampedVector<(Weight, Weight), E>,
+        bw_distances: &mut TimestampedVector<(Weight, Weight), E>,
         from: NodeId,
         to: NodeId,
     ) -> Option<(Weight, Weight)> {
@@ -88,23 +88,82 @@ impl<CCH: CCHT> CorridorEliminationTreeServer<CCH> {
             dist => Some(dist),
         }
     }
+
+    /// One-to-many variant of `query`: returns the bounds from `from` to each of the `targets`, in the same order.
+    /// The forward search space of `from` is explored completely (no pruning is possible with several targets),
+    /// afterwards a single top-down sweep over the union of the targets' elimination tree ancestors
+    /// propagates the bounds down to all targets. `bw_distances` contains the sweep distances afterwards.
+    pub fn query_multi_target<E: Epoch>(
+        cch: &CCH,
+        forward_graph: &UnweightedFirstOutGraph<&[EdgeId], &[NodeId]>,
+        forward_weights: &Vec<(Weight, Weight)>,
+        backward_graph: &UnweightedFirstOutGraph<&[EdgeId], &[NodeId]>,
+        backward_weights: &Vec<(Weight, Weight)>,
+        fw_distances: &mut TimestampedVector<(Weight, Weight), E>,
+        bw_distances: &mut TimestampedVector<(Weight, Weight), E>,
+        from: NodeId,
+        targets: &[NodeId],
+    ) -> Vec<Option<(Weight, Weight)>> {
+        let elimination_tree = cch.borrow().elimination_tree();
+
+        // 1. explore the complete forward search space
+        let from = cch.node_order().rank(from);
+        let mut fw_walk = CorridorEliminationTreeWalk::init(forward_graph, forward_weights, elimination_tree, fw_distances, from);
+        while let Some(_) = fw_walk.next() {}
+
+        // 2. collect the backward search spaces of all targets, sorted top-down
+        let targets = targets.iter().map(|&target| cch.node_order().rank(target)).collect::<Vec<NodeId>>();
+        let mut sweep_nodes = Vec::new();
+        for &target in &targets {
+            let mut current_node = Some(target);
+            while let Some(node) = current_node {
+                sweep_nodes.push(node);
+                current_node = elimination_tree[node as usize].value();
+            }
+        }
+        sweep_nodes.sort_unstable_by(|a, b| b.cmp(a));
+        sweep_nodes.dedup();
+
+        // 3. single sweep: all upward neighbors of a node are elimination tree ancestors, i.e. they were already processed
+        bw_distances.reset();
+        for &node in &sweep_nodes {
+            let mut dist = fw_distances[node as usize];
+
+            for (NodeIdT(upper_node), EdgeIdT(edge)) in LinkIterable::<(NodeIdT, EdgeIdT)>::link_iter(backward_graph, node) {
+                let (upper_lower, upper_upper) = bw_distances[upper_node as usize];
+                let (edge_lower, edge_upper) = backward_weights[edge as usize];
+
+                dist = (min(dist.0, upper_lower + edge_lower), min(dist.1, upper_upper + edge_upper));
+            }
+
+            bw_distances[node as usize] = dist;
+        }
+
+        targets
+            .iter()
+            .map(|&target| match bw_distances[target as usize] {
+                (INFINITY, INFINITY) => None,
+                dist => Some(dist),
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug)]
-pub struct CorridorEliminationTreeWalk<'a> {
+pub struct CorridorEliminationTreeWalk<'a, E: Epoch = u32> {
     graph: &'a UnweightedFirstOutGraph<&'a [EdgeId], &'a [NodeId]>,
     weights: &'a Vec<(Weight, Weight)>,
-    distances: &'a mut TimestampedVector<(Weight, Weight)>,
+    distances: &'a mut TimestampedVector<(Weight, Weight), E>,
     elimination_tree: &'a [InRangeOption<NodeId>],
     next: Option<NodeId>,
 }
 
-impl<'a> CorridorEliminationTreeWalk<'a> {
+impl<'a, E: Epoch> CorridorEliminationTreeWalk<'a, E> {
     pub fn init(
         graph: &'a UnweightedFirstOutGraph<&'a [EdgeId], &'a [NodeId]>,
         weights: &'a Vec<(Weight, Weight)>,
         elimination_tree: &'a [InRangeOption<NodeId>],
-        distances: &'a mut TimestampedVector<(Weight, Weight)>,
+        distances: &'a mut TimestampedVector<(Weight, Weight), E>,
         from: NodeId,
     ) -> Self {
         // reset distances
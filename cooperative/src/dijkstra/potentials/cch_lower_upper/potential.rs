@@ -37,6 +37,26 @@ impl<'a, CCH: CCHT> CCHLowerUpperPotential<'a, CCH> {
         }
     }
 
+    /// Backward potential on the same customization: estimates the distance from the node passed to `init` (i.e. the source of a reverse search) to a node.
+    /// This is the forward potential on the reversed CCH, so forward and backward graph and weights swap their roles.
+    pub fn new_backward(cch: &'a CCH, forward_cch_weights: Vec<(Weight, Weight)>, backward_cch_weights: Vec<(Weight, Weight)>) -> Self {
+        let forward_cch_graph = UnweightedFirstOutGraph::new(cch.backward_first_out(), cch.backward_head());
+        let backward_cch_graph = UnweightedFirstOutGraph::new(cch.forward_first_out(), cch.forward_head());
+        let n = forward_cch_graph.num_nodes();
+
+        Self {
+            cch,
+            stack: Vec::new(),
+            potentials: TimestampedVector::new(n),
+            forward_cch_graph,
+            forward_cch_weights: backward_cch_weights,
+            backward_distances: TimestampedVector::new(n),
+            backward_cch_graph,
+            backward_cch_weights: forward_cch_weights,
+            num_pot_computations: 0,
+        }
+    }
+
     pub fn num_pot_computations(&self) -> usize {
         self.num_pot_computations
     }
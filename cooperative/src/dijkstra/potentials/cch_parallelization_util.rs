@@ -1,8 +1,48 @@
 use rayon::prelude::*;
 use rust_road_router::algo::customizable_contraction_hierarchy::separator_decomposition::SeparatorTree;
-use rust_road_router::algo::customizable_contraction_hierarchy::CCH;
+use rust_road_router::algo::customizable_contraction_hierarchy::{DirectedCCH, CCH, CCHT};
 use rust_road_router::datastr::graph::{EdgeId, Graph, NodeId};
+use rust_road_router::report::progress::{ignore_progress, ProgressCallback};
+use std::error::Error;
+use std::fmt::{Display, Formatter};
 use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering::Relaxed};
+use std::sync::Arc;
+
+/// Shared flag to abort a running customization, e.g. when a newer traffic snapshot arrives.
+/// Clones share the same flag.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Relaxed)
+    }
+
+    /// Reset the flag, so the token can be reused for the next customization
+    pub fn reset(&self) {
+        self.0.store(false, Relaxed);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CustomizationCancelled;
+
+impl Display for CustomizationCancelled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "customization was cancelled")
+    }
+}
+
+impl Error for CustomizationCancelled {}
 
 pub struct SeparatorBasedParallelCustomization<'a, T, F, G> {
     cch: &'a CCH,
@@ -11,6 +51,10 @@ pub struct SeparatorBasedParallelCustomization<'a, T, F, G> {
     customize_separator: G,
     _t: std::marker::PhantomData<T>,
     reverse: bool,
+    progress: ProgressCallback<'a>,
+    num_processed: AtomicUsize,
+    cancellation: Option<&'a CancellationToken>,
+    stride: usize,
 }
 
 impl<'a, T, F, G> SeparatorBasedParallelCustomization<'a, T, F, G>
@@ -37,16 +81,55 @@ where
             customize_separator,
             _t: std::marker::PhantomData::<T>,
             reverse: false,
+            progress: &ignore_progress,
+            num_processed: AtomicUsize::new(0),
+            cancellation: None,
+            stride: 1,
         }
     }
 
+    /// Use a flat weight layout with `stride` consecutive entries per edge, i.e. the weights of edge `e` are found at `e * stride..(e + 1) * stride`.
+    /// The edge offset passed to the customization routines is still given in edges.
+    pub fn with_stride(mut self, stride: usize) -> Self {
+        assert!(stride > 0, "stride must be positive");
+        self.stride = stride;
+        self
+    }
+
+    /// Check the given token before each cell and separator, remaining cells are skipped once it is cancelled.
+    /// The weights are only partially customized afterwards and must not be used.
+    pub fn with_cancellation(mut self, cancellation: &'a CancellationToken) -> Self {
+        self.cancellation = Some(cancellation);
+        self
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation.map(|token| token.is_cancelled()).unwrap_or(false)
+    }
+
+    /// Report the number of customized nodes to the given callback after each cell and separator.
+    pub fn with_progress(mut self, progress: ProgressCallback<'a>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    fn report_progress(&self, num_nodes: usize) {
+        let num_processed = self.num_processed.fetch_add(num_nodes, Relaxed) + num_nodes;
+        (self.progress)(num_processed, self.cch.num_nodes());
+    }
+
     /// Execute customization. Takes a mut slice to the full memory where weights that should be customized are stored.
     /// The setup callback can be used to perform additional scoped setup work.
     /// It has to call the callback that gets passed to it in turn.
     /// Otherwise nothing will happen.
     pub fn customize(&self, upward: &'a mut [T], downward: &'a mut [T], setup: impl Fn(Box<dyn FnOnce() + '_>) + Sync) {
+        self.num_processed.store(0, Relaxed);
+
         if cfg!(feature = "cch-disable-par") {
-            setup(Box::new(|| (self.customize_cell)(0..self.cch.num_nodes(), 0, upward, downward)));
+            setup(Box::new(|| {
+                (self.customize_cell)(0..self.cch.num_nodes(), 0, upward, downward);
+                self.report_progress(self.cch.num_nodes());
+            }));
         } else {
             let core_ids = core_affinity::get_core_ids().unwrap();
             rayon::ThreadPoolBuilder::new()
@@ -62,15 +145,21 @@ where
     }
 
     fn customize_tree(&self, sep_tree: &SeparatorTree, offset: usize, upward: &'a mut [T], downward: &'a mut [T]) {
+        if self.is_cancelled() {
+            return;
+        }
+
         let edge_offset = self.cch.first_out[offset] as usize;
 
         if sep_tree.num_nodes < self.cch.num_nodes() / (32 * rayon::current_num_threads()) {
             // if the current cell is small enough (load balancing parameters) run the customize_cell routine on it
             (self.customize_cell)(offset..offset + sep_tree.num_nodes, edge_offset, upward, downward);
+            self.report_progress(sep_tree.num_nodes);
         } else {
             if self.reverse {
                 let sep_begin = offset + sep_tree.children.iter().map(|sub| sub.num_nodes).sum::<usize>();
                 (self.customize_separator)(sep_begin..offset + sep_tree.num_nodes, edge_offset, upward, downward);
+                self.report_progress(offset + sep_tree.num_nodes - sep_begin);
             }
 
             // if not, split at the separator, process all subcells independently in parallel and the separator afterwards
@@ -81,10 +170,10 @@ where
 
             rayon::scope(|s| {
                 for sub in &sep_tree.children {
-                    let (this_sub_up, rest_up) = (move || sub_upward)().split_at_mut(self.cch.first_out[sub_offset + sub.num_nodes] as usize - sub_edge_offset);
-                    let (this_sub_down, rest_down) =
-                        (move || sub_downward)().split_at_mut(self.cch.first_out[sub_offset + sub.num_nodes] as usize - sub_edge_offset);
-                    sub_edge_offset += this_sub_up.len();
+                    let sub_num_edges = self.cch.first_out[sub_offset + sub.num_nodes] as usize - sub_edge_offset;
+                    let (this_sub_up, rest_up) = (move || sub_upward)().split_at_mut(sub_num_edges * self.stride);
+                    let (this_sub_down, rest_down) = (move || sub_downward)().split_at_mut(sub_num_edges * self.stride);
+                    sub_edge_offset += sub_num_edges;
                     // if the subcell is small enough don't bother spawning a thread
                     // this catches the case of very small cell at high levels which may sometime occur
                     // subcells are ordered descending by their size, so we will always first spawn of tasks for the big ones
@@ -99,14 +188,125 @@ where
                 }
             });
 
-            if !self.reverse {
+            if !self.reverse && !self.is_cancelled() {
                 // once all subcells are processed, process the separator itself
-                (self.customize_separator)(sub_offset..offset + sep_tree.num_nodes, edge_offset, upward, downward)
+                (self.customize_separator)(sub_offset..offset + sep_tree.num_nodes, edge_offset, upward, downward);
+                self.report_progress(offset + sep_tree.num_nodes - sub_offset);
             }
         }
     }
 }
 
+/// Parallelization of basic customization on a `DirectedCCH`, forward and backward edges are split separately.
+/// Same as `SeparatorBasedParallelCustomization`, the weights may use a flat layout with `stride` entries per edge.
+pub struct SeparatorBasedParallelDirectedCustomization<'a, T, F, G> {
+    cch: &'a DirectedCCH,
+    separators: SeparatorTree,
+    customize_cell: F,
+    customize_separator: G,
+    _t: std::marker::PhantomData<T>,
+    stride: usize,
+}
+
+impl<'a, T, F, G> SeparatorBasedParallelDirectedCustomization<'a, T, F, G>
+where
+    T: Send + Sync,
+    F: Sync + Fn(Range<usize>, usize, usize, &mut [T], &mut [T]),
+    G: Sync + Fn(Range<usize>, usize, usize, &mut [T], &mut [T]),
+{
+    /// Setup for parallelization, the routines receive the node range as well as the forward and backward edge offsets of the weight slices.
+    pub fn new(cch: &'a DirectedCCH, customize_cell: F, customize_separator: G) -> Self {
+        let separators = cch.separators();
+        if !cfg!(feature = "cch-disable-par") {
+            separators.validate_for_parallelization();
+        }
+
+        Self {
+            cch,
+            separators,
+            customize_cell,
+            customize_separator,
+            _t: std::marker::PhantomData::<T>,
+            stride: 1,
+        }
+    }
+
+    /// Use a flat weight layout with `stride` consecutive entries per edge, edge offsets are still given in edges.
+    pub fn with_stride(mut self, stride: usize) -> Self {
+        assert!(stride > 0, "stride must be positive");
+        self.stride = stride;
+        self
+    }
+
+    /// Execute customization. Takes a mut slice to the full memory where weights that should be customized are stored.
+    /// The setup callback can be used to perform additional scoped setup work.
+    /// It has to call the callback that gets passed to it in turn.
+    pub fn customize(&self, upward: &'a mut [T], downward: &'a mut [T], setup: impl Fn(Box<dyn FnOnce() + '_>) + Sync) {
+        if cfg!(feature = "cch-disable-par") {
+            setup(Box::new(|| (self.customize_cell)(0..self.cch.num_nodes(), 0, 0, upward, downward)));
+        } else {
+            let core_ids = core_affinity::get_core_ids().unwrap();
+            rayon::ThreadPoolBuilder::new()
+                .build_scoped(
+                    |thread| {
+                        core_affinity::set_for_current(core_ids[thread.index()]);
+                        setup(Box::new(|| thread.run()));
+                    },
+                    |pool| pool.install(|| self.customize_tree(&self.separators, 0, upward, downward)),
+                )
+                .unwrap();
+        }
+    }
+
+    fn customize_tree(&self, sep_tree: &SeparatorTree, offset: usize, upward: &'a mut [T], downward: &'a mut [T]) {
+        let forward_first_out = self.cch.forward_first_out();
+        let backward_first_out = self.cch.backward_first_out();
+        let forward_edge_offset = forward_first_out[offset] as usize;
+        let backward_edge_offset = backward_first_out[offset] as usize;
+
+        if sep_tree.num_nodes < self.cch.num_nodes() / (32 * rayon::current_num_threads()) {
+            // if the current cell is small enough (load balancing parameters) run the customize_cell routine on it
+            (self.customize_cell)(offset..offset + sep_tree.num_nodes, forward_edge_offset, backward_edge_offset, upward, downward);
+        } else {
+            // if not, split at the separator, process all subcells independently in parallel and the separator afterwards
+            let mut sub_offset = offset;
+            let mut sub_forward_edge_offset = forward_edge_offset;
+            let mut sub_backward_edge_offset = backward_edge_offset;
+            let mut sub_upward = &mut upward[..];
+            let mut sub_downward = &mut downward[..];
+
+            rayon::scope(|s| {
+                for sub in &sep_tree.children {
+                    let sub_num_forward_edges = forward_first_out[sub_offset + sub.num_nodes] as usize - sub_forward_edge_offset;
+                    let sub_num_backward_edges = backward_first_out[sub_offset + sub.num_nodes] as usize - sub_backward_edge_offset;
+                    let (this_sub_up, rest_up) = (move || sub_upward)().split_at_mut(sub_num_forward_edges * self.stride);
+                    let (this_sub_down, rest_down) = (move || sub_downward)().split_at_mut(sub_num_backward_edges * self.stride);
+                    sub_forward_edge_offset += sub_num_forward_edges;
+                    sub_backward_edge_offset += sub_num_backward_edges;
+
+                    if sub.num_nodes < self.cch.num_nodes() / (32 * rayon::current_num_threads()) {
+                        self.customize_tree(sub, sub_offset, this_sub_up, this_sub_down);
+                    } else {
+                        s.spawn(move |_| self.customize_tree(sub, sub_offset, this_sub_up, this_sub_down));
+                    }
+                    sub_offset += sub.num_nodes;
+                    sub_upward = rest_up;
+                    sub_downward = rest_down;
+                }
+            });
+
+            // once all subcells are processed, process the separator itself
+            (self.customize_separator)(
+                sub_offset..offset + sep_tree.num_nodes,
+                forward_edge_offset,
+                backward_edge_offset,
+                upward,
+                downward,
+            );
+        }
+    }
+}
+
 // Ugly unsafe stuff for perfect customization.
 // We basically need many threads to mutable access the weights arrays at the same time.
 // We can't reasonably split everything into consecutive slice, so we just distribute the whole
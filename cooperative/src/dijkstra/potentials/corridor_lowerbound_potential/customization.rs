@@ -1,16 +1,22 @@
 use crate::dijkstra::potentials::cch_lower_upper::bounded_potential::BoundedLowerUpperPotentialContext;
 use crate::dijkstra::potentials::cch_lower_upper::customization::CustomizedLowerUpper;
-use crate::dijkstra::potentials::corridor_lowerbound_potential::customization_catchup::customize_td_graph;
+use crate::dijkstra::potentials::cch_parallelization_util::{CancellationToken, CustomizationCancelled};
+use crate::dijkstra::potentials::corridor_lowerbound_potential::customization_catchup::{
+    customize_td_graph_cancellable, customize_td_graph_with_approximation,
+};
 use crate::dijkstra::potentials::corridor_lowerbound_potential::shortcut::ShortcutWrapper;
 use crate::dijkstra::potentials::corridor_lowerbound_potential::CorridorLowerboundPotentialContext;
 use crate::graph::capacity_graph::CapacityGraph;
 use crate::graph::MAX_BUCKETS;
 use rust_road_router::algo::customizable_contraction_hierarchy::{DirectedCCH, CCH, CCHT};
-use rust_road_router::datastr::graph::floating_time_dependent::{TDGraph, TTFPoint};
+use rust_road_router::datastr::graph::floating_time_dependent::{FlWeight, TDGraph, TTFPoint};
 use rust_road_router::datastr::graph::{
     BuildReversed, EdgeId, EdgeIdT, Graph, LinkIterable, NodeId, NodeIdT, ReversedGraphWithEdgeIds, UnweightedFirstOutGraph, INFINITY,
 };
+use rust_road_router::datastr::timestamped_vector::Epoch;
 use rust_road_router::report::measure;
+use rust_road_router::report::progress::{ignore_progress, ProgressCallback};
+use rust_road_router::util::Vecs;
 use scoped_tls::scoped_thread_local;
 use std::cell::RefCell;
 use std::cmp::{max, min};
@@ -19,20 +25,40 @@ use std::cmp::{max, min};
 scoped_thread_local!(static UPWARD_WORKSPACE: RefCell<Vec<Vec<TTFPoint>>>);
 scoped_thread_local!(static DOWNWARD_WORKSPACE: RefCell<Vec<Vec<TTFPoint>>>);
 
-pub struct CustomizedCorridorLowerbound {
+pub struct CustomizedCorridorLowerbound<E: Epoch = u32> {
     pub cch: DirectedCCH,
     pub upward_intervals: Vec<u32>,
     pub downward_intervals: Vec<u32>,
     pub upward_bounds: Vec<(u32, u32)>,
     pub downward_bounds: Vec<(u32, u32)>,
     pub num_intervals: u32,
-    pub potential_context: CorridorLowerboundPotentialContext,
-    pub corridor_context: BoundedLowerUpperPotentialContext,
+    pub potential_context: CorridorLowerboundPotentialContext<E>,
+    pub corridor_context: BoundedLowerUpperPotentialContext<E>,
     pub customized_bounds: Option<CustomizedLowerUpper>,
 }
 
 impl CustomizedCorridorLowerbound {
     pub fn new_from_capacity(cch: &CCH, graph: &CapacityGraph, num_intervals: u32) -> Self {
+        Self::new_from_capacity_internal(cch, graph, num_intervals, None).unwrap()
+    }
+
+    /// Same as `new_from_capacity`, but aborts as soon as the given token is cancelled.
+    /// Both the interval minima and the upper bound customization check the token.
+    pub fn new_from_capacity_cancellable(
+        cch: &CCH,
+        graph: &CapacityGraph,
+        num_intervals: u32,
+        cancellation: &CancellationToken,
+    ) -> Result<Self, CustomizationCancelled> {
+        Self::new_from_capacity_internal(cch, graph, num_intervals, Some(cancellation))
+    }
+
+    fn new_from_capacity_internal(
+        cch: &CCH,
+        graph: &CapacityGraph,
+        num_intervals: u32,
+        cancellation: Option<&CancellationToken>,
+    ) -> Result<Self, CustomizationCancelled> {
         // basic workaround: convert to TD-Graph, then run PTV customization
         let mut first_ipp_of_arc = vec![0];
         let mut departure = Vec::new();
@@ -53,19 +79,49 @@ impl CustomizedCorridorLowerbound {
 
         let td_graph = TDGraph::new(graph.first_out().to_vec(), graph.head().to_vec(), first_ipp_of_arc, departure, travel_time);
 
-        let mut ret = Self::run_customization(cch, &td_graph, num_intervals);
-        ret.customize_upper_bound(cch, graph);
-        ret
+        let mut ret = Self::run_customization(cch, &td_graph, num_intervals, None, &ignore_progress, cancellation)?;
+        ret.customize_upper_bound_internal(cch, graph, cancellation)?;
+        Ok(ret)
+    }
+
+    /// Same as `new_from_capacity`, but the interval resolution is coarsened until the interval minima fit into the given memory budget (in bytes)
+    pub fn new_from_capacity_with_memory_budget(cch: &CCH, graph: &CapacityGraph, num_intervals: u32, memory_budget: usize) -> Self {
+        Self::new_from_capacity(cch, graph, fit_to_memory_budget(cch, num_intervals, memory_budget))
     }
 
     pub fn new_from_ptv(cch: &CCH, graph: &TDGraph, num_intervals: u32) -> Self {
-        Self::run_customization(cch, graph, num_intervals)
+        Self::run_customization(cch, graph, num_intervals, None, &ignore_progress, None).unwrap()
     }
 
-    fn run_customization(cch: &CCH, graph: &TDGraph, num_intervals: u32) -> Self {
+    pub fn new_from_ptv_with_progress(cch: &CCH, graph: &TDGraph, num_intervals: u32, progress: ProgressCallback) -> Self {
+        Self::run_customization(cch, graph, num_intervals, None, progress, None).unwrap()
+    }
+
+    /// Same as `new_from_ptv`, but the interval resolution is coarsened until the interval minima fit into the given memory budget (in bytes)
+    pub fn new_from_ptv_with_memory_budget(cch: &CCH, graph: &TDGraph, num_intervals: u32, memory_budget: usize) -> Self {
+        Self::new_from_ptv(cch, graph, fit_to_memory_budget(cch, num_intervals, memory_budget))
+    }
+
+    /// interval minima are taken from lower bounds approximating the travel time functions within `epsilon` seconds
+    pub fn new_from_ptv_with_approximation(cch: &CCH, graph: &TDGraph, num_intervals: u32, epsilon: FlWeight) -> Self {
+        Self::run_customization(cch, graph, num_intervals, Some(epsilon), &ignore_progress, None).unwrap()
+    }
+
+    fn run_customization(
+        cch: &CCH,
+        graph: &TDGraph,
+        num_intervals: u32,
+        approximation: Option<FlWeight>,
+        progress: ProgressCallback,
+        cancellation: Option<&CancellationToken>,
+    ) -> Result<Self, CustomizationCancelled> {
         debug_assert!(MAX_BUCKETS % num_intervals == 0);
 
-        let ((mut upward_weights, mut downward_weights), time) = measure(|| customize_td_graph(cch, graph, num_intervals));
+        let (weights, time) = measure(|| match cancellation {
+            Some(cancellation) => customize_td_graph_cancellable(cch, graph, num_intervals, approximation, progress, cancellation),
+            None => Ok(customize_td_graph_with_approximation(cch, graph, num_intervals, approximation, progress)),
+        });
+        let (mut upward_weights, mut downward_weights) = weights?;
         println!("Interval Minima Customization took {} ms", time.as_secs_f64() * 1000.0);
 
         // extract relevant data, scale upper bounds
@@ -98,7 +154,7 @@ impl CustomizedCorridorLowerbound {
         println!("Re-Building new CCH graph took {} ms", time.as_secs_f64() * 1000.0);
 
         let num_nodes = cch.num_nodes();
-        Self {
+        Ok(Self {
             cch,
             upward_intervals,
             downward_intervals,
@@ -108,11 +164,53 @@ impl CustomizedCorridorLowerbound {
             potential_context: CorridorLowerboundPotentialContext::new(num_nodes),
             corridor_context: BoundedLowerUpperPotentialContext::new(num_nodes),
             customized_bounds: None,
+        })
+    }
+}
+
+impl<E: Epoch> CustomizedCorridorLowerbound<E> {
+    /// replace the per-query labels by ones with epochs of type `E2`, e.g. `u16` to save memory on large graphs
+    pub fn with_epochs<E2: Epoch>(self) -> CustomizedCorridorLowerbound<E2> {
+        let num_nodes = self.cch.num_nodes();
+
+        CustomizedCorridorLowerbound {
+            cch: self.cch,
+            upward_intervals: self.upward_intervals,
+            downward_intervals: self.downward_intervals,
+            upward_bounds: self.upward_bounds,
+            downward_bounds: self.downward_bounds,
+            num_intervals: self.num_intervals,
+            potential_context: CorridorLowerboundPotentialContext::new(num_nodes),
+            corridor_context: BoundedLowerUpperPotentialContext::new(num_nodes),
+            customized_bounds: self.customized_bounds,
         }
     }
 
     pub fn customize_upper_bound(&mut self, cch: &CCH, graph: &CapacityGraph) {
-        let mut customized = CustomizedLowerUpper::new(cch, graph.travel_time());
+        self.customize_upper_bound_internal(cch, graph, None).unwrap()
+    }
+
+    /// Same as `customize_upper_bound`, but aborts as soon as the given token is cancelled.
+    /// In this case, the previous upper bounds are kept.
+    pub fn customize_upper_bound_cancellable(
+        &mut self,
+        cch: &CCH,
+        graph: &CapacityGraph,
+        cancellation: &CancellationToken,
+    ) -> Result<(), CustomizationCancelled> {
+        self.customize_upper_bound_internal(cch, graph, Some(cancellation))
+    }
+
+    fn customize_upper_bound_internal(
+        &mut self,
+        cch: &CCH,
+        graph: &CapacityGraph,
+        cancellation: Option<&CancellationToken>,
+    ) -> Result<(), CustomizationCancelled> {
+        let mut customized = match cancellation {
+            Some(cancellation) => CustomizedLowerUpper::new_cancellable(cch, graph.travel_time(), cancellation)?,
+            None => CustomizedLowerUpper::new(cch, graph.travel_time()),
+        };
 
         // scale upper bound
         customized.upward.iter_mut().for_each(|(_, upper)| {
@@ -125,6 +223,33 @@ impl CustomizedCorridorLowerbound {
         });
 
         self.customized_bounds = Some(customized);
+        self.potential_context.invalidate();
+        Ok(())
+    }
+
+    /// see `CorridorLowerboundPotentialContext::set_search_reuse`
+    pub fn set_search_reuse(&mut self, reuse_searches: bool) {
+        self.potential_context.set_search_reuse(reuse_searches);
+    }
+
+    /// Interval minima of the (upward or downward) CCH edge containing the original arc `arc`.
+    /// Returns `None` if that edge was removed during the customization.
+    pub fn orig_arc_interval_minima(&self, arc: EdgeId) -> Option<Vec<u32>> {
+        let find_minima = |cch_edge_to_orig_arc: &Vecs<EdgeIdT>, intervals: &Vec<u32>| {
+            let num_edges = intervals.len() / self.num_intervals as usize;
+            cch_edge_to_orig_arc
+                .iter()
+                .position(|arcs| arcs.contains(&EdgeIdT(arc)))
+                .map(|edge| {
+                    (0..self.num_intervals as usize)
+                        .map(|idx| intervals[idx * num_edges + edge])
+                        .collect::<Vec<u32>>()
+                })
+                .filter(|minima| minima.iter().all(|&val| val < INFINITY))
+        };
+
+        find_minima(self.cch.forward_cch_edge_to_orig_arc(), &self.upward_intervals)
+            .or_else(|| find_minima(self.cch.backward_cch_edge_to_orig_arc(), &self.downward_intervals))
     }
 
     pub fn forward_graph(&self) -> (UnweightedFirstOutGraph<&[EdgeId], &[NodeId]>, &Vec<u32>, &Vec<(u32, u32)>) {
@@ -144,6 +269,44 @@ impl CustomizedCorridorLowerbound {
     }
 }
 
+/// estimated peak memory (in bytes) of the interval minima, i.e. the per-shortcut minima
+/// plus the flattened weights which are filled while the former are released.
+/// The shortcut functions of the CATCHUp customization are not included, see `MemoryCeiling` for these.
+fn estimate_interval_minima_memory(cch: &CCH, num_intervals: u32) -> usize {
+    let weight_size = std::mem::size_of::<u32>();
+    let per_shortcut = 2 * cch.num_arcs() * (std::mem::size_of::<Vec<u32>>() + num_intervals as usize * weight_size);
+    let flattened = 2 * cch.num_arcs() * num_intervals as usize * weight_size;
+
+    per_shortcut + flattened
+}
+
+/// coarsen the interval resolution until the estimated memory fits into the budget.
+/// The number of intervals must divide `MAX_BUCKETS`, so the next smaller divisor is taken in each step.
+fn fit_to_memory_budget(cch: &CCH, num_intervals: u32, memory_budget: usize) -> u32 {
+    debug_assert!(MAX_BUCKETS % num_intervals == 0);
+
+    let fitting = (1..=num_intervals)
+        .rev()
+        .filter(|&candidate| MAX_BUCKETS % candidate == 0)
+        .find(|&candidate| estimate_interval_minima_memory(cch, candidate) <= memory_budget);
+
+    let num_intervals = fitting.unwrap_or_else(|| {
+        println!(
+            "WARNING: Interval minima exceed the memory budget of {} bytes even with a single interval",
+            memory_budget
+        );
+        1
+    });
+
+    println!(
+        "Memory budget: using {} intervals (estimated {} of {} bytes)",
+        num_intervals,
+        estimate_interval_minima_memory(cch, num_intervals),
+        memory_budget
+    );
+    num_intervals
+}
+
 fn extract_intervals_and_bounds(weights: &mut Vec<ShortcutWrapper>) -> (Vec<Vec<u32>>, Vec<(u32, u32)>, u32) {
     let mut num_removed_edges = 0;
     let (intervals, bounds) = weights
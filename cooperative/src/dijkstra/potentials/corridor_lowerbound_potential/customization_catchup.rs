@@ -1,6 +1,7 @@
 use crate::dijkstra::potentials::cch_parallelization_util::{
-    ForEachIter, ParIter, SeparatorBasedParallelCustomization, SeparatorBasedPerfectParallelCustomization, SeqIter,
+    CancellationToken, CustomizationCancelled, ForEachIter, ParIter, SeparatorBasedParallelCustomization, SeparatorBasedPerfectParallelCustomization, SeqIter,
 };
+use crate::dijkstra::potentials::corridor_lowerbound_potential::memory_ceiling::{CustomizationGuard, InterruptedCustomization, MemoryCeiling};
 use crate::dijkstra::potentials::corridor_lowerbound_potential::shortcut::{PartialShortcutWrapperGraph, ShortcutWrapper};
 use crate::dijkstra::potentials::{convert_timestamp_f64_to_u32, convert_timestamp_u32_to_f64};
 use crate::graph::MAX_BUCKETS;
@@ -9,10 +10,12 @@ use rust_road_router::algo::customizable_contraction_hierarchy::CCH;
 use rust_road_router::datastr::graph::floating_time_dependent::shortcut::Sources;
 use rust_road_router::datastr::graph::floating_time_dependent::shortcut_source::ShortcutSource;
 use rust_road_router::datastr::graph::floating_time_dependent::{
-    FlWeight, MergeBuffers, PeriodicATTF, PeriodicPiecewiseLinearFunction, Shortcut, TDGraph, TTFPoint, Timestamp, EPSILON, PLF,
+    approx_threshold, max_approximation_error, reset_max_approximation_error, set_approx_threshold, FlWeight, MergeBuffers, PartialPiecewiseLinearFunction,
+    PeriodicATTF, PeriodicPiecewiseLinearFunction, Shortcut, TDGraph, TTFPoint, Timestamp, EPSILON, PLF,
 };
 use rust_road_router::datastr::graph::{EdgeId, EdgeIdT, Graph, LinkIterable, NodeId, NodeIdT, Reversed, INFINITY};
 use rust_road_router::report;
+use rust_road_router::report::progress::{ignore_progress, ProgressCallback};
 use rust_road_router::report::*;
 use rust_road_router::util::in_range_option::InRangeOption;
 use scoped_tls::scoped_thread_local;
@@ -30,7 +33,143 @@ scoped_thread_local!(static DOWNWARD_WORKSPACE: RefCell<Vec<(FlWeight, FlWeight)
 scoped_thread_local!(static PERFECT_WORKSPACE: RefCell<Vec<InRangeOption<EdgeId>>>);
 
 pub fn customize_td_graph(cch: &CCH, metric: &TDGraph, num_intervals: u32) -> (Vec<ShortcutWrapper>, Vec<ShortcutWrapper>) {
+    customize_td_graph_with_progress(cch, metric, num_intervals, &ignore_progress)
+}
+
+/// Same as `customize_td_graph`, the progress of the main CATCHUp customization is reported to the given callback
+pub fn customize_td_graph_with_progress(
+    cch: &CCH,
+    metric: &TDGraph,
+    num_intervals: u32,
+    progress: ProgressCallback,
+) -> (Vec<ShortcutWrapper>, Vec<ShortcutWrapper>) {
+    customize_td_graph_with_approximation(cch, metric, num_intervals, None, progress)
+}
+
+/// Same as `customize_td_graph_with_progress`, but the interval minima are extracted from lower bounds
+/// which approximate the shortcut functions within the given error (if any). The largest actual error is reported.
+pub fn customize_td_graph_with_approximation(
+    cch: &CCH,
+    metric: &TDGraph,
+    num_intervals: u32,
+    approximation: Option<FlWeight>,
+    progress: ProgressCallback,
+) -> (Vec<ShortcutWrapper>, Vec<ShortcutWrapper>) {
+    let mut shortcuts = prepare_customization(cch, metric, approximation, None);
+    customize_main(cch, metric, num_intervals, approximation, progress, &mut shortcuts, None, None);
+    finish_customization(cch, metric, shortcuts, approximation)
+}
+
+/// Same as `customize_td_graph_with_approximation`, but aborts as soon as the given token is cancelled.
+/// The cancellation is checked between the cells of the pre- and main customization.
+pub fn customize_td_graph_cancellable(
+    cch: &CCH,
+    metric: &TDGraph,
+    num_intervals: u32,
+    approximation: Option<FlWeight>,
+    progress: ProgressCallback,
+    cancellation: &CancellationToken,
+) -> Result<(Vec<ShortcutWrapper>, Vec<ShortcutWrapper>), CustomizationCancelled> {
+    let mut shortcuts = prepare_customization(cch, metric, approximation, Some(cancellation));
+    if !cancellation.is_cancelled() {
+        customize_main(cch, metric, num_intervals, approximation, progress, &mut shortcuts, None, Some(cancellation));
+    }
+    if cancellation.is_cancelled() {
+        println!("Customization cancelled");
+        return Err(CustomizationCancelled);
+    }
+    Ok(finish_customization(cch, metric, shortcuts, approximation))
+}
+
+/// Same as `customize_td_graph_with_approximation`, but the memory usage is kept below the given ceiling.
+/// When it is exceeded, shortcut functions are approximated more aggressively.
+/// If that does not suffice, the customization is interrupted and its state returned, see `resume_td_customization`.
+pub fn customize_td_graph_with_memory_ceiling(
+    cch: &CCH,
+    metric: &TDGraph,
+    num_intervals: u32,
+    approximation: Option<FlWeight>,
+    ceiling: MemoryCeiling,
+    progress: ProgressCallback,
+) -> Result<(Vec<ShortcutWrapper>, Vec<ShortcutWrapper>), InterruptedCustomization> {
+    let initial_approx_threshold = approx_threshold();
+    let shortcuts = prepare_customization(cch, metric, approximation, None);
+    let guard = CustomizationGuard::new(ceiling, vec![false; cch.num_nodes()]);
+    customize_guarded(cch, metric, num_intervals, approximation, shortcuts, guard, initial_approx_threshold, progress)
+}
+
+/// Continue an interrupted customization with the given (possibly raised) ceiling.
+/// Nodes which were already customized are skipped.
+pub fn resume_td_customization(
+    cch: &CCH,
+    metric: &TDGraph,
+    interrupted: InterruptedCustomization,
+    ceiling: MemoryCeiling,
+    progress: ProgressCallback,
+) -> Result<(Vec<ShortcutWrapper>, Vec<ShortcutWrapper>), InterruptedCustomization> {
+    println!(
+        "Resuming customization with {} of {} nodes already done",
+        interrupted.num_customized_nodes(),
+        interrupted.num_nodes()
+    );
+    set_approx_threshold(interrupted.approx_threshold);
+    let guard = CustomizationGuard::new(ceiling, interrupted.customized);
+    customize_guarded(
+        cch,
+        metric,
+        interrupted.num_intervals,
+        interrupted.approximation,
+        interrupted.shortcuts,
+        guard,
+        interrupted.initial_approx_threshold,
+        progress,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn customize_guarded(
+    cch: &CCH,
+    metric: &TDGraph,
+    num_intervals: u32,
+    approximation: Option<FlWeight>,
+    mut shortcuts: (Vec<ShortcutWrapper>, Vec<ShortcutWrapper>),
+    guard: CustomizationGuard,
+    initial_approx_threshold: usize,
+    progress: ProgressCallback,
+) -> Result<(Vec<ShortcutWrapper>, Vec<ShortcutWrapper>), InterruptedCustomization> {
+    customize_main(cch, metric, num_intervals, approximation, progress, &mut shortcuts, Some(&guard), None);
+
+    // the lowered threshold only applies to this customization
+    let lowered_approx_threshold = approx_threshold();
+    set_approx_threshold(initial_approx_threshold);
+    report!("approx_threshold", lowered_approx_threshold);
+
+    if guard.is_interrupted() {
+        return Err(InterruptedCustomization {
+            shortcuts,
+            customized: guard.into_customized(),
+            num_intervals,
+            approximation,
+            approx_threshold: lowered_approx_threshold,
+            initial_approx_threshold,
+        });
+    }
+
+    Ok(finish_customization(cch, metric, shortcuts, approximation))
+}
+
+// Initialization and bound based precustomization
+fn prepare_customization(
+    cch: &CCH,
+    metric: &TDGraph,
+    approximation: Option<FlWeight>,
+    cancellation: Option<&CancellationToken>,
+) -> (Vec<ShortcutWrapper>, Vec<ShortcutWrapper>) {
     report!("algo", "Floating TDCCH Customization");
+    if let Some(epsilon) = approximation {
+        report!("interval_minima_approximation", f64::from(epsilon));
+        reset_max_approximation_error();
+    }
 
     let n = (cch.first_out.len() - 1) as NodeId;
     let m = cch.head.len();
@@ -130,85 +269,13 @@ pub fn customize_td_graph(cch: &CCH, metric: &TDGraph, num_intervals: u32) -> (V
         });
     };
 
-    // Routine for CATCHUp perfect precustomization on the bounds.
-    // The interface is similar to the one for the basic customization, but we need access to nonconsecutive ranges of edges,
-    // so we can't use slices. Thus, we just take a mutable pointer to the shortcut vecs.
-    // The logic of the perfect customization based on separators guarantees, that we will never concurrently modify
-    // the same shortcuts, but so far I haven't found a way to express that in safe rust.
-    let customize_perfect = |nodes: Range<usize>, upward: *mut ShortcutWrapper, downward: *mut ShortcutWrapper| {
-        PERFECT_WORKSPACE.with(|node_edge_ids| {
-            let mut node_edge_ids = node_edge_ids.borrow_mut();
-
-            // processing nodes in reverse order
-            for current_node in nodes.rev() {
-                let current_node = current_node as NodeId;
-                // store mapping of head node to corresponding outgoing edge id
-                for (node, edge_id) in cch.neighbor_iter(current_node).zip(cch.neighbor_edge_indices(current_node)) {
-                    node_edge_ids[node as usize] = InRangeOption::some(edge_id);
-                }
-
-                for (node, edge_id) in cch.neighbor_iter(current_node).zip(cch.neighbor_edge_indices(current_node)) {
-                    let shortcut_edge_ids = cch.neighbor_edge_indices(node);
-                    for (target, shortcut_edge_id) in cch.neighbor_iter(node).zip(shortcut_edge_ids) {
-                        if let Some(other_edge_id) = node_edge_ids[target as usize].value() {
-                            // Here we have both an intermediate and an upper triangle
-                            // depending on which edge we take as the base
-                            // Relax all them.
-                            unsafe {
-                                (*upward.add(other_edge_id as usize)).shortcut.upper_bound = min(
-                                    (*upward.add(other_edge_id as usize)).shortcut.upper_bound,
-                                    (*upward.add(edge_id as usize)).shortcut.upper_bound + (*upward.add(shortcut_edge_id as usize)).shortcut.upper_bound,
-                                );
-                                (*upward.add(other_edge_id as usize)).shortcut.lower_bound = min(
-                                    (*upward.add(other_edge_id as usize)).shortcut.lower_bound,
-                                    (*upward.add(edge_id as usize)).shortcut.lower_bound + (*upward.add(shortcut_edge_id as usize)).shortcut.lower_bound,
-                                );
-
-                                (*upward.add(edge_id as usize)).shortcut.upper_bound = min(
-                                    (*upward.add(edge_id as usize)).shortcut.upper_bound,
-                                    (*upward.add(other_edge_id as usize)).shortcut.upper_bound
-                                        + (*downward.add(shortcut_edge_id as usize)).shortcut.upper_bound,
-                                );
-                                (*upward.add(edge_id as usize)).shortcut.lower_bound = min(
-                                    (*upward.add(edge_id as usize)).shortcut.lower_bound,
-                                    (*upward.add(other_edge_id as usize)).shortcut.lower_bound
-                                        + (*downward.add(shortcut_edge_id as usize)).shortcut.lower_bound,
-                                );
-
-                                (*downward.add(other_edge_id as usize)).shortcut.upper_bound = min(
-                                    (*downward.add(other_edge_id as usize)).shortcut.upper_bound,
-                                    (*downward.add(edge_id as usize)).shortcut.upper_bound + (*downward.add(shortcut_edge_id as usize)).shortcut.upper_bound,
-                                );
-                                (*downward.add(other_edge_id as usize)).shortcut.lower_bound = min(
-                                    (*downward.add(other_edge_id as usize)).shortcut.lower_bound,
-                                    (*downward.add(edge_id as usize)).shortcut.lower_bound + (*downward.add(shortcut_edge_id as usize)).shortcut.lower_bound,
-                                );
-
-                                (*downward.add(edge_id as usize)).shortcut.upper_bound = min(
-                                    (*downward.add(edge_id as usize)).shortcut.upper_bound,
-                                    (*downward.add(other_edge_id as usize)).shortcut.upper_bound
-                                        + (*upward.add(shortcut_edge_id as usize)).shortcut.upper_bound,
-                                );
-                                (*downward.add(edge_id as usize)).shortcut.lower_bound = min(
-                                    (*downward.add(edge_id as usize)).shortcut.lower_bound,
-                                    (*downward.add(other_edge_id as usize)).shortcut.lower_bound
-                                        + (*upward.add(shortcut_edge_id as usize)).shortcut.lower_bound,
-                                );
-                            }
-                        }
-                    }
-                }
-
-                // reset the mapping
-                for node in cch.neighbor_iter(current_node) {
-                    node_edge_ids[node as usize] = InRangeOption::NONE;
-                }
-            }
-        });
-    };
+    let customize_perfect = |nodes, upward, downward| customize_perfect_nodes(cch, nodes, upward, downward);
 
     // parallelize precusotmization
-    let static_customization = SeparatorBasedParallelCustomization::new(cch, customize, customize);
+    let mut static_customization = SeparatorBasedParallelCustomization::new(cch, customize, customize);
+    if let Some(cancellation) = cancellation {
+        static_customization = static_customization.with_cancellation(cancellation);
+    }
     let static_perfect_customization = SeparatorBasedPerfectParallelCustomization::new(cch, customize_perfect, customize_perfect);
 
     // routine to disable shortcuts for which the perfect precustomization determined them to be irrelevant
@@ -244,31 +311,65 @@ pub fn customize_td_graph(cch: &CCH, metric: &TDGraph, num_intervals: u32) -> (V
         downward.par_iter_mut().zip(downward_preliminary_bounds.par_iter()).for_each(disable_dominated);
     });
 
-    // block for main CATCHUp customization
-    {
-        let subctxt = push_context("main".to_string());
-
-        // use separator based parallelization
-        let customization = SeparatorBasedParallelCustomization::new(
-            cch,
-            // routines created in this function
-            // we customize many cells in parallel - so iterate over triangles sequentially
-            create_customization_fn(&cch, metric, SeqIter(&cch), num_intervals),
-            // the final separator can only be customized, once everything else is done, but it still takes up a significant amount of time
-            // But we can still parallelize the processing of edges from one node within this separator.
-            create_customization_fn(&cch, metric, ParIter(&cch), num_intervals),
-        );
+    (upward, downward)
+}
 
-        report_time("TD-CCH Customization", || {
-            // execute main customization
-            customization.customize(&mut upward, &mut downward, |cb| {
-                MERGE_BUFFERS.set(&RefCell::new(MergeBuffers::new()), || {
-                    cb();
-                });
+// Main CATCHUp customization, nodes already marked as customized by the guard (if any) are skipped
+#[allow(clippy::too_many_arguments)]
+fn customize_main(
+    cch: &CCH,
+    metric: &TDGraph,
+    num_intervals: u32,
+    approximation: Option<FlWeight>,
+    progress: ProgressCallback,
+    (upward, downward): &mut (Vec<ShortcutWrapper>, Vec<ShortcutWrapper>),
+    guard: Option<&CustomizationGuard>,
+    cancellation: Option<&CancellationToken>,
+) {
+    let subctxt = push_context("main".to_string());
+
+    // use separator based parallelization
+    let mut customization = SeparatorBasedParallelCustomization::new(
+        cch,
+        // routines created in this function
+        // we customize many cells in parallel - so iterate over triangles sequentially
+        create_customization_fn(cch, metric, SeqIter(cch), num_intervals, approximation, guard),
+        // the final separator can only be customized, once everything else is done, but it still takes up a significant amount of time
+        // But we can still parallelize the processing of edges from one node within this separator.
+        create_customization_fn(cch, metric, ParIter(cch), num_intervals, approximation, guard),
+    )
+    .with_progress(progress);
+    if let Some(cancellation) = cancellation {
+        customization = customization.with_cancellation(cancellation);
+    }
+
+    report_time("TD-CCH Customization", || {
+        // execute main customization
+        customization.customize(upward, downward, |cb| {
+            MERGE_BUFFERS.set(&RefCell::new(MergeBuffers::new()), || {
+                cb();
             });
         });
+    });
 
-        drop(subctxt);
+    drop(subctxt);
+}
+
+// Post-customization on the final bounds and adjustment of the interval minima bounds
+fn finish_customization(
+    cch: &CCH,
+    metric: &TDGraph,
+    (mut upward, mut downward): (Vec<ShortcutWrapper>, Vec<ShortcutWrapper>),
+    approximation: Option<FlWeight>,
+) -> (Vec<ShortcutWrapper>, Vec<ShortcutWrapper>) {
+    let n = (cch.first_out.len() - 1) as NodeId;
+    let customize_perfect = |nodes, upward, downward| customize_perfect_nodes(cch, nodes, upward, downward);
+    let static_perfect_customization = SeparatorBasedPerfectParallelCustomization::new(cch, customize_perfect, customize_perfect);
+
+    if approximation.is_some() {
+        let max_error = f64::from(max_approximation_error());
+        println!("Maximum error of approximated interval minima: {}s", max_error);
+        report!("interval_minima_max_approximation_error", max_error);
     }
 
     // post-customization
@@ -359,6 +460,79 @@ pub fn customize_td_graph(cch: &CCH, metric: &TDGraph, num_intervals: u32) -> (V
     (upward, downward)
 }
 
+// Routine for CATCHUp perfect precustomization on the bounds.
+// The interface is similar to the one for the basic customization, but we need access to nonconsecutive ranges of edges,
+// so we can't use slices. Thus, we just take a mutable pointer to the shortcut vecs.
+// The logic of the perfect customization based on separators guarantees, that we will never concurrently modify
+// the same shortcuts, but so far I haven't found a way to express that in safe rust.
+fn customize_perfect_nodes(cch: &CCH, nodes: Range<usize>, upward: *mut ShortcutWrapper, downward: *mut ShortcutWrapper) {
+    PERFECT_WORKSPACE.with(|node_edge_ids| {
+        let mut node_edge_ids = node_edge_ids.borrow_mut();
+
+        // processing nodes in reverse order
+        for current_node in nodes.rev() {
+            let current_node = current_node as NodeId;
+            // store mapping of head node to corresponding outgoing edge id
+            for (node, edge_id) in cch.neighbor_iter(current_node).zip(cch.neighbor_edge_indices(current_node)) {
+                node_edge_ids[node as usize] = InRangeOption::some(edge_id);
+            }
+
+            for (node, edge_id) in cch.neighbor_iter(current_node).zip(cch.neighbor_edge_indices(current_node)) {
+                let shortcut_edge_ids = cch.neighbor_edge_indices(node);
+                for (target, shortcut_edge_id) in cch.neighbor_iter(node).zip(shortcut_edge_ids) {
+                    if let Some(other_edge_id) = node_edge_ids[target as usize].value() {
+                        // Here we have both an intermediate and an upper triangle
+                        // depending on which edge we take as the base
+                        // Relax all them.
+                        unsafe {
+                            (*upward.add(other_edge_id as usize)).shortcut.upper_bound = min(
+                                (*upward.add(other_edge_id as usize)).shortcut.upper_bound,
+                                (*upward.add(edge_id as usize)).shortcut.upper_bound + (*upward.add(shortcut_edge_id as usize)).shortcut.upper_bound,
+                            );
+                            (*upward.add(other_edge_id as usize)).shortcut.lower_bound = min(
+                                (*upward.add(other_edge_id as usize)).shortcut.lower_bound,
+                                (*upward.add(edge_id as usize)).shortcut.lower_bound + (*upward.add(shortcut_edge_id as usize)).shortcut.lower_bound,
+                            );
+
+                            (*upward.add(edge_id as usize)).shortcut.upper_bound = min(
+                                (*upward.add(edge_id as usize)).shortcut.upper_bound,
+                                (*upward.add(other_edge_id as usize)).shortcut.upper_bound + (*downward.add(shortcut_edge_id as usize)).shortcut.upper_bound,
+                            );
+                            (*upward.add(edge_id as usize)).shortcut.lower_bound = min(
+                                (*upward.add(edge_id as usize)).shortcut.lower_bound,
+                                (*upward.add(other_edge_id as usize)).shortcut.lower_bound + (*downward.add(shortcut_edge_id as usize)).shortcut.lower_bound,
+                            );
+
+                            (*downward.add(other_edge_id as usize)).shortcut.upper_bound = min(
+                                (*downward.add(other_edge_id as usize)).shortcut.upper_bound,
+                                (*downward.add(edge_id as usize)).shortcut.upper_bound + (*downward.add(shortcut_edge_id as usize)).shortcut.upper_bound,
+                            );
+                            (*downward.add(other_edge_id as usize)).shortcut.lower_bound = min(
+                                (*downward.add(other_edge_id as usize)).shortcut.lower_bound,
+                                (*downward.add(edge_id as usize)).shortcut.lower_bound + (*downward.add(shortcut_edge_id as usize)).shortcut.lower_bound,
+                            );
+
+                            (*downward.add(edge_id as usize)).shortcut.upper_bound = min(
+                                (*downward.add(edge_id as usize)).shortcut.upper_bound,
+                                (*downward.add(other_edge_id as usize)).shortcut.upper_bound + (*upward.add(shortcut_edge_id as usize)).shortcut.upper_bound,
+                            );
+                            (*downward.add(edge_id as usize)).shortcut.lower_bound = min(
+                                (*downward.add(edge_id as usize)).shortcut.lower_bound,
+                                (*downward.add(other_edge_id as usize)).shortcut.lower_bound + (*upward.add(shortcut_edge_id as usize)).shortcut.lower_bound,
+                            );
+                        }
+                    }
+                }
+            }
+
+            // reset the mapping
+            for node in cch.neighbor_iter(current_node) {
+                node_edge_ids[node as usize] = InRangeOption::NONE;
+            }
+        }
+    });
+}
+
 // Encapsulates the creation of the CATCHUp main customization lambdas
 // The function signature gives us some additional control of lifetimes and stuff
 fn create_customization_fn<'s, F: 's>(
@@ -366,6 +540,8 @@ fn create_customization_fn<'s, F: 's>(
     metric: &'s TDGraph,
     merge_iter: F,
     num_intervals: u32,
+    approximation: Option<FlWeight>,
+    guard: Option<&'s CustomizationGuard>,
 ) -> impl Fn(Range<usize>, usize, &mut [ShortcutWrapper], &mut [ShortcutWrapper]) + 's
 where
     for<'p> F: ForEachIter<'p, 's, ShortcutWrapper>,
@@ -373,6 +549,11 @@ where
     move |nodes, edge_offset, upward: &mut [ShortcutWrapper], downward: &mut [ShortcutWrapper]| {
         // for all nodes we should currently process
         for current_node in nodes {
+            // already done in an earlier run or the customization got interrupted
+            if guard.map_or(false, |guard| guard.skip_node(current_node)) {
+                continue;
+            }
+
             let (upward_below, upward_above) = upward.split_at_mut(cch.first_out[current_node as usize] as usize - edge_offset);
             let upward_active = &mut upward_above[0..cch.neighbor_edge_indices(current_node as NodeId).len()];
             let (downward_below, downward_above) = downward.split_at_mut(cch.first_out[current_node as usize] as usize - edge_offset);
@@ -442,42 +623,68 @@ where
             );
 
             // the outgoing edges will never be used again -> transform and free some memory
-            for (_, Reversed(EdgeIdT(edge_id))) in cch.inverted.link_iter(current_node as NodeId) {
-                if let Some(cache) = &upward[edge_id as usize - edge_offset].shortcut.cache {
-                    let ttf = PeriodicATTF::from(cache).bound_plfs().0.to_vec();
-                    let (interval_minima, lower_bound, upper_bound) = extract_interval_minima(&ttf, num_intervals);
-                    upward[edge_id as usize - edge_offset].interval_minima = interval_minima;
-                    upward[edge_id as usize - edge_offset].bounds = (lower_bound, upper_bound);
-                } else if let Sources::One(source) = &upward[edge_id as usize - edge_offset].shortcut.sources {
-                    if let ShortcutSource::OriginalEdge(id) = ShortcutSource::from(*source) {
-                        let ttf = PeriodicATTF::Exact(metric.travel_time_function(id)).bound_plfs().0.to_vec();
-                        let (interval_minima, lower_bound, upper_bound) = extract_interval_minima(&ttf, num_intervals);
-                        upward[edge_id as usize - edge_offset].interval_minima = interval_minima;
-                        upward[edge_id as usize - edge_offset].bounds = (lower_bound, upper_bound);
-                    }
+            // the interval minima of all these edges are independent of each other and extracted in parallel
+            let finished_edges = cch
+                .inverted
+                .link_iter(current_node as NodeId)
+                .map(|(_, Reversed(EdgeIdT(edge_id)))| edge_id as usize - edge_offset)
+                .collect::<Vec<usize>>();
+
+            let (upward_ref, downward_ref): (&[ShortcutWrapper], &[ShortcutWrapper]) = (upward, downward);
+            let extracted = finished_edges
+                .par_iter()
+                .map(|&edge_idx| {
+                    (
+                        extract_shortcut_interval_minima(&upward_ref[edge_idx], metric, num_intervals, approximation),
+                        extract_shortcut_interval_minima(&downward_ref[edge_idx], metric, num_intervals, approximation),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            for (&edge_idx, (upward_minima, downward_minima)) in finished_edges.iter().zip(extracted.into_iter()) {
+                if let Some((interval_minima, lower_bound, upper_bound)) = upward_minima {
+                    upward[edge_idx].interval_minima = interval_minima;
+                    upward[edge_idx].bounds = (lower_bound, upper_bound);
                 }
-                upward[edge_id as usize - edge_offset].shortcut.clear_plf();
+                upward[edge_idx].shortcut.clear_plf();
 
                 // analogous procedure for downward weights
-                if let Some(cache) = &downward[edge_id as usize - edge_offset].shortcut.cache {
-                    let ttf = PeriodicATTF::from(cache).bound_plfs().0.to_vec();
-                    let (interval_minima, lower_bound, upper_bound) = extract_interval_minima(&ttf, num_intervals);
-                    downward[edge_id as usize - edge_offset].interval_minima = interval_minima;
-                    downward[edge_id as usize - edge_offset].bounds = (lower_bound, upper_bound);
-                } else if let Sources::One(source) = &downward[edge_id as usize - edge_offset].shortcut.sources {
-                    if let ShortcutSource::OriginalEdge(id) = ShortcutSource::from(*source) {
-                        let ttf = PeriodicATTF::Exact(metric.travel_time_function(id)).bound_plfs().0.to_vec();
-                        let (interval_minima, lower_bound, upper_bound) = extract_interval_minima(&ttf, num_intervals);
-                        downward[edge_id as usize - edge_offset].interval_minima = interval_minima;
-                        downward[edge_id as usize - edge_offset].bounds = (lower_bound, upper_bound);
-                    }
+                if let Some((interval_minima, lower_bound, upper_bound)) = downward_minima {
+                    downward[edge_idx].interval_minima = interval_minima;
+                    downward[edge_idx].bounds = (lower_bound, upper_bound);
                 }
-                downward[edge_id as usize - edge_offset].shortcut.clear_plf();
+                downward[edge_idx].shortcut.clear_plf();
+            }
+
+            if let Some(guard) = guard {
+                guard.node_customized(current_node);
             }
         }
     }
 }
 
+/// interval minima and bounds of a finished shortcut, either from its cached ttf or from the original edge it represents
+fn extract_shortcut_interval_minima(
+    wrapper: &ShortcutWrapper,
+    metric: &TDGraph,
+    num_intervals: u32,
+    approximation: Option<FlWeight>,
+) -> Option<(Vec<u32>, u32, u32)> {
+    if let Some(cache) = &wrapper.shortcut.cache {
+        let ttf = PeriodicATTF::from(cache).bound_plfs().0.to_vec();
+        Some(extract_interval_minima(&ttf, num_intervals, approximation))
+    } else if let Sources::One(source) = &wrapper.shortcut.sources {
+        if let ShortcutSource::OriginalEdge(id) = ShortcutSource::from(*source) {
+            let ttf = PeriodicATTF::Exact(metric.travel_time_function(id)).bound_plfs().0.to_vec();
+            Some(extract_interval_minima(&ttf, num_intervals, approximation))
+        } else {
+            None
+        }
+    } else {
+        None
+    }
+}
+
 pub fn convert_to_td_graph(metric: &rust_road_router::datastr::graph::time_dependent::TDGraph) -> TDGraph {
     TDGraph::new(
         metric.first_out().to_vec(),
@@ -488,48 +695,37 @@ pub fn convert_to_td_graph(metric: &rust_road_router::datastr::graph::time_depen
     )
 }
 
-fn extract_interval_minima(ttf: &Vec<TTFPoint>, num_intervals: u32) -> (Vec<u32>, u32, u32) {
+fn extract_interval_minima(ttf: &Vec<TTFPoint>, num_intervals: u32, approximation: Option<FlWeight>) -> (Vec<u32>, u32, u32) {
     // collect minima within the current interval
     let interval_length = MAX_BUCKETS / num_intervals;
     let mut interval_min = vec![INFINITY; num_intervals as usize];
-    let mut bucket_idx = 0;
-
-    let mut global_min = INFINITY;
-    let mut global_max = 0;
-
-    // deal with constant functions
-    let ttf = if ttf.last().unwrap().at.fuzzy_lt(Timestamp::new(86400.0)) {
-        debug_assert_eq!(ttf.len(), 1, "Expected constant function, got: {:#?}", &ttf);
-        let val = ttf.first().unwrap().val;
-        vec![
-            TTFPoint { at: Timestamp::ZERO, val },
-            TTFPoint {
-                at: Timestamp::new(86400.0),
-                val,
-            },
-        ]
-    } else {
-        ttf.clone()
-    };
 
+    // minima of a lower bound are still valid lower bounds, but may be up to `epsilon` below the exact minima
+    let approximated = approximation.map(|epsilon| PeriodicPiecewiseLinearFunction::new(ttf).approximate_with_error(epsilon).lower);
+    let ttf = approximated.as_deref().unwrap_or(ttf);
+
+    // constant functions are expanded to cover the whole period
+    let ttf = PartialPiecewiseLinearFunction::new(ttf).to_periodic_vec();
+
+    // convert all points once, afterwards the minima are taken over contiguous slices which can be vectorized
+    let (timestamps, values): (Vec<u32>, Vec<u32>) = ttf[..ttf.len() - 1]
+        .iter()
+        .map(|point| (convert_timestamp_f64_to_u32(point.at.0), convert_timestamp_f64_to_u32(point.val.0)))
+        .unzip();
     debug_assert!(
-        ttf.last().unwrap().at.fuzzy_eq(Timestamp::new(86400.0)),
-        "last value: {}",
-        ttf.last().unwrap().at.0
+        timestamps.last().map(|&ts| ts < num_intervals * interval_length).unwrap_or(true),
+        "sentinel must not be exceeded!, timestamp: {:?}",
+        timestamps.last()
     );
 
-    ttf[..ttf.len() - 1].iter().for_each(|point| {
-        let ts = convert_timestamp_f64_to_u32(point.at.0);
+    let global_min = values.iter().copied().fold(INFINITY, min);
+    let global_max = values.iter().copied().fold(0, max);
 
-        while ts >= (bucket_idx + 1) * interval_length {
-            bucket_idx += 1;
-            debug_assert!(bucket_idx < num_intervals, "sentinel must not be exceeded!, timestamp: {}", ts);
-        }
-
-        let val = convert_timestamp_f64_to_u32(point.val.0);
-        interval_min[bucket_idx as usize] = min(interval_min[bucket_idx as usize], val);
-        global_min = min(global_min, val);
-        global_max = max(global_max, val);
+    let mut bucket_start = 0;
+    interval_min.iter_mut().enumerate().for_each(|(idx, val)| {
+        let bucket_end = bucket_start + timestamps[bucket_start..].partition_point(|&ts| ts < (idx as u32 + 1) * interval_length);
+        *val = values[bucket_start..bucket_end].iter().copied().fold(INFINITY, min);
+        bucket_start = bucket_end;
     });
 
     // also collect values at interval borders
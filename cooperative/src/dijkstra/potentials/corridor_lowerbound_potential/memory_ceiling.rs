@@ -0,0 +1,141 @@
+//! Memory ceiling for the CATCHUp customization.
+//!
+//! Once the memory usage exceeds the ceiling, shortcut functions get approximated more aggressively
+//! by lowering the approximation threshold step by step.
+//! When the threshold can not be lowered any further, the customization stops between two nodes
+//! and returns its state, so it can be resumed later (e.g. after freeing some memory) instead of being killed.
+
+use crate::dijkstra::potentials::corridor_lowerbound_potential::shortcut::ShortcutWrapper;
+use rust_road_router::datastr::graph::floating_time_dependent::{approx_threshold, set_approx_threshold, FlWeight};
+use rust_road_router::report::memory::current_memory_bytes;
+use std::cmp::max;
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering::Relaxed};
+
+// querying the memory usage is not free, so it is only checked every few nodes
+const CHECK_INTERVAL: usize = 64;
+
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryCeiling {
+    pub max_bytes: usize,
+    /// the approximation threshold is never lowered below this number of points
+    pub min_approx_threshold: usize,
+}
+
+impl MemoryCeiling {
+    pub fn new(max_bytes: usize) -> Self {
+        MemoryCeiling {
+            max_bytes,
+            min_approx_threshold: 50,
+        }
+    }
+
+    pub fn with_min_approx_threshold(self, min_approx_threshold: usize) -> Self {
+        MemoryCeiling { min_approx_threshold, ..self }
+    }
+
+    fn exceeded_by(&self) -> Option<usize> {
+        current_memory_bytes().filter(|&bytes| bytes > self.max_bytes)
+    }
+}
+
+/// Keeps track of the customized nodes and enforces the ceiling while the main customization runs.
+#[derive(Debug)]
+pub(super) struct CustomizationGuard {
+    ceiling: MemoryCeiling,
+    customized: Vec<AtomicBool>,
+    interrupted: AtomicBool,
+    num_customized: AtomicUsize,
+}
+
+impl CustomizationGuard {
+    pub(super) fn new(ceiling: MemoryCeiling, customized: Vec<bool>) -> Self {
+        let num_customized = customized.iter().filter(|&&done| done).count();
+        CustomizationGuard {
+            ceiling,
+            customized: customized.into_iter().map(AtomicBool::new).collect(),
+            interrupted: AtomicBool::new(false),
+            num_customized: AtomicUsize::new(num_customized),
+        }
+    }
+
+    pub(super) fn skip_node(&self, node: usize) -> bool {
+        self.interrupted.load(Relaxed) || self.customized[node].load(Relaxed)
+    }
+
+    pub(super) fn node_customized(&self, node: usize) {
+        self.customized[node].store(true, Relaxed);
+        if self.num_customized.fetch_add(1, Relaxed) % CHECK_INTERVAL != 0 {
+            return;
+        }
+
+        if let Some(bytes) = self.ceiling.exceeded_by() {
+            let threshold = approx_threshold();
+            if threshold > self.ceiling.min_approx_threshold {
+                let lowered = max(threshold / 2, self.ceiling.min_approx_threshold);
+                set_approx_threshold(lowered);
+                println!(
+                    "Memory ceiling exceeded ({} MiB), approximating functions with more than {} points",
+                    bytes >> 20,
+                    lowered
+                );
+            } else if !self.interrupted.swap(true, Relaxed) {
+                println!("Memory ceiling exceeded ({} MiB), interrupting customization", bytes >> 20);
+            }
+        }
+    }
+
+    pub(super) fn is_interrupted(&self) -> bool {
+        self.interrupted.load(Relaxed)
+    }
+
+    pub(super) fn into_customized(self) -> Vec<bool> {
+        self.customized.into_iter().map(AtomicBool::into_inner).collect()
+    }
+}
+
+/// State of a customization which was interrupted because of the memory ceiling.
+/// Pass it to `resume_td_customization` to continue with the remaining nodes.
+pub struct InterruptedCustomization {
+    pub(super) shortcuts: (Vec<ShortcutWrapper>, Vec<ShortcutWrapper>),
+    pub(super) customized: Vec<bool>,
+    pub(super) num_intervals: u32,
+    pub(super) approximation: Option<FlWeight>,
+    // the lowered threshold to continue with and the one to restore once done
+    pub(super) approx_threshold: usize,
+    pub(super) initial_approx_threshold: usize,
+}
+
+impl InterruptedCustomization {
+    pub fn num_customized_nodes(&self) -> usize {
+        self.customized.iter().filter(|&&done| done).count()
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.customized.len()
+    }
+}
+
+impl fmt::Debug for InterruptedCustomization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InterruptedCustomization")
+            .field("num_customized_nodes", &self.num_customized_nodes())
+            .field("num_nodes", &self.num_nodes())
+            .field("approx_threshold", &self.approx_threshold)
+            .finish()
+    }
+}
+
+impl fmt::Display for InterruptedCustomization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "customization interrupted by memory ceiling after {} of {} nodes",
+            self.num_customized_nodes(),
+            self.num_nodes()
+        )
+    }
+}
+
+impl Error for InterruptedCustomization {}
@@ -1,5 +1,6 @@
 pub mod customization;
 pub mod customization_catchup;
+pub mod memory_ceiling;
 pub mod potential;
 pub mod shortcut;
 
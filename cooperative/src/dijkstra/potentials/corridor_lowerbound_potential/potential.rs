@@ -4,50 +4,78 @@ use crate::dijkstra::potentials::TDPotential;
 use crate::graph::MAX_BUCKETS;
 use rust_road_router::algo::customizable_contraction_hierarchy::{DirectedCCH, CCHT};
 use rust_road_router::datastr::graph::time_dependent::Timestamp;
-use rust_road_router::datastr::graph::{EdgeId, EdgeIdT, Graph, LinkIterable, NodeId, NodeIdT, UnweightedFirstOutGraph, Weight, INFINITY};
-use rust_road_router::datastr::timestamped_vector::TimestampedVector;
+use rust_road_router::datastr::graph::{EdgeId, EdgeIdGraph, EdgeIdT, Graph, LinkIterable, NodeId, NodeIdT, UnweightedFirstOutGraph, Weight, INFINITY};
+use rust_road_router::datastr::timestamped_vector::{Epoch, TimestampedVector};
 use rust_road_router::util::in_range_option::InRangeOption;
 use std::borrow::Borrow;
 use std::cmp::min;
 
 // container for all variables which change after each query
 #[derive(Debug, Clone)]
-pub struct CorridorLowerboundPotentialContext {
+pub struct CorridorLowerboundPotentialContext<E: Epoch = u32> {
     num_pot_computations: usize,
-    query_start: Timestamp,
+    // departures covered by the current search space, a single timestamp unless searches are reused
+    departure_window: (Timestamp, Timestamp),
+    reuse_searches: bool,
+    // (capacity bounds, source, target, departure interval) of the current search space
+    cached_query: Option<(bool, NodeId, NodeId, u32)>,
+    num_reused_searches: usize,
     target_dist_bounds: Option<(Weight, Weight)>,
-    backward_distances: TimestampedVector<Weight>,
+    backward_distances: TimestampedVector<Weight, E>,
     stack: Vec<NodeId>,
-    potentials: TimestampedVector<InRangeOption<Weight>>,
+    potentials: TimestampedVector<InRangeOption<Weight>, E>,
+    edge_weights: Vec<Weight>,
 }
 
-impl CorridorLowerboundPotentialContext {
+impl<E: Epoch> CorridorLowerboundPotentialContext<E> {
     pub fn new(num_nodes: usize) -> Self {
         Self {
             num_pot_computations: 0,
-            query_start: 0,
+            departure_window: (0, 0),
+            reuse_searches: false,
+            cached_query: None,
+            num_reused_searches: 0,
             target_dist_bounds: None,
-            backward_distances: TimestampedVector::new(num_nodes),
+            backward_distances: TimestampedVector::with_epochs(num_nodes),
             stack: Vec::new(),
-            potentials: TimestampedVector::new(num_nodes),
+            potentials: TimestampedVector::with_epochs(num_nodes),
+            edge_weights: Vec::new(),
         }
     }
+
+    /// Reuse the search space of the previous query if source, target and departure interval are equal.
+    /// The search spaces then cover the whole departure interval, which slightly weakens the potential.
+    pub fn set_search_reuse(&mut self, reuse_searches: bool) {
+        self.reuse_searches = reuse_searches;
+        self.invalidate();
+    }
+
+    /// number of queries that reused the search space of their predecessor
+    pub fn num_reused_searches(&self) -> usize {
+        self.num_reused_searches
+    }
+
+    /// drop the cached search space, required after each re-customization
+    pub fn invalidate(&mut self) {
+        self.cached_query = None;
+    }
 }
 
-pub struct CorridorLowerboundPotential<'a> {
+pub struct CorridorLowerboundPotential<'a, E: Epoch = u32> {
     cch: &'a DirectedCCH,
     forward_cch_graph: UnweightedFirstOutGraph<&'a [EdgeId], &'a [NodeId]>,
     forward_cch_weights: &'a Vec<Weight>,
     backward_cch_graph: UnweightedFirstOutGraph<&'a [EdgeId], &'a [NodeId]>,
     backward_cch_weights: &'a Vec<Weight>,
-    forward_potential: BoundedLowerUpperPotential<'a, DirectedCCH>,
+    forward_potential: BoundedLowerUpperPotential<'a, DirectedCCH, E>,
     interval_length: u32,
     num_intervals: u32,
-    context: &'a mut CorridorLowerboundPotentialContext,
+    capacity_bounds: bool,
+    context: &'a mut CorridorLowerboundPotentialContext<E>,
 }
 
-impl<'a> CorridorLowerboundPotential<'a> {
-    pub fn prepare_capacity(customized: &'a mut CustomizedCorridorLowerbound) -> Self {
+impl<'a, E: Epoch> CorridorLowerboundPotential<'a, E> {
+    pub fn prepare_capacity(customized: &'a mut CustomizedCorridorLowerbound<E>) -> Self {
         let forward_cch_graph = UnweightedFirstOutGraph::new(customized.cch.borrow().forward_first_out(), customized.cch.borrow().forward_head());
         let backward_cch_graph = UnweightedFirstOutGraph::new(customized.cch.borrow().backward_first_out(), customized.cch.borrow().backward_head());
 
@@ -69,11 +97,12 @@ impl<'a> CorridorLowerboundPotential<'a> {
             forward_potential,
             interval_length: MAX_BUCKETS / customized.num_intervals,
             num_intervals: customized.num_intervals,
+            capacity_bounds: true,
             context: &mut customized.potential_context,
         }
     }
 
-    pub fn prepare_ptv(customized: &'a mut CustomizedCorridorLowerbound) -> Self {
+    pub fn prepare_ptv(customized: &'a mut CustomizedCorridorLowerbound<E>) -> Self {
         let forward_cch_graph = UnweightedFirstOutGraph::new(customized.cch.borrow().forward_first_out(), customized.cch.borrow().forward_head());
         let backward_cch_graph = UnweightedFirstOutGraph::new(customized.cch.borrow().backward_first_out(), customized.cch.borrow().backward_head());
 
@@ -93,6 +122,7 @@ impl<'a> CorridorLowerboundPotential<'a> {
             forward_potential,
             interval_length: MAX_BUCKETS / customized.num_intervals,
             num_intervals: customized.num_intervals,
+            capacity_bounds: false,
             context: &mut customized.potential_context,
         }
     }
@@ -100,12 +130,76 @@ impl<'a> CorridorLowerboundPotential<'a> {
     pub fn num_pot_computations(&self) -> usize {
         self.context.num_pot_computations
     }
+
+    pub fn num_reused_searches(&self) -> usize {
+        self.context.num_reused_searches
+    }
+
+    /// compute the potential of the given rank, all its upward neighbors must already be known
+    fn compute_potential(&mut self, current_node: NodeId) {
+        let current_node_orig = self.cch.node_order().node(current_node);
+
+        // check if the current node is feasible, i.e. is able to reach the target within the valid corridor
+        if let Some((node_lower, node_upper)) = self.forward_potential.potential_bounds(current_node_orig) {
+            let (window_start, window_end) = self.context.departure_window;
+            let start_interval = (((window_start + node_lower) % MAX_BUCKETS) / self.interval_length) as usize;
+            let end_interval = (((window_end + node_upper) % MAX_BUCKETS) / self.interval_length) as usize;
+
+            // even in the forward direction, we're still performing backward linking,
+            // current edges are all starting at `current_node`
+            // -> take the same edge interval of all outgoing edges as given by the corridor.
+            // The weights of all outgoing edges within one interval are a contiguous slice, so the minima are taken slice-wise
+            let edges = self.forward_cch_graph.neighbor_edge_indices_usize(current_node);
+            let num_arcs = self.forward_cch_graph.num_arcs();
+            let edge_weights = &mut self.context.edge_weights;
+            edge_weights.clear();
+            edge_weights.extend_from_slice(&self.forward_cch_weights[start_interval * num_arcs + edges.start..start_interval * num_arcs + edges.end]);
+
+            let mut idx = start_interval;
+            while idx != end_interval {
+                idx = (idx + 1) % self.num_intervals as usize;
+                let interval_weights = &self.forward_cch_weights[idx * num_arcs + edges.start..idx * num_arcs + edges.end];
+                edge_weights
+                    .iter_mut()
+                    .zip(interval_weights.iter())
+                    .for_each(|(weight, &interval_weight)| *weight = min(*weight, interval_weight));
+            }
+
+            let mut distance = self.context.backward_distances[current_node as usize];
+            for (&next_node, &edge_weight) in self.forward_cch_graph.head()[edges].iter().zip(self.context.edge_weights.iter()) {
+                if let Some(next_potential) = self.context.potentials[next_node as usize].value() {
+                    distance = min(distance, edge_weight + next_potential);
+                }
+            }
+
+            self.context.backward_distances[current_node as usize] = distance;
+            self.context.potentials[current_node as usize] = InRangeOption::some(distance);
+        } else {
+            self.context.potentials[current_node as usize] = InRangeOption::some(INFINITY);
+        }
+    }
 }
 
-impl<'a> TDPotential for CorridorLowerboundPotential<'a> {
+impl<'a, E: Epoch> TDPotential for CorridorLowerboundPotential<'a, E> {
     fn init(&mut self, source: u32, target: u32, timestamp: u32) {
         self.context.num_pot_computations = 0;
-        self.context.query_start = timestamp;
+
+        // 0. reuse the previous search space for repeated queries within the same departure interval,
+        // it covers all departures of the interval and thus stays admissible for each of them
+        if self.context.reuse_searches {
+            let key = (self.capacity_bounds, source, target, timestamp / self.interval_length);
+            if self.context.cached_query == Some(key) {
+                self.context.num_reused_searches += 1;
+                return;
+            }
+
+            let interval_start = timestamp - timestamp % self.interval_length;
+            self.context.departure_window = (interval_start, interval_start + self.interval_length - 1);
+            self.context.cached_query = Some(key);
+        } else {
+            self.context.departure_window = (timestamp, timestamp);
+        }
+        let (window_start, window_end) = self.context.departure_window;
 
         // 1. use interval query to determine the corridor at target
         self.context.target_dist_bounds = self.forward_potential.init(source, target);
@@ -133,8 +227,8 @@ impl<'a> TDPotential for CorridorLowerboundPotential<'a> {
                     if let Some((node_lower, node_upper)) = self.forward_potential.potential_bounds(next_node_orig) {
                         debug_assert!(target_dist_upper >= node_lower);
 
-                        let start_idx = (((timestamp + node_lower) % MAX_BUCKETS) / self.interval_length) as usize;
-                        let end_idx = (((timestamp + node_upper) % MAX_BUCKETS) / self.interval_length) as usize;
+                        let start_idx = (((window_start + node_lower) % MAX_BUCKETS) / self.interval_length) as usize;
+                        let end_idx = (((window_end + node_upper) % MAX_BUCKETS) / self.interval_length) as usize;
 
                         let mut idx = start_idx;
                         let mut edge_weight = *unsafe { self.backward_cch_weights.get_unchecked(idx * self.backward_cch_graph.num_arcs() + edge_id) };
@@ -175,34 +269,7 @@ impl<'a> TDPotential for CorridorLowerboundPotential<'a> {
 
             // 2. propagate the result back to the original start node
             while let Some(current_node) = self.context.stack.pop() {
-                let current_node_orig = self.cch.node_order().node(current_node);
-                // check if the current node is feasible, i.e. is able to reach the target within the valid corridor
-                if let Some((node_lower, node_upper)) = self.forward_potential.potential_bounds(current_node_orig) {
-                    let start_interval = (((self.context.query_start + node_lower) % MAX_BUCKETS) / self.interval_length) as usize;
-                    let end_interval = (((self.context.query_start + node_upper) % MAX_BUCKETS) / self.interval_length) as usize;
-
-                    for (NodeIdT(next_node), EdgeIdT(edge)) in LinkIterable::<(NodeIdT, EdgeIdT)>::link_iter(&self.forward_cch_graph, current_node) {
-                        // even in the forward direction, we're still performing backward linking,
-                        // current edges are all starting at `current_node`
-                        // -> take the same edge interval of all outgoing edges as given by the corridor
-                        if let Some(next_potential) = self.context.potentials[next_node as usize].value() {
-                            let mut idx = start_interval;
-                            let mut edge_weight = *unsafe { self.forward_cch_weights.get_unchecked(idx * self.forward_cch_graph.num_arcs() + edge as usize) };
-                            while idx != end_interval {
-                                idx = (idx + 1) % self.num_intervals as usize;
-                                edge_weight = min(edge_weight, *unsafe {
-                                    self.forward_cch_weights.get_unchecked(idx * self.forward_cch_graph.num_arcs() + edge as usize)
-                                });
-                            }
-
-                            self.context.backward_distances[current_node as usize] =
-                                min(self.context.backward_distances[current_node as usize], edge_weight + next_potential);
-                        }
-                    }
-                    self.context.potentials[current_node as usize] = InRangeOption::some(self.context.backward_distances[current_node as usize]);
-                } else {
-                    self.context.potentials[current_node as usize] = InRangeOption::some(INFINITY);
-                }
+                self.compute_potential(current_node);
             }
 
             self.context.potentials[node as usize].value().filter(|&pot| pot < INFINITY)
@@ -211,6 +278,49 @@ impl<'a> TDPotential for CorridorLowerboundPotential<'a> {
         }
     }
 
+    fn potential_batch(&mut self, nodes: &[NodeId], _timestamps: &[Timestamp], potentials: &mut [Option<Weight>]) {
+        debug_assert_eq!(nodes.len(), potentials.len());
+
+        if self.context.target_dist_bounds.is_some() {
+            let elimination_tree = self.cch.elimination_tree();
+
+            // 1. collect the missing parts of all upward search spaces at once
+            for &node in nodes {
+                let mut cur_node = Some(self.cch.node_order().rank(node));
+                while let Some(node) = cur_node {
+                    if self.context.potentials[node as usize].value().is_some() {
+                        break;
+                    }
+                    self.context.stack.push(node);
+                    cur_node = elimination_tree[node as usize].value();
+                }
+            }
+
+            // 2. shared search spaces are only computed once, top-down
+            self.context.stack.sort_unstable_by(|a, b| b.cmp(a));
+            self.context.stack.dedup();
+            self.context.num_pot_computations += self.context.stack.len();
+
+            let stack = std::mem::take(&mut self.context.stack);
+            for &current_node in &stack {
+                self.compute_potential(current_node);
+            }
+            self.context.stack = stack;
+            self.context.stack.clear();
+
+            for (&node, potential) in nodes.iter().zip(potentials.iter_mut()) {
+                let node = self.cch.node_order().rank(node);
+                *potential = self.context.potentials[node as usize].value().filter(|&pot| pot < INFINITY);
+            }
+        } else {
+            potentials.iter_mut().for_each(|potential| *potential = None);
+        }
+    }
+
+    fn num_computations(&self) -> Option<usize> {
+        Some(self.context.num_pot_computations)
+    }
+
     fn verify_result(&self, distance: Weight) -> bool {
         let result = distance == INFINITY || self.context.target_dist_bounds.unwrap().1 >= distance;
 
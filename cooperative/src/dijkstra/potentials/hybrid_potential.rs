@@ -0,0 +1,107 @@
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{NodeId, Weight};
+
+use crate::dijkstra::potentials::TDPotential;
+
+/// Potential that starts each query with a cheap lowerbound potential and only upgrades to an expensive
+/// (e.g. corridor or multi-metric) potential once the search front exceeds a size threshold.
+///
+/// Short-range queries usually finish before the threshold is reached, so the expensive `init` is skipped for them.
+/// The search front is measured by the number of potential evaluations of the current query.
+/// Both potentials have to be admissible. Nodes that were queued with the cheap potential keep their keys,
+/// this is fine as the query re-inserts nodes whose distance is improved after they were settled.
+pub struct HybridPotential<Cheap, Expensive> {
+    cheap: Cheap,
+    expensive: Expensive,
+    threshold: usize,
+    query: (NodeId, NodeId, Timestamp),
+    num_evaluations: usize,
+    upgraded: bool,
+    num_upgrades: usize,
+}
+
+impl<Cheap: TDPotential, Expensive: TDPotential> HybridPotential<Cheap, Expensive> {
+    pub fn new(cheap: Cheap, expensive: Expensive, threshold: usize) -> Self {
+        Self {
+            cheap,
+            expensive,
+            threshold,
+            query: (0, 0, 0),
+            num_evaluations: 0,
+            upgraded: false,
+            num_upgrades: 0,
+        }
+    }
+
+    /// whether the current query already switched to the expensive potential
+    pub fn upgraded(&self) -> bool {
+        self.upgraded
+    }
+
+    /// number of queries that switched to the expensive potential
+    pub fn num_upgrades(&self) -> usize {
+        self.num_upgrades
+    }
+
+    pub fn decompose(self) -> (Cheap, Expensive) {
+        (self.cheap, self.expensive)
+    }
+
+    /// count the evaluations, initialize the expensive potential once the threshold is exceeded
+    fn track_evaluations(&mut self, num_nodes: usize) {
+        self.num_evaluations += num_nodes;
+
+        if !self.upgraded && self.num_evaluations > self.threshold {
+            let (source, target, timestamp) = self.query;
+            self.expensive.init(source, target, timestamp);
+            self.upgraded = true;
+            self.num_upgrades += 1;
+        }
+    }
+}
+
+impl<Cheap: TDPotential, Expensive: TDPotential> TDPotential for HybridPotential<Cheap, Expensive> {
+    fn init(&mut self, source: NodeId, target: NodeId, timestamp: Timestamp) {
+        self.query = (source, target, timestamp);
+        self.num_evaluations = 0;
+        self.upgraded = false;
+
+        self.cheap.init(source, target, timestamp);
+    }
+
+    fn potential(&mut self, node: NodeId, timestamp: Timestamp) -> Option<Weight> {
+        self.track_evaluations(1);
+
+        if self.upgraded {
+            self.expensive.potential(node, timestamp)
+        } else {
+            self.cheap.potential(node, timestamp)
+        }
+    }
+
+    fn potential_batch(&mut self, nodes: &[NodeId], timestamps: &[Timestamp], potentials: &mut [Option<Weight>]) {
+        self.track_evaluations(nodes.len());
+
+        if self.upgraded {
+            self.expensive.potential_batch(nodes, timestamps, potentials)
+        } else {
+            self.cheap.potential_batch(nodes, timestamps, potentials)
+        }
+    }
+
+    fn verify_result(&self, distance: Weight) -> bool {
+        if self.upgraded {
+            self.expensive.verify_result(distance)
+        } else {
+            self.cheap.verify_result(distance)
+        }
+    }
+
+    fn num_computations(&self) -> Option<usize> {
+        if self.upgraded {
+            self.expensive.num_computations()
+        } else {
+            self.cheap.num_computations()
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use rust_road_router::algo::a_star::Potential;
+use rust_road_router::algo::alt::{ALTPotData, ALTPotential};
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{FirstOutGraph, NodeId, Weight};
+use std::cmp::max;
+
+use crate::dijkstra::potentials::multi_metric_potential::customization::{build_metric_entries, extract_metrics};
+use crate::dijkstra::potentials::TDPotential;
+use crate::graph::capacity_graph::CapacityGraph;
+
+/// Landmark distances on the lower bounds of all travel time functions within a departure window `[start, end]`.
+pub struct LandmarkWindow {
+    pub start: Timestamp,
+    pub end: Timestamp,
+    data: ALTPotData,
+}
+
+impl LandmarkWindow {
+    /// select `num_landmarks` farthest landmarks and compute their distances on the window's lower bounds
+    pub fn new(graph: &CapacityGraph, start: Timestamp, end: Timestamp, num_landmarks: usize) -> Self {
+        let metrics = extract_metrics(graph.departure(), graph.travel_time(), &build_metric_entries(&vec![(start, end)]));
+        // the window's metric follows the lowerbound and upperbound metric
+        let lower_bound = metrics.iter().map(|edge_metrics| edge_metrics[2]).collect::<Vec<Weight>>();
+        let window_graph = FirstOutGraph::new(graph.first_out(), graph.head(), lower_bound);
+
+        let landmarks = ALTPotData::farthest_landmarks(&window_graph, num_landmarks, 0);
+        Self {
+            start,
+            end,
+            data: ALTPotData::new(&window_graph, landmarks),
+        }
+    }
+}
+
+/// Maximum of a CCH lowerbound potential and ALT bounds of the tightest landmark window that covers the query.
+///
+/// The CCH potential only knows the period-wide lower bound, which can be very loose if an edge is congested all day except at night.
+/// A window covers a query if it contains the departure and the departure plus `max_travel_time`, so the ALT bounds are only
+/// admissible if no query takes longer. Queries which aren't covered by any window only use the CCH potential.
+pub struct CCHLandmarkPotential<'a, P> {
+    cch_potential: P,
+    windows: Vec<(Timestamp, Timestamp, ALTPotential<'a>)>,
+    max_travel_time: Weight,
+    current_window: Option<usize>,
+    num_landmark_improvements: usize,
+}
+
+impl<'a, P: TDPotential> CCHLandmarkPotential<'a, P> {
+    pub fn new(cch_potential: P, windows: &'a [LandmarkWindow], max_travel_time: Weight) -> Self {
+        Self {
+            cch_potential,
+            windows: windows
+                .iter()
+                .map(|window| (window.start, window.end, window.data.forward_potential()))
+                .collect(),
+            max_travel_time,
+            current_window: None,
+            num_landmark_improvements: 0,
+        }
+    }
+
+    /// number of evaluations in which the landmarks yielded a tighter bound than the CCH potential
+    pub fn num_landmark_improvements(&self) -> usize {
+        self.num_landmark_improvements
+    }
+
+    pub fn decompose(self) -> P {
+        self.cch_potential
+    }
+}
+
+impl<'a, P: TDPotential> TDPotential for CCHLandmarkPotential<'a, P> {
+    fn init(&mut self, source: NodeId, target: NodeId, timestamp: Timestamp) {
+        self.cch_potential.init(source, target, timestamp);
+
+        // tightest window that spans the departure and the latest arrival, smaller window ids win ties
+        let latest_arrival = timestamp.saturating_add(self.max_travel_time);
+        self.current_window = self
+            .windows
+            .iter()
+            .enumerate()
+            .filter(|(_, (start, end, _))| *start <= timestamp && latest_arrival <= *end)
+            .min_by_key(|(_, (start, end, _))| end - start)
+            .map(|(idx, _)| idx);
+
+        if let Some(idx) = self.current_window {
+            Potential::init(&mut self.windows[idx].2, target);
+        }
+    }
+
+    fn potential(&mut self, node: NodeId, timestamp: Timestamp) -> Option<Weight> {
+        let cch_potential = self.cch_potential.potential(node, timestamp)?;
+
+        match self.current_window {
+            Some(idx) => {
+                // the target isn't reachable if the landmark bounds are infinite
+                let landmark_potential = Potential::potential(&mut self.windows[idx].2, node)?;
+                if landmark_potential > cch_potential {
+                    self.num_landmark_improvements += 1;
+                }
+                Some(max(cch_potential, landmark_potential))
+            }
+            None => Some(cch_potential),
+        }
+    }
+
+    fn verify_result(&self, distance: Weight) -> bool {
+        self.cch_potential.verify_result(distance)
+    }
+
+    fn num_computations(&self) -> Option<usize> {
+        self.cch_potential.num_computations()
+    }
+}
@@ -5,16 +5,33 @@ use rust_road_router::datastr::graph::{NodeId, Weight};
 pub mod cch_lower_upper;
 pub mod cch_parallelization_util;
 pub mod corridor_lowerbound_potential;
+pub mod hybrid_potential;
 pub mod init_cch_potential;
+pub mod landmark_potential;
 pub mod multi_metric_potential;
 
 pub trait TDPotential {
     fn init(&mut self, source: NodeId, target: NodeId, timestamp: Timestamp);
     fn potential(&mut self, node: NodeId, timestamp: Timestamp) -> Option<Weight>;
 
+    /// Evaluate the potential for a whole batch of nodes, `potentials[i]` belongs to `nodes[i]` reached at `timestamps[i]`.
+    /// Implementations may share work between the nodes, by default they are evaluated one by one.
+    fn potential_batch(&mut self, nodes: &[NodeId], timestamps: &[Timestamp], potentials: &mut [Option<Weight>]) {
+        debug_assert!(nodes.len() == timestamps.len() && nodes.len() == potentials.len());
+
+        for ((&node, &timestamp), potential) in nodes.iter().zip(timestamps.iter()).zip(potentials.iter_mut()) {
+            *potential = self.potential(node, timestamp);
+        }
+    }
+
     fn verify_result(&self, _distance: Weight) -> bool {
         true
     }
+
+    /// number of potential computations during the current query, if the potential keeps track of them
+    fn num_computations(&self) -> Option<usize> {
+        None
+    }
 }
 
 impl<T: Potential> TDPotential for T {
@@ -27,6 +44,57 @@ impl<T: Potential> TDPotential for T {
     }
 }
 
+/// Potentials chosen at runtime, e.g. from a command line flag, so a single `CapacityServer<Box<dyn TDPotential>>` serves all of them.
+/// Potentials with their own update handling (multi-metric, corridor-lowerbound) need their customized server type instead.
+impl<'a> TDPotential for Box<dyn TDPotential + 'a> {
+    fn init(&mut self, source: NodeId, target: NodeId, timestamp: Timestamp) {
+        (**self).init(source, target, timestamp)
+    }
+
+    fn potential(&mut self, node: NodeId, timestamp: Timestamp) -> Option<Weight> {
+        (**self).potential(node, timestamp)
+    }
+
+    fn potential_batch(&mut self, nodes: &[NodeId], timestamps: &[Timestamp], potentials: &mut [Option<Weight>]) {
+        (**self).potential_batch(nodes, timestamps, potentials)
+    }
+
+    fn verify_result(&self, distance: Weight) -> bool {
+        (**self).verify_result(distance)
+    }
+
+    fn num_computations(&self) -> Option<usize> {
+        (**self).num_computations()
+    }
+}
+
+/// Adapter for backward potentials (e.g. `CCHLowerUpperPotential::new_backward` or `MultiMetricPotential::prepare_backward`) in reverse searches.
+/// A reverse search starts at the query's target, so source and target are swapped on `init`,
+/// the timestamp is the arrival time at the target.
+pub struct ReversedPotential<P>(pub P);
+
+impl<P: TDPotential> TDPotential for ReversedPotential<P> {
+    fn init(&mut self, source: NodeId, target: NodeId, timestamp: Timestamp) {
+        self.0.init(target, source, timestamp)
+    }
+
+    fn potential(&mut self, node: NodeId, timestamp: Timestamp) -> Option<Weight> {
+        self.0.potential(node, timestamp)
+    }
+
+    fn potential_batch(&mut self, nodes: &[NodeId], timestamps: &[Timestamp], potentials: &mut [Option<Weight>]) {
+        self.0.potential_batch(nodes, timestamps, potentials)
+    }
+
+    fn verify_result(&self, distance: Weight) -> bool {
+        self.0.verify_result(distance)
+    }
+
+    fn num_computations(&self) -> Option<usize> {
+        self.0.num_computations()
+    }
+}
+
 // additional helper functions
 
 /// basic conversion: `CapacityGraph` uses integer weights, but we rely on floats here
@@ -1,37 +1,66 @@
-use crate::dijkstra::potentials::cch_parallelization_util::SeparatorBasedParallelCustomization;
-use crate::dijkstra::potentials::multi_metric_potential::metric_reduction::{reduce_metrics, MetricEntry};
+use crate::dijkstra::potentials::cch_parallelization_util::{
+    CancellationToken, CustomizationCancelled, SeparatorBasedParallelCustomization, SeparatorBasedParallelDirectedCustomization,
+    SeparatorBasedPerfectParallelCustomization,
+};
+use crate::dijkstra::potentials::multi_metric_potential::metric_reduction::{deduplicate_metrics, reduce_metrics, MetricEntry};
 use crate::dijkstra::potentials::multi_metric_potential::potential::MultiMetricPotentialContext;
+use crate::graph::access_restrictions::VehicleClass;
 use crate::graph::capacity_graph::CapacityGraph;
 use crate::graph::MAX_BUCKETS;
 use rayon::prelude::*;
-use rust_road_router::algo::customizable_contraction_hierarchy::{CCH, CCHT};
+use rust_road_router::algo::customizable_contraction_hierarchy::{DirectedCCH, CCH, CCHT};
 use rust_road_router::datastr::graph::time_dependent::{PiecewiseLinearFunction, TDGraph, Timestamp};
-use rust_road_router::datastr::graph::{EdgeId, EdgeIdT, Graph, LinkIterable, NodeId, NodeIdT, Reversed, UnweightedFirstOutGraph, Weight, INFINITY};
+use rust_road_router::datastr::graph::{
+    BuildReversed, EdgeId, EdgeIdT, Graph, LinkIterable, NodeId, NodeIdT, Reversed, ReversedGraphWithEdgeIds, UnweightedFirstOutGraph, Weight, INFINITY,
+};
+use rust_road_router::datastr::node_order::NodeOrder;
+use rust_road_router::datastr::timestamped_vector::Epoch;
+use rust_road_router::report::progress::{ignore_progress, ProgressCallback};
 use rust_road_router::report::{measure, report_time, report_time_with_key};
+use rust_road_router::util::in_range_option::InRangeOption;
+use rust_road_router::util::Vecs;
 use scoped_tls::scoped_thread_local;
 use std::cell::RefCell;
-use std::cmp::{max, min};
+use std::cmp::{max, min, Reverse};
+use std::collections::BinaryHeap;
 use std::ops::Range;
 
-// One mapping of node id to weight for each thread during the scope of the customization.
-scoped_thread_local!(static UPWARD_WORKSPACE: RefCell<Vec<Vec<Weight>>>);
-scoped_thread_local!(static DOWNWARD_WORKSPACE: RefCell<Vec<Vec<Weight>>>);
+// One mapping of node id to weights for each thread during the scope of the customization.
+// The weights of all metrics are stored in a single flat array, node `v` owns the entries `v * num_metrics..(v + 1) * num_metrics`.
+scoped_thread_local!(static UPWARD_WORKSPACE: RefCell<Vec<Weight>>);
+scoped_thread_local!(static DOWNWARD_WORKSPACE: RefCell<Vec<Weight>>);
+scoped_thread_local!(static PERFECT_WORKSPACE: RefCell<Vec<InRangeOption<EdgeId>>>);
 
 const LOWERBOUND_METRIC: usize = 0;
 const UPPERBOUND_METRIC: usize = 1;
-
-pub struct CustomizedMultiMetrics {
-    pub cch: CCH,
+// metrics deviating by less than this on every edge are already considered equal by the pairwise reduction
+const METRIC_DEDUPLICATION_TOLERANCE: Weight = 499;
+const WEIGHTS_PER_CACHE_LINE: usize = 64 / std::mem::size_of::<Weight>();
+
+/// Customized multi-metric data. Customization works on the undirected `CCH` as well as on a `DirectedCCH`,
+/// a customized `CCH` can also be converted into a `DirectedCCH` with `into_directed`.
+/// The epoch width of the per-query labels can be narrowed with `with_epochs`.
+///
+/// The customized weights are interleaved: the weights of all metrics of a shortcut are stored contiguously,
+/// shortcut `e` owns the block `e * metric_stride(num_metrics)..(e + 1) * metric_stride(num_metrics)`.
+/// Blocks are padded with `INFINITY`, so they never straddle a cache line.
+pub struct CustomizedMultiMetrics<C = CCH, E: Epoch = u32> {
+    pub cch: C,
     pub upward: Vec<Weight>,
     pub downward: Vec<Weight>,
     pub metric_entries: Vec<MetricEntry>,
     pub num_metrics: usize,
 
-    pub potential_context: MultiMetricPotentialContext,
+    pub potential_context: MultiMetricPotentialContext<E>,
     pub forward_cch_bounds: Vec<(Weight, Weight)>,
     pub backward_cch_bounds: Vec<(Weight, Weight)>,
     pub orig_edge_to_forward_shortcut: Vec<Option<EdgeId>>,
     pub orig_edge_to_backward_shortcut: Vec<Option<EdgeId>>,
+
+    // upper limit (in bytes) for metric extraction and customization
+    memory_budget: Option<usize>,
+    // customize only the edges available to this vehicle class, see `set_vehicle_class`
+    vehicle_class: Option<VehicleClass>,
 }
 
 impl CustomizedMultiMetrics {
@@ -39,11 +68,78 @@ impl CustomizedMultiMetrics {
         debug_assert!(!intervals.is_empty(), "Intervals must not be empty!");
 
         let mut ret = Self::empty(cch);
-        ret.customize_internal(graph.departure(), graph.travel_time(), intervals, num_max_metrics, true);
+        ret.customize_internal(graph.departure(), graph.travel_time(), intervals, num_max_metrics, true, &ignore_progress, None)
+            .unwrap();
+        ret
+    }
+
+    /// Same as `new_from_capacity`, but only the edges available to vehicles of `vehicle_class` are customized (see `set_vehicle_class`)
+    pub fn new_from_capacity_for_class(
+        cch: CCH,
+        graph: &CapacityGraph,
+        vehicle_class: VehicleClass,
+        intervals: &Vec<(Timestamp, Timestamp)>,
+        num_max_metrics: usize,
+    ) -> Self {
+        debug_assert!(!intervals.is_empty(), "Intervals must not be empty!");
+
+        let mut ret = Self::empty(cch);
+        ret.set_vehicle_class(Some(vehicle_class));
+        ret.customize(graph, intervals, num_max_metrics);
+        ret
+    }
+
+    /// Same as `new_from_capacity`, but the interval resolution and number of metrics are adjusted to stay within the given memory budget (in bytes)
+    pub fn new_from_capacity_with_memory_budget(
+        cch: CCH,
+        graph: &CapacityGraph,
+        intervals: &Vec<(Timestamp, Timestamp)>,
+        num_max_metrics: usize,
+        memory_budget: usize,
+    ) -> Self {
+        debug_assert!(!intervals.is_empty(), "Intervals must not be empty!");
+
+        let mut ret = Self::empty(cch);
+        ret.set_memory_budget(Some(memory_budget));
+        ret.customize_internal(graph.departure(), graph.travel_time(), intervals, num_max_metrics, true, &ignore_progress, None)
+            .unwrap();
         ret
     }
 
     pub fn new_from_ptv(cch: CCH, graph: &TDGraph, intervals: &Vec<(Timestamp, Timestamp)>, num_max_metrics: usize) -> Self {
+        Self::new_from_ptv_with_progress(cch, graph, intervals, num_max_metrics, &ignore_progress)
+    }
+
+    /// Same as `new_from_ptv`, the progress of the basic customization is reported to the given callback
+    pub fn new_from_ptv_with_progress(
+        cch: CCH,
+        graph: &TDGraph,
+        intervals: &Vec<(Timestamp, Timestamp)>,
+        num_max_metrics: usize,
+        progress: ProgressCallback,
+    ) -> Self {
+        Self::new_from_ptv_internal(cch, graph, intervals, num_max_metrics, progress, None)
+    }
+
+    /// Same as `new_from_ptv`, but the interval resolution and number of metrics are adjusted to stay within the given memory budget (in bytes)
+    pub fn new_from_ptv_with_memory_budget(
+        cch: CCH,
+        graph: &TDGraph,
+        intervals: &Vec<(Timestamp, Timestamp)>,
+        num_max_metrics: usize,
+        memory_budget: usize,
+    ) -> Self {
+        Self::new_from_ptv_internal(cch, graph, intervals, num_max_metrics, &ignore_progress, Some(memory_budget))
+    }
+
+    fn new_from_ptv_internal(
+        cch: CCH,
+        graph: &TDGraph,
+        intervals: &Vec<(Timestamp, Timestamp)>,
+        num_max_metrics: usize,
+        progress: ProgressCallback,
+        memory_budget: Option<usize>,
+    ) -> Self {
         debug_assert!(!intervals.is_empty(), "Intervals must not be empty!");
 
         // extract departures and travel times from the graph
@@ -68,7 +164,9 @@ impl CustomizedMultiMetrics {
             .unzip();
 
         let mut ret = Self::empty(cch);
-        ret.customize_internal(&departures, &travel_times, intervals, num_max_metrics, false);
+        ret.set_memory_budget(memory_budget);
+        ret.customize_internal(&departures, &travel_times, intervals, num_max_metrics, false, progress, None)
+            .unwrap();
         ret
     }
 
@@ -86,25 +184,18 @@ impl CustomizedMultiMetrics {
             backward_cch_bounds: vec![],
             orig_edge_to_forward_shortcut: vec![],
             orig_edge_to_backward_shortcut: vec![],
+            memory_budget: None,
+            vehicle_class: None,
         }
     }
 
     pub fn restore(cch: CCH, upward: Vec<Weight>, downward: Vec<Weight>, metric_entries: Vec<MetricEntry>, num_metrics: usize, num_orig_edges: usize) -> Self {
-        let m = cch.num_arcs();
-
-        let forward_cch_bounds = upward[..m]
-            .iter()
-            .zip(upward[m..2 * m].iter())
-            .map(|(&lower, &upper)| (lower, upper))
-            .collect::<Vec<(Weight, Weight)>>();
-
-        let backward_cch_bounds = downward[..m]
-            .iter()
-            .zip(downward[m..2 * m].iter())
-            .map(|(&lower, &upper)| (lower, upper))
-            .collect::<Vec<(Weight, Weight)>>();
+        debug_assert_eq!(upward.len(), cch.num_arcs() * metric_stride(num_metrics));
+        let forward_cch_bounds = extract_bounds(&upward, num_metrics);
+        let backward_cch_bounds = extract_bounds(&downward, num_metrics);
 
-        let (orig_edge_to_forward_shortcut, orig_edge_to_backward_shortcut) = retrieve_orig_edge_to_shortcut_mapping(&cch, num_orig_edges);
+        let (orig_edge_to_forward_shortcut, orig_edge_to_backward_shortcut) =
+            retrieve_orig_edge_to_shortcut_mapping(&cch.forward_cch_edge_to_orig_arc, &cch.backward_cch_edge_to_orig_arc, num_orig_edges);
 
         let num_nodes = cch.num_nodes();
         Self {
@@ -118,8 +209,24 @@ impl CustomizedMultiMetrics {
             backward_cch_bounds,
             orig_edge_to_forward_shortcut,
             orig_edge_to_backward_shortcut,
+            memory_budget: None,
+            vehicle_class: None,
         }
     }
+}
+
+impl<E: Epoch> CustomizedMultiMetrics<CCH, E> {
+    /// Limit the memory used by subsequent customizations. If the estimated memory exceeds the budget,
+    /// the interval resolution is coarsened and, if this doesn't suffice, the number of metrics is reduced.
+    pub fn set_memory_budget(&mut self, memory_budget: Option<usize>) {
+        self.memory_budget = memory_budget;
+    }
+
+    /// Customize only the edges available to `vehicle_class` from the next customization on, e.g. to route general traffic
+    /// around bus lanes. The resulting potential is only admissible for classes which may use at least these edges.
+    pub fn set_vehicle_class(&mut self, vehicle_class: Option<VehicleClass>) {
+        self.vehicle_class = vehicle_class;
+    }
 
     fn customize_internal(
         &mut self,
@@ -128,76 +235,117 @@ impl CustomizedMultiMetrics {
         intervals: &Vec<(Timestamp, Timestamp)>,
         num_max_metrics: usize,
         cooperative: bool,
-    ) {
+        progress: ProgressCallback,
+        cancellation: Option<&CancellationToken>,
+    ) -> Result<(), CustomizationCancelled> {
         assert!(num_max_metrics >= 1, "At least one metric (lowerbound) must be kept!");
         let m = self.cch.num_arcs();
 
-        // 1. build metrics
-        self.metric_entries = build_metric_entries(intervals);
-
-        // 2. extract metrics
-        let (mut metrics, time) = measure(|| extract_metrics(departures, travel_times, &self.metric_entries));
-        println!("Extracting all metrics took {} ms", time.as_secs_f64() * 1000.0);
+        // 0. stay within the memory budget
+        let (intervals, num_max_metrics) = match self.memory_budget {
+            Some(memory_budget) => fit_to_memory_budget(&self.cch, departures.len(), intervals, num_max_metrics, memory_budget),
+            None => (intervals.clone(), num_max_metrics),
+        };
 
-        // 3. reduce the number of metrics by merging similar intervals
-        let (num_metrics, time) = measure(|| reduce_metrics(&mut metrics, &mut self.metric_entries, num_max_metrics, !cooperative));
-        println!("Reducing to {} metrics took {} ms", num_metrics, time.as_secs_f64() * 1000.0);
-        self.num_metrics = num_metrics;
+        // 1.-3. build, extract and reduce metrics
+        let (metrics, metric_entries, num_metrics) = extract_reduced_metrics(departures, travel_times, &intervals, num_max_metrics, cooperative);
 
         // these will contain our customized shortcuts
-        let mut upward_weights = vec![vec![INFINITY; num_metrics]; m];
-        let mut downward_weights = vec![vec![INFINITY; num_metrics]; m];
+        let mut upward_weights = vec![INFINITY; m * num_metrics];
+        let mut downward_weights = vec![INFINITY; m * num_metrics];
 
         // 4. initialize upward and downward weights with correct lower/upper bound
-        prepare_weights(&self.cch, &mut upward_weights, &mut downward_weights, &metrics);
-
-        // 5. run basic customization
-        customize_basic(&self.cch, &mut upward_weights, &mut downward_weights);
-
-        // 6. reorder weights, scale upper bounds graceful for cooperative graphs
-        self.upward = reorder_weights(&upward_weights, num_metrics, cooperative);
-        drop(upward_weights);
-        self.downward = reorder_weights(&downward_weights, num_metrics, cooperative);
-        drop(downward_weights);
-
-        // 7. initialize additional structs required for potential
-        self.forward_cch_bounds = self.upward[..m]
-            .iter()
-            .zip(self.upward[m..2 * m].iter())
-            .map(|(&lower, &upper)| (lower, upper))
-            .collect::<Vec<(Weight, Weight)>>();
-
-        self.backward_cch_bounds = self.downward[..m]
-            .iter()
-            .zip(self.downward[m..2 * m].iter())
-            .map(|(&lower, &upper)| (lower, upper))
-            .collect::<Vec<(Weight, Weight)>>();
+        prepare_weights(
+            &self.cch.forward_cch_edge_to_orig_arc,
+            &self.cch.backward_cch_edge_to_orig_arc,
+            &mut upward_weights,
+            &mut downward_weights,
+            &metrics,
+            num_metrics,
+        );
+        drop(metrics);
+
+        // 5. run basic customization, keep the previous state if it was cancelled in the meantime
+        customize_basic(&self.cch, &mut upward_weights, &mut downward_weights, num_metrics, progress, cancellation);
+        if cancellation.map(|token| token.is_cancelled()).unwrap_or(false) {
+            println!("Customization cancelled, keeping the previous metrics");
+            return Err(CustomizationCancelled);
+        }
 
-        let (orig_edge_to_forward_shortcut, orig_edge_to_backward_shortcut) = retrieve_orig_edge_to_shortcut_mapping(&self.cch, departures.len());
+        // 6.-7. reorder weights and initialize additional structs required for potential
+        let (orig_edge_to_forward_shortcut, orig_edge_to_backward_shortcut) = retrieve_orig_edge_to_shortcut_mapping(
+            &self.cch.forward_cch_edge_to_orig_arc,
+            &self.cch.backward_cch_edge_to_orig_arc,
+            departures.len(),
+        );
+        self.store_customized_weights(upward_weights, downward_weights, metric_entries, num_metrics, cooperative);
         self.orig_edge_to_forward_shortcut = orig_edge_to_forward_shortcut;
         self.orig_edge_to_backward_shortcut = orig_edge_to_backward_shortcut;
+        Ok(())
     }
 
     pub fn customize(&mut self, graph: &CapacityGraph, intervals: &Vec<(Timestamp, Timestamp)>, num_max_metrics: usize) {
-        self.customize_internal(graph.departure(), graph.travel_time(), intervals, num_max_metrics, true);
+        self.customize_with_progress(graph, intervals, num_max_metrics, &ignore_progress);
+    }
+
+    /// Same as `customize`, the progress of the basic customization is reported to the given callback
+    pub fn customize_with_progress(
+        &mut self,
+        graph: &CapacityGraph,
+        intervals: &Vec<(Timestamp, Timestamp)>,
+        num_max_metrics: usize,
+        progress: ProgressCallback,
+    ) {
+        let travel_times = graph.class_travel_times(self.vehicle_class);
+        self.customize_internal(graph.departure(), &travel_times, intervals, num_max_metrics, true, progress, None)
+            .unwrap();
+    }
+
+    /// Same as `customize`, but aborts as soon as the given token is cancelled.
+    /// In this case, the previous customization is kept.
+    pub fn customize_cancellable(
+        &mut self,
+        graph: &CapacityGraph,
+        intervals: &Vec<(Timestamp, Timestamp)>,
+        num_max_metrics: usize,
+        cancellation: &CancellationToken,
+    ) -> Result<(), CustomizationCancelled> {
+        let travel_times = graph.class_travel_times(self.vehicle_class);
+        self.customize_internal(
+            graph.departure(),
+            &travel_times,
+            intervals,
+            num_max_metrics,
+            true,
+            &ignore_progress,
+            Some(cancellation),
+        )
     }
 
     pub fn customize_upper_bound(&mut self, graph: &CapacityGraph) {
+        let travel_times = graph.class_travel_times(self.vehicle_class);
         let upper_bound = (0..graph.num_arcs())
             .into_iter()
-            .map(|e| vec![*graph.travel_time()[e].iter().max().unwrap()])
+            .map(|e| vec![*travel_times[e].iter().max().unwrap()])
             .collect::<Vec<Vec<Weight>>>();
 
-        let mut upwards = vec![vec![INFINITY; 1]; self.cch.num_arcs()];
-        let mut downwards = vec![vec![INFINITY; 1]; self.cch.num_arcs()];
+        let mut upwards = vec![INFINITY; self.cch.num_arcs()];
+        let mut downwards = vec![INFINITY; self.cch.num_arcs()];
 
         // run customization on upper bounds
-        prepare_weights(&self.cch, &mut upwards, &mut downwards, &upper_bound);
-        customize_basic(&self.cch, &mut upwards, &mut downwards);
+        prepare_weights(
+            &self.cch.forward_cch_edge_to_orig_arc,
+            &self.cch.backward_cch_edge_to_orig_arc,
+            &mut upwards,
+            &mut downwards,
+            &upper_bound,
+            1,
+        );
+        customize_basic(&self.cch, &mut upwards, &mut downwards, 1, &ignore_progress, None);
 
         // scale upper bounds
-        let upwards = upwards.iter().map(|v| min(INFINITY, max((v[0] / 2) * 3, 1))).collect::<Vec<Weight>>();
-        let downwards = downwards.iter().map(|v| min(INFINITY, max((v[0] / 2) * 3, 1))).collect::<Vec<Weight>>();
+        let upwards = upwards.iter().map(|&v| scale_upper_bound(v)).collect::<Vec<Weight>>();
+        let downwards = downwards.iter().map(|&v| scale_upper_bound(v)).collect::<Vec<Weight>>();
 
         // update bound entries
         self.forward_cch_bounds
@@ -210,6 +358,379 @@ impl CustomizedMultiMetrics {
             .for_each(|((_, upper), new_upper)| *upper = *new_upper);
     }
 
+    /// Targeted alternative to `customize_upper_bound` after a few original edges exceeded their customized upper bounds:
+    /// the shortcuts which directly represent these edges are raised to the (scaled) current maximum travel time of the edge,
+    /// the raise is then propagated bottom-up through the upper triangles of all affected shortcuts.
+    /// Only nodes with a changed lower shortcut are revisited, the bounds are never lowered.
+    ///
+    /// Returns the number of adjusted shortcuts
+    pub fn repair_upper_bounds(&mut self, graph: &CapacityGraph, edges: &[EdgeId]) -> usize {
+        let travel_times = graph.class_travel_times(self.vehicle_class);
+        let edge_upper_bound = |orig_arcs: &[EdgeIdT]| {
+            orig_arcs
+                .iter()
+                .map(|&EdgeIdT(arc)| scale_upper_bound(*travel_times[arc as usize].iter().max().unwrap()))
+                .min()
+                .unwrap_or(INFINITY)
+        };
+
+        let n = self.cch.num_nodes();
+        let mut queue = BinaryHeap::new();
+        let mut queued = vec![false; n];
+        let mut enqueue = |queue: &mut BinaryHeap<Reverse<NodeId>>, node: NodeId| {
+            if !queued[node as usize] {
+                queued[node as usize] = true;
+                queue.push(Reverse(node));
+            }
+        };
+
+        // start at the lower nodes of the shortcuts which directly represent the violating edges
+        for &edge_id in edges {
+            for shortcut_id in self.orig_edge_to_forward_shortcut[edge_id as usize]
+                .into_iter()
+                .chain(self.orig_edge_to_backward_shortcut[edge_id as usize])
+            {
+                let tail = self.cch.edge_id_to_tail(shortcut_id);
+                enqueue(&mut queue, tail);
+            }
+        }
+
+        let mut outgoing = vec![INFINITY; n];
+        let mut incoming = vec![INFINITY; n];
+        let mut num_repaired = 0;
+
+        // nodes are processed in ascending rank, so all lower triangles of a shortcut are final when it is recomputed
+        while let Some(Reverse(current_node)) = queue.pop() {
+            let shortcuts = self.cch.neighbor_edge_indices_usize(current_node);
+            for (node, edge) in self.cch.neighbor_iter(current_node).zip(shortcuts.clone()) {
+                outgoing[node as usize] = edge_upper_bound(&self.cch.forward_cch_edge_to_orig_arc[edge]);
+                incoming[node as usize] = edge_upper_bound(&self.cch.backward_cch_edge_to_orig_arc[edge]);
+            }
+
+            for (NodeIdT(low_node), Reversed(EdgeIdT(first_edge_id))) in self.cch.inverted.link_iter(current_node) {
+                let (_, first_up) = self.forward_cch_bounds[first_edge_id as usize];
+                let (_, first_down) = self.backward_cch_bounds[first_edge_id as usize];
+                let low_up_edges = self.cch.neighbor_edge_indices_usize(low_node);
+                for (node, edge) in self.cch.neighbor_iter(low_node).rev().zip(low_up_edges.rev()) {
+                    if node <= current_node {
+                        break;
+                    }
+
+                    let node = node as usize;
+                    outgoing[node] = min(outgoing[node], min(INFINITY, self.forward_cch_bounds[edge].1 + first_down));
+                    incoming[node] = min(incoming[node], min(INFINITY, self.backward_cch_bounds[edge].1 + first_up));
+                }
+            }
+
+            let mut changed = false;
+            for (node, edge) in self.cch.neighbor_iter(current_node).zip(shortcuts) {
+                let node = node as usize;
+                for (upper, new_upper) in [
+                    (&mut self.forward_cch_bounds[edge].1, outgoing[node]),
+                    (&mut self.backward_cch_bounds[edge].1, incoming[node]),
+                ] {
+                    if *upper < new_upper {
+                        *upper = new_upper;
+                        num_repaired += 1;
+                        changed = true;
+                    }
+                }
+                outgoing[node] = INFINITY;
+                incoming[node] = INFINITY;
+            }
+
+            // a changed shortcut is a lower edge of the triangles of all other shortcuts of this node
+            if changed {
+                for node in self.cch.neighbor_iter(current_node) {
+                    enqueue(&mut queue, node);
+                }
+            }
+        }
+
+        num_repaired
+    }
+
+    /// Convert into a `DirectedCCH`, all shortcuts which are infinite in every metric are removed.
+    /// This is considerably leaner for turn-expanded graphs. The result can be re-customized with the directed `customize`.
+    pub fn into_directed(self) -> CustomizedMultiMetrics<DirectedCCH, E> {
+        // remove all shortcuts with infinite lowerbound, they are infinite in all other metrics as well
+        let keep_forward = self.forward_cch_bounds.iter().map(|&(lower, _)| lower < INFINITY).collect::<Vec<bool>>();
+        let keep_backward = self.backward_cch_bounds.iter().map(|&(lower, _)| lower < INFINITY).collect::<Vec<bool>>();
+        self.retain_shortcuts(&keep_forward, &keep_backward)
+    }
+
+    /// Perfect customization of all metrics, afterwards all shortcuts that are not required by any metric are removed.
+    /// A shortcut is dropped if it is infinite or dominated by a path over upper or intermediate triangles in every metric,
+    /// the remaining shortcuts carry the exact distances of each metric.
+    /// This shrinks the search graphs of the elimination tree walks. The result can't be re-customized, see `PerfectCCH`.
+    pub fn into_perfect(mut self) -> CustomizedMultiMetrics<PerfectCCH, E> {
+        let m = self.cch.num_arcs();
+        let k = self.num_metrics;
+        let stride = metric_stride(k);
+        let upward_orig = self.upward.clone();
+        let downward_orig = self.downward.clone();
+
+        customize_perfect(&self.cch, &mut self.upward, &mut self.downward, k);
+
+        // keep a shortcut if it is still required in at least one metric
+        let required = |perfect: &Vec<Weight>, orig: &Vec<Weight>, edge_id: usize| {
+            (0..k).any(|metric| {
+                let idx = edge_id * stride + metric;
+                perfect[idx] < INFINITY && perfect[idx] >= orig[idx]
+            })
+        };
+        let keep_forward = (0..m).map(|edge_id| required(&self.upward, &upward_orig, edge_id)).collect::<Vec<bool>>();
+        let keep_backward = (0..m).map(|edge_id| required(&self.downward, &downward_orig, edge_id)).collect::<Vec<bool>>();
+        drop(upward_orig);
+        drop(downward_orig);
+
+        // the bounds have to match the perfect weights
+        self.forward_cch_bounds = extract_bounds(&self.upward, k);
+        self.backward_cch_bounds = extract_bounds(&self.downward, k);
+
+        let directed = self.retain_shortcuts(&keep_forward, &keep_backward);
+        CustomizedMultiMetrics {
+            cch: PerfectCCH(directed.cch),
+            upward: directed.upward,
+            downward: directed.downward,
+            metric_entries: directed.metric_entries,
+            num_metrics: directed.num_metrics,
+            potential_context: directed.potential_context,
+            forward_cch_bounds: directed.forward_cch_bounds,
+            backward_cch_bounds: directed.backward_cch_bounds,
+            orig_edge_to_forward_shortcut: directed.orig_edge_to_forward_shortcut,
+            orig_edge_to_backward_shortcut: directed.orig_edge_to_backward_shortcut,
+            memory_budget: directed.memory_budget,
+            vehicle_class: directed.vehicle_class,
+        }
+    }
+
+    /// build a `DirectedCCH` that only contains the given forward and backward shortcuts
+    fn retain_shortcuts(self, keep_forward: &[bool], keep_backward: &[bool]) -> CustomizedMultiMetrics<DirectedCCH, E> {
+        // 1. prune the forward and backward graph
+        let (forward_first_out, forward_head, forward_edge_ids) = prune_edges(self.cch.forward_first_out(), self.cch.forward_head(), keep_forward);
+        let (backward_first_out, backward_head, backward_edge_ids) = prune_edges(self.cch.backward_first_out(), self.cch.backward_head(), keep_backward);
+        println!(
+            "Directed CCH: Removed {} of {} forward and {} of {} backward edges.",
+            self.cch.num_arcs() - forward_head.len(),
+            self.cch.num_arcs(),
+            self.cch.num_arcs() - backward_head.len(),
+            self.cch.num_arcs()
+        );
+
+        // 2. remap weights, bounds and shortcut mappings to the new edge ids
+        let stride = metric_stride(self.num_metrics);
+        let upward = retain_edge_blocks(&self.upward, stride, &forward_edge_ids);
+        let downward = retain_edge_blocks(&self.downward, stride, &backward_edge_ids);
+        let forward_cch_bounds = retain_edges(&self.forward_cch_bounds, &forward_edge_ids);
+        let backward_cch_bounds = retain_edges(&self.backward_cch_bounds, &backward_edge_ids);
+
+        let orig_edge_to_forward_shortcut = self
+            .orig_edge_to_forward_shortcut
+            .iter()
+            .map(|shortcut| shortcut.and_then(|edge_id| forward_edge_ids[edge_id as usize]))
+            .collect();
+        let orig_edge_to_backward_shortcut = self
+            .orig_edge_to_backward_shortcut
+            .iter()
+            .map(|shortcut| shortcut.and_then(|edge_id| backward_edge_ids[edge_id as usize]))
+            .collect();
+
+        let forward_cch_edge_to_orig_arc = retain_edges(
+            &self.cch.forward_cch_edge_to_orig_arc.iter().map(|arcs| arcs.to_vec()).collect::<Vec<_>>(),
+            &forward_edge_ids,
+        );
+        let backward_cch_edge_to_orig_arc = retain_edges(
+            &self.cch.backward_cch_edge_to_orig_arc.iter().map(|arcs| arcs.to_vec()).collect::<Vec<_>>(),
+            &backward_edge_ids,
+        );
+
+        // 3. build directed cch
+        let forward_inverted = ReversedGraphWithEdgeIds::reversed(&UnweightedFirstOutGraph::new(&forward_first_out[..], &forward_head[..]));
+        let backward_inverted = ReversedGraphWithEdgeIds::reversed(&UnweightedFirstOutGraph::new(&backward_first_out[..], &backward_head[..]));
+
+        let cch = DirectedCCH::new(
+            forward_first_out,
+            forward_head,
+            backward_first_out,
+            backward_head,
+            self.cch.node_order().clone(),
+            forward_cch_edge_to_orig_arc,
+            backward_cch_edge_to_orig_arc,
+            self.cch.elimination_tree().to_vec(),
+            forward_inverted,
+            backward_inverted,
+        );
+
+        CustomizedMultiMetrics {
+            cch,
+            upward,
+            downward,
+            metric_entries: self.metric_entries,
+            num_metrics: self.num_metrics,
+            potential_context: self.potential_context,
+            forward_cch_bounds,
+            backward_cch_bounds,
+            orig_edge_to_forward_shortcut,
+            orig_edge_to_backward_shortcut,
+            memory_budget: self.memory_budget,
+            vehicle_class: self.vehicle_class,
+        }
+    }
+}
+
+/// `DirectedCCH` of a perfectly customized potential, see `CustomizedMultiMetrics::into_perfect`.
+/// A shortcut is only kept if it is required by the metrics of the perfect customization, other metrics may need the removed ones.
+/// Therefore, no customization is available for this type.
+///
+/// Only use it with static travel times, e.g. in a `PTVQueryServer`. A cooperative `CapacityServer` accepts it as well,
+/// but can't recover once its updates violate the customized upper bounds: `result_valid` and `update_valid` turn false
+/// and neither `restore_potential` nor `customize_upper_bound` exist for this type, so all further queries would have to be repeated without potential.
+pub struct PerfectCCH(DirectedCCH);
+
+impl CCHT for PerfectCCH {
+    fn forward_first_out(&self) -> &[EdgeId] {
+        self.0.forward_first_out()
+    }
+    fn backward_first_out(&self) -> &[EdgeId] {
+        self.0.backward_first_out()
+    }
+    fn forward_head(&self) -> &[NodeId] {
+        self.0.forward_head()
+    }
+    fn backward_head(&self) -> &[NodeId] {
+        self.0.backward_head()
+    }
+    fn forward_inverted(&self) -> &ReversedGraphWithEdgeIds {
+        self.0.forward_inverted()
+    }
+    fn backward_inverted(&self) -> &ReversedGraphWithEdgeIds {
+        self.0.backward_inverted()
+    }
+    fn forward_cch_edge_to_orig_arc(&self) -> &Vecs<EdgeIdT> {
+        self.0.forward_cch_edge_to_orig_arc()
+    }
+    fn backward_cch_edge_to_orig_arc(&self) -> &Vecs<EdgeIdT> {
+        self.0.backward_cch_edge_to_orig_arc()
+    }
+
+    fn node_order(&self) -> &NodeOrder {
+        self.0.node_order()
+    }
+
+    fn elimination_tree(&self) -> &[InRangeOption<NodeId>] {
+        self.0.elimination_tree()
+    }
+}
+
+impl CustomizedMultiMetrics<DirectedCCH> {
+    /// Same as `new_from_capacity`, but customizes a `DirectedCCH`, i.e. forward and backward shortcuts are handled separately
+    pub fn new_from_capacity_directed(cch: DirectedCCH, graph: &CapacityGraph, intervals: &Vec<(Timestamp, Timestamp)>, num_max_metrics: usize) -> Self {
+        debug_assert!(!intervals.is_empty(), "Intervals must not be empty!");
+        let num_nodes = cch.num_nodes();
+
+        let mut ret = Self {
+            cch,
+            upward: vec![],
+            downward: vec![],
+            metric_entries: vec![],
+            num_metrics: 0,
+            potential_context: MultiMetricPotentialContext::new(num_nodes),
+            forward_cch_bounds: vec![],
+            backward_cch_bounds: vec![],
+            orig_edge_to_forward_shortcut: vec![],
+            orig_edge_to_backward_shortcut: vec![],
+            memory_budget: None,
+            vehicle_class: None,
+        };
+        ret.customize(graph, intervals, num_max_metrics);
+        ret
+    }
+}
+
+impl<E: Epoch> CustomizedMultiMetrics<DirectedCCH, E> {
+    /// Directed multi-metric customization, e.g. for asymmetric congestion on both directions of a road.
+    /// Shortcuts that were removed by `into_directed` stay removed, they are infinite in every metric.
+    pub fn customize(&mut self, graph: &CapacityGraph, intervals: &Vec<(Timestamp, Timestamp)>, num_max_metrics: usize) {
+        assert!(num_max_metrics >= 1, "At least one metric (lowerbound) must be kept!");
+
+        // 1.-3. build, extract and reduce metrics
+        let travel_times = graph.class_travel_times(self.vehicle_class);
+        let (metrics, metric_entries, num_metrics) = extract_reduced_metrics(graph.departure(), &travel_times, intervals, num_max_metrics, true);
+        drop(travel_times);
+
+        // 4. initialize forward and backward weights separately, both directions may have a different number of shortcuts
+        let mut upward_weights = vec![INFINITY; self.cch.forward_head().len() * num_metrics];
+        let mut downward_weights = vec![INFINITY; self.cch.backward_head().len() * num_metrics];
+        prepare_weights(
+            self.cch.forward_cch_edge_to_orig_arc(),
+            self.cch.backward_cch_edge_to_orig_arc(),
+            &mut upward_weights,
+            &mut downward_weights,
+            &metrics,
+            num_metrics,
+        );
+        drop(metrics);
+
+        // 5. run directed customization
+        customize_directed_basic(&self.cch, &mut upward_weights, &mut downward_weights, num_metrics);
+
+        // 6.-7. reorder weights and initialize additional structs required for potential
+        let (orig_edge_to_forward_shortcut, orig_edge_to_backward_shortcut) = retrieve_orig_edge_to_shortcut_mapping(
+            self.cch.forward_cch_edge_to_orig_arc(),
+            self.cch.backward_cch_edge_to_orig_arc(),
+            graph.num_arcs(),
+        );
+        self.store_customized_weights(upward_weights, downward_weights, metric_entries, num_metrics, true);
+        self.orig_edge_to_forward_shortcut = orig_edge_to_forward_shortcut;
+        self.orig_edge_to_backward_shortcut = orig_edge_to_backward_shortcut;
+    }
+}
+
+impl<C: CCHT, E: Epoch> CustomizedMultiMetrics<C, E> {
+    /// reorder the customized weights into the potential layout and derive the lower/upper bounds of all shortcuts
+    fn store_customized_weights(
+        &mut self,
+        upward_weights: Vec<Weight>,
+        downward_weights: Vec<Weight>,
+        metric_entries: Vec<MetricEntry>,
+        num_metrics: usize,
+        cooperative: bool,
+    ) {
+        self.potential_context.invalidate();
+        self.metric_entries = metric_entries;
+        self.num_metrics = num_metrics;
+
+        // pad weights to the interleaved layout, scale upper bounds graceful for cooperative graphs
+        self.upward = interleave_weights(&upward_weights, num_metrics, cooperative);
+        drop(upward_weights);
+        self.downward = interleave_weights(&downward_weights, num_metrics, cooperative);
+        drop(downward_weights);
+
+        self.forward_cch_bounds = extract_bounds(&self.upward, num_metrics);
+        self.backward_cch_bounds = extract_bounds(&self.downward, num_metrics);
+    }
+
+    /// replace the per-query labels by ones with epochs of type `E2`, e.g. `u16` to save memory on large graphs
+    pub fn with_epochs<E2: Epoch>(self) -> CustomizedMultiMetrics<C, E2> {
+        let num_nodes = self.cch.node_order().len();
+
+        CustomizedMultiMetrics {
+            cch: self.cch,
+            upward: self.upward,
+            downward: self.downward,
+            metric_entries: self.metric_entries,
+            num_metrics: self.num_metrics,
+            potential_context: MultiMetricPotentialContext::new(num_nodes),
+            forward_cch_bounds: self.forward_cch_bounds,
+            backward_cch_bounds: self.backward_cch_bounds,
+            orig_edge_to_forward_shortcut: self.orig_edge_to_forward_shortcut,
+            orig_edge_to_backward_shortcut: self.orig_edge_to_backward_shortcut,
+            memory_budget: self.memory_budget,
+            vehicle_class: self.vehicle_class,
+        }
+    }
+
     pub fn forward_graph(&self) -> (UnweightedFirstOutGraph<&[EdgeId], &[NodeId]>, &Vec<Weight>) {
         (
             UnweightedFirstOutGraph::new(self.cch.forward_first_out(), self.cch.forward_head()),
@@ -224,13 +745,24 @@ impl CustomizedMultiMetrics {
         )
     }
 
-    pub fn decompose(self) -> CCH {
+    pub fn decompose(self) -> C {
         self.cch
     }
 }
 
 // subroutines
-fn build_metric_entries(intervals: &Vec<(Timestamp, Timestamp)>) -> Vec<MetricEntry> {
+/// number of entries per shortcut in the interleaved weight layout.
+/// Up to a cache line, blocks are padded to the next power of two, larger blocks to a multiple of the cache line.
+pub fn metric_stride(num_metrics: usize) -> usize {
+    if num_metrics <= WEIGHTS_PER_CACHE_LINE {
+        num_metrics.next_power_of_two()
+    } else {
+        (num_metrics + WEIGHTS_PER_CACHE_LINE - 1) / WEIGHTS_PER_CACHE_LINE * WEIGHTS_PER_CACHE_LINE
+    }
+}
+
+/// metric entries of the given intervals, preceded by the lowerbound metric over the whole day
+pub fn build_metric_entries(intervals: &Vec<(Timestamp, Timestamp)>) -> Vec<MetricEntry> {
     let mut ret = vec![MetricEntry::new(0, MAX_BUCKETS, LOWERBOUND_METRIC)];
 
     intervals.iter().enumerate().for_each(|(idx, &(start, end))| {
@@ -240,27 +772,135 @@ fn build_metric_entries(intervals: &Vec<(Timestamp, Timestamp)>) -> Vec<MetricEn
     ret
 }
 
-/// reorder weights, flatten the 2-dimensional vector into a single dimension
-/// data by metric and edge_id is found at index `metric * num_edges + edge_id`
-fn reorder_weights(weights: &Vec<Vec<Weight>>, num_metrics: usize, scale_upper_bound: bool) -> Vec<Weight> {
-    let mut ret = vec![0; weights.len() * num_metrics];
+/// build the metric entries of the given intervals, extract them from the travel time functions
+/// and merge similar intervals until at most `num_max_metrics` remain
+fn extract_reduced_metrics(
+    departures: &Vec<Vec<Timestamp>>,
+    travel_times: &Vec<Vec<Weight>>,
+    intervals: &Vec<(Timestamp, Timestamp)>,
+    num_max_metrics: usize,
+    cooperative: bool,
+) -> (Vec<Vec<Weight>>, Vec<MetricEntry>, usize) {
+    // 1. build metrics
+    let mut metric_entries = build_metric_entries(intervals);
+
+    // 2. extract metrics
+    let (mut metrics, time) = measure(|| extract_metrics(departures, travel_times, &metric_entries));
+    println!("Extracting all metrics took {} ms", time.as_secs_f64() * 1000.0);
+
+    // 3. merge (nearly) identical metrics right away, then reduce the number of metrics by merging similar intervals
+    // the cooperative setting does not pre-merge similar metrics, so only exact duplicates are shared there
+    let tolerance = if cooperative { 0 } else { METRIC_DEDUPLICATION_TOLERANCE };
+    let (num_deduplicated, time) = measure(|| deduplicate_metrics(&mut metrics, &mut metric_entries, tolerance));
+    println!("Deduplicating to {} metrics took {} ms", num_deduplicated, time.as_secs_f64() * 1000.0);
+    let (num_metrics, time) = measure(|| reduce_metrics(&mut metrics, &mut metric_entries, num_max_metrics, !cooperative));
+    println!("Reducing to {} metrics took {} ms", num_metrics, time.as_secs_f64() * 1000.0);
+
+    (metrics, metric_entries, num_metrics)
+}
+
+/// estimated peak memory (in bytes) of metric extraction and customization
+fn estimate_customization_memory(cch: &CCH, num_orig_edges: usize, num_intervals: usize, num_max_metrics: usize) -> usize {
+    let weight_size = std::mem::size_of::<Weight>();
+    // lower and upper bound are always kept
+    let num_metrics = min(num_max_metrics, num_intervals + 1) + 1;
+    let customized_weights = 2 * cch.num_arcs() * num_metrics * weight_size;
 
-    weights.iter().enumerate().for_each(|(edge_id, edge_weights)| {
-        edge_weights.iter().enumerate().for_each(|(metric_id, &val)| {
-            ret[metric_id * weights.len() + edge_id] = val;
-        });
-    });
+    // extracted metrics are dropped after the customized weights have been initialized
+    let extraction = num_orig_edges * (std::mem::size_of::<Vec<Weight>>() + (num_intervals + 3) * weight_size) + customized_weights;
+
+    // per-thread workspaces during the customization, an additional copy of one direction while reordering
+    let workspaces = 2 * rayon::current_num_threads() * cch.num_nodes() * num_metrics * weight_size;
+    let customization = max(customized_weights + workspaces, customized_weights * 3 / 2);
 
-    if scale_upper_bound {
-        for edge_id in 0..weights.len() {
-            ret[UPPERBOUND_METRIC * weights.len() + edge_id] = min(INFINITY, max((ret[UPPERBOUND_METRIC * weights.len() + edge_id] / 2) * 3, 1));
+    max(extraction, customization)
+}
+
+/// coarsen the interval resolution and reduce the number of metrics until the estimated memory fits into the budget
+fn fit_to_memory_budget(
+    cch: &CCH,
+    num_orig_edges: usize,
+    intervals: &Vec<(Timestamp, Timestamp)>,
+    num_max_metrics: usize,
+    memory_budget: usize,
+) -> (Vec<(Timestamp, Timestamp)>, usize) {
+    let mut intervals = intervals.clone();
+    let mut num_max_metrics = num_max_metrics;
+
+    // coarsen first: merged intervals still provide valid lower bounds, and the costly metric extraction scales with the number of intervals
+    while estimate_customization_memory(cch, num_orig_edges, intervals.len(), num_max_metrics) > memory_budget {
+        if intervals.len() > num_max_metrics {
+            intervals = coarsen_intervals(&intervals);
+        } else if num_max_metrics > 1 {
+            num_max_metrics -= 1;
+        } else {
+            println!(
+                "WARNING: Customization exceeds the memory budget of {} bytes even with a single metric",
+                memory_budget
+            );
+            break;
         }
     }
 
+    println!(
+        "Memory budget: using {} intervals and at most {} metrics (estimated {} of {} bytes)",
+        intervals.len(),
+        num_max_metrics,
+        estimate_customization_memory(cch, num_orig_edges, intervals.len(), num_max_metrics),
+        memory_budget
+    );
+    (intervals, num_max_metrics)
+}
+
+/// halve the interval resolution by merging pairs of consecutive intervals
+fn coarsen_intervals(intervals: &Vec<(Timestamp, Timestamp)>) -> Vec<(Timestamp, Timestamp)> {
+    let mut sorted = intervals.clone();
+    sorted.sort_unstable();
+
+    let mut ret = sorted
+        .chunks(2)
+        .map(|pair| {
+            (
+                pair.iter().map(|&(start, _)| start).min().unwrap(),
+                pair.iter().map(|&(_, end)| end).max().unwrap(),
+            )
+        })
+        .collect::<Vec<(Timestamp, Timestamp)>>();
+    ret.dedup();
     ret
 }
 
-fn extract_metrics(departures: &Vec<Vec<Timestamp>>, travel_times: &Vec<Vec<Weight>>, entries: &Vec<MetricEntry>) -> Vec<Vec<Weight>> {
+/// copy weights from the dense customization layout (`edge_id * num_metrics + metric`)
+/// into the padded potential layout: data by metric and edge_id is found at index `edge_id * metric_stride(num_metrics) + metric`,
+/// padding entries are set to `INFINITY`
+fn interleave_weights(weights: &Vec<Weight>, num_metrics: usize, scale_upper_bound: bool) -> Vec<Weight> {
+    let stride = metric_stride(num_metrics);
+    let num_edges = weights.len() / num_metrics;
+    let mut ret = vec![INFINITY; num_edges * stride];
+
+    ret.par_chunks_mut(stride)
+        .zip(weights.par_chunks(num_metrics))
+        .for_each(|(block, edge_weights)| {
+            block[..num_metrics].copy_from_slice(edge_weights);
+
+            if scale_upper_bound {
+                block[UPPERBOUND_METRIC] = min(INFINITY, max((block[UPPERBOUND_METRIC] / 2) * 3, 1));
+            }
+        });
+
+    ret
+}
+
+/// lower and upper bound of each shortcut, taken from the interleaved weights
+fn extract_bounds(weights: &[Weight], num_metrics: usize) -> Vec<(Weight, Weight)> {
+    weights
+        .chunks_exact(metric_stride(num_metrics))
+        .map(|block| (block[LOWERBOUND_METRIC], block[UPPERBOUND_METRIC]))
+        .collect()
+}
+
+/// interval minima of all travel time functions, one row of `entries.len() + 2` metrics per edge
+pub fn extract_metrics(departures: &Vec<Vec<Timestamp>>, travel_times: &Vec<Vec<Weight>>, entries: &Vec<MetricEntry>) -> Vec<Vec<Weight>> {
     let mut metrics = vec![vec![INFINITY; entries.len() + 2]; departures.len()];
 
     // collect the metrics edge by edge; this layout is also needed by the customization step
@@ -296,13 +936,20 @@ fn extract_metrics(departures: &Vec<Vec<Timestamp>>, travel_times: &Vec<Vec<Weig
     metrics
 }
 
-fn prepare_weights(cch: &CCH, upward_weights: &mut Vec<Vec<Weight>>, downward_weights: &mut Vec<Vec<Weight>>, metric: &Vec<Vec<Weight>>) {
+fn prepare_weights(
+    forward_orig_arcs: &Vecs<EdgeIdT>,
+    backward_orig_arcs: &Vecs<EdgeIdT>,
+    upward_weights: &mut Vec<Weight>,
+    downward_weights: &mut Vec<Weight>,
+    metric: &Vec<Vec<Weight>>,
+    num_metrics: usize,
+) {
     report_time("Apply weights", || {
         upward_weights
-            .par_iter_mut()
-            .zip(cch.forward_cch_edge_to_orig_arc.par_iter())
+            .par_chunks_mut(num_metrics)
+            .zip(forward_orig_arcs.par_iter())
             .for_each(|(upward, up_arcs)| {
-                for metric_idx in 0..upward.len() {
+                for metric_idx in 0..num_metrics {
                     for &EdgeIdT(up_arc) in up_arcs {
                         upward[metric_idx] = min(upward[metric_idx], metric[up_arc as usize][metric_idx]);
                     }
@@ -310,10 +957,10 @@ fn prepare_weights(cch: &CCH, upward_weights: &mut Vec<Vec<Weight>>, downward_we
             });
 
         downward_weights
-            .par_iter_mut()
-            .zip(cch.backward_cch_edge_to_orig_arc.par_iter())
+            .par_chunks_mut(num_metrics)
+            .zip(backward_orig_arcs.par_iter())
             .for_each(|(downward, down_arcs)| {
-                for metric_idx in 0..downward.len() {
+                for metric_idx in 0..num_metrics {
                     for &EdgeIdT(down_arc) in down_arcs {
                         downward[metric_idx] = min(downward[metric_idx], metric[down_arc as usize][metric_idx]);
                     }
@@ -322,11 +969,24 @@ fn prepare_weights(cch: &CCH, upward_weights: &mut Vec<Vec<Weight>>, downward_we
     });
 }
 
-fn customize_basic(cch: &CCH, upward_weights: &mut Vec<Vec<Weight>>, downward_weights: &mut Vec<Vec<Weight>>) {
-    let n = cch.num_nodes() as NodeId;
-    let num_metrics = upward_weights[0].len();
+/// upper bounds are relaxed by 50% to reduce the number of re-customizations
+fn scale_upper_bound(upper_bound: Weight) -> Weight {
+    min(INFINITY, max((upper_bound / 2) * 3, 1))
+}
 
-    let customize = |nodes: Range<usize>, offset: usize, upward_weights: &mut [Vec<Weight>], downward_weights: &mut [Vec<Weight>]| {
+/// customization on a flat weight layout: the `num_metrics` weights of edge `e` are stored at `e * num_metrics..(e + 1) * num_metrics`
+fn customize_basic(
+    cch: &CCH,
+    upward_weights: &mut Vec<Weight>,
+    downward_weights: &mut Vec<Weight>,
+    num_metrics: usize,
+    progress: ProgressCallback,
+    cancellation: Option<&CancellationToken>,
+) {
+    let n = cch.num_nodes();
+    let k = num_metrics;
+
+    let customize = |nodes: Range<usize>, offset: usize, upward_weights: &mut [Weight], downward_weights: &mut [Weight]| {
         UPWARD_WORKSPACE.with(|node_outgoing_weights| {
             let mut node_outgoing_weights = node_outgoing_weights.borrow_mut();
 
@@ -338,51 +998,44 @@ fn customize_basic(cch: &CCH, upward_weights: &mut Vec<Vec<Weight>>, downward_we
                     let mut edges = cch.neighbor_edge_indices_usize(current_node);
                     edges.start -= offset;
                     edges.end -= offset;
-                    for ((node, down), up) in cch
-                        .neighbor_iter(current_node)
-                        .zip(&downward_weights[edges.clone()])
-                        .zip(&upward_weights[edges.clone()])
-                    {
-                        node_incoming_weights[node as usize] = down.clone();
-                        node_outgoing_weights[node as usize] = up.clone();
+                    for (node, edge) in cch.neighbor_iter(current_node).zip(edges.clone()) {
+                        let node = node as usize;
+                        node_incoming_weights[node * k..(node + 1) * k].copy_from_slice(&downward_weights[edge * k..(edge + 1) * k]);
+                        node_outgoing_weights[node * k..(node + 1) * k].copy_from_slice(&upward_weights[edge * k..(edge + 1) * k]);
                     }
 
                     for (NodeIdT(low_node), Reversed(EdgeIdT(first_edge_id))) in cch.inverted.link_iter(current_node) {
-                        let first_down_weight = &downward_weights[first_edge_id as usize - offset];
-                        let first_up_weight = &upward_weights[first_edge_id as usize - offset];
+                        let first_edge_id = first_edge_id as usize - offset;
+                        let first_down_weight = &downward_weights[first_edge_id * k..(first_edge_id + 1) * k];
+                        let first_up_weight = &upward_weights[first_edge_id * k..(first_edge_id + 1) * k];
                         let mut low_up_edges = cch.neighbor_edge_indices_usize(low_node);
                         low_up_edges.start -= offset;
                         low_up_edges.end -= offset;
-                        for ((node, upward_weight), downward_weight) in cch
-                            .neighbor_iter(low_node)
-                            .rev()
-                            .zip(upward_weights[low_up_edges.clone()].iter().rev())
-                            .zip(downward_weights[low_up_edges].iter().rev())
-                        {
+                        for (node, edge) in cch.neighbor_iter(low_node).rev().zip(low_up_edges.rev()) {
                             if node <= current_node {
                                 break;
                             }
 
-                            let relax = unsafe { node_outgoing_weights.get_unchecked_mut(node as usize) };
-                            for i in 0..relax.len() {
+                            let node = node as usize;
+                            let upward_weight = &upward_weights[edge * k..(edge + 1) * k];
+                            let downward_weight = &downward_weights[edge * k..(edge + 1) * k];
+
+                            let relax = unsafe { node_outgoing_weights.get_unchecked_mut(node * k..(node + 1) * k) };
+                            for i in 0..k {
                                 relax[i] = min(relax[i], upward_weight[i] + first_down_weight[i]);
                             }
 
-                            let relax = unsafe { node_incoming_weights.get_unchecked_mut(node as usize) };
-                            for i in 0..relax.len() {
+                            let relax = unsafe { node_incoming_weights.get_unchecked_mut(node * k..(node + 1) * k) };
+                            for i in 0..k {
                                 relax[i] = min(relax[i], downward_weight[i] + first_up_weight[i]);
                             }
                         }
                     }
 
-                    for (((node, down), up), _edge_id) in cch
-                        .neighbor_iter(current_node)
-                        .zip(&mut downward_weights[edges.clone()])
-                        .zip(&mut upward_weights[edges.clone()])
-                        .zip(edges)
-                    {
-                        *down = node_incoming_weights[node as usize].clone();
-                        *up = node_outgoing_weights[node as usize].clone();
+                    for (node, edge) in cch.neighbor_iter(current_node).zip(edges) {
+                        let node = node as usize;
+                        downward_weights[edge * k..(edge + 1) * k].copy_from_slice(&node_incoming_weights[node * k..(node + 1) * k]);
+                        upward_weights[edge * k..(edge + 1) * k].copy_from_slice(&node_outgoing_weights[node * k..(node + 1) * k]);
                     }
                 }
             });
@@ -390,25 +1043,188 @@ fn customize_basic(cch: &CCH, upward_weights: &mut Vec<Vec<Weight>>, downward_we
     };
 
     // setup customization for parallelization
-    let customization = SeparatorBasedParallelCustomization::new(cch, customize, customize);
+    let mut customization = SeparatorBasedParallelCustomization::new(cch, customize, customize)
+        .with_stride(k)
+        .with_progress(progress);
+    if let Some(cancellation) = cancellation {
+        customization = customization.with_cancellation(cancellation);
+    }
 
     // execute customization
     report_time_with_key("CCH Customization", "basic_customization", || {
         customization.customize(upward_weights, downward_weights, |cb| {
-            // create workspace vectors for the scope of the customization
-            UPWARD_WORKSPACE.set(&RefCell::new(vec![vec![INFINITY; num_metrics]; n as usize]), || {
-                DOWNWARD_WORKSPACE.set(&RefCell::new(vec![vec![INFINITY; num_metrics]; n as usize]), cb);
+            // create flat workspace vectors for the scope of the customization
+            UPWARD_WORKSPACE.set(&RefCell::new(vec![INFINITY; n * k]), || {
+                DOWNWARD_WORKSPACE.set(&RefCell::new(vec![INFINITY; n * k]), cb);
             });
             // everything will be dropped here
         });
     });
 }
 
-fn retrieve_orig_edge_to_shortcut_mapping(cch: &CCH, num_orig_edges: usize) -> (Vec<Option<EdgeId>>, Vec<Option<EdgeId>>) {
+/// directed customization on a flat weight layout, forward and backward shortcuts are customized separately:
+/// a forward shortcut `(v, w)` is relaxed by the lower triangles `(v, u)` backward and `(u, w)` forward, vice versa for backward shortcuts
+fn customize_directed_basic(cch: &DirectedCCH, upward_weights: &mut Vec<Weight>, downward_weights: &mut Vec<Weight>, num_metrics: usize) {
+    let n = cch.num_nodes();
+    let k = num_metrics;
+    let (forward_first_out, forward_head) = (cch.forward_first_out(), cch.forward_head());
+    let (backward_first_out, backward_head) = (cch.backward_first_out(), cch.backward_head());
+
+    let customize = |nodes: Range<usize>, upward_offset: usize, downward_offset: usize, upward_weights: &mut [Weight], downward_weights: &mut [Weight]| {
+        UPWARD_WORKSPACE.with(|node_outgoing_weights| {
+            let mut node_outgoing_weights = node_outgoing_weights.borrow_mut();
+
+            DOWNWARD_WORKSPACE.with(|node_incoming_weights| {
+                let mut node_incoming_weights = node_incoming_weights.borrow_mut();
+
+                for current_node in nodes {
+                    let forward_edges = forward_first_out[current_node] as usize..forward_first_out[current_node + 1] as usize;
+                    let backward_edges = backward_first_out[current_node] as usize..backward_first_out[current_node + 1] as usize;
+
+                    for edge in forward_edges.clone() {
+                        let (node, edge_idx) = (forward_head[edge] as usize, edge - upward_offset);
+                        node_outgoing_weights[node * k..(node + 1) * k].copy_from_slice(&upward_weights[edge_idx * k..(edge_idx + 1) * k]);
+                    }
+                    for edge in backward_edges.clone() {
+                        let (node, edge_idx) = (backward_head[edge] as usize, edge - downward_offset);
+                        node_incoming_weights[node * k..(node + 1) * k].copy_from_slice(&downward_weights[edge_idx * k..(edge_idx + 1) * k]);
+                    }
+
+                    // forward shortcuts: lower triangles consist of a backward edge of the current node and a forward edge of the lower node
+                    for (NodeIdT(low_node), Reversed(EdgeIdT(first_edge_id))) in cch.backward_inverted().link_iter(current_node as NodeId) {
+                        let first_edge_id = first_edge_id as usize - downward_offset;
+                        let first_down_weight = &downward_weights[first_edge_id * k..(first_edge_id + 1) * k];
+
+                        for edge in (forward_first_out[low_node as usize] as usize..forward_first_out[low_node as usize + 1] as usize).rev() {
+                            let node = forward_head[edge] as usize;
+                            if node <= current_node {
+                                break;
+                            }
+
+                            let edge_idx = edge - upward_offset;
+                            let upward_weight = &upward_weights[edge_idx * k..(edge_idx + 1) * k];
+                            let relax = unsafe { node_outgoing_weights.get_unchecked_mut(node * k..(node + 1) * k) };
+                            for i in 0..k {
+                                relax[i] = min(relax[i], upward_weight[i] + first_down_weight[i]);
+                            }
+                        }
+                    }
+
+                    // backward shortcuts: lower triangles consist of a forward edge of the current node and a backward edge of the lower node
+                    for (NodeIdT(low_node), Reversed(EdgeIdT(first_edge_id))) in cch.forward_inverted().link_iter(current_node as NodeId) {
+                        let first_edge_id = first_edge_id as usize - upward_offset;
+                        let first_up_weight = &upward_weights[first_edge_id * k..(first_edge_id + 1) * k];
+
+                        for edge in (backward_first_out[low_node as usize] as usize..backward_first_out[low_node as usize + 1] as usize).rev() {
+                            let node = backward_head[edge] as usize;
+                            if node <= current_node {
+                                break;
+                            }
+
+                            let edge_idx = edge - downward_offset;
+                            let downward_weight = &downward_weights[edge_idx * k..(edge_idx + 1) * k];
+                            let relax = unsafe { node_incoming_weights.get_unchecked_mut(node * k..(node + 1) * k) };
+                            for i in 0..k {
+                                relax[i] = min(relax[i], downward_weight[i] + first_up_weight[i]);
+                            }
+                        }
+                    }
+
+                    for edge in forward_edges {
+                        let (node, edge_idx) = (forward_head[edge] as usize, edge - upward_offset);
+                        upward_weights[edge_idx * k..(edge_idx + 1) * k].copy_from_slice(&node_outgoing_weights[node * k..(node + 1) * k]);
+                    }
+                    for edge in backward_edges {
+                        let (node, edge_idx) = (backward_head[edge] as usize, edge - downward_offset);
+                        downward_weights[edge_idx * k..(edge_idx + 1) * k].copy_from_slice(&node_incoming_weights[node * k..(node + 1) * k]);
+                    }
+                }
+            });
+        });
+    };
+
+    // setup customization for parallelization
+    let customization = SeparatorBasedParallelDirectedCustomization::new(cch, customize, customize).with_stride(k);
+
+    // execute customization
+    report_time_with_key("Directed CCH Customization", "basic_customization", || {
+        customization.customize(upward_weights, downward_weights, |cb| {
+            // create flat workspace vectors for the scope of the customization
+            UPWARD_WORKSPACE.set(&RefCell::new(vec![INFINITY; n * k]), || {
+                DOWNWARD_WORKSPACE.set(&RefCell::new(vec![INFINITY; n * k]), cb);
+            });
+            // everything will be dropped here
+        });
+    });
+}
+
+/// perfect customization on the interleaved layout: data by metric and edge_id is found at index `edge_id * metric_stride(num_metrics) + metric`
+fn customize_perfect(cch: &CCH, upward_weights: &mut Vec<Weight>, downward_weights: &mut Vec<Weight>, num_metrics: usize) {
+    let n = cch.num_nodes();
+    let k = num_metrics;
+    let stride = metric_stride(k);
+
+    // Same as the basic perfect customization, but all triangles are relaxed in each metric.
+    // The separator based parallelization guarantees that the same shortcuts are never modified concurrently.
+    let customize_perfect = |nodes: Range<usize>, upward: *mut Weight, downward: *mut Weight| {
+        PERFECT_WORKSPACE.with(|node_edge_ids| {
+            let mut node_edge_ids = node_edge_ids.borrow_mut();
+
+            // processing nodes in reverse order
+            for current_node in nodes.rev() {
+                let current_node = current_node as NodeId;
+                // store mapping of head node to corresponding outgoing edge id
+                for (node, edge_id) in cch.neighbor_iter(current_node).zip(cch.neighbor_edge_indices(current_node)) {
+                    node_edge_ids[node as usize] = InRangeOption::new(Some(edge_id));
+                }
+
+                for (node, edge_id) in cch.neighbor_iter(current_node).zip(cch.neighbor_edge_indices(current_node)) {
+                    let shortcut_edge_ids = cch.neighbor_edge_indices(node);
+                    for (target, shortcut_edge_id) in cch.neighbor_iter(node).zip(shortcut_edge_ids) {
+                        if let Some(other_edge_id) = node_edge_ids[target as usize].value() {
+                            // both an intermediate and an upper triangle, relax all of them in every metric
+                            for metric in 0..k {
+                                let edge = edge_id as usize * stride + metric;
+                                let other_edge = other_edge_id as usize * stride + metric;
+                                let shortcut_edge = shortcut_edge_id as usize * stride + metric;
+
+                                unsafe {
+                                    *upward.add(other_edge) = min(*upward.add(other_edge), *upward.add(edge) + *upward.add(shortcut_edge));
+                                    *upward.add(edge) = min(*upward.add(edge), *upward.add(other_edge) + *downward.add(shortcut_edge));
+                                    *downward.add(other_edge) = min(*downward.add(other_edge), *downward.add(edge) + *downward.add(shortcut_edge));
+                                    *downward.add(edge) = min(*downward.add(edge), *downward.add(other_edge) + *upward.add(shortcut_edge));
+                                }
+                            }
+                        }
+                    }
+                }
+
+                // reset the mapping
+                for node in cch.neighbor_iter(current_node) {
+                    node_edge_ids[node as usize] = InRangeOption::new(None);
+                }
+            }
+        });
+    };
+
+    let static_perfect_customization = SeparatorBasedPerfectParallelCustomization::new(cch, customize_perfect, customize_perfect);
+
+    report_time_with_key("CCH Perfect Customization", "perfect_customization", || {
+        static_perfect_customization.customize(upward_weights, downward_weights, |cb| {
+            PERFECT_WORKSPACE.set(&RefCell::new(vec![InRangeOption::new(None); n]), cb);
+        });
+    });
+}
+
+fn retrieve_orig_edge_to_shortcut_mapping(
+    forward_orig_arcs: &Vecs<EdgeIdT>,
+    backward_orig_arcs: &Vecs<EdgeIdT>,
+    num_orig_edges: usize,
+) -> (Vec<Option<EdgeId>>, Vec<Option<EdgeId>>) {
     let mut orig_edge_to_forward_shortcut = vec![None; num_orig_edges];
     let mut orig_edge_to_backward_shortcut = vec![None; num_orig_edges];
 
-    cch.forward_cch_edge_to_orig_arc.iter().enumerate().for_each(|(idx, outgoing)| {
+    forward_orig_arcs.iter().enumerate().for_each(|(idx, outgoing)| {
         debug_assert!(outgoing.len() <= 1);
         outgoing.iter().for_each(|&EdgeIdT(orig_edge_id)| {
             debug_assert!(orig_edge_to_forward_shortcut[orig_edge_id as usize].is_none());
@@ -416,7 +1232,7 @@ fn retrieve_orig_edge_to_shortcut_mapping(cch: &CCH, num_orig_edges: usize) -> (
         });
     });
 
-    cch.backward_cch_edge_to_orig_arc.iter().enumerate().for_each(|(idx, outgoing)| {
+    backward_orig_arcs.iter().enumerate().for_each(|(idx, outgoing)| {
         debug_assert!(outgoing.len() <= 1);
         outgoing.iter().for_each(|&EdgeIdT(orig_edge_id)| {
             debug_assert!(orig_edge_to_backward_shortcut[orig_edge_id as usize].is_none());
@@ -434,3 +1250,38 @@ fn retrieve_orig_edge_to_shortcut_mapping(cch: &CCH, num_orig_edges: usize) -> (
 
     (orig_edge_to_forward_shortcut, orig_edge_to_backward_shortcut)
 }
+
+/// remove all edges that are not marked to be kept, returns the pruned graph and the new id of each original edge
+fn prune_edges(first_out: &[EdgeId], head: &[NodeId], keep: &[bool]) -> (Vec<EdgeId>, Vec<NodeId>, Vec<Option<EdgeId>>) {
+    let mut new_first_out = Vec::with_capacity(first_out.len());
+    new_first_out.push(0);
+    let mut new_head = Vec::with_capacity(head.len());
+    let mut new_edge_ids = vec![None; head.len()];
+
+    for edges in first_out.windows(2) {
+        for edge_id in edges[0] as usize..edges[1] as usize {
+            if keep[edge_id] {
+                new_edge_ids[edge_id] = Some(new_head.len() as EdgeId);
+                new_head.push(head[edge_id]);
+            }
+        }
+        new_first_out.push(new_head.len() as EdgeId);
+    }
+
+    (new_first_out, new_head, new_edge_ids)
+}
+
+/// keep the entries of all remaining edges, values may consist of several metrics stored consecutively
+/// same as `retain_edges`, but each edge owns a block of `stride` values
+fn retain_edge_blocks<T: Clone>(values: &[T], stride: usize, new_edge_ids: &[Option<EdgeId>]) -> Vec<T> {
+    values
+        .chunks_exact(stride)
+        .zip(new_edge_ids.iter())
+        .filter(|(_, id)| id.is_some())
+        .flat_map(|(block, _)| block.iter().cloned())
+        .collect()
+}
+
+fn retain_edges<T: Clone>(values: &[T], new_edge_ids: &[Option<EdgeId>]) -> Vec<T> {
+    retain_edge_blocks(values, 1, new_edge_ids)
+}
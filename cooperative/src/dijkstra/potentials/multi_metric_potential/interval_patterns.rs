@@ -1,4 +1,7 @@
+use crate::graph::MAX_BUCKETS;
+use rust_road_router::cli::CliErr;
 use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use std::error::Error;
 
 /// balanced pattern: 8 hours, 4 hours, 2 hours, 1 hour
 pub fn balanced_interval_pattern() -> Vec<(Timestamp, Timestamp)> {
@@ -65,6 +68,92 @@ pub fn rush_hour_pattern() -> Vec<(Timestamp, Timestamp)> {
     ]
 }
 
+/// restrict a given pattern to the intervals overlapping the horizon `[start, end)`
+///
+/// intervals are clipped to the horizon, duplicates after clipping are removed
+pub fn restrict_to_horizon(intervals: &Vec<(Timestamp, Timestamp)>, start: Timestamp, end: Timestamp) -> Vec<(Timestamp, Timestamp)> {
+    let mut ret = intervals
+        .iter()
+        .filter(|&&(interval_start, interval_end)| interval_start < end && interval_end > start)
+        .map(|&(interval_start, interval_end)| (interval_start.max(start), interval_end.min(end)))
+        .collect::<Vec<(Timestamp, Timestamp)>>();
+
+    ret.sort();
+    ret.dedup();
+    ret
+}
+
+/// Parse an interval pattern specification.
+///
+/// Either the name of a predefined pattern (`balanced`, `complete_balanced`, `rush_hour`),
+/// a comma separated list of `[start, end)` windows in the form `HH:MM-HH:MM`, e.g. `06:00-10:00,07:30-08:00`,
+/// or `@<path>` to read such windows from a file, one or several per line. Lines starting with `#` are ignored.
+/// All windows must be non-empty and lie within the bucket period.
+pub fn parse_interval_pattern(spec: &str) -> Result<Vec<(Timestamp, Timestamp)>, Box<dyn Error>> {
+    let spec = spec.trim();
+    match spec {
+        "balanced" => return Ok(balanced_interval_pattern()),
+        "complete_balanced" => return Ok(complete_balanced_interval_pattern()),
+        "rush_hour" => return Ok(rush_hour_pattern()),
+        _ => {}
+    }
+
+    let windows = if let Some(path) = spec.strip_prefix('@') {
+        std::fs::read_to_string(path)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .flat_map(|line| line.split(','))
+            .map(parse_window)
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        spec.split(',').map(parse_window).collect::<Result<Vec<_>, _>>()?
+    };
+
+    if windows.is_empty() {
+        return Err(Box::new(CliErr("Interval pattern must contain at least one window")));
+    }
+    Ok(windows)
+}
+
+/// Interval pattern from the `INTERVAL_PATTERN` env var (see `parse_interval_pattern`), or `default` if it is not set
+pub fn interval_pattern_from_env(default: fn() -> Vec<(Timestamp, Timestamp)>) -> Result<Vec<(Timestamp, Timestamp)>, Box<dyn Error>> {
+    match std::env::var("INTERVAL_PATTERN") {
+        Ok(spec) => {
+            let pattern = parse_interval_pattern(&spec)?;
+            println!("Using interval pattern `{}` with {} windows", spec, pattern.len());
+            Ok(pattern)
+        }
+        Err(_) => Ok(default()),
+    }
+}
+
+fn parse_window(window: &str) -> Result<(Timestamp, Timestamp), Box<dyn Error>> {
+    let (start, end) = window
+        .trim()
+        .split_once('-')
+        .ok_or_else(|| format!("Invalid interval window `{}`, expected `HH:MM-HH:MM`", window.trim()))?;
+    let (start, end) = (parse_time_of_day(start)?, parse_time_of_day(end)?);
+
+    if start >= end || end > MAX_BUCKETS {
+        return Err(format!("Invalid interval window `{}`, must be non-empty and within the period", window.trim()).into());
+    }
+    Ok((start, end))
+}
+
+pub fn parse_time_of_day(time: &str) -> Result<Timestamp, Box<dyn Error>> {
+    let (hour, minute) = time
+        .trim()
+        .split_once(':')
+        .ok_or_else(|| format!("Invalid time `{}`, expected `HH:MM`", time.trim()))?;
+    let (hour, minute) = (hour.parse::<u32>()?, minute.parse::<u32>()?);
+
+    if hour > 24 || minute >= 60 {
+        return Err(format!("Invalid time `{}`", time.trim()).into());
+    }
+    Ok(ts_from(hour, minute))
+}
+
 #[inline(always)]
 fn ts_from(hour: u32, minute: u32) -> Timestamp {
     hour * 3_600_000 + minute * 60_000
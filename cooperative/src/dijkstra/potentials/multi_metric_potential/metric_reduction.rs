@@ -108,16 +108,19 @@ pub fn reduce_metrics(data: &mut Vec<Vec<Weight>>, entries: &mut Vec<MetricEntry
     let num_metrics = data[0].len();
     let mut queue = IndexdMinHeap::new(num_metrics * num_metrics);
 
+    // several entries may already share a metric, e.g. after `deduplicate_metrics`
+    let metric_ids = distinct_metric_ids(entries);
+
     let start = Instant::now();
-    let queue_items: Vec<Vec<MetricItem>> = (0..entries.len() - 1)
+    let queue_items: Vec<Vec<MetricItem>> = (0..metric_ids.len().saturating_sub(1))
         .into_par_iter()
         .map(|i: usize| {
-            ((i + 1)..entries.len())
+            ((i + 1)..metric_ids.len())
                 .into_iter()
                 .map(|j| {
                     // avoid another parallel construct - calculation is already parallelized
-                    let diff = evaluate_metric_differences(data, entries[i].metric_id, entries[j].metric_id, false);
-                    let id = entries[i].metric_id * num_metrics + entries[j].metric_id;
+                    let diff = evaluate_metric_differences(data, metric_ids[i], metric_ids[j], false);
+                    let id = metric_ids[i] * num_metrics + metric_ids[j];
                     MetricItem::new(id, diff)
                 })
                 .collect::<Vec<MetricItem>>()
@@ -127,9 +130,8 @@ pub fn reduce_metrics(data: &mut Vec<Vec<Weight>>, entries: &mut Vec<MetricEntry
     println!("Initialized all metric comparisons in {}s", time.as_secs_f64());
 
     // remember all deleted metric ids
-    let highest_metric_id = entries.iter().max_by_key(|m| m.metric_id).map(|m| m.metric_id).unwrap();
-    let mut metric_deactivated = vec![false; highest_metric_id + 1];
-    let mut num_active_metrics = entries.len();
+    let mut metric_deactivated = vec![false; num_metrics];
+    let mut num_active_metrics = metric_ids.len();
 
     // reduce pairs with a difference of 0 right away, only insert non-zero values in the queue
     // exploit the order (a, b) where a < b: if b is merged right away, it will be known before b's entries are inspected
@@ -219,9 +221,51 @@ pub fn reduce_metrics(data: &mut Vec<Vec<Weight>>, entries: &mut Vec<MetricEntry
     }
     println!("Successfully merged metrics. Rebuilding data structures..");
 
+    remove_deactivated_metrics(data, entries, &metric_deactivated)
+}
+
+/// Merge metrics which deviate by at most `tolerance` on every edge, before the more expensive pairwise reduction.
+///
+/// This is a lot cheaper than `reduce_metrics` because most pairs differ on some edge early on.
+/// The remaining metric takes the minimum of both, so it stays a lower bound for all merged time windows.
+/// Entries of merged metrics point to the remaining one afterwards, returns the number of remaining metrics.
+pub fn deduplicate_metrics(data: &mut Vec<Vec<Weight>>, entries: &mut Vec<MetricEntry>, tolerance: Weight) -> usize {
+    let mut metric_deactivated = vec![false; data[0].len()];
+    let mut representatives: Vec<usize> = Vec::new();
+
+    // ascending ids, so the lowerbound metric is always kept
+    for metric_id in distinct_metric_ids(entries) {
+        let similar = representatives
+            .iter()
+            .copied()
+            .find(|&representative| metrics_within_tolerance(data, representative, metric_id, tolerance));
+
+        if let Some(representative) = similar {
+            merge_metrics(data, representative, metric_id);
+            metric_deactivated[metric_id] = true;
+            entries.iter_mut().filter(|entry| entry.metric_id == metric_id).for_each(|entry| {
+                entry.metric_id = representative;
+            });
+        } else {
+            representatives.push(metric_id);
+        }
+    }
+
+    let num_deduplicated = metric_deactivated.iter().filter(|&&v| v).count();
+    println!("Deduplicated {} metrics (tolerance: {})", num_deduplicated, tolerance);
+    if num_deduplicated == 0 {
+        return data[0].len();
+    }
+
+    remove_deactivated_metrics(data, entries, &metric_deactivated)
+}
+
+/// drop the values of deactivated metrics and compact the metric ids of the entries accordingly
+fn remove_deactivated_metrics(data: &mut Vec<Vec<Weight>>, entries: &mut Vec<MetricEntry>, metric_deactivated: &[bool]) -> usize {
     // re-build edge metrics, remove deactivated metric values
     // lower and upper bound must not be deactivated!
     debug_assert!(!metric_deactivated[0] && !metric_deactivated[1]);
+    let highest_metric_id = metric_deactivated.len() - 1;
 
     data.par_iter_mut().for_each(|edge_metrics| {
         *edge_metrics = (0..=highest_metric_id)
@@ -248,6 +292,19 @@ pub fn reduce_metrics(data: &mut Vec<Vec<Weight>>, entries: &mut Vec<MetricEntry
     metric_deactivated.iter().filter(|&&v| !v).count()
 }
 
+fn distinct_metric_ids(entries: &[MetricEntry]) -> Vec<usize> {
+    let mut metric_ids = entries.iter().map(|entry| entry.metric_id).collect::<Vec<usize>>();
+    metric_ids.sort_unstable();
+    metric_ids.dedup();
+    metric_ids
+}
+
+fn metrics_within_tolerance(metrics: &Vec<Vec<Weight>>, metric1: usize, metric2: usize, tolerance: Weight) -> bool {
+    metrics
+        .par_iter()
+        .all(|edge_metrics| edge_metrics[metric1].abs_diff(edge_metrics[metric2]) <= tolerance)
+}
+
 /// merge metric `metric_idx` with `other_metric_idx`, i.e. take the minimum and store it on `metric_idx`
 fn merge_metrics(metrics: &mut Vec<Vec<Weight>>, metric_idx: usize, other_metric_idx: usize) {
     metrics
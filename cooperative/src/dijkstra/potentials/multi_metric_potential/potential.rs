@@ -1,46 +1,70 @@
 use crate::dijkstra::potentials::cch_lower_upper::elimination_tree_server::CorridorEliminationTreeServer;
-use crate::dijkstra::potentials::multi_metric_potential::customization::CustomizedMultiMetrics;
+use crate::dijkstra::potentials::multi_metric_potential::customization::{metric_stride, CustomizedMultiMetrics};
 use crate::dijkstra::potentials::multi_metric_potential::metric_reduction::MetricEntry;
 use crate::dijkstra::potentials::TDPotential;
 use crate::graph::MAX_BUCKETS;
 use rust_road_router::algo::customizable_contraction_hierarchy::{CCH, CCHT};
 use rust_road_router::datastr::graph::time_dependent::Timestamp;
-use rust_road_router::datastr::graph::{EdgeId, EdgeIdT, Graph, LinkIterable, NodeId, NodeIdT, UnweightedFirstOutGraph, Weight, INFINITY};
-use rust_road_router::datastr::timestamped_vector::TimestampedVector;
+use rust_road_router::datastr::graph::{EdgeId, EdgeIdGraph, EdgeIdT, LinkIterable, NodeId, NodeIdT, UnweightedFirstOutGraph, Weight, INFINITY};
+use rust_road_router::datastr::timestamped_vector::{Epoch, TimestampedVector};
 use rust_road_router::util::in_range_option::InRangeOption;
-use std::borrow::Borrow;
 use std::cmp::min;
 
-pub struct MultiMetricPotentialContext {
+pub struct MultiMetricPotentialContext<E: Epoch = u32> {
     stack: Vec<NodeId>,
-    potentials: TimestampedVector<InRangeOption<Weight>>,
-    backward_distances: TimestampedVector<Weight>,
-    interval_forward_distances: TimestampedVector<(Weight, Weight)>,
-    interval_backward_distances: TimestampedVector<(Weight, Weight)>,
+    potentials: TimestampedVector<InRangeOption<Weight>, E>,
+    backward_distances: TimestampedVector<Weight, E>,
+    interval_forward_distances: TimestampedVector<(Weight, Weight), E>,
+    interval_backward_distances: TimestampedVector<(Weight, Weight), E>,
     current_metric: usize,
     latest_arrival_dist: Option<Weight>,
     query_start: Timestamp,
     num_pot_computations: usize,
+    share_target_searches: bool,
+    // (target, metric, reversed) of the current backward search if it is shared between queries
+    cached_target: Option<(NodeId, usize, bool)>,
+    num_shared_target_searches: usize,
 }
 
-impl MultiMetricPotentialContext {
+impl<E: Epoch> MultiMetricPotentialContext<E> {
     pub fn new(num_nodes: usize) -> Self {
         Self {
             stack: Vec::new(),
-            potentials: TimestampedVector::new(num_nodes),
-            backward_distances: TimestampedVector::new(num_nodes),
-            interval_forward_distances: TimestampedVector::new(num_nodes),
-            interval_backward_distances: TimestampedVector::new(num_nodes),
+            potentials: TimestampedVector::with_epochs(num_nodes),
+            backward_distances: TimestampedVector::with_epochs(num_nodes),
+            interval_forward_distances: TimestampedVector::with_epochs(num_nodes),
+            interval_backward_distances: TimestampedVector::with_epochs(num_nodes),
             current_metric: 0,
             latest_arrival_dist: None,
             query_start: 0,
             num_pot_computations: 0,
+            share_target_searches: false,
+            cached_target: None,
+            num_shared_target_searches: 0,
         }
     }
+
+    /// Share the backward search (and all potentials derived from it) between consecutive queries with the same target and metric.
+    /// The backward search then is not restricted to the corridor of a single query, which slightly weakens the pruning,
+    /// but queries grouped by target only need a single backward search per target.
+    pub fn set_target_search_sharing(&mut self, share_target_searches: bool) {
+        self.share_target_searches = share_target_searches;
+        self.invalidate();
+    }
+
+    /// number of queries that reused the backward search of their predecessor
+    pub fn num_shared_target_searches(&self) -> usize {
+        self.num_shared_target_searches
+    }
+
+    /// drop the cached backward search, required after each re-customization
+    pub fn invalidate(&mut self) {
+        self.cached_target = None;
+    }
 }
 
-pub struct MultiMetricPotential<'a> {
-    cch: &'a CCH,
+pub struct MultiMetricPotential<'a, C = CCH, E: Epoch = u32> {
+    cch: &'a C,
     forward_cch_graph: UnweightedFirstOutGraph<&'a [EdgeId], &'a [NodeId]>,
     forward_cch_weights: &'a Vec<Weight>,
     forward_cch_bounds: &'a Vec<(Weight, Weight)>,
@@ -48,13 +72,17 @@ pub struct MultiMetricPotential<'a> {
     backward_cch_weights: &'a Vec<Weight>,
     backward_cch_bounds: &'a Vec<(Weight, Weight)>,
     metric_entries: &'a Vec<MetricEntry>,
-    context: &'a mut MultiMetricPotentialContext,
+    // entries per shortcut in the interleaved weights
+    metric_stride: usize,
+    // backward potential, see `prepare_backward`
+    reversed: bool,
+    context: &'a mut MultiMetricPotentialContext<E>,
 }
 
-impl<'a> MultiMetricPotential<'a> {
-    pub fn prepare(customized: &'a mut CustomizedMultiMetrics) -> Self {
-        let forward_cch_graph = UnweightedFirstOutGraph::new(customized.cch.borrow().forward_first_out(), customized.cch.borrow().forward_head());
-        let backward_cch_graph = UnweightedFirstOutGraph::new(customized.cch.borrow().backward_first_out(), customized.cch.borrow().backward_head());
+impl<'a, C: CCHT, E: Epoch> MultiMetricPotential<'a, C, E> {
+    pub fn prepare(customized: &'a mut CustomizedMultiMetrics<C, E>) -> Self {
+        let forward_cch_graph = UnweightedFirstOutGraph::new(customized.cch.forward_first_out(), customized.cch.forward_head());
+        let backward_cch_graph = UnweightedFirstOutGraph::new(customized.cch.backward_first_out(), customized.cch.backward_head());
 
         Self {
             cch: &customized.cch,
@@ -65,6 +93,30 @@ impl<'a> MultiMetricPotential<'a> {
             forward_cch_bounds: &customized.forward_cch_bounds,
             backward_cch_bounds: &customized.backward_cch_bounds,
             metric_entries: &customized.metric_entries,
+            metric_stride: metric_stride(customized.num_metrics),
+            reversed: false,
+            context: &mut customized.potential_context,
+        }
+    }
+
+    /// Backward potential for reverse searches (use it with `ReversedPotential`): estimates the distance from the source to a node.
+    /// Forward and backward graph swap their roles, `init` expects the search to start at `source` with the arrival time as `timestamp`,
+    /// the metric is chosen by the interval which spans the earliest departure and the arrival.
+    pub fn prepare_backward(customized: &'a mut CustomizedMultiMetrics<C, E>) -> Self {
+        let forward_cch_graph = UnweightedFirstOutGraph::new(customized.cch.backward_first_out(), customized.cch.backward_head());
+        let backward_cch_graph = UnweightedFirstOutGraph::new(customized.cch.forward_first_out(), customized.cch.forward_head());
+
+        Self {
+            cch: &customized.cch,
+            forward_cch_graph,
+            backward_cch_graph,
+            forward_cch_weights: &customized.downward,
+            backward_cch_weights: &customized.upward,
+            forward_cch_bounds: &customized.backward_cch_bounds,
+            backward_cch_bounds: &customized.forward_cch_bounds,
+            metric_entries: &customized.metric_entries,
+            metric_stride: metric_stride(customized.num_metrics),
+            reversed: true,
             context: &mut customized.potential_context,
         }
     }
@@ -72,9 +124,28 @@ impl<'a> MultiMetricPotential<'a> {
     pub fn num_pot_computations(&self) -> usize {
         self.context.num_pot_computations
     }
+
+    /// compute the potential of the given rank, all its upward neighbors must already be known
+    fn compute_potential(&mut self, node: NodeId) {
+        // the weight blocks of the outgoing edges of a node are contiguous, the current metric is a fixed offset within each block
+        let edges = self.forward_cch_graph.neighbor_edge_indices_usize(node);
+        let heads = &self.forward_cch_graph.head()[edges.clone()];
+        let weights = &self.forward_cch_weights[edges.start * self.metric_stride..edges.end * self.metric_stride];
+
+        let backward_distances = &self.context.backward_distances;
+        let current_metric = self.context.current_metric;
+        let distance = heads
+            .iter()
+            .zip(weights.chunks_exact(self.metric_stride))
+            .map(|(&next_node, block)| backward_distances[next_node as usize] + block[current_metric])
+            .fold(backward_distances[node as usize], min);
+
+        self.context.backward_distances[node as usize] = distance;
+        self.context.potentials[node as usize] = InRangeOption::some(distance);
+    }
 }
 
-impl<'a> TDPotential for MultiMetricPotential<'a> {
+impl<'a, C: CCHT, E: Epoch> TDPotential for MultiMetricPotential<'a, C, E> {
     fn init(&mut self, source: u32, target: u32, timestamp: u32) {
         self.context.num_pot_computations = 0;
 
@@ -95,24 +166,41 @@ impl<'a> TDPotential for MultiMetricPotential<'a> {
 
         if let Some(upper_arrival_dist) = self.context.latest_arrival_dist {
             // 2. determine relevant metric: find shortest interval that spans the start and latest arrival
+            // (for backward potentials: the earliest departure and the arrival)
             // tie-breaking: smaller metric id wins (i.e. firstly iterated over)
-            let latest_arrival_ts = timestamp + upper_arrival_dist;
-            if latest_arrival_ts >= MAX_BUCKETS {
-                // special case treatment for queries whose arrival time possibly crosses midnight
-                self.context.current_metric = 0; // use lowerbound
+            let (interval_start, interval_end) = if self.reversed {
+                (timestamp.checked_sub(upper_arrival_dist), timestamp)
             } else {
-                let mut tightest_interval_len = MAX_BUCKETS + 1;
+                (Some(timestamp), timestamp + upper_arrival_dist)
+            };
+            match interval_start {
+                Some(interval_start) if interval_end < MAX_BUCKETS => {
+                    let mut tightest_interval_len = MAX_BUCKETS + 1;
 
-                self.metric_entries.iter().for_each(|entry| {
-                    if entry.start <= timestamp && entry.end >= latest_arrival_ts && (entry.end - entry.start < tightest_interval_len) {
-                        tightest_interval_len = entry.end - entry.start;
-                        self.context.current_metric = entry.metric_id;
-                    }
-                });
+                    self.metric_entries.iter().for_each(|entry| {
+                        if entry.start <= interval_start && entry.end >= interval_end && (entry.end - entry.start < tightest_interval_len) {
+                            tightest_interval_len = entry.end - entry.start;
+                            self.context.current_metric = entry.metric_id;
+                        }
+                    });
+                }
+                // special case treatment for queries whose departure or arrival time possibly crosses midnight
+                _ => self.context.current_metric = 0, // use lowerbound
             }
 
             // 3. intialize elimination tree, restrict to backward upward search space from interval query!
+            // a shared backward search must be valid for all sources and therefore can't be restricted
             let target = self.cch.node_order().rank(target);
+            let share_target_searches = self.context.share_target_searches;
+            if share_target_searches {
+                let cache_key = Some((target, self.context.current_metric, self.reversed));
+                if self.context.cached_target == cache_key {
+                    self.context.num_shared_target_searches += 1;
+                    return;
+                }
+                self.context.cached_target = cache_key;
+            }
+
             let query_backward_distances = &self.context.interval_backward_distances;
             self.context.potentials.reset();
             self.context.backward_distances.reset();
@@ -123,7 +211,7 @@ impl<'a> TDPotential for MultiMetricPotential<'a> {
                 current_node = self.cch.elimination_tree()[node as usize].value();
 
                 // additional pruning: only relax edges if the backward distance label is set for this node!
-                if query_backward_distances[node as usize].0 < INFINITY {
+                if share_target_searches || query_backward_distances[node as usize].0 < INFINITY {
                     // For each node we can reach, see if we can find a way with
                     // a lower distance going through this node
                     for (NodeIdT(next_node), EdgeIdT(edge)) in LinkIterable::<(NodeIdT, EdgeIdT)>::link_iter(&self.backward_cch_graph, node) {
@@ -132,7 +220,7 @@ impl<'a> TDPotential for MultiMetricPotential<'a> {
                         let weight = self.context.backward_distances[node as usize]
                             + *unsafe {
                                 self.backward_cch_weights
-                                    .get_unchecked(self.context.current_metric * self.backward_cch_graph.num_arcs() + edge as usize)
+                                    .get_unchecked(edge as usize * self.metric_stride + self.context.current_metric)
                             };
 
                         self.context.backward_distances[next_node] = min(self.context.backward_distances[next_node], weight);
@@ -145,7 +233,7 @@ impl<'a> TDPotential for MultiMetricPotential<'a> {
     fn potential(&mut self, node: NodeId, _timestamp: Timestamp) -> Option<Weight> {
         // if the target isn't reachable from the source, we can abort here
         if let Some(latest_arrival_dist) = self.context.latest_arrival_dist {
-            let node = self.cch.node_order.rank(node);
+            let node = self.cch.node_order().rank(node);
             let elimination_tree = self.cch.elimination_tree();
 
             // 1. upward search until a node with existing distance to target is found
@@ -162,17 +250,7 @@ impl<'a> TDPotential for MultiMetricPotential<'a> {
 
             // 2. propagate the result back to the original start node
             while let Some(current_node) = self.context.stack.pop() {
-                for (NodeIdT(next_node), EdgeIdT(edge)) in LinkIterable::<(NodeIdT, EdgeIdT)>::link_iter(&self.forward_cch_graph, current_node) {
-                    let weight = self.context.backward_distances[next_node as usize]
-                        + *unsafe {
-                            self.forward_cch_weights
-                                .get_unchecked(self.context.current_metric * self.forward_cch_graph.num_arcs() + edge as usize)
-                        };
-
-                    self.context.backward_distances[current_node as usize] = min(self.context.backward_distances[current_node as usize], weight);
-                }
-
-                self.context.potentials[current_node as usize] = InRangeOption::some(self.context.backward_distances[current_node as usize]);
+                self.compute_potential(current_node);
             }
 
             self.context.potentials[node as usize].value().filter(|&pot| pot <= latest_arrival_dist)
@@ -181,6 +259,49 @@ impl<'a> TDPotential for MultiMetricPotential<'a> {
         }
     }
 
+    fn potential_batch(&mut self, nodes: &[NodeId], _timestamps: &[Timestamp], potentials: &mut [Option<Weight>]) {
+        debug_assert_eq!(nodes.len(), potentials.len());
+
+        if let Some(latest_arrival_dist) = self.context.latest_arrival_dist {
+            let elimination_tree = self.cch.elimination_tree();
+
+            // 1. collect the missing parts of all upward search spaces at once
+            for &node in nodes {
+                let mut cur_node = Some(self.cch.node_order().rank(node));
+                while let Some(node) = cur_node {
+                    if self.context.potentials[node as usize].value().is_some() {
+                        break;
+                    }
+                    self.context.stack.push(node);
+                    cur_node = elimination_tree[node as usize].value();
+                }
+            }
+
+            // 2. shared search spaces are only computed once, top-down
+            self.context.stack.sort_unstable_by(|a, b| b.cmp(a));
+            self.context.stack.dedup();
+            self.context.num_pot_computations += self.context.stack.len();
+
+            let stack = std::mem::take(&mut self.context.stack);
+            for &current_node in &stack {
+                self.compute_potential(current_node);
+            }
+            self.context.stack = stack;
+            self.context.stack.clear();
+
+            for (&node, potential) in nodes.iter().zip(potentials.iter_mut()) {
+                let node = self.cch.node_order().rank(node);
+                *potential = self.context.potentials[node as usize].value().filter(|&pot| pot <= latest_arrival_dist);
+            }
+        } else {
+            potentials.iter_mut().for_each(|potential| *potential = None);
+        }
+    }
+
+    fn num_computations(&self) -> Option<usize> {
+        Some(self.context.num_pot_computations)
+    }
+
     fn verify_result(&self, distance: Weight) -> bool {
         distance == INFINITY || distance <= self.context.latest_arrival_dist.unwrap()
     }
@@ -3,12 +3,14 @@ use crate::dijkstra::potentials::corridor_lowerbound_potential::CorridorLowerbou
 use crate::dijkstra::potentials::multi_metric_potential::customization::CustomizedMultiMetrics;
 use crate::dijkstra::potentials::multi_metric_potential::potential::MultiMetricPotential;
 use crate::dijkstra::potentials::TDPotential;
+use rust_road_router::algo::customizable_contraction_hierarchy::CCHT;
 use rust_road_router::algo::dijkstra::query::td_dijkstra::TDDijkstraOps;
 use rust_road_router::algo::dijkstra::{DijkstraData, DijkstraOps, Label, State};
 use rust_road_router::algo::{GenQuery, TDQuery};
 use rust_road_router::datastr::graph::time_dependent::{TDGraph, Timestamp};
 use rust_road_router::datastr::graph::{Arc, EdgeIdT, Graph, LinkIterable, NodeIdT, Weight, INFINITY};
 use rust_road_router::datastr::index_heap::Indexing;
+use rust_road_router::datastr::timestamped_vector::Epoch;
 use rust_road_router::report::measure;
 use std::time::{Duration, Instant};
 
@@ -26,6 +28,8 @@ pub struct PTVQueryResult {
     pub num_relaxed_arcs: u32,
     pub num_queue_pops: u32,
     pub num_queue_pushs: u32,
+    /// potential of the start node, already included in `sum_potentials` of the server
+    pub start_potential: Weight,
 }
 
 impl<PotCustomized> PTVQueryServer<PotCustomized> {
@@ -116,7 +120,8 @@ impl<PotCustomized> PTVQueryServer<PotCustomized> {
         }
 
         let time_query = start.elapsed();
-        *sum_potentials += pot.potential(from, init).unwrap_or(0) as u64;
+        let start_potential = pot.potential(from, init).unwrap_or(0);
+        *sum_potentials += start_potential as u64;
 
         debug_assert!(
             result.unwrap_or(INFINITY) + 1 >= pot.potential(from, init).unwrap_or(INFINITY),
@@ -132,18 +137,19 @@ impl<PotCustomized> PTVQueryServer<PotCustomized> {
             num_relaxed_arcs,
             num_queue_pops,
             num_queue_pushs,
+            start_potential,
         }
     }
 }
 
-impl PTVQueryServer<CustomizedMultiMetrics> {
+impl<C: CCHT, E: Epoch> PTVQueryServer<CustomizedMultiMetrics<C, E>> {
     pub fn query(&mut self, query: &TDQuery<Timestamp>) -> PTVQueryResult {
         let mut pot = MultiMetricPotential::prepare(&mut self.customized);
         Self::query_internal(&self.graph, &mut self.dijkstra, query, &mut pot, &mut self.sum_potentials)
     }
 }
 
-impl PTVQueryServer<CustomizedCorridorLowerbound> {
+impl<E: Epoch> PTVQueryServer<CustomizedCorridorLowerbound<E>> {
     pub fn query(&mut self, query: &TDQuery<Timestamp>) -> PTVQueryResult {
         let mut pot = CorridorLowerboundPotential::prepare_ptv(&mut self.customized);
         Self::query_internal(&self.graph, &mut self.dijkstra, query, &mut pot, &mut self.sum_potentials)
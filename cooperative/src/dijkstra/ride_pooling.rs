@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use rust_road_router::algo::TDQuery;
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{NodeId, Weight};
+
+use crate::dijkstra::server::CapacityServerOps;
+
+/// Stop of a pooled vehicle, the service has to start within `[earliest, latest]`.
+/// Vehicles arriving early wait until `earliest`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stop {
+    pub node: NodeId,
+    pub earliest: Timestamp,
+    pub latest: Timestamp,
+}
+
+impl Stop {
+    pub fn new(node: NodeId, earliest: Timestamp, latest: Timestamp) -> Self {
+        assert!(earliest <= latest, "invalid time window");
+        Self { node, earliest, latest }
+    }
+}
+
+/// Current route of a vehicle, starting at `start` at `departure` and serving all stops in the given order
+#[derive(Clone, Debug)]
+pub struct VehicleRoute {
+    pub start: NodeId,
+    pub departure: Timestamp,
+    pub stops: Vec<Stop>,
+}
+
+impl VehicleRoute {
+    pub fn new(start: NodeId, departure: Timestamp, stops: Vec<Stop>) -> Self {
+        Self { start, departure, stops }
+    }
+}
+
+/// Cheapest feasible insertion of a pickup/dropoff pair into a vehicle route, see `cheapest_insertion`
+#[derive(Clone, Debug)]
+pub struct Insertion {
+    /// positions of pickup and dropoff in the new stop sequence
+    pub pickup_position: usize,
+    pub dropoff_position: usize,
+    /// new stop sequence with the service start at each stop
+    pub stops: Vec<Stop>,
+    pub service_times: Vec<Timestamp>,
+    /// additional time until the vehicle completes its route
+    pub added_time: Weight,
+    /// number of distinct time-dependent distance queries required for the evaluation
+    pub num_distance_queries: usize,
+}
+
+/// Evaluate all insertion positions of `pickup` and `dropoff` into `route` and return the one that delays the completion
+/// of the route the least, while all time windows (including those of the new stops) are kept.
+///
+/// Travel times between stops are time-dependent distances on the server's current graph, the graph is not updated.
+/// Legs are queried on demand and shared by all insertions with the same leg and departure. `query_batch` is not used
+/// because the departure of each leg depends on the arrival of the previous one.
+/// If the potential becomes invalid (see `CapacityServerOps::result_valid`), it is restored with `restore_potential`
+/// and the leg is queried again.
+/// Returns `None` if the current route is infeasible or there is no feasible insertion.
+pub fn cheapest_insertion<S: CapacityServerOps, R: FnMut(&mut S)>(
+    server: &mut S,
+    route: &VehicleRoute,
+    pickup: Stop,
+    dropoff: Stop,
+    restore_potential: R,
+) -> Option<Insertion> {
+    let mut legs = LegCache::new(server, restore_potential);
+
+    // the prefix of the current route is shared by all insertions
+    let current = legs.schedule(route.start, route.departure, &route.stops)?;
+    let completion = |service_times: &Vec<Timestamp>| service_times.last().cloned().unwrap_or(route.departure);
+    let current_completion = completion(&current);
+
+    let mut best: Option<Insertion> = None;
+    for pickup_position in 0..=route.stops.len() {
+        let (node, time) = if pickup_position == 0 {
+            (route.start, route.departure)
+        } else {
+            (route.stops[pickup_position - 1].node, current[pickup_position - 1])
+        };
+
+        for dropoff_position in pickup_position + 1..=route.stops.len() + 1 {
+            let mut stops = route.stops[..pickup_position].to_vec();
+            stops.push(pickup);
+            stops.extend_from_slice(&route.stops[pickup_position..dropoff_position - 1]);
+            stops.push(dropoff);
+            stops.extend_from_slice(&route.stops[dropoff_position - 1..]);
+
+            if let Some(suffix) = legs.schedule(node, time, &stops[pickup_position..]) {
+                let mut service_times = current[..pickup_position].to_vec();
+                service_times.extend(suffix);
+
+                let added_time = completion(&service_times) - current_completion;
+                if best.as_ref().map_or(true, |best| added_time < best.added_time) {
+                    best = Some(Insertion {
+                        pickup_position,
+                        dropoff_position,
+                        stops,
+                        service_times,
+                        added_time,
+                        num_distance_queries: 0,
+                    });
+                }
+            }
+        }
+    }
+
+    let num_distance_queries = legs.num_queries();
+    best.map(|insertion| Insertion {
+        num_distance_queries,
+        ..insertion
+    })
+}
+
+/// time-dependent travel times between stops, memoized by leg and departure
+struct LegCache<'a, S, R> {
+    server: &'a mut S,
+    restore_potential: R,
+    legs: HashMap<(NodeId, NodeId, Timestamp), Option<Weight>>,
+}
+
+impl<'a, S: CapacityServerOps, R: FnMut(&mut S)> LegCache<'a, S, R> {
+    fn new(server: &'a mut S, restore_potential: R) -> Self {
+        Self {
+            server,
+            restore_potential,
+            legs: HashMap::new(),
+        }
+    }
+
+    fn num_queries(&self) -> usize {
+        self.legs.len()
+    }
+
+    fn travel_time(&mut self, from: NodeId, to: NodeId, departure: Timestamp) -> Option<Weight> {
+        if from == to {
+            return Some(0);
+        }
+
+        if let Some(&travel_time) = self.legs.get(&(from, to, departure)) {
+            return travel_time;
+        }
+
+        let query = TDQuery { from, to, departure };
+        let mut potential_restored = false;
+        let travel_time = loop {
+            let distance = self.server.distance(&query).distance;
+            if self.server.result_valid() {
+                break distance;
+            }
+
+            if potential_restored {
+                // panic to avoid infinite loops
+                panic!("Query {:?} failed twice in the same step!", query);
+            }
+
+            // re-customization of the potential
+            potential_restored = true;
+            (self.restore_potential)(&mut *self.server);
+        };
+
+        self.legs.insert((from, to, departure), travel_time);
+        travel_time
+    }
+
+    /// service start at each of the stops, `None` as soon as a stop is unreachable or its time window is missed
+    fn schedule(&mut self, start: NodeId, departure: Timestamp, stops: &[Stop]) -> Option<Vec<Timestamp>> {
+        let mut node = start;
+        let mut time = departure;
+
+        stops
+            .iter()
+            .map(|stop| {
+                let arrival = time + self.travel_time(node, stop.node, time)?;
+                if arrival > stop.latest {
+                    return None;
+                }
+                node = stop.node;
+                time = arrival.max(stop.earliest);
+                Some(time)
+            })
+            .collect()
+    }
+}
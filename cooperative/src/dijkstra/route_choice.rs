@@ -0,0 +1,145 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use rust_road_router::algo::TDQuery;
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::INFINITY;
+
+use crate::dijkstra::model::{CapacityQueryResult, PathResult};
+use crate::dijkstra::server::{CapacityServer, CapacityServerOps};
+use crate::graph::{Capacity, PCE_SCALE};
+
+/// Result of a single route choice
+#[derive(Clone, Debug)]
+pub struct RouteChoiceResult {
+    pub result: CapacityQueryResult,
+    pub num_candidates: usize,
+    /// rank of the chosen path among all candidates, 0 is the currently shortest path
+    pub chosen_rank: usize,
+    pub probability: f64,
+}
+
+/// Stochastic route choice, modelling drivers with imperfect information.
+///
+/// For each query, up to `num_candidates` distinct paths are generated with the penalty method:
+/// each found path is temporarily added to the graph with `penalty_load`, the penalties are withdrawn afterwards.
+/// One of the candidates is chosen with multinomial-logit probabilities `exp(-theta * cost / min_cost)`,
+/// based on the travel times on the current (unpenalized) graph.
+///
+/// The server's potential must stay valid while penalties are added, e.g. a free-flow lowerbound potential.
+pub struct LogitRouteChoice {
+    num_candidates: usize,
+    theta: f64,
+    penalty_load: Capacity,
+    rng: StdRng,
+}
+
+impl LogitRouteChoice {
+    pub fn new(num_candidates: usize, theta: f64, penalty_load: Capacity, seed: u64) -> Self {
+        assert!(num_candidates > 0, "at least one candidate path is required");
+
+        Self {
+            num_candidates,
+            theta,
+            penalty_load,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// default configuration: 3 candidates, theta = 10, penalty of 10 passenger cars
+    pub fn with_seed(seed: u64) -> Self {
+        Self::new(3, 10.0, 10 * PCE_SCALE, seed)
+    }
+
+    /// distinct candidate paths, sorted by their travel time on the current graph
+    pub fn candidates<Pot>(&self, server: &mut CapacityServer<Pot>, query: &TDQuery<Timestamp>) -> Vec<CapacityQueryResult>
+    where
+        CapacityServer<Pot>: CapacityServerOps,
+    {
+        let mut candidates: Vec<PathResult> = Vec::with_capacity(self.num_candidates);
+        let mut penalties: Vec<PathResult> = Vec::new();
+
+        // 1. collect paths, penalize each found path (at most twice as many attempts as candidates)
+        for _ in 0..2 * self.num_candidates {
+            if candidates.len() == self.num_candidates {
+                break;
+            }
+
+            match server.query(query, false) {
+                Some(result) => {
+                    let penalty = result.path.clone().with_pce_load(self.penalty_load);
+                    server.update(&penalty);
+                    penalties.push(penalty);
+
+                    if candidates.iter().all(|path| path.edge_path != result.path.edge_path) {
+                        candidates.push(result.path);
+                    }
+                }
+                None => break,
+            }
+        }
+
+        // 2. restore the graph
+        penalties.iter().rev().for_each(|penalty| server.withdraw(penalty));
+
+        // 3. evaluate the candidates on the restored graph
+        let mut candidates = candidates
+            .into_iter()
+            .map(|path| {
+                let departure = server.borrow_graph().path_departures(&path.edge_path, query.departure);
+                let distance = departure.last().unwrap() - query.departure;
+                CapacityQueryResult::new(distance, PathResult::new(path.node_path, path.edge_path, departure))
+            })
+            .collect::<Vec<CapacityQueryResult>>();
+        candidates.sort_by_key(|candidate| candidate.distance);
+        candidates
+    }
+
+    /// choose a path for the given query, the chosen path contributes `pce_load` to the graph if `update` is set
+    pub fn choose<Pot>(&mut self, server: &mut CapacityServer<Pot>, query: &TDQuery<Timestamp>, pce_load: Capacity, update: bool) -> Option<RouteChoiceResult>
+    where
+        CapacityServer<Pot>: CapacityServerOps,
+    {
+        let candidates = self.candidates(server, query);
+        let min_cost = candidates.first()?.distance.max(1) as f64;
+
+        let weights = candidates
+            .iter()
+            .map(|candidate| {
+                if candidate.distance >= INFINITY {
+                    0.0
+                } else {
+                    (-self.theta * (candidate.distance as f64 / min_cost - 1.0)).exp()
+                }
+            })
+            .collect::<Vec<f64>>();
+        let total_weight = weights.iter().sum::<f64>();
+
+        // sample from the cumulative distribution, fall back to the shortest path on rounding issues
+        let sample = self.rng.gen::<f64>() * total_weight;
+        let mut chosen_rank = 0;
+        let mut cumulative = 0.0;
+        for (rank, &weight) in weights.iter().enumerate() {
+            cumulative += weight;
+            if sample < cumulative {
+                chosen_rank = rank;
+                break;
+            }
+        }
+
+        let num_candidates = candidates.len();
+        let mut result = candidates.into_iter().nth(chosen_rank).unwrap();
+        result.path.pce_load = pce_load;
+
+        if update {
+            server.update(&result.path);
+        }
+
+        Some(RouteChoiceResult {
+            result,
+            num_candidates,
+            chosen_rank,
+            probability: weights[chosen_rank] / total_weight,
+        })
+    }
+}
@@ -0,0 +1,133 @@
+use std::cmp::min;
+
+use rust_road_router::algo::TDQuery;
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{NodeId, Weight, INFINITY};
+
+use crate::dijkstra::model::CapacityQueryResult;
+use crate::dijkstra::server::CapacityServerOps;
+
+/// Multi-stop route, see `sequence_stops`
+#[derive(Clone, Debug)]
+pub struct SequencedRoute {
+    /// visiting order as indices into the given stops, starting with 0 (and ending with 0 for round trips)
+    pub order: Vec<usize>,
+    /// time-dependent route between consecutive stops
+    pub legs: Vec<CapacityQueryResult>,
+    pub travel_time: Weight,
+}
+
+/// Visit all `stops` starting at the first one, optionally returning to it in the end.
+///
+/// The visiting order is computed on a cost matrix evaluated at `departure` (one query batch) with the nearest neighbor
+/// heuristic, improved by 2-opt. The final order is then routed time-dependently leg by leg, see `route_via`.
+/// Returns `None` if some stop is unreachable.
+pub fn sequence_stops<S: CapacityServerOps>(server: &mut S, stops: &[NodeId], departure: Timestamp, return_to_start: bool) -> Option<SequencedRoute> {
+    assert!(!stops.is_empty(), "at least the start has to be given");
+
+    let costs = departure_cost_matrix(server, stops, departure);
+    let order = two_opt(&costs, nearest_neighbor_tour(&costs, return_to_start));
+    if tour_cost(&costs, &order) >= INFINITY as u64 {
+        return None;
+    }
+
+    let legs = route_via(server, &order.iter().map(|&idx| stops[idx]).collect::<Vec<NodeId>>(), departure, false)?;
+    let travel_time = legs.iter().fold(0, |acc: Weight, leg| min(INFINITY, acc + leg.distance));
+
+    Some(SequencedRoute { order, legs, travel_time })
+}
+
+/// Route through all `nodes` in the given order, each leg departs on arrival of the previous one.
+/// With `update`, the loads of all legs are added to the graph. Returns `None` if some leg has no path.
+pub fn route_via<S: CapacityServerOps>(server: &mut S, nodes: &[NodeId], departure: Timestamp, update: bool) -> Option<Vec<CapacityQueryResult>> {
+    let mut time = departure;
+
+    nodes
+        .windows(2)
+        .map(|leg| {
+            let result = server.query(
+                &TDQuery {
+                    from: leg[0],
+                    to: leg[1],
+                    departure: time,
+                },
+                update,
+            )?;
+            time += result.distance;
+            Some(result)
+        })
+        .collect()
+}
+
+/// Travel times between all pairs of `nodes` when departing at `departure`, `INFINITY` if unreachable
+pub fn departure_cost_matrix<S: CapacityServerOps>(server: &mut S, nodes: &[NodeId], departure: Timestamp) -> Vec<Vec<Weight>> {
+    let queries = nodes
+        .iter()
+        .flat_map(|&from| nodes.iter().map(move |&to| TDQuery { from, to, departure }))
+        .collect::<Vec<TDQuery<Timestamp>>>();
+    let results = server.query_batch(&queries);
+
+    results
+        .chunks(nodes.len())
+        .map(|row| row.iter().map(|result| result.distance_result.distance.unwrap_or(INFINITY)).collect())
+        .collect()
+}
+
+/// Greedy tour starting at 0, always continuing with the cheapest unvisited stop
+pub fn nearest_neighbor_tour(costs: &Vec<Vec<Weight>>, return_to_start: bool) -> Vec<usize> {
+    let mut visited = vec![false; costs.len()];
+    let mut tour = vec![0];
+    visited[0] = true;
+
+    while tour.len() < costs.len() {
+        let current = *tour.last().unwrap();
+        let next = (0..costs.len())
+            .filter(|&stop| !visited[stop])
+            .min_by_key(|&stop| costs[current][stop])
+            .unwrap();
+        visited[next] = true;
+        tour.push(next);
+    }
+
+    if return_to_start && costs.len() > 1 {
+        tour.push(0);
+    }
+    tour
+}
+
+/// Improve a tour by reversing segments as long as this reduces its cost. The start stays fixed,
+/// as well as the return to it for round trips. Costs may be asymmetric, so each move is evaluated on the whole tour.
+pub fn two_opt(costs: &Vec<Vec<Weight>>, mut tour: Vec<usize>) -> Vec<usize> {
+    // last position that may be moved
+    let last = if tour.len() > 1 && tour.first() == tour.last() {
+        tour.len() - 2
+    } else {
+        tour.len() - 1
+    };
+    let mut best_cost = tour_cost(costs, &tour);
+
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 1..last {
+            for j in i + 1..=last {
+                tour[i..=j].reverse();
+                let cost = tour_cost(costs, &tour);
+                if cost < best_cost {
+                    best_cost = cost;
+                    improved = true;
+                } else {
+                    tour[i..=j].reverse();
+                }
+            }
+        }
+    }
+    tour
+}
+
+/// total cost of a tour, saturated at `INFINITY`
+pub fn tour_cost(costs: &Vec<Vec<Weight>>, tour: &[usize]) -> u64 {
+    tour.windows(2)
+        .map(|leg| costs[leg[0]][leg[1]] as u64)
+        .fold(0, |acc, cost| (acc + cost).min(INFINITY as u64))
+}
@@ -1,21 +1,36 @@
-use rust_road_router::algo::dijkstra::{DijkstraData, DijkstraOps, Label, State};
+use rust_road_router::algo::a_star::ZeroPotential;
+use rust_road_router::algo::dijkstra::{DijkstraData, DijkstraOps, State};
 use rust_road_router::algo::{GenQuery, TDQuery};
 use rust_road_router::datastr::graph::time_dependent::Timestamp;
 use rust_road_router::datastr::graph::{Arc, EdgeId, EdgeIdT, Graph, LinkIterable, NodeIdT, Weight, INFINITY};
 use rust_road_router::datastr::index_heap::Indexing;
+use rust_road_router::datastr::timestamped_vector::Epoch;
 use rust_road_router::report;
 use rust_road_router::report::*;
+use std::cmp::{max, min};
+use std::collections::{BTreeMap, HashMap};
 use std::time::{Duration, Instant};
 
-use crate::dijkstra::capacity_dijkstra_ops::CapacityDijkstraOps;
-use crate::dijkstra::model::{CapacityQueryResult, DistanceMeasure, MeasuredCapacityQueryResult, PathResult};
+use crate::dijkstra::capacity_dijkstra_ops::{CapacityDijkstraOps, CapacityLabel};
+use crate::dijkstra::model::{
+    CapacityQueryResult, DistanceMeasure, MeasuredCapacityQueryResult, PathResult, PathSegment, RouteDiff, SearchStatistics, SettledNode, TripId,
+    TripStatistics, VehicleId, VehicleStatistics,
+};
+use crate::dijkstra::potentials::cch_parallelization_util::{CancellationToken, CustomizationCancelled};
 use crate::dijkstra::potentials::corridor_lowerbound_potential::customization::CustomizedCorridorLowerbound;
 use crate::dijkstra::potentials::corridor_lowerbound_potential::CorridorLowerboundPotential;
+use crate::dijkstra::potentials::hybrid_potential::HybridPotential;
 use crate::dijkstra::potentials::multi_metric_potential::customization::CustomizedMultiMetrics;
 use crate::dijkstra::potentials::multi_metric_potential::potential::MultiMetricPotential;
 use crate::dijkstra::potentials::TDPotential;
+use crate::graph::access_restrictions::{AccessRestrictions, VehicleClass, DEFAULT_VEHICLE_CLASS};
 use crate::graph::capacity_graph::CapacityGraph;
-use rust_road_router::algo::customizable_contraction_hierarchy::CCH;
+use crate::graph::load_feed::{LoadFeedStatistics, LoadObservation};
+use crate::graph::parking::ParkingModel;
+use crate::graph::speed_blend::SpeedBlend;
+use crate::graph::weather::WeatherFactor;
+use crate::graph::{Capacity, PCE_SCALE};
+use rust_road_router::algo::customizable_contraction_hierarchy::{CCH, CCHT};
 
 pub struct CapacityServer<PotCustomized> {
     graph: CapacityGraph,
@@ -23,8 +38,39 @@ pub struct CapacityServer<PotCustomized> {
     customized: PotCustomized,
     result_valid: bool,
     update_valid: bool,
+    // original edges whose customized upper bounds were exceeded by updates since the last customization,
+    // `None` if the potential can only be restored by a full re-customization
+    upper_bound_violations: Option<Vec<EdgeId>>,
+    // travel times decreased since the last customization, only a full customization restores the potential
+    needs_full_customization: bool,
+    trips: HashMap<TripId, TripEntry>,
+    // linked trips, unlinked trips form a vehicle of their own
+    vehicles: HashMap<TripId, VehicleId>,
+    // switch from plain dijkstra to the customized potential after this many potential evaluations
+    hybrid_threshold: Option<usize>,
+    num_hybrid_upgrades: usize,
+    // settled nodes of the latest query, only recorded if enabled
+    search_space: Option<Vec<SettledNode>>,
+    // restriction zones, enforced for the vehicle class of the current query
+    restrictions: Option<AccessRestrictions>,
+    vehicle_class: VehicleClass,
+    // parking search at the destination of tracked trips, occupied by updating queries
+    parking: Option<ParkingModel>,
 }
 
+/// the current path of a trip, required to evaluate the experienced travel time
+struct TripEntry {
+    edge_path: Vec<EdgeId>,
+    departure: Timestamp,
+    routed_travel_time: Weight,
+    parking_time: Weight,
+    num_routes: u32,
+    num_reroutes: u32,
+}
+
+/// fraction of all shortcuts up to which violated upper bounds are repaired individually instead of a full re-customization
+pub const MAX_UPPER_BOUND_REPAIR_FRACTION: f64 = 0.001;
+
 impl<PotCustomized> CapacityServer<PotCustomized> {
     pub fn new(graph: CapacityGraph, customized: PotCustomized) -> Self {
         let n = graph.num_nodes();
@@ -35,6 +81,16 @@ impl<PotCustomized> CapacityServer<PotCustomized> {
             customized,
             result_valid: true,
             update_valid: true,
+            upper_bound_violations: Some(Vec::new()),
+            needs_full_customization: false,
+            trips: HashMap::new(),
+            vehicles: HashMap::new(),
+            hybrid_threshold: None,
+            num_hybrid_upgrades: 0,
+            search_space: None,
+            restrictions: None,
+            vehicle_class: DEFAULT_VEHICLE_CLASS,
+            parking: None,
         }
     }
 
@@ -43,7 +99,80 @@ impl<PotCustomized> CapacityServer<PotCustomized> {
     }
 
     pub fn update_valid(&self) -> bool {
-        self.update_valid
+        self.update_valid && !self.needs_full_customization
+    }
+
+    /// `true` if travel times decreased since the last customization, e.g. by withdrawn load.
+    /// The lowerbound and interval metrics may overestimate the current travel times, so only a full customization
+    /// (`customize`) restores the potential, updates of the upper bounds keep this flag. Until then, queries run without potential.
+    pub fn needs_full_customization(&self) -> bool {
+        self.needs_full_customization
+    }
+
+    // bounds were violated in a way that can't be repaired locally
+    fn invalidate_update(&mut self) {
+        self.update_valid = false;
+        self.upper_bound_violations = None;
+    }
+
+    /// Travel times may have decreased (withdrawn or reconciled loads, re-opened lanes, weather, speed blend),
+    /// so the potential has to be fully re-customized before the next update, see `needs_full_customization`
+    fn require_full_customization(&mut self) {
+        self.needs_full_customization = true;
+        self.invalidate_update();
+    }
+
+    fn reset_update_validity(&mut self) {
+        self.result_valid = true;
+        self.update_valid = true;
+        self.upper_bound_violations = Some(Vec::new());
+    }
+
+    /// Start queries without a potential and only initialize the corridor/multi-metric potential once
+    /// the search exceeds `threshold` potential evaluations, see `HybridPotential`. `None` always uses the potential.
+    pub fn set_hybrid_threshold(&mut self, threshold: Option<usize>) {
+        self.hybrid_threshold = threshold;
+    }
+
+    /// number of hybrid queries that switched to the customized potential
+    pub fn num_hybrid_upgrades(&self) -> usize {
+        self.num_hybrid_upgrades
+    }
+
+    /// Record the settled nodes of every query (debugging only, see `search_space`)
+    pub fn set_search_space_recording(&mut self, record: bool) {
+        self.search_space = if record { Some(Vec::new()) } else { None };
+    }
+
+    /// settled nodes of the latest query in settling order, `None` if the recording is disabled
+    pub fn search_space(&self) -> Option<&[SettledNode]> {
+        self.search_space.as_deref()
+    }
+
+    /// Enforce restriction zones during the relaxation, see `AccessRestrictions`
+    pub fn set_access_restrictions(&mut self, restrictions: Option<AccessRestrictions>) {
+        self.restrictions = restrictions;
+    }
+
+    /// vehicle class of all following queries, only relevant with access restrictions or class-specific edges
+    pub fn set_vehicle_class(&mut self, vehicle_class: VehicleClass) {
+        self.vehicle_class = vehicle_class;
+    }
+
+    /// Add the parking search time at the destination to the results of all following trip queries, see `ParkingModel`
+    pub fn set_parking_model(&mut self, parking: Option<ParkingModel>) {
+        self.parking = parking;
+    }
+
+    pub fn parking_model(&self) -> Option<&ParkingModel> {
+        self.parking.as_ref()
+    }
+
+    /// Exchange the customized potential, e.g. to switch between the potentials of different vehicle classes.
+    /// The new potential must be customized for the current graph, the previous one is returned.
+    pub fn replace_customized(&mut self, customized: PotCustomized) -> PotCustomized {
+        self.reset_update_validity();
+        std::mem::replace(&mut self.customized, customized)
     }
 
     pub fn decompose(self) -> (CapacityGraph, PotCustomized) {
@@ -54,15 +183,138 @@ impl<PotCustomized> CapacityServer<PotCustomized> {
         &self.graph
     }
 
-    fn distance_internal<Pot: TDPotential>(
-        dijkstra: &mut DijkstraData<Weight, EdgeIdT, Weight>,
+    /// Reconcile the bucket loads of the graph with externally observed counts.
+    /// Loads may decrease, see `needs_full_customization`.
+    pub fn synchronize_loads(&mut self, observations: &[LoadObservation]) -> LoadFeedStatistics {
+        let (modified, statistics) = self.graph.reconcile_loads(observations);
+        if !modified.is_empty() {
+            self.require_full_customization();
+        }
+        statistics
+    }
+
+    /// Close lanes of an edge at runtime (0 re-opens all lanes), see `CapacityGraph::set_closed_lanes`.
+    pub fn set_closed_lanes(&mut self, edge_id: EdgeId, num_closed: u32) {
+        self.graph.set_closed_lanes(edge_id, num_closed);
+        self.require_full_customization();
+    }
+
+    /// Scale the free-flow speed and capacity of an edge at runtime, see `CapacityGraph::set_weather`.
+    pub fn set_weather(&mut self, edge_id: EdgeId, factor: WeatherFactor) {
+        self.graph.set_weather(edge_id, factor);
+        self.require_full_customization();
+    }
+
+    /// Change the blend of historic and live speeds at runtime, see `CapacityGraph::set_speed_blend`.
+    pub fn set_speed_blend(&mut self, blend: SpeedBlend) {
+        if !self.graph.set_speed_blend(blend).is_empty() {
+            self.require_full_customization();
+        }
+    }
+
+    /// statistics of all tracked trips, the experienced travel times are evaluated on the current graph
+    pub fn trip_statistics(&self) -> Vec<TripStatistics> {
+        let mut statistics = self
+            .trips
+            .iter()
+            .map(|(&trip_id, entry)| TripStatistics {
+                trip_id,
+                departure: entry.departure,
+                num_routes: entry.num_routes,
+                num_reroutes: entry.num_reroutes,
+                routed_travel_time: entry.routed_travel_time,
+                experienced_travel_time: self.path_distance_internal(&entry.edge_path, entry.departure),
+                free_flow_travel_time: entry
+                    .edge_path
+                    .iter()
+                    .map(|&edge_id| self.graph.free_flow_time()[edge_id as usize])
+                    .fold(0, |acc: Weight, val| min(INFINITY, acc + val)),
+                parking_time: entry.parking_time,
+            })
+            .collect::<Vec<TripStatistics>>();
+
+        statistics.sort_by_key(|entry| entry.trip_id);
+        statistics
+    }
+
+    /// link a return trip to the vehicle of its outward trip, both trips are then reported as one vehicle
+    pub fn link_trips(&mut self, outward_trip: TripId, return_trip: TripId) {
+        let vehicle_id = self.vehicle_id(outward_trip);
+        self.vehicles.insert(outward_trip, vehicle_id);
+        self.vehicles.insert(return_trip, vehicle_id);
+    }
+
+    /// the vehicle of a trip, trips without a link are identified by their own id
+    pub fn vehicle_id(&self, trip_id: TripId) -> VehicleId {
+        *self.vehicles.get(&trip_id).unwrap_or(&trip_id)
+    }
+
+    /// daily statistics of all vehicles, aggregated over the statistics of their tracked trips
+    pub fn vehicle_statistics(&self) -> Vec<VehicleStatistics> {
+        let mut statistics = BTreeMap::new();
+
+        for trip in self.trip_statistics() {
+            let vehicle_id = self.vehicle_id(trip.trip_id);
+            let entry = statistics.entry(vehicle_id).or_insert(VehicleStatistics {
+                vehicle_id,
+                num_trips: 0,
+                num_routes: 0,
+                num_reroutes: 0,
+                routed_travel_time: 0,
+                experienced_travel_time: 0,
+                free_flow_travel_time: 0,
+                parking_time: 0,
+            });
+
+            entry.num_trips += 1;
+            entry.num_routes += trip.num_routes;
+            entry.num_reroutes += trip.num_reroutes;
+            entry.routed_travel_time = min(INFINITY, entry.routed_travel_time + trip.routed_travel_time);
+            entry.experienced_travel_time = min(INFINITY, entry.experienced_travel_time + trip.experienced_travel_time);
+            entry.free_flow_travel_time = min(INFINITY, entry.free_flow_travel_time + trip.free_flow_travel_time);
+            entry.parking_time += trip.parking_time;
+        }
+
+        statistics.into_values().collect()
+    }
+
+    fn track_trip(&mut self, trip_id: TripId, result: &CapacityQueryResult, reroute: bool) {
+        let entry = self.trips.entry(trip_id).or_insert(TripEntry {
+            edge_path: vec![],
+            departure: 0,
+            routed_travel_time: 0,
+            parking_time: 0,
+            num_routes: 0,
+            num_reroutes: 0,
+        });
+
+        entry.edge_path = result.path.edge_path.clone();
+        entry.departure = *result.path.departure.first().unwrap();
+        entry.routed_travel_time = result.distance;
+        entry.parking_time = result.parking_time;
+        entry.num_routes += 1;
+        if reroute {
+            entry.num_reroutes += 1;
+        }
+    }
+
+    /// Query plumbing shared by all potentials, generic over the label type, see `CapacityLabel`
+    fn distance_internal<Pot: TDPotential, L: CapacityLabel>(
+        dijkstra: &mut DijkstraData<L, EdgeIdT, L>,
         graph: &CapacityGraph,
         pot: &mut Pot,
         result_valid: &mut bool,
+        search_space: &mut Option<Vec<SettledNode>>,
+        restrictions: Option<&AccessRestrictions>,
+        vehicle_class: VehicleClass,
         query: &TDQuery<Timestamp>,
     ) -> DistanceMeasure {
         report!("algo", "TD Dijkstra with Capacities");
 
+        if let Some(search_space) = search_space.as_mut() {
+            search_space.clear();
+        }
+
         // if the latest result was not valid, block the query execution
         if !*result_valid {
             return DistanceMeasure {
@@ -73,6 +325,8 @@ impl<PotCustomized> CapacityServer<PotCustomized> {
                 num_queue_pushs: 0,
                 num_queue_pops: 0,
                 num_relaxed_arcs: 0,
+                num_pot_computations: None,
+                search_statistics: None,
             };
         }
 
@@ -80,6 +334,10 @@ impl<PotCustomized> CapacityServer<PotCustomized> {
         let mut num_queue_pops = 0;
         let mut num_queue_pushs = 0;
         let mut num_relaxed_arcs = 0;
+        let mut num_restricted_arcs = 0;
+        let mut num_pruned_nodes = 0;
+        // the counters are optimized away without the `stats` feature
+        let mut stats = SearchStatistics::default();
 
         // time-dependent potentials are a little bit more complicated
         // for now, a slight modification of the generic dijkstra code should suffice
@@ -88,7 +346,7 @@ impl<PotCustomized> CapacityServer<PotCustomized> {
         let (_, time_potential) = measure(|| pot.init(query.from, query.to, query.departure));
 
         let start = Instant::now();
-        let mut ops = CapacityDijkstraOps::default();
+        let mut ops = CapacityDijkstraOps::<L>::default();
 
         // 1. reset data
         dijkstra.queue.clear();
@@ -99,55 +357,116 @@ impl<PotCustomized> CapacityServer<PotCustomized> {
             key: query.departure,
             node: query.from,
         });
-        dijkstra.distances[query.from as usize] = query.departure;
+        dijkstra.distances[query.from as usize] = L::at_departure(query.departure);
         dijkstra.predecessors[query.from as usize].0 = query.from;
 
         // 3. run query
-        while let Some(State { node, .. }) = dijkstra.queue.pop() {
+        // potentials of all improved heads of a node are evaluated as one batch
+        let mut improved_nodes = Vec::new();
+        let mut improved_timestamps = Vec::new();
+        let mut improved_potentials = Vec::new();
+
+        while let Some(State { node, key }) = dijkstra.queue.pop() {
             num_queue_pops += 1;
 
+            if let Some(search_space) = search_space.as_mut() {
+                let label = dijkstra.distances[node as usize].key();
+                search_space.push(SettledNode {
+                    node,
+                    distance: label - query.departure,
+                    potential: key - label,
+                });
+            }
+
             if node == query.to {
-                result = Some(dijkstra.distances[query.to as usize] - dijkstra.distances[query.from as usize]);
+                result = Some(dijkstra.distances[query.to as usize].arrival() - dijkstra.distances[query.from as usize].arrival());
                 break;
             }
+            if cfg!(feature = "stats") {
+                stats.num_settled_nodes += 1;
+            }
 
+            improved_nodes.clear();
             for link in LinkIterable::<(NodeIdT, EdgeIdT)>::link_iter(graph, node) {
+                // skip dedicated lanes of other classes and edges in active restriction zones
+                if !graph.is_available(link.1 .0, vehicle_class)
+                    || restrictions.map_or(false, |restrictions| {
+                        !restrictions.is_allowed(link.1 .0, vehicle_class, dijkstra.distances[node as usize].arrival())
+                    })
+                {
+                    num_restricted_arcs += 1;
+                    continue;
+                }
                 num_relaxed_arcs += 1;
                 let linked = ops.link(graph, &dijkstra.predecessors, NodeIdT(node), &dijkstra.distances[node as usize], &link);
 
                 if ops.merge(&mut dijkstra.distances[link.head() as usize], linked) {
                     dijkstra.predecessors[link.head() as usize] = (node, ops.predecessor_link(&link));
-                    let next_distance = &dijkstra.distances[link.head() as usize];
-
-                    if let Some(next_key) = pot.potential(link.head(), next_distance.clone()).map(|p| p + next_distance.key()) {
-                        let next = State {
-                            node: link.head(),
-                            key: next_key,
-                        };
-                        if dijkstra.queue.contains_index(next.as_index()) {
-                            dijkstra.queue.decrease_key(next);
-                        } else {
-                            num_queue_pushs += 1;
-                            dijkstra.queue.push(next);
+                    improved_nodes.push(link.head());
+                }
+            }
+            if cfg!(feature = "stats") {
+                stats.num_improved_labels += improved_nodes.len() as u32;
+                stats.num_potential_calls += improved_nodes.len() as u32;
+            }
+
+            improved_timestamps.clear();
+            improved_timestamps.extend(improved_nodes.iter().map(|&head| dijkstra.distances[head as usize].arrival()));
+            improved_potentials.clear();
+            improved_potentials.resize(improved_nodes.len(), None);
+            pot.potential_batch(&improved_nodes, &improved_timestamps, &mut improved_potentials);
+
+            for (&head, potential) in improved_nodes.iter().zip(improved_potentials.iter()) {
+                if let Some(next_key) = potential.map(|p| p + dijkstra.distances[head as usize].key()) {
+                    let next = State { node: head, key: next_key };
+                    if dijkstra.queue.contains_index(next.as_index()) {
+                        if cfg!(feature = "stats") {
+                            stats.num_decrease_keys += 1;
                         }
+                        dijkstra.queue.decrease_key(next);
+                    } else {
+                        num_queue_pushs += 1;
+                        dijkstra.queue.push(next);
+                    }
+                } else {
+                    num_pruned_nodes += 1;
+                    if cfg!(feature = "stats") {
+                        stats.num_pruned_nodes += 1;
                     }
                 }
             }
         }
 
+        // the potential is customized without restrictions, its pruning may have cut off the detour around restricted edges
+        // -> the result can't be trusted, re-run the query without pruning
+        if num_restricted_arcs > 0 && num_pruned_nodes > 0 {
+            return Self::distance_internal(
+                dijkstra,
+                graph,
+                &mut ZeroPotential(),
+                result_valid,
+                search_space,
+                restrictions,
+                vehicle_class,
+                query,
+            );
+        }
+
         let time_query = start.elapsed();
 
         *result_valid = match result {
             None => {
                 // case that should not happen: not reachable, but potential says so
-                pot.potential(query.from, query.departure).is_none()
+                // (unless restricted edges were skipped without pruning, the potential doesn't know about restrictions)
+                num_restricted_arcs > 0 || pot.potential(query.from, query.departure).is_none()
             }
             Some(1) => {
                 // nasty edge cases, caused by our graph preprocessing -> everything okay here
                 println!("-- WARNING: Distance 1, Potential: {:?}", &pot.potential(query.from, query.departure));
                 true
             }
-            Some(dist) => dist >= pot.potential(query.from, query.departure).unwrap_or(INFINITY) && pot.verify_result(dist),
+            // the upper bound of the potential ignores restrictions, a restricted detour may legitimately exceed it
+            Some(dist) => dist >= pot.potential(query.from, query.departure).unwrap_or(INFINITY) && (num_restricted_arcs > 0 || pot.verify_result(dist)),
         };
 
         if !*result_valid {
@@ -166,6 +485,8 @@ impl<PotCustomized> CapacityServer<PotCustomized> {
             num_queue_pushs,
             num_queue_pops,
             num_relaxed_arcs,
+            num_pot_computations: pot.num_computations(),
+            search_statistics: Some(stats).filter(|_| cfg!(feature = "stats")),
         }
     }
 
@@ -202,6 +523,28 @@ impl<PotCustomized> CapacityServer<PotCustomized> {
         PathResult::new(node_path, edge_path, departure)
     }
 
+    fn path_breakdown_internal(&self, edge_path: &Vec<EdgeId>, query_start: Timestamp) -> Vec<PathSegment> {
+        let mut segments = Vec::with_capacity(edge_path.len());
+        let mut departure = query_start;
+
+        for &edge_id in edge_path {
+            let travel_time = self.graph.eval_history_free(edge_id, departure);
+            segments.push(PathSegment {
+                edge_id,
+                departure,
+                travel_time,
+                bucket: self.graph.bucket_index(departure),
+            });
+
+            departure += travel_time;
+            if departure - query_start > INFINITY {
+                break;
+            }
+        }
+
+        segments
+    }
+
     fn path_distance_internal(&self, edge_path: &Vec<EdgeId>, query_start: Timestamp) -> Weight {
         let mut duration = 0;
 
@@ -219,43 +562,252 @@ impl<PotCustomized> CapacityServer<PotCustomized> {
     }
 }
 
-impl CapacityServer<CustomizedCorridorLowerbound> {
-    pub fn customize(&mut self, mut customized: CustomizedCorridorLowerbound) {
+impl<PotCustomized> CapacityServer<PotCustomized>
+where
+    Self: CapacityServerOps,
+{
+    /// run a query for the given trip, updates on the graph are tracked per trip
+    pub fn query_trip(&mut self, query: &TDQuery<Timestamp>, trip_id: TripId, update: bool) -> Option<CapacityQueryResult> {
+        self.query_trip_with_pce(query, trip_id, PCE_SCALE, update)
+    }
+
+    /// same as `query_trip`, the vehicle contributes `pce_load` (see `PCE_SCALE`) to the loads of its path
+    pub fn query_trip_with_pce(&mut self, query: &TDQuery<Timestamp>, trip_id: TripId, pce_load: Capacity, update: bool) -> Option<CapacityQueryResult> {
+        let result = self.query_with_pce(query, pce_load, false)?;
+
+        if update {
+            Some(self.commit_trip(trip_id, result))
+        } else {
+            Some(self.with_parking_time(result, false).with_trip_id(trip_id))
+        }
+    }
+
+    /// Add the path of a query result to the graph, park the vehicle at its destination and count the route for the trip.
+    /// Each call counts as a route, so if a query is repeated after an invalid result (see `result_valid`), only the final result should be committed.
+    pub fn commit_trip(&mut self, trip_id: TripId, result: CapacityQueryResult) -> CapacityQueryResult {
+        self.update(&result.path);
+        let result = self.with_parking_time(result, true).with_trip_id(trip_id);
+        self.track_trip(trip_id, &result, false);
+        result
+    }
+
+    /// add the parking search time at the destination of `result`, a space is only occupied if `park` is set
+    fn with_parking_time(&mut self, result: CapacityQueryResult, park: bool) -> CapacityQueryResult {
+        let destination = *result.path.node_path.last().unwrap();
+        let arrival = *result.path.departure.last().unwrap();
+
+        let parking_time = match self.parking.as_mut() {
+            Some(parking) if park => parking.park(destination, arrival),
+            Some(parking) => parking.search_time(destination, arrival),
+            None => 0,
+        };
+        result.with_parking_time(parking_time)
+    }
+
+    /// Route a query along a fixed edge path, e.g. a path that is optimal w.r.t. another metric (see `distance_metric::CustomizedObjectives`).
+    /// Travel times are evaluated on the current graph. `None` if the path exceeds `INFINITY` or uses edges that are not available
+    /// to the current vehicle class or restricted at the time they are entered (see `set_access_restrictions`).
+    pub fn query_path(&mut self, query: &TDQuery<Timestamp>, edge_path: Vec<EdgeId>, update: bool) -> Option<CapacityQueryResult> {
+        let mut node_path = Vec::with_capacity(edge_path.len() + 1);
+        let mut departure = Vec::with_capacity(edge_path.len() + 1);
+        node_path.push(query.from);
+        departure.push(query.departure);
+
+        for &edge_id in &edge_path {
+            let current_time = *departure.last().unwrap();
+            if !self.graph.is_available(edge_id, self.vehicle_class)
+                || self
+                    .restrictions
+                    .as_ref()
+                    .map_or(false, |restrictions| !restrictions.is_allowed(edge_id, self.vehicle_class, current_time))
+            {
+                return None;
+            }
+
+            // abort if the distance exceeds infinity
+            let arrival = current_time.saturating_add(self.graph.travel_time_function(edge_id).eval(current_time));
+            if arrival - query.departure >= INFINITY {
+                return None;
+            }
+
+            node_path.push(self.graph.head()[edge_id as usize]);
+            departure.push(arrival);
+        }
+
+        let distance = *departure.last().unwrap() - query.departure;
+        let path = PathResult::new(node_path, edge_path, departure);
+        if update {
+            self.update(&path);
+        }
+        Some(CapacityQueryResult::new(distance, path))
+    }
+
+    /// withdraw the current path of the given trip and route it again
+    pub fn reroute_trip(&mut self, previous: &PathResult, query: &TDQuery<Timestamp>, trip_id: TripId) -> Option<(CapacityQueryResult, RouteDiff)> {
+        // the vehicle keeps the parking space reserved on its first arrival, so the tracked search time stays valid
+        let parking_time = self.trips.get(&trip_id).map(|entry| entry.parking_time);
+        let result = self.reroute(previous, query).map(|(result, diff)| {
+            let result = match parking_time {
+                Some(parking_time) => result.with_parking_time(parking_time),
+                None => self.with_parking_time(result, false),
+            };
+            (result.with_trip_id(trip_id), diff)
+        });
+
+        if let Some((result, _)) = &result {
+            self.track_trip(trip_id, result, true);
+        }
+        result
+    }
+}
+
+impl<E: Epoch> CapacityServer<CustomizedCorridorLowerbound<E>> {
+    pub fn customize(&mut self, mut customized: CustomizedCorridorLowerbound<E>) {
         std::mem::swap(&mut self.customized, &mut customized);
-        self.result_valid = true;
-        self.update_valid = true;
+        self.needs_full_customization = false;
+        self.reset_update_validity();
     }
 
     pub fn customize_upper_bound(&mut self, cch: &CCH) {
         self.customized.customize_upper_bound(cch, &self.graph);
-        self.result_valid = true;
-        self.update_valid = true;
+        self.reset_update_validity();
+    }
+
+    /// Fully re-customize the potential with `num_intervals`, abort as soon as the given token is cancelled.
+    /// A cancelled customization keeps the previous potential (and its validity).
+    pub fn customize_cancellable(&mut self, cch: &CCH, num_intervals: u32, cancellation: &CancellationToken) -> Result<(), CustomizationCancelled> {
+        let customized = CustomizedCorridorLowerbound::new_from_capacity_cancellable(cch, &self.graph, num_intervals, cancellation)?;
+        self.customize(customized.with_epochs());
+        Ok(())
+    }
+
+    /// Same as `customize_upper_bound`, but aborts as soon as the given token is cancelled.
+    pub fn customize_upper_bound_cancellable(&mut self, cch: &CCH, cancellation: &CancellationToken) -> Result<(), CustomizationCancelled> {
+        self.customized.customize_upper_bound_cancellable(cch, &self.graph, cancellation)?;
+        self.reset_update_validity();
+        Ok(())
+    }
+
+    /// Restore the potential after `result_valid` or `update_valid` failed: fully re-customize with `num_intervals`
+    /// if travel times decreased (see `needs_full_customization`), otherwise only the upper bounds are re-customized
+    pub fn restore_potential(&mut self, cch: &CCH, num_intervals: u32) {
+        if self.needs_full_customization {
+            let customized = CustomizedCorridorLowerbound::new_from_capacity(cch, &self.graph, num_intervals);
+            self.customize(customized.with_epochs());
+        } else {
+            self.customize_upper_bound(cch);
+        }
     }
 }
 
-impl CapacityServer<CustomizedMultiMetrics> {
+impl<E: Epoch> CapacityServer<CustomizedMultiMetrics<CCH, E>> {
     pub fn customize(&mut self, intervals: &Vec<(u32, u32)>, num_max_metrics: usize) {
         self.customized.customize(&self.graph, intervals, num_max_metrics);
-        self.result_valid = true;
-        self.update_valid = true;
+        self.needs_full_customization = false;
+        self.reset_update_validity();
+    }
+
+    /// Re-customize the potential, abort as soon as the given token is cancelled.
+    /// A cancelled customization keeps the previous potential (and its validity).
+    pub fn customize_cancellable(
+        &mut self,
+        intervals: &Vec<(u32, u32)>,
+        num_max_metrics: usize,
+        cancellation: &CancellationToken,
+    ) -> Result<(), CustomizationCancelled> {
+        self.customized.customize_cancellable(&self.graph, intervals, num_max_metrics, cancellation)?;
+        self.needs_full_customization = false;
+        self.reset_update_validity();
+        Ok(())
     }
 
     pub fn customize_upper_bound(&mut self) {
         self.customized.customize_upper_bound(&self.graph);
-        self.result_valid = true;
-        self.update_valid = true;
+        self.reset_update_validity();
+    }
+
+    /// Restore the potential after `result_valid` or `update_valid` failed: fully re-customize if travel times decreased
+    /// (see `needs_full_customization`), otherwise only the upper bounds are repaired (see `repair_upper_bound`)
+    pub fn restore_potential(&mut self, intervals: &Vec<(u32, u32)>, num_max_metrics: usize) {
+        if self.needs_full_customization {
+            self.customize(intervals, num_max_metrics);
+        } else {
+            self.repair_upper_bound();
+        }
+    }
+
+    /// Restore the upper bounds after `update_valid` failed. If only a few edges exceeded their bounds,
+    /// just the shortcuts above these edges are adjusted (see `CustomizedMultiMetrics::repair_upper_bounds`),
+    /// otherwise (or if the violation can't be repaired locally) the upper bounds are fully re-customized.
+    /// An invalid result without any recorded violation also requires the full re-customization.
+    ///
+    /// Returns `true` if the targeted repair was sufficient
+    pub fn repair_upper_bound(&mut self) -> bool {
+        let max_repairs = max(1, (self.customized.forward_cch_bounds.len() as f64 * MAX_UPPER_BOUND_REPAIR_FRACTION) as usize);
+
+        match self.upper_bound_violations.take() {
+            Some(mut edges) if edges.len() <= max_repairs && (!edges.is_empty() || self.result_valid) => {
+                edges.sort_unstable();
+                edges.dedup();
+                let num_repaired = self.customized.repair_upper_bounds(&self.graph, &edges);
+                println!("Repaired the upper bounds of {} shortcuts ({} edges)", num_repaired, edges.len());
+
+                self.reset_update_validity();
+                true
+            }
+            _ => {
+                self.customize_upper_bound();
+                false
+            }
+        }
     }
 }
 
 pub trait CapacityServerOps {
     fn distance(&mut self, query: &TDQuery<Timestamp>) -> DistanceMeasure;
     fn update(&mut self, path: &PathResult);
+    fn withdraw(&mut self, path: &PathResult);
     fn path(&self, query: &TDQuery<Timestamp>) -> PathResult;
     fn path_distance(&self, edge_path: &Vec<EdgeId>, query_start: Timestamp) -> Weight;
 
+    /// Same as `path_distance`, but returns the traversal time and the used bucket of each edge.
+    /// The breakdown stops at the first edge that exceeds infinity.
+    fn path_breakdown(&self, edge_path: &Vec<EdgeId>, query_start: Timestamp) -> Vec<PathSegment>;
+
+    /// share work between consecutive queries towards the same target, if the potential supports it
+    fn set_target_search_sharing(&mut self, _share_target_searches: bool) {}
+
+    /// false if the potential of the last distance query was invalid, the query has to be repeated after restoring it
+    fn result_valid(&self) -> bool {
+        true
+    }
+
+    /// Run a batch of queries without updating the graph.
+    /// The queries are grouped by target (and ordered by departure within a group), so the backward search
+    /// of the potential can be shared by all queries towards the same target. Results are in the original order.
+    fn query_batch(&mut self, queries: &[TDQuery<Timestamp>]) -> Vec<MeasuredCapacityQueryResult> {
+        let mut order = (0..queries.len()).collect::<Vec<usize>>();
+        order.sort_by_key(|&idx| (queries[idx].to, queries[idx].departure));
+
+        self.set_target_search_sharing(true);
+        let mut results = order
+            .into_iter()
+            .map(|idx| (idx, self.query_measured(&queries[idx], false)))
+            .collect::<Vec<_>>();
+        self.set_target_search_sharing(false);
+
+        results.sort_by_key(|&(idx, _)| idx);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
     fn query(&mut self, query: &TDQuery<Timestamp>, update: bool) -> Option<CapacityQueryResult> {
+        self.query_with_pce(query, PCE_SCALE, update)
+    }
+
+    /// run a query for a vehicle contributing `pce_load` (see `PCE_SCALE`) to the loads of its path
+    fn query_with_pce(&mut self, query: &TDQuery<Timestamp>, pce_load: Capacity, update: bool) -> Option<CapacityQueryResult> {
         if let Some(distance) = self.distance(query).distance {
-            let path = self.path(&query);
+            let path = self.path(&query).with_pce_load(pce_load);
             debug_assert_eq!(*path.departure.last().unwrap() - *path.departure.first().unwrap(), distance);
             if update {
                 self.update(&path);
@@ -296,15 +848,48 @@ pub trait CapacityServerOps {
             }
         }
     }
+
+    /// withdraw a previously routed path and route the query again, the new path keeps the load of the previous one
+    ///
+    /// returns the new result together with its difference to the previous path.
+    /// if no new path is found, the previous path is restored.
+    fn reroute(&mut self, previous: &PathResult, query: &TDQuery<Timestamp>) -> Option<(CapacityQueryResult, RouteDiff)> {
+        self.withdraw(previous);
+
+        if let Some(result) = self.query_with_pce(query, previous.pce_load, true) {
+            let diff = RouteDiff::new(previous, &result.path);
+            Some((result, diff))
+        } else {
+            self.update(previous);
+            None
+        }
+    }
 }
 
 impl<PotCustomized: TDPotential> CapacityServerOps for CapacityServer<PotCustomized> {
     fn distance(&mut self, query: &TDQuery<u32>) -> DistanceMeasure {
-        Self::distance_internal(&mut self.dijkstra, &self.graph, &mut self.customized, &mut self.result_valid, query)
+        Self::distance_internal(
+            &mut self.dijkstra,
+            &self.graph,
+            &mut self.customized,
+            &mut self.result_valid,
+            &mut self.search_space,
+            self.restrictions.as_ref(),
+            self.vehicle_class,
+            query,
+        )
+    }
+
+    fn result_valid(&self) -> bool {
+        self.result_valid
     }
 
     fn update(&mut self, path: &PathResult) {
-        self.graph.increase_weights(&path.edge_path, &path.departure);
+        self.graph.increase_weights(&path.edge_path, &path.departure, path.pce_load);
+    }
+
+    fn withdraw(&mut self, path: &PathResult) {
+        self.graph.decrease_weights(&path.edge_path, &path.departure, path.pce_load);
     }
 
     fn path(&self, query: &TDQuery<Timestamp>) -> PathResult {
@@ -314,73 +899,181 @@ impl<PotCustomized: TDPotential> CapacityServerOps for CapacityServer<PotCustomi
     fn path_distance(&self, edge_path: &Vec<EdgeId>, query_start: Timestamp) -> Weight {
         self.path_distance_internal(edge_path, query_start)
     }
+
+    fn path_breakdown(&self, edge_path: &Vec<EdgeId>, query_start: Timestamp) -> Vec<PathSegment> {
+        self.path_breakdown_internal(edge_path, query_start)
+    }
 }
 
-impl CapacityServerOps for CapacityServer<CustomizedMultiMetrics> {
+impl<C: CCHT, E: Epoch> CapacityServerOps for CapacityServer<CustomizedMultiMetrics<C, E>> {
     fn distance(&mut self, query: &TDQuery<Timestamp>) -> DistanceMeasure {
-        let mut pot = MultiMetricPotential::prepare(&mut self.customized);
+        if self.needs_full_customization {
+            // the customized lower bounds may exceed the decreased travel times, run without potential until re-customized
+            return Self::distance_internal(
+                &mut self.dijkstra,
+                &self.graph,
+                &mut ZeroPotential(),
+                &mut self.result_valid,
+                &mut self.search_space,
+                self.restrictions.as_ref(),
+                self.vehicle_class,
+                query,
+            );
+        }
 
-        Self::distance_internal(&mut self.dijkstra, &self.graph, &mut pot, &mut self.result_valid, query)
+        let pot = MultiMetricPotential::prepare(&mut self.customized);
+
+        if let Some(threshold) = self.hybrid_threshold {
+            let mut pot = HybridPotential::new(ZeroPotential(), pot, threshold);
+            let result = Self::distance_internal(
+                &mut self.dijkstra,
+                &self.graph,
+                &mut pot,
+                &mut self.result_valid,
+                &mut self.search_space,
+                self.restrictions.as_ref(),
+                self.vehicle_class,
+                query,
+            );
+            self.num_hybrid_upgrades += pot.upgraded() as usize;
+            result
+        } else {
+            let mut pot = pot;
+            Self::distance_internal(
+                &mut self.dijkstra,
+                &self.graph,
+                &mut pot,
+                &mut self.result_valid,
+                &mut self.search_space,
+                self.restrictions.as_ref(),
+                self.vehicle_class,
+                query,
+            )
+        }
+    }
+
+    fn result_valid(&self) -> bool {
+        self.result_valid
     }
 
     fn update(&mut self, path: &PathResult) {
-        self.update_valid = self
+        // all edges are checked, the violated ones can be repaired individually (see `repair_upper_bound`)
+        let violations = self
             .graph
-            .increase_weights(&path.edge_path, &path.departure)
+            .increase_weights(&path.edge_path, &path.departure, path.pce_load)
             .iter()
-            .all(|&(edge_id, edge_lower, edge_upper)| {
-                if let Some(shortcut_id) = self.customized.orig_edge_to_forward_shortcut[edge_id as usize] {
-                    let (lower_bound, upper_bound) = self.customized.forward_cch_bounds[shortcut_id as usize];
-
-                    debug_assert!(lower_bound <= edge_lower);
-                    if upper_bound < edge_upper {
-                        println!("Bound violated: Found {}, expected <= {}", edge_upper, upper_bound);
-                        return false;
-                    }
-                }
-
-                if let Some(shortcut_id) = self.customized.orig_edge_to_backward_shortcut[edge_id as usize] {
-                    let (lower_bound, upper_bound) = self.customized.backward_cch_bounds[shortcut_id as usize];
-
-                    debug_assert!(lower_bound <= edge_lower);
+            .filter(|&&(edge_id, edge_lower, edge_upper)| {
+                let forward =
+                    self.customized.orig_edge_to_forward_shortcut[edge_id as usize].map(|shortcut_id| self.customized.forward_cch_bounds[shortcut_id as usize]);
+                let backward = self.customized.orig_edge_to_backward_shortcut[edge_id as usize]
+                    .map(|shortcut_id| self.customized.backward_cch_bounds[shortcut_id as usize]);
+
+                [forward, backward].iter().flatten().any(|&(lower_bound, upper_bound)| {
+                    debug_assert!(self.needs_full_customization || lower_bound <= edge_lower);
                     if upper_bound < edge_upper {
                         println!("Bound violated: Found {}, expected <= {}", edge_upper, upper_bound);
-                        return false;
+                        return true;
                     }
-                }
+                    false
+                })
+            })
+            .map(|&(edge_id, _, _)| edge_id)
+            .collect::<Vec<EdgeId>>();
+
+        if !violations.is_empty() {
+            self.update_valid = false;
+            if let Some(upper_bound_violations) = self.upper_bound_violations.as_mut() {
+                upper_bound_violations.extend(violations);
+            }
+        }
+    }
 
-                true
-            });
+    fn withdraw(&mut self, path: &PathResult) {
+        // the lowerbound and interval metrics may now overestimate the travel times
+        self.graph.decrease_weights(&path.edge_path, &path.departure, path.pce_load);
+        self.require_full_customization();
     }
 
     fn path(&self, query: &TDQuery<Timestamp>) -> PathResult {
         self.path_internal(query)
     }
 
+    fn set_target_search_sharing(&mut self, share_target_searches: bool) {
+        self.customized.potential_context.set_target_search_sharing(share_target_searches);
+    }
+
     fn path_distance(&self, edge_path: &Vec<EdgeId>, query_start: Timestamp) -> u32 {
         self.path_distance_internal(edge_path, query_start)
     }
+
+    fn path_breakdown(&self, edge_path: &Vec<EdgeId>, query_start: Timestamp) -> Vec<PathSegment> {
+        self.path_breakdown_internal(edge_path, query_start)
+    }
 }
 
-impl CapacityServerOps for CapacityServer<CustomizedCorridorLowerbound> {
+impl<E: Epoch> CapacityServerOps for CapacityServer<CustomizedCorridorLowerbound<E>> {
     fn distance(&mut self, query: &TDQuery<Timestamp>) -> DistanceMeasure {
-        let mut pot = CorridorLowerboundPotential::prepare_capacity(&mut self.customized);
+        if self.needs_full_customization {
+            // the customized lower bounds may exceed the decreased travel times, run without potential until re-customized
+            return Self::distance_internal(
+                &mut self.dijkstra,
+                &self.graph,
+                &mut ZeroPotential(),
+                &mut self.result_valid,
+                &mut self.search_space,
+                self.restrictions.as_ref(),
+                self.vehicle_class,
+                query,
+            );
+        }
 
-        Self::distance_internal(&mut self.dijkstra, &self.graph, &mut pot, &mut self.result_valid, query)
+        let pot = CorridorLowerboundPotential::prepare_capacity(&mut self.customized);
+
+        if let Some(threshold) = self.hybrid_threshold {
+            let mut pot = HybridPotential::new(ZeroPotential(), pot, threshold);
+            let result = Self::distance_internal(
+                &mut self.dijkstra,
+                &self.graph,
+                &mut pot,
+                &mut self.result_valid,
+                &mut self.search_space,
+                self.restrictions.as_ref(),
+                self.vehicle_class,
+                query,
+            );
+            self.num_hybrid_upgrades += pot.upgraded() as usize;
+            result
+        } else {
+            let mut pot = pot;
+            Self::distance_internal(
+                &mut self.dijkstra,
+                &self.graph,
+                &mut pot,
+                &mut self.result_valid,
+                &mut self.search_space,
+                self.restrictions.as_ref(),
+                self.vehicle_class,
+                query,
+            )
+        }
+    }
+
+    fn result_valid(&self) -> bool {
+        self.result_valid
     }
 
     fn update(&mut self, path: &PathResult) {
         debug_assert!(self.customized.customized_bounds.is_some());
         let customized_bounds = self.customized.customized_bounds.as_ref().unwrap();
 
-        self.update_valid = self
+        let valid = self
             .graph
-            .increase_weights(&path.edge_path, &path.departure)
+            .increase_weights(&path.edge_path, &path.departure, path.pce_load)
             .iter()
             .all(|&(edge_id, lower_bound, upper_bound)| {
                 debug_assert!(upper_bound > 0);
                 if let Some(shortcut_id) = customized_bounds.orig_edge_to_forward_shortcut[edge_id as usize] {
-                    debug_assert!(customized_bounds.upward[shortcut_id as usize].0 <= lower_bound);
+                    debug_assert!(self.needs_full_customization || customized_bounds.upward[shortcut_id as usize].0 <= lower_bound);
                     if customized_bounds.upward[shortcut_id as usize].1 < upper_bound {
                         println!(
                             "Bound violated: Found {}, expected <= {}",
@@ -391,7 +1084,7 @@ impl CapacityServerOps for CapacityServer<CustomizedCorridorLowerbound> {
                 }
 
                 if let Some(shortcut_id) = customized_bounds.orig_edge_to_backward_shortcut[edge_id as usize] {
-                    debug_assert!(customized_bounds.downward[shortcut_id as usize].0 <= lower_bound);
+                    debug_assert!(self.needs_full_customization || customized_bounds.downward[shortcut_id as usize].0 <= lower_bound);
                     if customized_bounds.downward[shortcut_id as usize].1 < upper_bound {
                         println!(
                             "Bound violated: Found {}, expected <= {}",
@@ -403,6 +1096,16 @@ impl CapacityServerOps for CapacityServer<CustomizedCorridorLowerbound> {
 
                 true
             });
+
+        if !valid {
+            self.invalidate_update();
+        }
+    }
+
+    fn withdraw(&mut self, path: &PathResult) {
+        // the lower bounds of the corridor may now overestimate the travel times
+        self.graph.decrease_weights(&path.edge_path, &path.departure, path.pce_load);
+        self.require_full_customization();
     }
 
     fn path(&self, query: &TDQuery<Timestamp>) -> PathResult {
@@ -412,4 +1115,8 @@ impl CapacityServerOps for CapacityServer<CustomizedCorridorLowerbound> {
     fn path_distance(&self, edge_path: &Vec<EdgeId>, query_start: Timestamp) -> u32 {
         self.path_distance_internal(edge_path, query_start)
     }
+
+    fn path_breakdown(&self, edge_path: &Vec<EdgeId>, query_start: Timestamp) -> Vec<PathSegment> {
+        self.path_breakdown_internal(edge_path, query_start)
+    }
 }
@@ -0,0 +1,60 @@
+use crate::graph::capacity_graph::CapacityGraph;
+use rust_road_router::algo::contraction_hierarchy::query::Server;
+use rust_road_router::algo::contraction_hierarchy::{contract, greedy_order};
+use rust_road_router::algo::{GenQuery, Query, QueryServer};
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{EdgeId, EdgeIdGraph, EdgeIdT, FirstOutGraph, Graph, NodeId, Weight};
+
+/// Static baseline: a classic contraction hierarchy on a snapshot of the capacity graph's weights.
+/// Node order and shortcuts are recomputed from scratch on each rebuild.
+pub struct StaticCHServer {
+    server: Server,
+}
+
+impl StaticCHServer {
+    pub fn new(graph: &CapacityGraph, timestamp: Timestamp) -> Self {
+        Self {
+            server: Self::build(graph, timestamp),
+        }
+    }
+
+    /// rebuild the hierarchy using the current weights of the graph at the given timestamp
+    pub fn rebuild(&mut self, graph: &CapacityGraph, timestamp: Timestamp) {
+        self.server = Self::build(graph, timestamp);
+    }
+
+    fn build(graph: &CapacityGraph, timestamp: Timestamp) -> Server {
+        let snapshot = graph_at_timestamp(graph, timestamp);
+        let order = greedy_order(&snapshot);
+        Server::new(contract(&snapshot, order.clone()), order)
+    }
+
+    /// returns the shortest path as edge ids of the capacity graph
+    pub fn query(&mut self, graph: &CapacityGraph, from: NodeId, to: NodeId) -> Option<Vec<EdgeId>> {
+        self.server
+            .query(Query::new(from, to, 0))
+            .node_path()
+            .map(|path| node_path_to_edge_path(graph, &path))
+    }
+}
+
+/// static snapshot of the graph's travel times at the given timestamp
+pub fn graph_at_timestamp(graph: &CapacityGraph, ts: Timestamp) -> FirstOutGraph<&[EdgeId], &[NodeId], Vec<Weight>> {
+    let weights = (0..graph.num_arcs() as EdgeId)
+        .map(|e| graph.travel_time_function(e).eval(ts))
+        .collect::<Vec<Weight>>();
+    FirstOutGraph::new(graph.first_out(), graph.head(), weights)
+}
+
+/// map a node path to edge ids, parallel edges are resolved by their free-flow travel time
+pub fn node_path_to_edge_path(graph: &CapacityGraph, path: &[NodeId]) -> Vec<EdgeId> {
+    path.windows(2)
+        .map(|edge| {
+            graph
+                .edge_indices(edge[0], edge[1])
+                .min_by_key(|&EdgeIdT(e)| graph.free_flow_time()[e as usize])
+                .map(|EdgeIdT(e)| e)
+                .unwrap()
+        })
+        .collect::<Vec<EdgeId>>()
+}
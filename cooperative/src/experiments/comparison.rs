@@ -0,0 +1,538 @@
+//! Shared driver of the `compare_static_cooperative*` experiments.
+//!
+//! Queries are executed on several cooperative servers (one per bucket count) in parallel.
+//! Static baselines implement `StaticVariant` and are attached to the last cooperative server, whose graph
+//! provides their weights whenever they are updated. After each evaluation step, the paths of all servers
+//! are evaluated on the graph of the last cooperative server, i.e. it should have the highest bucket count.
+//! Additionally, the vehicle-hours and vehicle-kilometers per bucket of each cooperative server are recorded.
+
+use crate::dijkstra::potentials::multi_metric_potential::customization::CustomizedMultiMetrics;
+use crate::dijkstra::server::{CapacityServer, CapacityServerOps};
+use crate::dijkstra::static_ch_server::{graph_at_timestamp, StaticCHServer};
+use crate::experiments::query_records::QueryRecord;
+use crate::graph::capacity_graph::CapacityGraph;
+use crate::graph::system_statistics::IntervalStatistics;
+use rayon::prelude::*;
+use rust_road_router::algo::customizable_contraction_hierarchy::query::Server as CCHServer;
+use rust_road_router::algo::customizable_contraction_hierarchy::{customize, customize_perfect, DirectedCCH, CCH};
+use rust_road_router::algo::{GenQuery, Query, QueryServer, TDQuery};
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{EdgeId, FirstOutGraph, Weight, INFINITY};
+use rust_road_router::report::measure;
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::ops::Add;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// A static baseline whose weights are only updated every `update_frequency` queries
+pub trait StaticVariant: Send + Sync {
+    fn type_name(&self) -> String;
+
+    fn update_frequency(&self) -> u32;
+
+    /// re-build the weights from the cooperative graph at the given timestamp
+    fn update(&mut self, graph: &CapacityGraph, timestamp: Timestamp);
+
+    /// shortest path on the current weights, given as edge path of the cooperative graph
+    fn query(&mut self, graph: &CapacityGraph, query: &TDQuery<Timestamp>) -> Option<Vec<EdgeId>>;
+}
+
+/// CCH, re-customized with the weights at the current departure
+pub struct CCHVariant<'a> {
+    cch: &'a CCH,
+    server: CCHServer<DirectedCCH, DirectedCCH>,
+    // weights of the latest customization, required to resolve parallel edges
+    weights: Vec<Weight>,
+    update_frequency: u32,
+}
+
+impl<'a> CCHVariant<'a> {
+    pub fn new(cch: &'a CCH, graph: &CapacityGraph, update_frequency: u32) -> Self {
+        let lower_bound = graph_at_timestamp(graph, 0);
+        let server = CCHServer::new(customize_perfect(customize(cch, &lower_bound)));
+
+        Self {
+            cch,
+            server,
+            weights: lower_bound.weight().to_vec(),
+            update_frequency,
+        }
+    }
+}
+
+impl StaticVariant for CCHVariant<'_> {
+    fn type_name(&self) -> String {
+        format!("cch-{}", self.update_frequency)
+    }
+
+    fn update_frequency(&self) -> u32 {
+        self.update_frequency
+    }
+
+    fn update(&mut self, graph: &CapacityGraph, timestamp: Timestamp) {
+        let cch_graph = graph_at_timestamp(graph, timestamp);
+        self.server.update(customize_perfect(customize(self.cch, &cch_graph)));
+        self.weights = cch_graph.weight().to_vec();
+    }
+
+    fn query(&mut self, graph: &CapacityGraph, query: &TDQuery<Timestamp>) -> Option<Vec<EdgeId>> {
+        // parallel edges are resolved by the same weights as in the customization
+        let metric = FirstOutGraph::new(graph.first_out(), graph.head(), &self.weights[..]);
+        let mut result = self.server.query(Query::new(query.from, query.to, 0));
+        result.distance().map(|_| result.data().orig_edge_path(&metric))
+    }
+}
+
+/// classic CH, rebuilt from scratch (including the node order) on each update
+pub struct CHVariant {
+    server: StaticCHServer,
+    update_frequency: u32,
+}
+
+impl CHVariant {
+    pub fn new(graph: &CapacityGraph, update_frequency: u32) -> Self {
+        Self {
+            server: StaticCHServer::new(graph, 0),
+            update_frequency,
+        }
+    }
+}
+
+impl StaticVariant for CHVariant {
+    fn type_name(&self) -> String {
+        format!("ch-{}", self.update_frequency)
+    }
+
+    fn update_frequency(&self) -> u32 {
+        self.update_frequency
+    }
+
+    fn update(&mut self, graph: &CapacityGraph, timestamp: Timestamp) {
+        self.server.rebuild(graph, timestamp);
+    }
+
+    fn query(&mut self, graph: &CapacityGraph, query: &TDQuery<Timestamp>) -> Option<Vec<EdgeId>> {
+        self.server.query(graph, query.from, query.to)
+    }
+}
+
+/// Aggregated statistics of a single server after an evaluation step
+#[derive(Clone)]
+pub struct ComparisonStatisticEntry {
+    pub query_type: String,
+    pub query_time: Duration,
+    pub customization_time: Duration,
+    pub num_runs: u32,
+    pub num_actual_runs: u32,
+    pub total_dist: u64,
+    pub avg_dist: u64,
+}
+
+/// System-level traffic of a single bucket of a cooperative server after an evaluation step
+#[derive(Clone)]
+pub struct IntervalStatisticEntry {
+    pub query_type: String,
+    pub num_runs: u32,
+    pub interval: IntervalStatistics,
+}
+
+/// timings, paths and records collected for one server
+struct VariantRun {
+    type_name: String,
+    cust_time: Duration,
+    query_time: Duration,
+    query_paths: Vec<Vec<EdgeId>>,
+    query_departures: Vec<Timestamp>,
+    query_records: Vec<QueryRecord>,
+}
+
+impl VariantRun {
+    fn new(type_name: String, init_time: Duration) -> Self {
+        Self {
+            type_name,
+            cust_time: init_time,
+            query_time: Duration::ZERO,
+            query_paths: vec![],
+            query_departures: vec![],
+            query_records: vec![],
+        }
+    }
+
+    fn add_path(&mut self, path: Vec<EdgeId>, departure: Timestamp) {
+        self.query_paths.push(path);
+        self.query_departures.push(departure);
+    }
+
+    fn evaluate(&self, evaluation_server: &CapacityServer<CustomizedMultiMetrics>, num_runs: u32) -> ComparisonStatisticEntry {
+        let total_dist = sum_path_distances(evaluation_server, &self.query_paths, &self.query_departures);
+        let num_actual_runs = self.query_departures.len() as u32;
+        let avg_dist = total_dist / num_actual_runs.max(1) as u64;
+
+        println!("------------------------------------------");
+        println!("Statistics of {} after {} runs:", self.type_name, num_runs);
+        println!(
+            "Customization: {}s, Query: {}s, total distance: {} ({} runs -> avg: {})",
+            self.cust_time.as_secs_f64(),
+            self.query_time.as_secs_f64(),
+            total_dist,
+            num_actual_runs,
+            avg_dist,
+        );
+
+        ComparisonStatisticEntry {
+            query_type: self.type_name.clone(),
+            query_time: self.query_time,
+            customization_time: self.cust_time,
+            num_runs,
+            num_actual_runs,
+            total_dist,
+            avg_dist,
+        }
+    }
+}
+
+/// a cooperative server and the static variants taking their weights from its graph
+struct CooperativeEntry<'a> {
+    server: CapacityServer<CustomizedMultiMetrics>,
+    run: VariantRun,
+    static_variants: Vec<(Box<dyn StaticVariant + 'a>, VariantRun)>,
+}
+
+pub struct ComparisonExperiment<'a> {
+    intervals: Vec<(Timestamp, Timestamp)>,
+    pot_num_metrics: usize,
+    pot_update_frequency: u32,
+    query_records: bool,
+    entries: Vec<CooperativeEntry<'a>>,
+    interval_results: Vec<IntervalStatisticEntry>,
+}
+
+impl<'a> ComparisonExperiment<'a> {
+    /// `pot_update_frequency`: number of queries after which the potentials of the cooperative servers are re-customized
+    pub fn new(intervals: Vec<(Timestamp, Timestamp)>, pot_num_metrics: usize, pot_update_frequency: u32, query_records: bool) -> Self {
+        Self {
+            intervals,
+            pot_num_metrics,
+            pot_update_frequency,
+            query_records,
+            entries: vec![],
+            interval_results: vec![],
+        }
+    }
+
+    pub fn add_cooperative(&mut self, server: CapacityServer<CustomizedMultiMetrics>, init_time: Duration) {
+        let type_name = format!("coop-{}", server.borrow_graph().num_buckets());
+        self.entries.push(CooperativeEntry {
+            server,
+            run: VariantRun::new(type_name, init_time),
+            static_variants: vec![],
+        });
+    }
+
+    /// Graph of the last cooperative server, i.e. the one static variants are attached to
+    pub fn reference_graph(&self) -> &CapacityGraph {
+        self.entries.last().expect("no cooperative server added").server.borrow_graph()
+    }
+
+    /// Attach a static variant to the last cooperative server
+    pub fn add_static(&mut self, variant: Box<dyn StaticVariant + 'a>, init_time: Duration) {
+        let entry = self.entries.last_mut().expect("static variants require a cooperative server");
+        let run = VariantRun::new(variant.type_name(), init_time);
+        entry.static_variants.push((variant, run));
+    }
+
+    /// Execute all queries and evaluate the servers every `evaluation_frequency` queries.
+    /// `on_evaluation` receives the number of processed queries and the statistics of the current evaluation step.
+    pub fn run(
+        &mut self,
+        queries: &[TDQuery<Timestamp>],
+        evaluation_frequency: u32,
+        mut on_evaluation: impl FnMut(u32, &[ComparisonStatisticEntry]) -> Result<(), Box<dyn Error>>,
+    ) -> Result<Vec<ComparisonStatisticEntry>, Box<dyn Error>> {
+        assert!(!self.entries.is_empty(), "no cooperative server added");
+        assert_eq!(
+            queries.len() as u32 % evaluation_frequency,
+            0,
+            "Number of queries ({}) must be divisible by the evaluation frequency ({})",
+            queries.len(),
+            evaluation_frequency
+        );
+        let evaluation_breakpoints = (0..=(queries.len() as u32 / evaluation_frequency))
+            .map(|i| i * evaluation_frequency)
+            .collect::<Vec<u32>>();
+
+        let mut results = Vec::new();
+
+        for a in evaluation_breakpoints.windows(2) {
+            // parallel query execution for all cooperative servers
+            let (intervals, pot_num_metrics, pot_update_frequency, query_records) =
+                (&self.intervals, self.pot_num_metrics, self.pot_update_frequency, self.query_records);
+            self.entries.par_iter_mut().for_each(|entry| {
+                (a[0] as usize..a[1] as usize)
+                    .zip(queries[a[0] as usize..a[1] as usize].iter())
+                    .for_each(|(idx, query)| {
+                        if (idx + 1) % 10000 == 0 {
+                            entry.print_progress(idx, queries.len());
+                        }
+
+                        entry.cooperative_query(idx, query, intervals, pot_num_metrics, pot_update_frequency, query_records);
+                        entry.static_queries(idx, query, query_records);
+                    });
+            });
+
+            // evaluate the results on the server with the highest bucket count
+            let evaluation_server = &self.entries.last().unwrap().server;
+            debug_assert_eq!(
+                evaluation_server.borrow_graph().num_buckets(),
+                self.entries.iter().map(|entry| entry.server.borrow_graph().num_buckets()).max().unwrap()
+            );
+
+            let evaluation_start = Instant::now();
+            let current_results = self
+                .entries
+                .par_iter()
+                .flat_map(|entry| {
+                    let mut temp_results = vec![entry.run.evaluate(evaluation_server, a[1])];
+                    temp_results.extend(entry.static_variants.iter().map(|(_, run)| run.evaluate(evaluation_server, a[1])));
+                    temp_results
+                })
+                .collect::<Vec<ComparisonStatisticEntry>>();
+
+            println!("------------------------------------------");
+            println!("Evaluation took {}s", evaluation_start.elapsed().as_secs_f64());
+
+            on_evaluation(a[1], &current_results)?;
+            results.extend_from_slice(&current_results);
+
+            for entry in &self.entries {
+                let intervals = entry.server.borrow_graph().interval_statistics();
+                self.interval_results.extend(intervals.into_iter().map(|interval| IntervalStatisticEntry {
+                    query_type: entry.run.type_name.clone(),
+                    num_runs: a[1],
+                    interval,
+                }));
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// vehicle-hours and vehicle-kilometers per bucket of the cooperative servers, recorded after each evaluation step
+    pub fn interval_statistics(&self) -> &[IntervalStatisticEntry] {
+        &self.interval_results
+    }
+
+    /// per-query records of all servers, only collected if enabled
+    pub fn query_records(&self) -> Vec<QueryRecord> {
+        self.entries
+            .iter()
+            .flat_map(|entry| {
+                entry
+                    .run
+                    .query_records
+                    .iter()
+                    .chain(entry.static_variants.iter().flat_map(|(_, run)| run.query_records.iter()))
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+impl CooperativeEntry<'_> {
+    fn print_progress(&self, idx: usize, num_queries: usize) {
+        println!("-----------------");
+        println!("{}: Finished {} of {} queries", self.run.type_name, idx + 1, num_queries);
+        println!(
+            "Time: {}s customization, {}s queries",
+            self.run.cust_time.as_secs_f64(),
+            self.run.query_time.as_secs_f64()
+        );
+        if !self.static_variants.is_empty() {
+            println!(
+                "Static ({:?}): {:?} customization, {:?} query",
+                self.static_variants.iter().map(|(_, run)| run.type_name.clone()).collect::<Vec<String>>(),
+                self.static_variants.iter().map(|(_, run)| run.cust_time.as_secs_f64()).collect::<Vec<f64>>(),
+                self.static_variants.iter().map(|(_, run)| run.query_time.as_secs_f64()).collect::<Vec<f64>>(),
+            )
+        }
+        println!("-----------------");
+    }
+
+    fn cooperative_query(
+        &mut self,
+        idx: usize,
+        query: &TDQuery<Timestamp>,
+        intervals: &Vec<(Timestamp, Timestamp)>,
+        pot_num_metrics: usize,
+        pot_update_frequency: u32,
+        query_records: bool,
+    ) {
+        let run = &mut self.run;
+        let mut coop_updated = false;
+        let mut num_recustomizations = 0;
+
+        // check for regular customization of coop server
+        if (idx as u32 + 1) % pot_update_frequency == 0 {
+            let (_, time) = measure(|| self.server.customize(intervals, pot_num_metrics));
+            run.cust_time = run.cust_time.add(time);
+            coop_updated = true;
+            num_recustomizations += 1;
+        }
+
+        // repeat query if it fails the first time, panic after second fail
+        loop {
+            let (coop_result, time) = measure(|| self.server.query_measured(query, true));
+            run.query_time = run.query_time.add(time);
+
+            // check if potential needs to be updated
+            if !self.server.result_valid() || !self.server.update_valid() {
+                if coop_updated {
+                    // panic to avoid infinite loops
+                    panic!("{} - failed twice in the same step!", &run.type_name);
+                } else {
+                    // repair of upper bounds, re-customization if travel times decreased
+                    coop_updated = true;
+                    num_recustomizations += 1;
+                    println!("-- {} - potential update after {} steps", &run.type_name, idx + 1);
+                    let (_, time) = measure(|| self.server.restore_potential(intervals, pot_num_metrics));
+                    run.cust_time = run.cust_time.add(time);
+                }
+            }
+
+            if self.server.result_valid() {
+                if query_records {
+                    let mut record = QueryRecord::new(&run.type_name, idx, query.from, query.to, query.departure).with_measured_result(&coop_result);
+                    record.num_recustomizations = num_recustomizations;
+                    run.query_records.push(record);
+                }
+
+                if let Some(result) = coop_result.query_result {
+                    run.add_path(result.path.edge_path, query.departure);
+                }
+                break;
+            }
+        }
+    }
+
+    fn static_queries(&mut self, idx: usize, query: &TDQuery<Timestamp>, query_records: bool) {
+        let server = &self.server;
+        let graph = server.borrow_graph();
+
+        self.static_variants.iter_mut().for_each(|(variant, run)| {
+            let mut num_recustomizations = 0;
+
+            // check if customization is required
+            if (idx + 1) as u32 % variant.update_frequency() == 0 {
+                num_recustomizations += 1;
+                println!(
+                    "Updating {} after {} queries (frequency: {}, timestamp: {})",
+                    &run.type_name,
+                    idx + 1,
+                    variant.update_frequency(),
+                    query.departure
+                );
+
+                let (_, time) = measure(|| variant.update(graph, query.departure));
+                run.cust_time = run.cust_time.add(time);
+            }
+
+            // execute query and re-build path
+            let (result, time) = measure(|| variant.query(graph, query));
+            run.query_time = run.query_time.add(time);
+
+            if query_records {
+                let mut record = QueryRecord::new(&run.type_name, idx, query.from, query.to, query.departure);
+                record.time_query = time;
+                // static paths are evaluated on the current travel times, as done by the cooperative server
+                record.distance = result
+                    .as_ref()
+                    .map(|edge_path| server.path_distance(edge_path, query.departure))
+                    .filter(|&dist| dist != INFINITY);
+                record.path_length = result.as_ref().map(|edge_path| edge_path.len());
+                record.num_recustomizations = num_recustomizations;
+                run.query_records.push(record);
+            }
+
+            if let Some(edge_path) = result {
+                run.add_path(edge_path, query.departure);
+            }
+        });
+    }
+}
+
+/// Parse a comma separated list of update frequencies, 0 disables updates. The result is sorted and free of duplicates.
+pub fn parse_update_frequencies(frequencies: &str) -> Vec<u32> {
+    let mut frequencies = frequencies
+        .split(',')
+        .filter_map(|val| val.trim().parse::<u32>().ok())
+        .map(|val| if val == 0 { INFINITY } else { val })
+        .collect::<Vec<u32>>();
+    frequencies.sort_unstable();
+    frequencies.dedup();
+    frequencies
+}
+
+/// Write the statistics of all evaluation steps as csv file
+pub fn write_comparison_results(results: &[ComparisonStatisticEntry], path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut file = File::create(path)?;
+
+    let header = "type,cust_time,query_time,num_runs,num_actual_runs,total_dist,avg_dist\n";
+    file.write_all(header.as_bytes())?;
+
+    for entry in results {
+        let line = format!(
+            "{},{},{},{},{},{},{}\n",
+            entry.query_type,
+            entry.customization_time.as_secs_f64(),
+            entry.query_time.as_secs_f64(),
+            entry.num_runs,
+            entry.num_actual_runs,
+            entry.total_dist,
+            entry.avg_dist
+        );
+        file.write_all(line.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Write the interval statistics of all evaluation steps as csv file
+pub fn write_interval_statistics(results: &[IntervalStatisticEntry], path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut file = File::create(path)?;
+
+    let header = "type,num_runs,interval_start,interval_end,vehicle_hours,vehicle_km,avg_speed\n";
+    file.write_all(header.as_bytes())?;
+
+    for entry in results {
+        let line = format!(
+            "{},{},{},{},{},{},{}\n",
+            entry.query_type,
+            entry.num_runs,
+            entry.interval.start,
+            entry.interval.end,
+            entry.interval.vehicle_hours,
+            entry.interval.vehicle_km,
+            entry.interval.avg_speed()
+        );
+        file.write_all(line.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+fn sum_path_distances(evaluation_server: &CapacityServer<CustomizedMultiMetrics>, paths: &[Vec<EdgeId>], departures: &[Timestamp]) -> u64 {
+    debug_assert_eq!(paths.len(), departures.len());
+
+    paths
+        .iter()
+        .zip(departures.iter())
+        .map(|(path, &departure)| {
+            Some(evaluation_server.path_distance(path, departure))
+                .filter(|&dist| dist != INFINITY)
+                .map(|dist| dist as u64)
+                .unwrap_or(0)
+        })
+        .sum::<u64>()
+}
@@ -1,2 +1,7 @@
+pub mod comparison;
 pub mod queries;
+pub mod query_records;
+#[cfg(feature = "sqlite")]
+pub mod result_sink;
+pub mod scenario;
 pub mod types;
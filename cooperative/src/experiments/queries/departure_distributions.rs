@@ -55,11 +55,110 @@ impl DepartureDistribution for NormalDeparture {
 
 // TODO find commonly used distributions in literature ;)
 
+const HOUR: f64 = 3_600_000.0;
+
+/// Gaussian departure peak, `time` and `spread` are given in hours
+#[derive(Debug, Clone, Copy)]
+pub struct DeparturePeak {
+    pub time: f64,
+    pub spread: f64,
+    pub weight: f64,
+}
+
+impl DeparturePeak {
+    pub fn new(time: f64, spread: f64, weight: f64) -> Self {
+        assert!(spread > 0.0 && weight >= 0.0);
+        Self { time, spread, weight }
+    }
+}
+
+/// Configurable rush hour scheme: two Gaussian peaks (morning and evening) on top of a uniform floor.
+/// Departures of the peaks wrap around midnight.
+///
+/// `new()` uses peaks at 8:00 and 17:00, which can be overwritten by the environment variable `BIMODAL_DEPARTURE`,
+/// given as `<time_1>,<spread_1>,<weight_1>,<time_2>,<spread_2>,<weight_2>,<floor_weight>` (times in hours).
+pub struct BimodalDeparture {
+    peaks: [(DeparturePeak, Normal<f64>); 2],
+    floor_weight: f64,
+}
+
+impl BimodalDeparture {
+    pub fn with_peaks(morning: DeparturePeak, evening: DeparturePeak, floor_weight: f64) -> Self {
+        assert!(floor_weight >= 0.0 && morning.weight + evening.weight + floor_weight > 0.0);
+        let distribution = |peak: DeparturePeak| (peak, Normal::new(peak.time * HOUR, peak.spread * HOUR).unwrap());
+
+        Self {
+            peaks: [distribution(morning), distribution(evening)],
+            floor_weight,
+        }
+    }
+}
+
+impl DepartureDistribution for BimodalDeparture {
+    fn new() -> Self {
+        let params = std::env::var("BIMODAL_DEPARTURE").map_or(vec![8.0, 1.5, 0.4, 17.0, 2.0, 0.45, 0.15], |params| {
+            params.split(',').map(|param| param.trim().parse::<f64>().unwrap()).collect()
+        });
+        assert_eq!(params.len(), 7, "`BIMODAL_DEPARTURE` must consist of 7 values!");
+
+        Self::with_peaks(
+            DeparturePeak::new(params[0], params[1], params[2]),
+            DeparturePeak::new(params[3], params[4], params[5]),
+            params[6],
+        )
+    }
+
+    fn rand<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Timestamp {
+        // step 1: pick one of the peaks or the floor according to their weights
+        let total_weight = self.peaks[0].0.weight + self.peaks[1].0.weight + self.floor_weight;
+        let val = rng.gen_range(0.0..total_weight);
+
+        // step 2: sample within the chosen component
+        let departure = if val < self.peaks[0].0.weight {
+            self.peaks[0].1.sample(rng)
+        } else if val < self.peaks[0].0.weight + self.peaks[1].0.weight {
+            self.peaks[1].1.sample(rng)
+        } else {
+            return rng.gen_range(0..MAX_BUCKETS);
+        };
+
+        (departure as i64).rem_euclid(MAX_BUCKETS as i64) as Timestamp
+    }
+}
+
 /// trip departures are following a commonly observed rush hour scheme, morning only
-pub struct MorningRushHourDeparture();
+pub struct MorningRushHourDeparture(BimodalDeparture);
+
+impl DepartureDistribution for MorningRushHourDeparture {
+    fn new() -> Self {
+        Self(BimodalDeparture::with_peaks(
+            DeparturePeak::new(8.0, 1.5, 0.85),
+            DeparturePeak::new(17.0, 2.0, 0.0),
+            0.15,
+        ))
+    }
+
+    fn rand<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Timestamp {
+        self.0.rand(rng)
+    }
+}
 
 /// trip departures are following a commonly observed rush hour scheme, afternoon only
-pub struct AfternoonRushHourDeparture();
+pub struct AfternoonRushHourDeparture(BimodalDeparture);
+
+impl DepartureDistribution for AfternoonRushHourDeparture {
+    fn new() -> Self {
+        Self(BimodalDeparture::with_peaks(
+            DeparturePeak::new(8.0, 1.5, 0.0),
+            DeparturePeak::new(17.0, 2.0, 0.85),
+            0.15,
+        ))
+    }
+
+    fn rand<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Timestamp {
+        self.0.rand(rng)
+    }
+}
 
 /// trip departures are following a commonly observed rush hour scheme
 pub struct RushHourDeparture {
@@ -8,141 +8,161 @@ use crate::experiments::queries::population_density_based::{build_population_gri
 use crate::io::io_population_grid::PopulationGridEntry;
 use kdtree::kdtree::Kdtree;
 use rust_road_router::algo::dijkstra::{DefaultOps, DijkstraData, DijkstraInit, DijkstraRun};
-use rust_road_router::datastr::graph::{Link, LinkIterable, NodeId};
+use rust_road_router::datastr::graph::{Link, LinkIterable, NodeId, Weight};
 
+/// power of the first rank class, direct neighbors make no sense!
+pub const MIN_RANK_POW: u32 = 8;
+
+/// Dijkstra-rank queries: each source yields one query per rank class `2^i` (`MIN_RANK_POW <= i <= max_rank_pow`),
+/// so all rank classes are generated from the same sources.
+///
+/// The queries are grouped by rank class, the second result holds the rank power `i` of each query.
 pub fn generate_dijkstra_rank_queries<G: LinkIterable<Link>, D: DepartureDistribution>(
     graph: &G,
     num_queries_per_rank: u32,
     max_rank_pow: u32,
     mut departure_distribution: D,
-) -> Vec<TDQuery<Timestamp>> {
-    let max_rank = 2u32.pow(max_rank_pow);
-
-    // assert that context is valid, i.e. the maximum rank is feasible
-    // this assumption must also hold in release mode!
-    assert!(max_rank_pow < 32, "Max. rank power should be less than 32!");
-    assert!(
-        graph.num_nodes() as u32 > max_rank,
-        "Max. rank power is too high for the current graph (max rank: {}, num nodes: {})",
-        max_rank,
-        graph.num_nodes()
-    );
+) -> (Vec<TDQuery<Timestamp>>, Vec<u32>) {
+    assert_valid_max_rank(graph, max_rank_pow);
 
     // init context
     let mut rng = thread_rng();
     let mut data = DijkstraData::new(graph.num_nodes());
-    let mut queries = vec![TDQuery::new(0, 0, 0); (num_queries_per_rank * (max_rank_pow - 7)) as usize];
+    let mut queries = vec![TDQuery::new(0, 0, 0); (num_queries_per_rank * (max_rank_pow + 1 - MIN_RANK_POW)) as usize];
 
     for query_idx in 0..num_queries_per_rank as usize {
-        let mut result: Option<Vec<NodeId>> = None;
-        let mut source = 0;
-
-        while result.is_none() {
-            let mut rank_nodes = Vec::with_capacity(max_rank_pow as usize);
-
+        let (source, targets) = loop {
             // pick a random start node
-            source = rng.gen_range(0..graph.num_nodes()) as NodeId;
-
-            let mut ops = DefaultOps::default();
-            let mut dijkstra = DijkstraRun::query(graph, &mut data, &mut ops, DijkstraInit::from(source));
-            let mut counter = 0u32;
-            let mut next_rank = 256; // 2^8, direct neighbors make no sense!
-
-            while let Some(node) = dijkstra.next() {
-                counter += 1;
-                if counter == next_rank {
-                    rank_nodes.push(node);
-
-                    if next_rank == max_rank {
-                        result = Some(rank_nodes);
-                        break;
-                    } else {
-                        next_rank *= 2;
-                    }
-                }
+            let source = rng.gen_range(0..graph.num_nodes()) as NodeId;
+            if let Some(targets) = rank_targets(graph, &mut data, source, max_rank_pow) {
+                break (source, targets);
             }
-        }
-
-        result.unwrap().iter().enumerate().for_each(|(rank_idx, &target)| {
-            let query = &mut queries[rank_idx * num_queries_per_rank as usize + query_idx];
-            query.from = source;
-            query.to = target;
-            // pick a random departure in each query!
-            query.departure = departure_distribution.rand(&mut rng);
-        });
+        };
+
+        assign_rank_targets(
+            &mut queries,
+            query_idx,
+            num_queries_per_rank,
+            source,
+            &targets,
+            &mut departure_distribution,
+            &mut rng,
+        );
     }
 
-    queries
+    (queries, query_ranks(num_queries_per_rank, max_rank_pow))
 }
 
+/// Dijkstra-rank queries with sources picked according to the population density, see `generate_dijkstra_rank_queries`
 pub fn generate_population_dijkstra_rank_queries<G: LinkIterable<Link>, D: DepartureDistribution>(
-    longitude: &Vec<f32>,
-    latitude: &Vec<f32>,
+    longitude: &Vec<f64>,
+    latitude: &Vec<f64>,
     grid_tree: &Kdtree<PopulationGridEntry>,
     grid_population: &Vec<u32>,
     graph: &G,
     num_queries_per_rank: u32,
     max_rank_pow: u32,
     mut departure_distribution: D,
-) -> Vec<TDQuery<Timestamp>> {
+) -> (Vec<TDQuery<Timestamp>>, Vec<u32>) {
     // init population grid
     let (vertex_grid, grid_population_intervals, population_counter) = build_population_grid(longitude, latitude, grid_tree, grid_population);
 
-    // init dijkstra-rank structs
-    assert!(max_rank_pow < 32, "Max. rank power should be less than 32!");
-    let max_rank = 2u32.pow(max_rank_pow);
-    assert!(
-        graph.num_nodes() as u32 > max_rank,
-        "Max. rank power is too high for the current graph (max rank: {}, num nodes: {})",
-        max_rank,
-        graph.num_nodes()
-    );
+    assert_valid_max_rank(graph, max_rank_pow);
 
     // init context
     let mut rng = thread_rng();
     let mut data = DijkstraData::new(graph.num_nodes());
-    let mut queries = vec![TDQuery::new(0, 0, 0); (num_queries_per_rank * (max_rank_pow - 7)) as usize];
+    let mut queries = vec![TDQuery::new(0, 0, 0); (num_queries_per_rank * (max_rank_pow + 1 - MIN_RANK_POW)) as usize];
 
     for query_idx in 0..num_queries_per_rank as usize {
-        let mut result: Option<Vec<NodeId>> = None;
-        let mut source = 0;
-
-        while result.is_none() {
-            let mut rank_nodes = Vec::with_capacity(max_rank_pow as usize);
-
+        let (source, targets) = loop {
             // pick a random start node according to population density
             let start_cell_id = find_population_interval(&grid_population_intervals, rng.gen_range(0..population_counter));
             let start_cell_vertex_pos = rng.gen_range(0..vertex_grid[start_cell_id].len());
-            source = vertex_grid[start_cell_id][start_cell_vertex_pos];
-
-            let mut ops = DefaultOps::default();
-            let mut dijkstra = DijkstraRun::query(graph, &mut data, &mut ops, DijkstraInit::from(source));
-            let mut counter = 0u32;
-            let mut next_rank = 256; // 2^8, direct neighbors make no sense!
-
-            while let Some(node) = dijkstra.next() {
-                counter += 1;
-                if counter == next_rank {
-                    rank_nodes.push(node);
-
-                    if next_rank == max_rank {
-                        result = Some(rank_nodes);
-                        break;
-                    } else {
-                        next_rank *= 2;
-                    }
-                }
+            let source = vertex_grid[start_cell_id][start_cell_vertex_pos];
+
+            if let Some(targets) = rank_targets(graph, &mut data, source, max_rank_pow) {
+                break (source, targets);
             }
-        }
+        };
+
+        assign_rank_targets(
+            &mut queries,
+            query_idx,
+            num_queries_per_rank,
+            source,
+            &targets,
+            &mut departure_distribution,
+            &mut rng,
+        );
+    }
 
-        result.unwrap().iter().enumerate().for_each(|(rank_idx, &target)| {
-            let query = &mut queries[rank_idx * num_queries_per_rank as usize + query_idx];
-            query.from = source;
-            query.to = target;
-            // pick a random departure in each query!
-            query.departure = departure_distribution.rand(&mut rng);
-        });
+    (queries, query_ranks(num_queries_per_rank, max_rank_pow))
+}
+
+/// Exact rank targets of a Dijkstra search from `source`, i.e. the `2^i`-th settled node of each rank class.
+/// The source itself has rank 0. Returns `None` if less than `2^max_rank_pow` nodes are reachable.
+pub fn rank_targets<G: LinkIterable<Link>>(graph: &G, data: &mut DijkstraData<Weight>, source: NodeId, max_rank_pow: u32) -> Option<Vec<NodeId>> {
+    let max_rank = 2u32.pow(max_rank_pow);
+    let mut targets = Vec::with_capacity((max_rank_pow + 1 - MIN_RANK_POW) as usize);
+
+    let mut ops = DefaultOps::default();
+    let mut dijkstra = DijkstraRun::query(graph, data, &mut ops, DijkstraInit::from(source));
+    let mut rank = 0u32;
+    let mut next_rank = 2u32.pow(MIN_RANK_POW);
+
+    while let Some(node) = dijkstra.next() {
+        if rank == next_rank {
+            targets.push(node);
+
+            if next_rank == max_rank {
+                return Some(targets);
+            }
+            next_rank *= 2;
+        }
+        rank += 1;
     }
 
-    queries
+    None
+}
+
+fn assert_valid_max_rank<G: LinkIterable<Link>>(graph: &G, max_rank_pow: u32) {
+    // assert that context is valid, i.e. the maximum rank is feasible
+    // this assumption must also hold in release mode!
+    assert!(
+        (MIN_RANK_POW..32).contains(&max_rank_pow),
+        "Max. rank power should be between {} and 31!",
+        MIN_RANK_POW
+    );
+    let max_rank = 2u32.pow(max_rank_pow);
+    assert!(
+        graph.num_nodes() as u32 > max_rank,
+        "Max. rank power is too high for the current graph (max rank: {}, num nodes: {})",
+        max_rank,
+        graph.num_nodes()
+    );
+}
+
+fn assign_rank_targets<D: DepartureDistribution, R: Rng>(
+    queries: &mut [TDQuery<Timestamp>],
+    query_idx: usize,
+    num_queries_per_rank: u32,
+    source: NodeId,
+    targets: &[NodeId],
+    departure_distribution: &mut D,
+    rng: &mut R,
+) {
+    targets.iter().enumerate().for_each(|(rank_idx, &target)| {
+        let query = &mut queries[rank_idx * num_queries_per_rank as usize + query_idx];
+        query.from = source;
+        query.to = target;
+        // pick a random departure in each query!
+        query.departure = departure_distribution.rand(rng);
+    });
+}
+
+fn query_ranks(num_queries_per_rank: u32, max_rank_pow: u32) -> Vec<u32> {
+    (MIN_RANK_POW..=max_rank_pow)
+        .flat_map(|rank_pow| std::iter::repeat(rank_pow).take(num_queries_per_rank as usize))
+        .collect()
 }
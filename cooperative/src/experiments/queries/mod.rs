@@ -5,8 +5,8 @@ use rust_road_router::cli::CliErr;
 use rust_road_router::datastr::graph::time_dependent::Timestamp;
 use rust_road_router::datastr::graph::{FirstOutGraph, Graph};
 
-use crate::experiments::queries::departure_distributions::{DepartureDistribution, NormalDeparture, UniformDeparture};
-use crate::experiments::queries::random_geometric::generate_random_geometric_queries;
+use crate::experiments::queries::departure_distributions::{DepartureDistribution, NormalDeparture, RushHourDeparture, UniformDeparture};
+use crate::experiments::queries::random_geometric::{generate_random_geometric_queries, generate_random_td_geometric_queries};
 use crate::experiments::queries::random_uniform::generate_random_uniform_queries;
 use crate::graph::capacity_graph::CapacityGraph;
 use rand::{thread_rng, Rng};
@@ -22,12 +22,17 @@ pub enum QueryType {
     Uniform,
     UniformRushHourDep,
     UniformNormalDep,
+    UniformBimodalDep,
     Geometric,
     GeometricRushHourDep,
+    GeometricTravelTime,
+    GeometricTravelTimeRushHourDep,
     PopulationUniform,
     PopulationUniformConstantDep,
     PopulationGeometric,
     PopulationGeometricConstantDep,
+    PopulationCommuter,
+    PopulationCommuterRoundTrip,
     DijkstraRank,
     DijkstraRankRushHourDep,
     PopulationDijkstraRank,
@@ -42,12 +47,17 @@ impl FromStr for QueryType {
             "UNIFORM" => Ok(QueryType::Uniform),
             "UNIFORM_RUSH_HOUR" => Ok(QueryType::UniformRushHourDep),
             "UNIFORM_NORMAL_DEPARTURE" => Ok(QueryType::UniformNormalDep),
+            "UNIFORM_BIMODAL" => Ok(QueryType::UniformBimodalDep),
             "GEOMETRIC" => Ok(QueryType::Geometric),
             "GEOMETRIC_RUSH_HOUR" => Ok(QueryType::GeometricRushHourDep),
+            "GEOMETRIC_TRAVEL_TIME" => Ok(QueryType::GeometricTravelTime),
+            "GEOMETRIC_TRAVEL_TIME_RUSH_HOUR" => Ok(QueryType::GeometricTravelTimeRushHourDep),
             "POPULATION_UNIFORM" => Ok(QueryType::PopulationUniform),
             "POPULATION_UNIFORM_CONSTANT_DEPARTURE" => Ok(QueryType::PopulationUniformConstantDep),
             "POPULATION_GEOMETRIC" => Ok(QueryType::PopulationGeometric),
             "POPULATION_GEOMETRIC_CONSTANT_DEPARTURE" => Ok(QueryType::PopulationGeometricConstantDep),
+            "POPULATION_COMMUTER" => Ok(QueryType::PopulationCommuter),
+            "POPULATION_COMMUTER_ROUND_TRIP" => Ok(QueryType::PopulationCommuterRoundTrip),
             "DIJKSTRA_RANK" => Ok(QueryType::DijkstraRank),
             "DIJKSTRA_RANK_RUSH_HOUR" => Ok(QueryType::DijkstraRankRushHourDep),
             "POPULATION_DIJKSTRA_RANK" => Ok(QueryType::PopulationDijkstraRank),
@@ -83,6 +93,8 @@ pub fn generate_queries(graph: &CapacityGraph, query_type: QueryType, num_querie
             let distance_graph = FirstOutGraph::new(graph.first_out(), graph.head(), graph.distance());
             generate_random_geometric_queries(&distance_graph, true, num_queries, UniformDeparture::new())
         }
+        QueryType::GeometricTravelTime => generate_random_td_geometric_queries(graph, num_queries, UniformDeparture::new()),
+        QueryType::GeometricTravelTimeRushHourDep => generate_random_td_geometric_queries(graph, num_queries, RushHourDeparture::new()),
         _ => unimplemented!(),
     }
 }
@@ -4,20 +4,22 @@ use rand::{thread_rng, Rng};
 use rust_road_router::algo::{GenQuery, TDQuery};
 use rust_road_router::datastr::graph::{Link, LinkIterable, NodeId};
 
-use crate::experiments::queries::departure_distributions::DepartureDistribution;
+use crate::experiments::queries::departure_distributions::{AfternoonRushHourDeparture, DepartureDistribution, MorningRushHourDeparture};
+use crate::graph::MAX_BUCKETS;
 use crate::io::io_population_grid::PopulationGridEntry;
 use rand_distr::Distribution;
 use rand_distr::Geometric;
 use rust_road_router::algo::dijkstra::{DefaultOps, DijkstraData, DijkstraInit, DijkstraRun};
 use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use std::cmp::{max, min};
 use std::collections::HashSet;
 
 const INV_AVERAGE_TRIP_LENGTH: f64 = 1.0 / 40_000.0; // avg trip length is ~40 km
 const INV_AVERAGE_TRIP_DURATION: f64 = 1.0 / (2_700_000.0); // avg trip duration: 45 minutes
 
 pub fn generate_uniform_population_density_based_queries<D: DepartureDistribution>(
-    longitude: &Vec<f32>,
-    latitude: &Vec<f32>,
+    longitude: &Vec<f64>,
+    latitude: &Vec<f64>,
     grid_tree: &Kdtree<PopulationGridEntry>,
     grid_population: &Vec<u32>,
     num_queries: u32,
@@ -50,10 +52,111 @@ pub fn generate_uniform_population_density_based_queries<D: DepartureDistributio
     queries
 }
 
+/// Commuter queries based on two population layers: residential (home) and workplace density.
+/// Trips departing in the first half of the day lead from home to work, all later trips lead back home.
+pub fn generate_commuter_population_density_based_queries<D: DepartureDistribution>(
+    longitude: &Vec<f64>,
+    latitude: &Vec<f64>,
+    home_grid_tree: &Kdtree<PopulationGridEntry>,
+    home_population: &Vec<u32>,
+    work_grid_tree: &Kdtree<PopulationGridEntry>,
+    work_population: &Vec<u32>,
+    num_queries: u32,
+    mut departure_distribution: D,
+) -> Vec<TDQuery<Timestamp>> {
+    // init both population layers
+    let (home_vertex_grid, home_intervals, home_counter) = build_population_grid(longitude, latitude, home_grid_tree, home_population);
+    let (work_vertex_grid, work_intervals, work_counter) = build_population_grid(longitude, latitude, work_grid_tree, work_population);
+
+    let mut rng = thread_rng();
+    let mut queries = (0..num_queries)
+        .into_iter()
+        .map(|_| {
+            // draw random cells according to the respective density, pick a random node inside
+            let home_cell_id = find_population_interval(&home_intervals, rng.gen_range(0..home_counter));
+            let home = home_vertex_grid[home_cell_id][rng.gen_range(0..home_vertex_grid[home_cell_id].len())];
+
+            let work_cell_id = find_population_interval(&work_intervals, rng.gen_range(0..work_counter));
+            let work = work_vertex_grid[work_cell_id][rng.gen_range(0..work_vertex_grid[work_cell_id].len())];
+
+            // the departure determines the commuting direction
+            let departure = departure_distribution.rand(&mut rng);
+            if departure % MAX_BUCKETS < MAX_BUCKETS / 2 {
+                TDQuery::new(home, work, departure)
+            } else {
+                TDQuery::new(work, home, departure)
+            }
+        })
+        .collect::<Vec<TDQuery<Timestamp>>>();
+
+    // sort queries by departure for a more realistic usage scenario
+    queries.sort_by_key(|query| query.departure);
+
+    queries
+}
+
+/// Commuter round trips: each commuter travels from home to work in the morning and back home in the evening.
+/// Endpoints are drawn from the residential (home) and workplace density, respectively.
+///
+/// Returns `2 * num_round_trips` queries sorted by departure and, for each query, the index of its partner trip.
+pub fn generate_commuter_round_trip_queries(
+    longitude: &Vec<f64>,
+    latitude: &Vec<f64>,
+    home_grid_tree: &Kdtree<PopulationGridEntry>,
+    home_population: &Vec<u32>,
+    work_grid_tree: &Kdtree<PopulationGridEntry>,
+    work_population: &Vec<u32>,
+    num_round_trips: u32,
+) -> (Vec<TDQuery<Timestamp>>, Vec<u32>) {
+    // init both population layers
+    let (home_vertex_grid, home_intervals, home_counter) = build_population_grid(longitude, latitude, home_grid_tree, home_population);
+    let (work_vertex_grid, work_intervals, work_counter) = build_population_grid(longitude, latitude, work_grid_tree, work_population);
+
+    let mut morning_departure = MorningRushHourDeparture::new();
+    let mut evening_departure = AfternoonRushHourDeparture::new();
+
+    let mut rng = thread_rng();
+    // (query, round trip id)
+    let mut queries = Vec::with_capacity(2 * num_round_trips as usize);
+
+    for round_trip in 0..num_round_trips {
+        let home_cell_id = find_population_interval(&home_intervals, rng.gen_range(0..home_counter));
+        let home = home_vertex_grid[home_cell_id][rng.gen_range(0..home_vertex_grid[home_cell_id].len())];
+
+        let work_cell_id = find_population_interval(&work_intervals, rng.gen_range(0..work_counter));
+        let work = work_vertex_grid[work_cell_id][rng.gen_range(0..work_vertex_grid[work_cell_id].len())];
+
+        // the return trip must start after the outward trip
+        let (morning, evening) = {
+            let (a, b) = (morning_departure.rand(&mut rng), evening_departure.rand(&mut rng));
+            (min(a, b), max(max(a, b), min(a, b) + 1))
+        };
+
+        queries.push((TDQuery::new(home, work, morning), round_trip));
+        queries.push((TDQuery::new(work, home, evening), round_trip));
+    }
+
+    // sort queries by departure for a more realistic usage scenario, afterwards link both trips of each round trip
+    queries.sort_by_key(|(query, _)| query.departure);
+
+    let mut first_trip = vec![None; num_round_trips as usize];
+    let mut partner = vec![0; queries.len()];
+    for (idx, &(_, round_trip)) in queries.iter().enumerate() {
+        if let Some(first_idx) = first_trip[round_trip as usize] {
+            partner[first_idx] = idx as u32;
+            partner[idx] = first_idx as u32;
+        } else {
+            first_trip[round_trip as usize] = Some(idx);
+        }
+    }
+
+    (queries.into_iter().map(|(query, _)| query).collect(), partner)
+}
+
 pub fn generate_geometric_population_density_based_queries<D: DepartureDistribution, G: LinkIterable<Link>>(
     graph: &G,
-    longitude: &Vec<f32>,
-    latitude: &Vec<f32>,
+    longitude: &Vec<f64>,
+    latitude: &Vec<f64>,
     grid_tree: &Kdtree<PopulationGridEntry>,
     grid_population: &Vec<u32>,
     num_queries: u32,
@@ -162,8 +265,8 @@ pub fn find_population_interval(vec: &Vec<(u32, usize)>, val: u32) -> usize {
 }
 
 pub fn build_population_grid(
-    longitude: &Vec<f32>,
-    latitude: &Vec<f32>,
+    longitude: &Vec<f64>,
+    latitude: &Vec<f64>,
     grid_tree: &Kdtree<PopulationGridEntry>,
     grid_population: &Vec<u32>,
 ) -> (Vec<Vec<u32>>, Vec<(u32, usize)>, u32) {
@@ -4,9 +4,11 @@ use rand_distr::{Distribution, Geometric};
 use rust_road_router::algo::dijkstra::{DefaultOps, DijkstraData, DijkstraInit, DijkstraRun};
 use rust_road_router::algo::{GenQuery, TDQuery};
 use rust_road_router::datastr::graph::time_dependent::Timestamp;
-use rust_road_router::datastr::graph::{Link, LinkIterable, NodeId};
+use rust_road_router::datastr::graph::{EdgeIdT, Graph, Link, LinkIterable, NodeId, Weight, INFINITY};
 
+use crate::dijkstra::capacity_dijkstra_ops::CapacityDijkstraOps;
 use crate::experiments::queries::departure_distributions::DepartureDistribution;
+use crate::graph::capacity_graph::CapacityGraph;
 
 const INV_AVERAGE_TRIP_LENGTH: f64 = 1.0 / 40_000.0; // avg trip length is ~40 km
 const INV_AVERAGE_TRIP_DURATION: f64 = 1.0 / (2_700_000.0); // avg trip duration: 45 minutes
@@ -65,3 +67,59 @@ pub fn generate_random_geometric_queries<G: LinkIterable<Link>, D: DepartureDist
 
     queries
 }
+
+/// Geometric queries on the time-dependent travel times of `graph`, evaluated at the departure of each query.
+///
+/// The trip durations follow the same distribution as in `generate_random_geometric_queries` on a travel time metric,
+/// so the query lengths remain comparable between graphs with a different mix of road speeds.
+/// Without historic speeds or loads, this is equivalent to using the free-flow travel times.
+pub fn generate_random_td_geometric_queries<D: DepartureDistribution>(
+    graph: &CapacityGraph,
+    num_queries: u32,
+    mut departure_distribution: D,
+) -> Vec<TDQuery<Timestamp>> {
+    let mut rng = thread_rng();
+    let distribution = Geometric::new(INV_AVERAGE_TRIP_DURATION).unwrap();
+
+    // init dijkstra context
+    let mut data = DijkstraData::<Weight, EdgeIdT, Weight>::new(graph.num_nodes());
+
+    let mut queries = (0..num_queries)
+        .map(|idx| {
+            let mut result: Option<TDQuery<Timestamp>> = None;
+
+            while result.is_none() {
+                // the departure is fixed beforehand, the travel times depend on it
+                let from = rng.gen_range(0..graph.num_nodes()) as NodeId;
+                let departure = departure_distribution.rand(&mut rng);
+                let duration = distribution.sample(&mut rng) as u32;
+
+                let query = TDQuery::new(from, 0, departure);
+                let mut ops = CapacityDijkstraOps::<Weight>::default();
+                let mut dijkstra = DijkstraRun::query(graph, &mut data, &mut ops, DijkstraInit::from_query(&query));
+
+                while let Some(node) = dijkstra.next() {
+                    let travel_time = *dijkstra.tentative_distance(node) - departure;
+                    if travel_time >= INFINITY {
+                        // remaining nodes can only be reached via closed edges
+                        break;
+                    } else if travel_time > duration {
+                        result = Some(TDQuery::new(from, node, departure));
+                        break;
+                    }
+                }
+            }
+
+            if idx % 100 == 0 {
+                println!("Finished {}/{} queries", idx, num_queries);
+            }
+
+            result.unwrap()
+        })
+        .collect::<Vec<TDQuery<Timestamp>>>();
+
+    // sort queries by departure for a more realistic usage scenario
+    queries.sort_by_key(|query| query.departure);
+
+    queries
+}
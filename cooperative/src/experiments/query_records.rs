@@ -0,0 +1,100 @@
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{NodeId, Weight};
+use rust_road_router::report::*;
+use rust_road_router::{report, report_silent};
+use std::time::Duration;
+
+use crate::dijkstra::model::{DistanceMeasure, MeasuredCapacityQueryResult, SearchStatistics};
+
+/// Structured record of a single query, emitted as one JSON object per query through the `report` framework
+#[derive(Debug, Clone)]
+pub struct QueryRecord {
+    pub server: String,
+    pub query_id: usize,
+    pub from: NodeId,
+    pub to: NodeId,
+    pub departure: Timestamp,
+    pub distance: Option<Weight>,
+    pub time_query: Duration,
+    pub time_potential: Duration,
+    pub num_pot_computations: Option<usize>,
+    pub num_queue_pops: u32,
+    pub num_relaxed_arcs: u32,
+    pub path_length: Option<usize>,
+    pub num_recustomizations: u32,
+    pub search_statistics: Option<SearchStatistics>,
+}
+
+impl QueryRecord {
+    pub fn new(server: &str, query_id: usize, from: NodeId, to: NodeId, departure: Timestamp) -> Self {
+        Self {
+            server: server.to_string(),
+            query_id,
+            from,
+            to,
+            departure,
+            distance: None,
+            time_query: Duration::ZERO,
+            time_potential: Duration::ZERO,
+            num_pot_computations: None,
+            num_queue_pops: 0,
+            num_relaxed_arcs: 0,
+            path_length: None,
+            num_recustomizations: 0,
+            search_statistics: None,
+        }
+    }
+
+    /// take over distance, runtime and search space statistics of a measured query
+    pub fn with_measured_result(mut self, result: &MeasuredCapacityQueryResult) -> Self {
+        self.path_length = result.query_result.as_ref().map(|result| result.path.edge_path.len());
+        self.with_distance_measure(&result.distance_result)
+    }
+
+    pub fn with_distance_measure(mut self, measure: &DistanceMeasure) -> Self {
+        self.distance = measure.distance;
+        self.time_query = measure.time_query;
+        self.time_potential = measure.time_potential;
+        self.num_pot_computations = measure.num_pot_computations;
+        self.num_queue_pops = measure.num_queue_pops;
+        self.num_relaxed_arcs = measure.num_relaxed_arcs;
+        self.search_statistics = measure.search_statistics;
+        self
+    }
+
+    fn report(&self) {
+        report_silent!("server", self.server);
+        report_silent!("query_id", self.query_id);
+        report_silent!("from", self.from);
+        report_silent!("to", self.to);
+        report_silent!("departure", self.departure);
+        report_silent!("distance", self.distance);
+        report_silent!("running_time_ms", self.time_query.as_secs_f64() * 1000.0);
+        report_silent!("potential_time_ms", self.time_potential.as_secs_f64() * 1000.0);
+        report_silent!("num_pot_computations", self.num_pot_computations);
+        report_silent!("num_queue_pops", self.num_queue_pops);
+        report_silent!("num_relaxed_arcs", self.num_relaxed_arcs);
+        report_silent!("path_length", self.path_length);
+        report_silent!("num_recustomizations", self.num_recustomizations);
+
+        if let Some(stats) = &self.search_statistics {
+            report_silent!("num_settled_nodes", stats.num_settled_nodes);
+            report_silent!("num_improved_labels", stats.num_improved_labels);
+            report_silent!("num_decrease_keys", stats.num_decrease_keys);
+            report_silent!("num_potential_calls", stats.num_potential_calls);
+            report_silent!("num_pruned_nodes", stats.num_pruned_nodes);
+        }
+    }
+}
+
+/// Emit the given records as a collection under `key`.
+/// The reporter is thread-local, so this must be called from the thread that enabled reporting.
+pub fn report_query_records(key: &str, records: &[QueryRecord]) {
+    report!("num_query_records", records.len());
+
+    let mut records_ctxt = push_collection_context(key.to_string());
+    for record in records {
+        let _record_ctxt = records_ctxt.push_collection_item();
+        record.report();
+    }
+}
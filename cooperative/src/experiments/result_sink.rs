@@ -0,0 +1,148 @@
+use std::error::Error;
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+use crate::experiments::query_records::QueryRecord;
+
+/// Version of the table layout below, stored in `PRAGMA user_version`. Bump it on every change of the schema.
+pub const SCHEMA_VERSION: u32 = 1;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS experiments (
+    experiment_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    program TEXT NOT NULL,
+    graph TEXT NOT NULL,
+    args TEXT NOT NULL,
+    start_time TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS parameters (
+    experiment_id INTEGER NOT NULL REFERENCES experiments(experiment_id),
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (experiment_id, key)
+);
+CREATE TABLE IF NOT EXISTS iteration_statistics (
+    experiment_id INTEGER NOT NULL REFERENCES experiments(experiment_id),
+    series TEXT NOT NULL,
+    iteration INTEGER NOT NULL,
+    key TEXT NOT NULL,
+    value REAL,
+    PRIMARY KEY (experiment_id, series, iteration, key)
+);
+CREATE TABLE IF NOT EXISTS query_samples (
+    experiment_id INTEGER NOT NULL REFERENCES experiments(experiment_id),
+    server TEXT NOT NULL,
+    query_id INTEGER NOT NULL,
+    from_node INTEGER NOT NULL,
+    to_node INTEGER NOT NULL,
+    departure INTEGER NOT NULL,
+    distance INTEGER,
+    running_time_ms REAL NOT NULL,
+    potential_time_ms REAL NOT NULL,
+    num_pot_computations INTEGER,
+    num_queue_pops INTEGER NOT NULL,
+    num_relaxed_arcs INTEGER NOT NULL,
+    path_length INTEGER,
+    num_recustomizations INTEGER NOT NULL
+);
+";
+
+/// Appends the results of one experiment run to a SQLite database with a fixed schema.
+///
+/// Each run gets a new row in `experiments`, all other tables reference it by `experiment_id`.
+/// Per-iteration statistics are stored as (series, iteration, key, value) rows, so different binaries share the same table.
+pub struct SqliteResultSink {
+    connection: Connection,
+    experiment_id: i64,
+}
+
+impl SqliteResultSink {
+    /// Open (or create) the database at `path` and register a new experiment run
+    pub fn open(path: &Path, program: &str, graph: &str) -> Result<Self, Box<dyn Error>> {
+        let connection = Connection::open(path)?;
+
+        let version: u32 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        let has_tables: bool = connection.query_row("SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table'", [], |row| row.get(0))?;
+        if has_tables && version != SCHEMA_VERSION {
+            return Err(format!("Result database has schema version {}, expected {}", version, SCHEMA_VERSION).into());
+        }
+
+        connection.execute_batch(SCHEMA)?;
+        connection.execute_batch(&format!("PRAGMA user_version = {}", SCHEMA_VERSION))?;
+
+        connection.execute(
+            "INSERT INTO experiments (program, graph, args, start_time) VALUES (?1, ?2, ?3, datetime('now'))",
+            params![program, graph, std::env::args().collect::<Vec<String>>().join(" ")],
+        )?;
+        let experiment_id = connection.last_insert_rowid();
+
+        Ok(Self { connection, experiment_id })
+    }
+
+    /// Open the database given by the `RESULTS_DB` environment variable, `None` if it is not set
+    pub fn open_from_env(program: &str, graph: &str) -> Result<Option<Self>, Box<dyn Error>> {
+        match std::env::var("RESULTS_DB") {
+            Ok(path) => Ok(Some(Self::open(Path::new(&path), program, graph)?)),
+            Err(_) => Ok(None),
+        }
+    }
+
+    pub fn experiment_id(&self) -> i64 {
+        self.experiment_id
+    }
+
+    pub fn add_parameter<T: ToString>(&mut self, key: &str, value: T) -> Result<(), Box<dyn Error>> {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO parameters (experiment_id, key, value) VALUES (?1, ?2, ?3)",
+            params![self.experiment_id, key, value.to_string()],
+        )?;
+        Ok(())
+    }
+
+    /// store the statistics of one iteration, e.g. `("relative_gap", 0.01)`, within the given series
+    pub fn add_iteration_statistics(&mut self, series: &str, iteration: u32, statistics: &[(&str, f64)]) -> Result<(), Box<dyn Error>> {
+        let transaction = self.connection.transaction()?;
+        {
+            let mut statement = transaction
+                .prepare("INSERT OR REPLACE INTO iteration_statistics (experiment_id, series, iteration, key, value) VALUES (?1, ?2, ?3, ?4, ?5)")?;
+            for &(key, value) in statistics {
+                statement.execute(params![self.experiment_id, series, iteration, key, value])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// store one sample per query, all records are inserted within a single transaction
+    pub fn add_query_records(&mut self, records: &[QueryRecord]) -> Result<(), Box<dyn Error>> {
+        let transaction = self.connection.transaction()?;
+        {
+            let mut statement = transaction.prepare(
+                "INSERT INTO query_samples (experiment_id, server, query_id, from_node, to_node, departure, distance, running_time_ms, \
+                 potential_time_ms, num_pot_computations, num_queue_pops, num_relaxed_arcs, path_length, num_recustomizations) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            )?;
+            for record in records {
+                statement.execute(params![
+                    self.experiment_id,
+                    record.server,
+                    record.query_id as i64,
+                    record.from,
+                    record.to,
+                    record.departure,
+                    record.distance,
+                    record.time_query.as_secs_f64() * 1000.0,
+                    record.time_potential.as_secs_f64() * 1000.0,
+                    record.num_pot_computations.map(|num| num as i64),
+                    record.num_queue_pops,
+                    record.num_relaxed_arcs,
+                    record.path_length.map(|len| len as i64),
+                    record.num_recustomizations,
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+use crate::dijkstra::server::CapacityServer;
+use crate::graph::capacity_graph::CapacityGraph;
+use crate::graph::weather::WeatherFactor;
+use crate::graph::Capacity;
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{EdgeId, Graph};
+use std::collections::BTreeMap;
+use std::error::Error;
+
+/// Time-dependent disruptions of an experiment, see `load_scenario` for the file format.
+/// All events are active within `[start, end)`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Scenario {
+    pub lane_closures: Vec<LaneClosure>,
+    pub demand_scalings: Vec<DemandScaling>,
+    pub weather: Vec<WeatherEvent>,
+}
+
+/// Closed lanes of an edge, e.g. caused by an incident or road works.
+/// The lane model keeps at least one lane open, so `closed_lanes = None` closes all but one lane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LaneClosure {
+    pub edge_id: EdgeId,
+    pub closed_lanes: Option<u32>,
+    pub start: Timestamp,
+    pub end: Timestamp,
+}
+
+/// Scales the load of all vehicles departing within the time window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DemandScaling {
+    pub factor: f64,
+    pub start: Timestamp,
+    pub end: Timestamp,
+}
+
+/// Weather conditions (e.g. rain or snow) scaling free-flow speeds and capacities within an area
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeatherEvent {
+    pub area: WeatherArea,
+    pub factor: WeatherFactor,
+    pub start: Timestamp,
+    pub end: Timestamp,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WeatherArea {
+    Everywhere,
+    Edges(Vec<EdgeId>),
+    /// bounding box `[min_lon, min_lat, max_lon, max_lat]`, containing all edges whose tail lies inside,
+    /// has to be resolved to edges before the scenario is applied, see `resolve_weather_regions`
+    Region([f64; 4]),
+}
+
+impl Scenario {
+    /// check the events against the graph they are applied to
+    pub fn validate(&self, graph: &CapacityGraph) -> Result<(), Box<dyn Error>> {
+        for closure in &self.lane_closures {
+            let edge_id = closure.edge_id as usize;
+            if edge_id >= graph.num_arcs() {
+                return Err(format!("lane closure on invalid edge {} (graph has {} edges)", closure.edge_id, graph.num_arcs()).into());
+            }
+            if closure.closed_lanes.map_or(false, |closed| closed >= graph.num_lanes()[edge_id]) {
+                return Err(format!(
+                    "lane closure on edge {} must leave at least one of {} lanes open",
+                    closure.edge_id,
+                    graph.num_lanes()[edge_id]
+                )
+                .into());
+            }
+        }
+        for event in &self.weather {
+            match &event.area {
+                WeatherArea::Edges(edges) => {
+                    if let Some(edge_id) = edges.iter().find(|&&edge_id| edge_id as usize >= graph.num_arcs()) {
+                        return Err(format!("weather on invalid edge {} (graph has {} edges)", edge_id, graph.num_arcs()).into());
+                    }
+                }
+                WeatherArea::Region(_) => return Err("weather regions must be resolved to edges, see `resolve_weather_regions`".into()),
+                WeatherArea::Everywhere => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// replace the regions of all weather events by the edges whose tail lies inside, given the node coordinates
+    pub fn resolve_weather_regions(&mut self, graph: &CapacityGraph, longitude: &[f64], latitude: &[f64]) {
+        for event in &mut self.weather {
+            if let WeatherArea::Region([min_lon, min_lat, max_lon, max_lat]) = event.area {
+                let edges = (0..graph.num_nodes())
+                    .filter(|&node| (min_lon..=max_lon).contains(&longitude[node]) && (min_lat..=max_lat).contains(&latitude[node]))
+                    .flat_map(|node| graph.first_out()[node]..graph.first_out()[node + 1])
+                    .collect::<Vec<EdgeId>>();
+                event.area = WeatherArea::Edges(edges);
+            }
+        }
+    }
+
+    pub fn has_weather_regions(&self) -> bool {
+        self.weather.iter().any(|event| matches!(event.area, WeatherArea::Region(_)))
+    }
+
+    /// combined weather factor of each edge affected by the scenario at `ts`, the default factor if none of its events is active
+    pub fn weather_at(&self, graph: &CapacityGraph, ts: Timestamp) -> BTreeMap<EdgeId, WeatherFactor> {
+        let mut factors = BTreeMap::new();
+        for event in &self.weather {
+            let edges = match &event.area {
+                WeatherArea::Everywhere => (0..graph.num_arcs() as EdgeId).collect(),
+                WeatherArea::Edges(edges) => edges.clone(),
+                WeatherArea::Region(_) => panic!("weather regions must be resolved to edges first"),
+            };
+            let active = event.start <= ts && ts < event.end;
+            for edge_id in edges {
+                let entry = factors.entry(edge_id).or_insert_with(WeatherFactor::default);
+                if active {
+                    *entry = entry.combine(&event.factor);
+                }
+            }
+        }
+        factors
+    }
+
+    /// Bring the weather of the server's graph to the state of the scenario at `ts`.
+    /// Returns the number of modified edges.
+    pub fn apply_weather<P>(&self, server: &mut CapacityServer<P>, ts: Timestamp) -> usize {
+        let modified = self
+            .weather_at(server.borrow_graph(), ts)
+            .into_iter()
+            .filter(|&(edge_id, factor)| server.borrow_graph().weather(edge_id) != factor)
+            .collect::<Vec<(EdgeId, WeatherFactor)>>();
+
+        for &(edge_id, factor) in &modified {
+            server.set_weather(edge_id, factor);
+        }
+        modified.len()
+    }
+
+    /// number of closed lanes on each edge affected by the scenario at `ts`, 0 if none of its closures is active
+    pub fn closed_lanes_at(&self, graph: &CapacityGraph, ts: Timestamp) -> BTreeMap<EdgeId, u32> {
+        let mut closed_lanes = BTreeMap::new();
+        for closure in &self.lane_closures {
+            let closed = if closure.start <= ts && ts < closure.end {
+                closure.closed_lanes.unwrap_or(graph.num_lanes()[closure.edge_id as usize] - 1)
+            } else {
+                0
+            };
+            let entry = closed_lanes.entry(closure.edge_id).or_insert(0);
+            *entry = (*entry).max(closed);
+        }
+        closed_lanes
+    }
+
+    /// Bring the closed lanes of the server's graph to the state of the scenario at `ts`.
+    /// Returns the number of modified edges.
+    pub fn apply_lane_closures<P>(&self, server: &mut CapacityServer<P>, ts: Timestamp) -> usize {
+        let modified = self
+            .closed_lanes_at(server.borrow_graph(), ts)
+            .into_iter()
+            .filter(|&(edge_id, closed)| server.borrow_graph().closed_lanes()[edge_id as usize] != closed)
+            .collect::<Vec<(EdgeId, u32)>>();
+
+        for &(edge_id, closed) in &modified {
+            server.set_closed_lanes(edge_id, closed);
+        }
+        modified.len()
+    }
+
+    /// product of all demand scalings active at `ts`
+    pub fn demand_factor(&self, ts: Timestamp) -> f64 {
+        self.demand_scalings
+            .iter()
+            .filter(|scaling| scaling.start <= ts && ts < scaling.end)
+            .map(|scaling| scaling.factor)
+            .product()
+    }
+
+    /// scale a vehicle's load according to the demand at its departure
+    pub fn scaled_load(&self, load: Capacity, departure: Timestamp) -> Capacity {
+        ((load as f64 * self.demand_factor(departure)).round() as Capacity).max(1)
+    }
+}
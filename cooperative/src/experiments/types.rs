@@ -30,3 +30,31 @@ impl ToString for PotentialType {
         }
     }
 }
+
+/// Potentials which remain valid without re-customization, see `CapacityServer<Box<dyn TDPotential>>`
+#[derive(Debug, Clone)]
+pub enum StaticPotentialType {
+    Zero,
+    CCHLowerbound,
+}
+
+impl FromStr for StaticPotentialType {
+    type Err = CliErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "ZERO" => Ok(Self::Zero),
+            "CCH_POT" => Ok(Self::CCHLowerbound),
+            _ => Err(CliErr("Invalid Static Potential Type [ZERO/CCH_POT]")),
+        }
+    }
+}
+
+impl ToString for StaticPotentialType {
+    fn to_string(&self) -> String {
+        match self {
+            StaticPotentialType::Zero => "Zero".to_string(),
+            StaticPotentialType::CCHLowerbound => "CCH-Pot".to_string(),
+        }
+    }
+}
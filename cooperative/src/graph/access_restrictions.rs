@@ -0,0 +1,116 @@
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{EdgeId, Graph};
+
+use crate::graph::capacity_graph::CapacityGraph;
+use crate::graph::MAX_BUCKETS;
+
+/// Vehicle classes are identified by small integers (e.g. 0 = car, 1 = truck), at most `MAX_VEHICLE_CLASSES`
+pub type VehicleClass = u8;
+pub const MAX_VEHICLE_CLASSES: VehicleClass = 32;
+/// class of all queries without further information
+pub const DEFAULT_VEHICLE_CLASS: VehicleClass = 0;
+
+/// Polygonal zone (e.g. low-emission zone or closed city center) that may not be entered by some vehicle classes
+#[derive(Debug, Clone, PartialEq)]
+pub struct RestrictionZone {
+    /// outer ring as `(longitude, latitude)`
+    pub polygon: Vec<(f64, f64)>,
+    /// bit mask of the restricted vehicle classes
+    pub restricted_classes: u32,
+    /// time windows `[start, end)` of the restriction, the whole day if empty
+    pub time_windows: Vec<(Timestamp, Timestamp)>,
+}
+
+impl RestrictionZone {
+    pub fn new(polygon: Vec<(f64, f64)>, restricted_classes: &[VehicleClass], time_windows: Vec<(Timestamp, Timestamp)>) -> Self {
+        assert!(polygon.len() >= 3, "a restriction zone needs at least 3 points");
+        assert!(restricted_classes.iter().all(|&class| class < MAX_VEHICLE_CLASSES), "invalid vehicle class");
+        assert!(
+            time_windows.iter().all(|&(start, end)| start < end && end <= MAX_BUCKETS),
+            "invalid time window"
+        );
+
+        Self {
+            polygon,
+            restricted_classes: restricted_classes.iter().fold(0, |mask, &class| mask | (1 << class)),
+            time_windows,
+        }
+    }
+
+    /// whether vehicles of `class` may not enter the zone at `ts`
+    pub fn restricts(&self, class: VehicleClass, ts: Timestamp) -> bool {
+        let ts = ts % MAX_BUCKETS;
+        self.restricted_classes & (1 << class) != 0 && (self.time_windows.is_empty() || self.time_windows.iter().any(|&(start, end)| start <= ts && ts < end))
+    }
+
+    pub fn contains(&self, lon: f64, lat: f64) -> bool {
+        polygon_contains(&self.polygon, lon, lat)
+    }
+}
+
+/// point-in-polygon test (even-odd rule), the polygon is given as `(longitude, latitude)`
+pub fn polygon_contains(polygon: &[(f64, f64)], lon: f64, lat: f64) -> bool {
+    let mut inside = false;
+    let mut prev = polygon[polygon.len() - 1];
+    for &point in polygon {
+        if (point.1 > lat) != (prev.1 > lat) && lon < (prev.0 - point.0) * (lat - point.1) / (prev.1 - point.1) + point.0 {
+            inside = !inside;
+        }
+        prev = point;
+    }
+    inside
+}
+
+/// Restriction zones mapped onto the edges of a graph. An edge belongs to a zone if its head lies inside,
+/// i.e. restricted vehicles can leave a zone, but not enter or traverse it.
+///
+/// Restrictions only remove edges from the search, so lower bounds customized without them stay admissible.
+#[derive(Debug, Clone)]
+pub struct AccessRestrictions {
+    zones: Vec<RestrictionZone>,
+    // zones of each edge, indexed like `first_out`
+    first_zone: Vec<u32>,
+    zone_ids: Vec<u32>,
+}
+
+impl AccessRestrictions {
+    pub fn new(zones: Vec<RestrictionZone>, graph: &CapacityGraph, longitude: &[f64], latitude: &[f64]) -> Self {
+        assert_eq!(longitude.len(), graph.num_nodes(), "data containers must have the same size!");
+
+        let node_zones = (0..graph.num_nodes())
+            .map(|node| {
+                (0..zones.len() as u32)
+                    .filter(|&zone| zones[zone as usize].contains(longitude[node], latitude[node]))
+                    .collect::<Vec<u32>>()
+            })
+            .collect::<Vec<Vec<u32>>>();
+
+        let mut first_zone = Vec::with_capacity(graph.num_arcs() + 1);
+        let mut zone_ids = Vec::new();
+        first_zone.push(0);
+        for &head in graph.head() {
+            zone_ids.extend_from_slice(&node_zones[head as usize]);
+            first_zone.push(zone_ids.len() as u32);
+        }
+
+        Self { zones, first_zone, zone_ids }
+    }
+
+    pub fn zones(&self) -> &[RestrictionZone] {
+        &self.zones
+    }
+
+    /// number of edges inside at least one zone
+    pub fn num_restricted_edges(&self) -> usize {
+        self.first_zone.windows(2).filter(|w| w[0] < w[1]).count()
+    }
+
+    /// whether a vehicle of `class` may traverse `edge_id` when entering it at `ts`
+    #[inline(always)]
+    pub fn is_allowed(&self, edge_id: EdgeId, class: VehicleClass, ts: Timestamp) -> bool {
+        let edge_id = edge_id as usize;
+        self.zone_ids[self.first_zone[edge_id] as usize..self.first_zone[edge_id + 1] as usize]
+            .iter()
+            .all(|&zone| !self.zones[zone as usize].restricts(class, ts))
+    }
+}
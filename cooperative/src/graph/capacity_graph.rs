@@ -1,11 +1,20 @@
 use rust_road_router::datastr::graph::time_dependent::{PiecewiseLinearFunction, Timestamp};
 use rust_road_router::datastr::graph::{EdgeId, Graph, NodeId, Weight, INFINITY};
 
+use crate::graph::access_restrictions::VehicleClass;
 use crate::graph::edge_buckets::{CapacityBuckets, SpeedBuckets};
-use crate::graph::traffic_functions::BPRTrafficFunction;
-use crate::graph::{Capacity, MAX_BUCKETS};
+use crate::graph::load_feed::{LoadFeedStatistics, LoadObservation};
+use crate::graph::speed_blend::SpeedBlend;
+use crate::graph::system_statistics::{IntervalStatistics, SystemStatistics};
+use crate::graph::traffic_functions::{BPRTrafficFunction, EnergyModel, GradientSpeedModel};
+use crate::graph::weather::WeatherFactor;
+use crate::graph::{Capacity, Velocity, MAX_BUCKETS, PCE_SCALE};
 use conversion::speed_profile_to_tt_profile;
+use rayon::prelude::*;
+use rust_road_router::io::{Deconstruct, Loader, Reconstruct, Store};
+use std::borrow::Cow;
 use std::cmp::{max, min};
+use std::collections::BTreeMap;
 
 /// Structure of a time-dependent graph with capacity buckets for each edge
 /// After each query, the capacities of all edges on the shortest path get modified
@@ -25,12 +34,26 @@ pub struct CapacityGraph {
 
     // historic values, used as additional prediction for future traffic conditions
     historic_speeds: Option<Vec<SpeedBuckets>>,
+    speed_blend: SpeedBlend,
+
+    // vehicle-hours and vehicle-kilometers per bucket of all added paths
+    system_statistics: SystemStatistics,
 
     // static values
     distance: Vec<Weight>,
     max_capacity: Vec<Capacity>,
+    // lane model: the capacity of an edge is given by its open lanes times the per-lane capacity
+    num_lanes: Vec<u32>,
+    closed_lanes: Vec<u32>,
+    lane_capacity: Vec<Capacity>,
     free_flow_travel_time: Vec<Weight>,
     free_flow_speed_kmh: Vec<Weight>,
+    // gradient of each edge in percent, only known if elevation data was added
+    gradient: Option<Vec<f32>>,
+    // weather factor of each edge and the free-flow speeds without weather, only present once weather was set
+    weather: Option<(Vec<WeatherFactor>, Vec<Velocity>)>,
+    // bit mask of the vehicle classes allowed on each edge (e.g. bus or HOV lanes), all classes if not given
+    edge_classes: Option<Vec<u32>>,
 
     traffic_function: BPRTrafficFunction,
 }
@@ -64,7 +87,7 @@ impl CapacityGraph {
         // adjust capacity of each edge -> more buckets do not allow more traffic flow
         let capacity_adjustment_factor = 24.0 / (num_buckets as f64);
         let max_capacity = max_capacity
-            .iter()
+            .par_iter()
             .map(|&capacity| {
                 // avoid unnecessary edges
                 if capacity >= 50 {
@@ -78,7 +101,7 @@ impl CapacityGraph {
         // initialize free-flow speed
         // fallback to speed 1 if capacity or time are invalid
         let free_flow_speed_kmh = (0..num_edges)
-            .into_iter()
+            .into_par_iter()
             .map(|idx| {
                 if free_flow_travel_time[idx] == 1 || free_flow_travel_time[idx] >= INFINITY || max_capacity[idx] == 0 {
                     1
@@ -89,7 +112,7 @@ impl CapacityGraph {
             .collect::<Vec<Weight>>();
 
         let free_flow_travel_time = (0..num_edges)
-            .into_iter()
+            .into_par_iter()
             .map(|idx| {
                 if free_flow_travel_time[idx] >= INFINITY || max_capacity[idx] == 0 {
                     INFINITY
@@ -101,7 +124,7 @@ impl CapacityGraph {
             })
             .collect::<Vec<u32>>();
 
-        assert!(!free_flow_travel_time.iter().any(|&x| x > INFINITY));
+        assert!(!free_flow_travel_time.par_iter().any(|&x| x > INFINITY));
 
         // initialize bucket containers as well as departure and travel_time structs
         let used_capacity = vec![CapacityBuckets::Unused; num_edges];
@@ -109,13 +132,18 @@ impl CapacityGraph {
 
         let departure = vec![vec![0, MAX_BUCKETS]; num_edges];
         let travel_time = (0..num_edges)
-            .into_iter()
+            .into_par_iter()
             .map(|i| {
                 debug_assert!(max_capacity[i] > 0 || free_flow_travel_time[i] == INFINITY);
                 vec![free_flow_travel_time[i], free_flow_travel_time[i]]
             })
             .collect::<Vec<Vec<Weight>>>();
 
+        // without lane information, each edge is modeled as a single lane
+        let num_lanes = vec![1; num_edges];
+        let closed_lanes = vec![0; num_edges];
+        let lane_capacity = max_capacity.clone();
+
         Self {
             num_buckets,
             first_out,
@@ -127,9 +155,17 @@ impl CapacityGraph {
             distance,
             free_flow_speed_kmh,
             max_capacity,
+            num_lanes,
+            closed_lanes,
+            lane_capacity,
             free_flow_travel_time,
             traffic_function,
             historic_speeds: None,
+            speed_blend: SpeedBlend::default(),
+            system_statistics: SystemStatistics::new(num_buckets),
+            gradient: None,
+            weather: None,
+            edge_classes: None,
         }
     }
 
@@ -164,10 +200,22 @@ impl CapacityGraph {
     }
 
     /// Borrow a slice of `free_flow_time`: useful as lower bound time for potentials
+    pub fn num_lanes(&self) -> &Vec<u32> {
+        &self.num_lanes
+    }
+
+    pub fn closed_lanes(&self) -> &Vec<u32> {
+        &self.closed_lanes
+    }
+
     pub fn free_flow_time(&self) -> &Vec<Weight> {
         &self.free_flow_travel_time
     }
 
+    pub fn free_flow_speed(&self) -> &Vec<Velocity> {
+        &self.free_flow_speed_kmh
+    }
+
     /// Borrow an individual travel time function.
     #[inline(always)]
     pub fn travel_time_function(&self, edge_id: EdgeId) -> PiecewiseLinearFunction {
@@ -175,6 +223,38 @@ impl CapacityGraph {
         PiecewiseLinearFunction::new(&self.departure[edge_id], &self.travel_time[edge_id])
     }
 
+    /// Beckmann objective of the current bucket loads, i.e. the sum of the integrated BPR functions over all used buckets.
+    /// The result is given in vehicles (passenger car equivalents) times milliseconds.
+    pub fn beckmann_objective(&self) -> f64 {
+        (0..self.num_arcs())
+            .filter(|&edge_id| self.max_capacity[edge_id] > 0 && self.free_flow_travel_time[edge_id] < INFINITY)
+            .map(|edge_id| match &self.used_capacity[edge_id] {
+                CapacityBuckets::Unused => 0.0,
+                CapacityBuckets::Used(inner) => inner
+                    .iter()
+                    .map(|&(_, load)| {
+                        self.traffic_function
+                            .travel_time_integral(self.free_flow_travel_time[edge_id], self.max_capacity[edge_id] * PCE_SCALE, load)
+                    })
+                    .sum::<f64>(),
+            })
+            .sum::<f64>()
+            / PCE_SCALE as f64
+    }
+
+    /// timestamps at each node of the given path, starting at `departure`
+    pub fn path_departures(&self, edge_path: &[EdgeId], departure: Timestamp) -> Vec<Timestamp> {
+        let mut departures = Vec::with_capacity(edge_path.len() + 1);
+        let mut current_time = departure;
+        departures.push(current_time);
+
+        for &edge_id in edge_path {
+            current_time += self.travel_time_function(edge_id).eval(current_time);
+            departures.push(current_time);
+        }
+        departures
+    }
+
     pub fn eval_history_free(&self, edge_id: EdgeId, ts: Timestamp) -> Weight {
         let edge_id = edge_id as usize;
 
@@ -183,7 +263,7 @@ impl CapacityGraph {
             CapacityBuckets::Used(inner) => {
                 if self.num_buckets == 1 {
                     self.traffic_function
-                        .travel_time(self.free_flow_travel_time[edge_id], self.max_capacity[edge_id], inner[0].1)
+                        .travel_time(self.free_flow_travel_time[edge_id], self.max_capacity[edge_id] * PCE_SCALE, inner[0].1)
                 } else {
                     match &self.used_speeds[edge_id] {
                         SpeedBuckets::Unused => unimplemented!(),
@@ -206,8 +286,12 @@ impl CapacityGraph {
                 + self.head.capacity()
                 + self.distance.capacity()
                 + self.max_capacity.capacity()
+                + self.num_lanes.capacity()
+                + self.closed_lanes.capacity()
+                + self.lane_capacity.capacity()
                 + self.free_flow_speed_kmh.capacity()
-                + self.free_flow_travel_time.capacity());
+                + self.free_flow_travel_time.capacity()
+                + self.gradient.as_ref().map_or(0, |gradient| gradient.capacity()));
 
         let capacity_bucket_size = self
             .used_capacity
@@ -263,6 +347,11 @@ impl CapacityGraph {
 
     /// round timestamp to nearest bucket interval
     #[inline(always)]
+    /// index of the bucket containing `timestamp`, single-bucket graphs always use bucket 0
+    pub fn bucket_index(&self, timestamp: Timestamp) -> u32 {
+        self.bucket_timestamp(timestamp) / (MAX_BUCKETS / self.num_buckets)
+    }
+
     fn round_timestamp(&self, timestamp: Timestamp) -> Timestamp {
         let bucket_size = MAX_BUCKETS / self.num_buckets;
         bucket_size * ((timestamp % MAX_BUCKETS) / bucket_size)
@@ -287,7 +376,7 @@ impl CapacityGraph {
                     // special-case treatment for single-bucket graphs -> updating the capacities and ttf is straightforward
                     let travel_time = self.traffic_function.travel_time(
                         self.free_flow_travel_time[edge_id],
-                        self.max_capacity[edge_id],
+                        self.max_capacity[edge_id] * PCE_SCALE,
                         self.used_capacity[edge_id].inner()[0].1,
                     );
 
@@ -305,41 +394,8 @@ impl CapacityGraph {
             }
             Some(SpeedBuckets::Used(historic_speeds)) => {
                 // iterate over used speed buckets, combine with historic data
-                let (departure, travel_time) = if let SpeedBuckets::Used(speed_coop) = &self.used_speeds[edge_id] {
-                    let mut speeds = vec![];
-                    let mut coop_idx = 0;
-                    let mut hist_idx = 0;
-
-                    loop {
-                        match (historic_speeds.get(hist_idx), speed_coop.get(coop_idx)) {
-                            (Some((hist_ts, hist_val)), Some((coop_ts, coop_val))) => {
-                                if hist_ts < coop_ts {
-                                    speeds.push((*hist_ts, *hist_val));
-                                    hist_idx += 1;
-                                } else if hist_ts == coop_ts {
-                                    speeds.push((*hist_ts, min(*hist_val, *coop_val)));
-                                    hist_idx += 1;
-                                    coop_idx += 1;
-                                } else {
-                                    speeds.push((*coop_ts, *coop_val));
-                                    coop_idx += 1;
-                                }
-                            }
-                            (Some((hist_ts, hist_val)), None) => {
-                                speeds.push((*hist_ts, *hist_val));
-                                hist_idx += 1;
-                            }
-                            (None, Some((coop_ts, coop_val))) => {
-                                speeds.push((*coop_ts, *coop_val));
-                                coop_idx += 1;
-                            }
-                            (None, None) => break,
-                        }
-                    }
-                    speed_profile_to_tt_profile(&speeds, self.distance[edge_id]).iter().cloned().unzip()
-                } else {
-                    speed_profile_to_tt_profile(historic_speeds, self.distance[edge_id]).iter().cloned().unzip()
-                };
+                let speeds = self.blended_speeds(edge_id, historic_speeds);
+                let (departure, travel_time) = speed_profile_to_tt_profile(&speeds, self.distance[edge_id]).iter().cloned().unzip();
 
                 self.departure[edge_id] = departure;
                 self.travel_time[edge_id] = travel_time;
@@ -347,7 +403,18 @@ impl CapacityGraph {
         }
     }
 
-    pub fn increase_weights(&mut self, edges: &[EdgeId], departure: &[Timestamp]) -> Vec<(EdgeId, Weight, Weight)> {
+    /// speed of an edge under the given load (in PCE units, see `PCE_SCALE`)
+    fn loaded_speed(&self, edge_id: usize, load: Capacity) -> Velocity {
+        self.traffic_function
+            .speed(self.free_flow_speed_kmh[edge_id], self.max_capacity[edge_id] * PCE_SCALE, load)
+    }
+
+    /// Add a path to the graph, i.e. increase the loads of all edges on the path by `load` (in PCE units, see `PCE_SCALE`)
+    ///
+    /// Returns the new minimum and maximum travel time of each modified edge
+    pub fn increase_weights(&mut self, edges: &[EdgeId], departure: &[Timestamp], load: Capacity) -> Vec<(EdgeId, Weight, Weight)> {
+        self.update_system_statistics(edges, departure, load, true);
+
         edges
             .iter()
             .zip(departure.iter())
@@ -364,17 +431,15 @@ impl CapacityGraph {
                         }
                     };
 
-                    self.used_capacity[edge_id] = CapacityBuckets::Used(vec![(0, prev_capacity + 1)]);
+                    self.used_capacity[edge_id] = CapacityBuckets::Used(vec![(0, prev_capacity + load)]);
                 } else {
                     // find suitable bucket in which to insert, then update capacity and adjust speed profile
                     let ts_rounded = self.round_timestamp(timestamp);
                     let next_ts = (ts_rounded + (MAX_BUCKETS / self.num_buckets)) % MAX_BUCKETS;
 
-                    let adjusted_capacity = self.used_capacity[edge_id].increment(ts_rounded);
+                    let adjusted_capacity = self.used_capacity[edge_id].increase(ts_rounded, load);
 
-                    let adjusted_speed = self
-                        .traffic_function
-                        .speed(self.free_flow_speed_kmh[edge_id], self.max_capacity[edge_id], adjusted_capacity);
+                    let adjusted_speed = self.loaded_speed(edge_id, adjusted_capacity);
                     self.used_speeds[edge_id].update(ts_rounded, adjusted_speed, next_ts, self.free_flow_speed_kmh[edge_id]);
                 }
                 self.rebuild_travel_time_profile(edge_id);
@@ -388,12 +453,431 @@ impl CapacityGraph {
             .collect()
     }
 
+    /// Withdraw a previously added path, i.e. decrease the loads of all edges on the path by the `load` it was added with
+    ///
+    /// Returns the new minimum and maximum travel time of each modified edge
+    pub fn decrease_weights(&mut self, edges: &[EdgeId], departure: &[Timestamp], load: Capacity) -> Vec<(EdgeId, Weight, Weight)> {
+        self.update_system_statistics(edges, departure, load, false);
+
+        edges
+            .iter()
+            .zip(departure.iter())
+            .map(|(&edge_id, &timestamp)| {
+                let edge_id = edge_id as usize;
+
+                // single-bucket graphs only use the bucket at midnight
+                let ts_rounded = if self.num_buckets == 1 { 0 } else { self.round_timestamp(timestamp) };
+                let adjusted_capacity = self.used_capacity[edge_id].decrease(ts_rounded, load);
+
+                if !self.used_capacity[edge_id].is_used() {
+                    // no traffic left on this edge -> fall back to free-flow time
+                    self.used_speeds[edge_id] = SpeedBuckets::Unused;
+                    self.departure[edge_id] = vec![0, MAX_BUCKETS];
+                    self.travel_time[edge_id] = vec![self.free_flow_travel_time[edge_id], self.free_flow_travel_time[edge_id]];
+                } else if self.num_buckets > 1 {
+                    let next_ts = (ts_rounded + (MAX_BUCKETS / self.num_buckets)) % MAX_BUCKETS;
+
+                    let adjusted_speed = if adjusted_capacity == 0 {
+                        self.free_flow_speed_kmh[edge_id]
+                    } else {
+                        self.loaded_speed(edge_id, adjusted_capacity)
+                    };
+                    self.used_speeds[edge_id].update(ts_rounded, adjusted_speed, next_ts, self.free_flow_speed_kmh[edge_id]);
+                }
+                self.rebuild_travel_time_profile(edge_id);
+
+                (
+                    edge_id as EdgeId,
+                    self.travel_time[edge_id].iter().min().cloned().unwrap(),
+                    self.travel_time[edge_id].iter().max().cloned().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    /// account (or withdraw) the edge traversals of a path in the system statistics.
+    /// Travel times are taken from the path's departures, only the last edge is evaluated if the arrival is missing.
+    fn update_system_statistics(&mut self, edges: &[EdgeId], departure: &[Timestamp], load: Capacity, add: bool) {
+        for (idx, (&edge_id, &timestamp)) in edges.iter().zip(departure.iter()).enumerate() {
+            let travel_time = match departure.get(idx + 1) {
+                Some(&next_timestamp) => next_timestamp - timestamp,
+                None => self.travel_time_function(edge_id).eval(timestamp),
+            };
+            let distance = self.distance[edge_id as usize];
+
+            if add {
+                self.system_statistics.add(timestamp, travel_time, distance, load);
+            } else {
+                self.system_statistics.remove(timestamp, travel_time, distance, load);
+            }
+        }
+    }
+
+    /// Vehicle-hours and vehicle-kilometers per bucket of all paths added via `increase_weights` (minus withdrawn ones).
+    /// Loads which are set directly (e.g. `set_bucket_loads`, `reconcile_loads`) are not part of these statistics.
+    pub fn system_statistics(&self) -> &SystemStatistics {
+        &self.system_statistics
+    }
+
+    /// shorthand for `system_statistics().intervals()`
+    pub fn interval_statistics(&self) -> Vec<IntervalStatistics> {
+        self.system_statistics.intervals()
+    }
+
+    /// Reconcile the bucket loads with externally observed counts, which are interpreted as passenger cars.
+    ///
+    /// For each observed edge, all of its buckets are scaled by the ratio of the observed to the simulated load
+    /// within the observed buckets. Observed buckets without any simulated load take over the observed count.
+    /// Loads may decrease, so the potential has to be re-customized afterwards.
+    ///
+    /// Returns the new minimum and maximum travel time of each modified edge and a summary of the synchronization
+    pub fn reconcile_loads(&mut self, observations: &[LoadObservation]) -> (Vec<(EdgeId, Weight, Weight)>, LoadFeedStatistics) {
+        // observed count per edge and (rounded) bucket, multiple observations of the same bucket are summed up
+        let mut observed = BTreeMap::<EdgeId, BTreeMap<Timestamp, Capacity>>::new();
+        for observation in observations {
+            *observed
+                .entry(observation.edge_id)
+                .or_default()
+                .entry(self.bucket_timestamp(observation.timestamp))
+                .or_default() += observation.count * PCE_SCALE;
+        }
+
+        let mut statistics = LoadFeedStatistics {
+            num_observations: observations.len(),
+            num_edges: observed.len(),
+            ..LoadFeedStatistics::default()
+        };
+
+        let mut modified = Vec::with_capacity(observed.len());
+        for (edge_id, buckets) in observed {
+            let edge_id = edge_id as usize;
+            if self.max_capacity[edge_id] == 0 {
+                // zero-capacity edges are never traversed
+                continue;
+            }
+
+            let simulated_load = buckets.keys().map(|&ts| self.used_capacity[edge_id].get(ts) as u64).sum::<u64>();
+            let observed_load = buckets.values().map(|&count| count as u64).sum::<u64>();
+            statistics.simulated_load += simulated_load / PCE_SCALE as u64;
+            statistics.observed_load += observed_load / PCE_SCALE as u64;
+
+            // 1. scale all buckets of the edge
+            if simulated_load > 0 {
+                let factor = observed_load as f64 / simulated_load as f64;
+                let scaled = match &self.used_capacity[edge_id] {
+                    CapacityBuckets::Unused => vec![],
+                    CapacityBuckets::Used(inner) => inner
+                        .iter()
+                        .map(|&(ts, load)| (ts, (load as f64 * factor).round() as Capacity))
+                        .collect::<Vec<(Timestamp, Capacity)>>(),
+                };
+                scaled.into_iter().for_each(|(ts, load)| self.set_bucket_load(edge_id, ts, load));
+            }
+
+            // 2. observed buckets without simulated load
+            for (ts, count) in buckets {
+                if self.used_capacity[edge_id].get(ts) == 0 && count > 0 {
+                    self.set_bucket_load(edge_id, ts, count);
+                }
+            }
+
+            modified.push((
+                edge_id as EdgeId,
+                self.travel_time[edge_id].iter().min().cloned().unwrap(),
+                self.travel_time[edge_id].iter().max().cloned().unwrap(),
+            ));
+        }
+
+        (modified, statistics)
+    }
+
+    /// Loads of all edges as `(bucket start, load)` in PCE units (see `PCE_SCALE`), edges without load have an empty profile.
+    pub fn export_bucket_loads(&self) -> Vec<Vec<(Timestamp, Capacity)>> {
+        self.used_capacity
+            .iter()
+            .map(|buckets| match buckets {
+                CapacityBuckets::Unused => Vec::new(),
+                CapacityBuckets::Used(inner) => inner.clone(),
+            })
+            .collect()
+    }
+
+    /// Replace the loads of all edges, e.g. by an externally computed initial congestion state.
+    /// `loads` holds a `(timestamp, load)` profile for each edge, timestamps are rounded to the start of their bucket.
+    /// Loads on edges which can not be traversed are ignored.
+    ///
+    /// Returns the new minimum and maximum travel time of each edge
+    pub fn set_bucket_loads(&mut self, loads: &[Vec<(Timestamp, Capacity)>]) -> Vec<(EdgeId, Weight, Weight)> {
+        assert_eq!(loads.len(), self.num_arcs(), "data containers must have the same size!");
+
+        loads
+            .iter()
+            .enumerate()
+            .map(|(edge_id, profile)| {
+                // zero-capacity edges never carry any load
+                if self.max_capacity[edge_id] > 0 {
+                    self.used_capacity[edge_id] = CapacityBuckets::Unused;
+                    self.used_speeds[edge_id] = SpeedBuckets::Unused;
+                    self.departure[edge_id] = vec![0, MAX_BUCKETS];
+                    self.travel_time[edge_id] = vec![self.free_flow_travel_time[edge_id], self.free_flow_travel_time[edge_id]];
+                    self.rebuild_travel_time_profile(edge_id);
+
+                    for &(timestamp, load) in profile.iter().filter(|&&(_, load)| load > 0) {
+                        let ts_rounded = self.bucket_timestamp(timestamp);
+                        self.set_bucket_load(edge_id, ts_rounded, load);
+                    }
+                }
+
+                (
+                    edge_id as EdgeId,
+                    self.travel_time[edge_id].iter().min().cloned().unwrap(),
+                    self.travel_time[edge_id].iter().max().cloned().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    /// start of the bucket containing `timestamp`, single-bucket graphs only use the bucket at midnight
+    fn bucket_timestamp(&self, timestamp: Timestamp) -> Timestamp {
+        if self.num_buckets == 1 {
+            0
+        } else {
+            self.round_timestamp(timestamp)
+        }
+    }
+
+    /// overwrite the load of a single bucket and adjust the speed profile accordingly
+    fn set_bucket_load(&mut self, edge_id: usize, ts_rounded: Timestamp, load: Capacity) {
+        self.used_capacity[edge_id].set(ts_rounded, load);
+
+        if !self.used_capacity[edge_id].is_used() {
+            // no traffic left on this edge -> fall back to free-flow time
+            self.used_speeds[edge_id] = SpeedBuckets::Unused;
+            self.departure[edge_id] = vec![0, MAX_BUCKETS];
+            self.travel_time[edge_id] = vec![self.free_flow_travel_time[edge_id], self.free_flow_travel_time[edge_id]];
+        } else if self.num_buckets > 1 {
+            let next_ts = (ts_rounded + (MAX_BUCKETS / self.num_buckets)) % MAX_BUCKETS;
+
+            let adjusted_speed = if load == 0 {
+                self.free_flow_speed_kmh[edge_id]
+            } else {
+                self.loaded_speed(edge_id, load)
+            };
+            self.used_speeds[edge_id].update(ts_rounded, adjusted_speed, next_ts, self.free_flow_speed_kmh[edge_id]);
+        }
+        self.rebuild_travel_time_profile(edge_id);
+    }
+
+    /// Model the capacity of each edge as `num_lanes * saturation_flow` (given in vehicles per lane and hour).
+    ///
+    /// Edges without lane information (`num_lanes == 0`) keep their capacity as a single lane,
+    /// edges without capacity remain closed. All lanes are re-opened.
+    pub fn set_lanes(&mut self, num_lanes: Vec<u32>, saturation_flow: Capacity) {
+        assert_eq!(num_lanes.len(), self.num_arcs(), "data containers must have the same size!");
+
+        // same adjustment as in the constructor, more buckets do not allow more traffic flow
+        let lane_capacity = (saturation_flow as f64 * 24.0 / (self.num_buckets as f64)) as Capacity;
+
+        for edge_id in 0..self.num_arcs() {
+            if self.max_capacity[edge_id] > 0 && num_lanes[edge_id] > 0 {
+                self.num_lanes[edge_id] = num_lanes[edge_id];
+                self.lane_capacity[edge_id] = lane_capacity;
+            } else {
+                self.num_lanes[edge_id] = 1;
+                self.lane_capacity[edge_id] = self.max_capacity[edge_id];
+            }
+            self.closed_lanes[edge_id] = 0;
+            self.update_lane_capacity(edge_id);
+        }
+    }
+
+    /// Close `num_closed` lanes of an edge at runtime (0 re-opens all lanes), at least one lane has to remain open.
+    /// The topology is kept, only the capacity of the edge and hence its travel times change.
+    ///
+    /// Returns the new minimum and maximum travel time of the edge
+    pub fn set_closed_lanes(&mut self, edge_id: EdgeId, num_closed: u32) -> (EdgeId, Weight, Weight) {
+        let edge_id = edge_id as usize;
+        assert!(num_closed < self.num_lanes[edge_id], "at least one lane must remain open");
+
+        self.closed_lanes[edge_id] = num_closed;
+        self.update_lane_capacity(edge_id);
+
+        (
+            edge_id as EdgeId,
+            self.travel_time[edge_id].iter().min().cloned().unwrap(),
+            self.travel_time[edge_id].iter().max().cloned().unwrap(),
+        )
+    }
+
+    /// Adjust the free-flow speeds to the gradient of each edge, given the elevation of each node (in meters).
+    ///
+    /// Existing loads are re-evaluated with the new free-flow speeds, historic speeds are kept as they are
+    /// (observed speeds already reflect the gradient). Can only be applied once.
+    pub fn set_elevation(&mut self, elevation: &[f64], model: &GradientSpeedModel) {
+        assert_eq!(elevation.len(), self.num_nodes(), "data containers must have the same size!");
+        assert!(self.gradient.is_none(), "gradients must only be applied once");
+        assert!(self.weather.is_none(), "gradients must be applied before any weather");
+
+        let mut gradient = vec![0.0; self.num_arcs()];
+        for node in 0..self.num_nodes() {
+            for edge_id in self.first_out[node] as usize..self.first_out[node + 1] as usize {
+                let rise = elevation[self.head[edge_id] as usize] - elevation[node];
+                gradient[edge_id] = (100.0 * rise / self.distance[edge_id] as f64) as f32;
+            }
+        }
+
+        for edge_id in 0..self.num_arcs() {
+            let free_flow_time = self.free_flow_travel_time[edge_id];
+            if self.max_capacity[edge_id] == 0 || free_flow_time >= INFINITY || free_flow_time == 1 {
+                // closed and dummy edges keep their travel times
+                continue;
+            }
+
+            let factor = model.speed_factor(gradient[edge_id] as f64);
+            let speed = max((self.free_flow_speed_kmh[edge_id] as f64 * factor).round() as Weight, 1);
+            self.set_free_flow_speed(edge_id, speed);
+        }
+
+        self.gradient = Some(gradient);
+    }
+
+    /// Scale the free-flow speed and capacity of an edge according to the weather (the default factor restores the original values).
+    /// Closed and dummy edges are not affected. Existing loads are re-evaluated with the new speed and capacity.
+    ///
+    /// Returns the new minimum and maximum travel time of the edge
+    pub fn set_weather(&mut self, edge_id: EdgeId, factor: WeatherFactor) -> (EdgeId, Weight, Weight) {
+        let edge_id = edge_id as usize;
+        if self.weather.is_none() {
+            self.weather = Some((vec![WeatherFactor::default(); self.num_arcs()], self.free_flow_speed_kmh.clone()));
+        }
+        let (factors, base_speed) = self.weather.as_mut().unwrap();
+        factors[edge_id] = factor;
+        let speed = max((base_speed[edge_id] as f64 * factor.speed).round() as Weight, 1);
+
+        let free_flow_time = self.free_flow_travel_time[edge_id];
+        if self.max_capacity[edge_id] > 0 && free_flow_time < INFINITY && free_flow_time != 1 {
+            self.set_free_flow_speed(edge_id, speed);
+        }
+
+        (
+            edge_id as EdgeId,
+            self.travel_time[edge_id].iter().min().cloned().unwrap(),
+            self.travel_time[edge_id].iter().max().cloned().unwrap(),
+        )
+    }
+
+    /// current weather factor of an edge, see `set_weather`
+    pub fn weather(&self, edge_id: EdgeId) -> WeatherFactor {
+        self.weather.as_ref().map_or(WeatherFactor::default(), |(factors, _)| factors[edge_id as usize])
+    }
+
+    /// change the free-flow speed of an edge and rebuild its travel times from scratch
+    fn set_free_flow_speed(&mut self, edge_id: usize, speed: Velocity) {
+        self.free_flow_speed_kmh[edge_id] = speed;
+        self.free_flow_travel_time[edge_id] = 3600 * self.distance[edge_id] / speed;
+
+        // speed buckets are re-filled with the new free-flow speed
+        self.used_speeds[edge_id] = SpeedBuckets::Unused;
+        self.departure[edge_id] = vec![0, MAX_BUCKETS];
+        self.travel_time[edge_id] = vec![self.free_flow_travel_time[edge_id], self.free_flow_travel_time[edge_id]];
+        self.update_lane_capacity(edge_id);
+        if !self.used_capacity[edge_id].is_used() {
+            self.rebuild_travel_time_profile(edge_id);
+        }
+    }
+
+    /// Restrict edges to certain vehicle classes (bit mask per edge, e.g. `1 << BUS_CLASS` for bus lanes).
+    /// Dedicated lanes are modelled as separate parallel edges, so their capacity is not available to general traffic.
+    pub fn set_edge_classes(&mut self, edge_classes: Vec<u32>) {
+        assert_eq!(edge_classes.len(), self.num_arcs(), "data containers must have the same size!");
+        self.edge_classes = Some(edge_classes);
+    }
+
+    pub fn edge_classes(&self) -> Option<&Vec<u32>> {
+        self.edge_classes.as_ref()
+    }
+
+    /// whether vehicles of `class` may use the edge at all
+    #[inline(always)]
+    pub fn is_available(&self, edge_id: EdgeId, class: VehicleClass) -> bool {
+        self.edge_classes
+            .as_ref()
+            .map_or(true, |edge_classes| edge_classes[edge_id as usize] & (1 << class) != 0)
+    }
+
+    /// Travel time functions as seen by vehicles of `class`, i.e. infinite on all edges unavailable to the class.
+    /// The departures remain unchanged. Without a class (or class restrictions), the travel times are borrowed.
+    pub fn class_travel_times(&self, class: Option<VehicleClass>) -> Cow<'_, Vec<Vec<Weight>>> {
+        match (class, &self.edge_classes) {
+            (Some(class), Some(_)) => Cow::Owned(
+                self.travel_time
+                    .par_iter()
+                    .enumerate()
+                    .map(|(edge_id, travel_time)| {
+                        if self.is_available(edge_id as EdgeId, class) {
+                            travel_time.clone()
+                        } else {
+                            vec![INFINITY; travel_time.len()]
+                        }
+                    })
+                    .collect(),
+            ),
+            _ => Cow::Borrowed(&self.travel_time),
+        }
+    }
+
+    /// gradient of each edge in percent (positive uphill), see `set_elevation`
+    pub fn gradient(&self) -> Option<&Vec<f32>> {
+        self.gradient.as_ref()
+    }
+
+    /// Static energy metric (in joules) of each edge at its free-flow speed, flat if no elevation data was added.
+    /// Closed edges are not traversable.
+    pub fn energy_consumption(&self, model: &EnergyModel) -> Vec<Weight> {
+        (0..self.num_arcs())
+            .map(|edge_id| {
+                if self.free_flow_travel_time[edge_id] >= INFINITY {
+                    INFINITY
+                } else {
+                    let gradient = self.gradient.as_ref().map_or(0.0, |gradient| gradient[edge_id] as f64);
+                    let energy = model.edge_energy(self.distance[edge_id] as f64, self.free_flow_speed_kmh[edge_id] as f64, gradient);
+                    min(energy.round() as Weight, INFINITY - 1)
+                }
+            })
+            .collect()
+    }
+
+    /// derive the capacity from the open lanes and re-evaluate the traffic function on all used buckets
+    fn update_lane_capacity(&mut self, edge_id: usize) {
+        if self.max_capacity[edge_id] == 0 {
+            // zero-capacity edges must not be traversed at all
+            return;
+        }
+        self.max_capacity[edge_id] = self.weather_capacity(edge_id);
+
+        let loads = match &self.used_capacity[edge_id] {
+            CapacityBuckets::Unused => vec![],
+            CapacityBuckets::Used(inner) => inner.clone(),
+        };
+        loads.into_iter().for_each(|(ts, load)| self.set_bucket_load(edge_id, ts, load));
+    }
+
+    /// capacity of the open lanes, scaled by the weather
+    fn weather_capacity(&self, edge_id: usize) -> Capacity {
+        let capacity = (self.num_lanes[edge_id] - self.closed_lanes[edge_id]) * self.lane_capacity[edge_id];
+        match &self.weather {
+            None => capacity,
+            Some((factors, _)) => max((capacity as f64 * factors[edge_id].capacity).round() as Capacity, 1),
+        }
+    }
+
     pub fn reset_weights(&mut self) {
         for edge_id in 0..self.num_arcs() {
             self.used_capacity[edge_id] = CapacityBuckets::Unused;
             self.departure[edge_id] = vec![0, MAX_BUCKETS];
             self.travel_time[edge_id] = vec![self.free_flow_travel_time[edge_id], self.free_flow_travel_time[edge_id]];
         }
+        self.system_statistics.reset();
     }
 
     pub fn export_speeds(&self) -> Vec<Vec<(u32, u32)>> {
@@ -406,12 +890,319 @@ impl CapacityGraph {
             .collect()
     }
 
-    pub fn add_historic_speeds(&mut self, speeds: Vec<SpeedBuckets>) {
+    /// Export the speeds the graph ended up with, i.e. the cooperative speeds combined with the historic speeds (if any).
+    ///
+    /// The result can be stored via `store_speed_profiles` and loaded as historic speeds of the next run.
+    /// Edges which are neither used nor covered by historic data are exported without any profile (free-flow speed).
+    pub fn export_learned_speeds(&self) -> Vec<Vec<(Timestamp, Velocity)>> {
+        (0..self.num_arcs())
+            .map(|edge_id| {
+                let historic = self.historic_speeds.as_ref().map(|speeds| &speeds[edge_id]);
+
+                if self.num_buckets == 1 {
+                    // single-bucket graphs don't maintain speed buckets, derive a constant speed from the load
+                    return match (&self.used_capacity[edge_id], historic) {
+                        (CapacityBuckets::Used(inner), Some(SpeedBuckets::Used(historic_speeds))) => {
+                            let speed = self.loaded_speed(edge_id, inner[0].1);
+                            historic_speeds.iter().map(|&(ts, hist_speed)| (ts, min(hist_speed, speed))).collect()
+                        }
+                        (CapacityBuckets::Used(inner), _) => {
+                            let speed = self.loaded_speed(edge_id, inner[0].1);
+                            vec![(0, speed), (MAX_BUCKETS, speed)]
+                        }
+                        (CapacityBuckets::Unused, Some(SpeedBuckets::Used(historic_speeds))) => historic_speeds.clone(),
+                        (CapacityBuckets::Unused, _) => Vec::new(),
+                    };
+                }
+
+                match (&self.used_speeds[edge_id], historic) {
+                    (_, Some(SpeedBuckets::Used(historic_speeds))) => self.blended_speeds(edge_id, historic_speeds),
+                    (SpeedBuckets::Used(speed_coop), _) => speed_coop.clone(),
+                    (SpeedBuckets::Unused, _) => Vec::new(),
+                }
+            })
+            .collect()
+    }
+
+    /// Add historic speeds as prediction of future traffic conditions, combined with the live speeds as given by `blend`
+    pub fn add_historic_speeds(&mut self, speeds: Vec<SpeedBuckets>, blend: SpeedBlend) {
         debug_assert_eq!(self.num_arcs(), speeds.len());
         self.historic_speeds = Some(speeds);
+        self.speed_blend = blend;
 
         for edge_id in 0..self.num_arcs() {
             self.rebuild_travel_time_profile(edge_id);
         }
     }
+
+    pub fn historic_speeds(&self) -> Option<&Vec<SpeedBuckets>> {
+        self.historic_speeds.as_ref()
+    }
+
+    pub fn speed_blend(&self) -> &SpeedBlend {
+        &self.speed_blend
+    }
+
+    /// Change the blend of historic and live speeds while the graph is in use, e.g. to rely on live loads later in the day.
+    ///
+    /// Returns the new minimum and maximum travel time of each edge with historic speeds
+    pub fn set_speed_blend(&mut self, blend: SpeedBlend) -> Vec<(EdgeId, Weight, Weight)> {
+        self.speed_blend = blend;
+
+        let edges = match &self.historic_speeds {
+            None => return Vec::new(),
+            Some(speeds) => (0..self.num_arcs()).filter(|&edge_id| speeds[edge_id].is_used()).collect::<Vec<usize>>(),
+        };
+
+        edges
+            .into_iter()
+            .map(|edge_id| {
+                self.rebuild_travel_time_profile(edge_id);
+                (
+                    edge_id as EdgeId,
+                    self.travel_time[edge_id].iter().min().cloned().unwrap(),
+                    self.travel_time[edge_id].iter().max().cloned().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    /// combined historic and live speeds of an edge
+    fn blended_speeds(&self, edge_id: usize, historic_speeds: &[(Timestamp, Velocity)]) -> Vec<(Timestamp, Velocity)> {
+        // historic speeds were observed under normal conditions, so they are subject to the weather as well
+        let weather_speed = self.weather(edge_id as EdgeId).speed;
+        let scaled_speeds;
+        let historic_speeds = if weather_speed != 1.0 {
+            scaled_speeds = historic_speeds
+                .iter()
+                .map(|&(ts, speed)| (ts, max((speed as f64 * weather_speed).round() as Velocity, 1)))
+                .collect::<Vec<(Timestamp, Velocity)>>();
+            &scaled_speeds
+        } else {
+            historic_speeds
+        };
+
+        match (&self.speed_blend, &self.used_speeds[edge_id]) {
+            (SpeedBlend::Minimum, SpeedBuckets::Unused) => historic_speeds.to_vec(),
+            (blend, SpeedBuckets::Used(speed_coop)) => blend.blend(historic_speeds, speed_coop),
+            (blend, SpeedBuckets::Unused) => {
+                let free_flow_speed = self.free_flow_speed_kmh[edge_id];
+                blend.blend(historic_speeds, &[(0, free_flow_speed), (MAX_BUCKETS, free_flow_speed)])
+            }
+        }
+    }
+}
+
+/// Full state of the graph, i.e. the static data as well as bucket loads, speeds, historic speeds and the derived travel time functions.
+/// A warmed-up graph can be stored once and serve as common starting point of several experiments.
+///
+/// Per-edge profiles are stored flat with a prefix sum over the edges, edges without buckets have an empty range.
+/// Optional data (historic speeds, gradient, weather, edge classes) is only written if present.
+impl Deconstruct for CapacityGraph {
+    fn store_each(&self, store: &dyn Fn(&str, &dyn Store) -> std::io::Result<()>) -> std::io::Result<()> {
+        store("num_buckets", &vec![self.num_buckets])?;
+        store("first_out", &self.first_out)?;
+        store("head", &self.head)?;
+
+        let used_capacity = self.used_capacity.iter().map(|buckets| match buckets {
+            CapacityBuckets::Unused => &[][..],
+            CapacityBuckets::Used(inner) => &inner[..],
+        });
+        store_profiles(store, "capacity", used_capacity)?;
+        store_profiles(store, "speed", self.used_speeds.iter().map(speed_bucket_entries))?;
+        if let Some(historic_speeds) = &self.historic_speeds {
+            store_profiles(store, "historic_speed", historic_speeds.iter().map(speed_bucket_entries))?;
+        }
+
+        let ttf_prefix_sum = prefix_sum(self.departure.iter().map(|departure| departure.len()));
+        store("ttf_prefix_sum", &ttf_prefix_sum)?;
+        store("ttf_departure", &self.departure.concat())?;
+        store("ttf_travel_time", &self.travel_time.concat())?;
+
+        let (blend_timestamps, blend_weights): (Vec<Timestamp>, Vec<f64>) = match &self.speed_blend {
+            SpeedBlend::Minimum => (Vec::new(), Vec::new()),
+            SpeedBlend::Weighted(weights) => weights.iter().cloned().unzip(),
+        };
+        store("speed_blend_timestamps", &blend_timestamps)?;
+        store("speed_blend_weights", &blend_weights)?;
+
+        store("distance", &self.distance)?;
+        store("max_capacity", &self.max_capacity)?;
+        store("num_lanes", &self.num_lanes)?;
+        store("closed_lanes", &self.closed_lanes)?;
+        store("lane_capacity", &self.lane_capacity)?;
+        store("free_flow_travel_time", &self.free_flow_travel_time)?;
+        store("free_flow_speed", &self.free_flow_speed_kmh)?;
+
+        if let Some(gradient) = &self.gradient {
+            store("gradient", gradient)?;
+        }
+        if let Some((factors, base_speeds)) = &self.weather {
+            store("weather_speed", &factors.iter().map(|factor| factor.speed).collect::<Vec<f64>>())?;
+            store("weather_capacity", &factors.iter().map(|factor| factor.capacity).collect::<Vec<f64>>())?;
+            store("weather_free_flow_speed", base_speeds)?;
+        }
+        if let Some(edge_classes) = &self.edge_classes {
+            store("edge_classes", edge_classes)?;
+        }
+        let (vehicle_time, vehicle_distance) = self.system_statistics.raw_values();
+        store("system_vehicle_time", vehicle_time)?;
+        store("system_vehicle_distance", vehicle_distance)?;
+
+        store("bpr_alpha", &vec![self.traffic_function.alpha()])?;
+        store("bpr_beta", &vec![self.traffic_function.beta()])?;
+        Ok(())
+    }
+}
+
+impl Reconstruct for CapacityGraph {
+    fn reconstruct_with(loader: Loader) -> std::io::Result<Self> {
+        let num_buckets = loader.load::<Vec<u32>, _>("num_buckets")?[0];
+        let first_out: Vec<EdgeId> = loader.load("first_out")?;
+        let head: Vec<NodeId> = loader.load("head")?;
+        let num_edges = head.len();
+
+        let used_capacity = load_profiles(&loader, "capacity")?
+            .into_iter()
+            .map(|inner| {
+                if inner.is_empty() {
+                    CapacityBuckets::Unused
+                } else {
+                    CapacityBuckets::Used(inner)
+                }
+            })
+            .collect::<Vec<CapacityBuckets>>();
+        let used_speeds = load_profiles(&loader, "speed")?.into_iter().map(speed_buckets).collect::<Vec<SpeedBuckets>>();
+        let historic_speeds = if loader.path().join("historic_speed_prefix_sum").exists() {
+            Some(
+                load_profiles(&loader, "historic_speed")?
+                    .into_iter()
+                    .map(speed_buckets)
+                    .collect::<Vec<SpeedBuckets>>(),
+            )
+        } else {
+            None
+        };
+
+        let ttf_prefix_sum: Vec<u32> = loader.load("ttf_prefix_sum")?;
+        let ttf_departure: Vec<Timestamp> = loader.load("ttf_departure")?;
+        let ttf_travel_time: Vec<Weight> = loader.load("ttf_travel_time")?;
+        let (departure, travel_time): (Vec<Vec<Timestamp>>, Vec<Vec<Weight>>) = ttf_prefix_sum
+            .windows(2)
+            .map(|range| {
+                let range = range[0] as usize..range[1] as usize;
+                (ttf_departure[range.clone()].to_vec(), ttf_travel_time[range].to_vec())
+            })
+            .unzip();
+
+        let blend_timestamps: Vec<Timestamp> = loader.load("speed_blend_timestamps")?;
+        let blend_weights: Vec<f64> = loader.load("speed_blend_weights")?;
+        let speed_blend = if blend_timestamps.is_empty() {
+            SpeedBlend::Minimum
+        } else {
+            SpeedBlend::Weighted(blend_timestamps.into_iter().zip(blend_weights).collect())
+        };
+
+        let optional = |name: &str| loader.path().join(name).exists();
+        let gradient = if optional("gradient") { Some(loader.load("gradient")?) } else { None };
+        let weather = if optional("weather_speed") {
+            let speed: Vec<f64> = loader.load("weather_speed")?;
+            let capacity: Vec<f64> = loader.load("weather_capacity")?;
+            let factors = speed
+                .into_iter()
+                .zip(capacity)
+                .map(|(speed, capacity)| WeatherFactor { speed, capacity })
+                .collect::<Vec<WeatherFactor>>();
+            Some((factors, loader.load("weather_free_flow_speed")?))
+        } else {
+            None
+        };
+        let edge_classes = if optional("edge_classes") { Some(loader.load("edge_classes")?) } else { None };
+        let system_statistics = if optional("system_vehicle_time") {
+            SystemStatistics::from_raw_values(loader.load("system_vehicle_time")?, loader.load("system_vehicle_distance")?)
+        } else {
+            SystemStatistics::new(num_buckets)
+        };
+
+        let graph = Self {
+            num_buckets,
+            first_out,
+            head,
+            used_capacity,
+            used_speeds,
+            departure,
+            travel_time,
+            historic_speeds,
+            speed_blend,
+            system_statistics,
+            distance: loader.load("distance")?,
+            max_capacity: loader.load("max_capacity")?,
+            num_lanes: loader.load("num_lanes")?,
+            closed_lanes: loader.load("closed_lanes")?,
+            lane_capacity: loader.load("lane_capacity")?,
+            free_flow_travel_time: loader.load("free_flow_travel_time")?,
+            free_flow_speed_kmh: loader.load("free_flow_speed")?,
+            gradient,
+            weather,
+            edge_classes,
+            traffic_function: BPRTrafficFunction::new(loader.load::<Vec<f64>, _>("bpr_alpha")?[0], loader.load::<Vec<i32>, _>("bpr_beta")?[0]),
+        };
+
+        if graph.used_capacity.len() != num_edges || graph.departure.len() != num_edges || graph.distance.len() != num_edges {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "inconsistent number of edges in stored graph",
+            ));
+        }
+        Ok(graph)
+    }
+}
+
+fn speed_bucket_entries(buckets: &SpeedBuckets) -> &[(Timestamp, Velocity)] {
+    match buckets {
+        SpeedBuckets::Unused => &[],
+        SpeedBuckets::Used(inner) => inner,
+    }
+}
+
+fn speed_buckets(inner: Vec<(Timestamp, Velocity)>) -> SpeedBuckets {
+    if inner.is_empty() {
+        SpeedBuckets::Unused
+    } else {
+        SpeedBuckets::Used(inner)
+    }
+}
+
+fn prefix_sum(lengths: impl Iterator<Item = usize>) -> Vec<u32> {
+    let mut ret = vec![0];
+    for len in lengths {
+        ret.push(*ret.last().unwrap() + len as u32);
+    }
+    ret
+}
+
+/// store per-edge `(timestamp, value)` profiles as `<name>_prefix_sum`, `<name>_timestamps` and `<name>_values`
+fn store_profiles<'a>(
+    store: &dyn Fn(&str, &dyn Store) -> std::io::Result<()>,
+    name: &str,
+    profiles: impl Iterator<Item = &'a [(Timestamp, u32)]> + Clone,
+) -> std::io::Result<()> {
+    let (timestamps, values): (Vec<Timestamp>, Vec<u32>) = profiles.clone().flatten().cloned().unzip();
+    store(&format!("{}_prefix_sum", name), &prefix_sum(profiles.map(|profile| profile.len())))?;
+    store(&format!("{}_timestamps", name), &timestamps)?;
+    store(&format!("{}_values", name), &values)
+}
+
+fn load_profiles(loader: &Loader, name: &str) -> std::io::Result<Vec<Vec<(Timestamp, u32)>>> {
+    let prefix_sum: Vec<u32> = loader.load(format!("{}_prefix_sum", name))?;
+    let timestamps: Vec<Timestamp> = loader.load(format!("{}_timestamps", name))?;
+    let values: Vec<u32> = loader.load(format!("{}_values", name))?;
+
+    Ok(prefix_sum
+        .windows(2)
+        .map(|range| {
+            let range = range[0] as usize..range[1] as usize;
+            timestamps[range.clone()].iter().cloned().zip(values[range].iter().cloned()).collect()
+        })
+        .collect())
 }
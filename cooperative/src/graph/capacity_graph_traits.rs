@@ -1,5 +1,7 @@
 use crate::graph::capacity_graph::CapacityGraph;
-use rust_road_router::datastr::graph::{EdgeIdGraph, EdgeIdT, EdgeRandomAccessGraph, Graph, Link, LinkIterable, NodeId, NodeIdT};
+use rust_road_router::datastr::graph::{
+    EdgeId, EdgeIdGraph, EdgeIdT, EdgeIterGraph, EdgeRandomAccessGraph, Graph, Link, LinkIterable, NodeId, NodeIdT, Weight,
+};
 use std::ops::Range;
 
 impl Graph for CapacityGraph {
@@ -91,3 +93,15 @@ impl LinkIterable<Link> for CapacityGraph {
         })
     }
 }
+
+/// all arcs with their free-flow travel time, e.g. for analysis and export
+impl EdgeIterGraph for CapacityGraph {
+    type EdgeIter<'a> = impl Iterator<Item = (NodeId, NodeId, EdgeId, Weight)> + 'a;
+
+    fn edge_iter(&self) -> Self::EdgeIter<'_> {
+        (0..self.num_nodes() as NodeId).flat_map(move |node| {
+            self.neighbor_edge_indices(node)
+                .map(move |edge_id| (node, self.head()[edge_id as usize], edge_id, self.free_flow_time()[edge_id as usize]))
+        })
+    }
+}
@@ -25,26 +25,102 @@ impl CapacityBuckets {
 
     /// increment the capacity at `ts` by one and returns the updated value
     pub fn increment(&mut self, ts: Timestamp) -> Capacity {
+        self.increase(ts, 1)
+    }
+
+    /// increase the capacity at `ts` by `amount` and returns the updated value
+    pub fn increase(&mut self, ts: Timestamp, amount: Capacity) -> Capacity {
         match self {
             CapacityBuckets::Unused => {
-                *self = CapacityBuckets::Used(vec![(ts, 1)]);
-                1
+                *self = CapacityBuckets::Used(vec![(ts, amount)]);
+                amount
             }
             CapacityBuckets::Used(inner) => {
                 let pos = inner.binary_search_by_key(&ts, |&(bucket_ts, _)| bucket_ts);
 
                 if let Ok(pos) = pos {
-                    inner[pos].1 += 1;
+                    inner[pos].1 += amount;
                     inner[pos].1
                 } else if let Err(pos) = pos {
-                    inner.insert(pos, (ts, 1));
-                    1
+                    inner.insert(pos, (ts, amount));
+                    amount
                 } else {
                     unimplemented!()
                 }
             }
         }
     }
+
+    /// capacity of the bucket starting at `ts`
+    pub fn get(&self, ts: Timestamp) -> Capacity {
+        match self {
+            CapacityBuckets::Unused => 0,
+            CapacityBuckets::Used(inner) => inner
+                .binary_search_by_key(&ts, |&(bucket_ts, _)| bucket_ts)
+                .map(|pos| inner[pos].1)
+                .unwrap_or(0),
+        }
+    }
+
+    /// overwrite the capacity at `ts`, empty buckets are removed
+    pub fn set(&mut self, ts: Timestamp, capacity: Capacity) {
+        match self {
+            CapacityBuckets::Unused => {
+                if capacity > 0 {
+                    *self = CapacityBuckets::Used(vec![(ts, capacity)]);
+                }
+            }
+            CapacityBuckets::Used(inner) => {
+                match inner.binary_search_by_key(&ts, |&(bucket_ts, _)| bucket_ts) {
+                    Ok(pos) if capacity == 0 => {
+                        inner.remove(pos);
+                    }
+                    Ok(pos) => inner[pos].1 = capacity,
+                    Err(pos) if capacity > 0 => inner.insert(pos, (ts, capacity)),
+                    Err(_) => {}
+                }
+
+                if inner.is_empty() {
+                    *self = CapacityBuckets::Unused;
+                }
+            }
+        }
+    }
+
+    /// decrement the capacity at `ts` by one and returns the updated value
+    ///
+    /// empty buckets are removed, the container falls back to `Unused` if no bucket remains
+    pub fn decrement(&mut self, ts: Timestamp) -> Capacity {
+        self.decrease(ts, 1)
+    }
+
+    /// decrease the capacity at `ts` by `amount` (saturating at zero) and returns the updated value
+    ///
+    /// empty buckets are removed, the container falls back to `Unused` if no bucket remains
+    pub fn decrease(&mut self, ts: Timestamp, amount: Capacity) -> Capacity {
+        match self {
+            CapacityBuckets::Unused => 0,
+            CapacityBuckets::Used(inner) => {
+                let capacity = match inner.binary_search_by_key(&ts, |&(bucket_ts, _)| bucket_ts) {
+                    Ok(pos) => {
+                        inner[pos].1 = inner[pos].1.saturating_sub(amount);
+                        let capacity = inner[pos].1;
+
+                        if capacity == 0 {
+                            inner.remove(pos);
+                        }
+                        capacity
+                    }
+                    Err(_) => 0,
+                };
+
+                if inner.is_empty() {
+                    *self = CapacityBuckets::Unused;
+                }
+                capacity
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
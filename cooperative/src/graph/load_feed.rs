@@ -0,0 +1,36 @@
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::EdgeId;
+
+use crate::graph::Capacity;
+
+/// Externally observed load of an edge, e.g. the vehicle count of a loop detector.
+/// The count refers to the bucket containing `timestamp`, i.e. it is comparable to the internal bucket loads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadObservation {
+    pub edge_id: EdgeId,
+    pub timestamp: Timestamp,
+    pub count: Capacity,
+}
+
+impl LoadObservation {
+    pub fn new(edge_id: EdgeId, timestamp: Timestamp, count: Capacity) -> Self {
+        Self { edge_id, timestamp, count }
+    }
+}
+
+/// Summary of a single synchronization with an external load feed
+#[derive(Debug, Clone, Default)]
+pub struct LoadFeedStatistics {
+    pub num_observations: usize,
+    pub num_edges: usize,
+    /// total simulated and observed load of all observed buckets, prior to the synchronization
+    pub simulated_load: u64,
+    pub observed_load: u64,
+}
+
+/// Observations within `[start, end)`, observations must be sorted by timestamp
+pub fn observations_in_range(observations: &[LoadObservation], start: Timestamp, end: Timestamp) -> &[LoadObservation] {
+    let first = observations.partition_point(|observation| observation.timestamp < start);
+    let last = observations.partition_point(|observation| observation.timestamp < end);
+    &observations[first..last.max(first)]
+}
@@ -1,14 +1,25 @@
 use rust_road_router::datastr::graph::Weight;
 
+pub mod access_restrictions;
 pub mod capacity_graph;
 pub mod capacity_graph_traits;
 pub mod edge_buckets;
+pub mod load_feed;
+pub mod parking;
+pub mod speed_blend;
+pub mod system_statistics;
 pub mod traffic_functions;
 pub mod travel_time_function;
+pub mod weather;
 
 pub type Capacity = u32;
 pub type Velocity = u32;
 pub const MAX_BUCKETS: u32 = 86400000; //max timestamp
+/// bucket loads are accounted in passenger car equivalents (PCE), scaled by this factor to support fractional factors
+/// (e.g. trucks = 2.5), i.e. a passenger car adds a load of `PCE_SCALE` to each bucket on its path
+pub const PCE_SCALE: Capacity = 10;
+/// default saturation flow of a single lane, given in vehicles per hour
+pub const DEFAULT_SATURATION_FLOW: Capacity = 1800;
 
 #[inline(always)]
 pub fn travel_time(speed_km_h: u32, len_m: u32) -> Weight {
@@ -20,3 +31,10 @@ pub fn velocity(len_m: Weight, time_s: Weight) -> Velocity {
     debug_assert!(len_m > 0 && time_s > 0, "Invalid distance/time values! (must be > 0)");
     (len_m * 36) / (time_s * 10)
 }
+
+/// convert a passenger car equivalent factor (1.0 = passenger car) into bucket load units
+#[inline(always)]
+pub fn pce_load(pce_factor: f32) -> Capacity {
+    debug_assert!(pce_factor > 0.0, "PCE factor must be positive!");
+    ((pce_factor * PCE_SCALE as f32).round() as Capacity).max(1)
+}
@@ -0,0 +1,117 @@
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{NodeId, Weight};
+
+use crate::graph::access_restrictions::polygon_contains;
+
+// search times rise slowly while spaces are available and sharply once the zone fills up, similar to the BPR function
+const OCCUPANCY_EXPONENT: f64 = 4.0;
+
+/// Polygonal zone (e.g. a city center) with a limited number of parking spaces.
+/// All times are given in milliseconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParkingZone {
+    /// outer ring as `(longitude, latitude)`
+    pub polygon: Vec<(f64, f64)>,
+    /// search time in an empty zone
+    pub base_search_time: Weight,
+    /// search time in a fully occupied zone
+    pub max_search_time: Weight,
+    /// number of parking spaces
+    pub capacity: u32,
+    /// how long an arriving vehicle occupies its space
+    pub dwell_time: Weight,
+}
+
+impl ParkingZone {
+    pub fn new(polygon: Vec<(f64, f64)>, base_search_time: Weight, max_search_time: Weight, capacity: u32, dwell_time: Weight) -> Self {
+        assert!(polygon.len() >= 3, "a parking zone needs at least 3 points");
+        assert!(
+            base_search_time <= max_search_time,
+            "the base search time must not exceed the maximum search time"
+        );
+        assert!(capacity > 0 && dwell_time > 0, "capacity and dwell time must be positive");
+
+        Self {
+            polygon,
+            base_search_time,
+            max_search_time,
+            capacity,
+            dwell_time,
+        }
+    }
+
+    pub fn contains(&self, lon: f64, lat: f64) -> bool {
+        polygon_contains(&self.polygon, lon, lat)
+    }
+
+    /// search time if `occupied` spaces are currently taken
+    pub fn search_time(&self, occupied: u32) -> Weight {
+        let ratio = (occupied as f64 / self.capacity as f64).min(1.0);
+        self.base_search_time + ((self.max_search_time - self.base_search_time) as f64 * ratio.powf(OCCUPANCY_EXPONENT)).round() as Weight
+    }
+}
+
+/// Destination-dependent terminal cost: the time to find a parking space after arriving at the destination node.
+/// The occupancy of each zone is given by the vehicles parked within the last `dwell_time`,
+/// so busy (central) destinations get more expensive the more vehicles are routed towards them.
+/// Destinations outside of all zones have no search time.
+#[derive(Debug, Clone)]
+pub struct ParkingModel {
+    zones: Vec<ParkingZone>,
+    // zone of each node, the first containing one if zones overlap
+    node_zone: Vec<Option<u32>>,
+    // sorted parking times of all vehicles per zone
+    parked: Vec<Vec<Timestamp>>,
+}
+
+impl ParkingModel {
+    pub fn new(zones: Vec<ParkingZone>, longitude: &[f64], latitude: &[f64]) -> Self {
+        assert_eq!(longitude.len(), latitude.len(), "data containers must have the same size!");
+
+        let node_zone = longitude
+            .iter()
+            .zip(latitude.iter())
+            .map(|(&lon, &lat)| zones.iter().position(|zone| zone.contains(lon, lat)).map(|zone| zone as u32))
+            .collect();
+        let parked = vec![Vec::new(); zones.len()];
+
+        Self { zones, node_zone, parked }
+    }
+
+    pub fn zones(&self) -> &[ParkingZone] {
+        &self.zones
+    }
+
+    /// number of nodes inside at least one zone
+    pub fn num_zone_nodes(&self) -> usize {
+        self.node_zone.iter().filter(|zone| zone.is_some()).count()
+    }
+
+    /// number of spaces of `zone` occupied at `ts`
+    pub fn occupancy(&self, zone: usize, ts: Timestamp) -> u32 {
+        let parked = &self.parked[zone];
+        let first = parked.partition_point(|&parked_at| parked_at + self.zones[zone].dwell_time <= ts);
+        let last = parked.partition_point(|&parked_at| parked_at <= ts);
+        (last - first) as u32
+    }
+
+    /// expected search time of a vehicle arriving at `node` at `arrival`, without occupying a space
+    pub fn search_time(&self, node: NodeId, arrival: Timestamp) -> Weight {
+        match self.node_zone[node as usize] {
+            Some(zone) => self.zones[zone as usize].search_time(self.occupancy(zone as usize, arrival)),
+            None => 0,
+        }
+    }
+
+    /// search a space for a vehicle arriving at `node` at `arrival` and occupy it, returns the search time
+    pub fn park(&mut self, node: NodeId, arrival: Timestamp) -> Weight {
+        let search_time = self.search_time(node, arrival);
+
+        if let Some(zone) = self.node_zone[node as usize] {
+            let parked = &mut self.parked[zone as usize];
+            let parked_at = arrival + search_time;
+            parked.insert(parked.partition_point(|&ts| ts <= parked_at), parked_at);
+        }
+        search_time
+    }
+}
@@ -0,0 +1,113 @@
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use std::cmp::min;
+
+use crate::graph::{Velocity, MAX_BUCKETS};
+
+/// Strategy to combine historic speeds with the live (cooperative) speeds of an edge
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum SpeedBlend {
+    /// Interleave both profiles, taking the lower speed on common breakpoints
+    #[default]
+    Minimum,
+    /// Weighted average of historic and live speeds. The weight of the historic speeds is a
+    /// piecewise constant function over the day, given by `(start, weight)` pairs starting at 0.
+    /// Edges without live data are blended with their free-flow speed.
+    Weighted(Vec<(Timestamp, f64)>),
+}
+
+impl SpeedBlend {
+    /// Use the same weight for historic speeds over the whole day
+    pub fn constant(historic_weight: f64) -> Self {
+        Self::weighted(vec![(0, historic_weight)])
+    }
+
+    /// Time-dependent weights for historic speeds, e.g. `[(0, 0.8), (12 * 3600 * 1000, 0.3)]`
+    pub fn weighted(historic_weights: Vec<(Timestamp, f64)>) -> Self {
+        assert_eq!(historic_weights.first().map(|&(ts, _)| ts), Some(0), "historic weights must start at 0");
+        assert!(historic_weights.windows(2).all(|w| w[0].0 < w[1].0 && w[1].0 < MAX_BUCKETS));
+        assert!(historic_weights.iter().all(|&(_, weight)| (0.0..=1.0).contains(&weight)));
+        SpeedBlend::Weighted(historic_weights)
+    }
+
+    /// Combine historic and live speed profiles. Both profiles must cover the whole day, ending with the sentinel at `MAX_BUCKETS`.
+    pub fn blend(&self, historic_speeds: &[(Timestamp, Velocity)], live_speeds: &[(Timestamp, Velocity)]) -> Vec<(Timestamp, Velocity)> {
+        match self {
+            SpeedBlend::Minimum => merge_speed_profiles(historic_speeds, live_speeds),
+            SpeedBlend::Weighted(historic_weights) => weighted_speed_profiles(historic_speeds, live_speeds, historic_weights),
+        }
+    }
+}
+
+/// Combine historic and cooperative speed profiles, taking the lower speed on common breakpoints
+pub fn merge_speed_profiles(historic_speeds: &[(Timestamp, Velocity)], coop_speeds: &[(Timestamp, Velocity)]) -> Vec<(Timestamp, Velocity)> {
+    let mut speeds = Vec::with_capacity(historic_speeds.len() + coop_speeds.len());
+    let mut coop_idx = 0;
+    let mut hist_idx = 0;
+
+    loop {
+        match (historic_speeds.get(hist_idx), coop_speeds.get(coop_idx)) {
+            (Some((hist_ts, hist_val)), Some((coop_ts, coop_val))) => {
+                if hist_ts < coop_ts {
+                    speeds.push((*hist_ts, *hist_val));
+                    hist_idx += 1;
+                } else if hist_ts == coop_ts {
+                    speeds.push((*hist_ts, min(*hist_val, *coop_val)));
+                    hist_idx += 1;
+                    coop_idx += 1;
+                } else {
+                    speeds.push((*coop_ts, *coop_val));
+                    coop_idx += 1;
+                }
+            }
+            (Some((hist_ts, hist_val)), None) => {
+                speeds.push((*hist_ts, *hist_val));
+                hist_idx += 1;
+            }
+            (None, Some((coop_ts, coop_val))) => {
+                speeds.push((*coop_ts, *coop_val));
+                coop_idx += 1;
+            }
+            (None, None) => break,
+        }
+    }
+
+    speeds
+}
+
+/// Weighted average of both step functions on the union of their breakpoints (and those of the weights)
+fn weighted_speed_profiles(
+    historic_speeds: &[(Timestamp, Velocity)],
+    live_speeds: &[(Timestamp, Velocity)],
+    historic_weights: &[(Timestamp, f64)],
+) -> Vec<(Timestamp, Velocity)> {
+    let mut breakpoints = historic_speeds
+        .iter()
+        .chain(live_speeds.iter())
+        .map(|&(ts, _)| ts)
+        .chain(historic_weights.iter().map(|&(ts, _)| ts))
+        .filter(|&ts| ts < MAX_BUCKETS)
+        .collect::<Vec<Timestamp>>();
+    breakpoints.sort_unstable();
+    breakpoints.dedup();
+
+    let mut speeds: Vec<(Timestamp, Velocity)> = Vec::with_capacity(breakpoints.len() + 1);
+    for ts in breakpoints {
+        let weight = step_value(historic_weights, ts);
+        let speed = weight * step_value(historic_speeds, ts) as f64 + (1.0 - weight) * step_value(live_speeds, ts) as f64;
+        let speed = (speed.round() as Velocity).max(1);
+
+        if speeds.last().map(|&(_, last_speed)| last_speed) != Some(speed) {
+            speeds.push((ts, speed));
+        }
+    }
+
+    // sentinel, equal to the speed at midnight
+    speeds.push((MAX_BUCKETS, speeds[0].1));
+    speeds
+}
+
+/// value of a piecewise constant function at `ts`
+fn step_value<T: Copy>(function: &[(Timestamp, T)], ts: Timestamp) -> T {
+    let idx = function.partition_point(|&(start, _)| start <= ts);
+    function[idx.max(1) - 1].1
+}
@@ -0,0 +1,115 @@
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::Weight;
+
+use crate::graph::{Capacity, MAX_BUCKETS, PCE_SCALE};
+
+/// System-level traffic per time interval, accumulated as paths are added to (or withdrawn from) a `CapacityGraph`.
+/// Each edge traversal is attributed to the interval in which the edge is entered, weighted by the load of the path.
+///
+/// Values are kept in milliseconds and meters times the load (PCE units, see `PCE_SCALE`),
+/// so withdrawing a path exactly reverts its contribution.
+#[derive(Debug, Clone)]
+pub struct SystemStatistics {
+    interval_length: Timestamp,
+    vehicle_time: Vec<u64>,
+    vehicle_distance: Vec<u64>,
+}
+
+/// Traffic of a single time interval, given in passenger car equivalents
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntervalStatistics {
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub vehicle_hours: f64,
+    pub vehicle_km: f64,
+}
+
+impl IntervalStatistics {
+    /// average speed of all vehicles within the interval in km/h, 0 without traffic
+    pub fn avg_speed(&self) -> f64 {
+        if self.vehicle_hours > 0.0 {
+            self.vehicle_km / self.vehicle_hours
+        } else {
+            0.0
+        }
+    }
+}
+
+impl SystemStatistics {
+    pub fn new(num_intervals: u32) -> Self {
+        assert!(num_intervals > 0 && MAX_BUCKETS % num_intervals == 0);
+        Self {
+            interval_length: MAX_BUCKETS / num_intervals,
+            vehicle_time: vec![0; num_intervals as usize],
+            vehicle_distance: vec![0; num_intervals as usize],
+        }
+    }
+
+    /// restore from the raw values, see `raw_values`
+    pub fn from_raw_values(vehicle_time: Vec<u64>, vehicle_distance: Vec<u64>) -> Self {
+        assert_eq!(vehicle_time.len(), vehicle_distance.len(), "data containers must have the same size!");
+        let mut ret = Self::new(vehicle_time.len() as u32);
+        ret.vehicle_time = vehicle_time;
+        ret.vehicle_distance = vehicle_distance;
+        ret
+    }
+
+    /// accumulated vehicle time (ms * load) and vehicle distance (m * load) of each interval
+    pub fn raw_values(&self) -> (&Vec<u64>, &Vec<u64>) {
+        (&self.vehicle_time, &self.vehicle_distance)
+    }
+
+    pub fn num_intervals(&self) -> u32 {
+        self.vehicle_time.len() as u32
+    }
+
+    /// add the traversal of an edge entered at `timestamp`
+    pub fn add(&mut self, timestamp: Timestamp, travel_time: Weight, distance: Weight, load: Capacity) {
+        let idx = self.interval_index(timestamp);
+        self.vehicle_time[idx] += travel_time as u64 * load as u64;
+        self.vehicle_distance[idx] += distance as u64 * load as u64;
+    }
+
+    /// withdraw a previously added traversal, the parameters must be the same as in `add`
+    pub fn remove(&mut self, timestamp: Timestamp, travel_time: Weight, distance: Weight, load: Capacity) {
+        let idx = self.interval_index(timestamp);
+        debug_assert!(self.vehicle_time[idx] >= travel_time as u64 * load as u64);
+        debug_assert!(self.vehicle_distance[idx] >= distance as u64 * load as u64);
+        self.vehicle_time[idx] = self.vehicle_time[idx].saturating_sub(travel_time as u64 * load as u64);
+        self.vehicle_distance[idx] = self.vehicle_distance[idx].saturating_sub(distance as u64 * load as u64);
+    }
+
+    pub fn reset(&mut self) {
+        self.vehicle_time.iter_mut().for_each(|value| *value = 0);
+        self.vehicle_distance.iter_mut().for_each(|value| *value = 0);
+    }
+
+    /// vehicle-hours and vehicle-kilometers of each interval
+    pub fn intervals(&self) -> Vec<IntervalStatistics> {
+        (0..self.vehicle_time.len())
+            .map(|idx| {
+                let start = idx as Timestamp * self.interval_length;
+                IntervalStatistics {
+                    start,
+                    end: start + self.interval_length,
+                    vehicle_hours: self.vehicle_time[idx] as f64 / (3_600_000.0 * PCE_SCALE as f64),
+                    vehicle_km: self.vehicle_distance[idx] as f64 / (1000.0 * PCE_SCALE as f64),
+                }
+            })
+            .collect()
+    }
+
+    /// total vehicle-hours and vehicle-kilometers over all intervals
+    pub fn total(&self) -> (f64, f64) {
+        let vehicle_time = self.vehicle_time.iter().sum::<u64>();
+        let vehicle_distance = self.vehicle_distance.iter().sum::<u64>();
+        (
+            vehicle_time as f64 / (3_600_000.0 * PCE_SCALE as f64),
+            vehicle_distance as f64 / (1000.0 * PCE_SCALE as f64),
+        )
+    }
+
+    fn interval_index(&self, timestamp: Timestamp) -> usize {
+        ((timestamp % MAX_BUCKETS) / self.interval_length) as usize
+    }
+}
@@ -22,6 +22,14 @@ impl BPRTrafficFunction {
         Self { alpha, beta }
     }
 
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    pub fn beta(&self) -> i32 {
+        self.beta
+    }
+
     pub fn travel_time(&self, free_flow_time: Weight, max_capacity: Capacity, used_capacity: Capacity) -> Weight {
         if free_flow_time == INFINITY || max_capacity == 0 {
             INFINITY
@@ -44,4 +52,85 @@ impl BPRTrafficFunction {
             max(result.round() as Weight, 1)
         }
     }
+
+    /// integral of the travel time over the load, from zero to `used_capacity` (i.e. the Beckmann term of a single edge)
+    pub fn travel_time_integral(&self, free_flow_time: Weight, max_capacity: Capacity, used_capacity: Capacity) -> f64 {
+        let load = used_capacity as f64;
+        if max_capacity == 0 {
+            free_flow_time as f64 * load
+        } else {
+            free_flow_time as f64 * (load + self.alpha * load * (load / max_capacity as f64).powi(self.beta) / (self.beta + 1) as f64)
+        }
+    }
+}
+
+/// Speed adjustment of an edge depending on its gradient (in percent, positive uphill).
+///
+/// Uphill edges are slowed down linearly with their gradient, downhill edges only beyond `downhill_threshold`
+/// (moderate slopes hardly affect cars, steep ones require braking). The speed never drops below `min_factor`.
+#[derive(Clone, Debug)]
+pub struct GradientSpeedModel {
+    pub uphill_penalty: f64,
+    pub downhill_threshold: f64,
+    pub downhill_penalty: f64,
+    pub min_factor: f64,
+}
+
+impl Default for GradientSpeedModel {
+    fn default() -> Self {
+        Self {
+            uphill_penalty: 0.02,
+            downhill_threshold: 6.0,
+            downhill_penalty: 0.015,
+            min_factor: 0.5,
+        }
+    }
+}
+
+impl GradientSpeedModel {
+    /// factor in `[min_factor, 1]` applied to the free-flow speed of an edge
+    pub fn speed_factor(&self, gradient: f64) -> f64 {
+        let penalty = if gradient > 0.0 {
+            self.uphill_penalty * gradient
+        } else {
+            self.downhill_penalty * (-gradient - self.downhill_threshold).max(0.0)
+        };
+        (1.0 - penalty).max(self.min_factor).min(1.0)
+    }
+}
+
+/// Simple longitudinal vehicle model (rolling resistance, aerodynamic drag and climbing) to derive an energy metric.
+/// Downhill edges cost no energy, but never yield any (no recuperation), so the metric stays non-negative.
+#[derive(Clone, Debug)]
+pub struct EnergyModel {
+    pub mass_kg: f64,
+    pub rolling_resistance: f64,
+    /// drag coefficient times frontal area (m²)
+    pub drag_area: f64,
+    pub air_density: f64,
+}
+
+impl Default for EnergyModel {
+    fn default() -> Self {
+        Self {
+            mass_kg: 1500.0,
+            rolling_resistance: 0.012,
+            drag_area: 0.7,
+            air_density: 1.2,
+        }
+    }
+}
+
+impl EnergyModel {
+    /// energy (in joules) to traverse `distance` meters with a constant speed (km/h) and gradient (percent)
+    pub fn edge_energy(&self, distance: f64, speed_kmh: f64, gradient: f64) -> f64 {
+        let speed = speed_kmh / 3.6;
+        let angle = (gradient / 100.0).atan();
+
+        let rolling = self.mass_kg * 9.81 * self.rolling_resistance * angle.cos();
+        let drag = 0.5 * self.air_density * self.drag_area * speed * speed;
+        let climbing = self.mass_kg * 9.81 * angle.sin();
+
+        ((rolling + drag + climbing) * distance).max(0.0)
+    }
 }
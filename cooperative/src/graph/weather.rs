@@ -0,0 +1,27 @@
+/// Weather-dependent scaling of an edge's free-flow speed and capacity, e.g. `speed = 0.8` for heavy rain
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeatherFactor {
+    pub speed: f64,
+    pub capacity: f64,
+}
+
+impl Default for WeatherFactor {
+    fn default() -> Self {
+        Self { speed: 1.0, capacity: 1.0 }
+    }
+}
+
+impl WeatherFactor {
+    pub fn new(speed: f64, capacity: f64) -> Self {
+        assert!(speed > 0.0 && capacity > 0.0, "weather factors must be positive");
+        Self { speed, capacity }
+    }
+
+    /// combination of two overlapping weather conditions
+    pub fn combine(&self, other: &WeatherFactor) -> WeatherFactor {
+        WeatherFactor {
+            speed: self.speed * other.speed,
+            capacity: self.capacity * other.capacity,
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Bucket occupancy of a capacity graph, independent of the full graph serialization.
+//!
+//! Allows to store the loads of a finished run or to start from hand-crafted or externally computed congestion.
+//! Each record holds an edge id, a bucket index (in `0..num_buckets`) and a load in PCE units scaled by `PCE_SCALE`:
+//!
+//! - binary: a directory with the `u32` files `edge_id`, `bucket` and `load` (one entry per record)
+//!   and `num_buckets` (a single entry, must match the graph's bucket count)
+//! - CSV: header `edge_id,bucket,load`, followed by one record per line, the bucket count is taken from the graph
+//!
+//! Buckets without a record are empty, records with a load of 0 are allowed and ignored.
+
+use crate::graph::capacity_graph::CapacityGraph;
+use crate::graph::{Capacity, MAX_BUCKETS};
+use crate::io::io_csv::read_u32_columns;
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{EdgeId, Graph, Weight};
+use rust_road_router::io::{Load, Store};
+use std::error::Error;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BucketLoad {
+    pub edge_id: EdgeId,
+    pub bucket: u32,
+    pub load: Capacity,
+}
+
+/// Replace the loads of `graph` by the records in `path`, either a directory in the binary layout or a CSV file.
+/// Returns the new minimum and maximum travel time of each edge, e.g. to update a customization.
+pub fn apply_bucket_loads(path: &Path, graph: &mut CapacityGraph) -> Result<Vec<(EdgeId, Weight, Weight)>, Box<dyn Error>> {
+    let records = if path.is_dir() {
+        read_bucket_loads(path, graph.num_buckets())?
+    } else {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => read_bucket_loads_csv(path)?,
+            _ => return Err(format!("unsupported bucket load format: {}", path.display()).into()),
+        }
+    };
+    println!("Read {} bucket loads from {}", records.len(), path.display());
+
+    let loads = bucket_loads_to_profiles(&records, graph)?;
+    Ok(graph.set_bucket_loads(&loads))
+}
+
+/// All non-empty buckets of `graph`, ordered by edge id and bucket
+pub fn bucket_load_records(graph: &CapacityGraph) -> Vec<BucketLoad> {
+    let bucket_width = MAX_BUCKETS / graph.num_buckets();
+
+    graph
+        .export_bucket_loads()
+        .iter()
+        .enumerate()
+        .flat_map(|(edge_id, profile)| {
+            profile.iter().map(move |&(timestamp, load)| BucketLoad {
+                edge_id: edge_id as EdgeId,
+                bucket: timestamp / bucket_width,
+                load,
+            })
+        })
+        .collect()
+}
+
+/// Validate the records and convert them to load profiles which can be passed to `CapacityGraph::set_bucket_loads`.
+///
+/// Invalid edge ids and buckets as well as duplicate records are rejected.
+pub fn bucket_loads_to_profiles(records: &[BucketLoad], graph: &CapacityGraph) -> Result<Vec<Vec<(Timestamp, Capacity)>>, Box<dyn Error>> {
+    let num_edges = graph.num_arcs();
+    let num_buckets = graph.num_buckets();
+    let bucket_width = MAX_BUCKETS / num_buckets;
+
+    let mut loads: Vec<Vec<(Timestamp, Capacity)>> = vec![Vec::new(); num_edges];
+    for record in records {
+        let edge_id = record.edge_id as usize;
+        if edge_id >= num_edges {
+            return Err(format!("invalid edge id {} (graph has {} edges)", record.edge_id, num_edges).into());
+        }
+        if record.bucket >= num_buckets {
+            return Err(format!("invalid bucket {} on edge {} (expected {} buckets)", record.bucket, record.edge_id, num_buckets).into());
+        }
+        if record.load > 0 {
+            loads[edge_id].push((record.bucket * bucket_width, record.load));
+        }
+    }
+
+    for (edge_id, profile) in loads.iter_mut().enumerate() {
+        profile.sort_unstable_by_key(|&(timestamp, _)| timestamp);
+        if let Some(window) = profile.windows(2).find(|window| window[0].0 == window[1].0) {
+            return Err(format!("duplicate record for edge {}, bucket {}", edge_id, window[0].0 / bucket_width).into());
+        }
+    }
+
+    Ok(loads)
+}
+
+/// Stores the loads of `graph` in the binary layout
+pub fn store_bucket_loads(directory: &Path, graph: &CapacityGraph) -> Result<(), Box<dyn Error>> {
+    let records = bucket_load_records(graph);
+
+    vec![graph.num_buckets()].write_to(&directory.join("num_buckets"))?;
+    records
+        .iter()
+        .map(|record| record.edge_id)
+        .collect::<Vec<u32>>()
+        .write_to(&directory.join("edge_id"))?;
+    records
+        .iter()
+        .map(|record| record.bucket)
+        .collect::<Vec<u32>>()
+        .write_to(&directory.join("bucket"))?;
+    records
+        .iter()
+        .map(|record| record.load)
+        .collect::<Vec<u32>>()
+        .write_to(&directory.join("load"))?;
+
+    Ok(())
+}
+
+/// Reads records in the binary layout, `num_buckets` is the bucket count of the target graph
+pub fn read_bucket_loads(directory: &Path, num_buckets: u32) -> Result<Vec<BucketLoad>, Box<dyn Error>> {
+    let stored_buckets = Vec::<u32>::load_from(directory.join("num_buckets"))?;
+    if stored_buckets != [num_buckets] {
+        return Err(format!("bucket loads were stored with {:?} buckets, graph has {}", stored_buckets, num_buckets).into());
+    }
+
+    let edge_ids = Vec::<u32>::load_from(directory.join("edge_id"))?;
+    let buckets = Vec::<u32>::load_from(directory.join("bucket"))?;
+    let loads = Vec::<u32>::load_from(directory.join("load"))?;
+    if edge_ids.len() != buckets.len() || edge_ids.len() != loads.len() {
+        return Err(format!(
+            "bucket load files differ in length: {} edge ids, {} buckets, {} loads",
+            edge_ids.len(),
+            buckets.len(),
+            loads.len()
+        )
+        .into());
+    }
+
+    Ok(edge_ids
+        .iter()
+        .zip(buckets.iter())
+        .zip(loads.iter())
+        .map(|((&edge_id, &bucket), &load)| BucketLoad { edge_id, bucket, load })
+        .collect())
+}
+
+/// Stores the loads of `graph` as CSV file
+pub fn store_bucket_loads_csv(path: &Path, graph: &CapacityGraph) -> Result<(), Box<dyn Error>> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(&["edge_id", "bucket", "load"])?;
+    for record in bucket_load_records(graph) {
+        writer.write_record(&[record.edge_id.to_string(), record.bucket.to_string(), record.load.to_string()])?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+pub fn read_bucket_loads_csv(path: &Path) -> Result<Vec<BucketLoad>, Box<dyn Error>> {
+    Ok(read_u32_columns(path, ["edge_id", "bucket", "load"])?
+        .into_iter()
+        .map(|[edge_id, bucket, load]| BucketLoad { edge_id, bucket, load })
+        .collect())
+}
@@ -3,9 +3,73 @@ use std::path::Path;
 
 use rust_road_router::io::Load;
 
+use crate::util::projection::CoordinateSystem;
+
+/// Load the node coordinates as `(longitude, latitude)` in f32 precision, see `load_coords_f64`.
 pub fn load_coords(graph_directory: &Path) -> Result<(Vec<f32>, Vec<f32>), Box<dyn Error>> {
-    let lon = Vec::load_from(graph_directory.join("longitude"))?;
-    let lat = Vec::load_from(graph_directory.join("latitude"))?;
+    let (lon, lat) = load_coords_f64(graph_directory)?;
+
+    Ok((lon.iter().map(|&x| x as f32).collect(), lat.iter().map(|&y| y as f32).collect()))
+}
+
+/// Load the node coordinates as `(longitude, latitude)` in degrees.
+///
+/// The `longitude` and `latitude` files may contain f32 or f64 values (detected by their size).
+/// If the graph directory contains a `coordinate_system` file (e.g. `EPSG:32632`, see `CoordinateSystem::parse`),
+/// the files contain projected x/y coordinates which are converted to WGS84.
+pub fn load_coords_f64(graph_directory: &Path) -> Result<(Vec<f64>, Vec<f64>), Box<dyn Error>> {
+    let num_nodes = num_nodes(graph_directory)?;
+    let x = load_coordinate_file(&graph_directory.join("longitude"), num_nodes)?;
+    let y = load_coordinate_file(&graph_directory.join("latitude"), num_nodes)?;
+
+    match load_coordinate_system(graph_directory)? {
+        CoordinateSystem::Wgs84 => Ok((x, y)),
+        crs => {
+            println!("Converting coordinates from {:?} to WGS84", crs);
+            Ok(x.iter().zip(y.iter()).map(|(&x, &y)| crs.to_wgs84(x, y)).unzip())
+        }
+    }
+}
+
+/// Load the elevation of each node (in meters, f32 or f64) from the optional `elevation` file, see `CapacityGraph::set_elevation`.
+pub fn load_elevation(graph_directory: &Path) -> Result<Option<Vec<f64>>, Box<dyn Error>> {
+    let path = graph_directory.join("elevation");
+    if path.exists() {
+        Ok(Some(load_coordinate_file(&path, num_nodes(graph_directory)?)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// coordinate system of the graph's `longitude` and `latitude` files, WGS84 if not specified
+pub fn load_coordinate_system(graph_directory: &Path) -> Result<CoordinateSystem, Box<dyn Error>> {
+    let path = graph_directory.join("coordinate_system");
+    if path.exists() {
+        CoordinateSystem::parse(&std::fs::read_to_string(path)?)
+    } else {
+        Ok(CoordinateSystem::Wgs84)
+    }
+}
+
+fn num_nodes(graph_directory: &Path) -> Result<usize, Box<dyn Error>> {
+    Ok((std::fs::metadata(graph_directory.join("first_out"))?.len() as usize / std::mem::size_of::<u32>()).saturating_sub(1))
+}
+
+fn load_coordinate_file(path: &Path, num_nodes: usize) -> Result<Vec<f64>, Box<dyn Error>> {
+    let num_bytes = std::fs::metadata(path)?.len() as usize;
 
-    Ok((lon, lat))
+    if num_bytes == num_nodes * std::mem::size_of::<f64>() {
+        Ok(Vec::load_from(path)?)
+    } else if num_bytes == num_nodes * std::mem::size_of::<f32>() {
+        let values: Vec<f32> = Vec::load_from(path)?;
+        Ok(values.iter().map(|&val| val as f64).collect())
+    } else {
+        Err(format!(
+            "{} contains {} bytes, expected f32 or f64 values for {} nodes",
+            path.display(),
+            num_bytes,
+            num_nodes
+        )
+        .into())
+    }
 }
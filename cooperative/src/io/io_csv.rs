@@ -0,0 +1,37 @@
+//! Column-based reading of CSV files with a header line.
+
+use std::error::Error;
+use std::path::Path;
+
+/// Reads the given unsigned integer columns of a CSV file, one array per record in the order of `columns`.
+///
+/// Columns are identified by their header, additional columns are ignored.
+/// Missing columns and unparseable values are rejected with the offending line.
+pub fn read_u32_columns<const N: usize>(path: &Path, columns: [&str; N]) -> Result<Vec<[u32; N]>, Box<dyn Error>> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_path(path)?;
+
+    let headers = reader.headers()?.clone();
+    let mut indices = [0; N];
+    for (index, &name) in indices.iter_mut().zip(columns.iter()) {
+        *index = headers
+            .iter()
+            .position(|header| header == name)
+            .ok_or_else(|| format!("missing column '{}' in {}", name, path.display()))?;
+    }
+
+    let mut records = Vec::new();
+    for (idx, row) in reader.records().enumerate() {
+        let row = row?;
+        let mut record = [0; N];
+        for (value, &col) in record.iter_mut().zip(indices.iter()) {
+            let field = row.get(col).unwrap_or("");
+            // line 1 is the header
+            *value = field
+                .parse::<u32>()
+                .map_err(|_| format!("line {}: invalid value '{}' in column '{}'", idx + 2, field, &headers[col]))?;
+        }
+        records.push(record);
+    }
+
+    Ok(records)
+}
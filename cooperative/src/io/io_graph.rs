@@ -6,35 +6,106 @@ use rust_road_router::io::{Load, Store};
 
 use crate::graph::capacity_graph::CapacityGraph;
 use crate::graph::edge_buckets::SpeedBuckets;
-use crate::graph::traffic_functions::BPRTrafficFunction;
+use crate::graph::traffic_functions::{BPRTrafficFunction, GradientSpeedModel};
+use crate::graph::Capacity;
+use crate::io::io_coordinates::load_elevation;
 
 /// Loads and initializes a capacity graph with empty capacity buckets.
+///
+/// All files are loaded concurrently, the validation of distance and travel time overlaps with loading the remaining files.
 pub fn load_capacity_graph(graph_directory: &Path, num_buckets: u32, traffic_function: BPRTrafficFunction) -> Result<CapacityGraph, Box<dyn Error>> {
-    let first_out = Vec::load_from(graph_directory.join("first_out"))?;
-    let head = Vec::load_from(graph_directory.join("head"))?;
-    let geo_distance = Vec::load_from(graph_directory.join("geo_distance"))?;
-    let travel_time = Vec::<u32>::load_from(graph_directory.join("travel_time"))?;
-    let capacity = Vec::load_from(graph_directory.join("capacity"))?;
-
-    // modify distance and travel_time to avoid divisions by zero
-    let distance = geo_distance.iter().map(|&dist| max(dist, 1)).collect::<Vec<u32>>();
-    let freeflow_time = travel_time.iter().map(|&time| max(time, 1)).collect::<Vec<u32>>();
+    let ((first_out, head), ((distance, freeflow_time), capacity)) = rayon::join(
+        || {
+            rayon::join(
+                || Vec::<u32>::load_from(graph_directory.join("first_out")),
+                || Vec::<u32>::load_from(graph_directory.join("head")),
+            )
+        },
+        || {
+            rayon::join(
+                || {
+                    rayon::join(
+                        // modify distance and travel_time to avoid divisions by zero
+                        || {
+                            Vec::<u32>::load_from(graph_directory.join("geo_distance"))
+                                .map(|distance| distance.into_iter().map(|dist| max(dist, 1)).collect::<Vec<u32>>())
+                        },
+                        || {
+                            Vec::<u32>::load_from(graph_directory.join("travel_time"))
+                                .map(|travel_time| travel_time.into_iter().map(|time| max(time, 1)).collect::<Vec<u32>>())
+                        },
+                    )
+                },
+                || Vec::<u32>::load_from(graph_directory.join("capacity")),
+            )
+        },
+    );
 
     Ok(CapacityGraph::new(
         num_buckets,
-        first_out,
-        head,
-        distance,
-        freeflow_time,
-        capacity,
+        first_out?,
+        head?,
+        distance?,
+        freeflow_time?,
+        capacity?,
         traffic_function,
     ))
 }
 
+/// Loads a capacity graph whose capacities are derived from per-edge lane counts (`lanes`) and the given per-lane saturation flow.
+/// Edges without lane information keep the capacity given by the `capacity` file.
+pub fn load_capacity_graph_with_lanes(
+    graph_directory: &Path,
+    num_buckets: u32,
+    traffic_function: BPRTrafficFunction,
+    saturation_flow: Capacity,
+) -> Result<CapacityGraph, Box<dyn Error>> {
+    let mut graph = load_capacity_graph(graph_directory, num_buckets, traffic_function)?;
+    let num_lanes = Vec::<u32>::load_from(graph_directory.join("lanes"))?;
+
+    graph.set_lanes(num_lanes, saturation_flow);
+    Ok(graph)
+}
+
+/// Loads a capacity graph whose free-flow speeds are adjusted to the gradients given by the node elevations (`elevation`),
+/// see `CapacityGraph::set_elevation`. Graphs without elevation data are loaded unchanged.
+pub fn load_capacity_graph_with_elevation(
+    graph_directory: &Path,
+    num_buckets: u32,
+    traffic_function: BPRTrafficFunction,
+    gradient_model: &GradientSpeedModel,
+) -> Result<CapacityGraph, Box<dyn Error>> {
+    let mut graph = load_capacity_graph(graph_directory, num_buckets, traffic_function)?;
+
+    match load_elevation(graph_directory)? {
+        Some(elevation) => graph.set_elevation(&elevation, gradient_model),
+        None => println!("No elevation data found, keeping flat free-flow speeds"),
+    }
+    Ok(graph)
+}
+
+/// Loads the vehicle classes allowed on each edge (`edge_classes`, bit mask per edge), see `CapacityGraph::set_edge_classes`.
+/// Returns `None` if the graph has no class-specific edges.
+pub fn load_edge_classes(graph_directory: &Path) -> Result<Option<Vec<u32>>, Box<dyn Error>> {
+    let path = graph_directory.join("edge_classes");
+    if path.exists() {
+        Ok(Some(Vec::<u32>::load_from(path)?))
+    } else {
+        Ok(None)
+    }
+}
+
 pub fn load_used_speed_profiles(directory: &Path) -> Result<Vec<SpeedBuckets>, Box<dyn Error>> {
-    let prefix_sum = Vec::<u32>::load_from(&directory.join("prefix_sum"))?;
-    let timestamps = Vec::<u32>::load_from(&directory.join("timestamps"))?;
-    let speeds = Vec::<u32>::load_from(&directory.join("speeds"))?;
+    let (prefix_sum, (timestamps, speeds)) = rayon::join(
+        || Vec::<u32>::load_from(&directory.join("prefix_sum")),
+        || {
+            rayon::join(
+                || Vec::<u32>::load_from(&directory.join("timestamps")),
+                || Vec::<u32>::load_from(&directory.join("speeds")),
+            )
+        },
+    );
+    let (prefix_sum, timestamps, speeds) = (prefix_sum?, timestamps?, speeds?);
 
     let mut ret = Vec::with_capacity(prefix_sum.len() - 1);
 
@@ -55,10 +126,20 @@ pub fn load_used_speed_profiles(directory: &Path) -> Result<Vec<SpeedBuckets>, B
 }
 
 pub fn store_speed_buckets(directory: &Path, graph: &CapacityGraph) -> Result<(), Box<dyn Error>> {
+    store_speed_profiles(directory, &graph.export_speeds())
+}
+
+/// Stores the learned speeds of a graph after an experiment, i.e. the cooperative speeds combined with its historic speeds.
+/// The result can be loaded via `load_used_speed_profiles` and serve as historic speeds of the next run.
+pub fn store_learned_speed_profiles(directory: &Path, graph: &CapacityGraph) -> Result<(), Box<dyn Error>> {
+    store_speed_profiles(directory, &graph.export_learned_speeds())
+}
+
+/// Stores speed profiles in the format expected by `load_used_speed_profiles`
+pub fn store_speed_profiles(directory: &Path, speed_buckets: &Vec<Vec<(u32, u32)>>) -> Result<(), Box<dyn Error>> {
     let mut prefix_sum = vec![0];
-    let speed_buckets = graph.export_speeds();
 
-    for speed_bucket in &speed_buckets {
+    for speed_bucket in speed_buckets {
         prefix_sum.push(*prefix_sum.last().unwrap() + speed_bucket.len() as u32);
     }
 
@@ -0,0 +1,190 @@
+//! Historic speed profiles from external sources.
+//!
+//! Besides the binary layout of `load_used_speed_profiles`, historic speeds can be given as CSV or JSON file.
+//! Each record holds an edge id, a bucket index and a speed in km/h:
+//!
+//! - CSV: header `edge_id,bucket,speed`, followed by one record per line
+//! - JSON: an array of objects `{"edge_id": 0, "bucket": 32, "speed": 45}`
+//!
+//! The buckets of the input file are resampled to the bucket count of the graph.
+//! Buckets without a record keep the free-flow speed of the edge.
+
+use crate::graph::capacity_graph::CapacityGraph;
+use crate::graph::edge_buckets::SpeedBuckets;
+use crate::graph::{Velocity, MAX_BUCKETS};
+use crate::io::io_csv::read_u32_columns;
+use crate::io::io_graph::load_used_speed_profiles;
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{EdgeId, Graph, INFINITY};
+use serde_json::Value;
+use std::cmp::min;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// bucket count of CSV/JSON inputs if `HISTORIC_SPEED_BUCKETS` is not set
+pub const DEFAULT_SOURCE_BUCKETS: u32 = 96;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpeedRecord {
+    pub edge_id: EdgeId,
+    pub bucket: u32,
+    pub speed: Velocity,
+}
+
+/// Load historic speeds for `graph` from either a directory in the binary layout or a CSV/JSON file.
+///
+/// The bucket count of CSV/JSON files is read from `HISTORIC_SPEED_BUCKETS` (default: 96, i.e. 15 minutes).
+pub fn load_historic_speeds(path: &Path, graph: &CapacityGraph) -> Result<Vec<SpeedBuckets>, Box<dyn Error>> {
+    if path.is_dir() {
+        let speeds = load_used_speed_profiles(path)?;
+        if speeds.len() != graph.num_arcs() {
+            return Err(format!("speed profiles contain {} edges, graph has {}", speeds.len(), graph.num_arcs()).into());
+        }
+        return Ok(speeds);
+    }
+
+    let num_source_buckets = match std::env::var("HISTORIC_SPEED_BUCKETS") {
+        Ok(value) => value.parse::<u32>()?,
+        Err(_) => DEFAULT_SOURCE_BUCKETS,
+    };
+    load_external_speed_profiles(path, graph, num_source_buckets)
+}
+
+/// Load a CSV/JSON file (chosen by extension) with `num_source_buckets` buckets per day
+/// and resample the speeds to the bucket count of `graph`.
+pub fn load_external_speed_profiles(path: &Path, graph: &CapacityGraph, num_source_buckets: u32) -> Result<Vec<SpeedBuckets>, Box<dyn Error>> {
+    let records = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("csv") => read_speed_records_csv(path)?,
+        Some("json") => read_speed_records_json(path)?,
+        _ => return Err(format!("unsupported speed profile format: {}", path.display()).into()),
+    };
+    println!("Read {} speed records from {}", records.len(), path.display());
+
+    speed_records_to_profiles(&records, graph, num_source_buckets)
+}
+
+pub fn read_speed_records_csv(path: &Path) -> Result<Vec<SpeedRecord>, Box<dyn Error>> {
+    Ok(read_u32_columns(path, ["edge_id", "bucket", "speed"])?
+        .into_iter()
+        .map(|[edge_id, bucket, speed]| SpeedRecord { edge_id, bucket, speed })
+        .collect())
+}
+
+pub fn read_speed_records_json(path: &Path) -> Result<Vec<SpeedRecord>, Box<dyn Error>> {
+    let json: Value = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+    let entries = json.as_array().ok_or("expected a JSON array of speed records")?;
+
+    entries
+        .iter()
+        .enumerate()
+        .map(|(idx, entry)| {
+            let field = |name: &str| -> Result<u32, Box<dyn Error>> {
+                entry
+                    .get(name)
+                    .and_then(Value::as_u64)
+                    .filter(|&value| value <= u32::MAX as u64)
+                    .map(|value| value as u32)
+                    .ok_or_else(|| format!("record {}: missing or invalid field '{}'", idx, name).into())
+            };
+            Ok(SpeedRecord {
+                edge_id: field("edge_id")?,
+                bucket: field("bucket")?,
+                speed: field("speed")?,
+            })
+        })
+        .collect()
+}
+
+/// Validate the records and convert them to speed profiles with the bucket count of `graph`.
+///
+/// Records on edges which can not be traversed (no capacity or length) are ignored,
+/// invalid edge ids, buckets or speeds as well as duplicate records are rejected.
+pub fn speed_records_to_profiles(records: &[SpeedRecord], graph: &CapacityGraph, num_source_buckets: u32) -> Result<Vec<SpeedBuckets>, Box<dyn Error>> {
+    if num_source_buckets == 0 || MAX_BUCKETS % num_source_buckets != 0 {
+        return Err(format!("invalid source bucket count: {}", num_source_buckets).into());
+    }
+
+    let num_edges = graph.num_arcs();
+    let mut source_speeds: Vec<Option<Vec<Option<Velocity>>>> = vec![None; num_edges];
+    let mut num_ignored = 0;
+
+    for record in records {
+        let edge_id = record.edge_id as usize;
+        if edge_id >= num_edges {
+            return Err(format!("invalid edge id {} (graph has {} edges)", record.edge_id, num_edges).into());
+        }
+        if record.bucket >= num_source_buckets {
+            return Err(format!(
+                "invalid bucket {} on edge {} (expected {} buckets)",
+                record.bucket, record.edge_id, num_source_buckets
+            )
+            .into());
+        }
+        if record.speed == 0 {
+            return Err(format!("invalid speed 0 on edge {}, bucket {}", record.edge_id, record.bucket).into());
+        }
+        if graph.free_flow_time()[edge_id] >= INFINITY || graph.distance()[edge_id] == 0 {
+            num_ignored += 1;
+            continue;
+        }
+
+        let buckets = source_speeds[edge_id].get_or_insert_with(|| vec![None; num_source_buckets as usize]);
+        if buckets[record.bucket as usize].replace(record.speed).is_some() {
+            return Err(format!("duplicate record for edge {}, bucket {}", record.edge_id, record.bucket).into());
+        }
+    }
+
+    if num_ignored > 0 {
+        println!("Ignored {} speed records on edges which can not be traversed", num_ignored);
+    }
+
+    Ok(source_speeds
+        .into_iter()
+        .enumerate()
+        .map(|(edge_id, buckets)| match buckets {
+            None => SpeedBuckets::Unused,
+            Some(buckets) => {
+                let free_flow_speed = graph.free_flow_speed()[edge_id];
+                let speeds = buckets.iter().map(|speed| speed.unwrap_or(free_flow_speed)).collect::<Vec<Velocity>>();
+                SpeedBuckets::Used(resample_speeds(&speeds, graph.num_buckets()))
+            }
+        })
+        .collect())
+}
+
+/// Resample equally-sized speed buckets to `num_target_buckets` buckets.
+///
+/// Each target bucket gets the harmonic mean of the overlapping source buckets (weighted by their overlap),
+/// so the travel time along the bucket is preserved. Consecutive buckets with equal speed are merged,
+/// the result ends with the usual sentinel at `MAX_BUCKETS`.
+pub fn resample_speeds(speeds: &[Velocity], num_target_buckets: u32) -> Vec<(Timestamp, Velocity)> {
+    debug_assert!(!speeds.is_empty() && speeds.iter().all(|&speed| speed > 0));
+    let source_width = MAX_BUCKETS / speeds.len() as u32;
+    let target_width = MAX_BUCKETS / num_target_buckets;
+
+    let mut profile: Vec<(Timestamp, Velocity)> = Vec::new();
+    for bucket in 0..num_target_buckets {
+        let (start, end) = (bucket * target_width, (bucket + 1) * target_width);
+
+        // sum up the time (per unit of distance) spent in each overlapping source bucket
+        let mut time = 0.0;
+        let mut ts = start;
+        while ts < end {
+            let source_idx = ts / source_width;
+            let next_ts = min(end, (source_idx + 1) * source_width);
+            time += (next_ts - ts) as f64 / speeds[source_idx as usize] as f64;
+            ts = next_ts;
+        }
+        let speed = ((target_width as f64 / time).round() as Velocity).max(1);
+
+        if profile.last().map(|&(_, last_speed)| last_speed) != Some(speed) {
+            profile.push((start, speed));
+        }
+    }
+
+    let first_speed = profile[0].1;
+    profile.push((MAX_BUCKETS, first_speed));
+    profile
+}
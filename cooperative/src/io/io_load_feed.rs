@@ -0,0 +1,40 @@
+use crate::graph::load_feed::LoadObservation;
+use rust_road_router::io::{Load, Store};
+use std::error::Error;
+use std::path::Path;
+
+/// load an external load feed (e.g. loop detector counts) from a given directory, sorted by timestamp
+pub fn load_load_feed(directory: &Path) -> Result<Vec<LoadObservation>, Box<dyn Error>> {
+    let edge_ids: Vec<u32> = Vec::load_from(directory.join("edge_id"))?;
+    let timestamps: Vec<u32> = Vec::load_from(directory.join("timestamp"))?;
+    let counts: Vec<u32> = Vec::load_from(directory.join("count"))?;
+
+    assert!(edge_ids.len() == timestamps.len() && edge_ids.len() == counts.len());
+
+    let mut observations = edge_ids
+        .iter()
+        .zip(timestamps.iter())
+        .zip(counts.iter())
+        .map(|((&edge_id, &timestamp), &count)| LoadObservation::new(edge_id, timestamp, count))
+        .collect::<Vec<LoadObservation>>();
+    observations.sort_by_key(|observation| observation.timestamp);
+
+    Ok(observations)
+}
+
+/// store an external load feed in a given directory
+pub fn store_load_feed(observations: &Vec<LoadObservation>, directory: &Path) -> Result<(), Box<dyn Error>> {
+    observations
+        .iter()
+        .map(|o| o.edge_id)
+        .collect::<Vec<u32>>()
+        .write_to(&directory.join("edge_id"))?;
+    observations
+        .iter()
+        .map(|o| o.timestamp)
+        .collect::<Vec<u32>>()
+        .write_to(&directory.join("timestamp"))?;
+    observations.iter().map(|o| o.count).collect::<Vec<u32>>().write_to(&directory.join("count"))?;
+
+    Ok(())
+}
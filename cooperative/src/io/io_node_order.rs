@@ -1,3 +1,6 @@
+use crate::io::io_coordinates::load_coords;
+use rust_road_router::algo::customizable_contraction_hierarchy::CCH;
+use rust_road_router::datastr::graph::{EdgeIdGraph, LinkIterable, NodeIdT};
 use rust_road_router::datastr::node_order::NodeOrder;
 use rust_road_router::io::Load;
 use std::error::Error;
@@ -5,5 +8,23 @@ use std::path::Path;
 
 pub fn load_node_order(directory: &Path) -> Result<NodeOrder, Box<dyn Error>> {
     let order = Vec::load_from(directory.join("order"))?;
+
+    if !NodeOrder::is_valid_permutation(&order) {
+        return Err(format!("{} is not a valid node order", directory.join("order").display()).into());
+    }
+
     Ok(NodeOrder::from_node_order(order))
 }
+
+/// load the node order and reorder its top-level separators by the coordinates stored in the same directory
+/// if no coordinates are available, the plain node order is returned
+pub fn load_coordinate_aware_node_order(directory: &Path, graph: &(impl LinkIterable<NodeIdT> + EdgeIdGraph)) -> Result<NodeOrder, Box<dyn Error>> {
+    let order = load_node_order(directory)?;
+
+    if directory.join("longitude").exists() && directory.join("latitude").exists() {
+        let (longitude, latitude) = load_coords(directory)?;
+        Ok(CCH::coordinate_aware_order(graph, order, &latitude, &longitude))
+    } else {
+        Ok(order)
+    }
+}
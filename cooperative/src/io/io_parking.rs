@@ -0,0 +1,67 @@
+use crate::graph::parking::ParkingZone;
+use crate::io::io_restrictions::parse_polygon;
+use rust_road_router::datastr::graph::Weight;
+use serde_json::Value;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+// vehicles without a given dwell time occupy their space for two hours
+const DEFAULT_DWELL_TIME_MINUTES: f64 = 120.0;
+
+/// Load parking zones from a GeoJSON file with one `Polygon` feature per zone, e.g.
+///
+/// ```json
+/// {
+///   "type": "FeatureCollection",
+///   "features": [{
+///     "type": "Feature",
+///     "geometry": { "type": "Polygon", "coordinates": [[[8.38, 49.00], [8.42, 49.00], [8.42, 49.02], [8.38, 49.02], [8.38, 49.00]]] },
+///     "properties": { "base_search_time": 60, "max_search_time": 900, "capacity": 2500, "dwell_time": 120 }
+///   }]
+/// }
+/// ```
+///
+/// Search times are given in seconds, the dwell time in minutes (defaults to 120).
+pub fn load_parking_zones(path: &Path) -> Result<Vec<ParkingZone>, Box<dyn Error>> {
+    let json: Value = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+    let features = json
+        .get("features")
+        .and_then(Value::as_array)
+        .ok_or("parking zones must contain a `features` array")?;
+
+    let zones = features
+        .iter()
+        .enumerate()
+        .map(|(idx, feature)| parse_zone(feature).map_err(|e| format!("zone {}: {}", idx, e).into()))
+        .collect::<Result<Vec<ParkingZone>, Box<dyn Error>>>()?;
+
+    println!("Loaded {} parking zones", zones.len());
+    Ok(zones)
+}
+
+fn parse_zone(feature: &Value) -> Result<ParkingZone, Box<dyn Error>> {
+    let polygon = parse_polygon(feature)?;
+
+    let properties = feature.get("properties").ok_or("missing properties")?;
+    let number = |key: &str| properties.get(key).and_then(Value::as_f64).filter(|&val| val >= 0.0);
+    let base_search_time = number("base_search_time").ok_or("invalid `base_search_time`")?;
+    let max_search_time = number("max_search_time").ok_or("invalid `max_search_time`")?;
+    let capacity = number("capacity").filter(|&val| val >= 1.0).ok_or("invalid `capacity`")?;
+    let dwell_time = match properties.get("dwell_time") {
+        None => DEFAULT_DWELL_TIME_MINUTES,
+        Some(_) => number("dwell_time").filter(|&val| val > 0.0).ok_or("invalid `dwell_time`")?,
+    };
+    if base_search_time > max_search_time {
+        return Err("`base_search_time` must not exceed `max_search_time`".into());
+    }
+
+    Ok(ParkingZone::new(
+        polygon,
+        (base_search_time * 1000.0).round() as Weight,
+        (max_search_time * 1000.0).round() as Weight,
+        capacity as u32,
+        (dwell_time * 60_000.0).round() as Weight,
+    ))
+}
@@ -0,0 +1,66 @@
+use crate::dijkstra::model::PathResult;
+use rust_road_router::io::{Load, Store};
+use std::error::Error;
+use std::path::Path;
+
+/// store assigned paths (node path, edge path, departures and load) in a given directory
+pub fn store_assigned_paths(paths: &Vec<PathResult>, directory: &Path) -> Result<(), Box<dyn Error>> {
+    let mut first_out = vec![0u32];
+    for path in paths {
+        first_out.push(*first_out.last().unwrap() + path.edge_path.len() as u32);
+    }
+
+    first_out.write_to(&directory.join("path_first_out"))?;
+    paths
+        .iter()
+        .flat_map(|path| path.edge_path.iter().cloned())
+        .collect::<Vec<u32>>()
+        .write_to(&directory.join("path_edges"))?;
+    paths
+        .iter()
+        .flat_map(|path| path.node_path.iter().cloned())
+        .collect::<Vec<u32>>()
+        .write_to(&directory.join("path_nodes"))?;
+    paths
+        .iter()
+        .flat_map(|path| path.departure.iter().cloned())
+        .collect::<Vec<u32>>()
+        .write_to(&directory.join("path_departures"))?;
+    paths
+        .iter()
+        .map(|path| path.pce_load)
+        .collect::<Vec<u32>>()
+        .write_to(&directory.join("path_pce_load"))?;
+
+    Ok(())
+}
+
+/// load assigned paths, stored by `store_assigned_paths`
+/// node paths and departures contain one entry more than the edge path of each path
+pub fn load_assigned_paths(directory: &Path) -> Result<Vec<PathResult>, Box<dyn Error>> {
+    let first_out: Vec<u32> = Vec::load_from(directory.join("path_first_out"))?;
+    let edges: Vec<u32> = Vec::load_from(directory.join("path_edges"))?;
+    let nodes: Vec<u32> = Vec::load_from(directory.join("path_nodes"))?;
+    let departures: Vec<u32> = Vec::load_from(directory.join("path_departures"))?;
+    let pce_loads: Vec<u32> = Vec::load_from(directory.join("path_pce_load"))?;
+
+    let num_paths = first_out.len() - 1;
+    assert_eq!(pce_loads.len(), num_paths);
+    assert!(nodes.len() == edges.len() + num_paths && departures.len() == edges.len() + num_paths);
+
+    let paths = first_out
+        .windows(2)
+        .enumerate()
+        .map(|(idx, range)| {
+            let (start, end) = (range[0] as usize, range[1] as usize);
+            PathResult::new(
+                nodes[start + idx..end + idx + 1].to_vec(),
+                edges[start..end].to_vec(),
+                departures[start + idx..end + idx + 1].to_vec(),
+            )
+            .with_pce_load(pce_loads[idx])
+        })
+        .collect();
+
+    Ok(paths)
+}
@@ -1,11 +1,16 @@
+use std::collections::HashMap;
 use std::error::Error;
 use std::path::Path;
 
 use kdtree::kdtree::{Kdtree, KdtreePointTrait};
 
+use rust_road_router::cli::CliErr;
 use rust_road_router::io::Load;
 use rust_road_router::report::measure;
 
+// scale of the node-based population, large enough to keep small densities distinguishable
+const NODE_POPULATION_SCALE: f64 = (1u32 << 30) as f64;
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct PopulationGridEntry {
     pub id: usize,
@@ -17,11 +22,8 @@ impl PopulationGridEntry {
         Self { id, coords: [lon, lat] }
     }
 
-    pub fn from_coords(lon: f32, lat: f32) -> Self {
-        Self {
-            id: 0,
-            coords: [lon as f64, lat as f64],
-        }
+    pub fn from_coords(lon: f64, lat: f64) -> Self {
+        Self { id: 0, coords: [lon, lat] }
     }
 }
 
@@ -48,6 +50,177 @@ pub fn load_population_grid(directory: &Path) -> Result<(Kdtree<PopulationGridEn
     Ok((Kdtree::new(&mut entries), population))
 }
 
+/// Loads the population grid(s) for the nodes of a graph. `directories` is a comma-separated list of grid directories.
+///
+/// A single grid is returned unchanged. Multiple grids (possibly of different resolutions) are combined by interpolating
+/// the population density at each node, using the finest grid covering it. The result is a grid with one cell per node,
+/// so it can directly be used for population-based query generation.
+pub fn load_population_grid_for_nodes(
+    directories: &str,
+    longitude: &Vec<f64>,
+    latitude: &Vec<f64>,
+) -> Result<(Kdtree<PopulationGridEntry>, Vec<u32>), Box<dyn Error>> {
+    let directories = directories.split(',').filter(|dir| !dir.is_empty()).collect::<Vec<&str>>();
+
+    if directories.len() == 1 {
+        return load_population_grid(Path::new(directories[0]));
+    }
+
+    let mut grids = directories
+        .iter()
+        .map(|dir| PopulationGrid::load(Path::new(dir)))
+        .collect::<Result<Vec<PopulationGrid>, Box<dyn Error>>>()?;
+    grids.sort_by(|a, b| a.cell_area().partial_cmp(&b.cell_area()).unwrap());
+
+    let node_population = interpolate_node_population(&grids, longitude, latitude);
+    let total_population = node_population.iter().sum::<f64>();
+    if total_population <= 0.0 {
+        return Err(Box::new(CliErr("No node is covered by the given population grids!")));
+    }
+
+    let scale = NODE_POPULATION_SCALE / total_population;
+    let population = node_population.iter().map(|&pop| (pop * scale).round() as u32).collect::<Vec<u32>>();
+
+    let mut entries = longitude
+        .iter()
+        .zip(latitude.iter())
+        .enumerate()
+        .map(|(id, (&lon, &lat))| PopulationGridEntry::new(id, lon, lat))
+        .collect::<Vec<PopulationGridEntry>>();
+
+    Ok((Kdtree::new(&mut entries), population))
+}
+
+/// Regular population grid, cells are addressed by their (x, y) index relative to the grid origin
+pub struct PopulationGrid {
+    origin: [f64; 2],
+    resolution: [f64; 2],
+    cells: HashMap<(i64, i64), u32>,
+}
+
+impl PopulationGrid {
+    pub fn load(directory: &Path) -> Result<Self, Box<dyn Error>> {
+        let longitude: Vec<f64> = Vec::load_from(directory.join("grid_x"))?;
+        let latitude: Vec<f64> = Vec::load_from(directory.join("grid_y"))?;
+        let population: Vec<u32> = Vec::load_from(directory.join("population"))?;
+
+        if longitude.is_empty() {
+            return Err(Box::new(CliErr("Empty population grid!")));
+        }
+
+        let origin = [
+            longitude.iter().cloned().fold(f64::INFINITY, f64::min),
+            latitude.iter().cloned().fold(f64::INFINITY, f64::min),
+        ];
+        let resolution = [estimate_resolution(&longitude), estimate_resolution(&latitude)];
+
+        let mut grid = Self {
+            origin,
+            resolution,
+            cells: HashMap::with_capacity(population.len()),
+        };
+        for ((&lon, &lat), &pop) in longitude.iter().zip(latitude.iter()).zip(population.iter()) {
+            let (x, y) = grid.cell_position(lon, lat);
+            *grid.cells.entry((x.round() as i64, y.round() as i64)).or_insert(0) += pop;
+        }
+
+        println!(
+            "Loaded population grid with {} cells, resolution {:.5} x {:.5}",
+            grid.cells.len(),
+            resolution[0],
+            resolution[1]
+        );
+        Ok(grid)
+    }
+
+    pub fn cell_area(&self) -> f64 {
+        self.resolution[0] * self.resolution[1]
+    }
+
+    /// fractional cell index of the given coordinates
+    fn cell_position(&self, lon: f64, lat: f64) -> (f64, f64) {
+        ((lon - self.origin[0]) / self.resolution[0], (lat - self.origin[1]) / self.resolution[1])
+    }
+
+    fn cell_index(&self, lon: f64, lat: f64) -> (i64, i64) {
+        let (x, y) = self.cell_position(lon, lat);
+        (x.round() as i64, y.round() as i64)
+    }
+
+    /// Bilinear interpolation of the population per cell between the four surrounding cell centers.
+    /// Missing cells are ignored, `None` if the position is not covered by the grid at all.
+    pub fn interpolate(&self, lon: f64, lat: f64) -> Option<f64> {
+        let (x, y) = self.cell_position(lon, lat);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+
+        let corners = [
+            ((x0 as i64, y0 as i64), (1.0 - tx) * (1.0 - ty)),
+            ((x0 as i64 + 1, y0 as i64), tx * (1.0 - ty)),
+            ((x0 as i64, y0 as i64 + 1), (1.0 - tx) * ty),
+            ((x0 as i64 + 1, y0 as i64 + 1), tx * ty),
+        ];
+
+        let (weighted_population, sum_weights) = corners
+            .iter()
+            .filter_map(|(cell, weight)| self.cells.get(cell).map(|&pop| (pop as f64 * weight, *weight)))
+            .fold((0.0, 0.0), |(acc_pop, acc_weight), (pop, weight)| (acc_pop + pop, acc_weight + weight));
+
+        if sum_weights > 0.0 {
+            Some(weighted_population / sum_weights)
+        } else {
+            None
+        }
+    }
+}
+
+/// Population of each node. The interpolated density of the finest covering grid is distributed evenly
+/// among all nodes of the same cell, so the total population doesn't depend on the node density.
+/// `grids` must be sorted by resolution (finest first).
+pub fn interpolate_node_population(grids: &Vec<PopulationGrid>, longitude: &Vec<f64>, latitude: &Vec<f64>) -> Vec<f64> {
+    // 1. interpolate with the finest covering grid
+    let node_population = longitude
+        .iter()
+        .zip(latitude.iter())
+        .map(|(&lon, &lat)| {
+            grids
+                .iter()
+                .enumerate()
+                .find_map(|(grid_id, grid)| grid.interpolate(lon, lat).map(|pop| (grid_id, grid.cell_index(lon, lat), pop)))
+        })
+        .collect::<Vec<Option<(usize, (i64, i64), f64)>>>();
+
+    // 2. share the cell population among all nodes inside
+    let mut nodes_per_cell = HashMap::new();
+    node_population
+        .iter()
+        .flatten()
+        .for_each(|&(grid_id, cell, _)| *nodes_per_cell.entry((grid_id, cell)).or_insert(0usize) += 1);
+
+    let num_uncovered = node_population.iter().filter(|pop| pop.is_none()).count();
+    if num_uncovered > 0 {
+        println!("{} of {} nodes are not covered by any population grid", num_uncovered, longitude.len());
+    }
+
+    node_population
+        .iter()
+        .map(|entry| entry.map(|(grid_id, cell, pop)| pop / nodes_per_cell[&(grid_id, cell)] as f64).unwrap_or(0.0))
+        .collect()
+}
+
+/// smallest positive distance between two distinct coordinates
+fn estimate_resolution(coords: &Vec<f64>) -> f64 {
+    let mut sorted = coords.clone();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    sorted
+        .windows(2)
+        .map(|w| w[1] - w[0])
+        .filter(|&diff| diff > 1e-9)
+        .fold(f64::INFINITY, f64::min)
+        .min(1.0) // fallback for grids with a single row/column
+}
+
 impl KdtreePointTrait for PopulationGridEntry {
     #[inline] // the inline on this method is important! Without it there is ~25% speed loss on the tree when cross-crate usage.
     fn dims(&self) -> &[f64] {
@@ -1,9 +1,11 @@
 use crate::dijkstra::potentials::cch_lower_upper::bounded_potential::BoundedLowerUpperPotentialContext;
 use crate::dijkstra::potentials::corridor_lowerbound_potential::customization::CustomizedCorridorLowerbound;
+use crate::dijkstra::potentials::corridor_lowerbound_potential::customization_catchup::convert_to_td_graph;
 use crate::dijkstra::potentials::corridor_lowerbound_potential::CorridorLowerboundPotentialContext;
-use crate::dijkstra::potentials::multi_metric_potential::customization::CustomizedMultiMetrics;
+use crate::dijkstra::potentials::multi_metric_potential::customization::{metric_stride, CustomizedMultiMetrics};
 use crate::dijkstra::potentials::multi_metric_potential::metric_reduction::MetricEntry;
 use rust_road_router::algo::customizable_contraction_hierarchy::{DirectedCCH, CCH};
+use rust_road_router::datastr::graph::time_dependent::TDGraph;
 use rust_road_router::datastr::graph::Graph;
 use rust_road_router::io::{Deconstruct, Load, Reconstruct, Store};
 use rust_road_router::report::measure;
@@ -84,16 +86,68 @@ pub fn store_interval_minima(directory: &Path, customized: &CustomizedCorridorLo
     Ok(())
 }
 
+/// Load the interval minima for the given graph, order and number of intervals from `cache_directory`.
+/// If there is no such entry yet, the CATCHUp customization is run and its result stored for the next time.
+pub fn load_or_customize_interval_minima(
+    cache_directory: &Path,
+    cch: &CCH,
+    graph: &TDGraph,
+    num_intervals: u32,
+) -> Result<CustomizedCorridorLowerbound, Box<dyn Error>> {
+    let entry = cache_directory.join(format!("{:016x}_{}", customization_input_hash(cch, graph), num_intervals));
+    if entry.join("num_intervals").exists() {
+        println!("Loading cached interval minima from {}", entry.display());
+        return load_interval_minima(&entry);
+    }
+
+    println!("No cached interval minima in {}, running customization", entry.display());
+    let customized = CustomizedCorridorLowerbound::new_from_ptv(cch, &convert_to_td_graph(graph), num_intervals);
+
+    // write into a temporary directory first, so an aborted run never leaves an incomplete entry behind
+    std::fs::create_dir_all(cache_directory)?;
+    let incomplete = entry.with_extension("incomplete");
+    if incomplete.exists() {
+        std::fs::remove_dir_all(&incomplete)?;
+    }
+    store_interval_minima(&incomplete, &customized)?;
+    std::fs::rename(&incomplete, &entry)?;
+
+    Ok(customized)
+}
+
+/// Hash over everything the interval minima customization depends on: topology, travel time functions and node order.
+/// FNV-1a, so the cache keys stay the same across builds and platforms.
+pub fn customization_input_hash(cch: &CCH, graph: &TDGraph) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    for slice in [
+        graph.first_out(),
+        graph.head(),
+        graph.first_ipp_of_arc(),
+        graph.ipp_departure_time(),
+        graph.ipp_travel_time(),
+        cch.node_order.order(),
+    ] {
+        // length first, so the borders between the slices are part of the hash
+        for value in std::iter::once(slice.len() as u32).chain(slice.iter().copied()) {
+            for byte in value.to_le_bytes() {
+                hash ^= u64::from(byte);
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+    }
+    hash
+}
+
 /* ----------------------------------------------------------------------------------------*/
 
 pub fn load_multiple_metrics(directory: &Path, cch: CCH, num_orig_edges: usize) -> Result<CustomizedMultiMetrics, Box<dyn Error>> {
     let upward = Vec::<u32>::load_from(&directory.join("upward_weights"))?;
     let downward = Vec::<u32>::load_from(&directory.join("downward_weights"))?;
 
-    let num_metrics = upward.len() / cch.num_arcs();
+    let num_metrics = Vec::<u32>::load_from(&directory.join("num_metrics"))?[0] as usize;
 
     assert_eq!(downward.len(), upward.len());
-    assert_eq!(upward.len() % cch.num_arcs(), 0);
+    assert_eq!(upward.len(), cch.num_arcs() * metric_stride(num_metrics));
 
     let metric_start = Vec::<u32>::load_from(&directory.join("metric_start"))?;
     let metric_end = Vec::<u32>::load_from(&directory.join("metric_end"))?;
@@ -127,6 +181,7 @@ pub fn store_multiple_metrics(directory: &Path, customized: &CustomizedMultiMetr
     metric_start.write_to(&directory.join("metric_start"))?;
     metric_end.write_to(&directory.join("metric_end"))?;
     metric_ids.write_to(&directory.join("metric_ids"))?;
+    vec![customized.num_metrics as u32].write_to(&directory.join("num_metrics"))?;
 
     customized.upward.write_to(&directory.join("upward_weights"))?;
     customized.downward.write_to(&directory.join("downward_weights"))?;
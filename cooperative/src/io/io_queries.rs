@@ -1,3 +1,5 @@
+use crate::dijkstra::model::TripId;
+use crate::graph::access_restrictions::{VehicleClass, DEFAULT_VEHICLE_CLASS, MAX_VEHICLE_CLASSES};
 use rust_road_router::algo::{GenQuery, TDQuery};
 use rust_road_router::datastr::graph::time_dependent::Timestamp;
 use rust_road_router::datastr::graph::NodeId;
@@ -35,3 +37,99 @@ pub fn store_queries(queries: &Vec<TDQuery<Timestamp>>, directory: &Path) -> Res
 
     Ok(())
 }
+
+/// load the trip ids of the queries in a given directory
+/// if no ids are stored, the query index is used as trip id
+pub fn load_trip_ids(directory: &Path, num_queries: usize) -> Result<Vec<TripId>, Box<dyn Error>> {
+    let path = directory.join("trip_id");
+
+    if path.exists() {
+        let trip_ids: Vec<TripId> = Vec::load_from(path)?;
+        if trip_ids.len() != num_queries {
+            return Err(format!("{} trip ids stored for {} queries", trip_ids.len(), num_queries).into());
+        }
+        Ok(trip_ids)
+    } else {
+        Ok((0..num_queries as TripId).collect())
+    }
+}
+
+/// store the trip ids of the queries in a given directory
+pub fn store_trip_ids(trip_ids: &Vec<TripId>, directory: &Path) -> Result<(), Box<dyn Error>> {
+    trip_ids.write_to(&directory.join("trip_id"))?;
+    Ok(())
+}
+
+/// load the partner trip of each query (e.g. the evening return trip of a commuter), if stored in the given directory
+pub fn load_return_trips(directory: &Path, num_queries: usize) -> Result<Option<Vec<u32>>, Box<dyn Error>> {
+    let path = directory.join("return_trip");
+
+    if path.exists() {
+        let return_trips: Vec<u32> = Vec::load_from(path)?;
+        if return_trips.len() != num_queries {
+            return Err(format!("{} return trips stored for {} queries", return_trips.len(), num_queries).into());
+        }
+        if let Some(idx) = (0..num_queries).find(|&idx| return_trips[idx] as usize >= num_queries) {
+            return Err(format!("partner {} of query {} is out of range", return_trips[idx], idx).into());
+        }
+        if let Some(idx) = (0..num_queries).find(|&idx| return_trips[return_trips[idx] as usize] as usize != idx) {
+            return Err(format!("query {} is paired with {}, but not vice versa", idx, return_trips[idx]).into());
+        }
+        Ok(Some(return_trips))
+    } else {
+        Ok(None)
+    }
+}
+
+/// load the Dijkstra rank power of each query (see `generate_dijkstra_rank_queries`), if stored in the given directory
+pub fn load_query_ranks(directory: &Path, num_queries: usize) -> Result<Option<Vec<u32>>, Box<dyn Error>> {
+    let path = directory.join("rank");
+
+    if path.exists() {
+        let ranks: Vec<u32> = Vec::load_from(path)?;
+        if ranks.len() != num_queries {
+            return Err(format!("{} ranks stored for {} queries", ranks.len(), num_queries).into());
+        }
+        Ok(Some(ranks))
+    } else {
+        Ok(None)
+    }
+}
+
+/// load the vehicle class of each query (see `VehicleClass`) from a given directory
+/// if no classes are stored, all queries use the default class
+pub fn load_vehicle_classes(directory: &Path, num_queries: usize) -> Result<Vec<VehicleClass>, Box<dyn Error>> {
+    let path = directory.join("vehicle_class");
+
+    if path.exists() {
+        let classes: Vec<u8> = Vec::load_from(path)?;
+        if classes.len() != num_queries {
+            return Err(format!("{} vehicle classes stored for {} queries", classes.len(), num_queries).into());
+        }
+        if let Some(&class) = classes.iter().find(|&&class| class >= MAX_VEHICLE_CLASSES) {
+            return Err(format!("invalid vehicle class {}", class).into());
+        }
+        Ok(classes)
+    } else {
+        Ok(vec![DEFAULT_VEHICLE_CLASS; num_queries])
+    }
+}
+
+/// load the passenger car equivalent factor of each query (e.g. trucks = 2.5, buses = 3) from a given directory
+/// if no factors are stored, all queries are treated as passenger cars
+pub fn load_pce_factors(directory: &Path, num_queries: usize) -> Result<Vec<f32>, Box<dyn Error>> {
+    let path = directory.join("pce");
+
+    if path.exists() {
+        let pce_factors: Vec<f32> = Vec::load_from(path)?;
+        if pce_factors.len() != num_queries {
+            return Err(format!("{} PCE factors stored for {} queries", pce_factors.len(), num_queries).into());
+        }
+        if let Some(&factor) = pce_factors.iter().find(|&&factor| !(factor.is_finite() && factor > 0.0)) {
+            return Err(format!("invalid PCE factor {}", factor).into());
+        }
+        Ok(pce_factors)
+    } else {
+        Ok(vec![1.0; num_queries])
+    }
+}
@@ -0,0 +1,99 @@
+use crate::dijkstra::potentials::multi_metric_potential::interval_patterns::parse_time_of_day;
+use crate::graph::access_restrictions::{RestrictionZone, VehicleClass, MAX_VEHICLE_CLASSES};
+use crate::graph::MAX_BUCKETS;
+use serde_json::Value;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Load restriction zones from a GeoJSON file with one `Polygon` feature per zone, e.g.
+///
+/// ```json
+/// {
+///   "type": "FeatureCollection",
+///   "features": [{
+///     "type": "Feature",
+///     "geometry": { "type": "Polygon", "coordinates": [[[8.38, 49.00], [8.42, 49.00], [8.42, 49.02], [8.38, 49.02], [8.38, 49.00]]] },
+///     "properties": { "vehicle_classes": [1, 2], "time_windows": [["07:00", "19:00"]] }
+///   }]
+/// }
+/// ```
+///
+/// Only the outer ring of each polygon is used. Zones without `vehicle_classes` restrict all classes,
+/// zones without `time_windows` apply for the whole day.
+pub fn load_restriction_zones(path: &Path) -> Result<Vec<RestrictionZone>, Box<dyn Error>> {
+    let json: Value = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+    let features = json
+        .get("features")
+        .and_then(Value::as_array)
+        .ok_or("restriction zones must contain a `features` array")?;
+
+    let zones = features
+        .iter()
+        .enumerate()
+        .map(|(idx, feature)| parse_zone(feature).map_err(|e| format!("zone {}: {}", idx, e).into()))
+        .collect::<Result<Vec<RestrictionZone>, Box<dyn Error>>>()?;
+
+    println!("Loaded {} restriction zones", zones.len());
+    Ok(zones)
+}
+
+fn parse_zone(feature: &Value) -> Result<RestrictionZone, Box<dyn Error>> {
+    let polygon = parse_polygon(feature)?;
+
+    let properties = feature.get("properties");
+    let classes = match properties.and_then(|properties| properties.get("vehicle_classes")) {
+        None => (0..MAX_VEHICLE_CLASSES).collect(),
+        Some(classes) => classes
+            .as_array()
+            .and_then(|classes| {
+                classes
+                    .iter()
+                    .map(|class| {
+                        class
+                            .as_u64()
+                            .filter(|&class| class < MAX_VEHICLE_CLASSES as u64)
+                            .map(|class| class as VehicleClass)
+                    })
+                    .collect::<Option<Vec<VehicleClass>>>()
+            })
+            .ok_or("invalid `vehicle_classes`")?,
+    };
+
+    let mut time_windows = Vec::new();
+    if let Some(windows) = properties.and_then(|properties| properties.get("time_windows")) {
+        for window in windows.as_array().ok_or("invalid `time_windows`")? {
+            let (start, end) = match window.as_array().map(|window| window.as_slice()) {
+                Some([Value::String(start), Value::String(end)]) => (parse_time_of_day(start)?, parse_time_of_day(end)?),
+                _ => return Err("time windows must be given as [\"HH:MM\", \"HH:MM\"]".into()),
+            };
+            if start >= end || end > MAX_BUCKETS {
+                return Err(format!("invalid time window [{}, {})", start, end).into());
+            }
+            time_windows.push((start, end));
+        }
+    }
+
+    Ok(RestrictionZone::new(polygon, &classes, time_windows))
+}
+
+/// outer ring of a GeoJSON `Polygon` feature as `(longitude, latitude)`
+pub(crate) fn parse_polygon(feature: &Value) -> Result<Vec<(f64, f64)>, Box<dyn Error>> {
+    let geometry = feature.get("geometry").ok_or("missing geometry")?;
+    if geometry.get("type").and_then(Value::as_str) != Some("Polygon") {
+        return Err("only `Polygon` geometries are supported".into());
+    }
+    let polygon = geometry
+        .get("coordinates")
+        .and_then(|rings| rings.get(0))
+        .and_then(Value::as_array)
+        .and_then(|ring| {
+            ring.iter()
+                .map(|point| Some((point.get(0)?.as_f64()?, point.get(1)?.as_f64()?)))
+                .collect::<Option<Vec<(f64, f64)>>>()
+        })
+        .filter(|ring| ring.len() >= 3)
+        .ok_or("invalid polygon coordinates")?;
+    Ok(polygon)
+}
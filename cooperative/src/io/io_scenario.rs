@@ -0,0 +1,122 @@
+use crate::dijkstra::potentials::multi_metric_potential::interval_patterns::parse_time_of_day;
+use crate::experiments::scenario::{DemandScaling, LaneClosure, Scenario, WeatherArea, WeatherEvent};
+use crate::graph::weather::WeatherFactor;
+use crate::graph::MAX_BUCKETS;
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use serde_json::Value;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Load a scenario from a JSON file of the form
+///
+/// ```json
+/// {
+///   "events": [
+///     { "type": "lane_closure", "edge_id": 4711, "closed_lanes": 1, "start": "07:30", "end": "09:00" },
+///     { "type": "lane_closure", "edge_id": 4712, "start": 27000000, "end": 32400000 },
+///     { "type": "demand_scaling", "factor": 1.2, "start": "06:00", "end": "10:00" },
+///     { "type": "weather", "speed_factor": 0.8, "capacity_factor": 0.9, "region": [8.3, 48.9, 8.5, 49.1], "start": "16:00" },
+///     { "type": "weather", "speed_factor": 0.6, "edge_ids": [4711, 4712] }
+///   ]
+/// }
+/// ```
+///
+/// Times are given either in milliseconds or as `HH:MM`, `start` and `end` default to the whole day.
+/// Lane closures without `closed_lanes` close all but one lane of the edge.
+/// Weather applies to the given `edge_ids`, a `region` (`[min_lon, min_lat, max_lon, max_lat]`) or the whole graph,
+/// missing factors default to 1.
+pub fn load_scenario(path: &Path) -> Result<Scenario, Box<dyn Error>> {
+    let json: Value = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+    let events = json.get("events").and_then(Value::as_array).ok_or("scenario must contain an `events` array")?;
+
+    let mut scenario = Scenario::default();
+    for (idx, event) in events.iter().enumerate() {
+        parse_event(event, &mut scenario).map_err(|e| format!("event {}: {}", idx, e))?;
+    }
+
+    println!(
+        "Loaded scenario with {} lane closures, {} demand scalings and {} weather events",
+        scenario.lane_closures.len(),
+        scenario.demand_scalings.len(),
+        scenario.weather.len()
+    );
+    Ok(scenario)
+}
+
+fn parse_event(event: &Value, scenario: &mut Scenario) -> Result<(), Box<dyn Error>> {
+    let start = parse_time(event.get("start"), 0)?;
+    let end = parse_time(event.get("end"), MAX_BUCKETS)?;
+    if start >= end || end > MAX_BUCKETS {
+        return Err(format!("invalid time window [{}, {})", start, end).into());
+    }
+
+    match event.get("type").and_then(Value::as_str) {
+        Some("lane_closure") => {
+            let closed_lanes = match event.get("closed_lanes") {
+                None => None,
+                Some(value) => Some(parse_u32(value).ok_or("invalid `closed_lanes`")?),
+            };
+            scenario.lane_closures.push(LaneClosure {
+                edge_id: event.get("edge_id").and_then(parse_u32).ok_or("missing or invalid `edge_id`")?,
+                closed_lanes,
+                start,
+                end,
+            });
+        }
+        Some("demand_scaling") => {
+            let factor = event
+                .get("factor")
+                .and_then(Value::as_f64)
+                .filter(|&factor| factor > 0.0)
+                .ok_or("missing or invalid `factor`")?;
+            scenario.demand_scalings.push(DemandScaling { factor, start, end });
+        }
+        Some("weather") => {
+            let factor = |key: &str| match event.get(key) {
+                None => Ok(1.0),
+                Some(value) => value.as_f64().filter(|&factor| factor > 0.0).ok_or(format!("invalid `{}`", key)),
+            };
+            let area = match (event.get("edge_ids"), event.get("region")) {
+                (Some(_), Some(_)) => return Err("weather must not specify both `edge_ids` and `region`".into()),
+                (Some(edges), None) => WeatherArea::Edges(
+                    edges
+                        .as_array()
+                        .and_then(|edges| edges.iter().map(parse_u32).collect::<Option<Vec<u32>>>())
+                        .ok_or("invalid `edge_ids`")?,
+                ),
+                (None, Some(region)) => {
+                    let bounds = region
+                        .as_array()
+                        .and_then(|bounds| bounds.iter().map(Value::as_f64).collect::<Option<Vec<f64>>>())
+                        .filter(|bounds| bounds.len() == 4 && bounds[0] <= bounds[2] && bounds[1] <= bounds[3])
+                        .ok_or("invalid `region`, expected [min_lon, min_lat, max_lon, max_lat]")?;
+                    WeatherArea::Region([bounds[0], bounds[1], bounds[2], bounds[3]])
+                }
+                (None, None) => WeatherArea::Everywhere,
+            };
+            scenario.weather.push(WeatherEvent {
+                area,
+                factor: WeatherFactor::new(factor("speed_factor")?, factor("capacity_factor")?),
+                start,
+                end,
+            });
+        }
+        Some(other) => return Err(format!("unknown event type `{}`", other).into()),
+        None => return Err("missing event type".into()),
+    }
+    Ok(())
+}
+
+fn parse_time(value: Option<&Value>, default: Timestamp) -> Result<Timestamp, Box<dyn Error>> {
+    match value {
+        None => Ok(default),
+        Some(Value::String(time)) => parse_time_of_day(time),
+        Some(value) => parse_u32(value).ok_or_else(|| format!("invalid time `{}`", value).into()),
+    }
+}
+
+fn parse_u32(value: &Value) -> Option<u32> {
+    value.as_u64().filter(|&value| value <= u32::MAX as u64).map(|value| value as u32)
+}
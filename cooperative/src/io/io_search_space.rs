@@ -0,0 +1,33 @@
+use crate::dijkstra::model::SettledNode;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Store the settled nodes of a query as GeoJSON point layer.
+/// Each point carries its settling `rank`, its `distance` from the departure, its `potential` and the resulting queue `key` (all in ms).
+pub fn store_search_space_geojson(path: &Path, search_space: &[SettledNode], lat: &[f32], lon: &[f32]) -> Result<(), Box<dyn Error>> {
+    let features = search_space
+        .iter()
+        .enumerate()
+        .map(|(rank, settled)| {
+            let node = settled.node as usize;
+            json!({
+                "type": "Feature",
+                "geometry": { "type": "Point", "coordinates": [lon[node], lat[node]] },
+                "properties": {
+                    "node": settled.node,
+                    "rank": rank,
+                    "distance": settled.distance,
+                    "potential": settled.potential,
+                    "key": settled.distance + settled.potential,
+                },
+            })
+        })
+        .collect::<Vec<Value>>();
+
+    let collection = json!({ "type": "FeatureCollection", "features": features });
+    serde_json::to_writer(BufWriter::new(File::create(path)?), &collection)?;
+    Ok(())
+}
@@ -1,7 +1,16 @@
+pub mod io_bucket_loads;
 pub mod io_coordinates;
+pub mod io_csv;
 pub mod io_graph;
+pub mod io_historic_speeds;
+pub mod io_load_feed;
 pub mod io_node_order;
+pub mod io_parking;
+pub mod io_paths;
 pub mod io_population_grid;
 pub mod io_ptv_customization;
 pub mod io_queries;
+pub mod io_restrictions;
+pub mod io_scenario;
+pub mod io_search_space;
 pub mod modification;
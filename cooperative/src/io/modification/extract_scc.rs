@@ -1,6 +1,9 @@
+use std::cmp::min;
 use std::error::Error;
 use std::path::Path;
+use std::str::FromStr;
 
+use rust_road_router::cli::CliErr;
 use rust_road_router::datastr::graph::{EdgeId, EdgeIdGraph, Graph, NodeId, UnweightedFirstOutGraph, Weight};
 use rust_road_router::datastr::rank_select_map::{BitVec, RankSelectMap};
 use rust_road_router::io::{Load, Store};
@@ -8,17 +11,59 @@ use rust_road_router::io::{Load, Store};
 use crate::graph::Capacity;
 use crate::io::io_coordinates::load_coords;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentType {
+    Strong,
+    Weak,
+}
+
+impl FromStr for ComponentType {
+    type Err = CliErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "SCC" | "STRONG" => Ok(Self::Strong),
+            "WCC" | "WEAK" => Ok(Self::Weak),
+            _ => Err(CliErr("Invalid Component Type [SCC/WCC]")),
+        }
+    }
+}
+
 /// Extract the largest strongly connected component of a given Graph.
 /// This preprocessing step avoids invalid (s,t)-queries where t is not reachable from s.
-/// The result will be written to the output directory
+/// The component is read from the file `largest_scc`, the result will be written to the output directory
 pub fn extract_largest_scc(graph_directory: &Path, out_directory: &Path) -> Result<(), Box<dyn Error>> {
+    let is_largest_scc = Vec::<u32>::load_from(&graph_directory.join("largest_scc"))?;
+    extract_component(graph_directory, out_directory, &is_largest_scc)
+}
+
+/// Compute the largest strongly or weakly connected component of a given Graph and extract it to the output directory.
+/// Weak components are useful for undirected-ish networks where single one-way edges would otherwise cut off large parts.
+pub fn extract_largest_component(graph_directory: &Path, out_directory: &Path, component_type: ComponentType) -> Result<(), Box<dyn Error>> {
+    let first_out = Vec::<EdgeId>::load_from(graph_directory.join("first_out"))?;
+    let head = Vec::<NodeId>::load_from(graph_directory.join("head"))?;
+
+    let is_largest_component = mark_largest_component(&first_out, &head, component_type);
+    extract_component(graph_directory, out_directory, &is_largest_component)
+}
+
+/// Marks the members of the largest component with 1, all other nodes with 0 (same format as `largest_scc`).
+/// Component size statistics are printed to stdout.
+pub fn mark_largest_component(first_out: &[EdgeId], head: &[NodeId], component_type: ComponentType) -> Vec<u32> {
+    let component = match component_type {
+        ComponentType::Strong => strongly_connected_components(first_out, head),
+        ComponentType::Weak => weakly_connected_components(first_out, head),
+    };
+    largest_component(&component)
+}
+
+fn extract_component(graph_directory: &Path, out_directory: &Path, is_largest_scc: &Vec<u32>) -> Result<(), Box<dyn Error>> {
     let first_out = Vec::load_from(graph_directory.join("first_out"))?;
     let head = Vec::load_from(graph_directory.join("head"))?;
     let geo_distance = Vec::<Weight>::load_from(graph_directory.join("geo_distance"))?;
     let travel_time = Vec::<Weight>::load_from(graph_directory.join("travel_time"))?;
     let capacity = Vec::<Capacity>::load_from(graph_directory.join("capacity"))?;
     let (longitude, latitude) = load_coords(graph_directory)?;
-    let is_largest_scc = Vec::<u32>::load_from(&graph_directory.join("largest_scc"))?;
 
     // initialize RankSelectMap structure
     let mut bit_vec = BitVec::new(is_largest_scc.len());
@@ -77,3 +122,116 @@ pub fn extract_largest_scc(graph_directory: &Path, out_directory: &Path) -> Resu
 
     Ok(())
 }
+
+/// component id of each node (iterative variant of Tarjan's algorithm)
+fn strongly_connected_components(first_out: &[EdgeId], head: &[NodeId]) -> Vec<u32> {
+    let n = first_out.len() - 1;
+    let mut index = vec![u32::MAX; n];
+    let mut low_link = vec![0; n];
+    let mut on_stack = vec![false; n];
+    let mut stack = Vec::new();
+    let mut component = vec![u32::MAX; n];
+
+    let mut next_index = 0;
+    let mut num_components = 0;
+    // (node, next edge to explore)
+    let mut call_stack: Vec<(usize, usize)> = Vec::new();
+
+    for root in 0..n {
+        if index[root] != u32::MAX {
+            continue;
+        }
+
+        index[root] = next_index;
+        low_link[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+        call_stack.push((root, first_out[root] as usize));
+
+        while let Some(&(node, edge)) = call_stack.last() {
+            if edge < first_out[node + 1] as usize {
+                call_stack.last_mut().unwrap().1 += 1;
+                let next = head[edge] as usize;
+
+                if index[next] == u32::MAX {
+                    index[next] = next_index;
+                    low_link[next] = next_index;
+                    next_index += 1;
+                    stack.push(next);
+                    on_stack[next] = true;
+                    call_stack.push((next, first_out[next] as usize));
+                } else if on_stack[next] {
+                    low_link[node] = min(low_link[node], index[next]);
+                }
+            } else {
+                call_stack.pop();
+                if let Some(&(parent, _)) = call_stack.last() {
+                    low_link[parent] = min(low_link[parent], low_link[node]);
+                }
+
+                // `node` is the root of a component
+                if low_link[node] == index[node] {
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        component[member] = num_components;
+                        if member == node {
+                            break;
+                        }
+                    }
+                    num_components += 1;
+                }
+            }
+        }
+    }
+
+    component
+}
+
+/// component id of each node, edge directions are ignored (union-find)
+fn weakly_connected_components(first_out: &[EdgeId], head: &[NodeId]) -> Vec<u32> {
+    fn find(parent: &mut Vec<u32>, mut node: u32) -> u32 {
+        while parent[node as usize] != node {
+            parent[node as usize] = parent[parent[node as usize] as usize];
+            node = parent[node as usize];
+        }
+        node
+    }
+
+    let n = first_out.len() - 1;
+    let mut parent = (0..n as u32).collect::<Vec<u32>>();
+
+    for (tail, edges) in first_out.windows(2).enumerate() {
+        for &next in &head[edges[0] as usize..edges[1] as usize] {
+            let (a, b) = (find(&mut parent, tail as u32), find(&mut parent, next));
+            if a != b {
+                parent[a as usize] = b;
+            }
+        }
+    }
+
+    (0..n as u32).map(|node| find(&mut parent, node)).collect()
+}
+
+/// marks the members of the largest component with 1, prints component size statistics
+fn largest_component(component: &Vec<u32>) -> Vec<u32> {
+    let mut sizes = vec![0usize; component.len()];
+    component.iter().for_each(|&c| sizes[c as usize] += 1);
+
+    let (largest, &largest_size) = sizes.iter().enumerate().max_by_key(|&(_, size)| size).unwrap();
+
+    let mut component_sizes = sizes.iter().cloned().filter(|&size| size > 0).collect::<Vec<usize>>();
+    component_sizes.sort_unstable_by(|a, b| b.cmp(a));
+
+    println!(
+        "Found {} components: largest with {} of {} nodes ({:.2}%), second largest with {} nodes, {} isolated nodes",
+        component_sizes.len(),
+        largest_size,
+        component.len(),
+        100.0 * largest_size as f64 / component.len() as f64,
+        component_sizes.get(1).cloned().unwrap_or(0),
+        component_sizes.iter().filter(|&&size| size == 1).count()
+    );
+
+    component.iter().map(|&c| (c as usize == largest) as u32).collect()
+}
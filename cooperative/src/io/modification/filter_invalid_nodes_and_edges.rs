@@ -1,9 +1,34 @@
+use crate::graph::edge_buckets::SpeedBuckets;
+use crate::graph::{Velocity, MAX_BUCKETS};
 use crate::io::modification::CapacityGraphContainer;
-use rust_road_router::datastr::graph::{EdgeId, EdgeIdGraph, Graph, NodeId, UnweightedFirstOutGraph};
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{EdgeId, EdgeIdGraph, Graph, NodeId, UnweightedFirstOutGraph, Weight};
 use rust_road_router::datastr::rank_select_map::{BitVec, RankSelectMap};
 use std::collections::HashMap;
 
 pub fn filter_invalid_nodes_and_edges(raw_data: &CapacityGraphContainer, is_valid_node: &Vec<bool>, is_valid_edge: &Vec<bool>) -> CapacityGraphContainer {
+    filter_internal(raw_data, is_valid_node, is_valid_edge, None).0
+}
+
+/// Same as `filter_invalid_nodes_and_edges`, but multi-edges are merged profile-aware:
+/// the merged edge takes the pointwise minimum travel time of all parallel edges and the sum of their capacities.
+/// Returns the speed profiles of the reduced graph, relative to the distance of the merged edges.
+pub fn filter_invalid_nodes_and_edges_with_profiles(
+    raw_data: &CapacityGraphContainer,
+    is_valid_node: &Vec<bool>,
+    is_valid_edge: &Vec<bool>,
+    speed_profiles: &Vec<SpeedBuckets>,
+) -> (CapacityGraphContainer, Vec<SpeedBuckets>) {
+    debug_assert_eq!(speed_profiles.len(), raw_data.head.len());
+    filter_internal(raw_data, is_valid_node, is_valid_edge, Some(speed_profiles))
+}
+
+fn filter_internal(
+    raw_data: &CapacityGraphContainer,
+    is_valid_node: &Vec<bool>,
+    is_valid_edge: &Vec<bool>,
+    speed_profiles: Option<&Vec<SpeedBuckets>>,
+) -> (CapacityGraphContainer, Vec<SpeedBuckets>) {
     // initialize RankSelectMap
     let mut bit_vec = BitVec::new(is_valid_node.len());
     is_valid_node.iter().enumerate().filter(|(_, &val)| val).for_each(|(idx, _)| bit_vec.set(idx));
@@ -20,6 +45,7 @@ pub fn filter_invalid_nodes_and_edges(raw_data: &CapacityGraphContainer, is_vali
     let mut max_capacity = Vec::with_capacity(graph.num_arcs());
     let mut longitude = Vec::with_capacity(graph.num_nodes());
     let mut latitude = Vec::with_capacity(graph.num_nodes());
+    let mut merged_profiles = Vec::with_capacity(speed_profiles.map(|_| graph.num_arcs()).unwrap_or(0));
 
     for node_id in 0..graph.num_nodes() {
         if rank_select_map.get(node_id).is_some() {
@@ -27,44 +53,36 @@ pub fn filter_invalid_nodes_and_edges(raw_data: &CapacityGraphContainer, is_vali
             longitude.push(raw_data.longitude[node_id]);
             latitude.push(raw_data.latitude[node_id]);
 
-            // collect valid edges
-            let mut neighbor_data = HashMap::<u32, (u32, u32, u32)>::new();
+            // collect valid edges, group parallel edges by their head
+            let mut neighbor_data = HashMap::<u32, Vec<usize>>::new();
             graph
                 .neighbor_edge_indices_usize(node_id as NodeId)
                 .filter(|&edge_id| is_valid_edge[edge_id] && rank_select_map.get(graph.head()[edge_id] as usize).is_some())
                 .for_each(|edge_id| {
                     let edge_head = rank_select_map.get(graph.head()[edge_id] as usize).unwrap() as NodeId;
-
-                    if let Some((edge_capacity, edge_distance, edge_tt)) = neighbor_data.get_mut(&edge_head) {
-                        // multi-edge found: sum up capacities, take tt/dist based on faster tt
-                        *edge_capacity += raw_data.max_capacity[edge_id];
-
-                        if *edge_tt > raw_data.travel_time[edge_id] {
-                            *edge_tt = raw_data.travel_time[edge_id];
-                            *edge_distance = raw_data.geo_distance[edge_id];
-                        }
-                    } else {
-                        // new entry found
-                        neighbor_data.insert(
-                            edge_head,
-                            (raw_data.max_capacity[edge_id], raw_data.geo_distance[edge_id], raw_data.travel_time[edge_id]),
-                        );
-                    }
+                    neighbor_data.entry(edge_head).or_default().push(edge_id);
                 });
 
             // append data to new structs
-            for (edge_head, (edge_capacity, edge_distance, edge_tt)) in neighbor_data {
+            for (edge_head, edges) in neighbor_data {
+                // multi-edges: sum up capacities, take tt/dist based on faster tt
+                let fastest_edge = *edges.iter().min_by_key(|&&edge_id| raw_data.travel_time[edge_id]).unwrap();
+
                 head.push(edge_head);
-                max_capacity.push(edge_capacity);
-                geo_distance.push(edge_distance);
-                travel_time.push(edge_tt);
+                max_capacity.push(edges.iter().map(|&edge_id| raw_data.max_capacity[edge_id]).sum());
+                geo_distance.push(raw_data.geo_distance[fastest_edge]);
+                travel_time.push(raw_data.travel_time[fastest_edge]);
+
+                if let Some(speed_profiles) = speed_profiles {
+                    merged_profiles.push(merge_speed_profiles(raw_data, speed_profiles, &edges, raw_data.geo_distance[fastest_edge]));
+                }
             }
 
             first_out.push(head.len() as EdgeId);
         }
     }
 
-    CapacityGraphContainer {
+    let reduced_data = CapacityGraphContainer {
         first_out,
         head,
         geo_distance,
@@ -72,5 +90,172 @@ pub fn filter_invalid_nodes_and_edges(raw_data: &CapacityGraphContainer, is_vali
         max_capacity,
         latitude,
         longitude,
+    };
+
+    (reduced_data, merged_profiles)
+}
+
+/// Merge the speed profiles of parallel edges into a single profile of length `distance`.
+/// Speeds are constant within each bucket, so the pointwise minimum travel time
+/// corresponds to the maximum distance-scaled speed on the union of all bucket borders.
+fn merge_speed_profiles(raw_data: &CapacityGraphContainer, speed_profiles: &Vec<SpeedBuckets>, edges: &Vec<usize>, distance: Weight) -> SpeedBuckets {
+    if edges.len() == 1 || edges.iter().all(|&edge_id| !speed_profiles[edge_id].is_used()) {
+        return speed_profiles[edges[0]].clone();
+    }
+
+    // unused profiles are treated as constant free-flow speed
+    let profiles = edges
+        .iter()
+        .map(|&edge_id| match &speed_profiles[edge_id] {
+            SpeedBuckets::Used(inner) => inner.clone(),
+            SpeedBuckets::Unused => {
+                let free_flow_speed = (3600 * raw_data.geo_distance[edge_id]) / raw_data.travel_time[edge_id].max(1);
+                vec![(0, free_flow_speed), (MAX_BUCKETS, free_flow_speed)]
+            }
+        })
+        .collect::<Vec<Vec<(Timestamp, Velocity)>>>();
+
+    let mut timestamps = profiles.iter().flatten().map(|&(ts, _)| ts).collect::<Vec<Timestamp>>();
+    timestamps.sort_unstable();
+    timestamps.dedup();
+
+    let mut merged = timestamps
+        .iter()
+        .map(|&ts| {
+            let speed = profiles
+                .iter()
+                .zip(edges.iter())
+                .map(|(profile, &edge_id)| {
+                    // speed of the bucket containing `ts`
+                    let pos = profile.partition_point(|&(bucket_ts, _)| bucket_ts <= ts) - 1;
+                    let edge_distance = raw_data.geo_distance[edge_id].max(1) as u64;
+                    profile[pos].1 as u64 * distance as u64 / edge_distance
+                })
+                .max()
+                .unwrap();
+            (ts, speed.max(1) as Velocity)
+        })
+        .collect::<Vec<(Timestamp, Velocity)>>();
+
+    // remove redundant bucket borders, the midnight sentinel is always kept
+    merged.dedup_by(|next, prev| next.0 != MAX_BUCKETS && next.1 == prev.1);
+    SpeedBuckets::Used(merged)
+}
+
+/// Flag nodes with broken coordinates, i.e. non-finite values, (0,0) or positions far outside the bounding box of the graph.
+/// The bounding box is spanned by the 1st and 99th percentile of all coordinates and extended by its own size in each direction.
+pub fn find_coordinate_outliers(raw_data: &CapacityGraphContainer) -> Vec<bool> {
+    let is_broken = |node_id: usize| {
+        let (lon, lat) = (raw_data.longitude[node_id], raw_data.latitude[node_id]);
+        !lon.is_finite() || !lat.is_finite() || (lon == 0.0 && lat == 0.0)
+    };
+    let num_nodes = raw_data.longitude.len();
+
+    let (mut longitudes, mut latitudes): (Vec<f32>, Vec<f32>) = (0..num_nodes)
+        .filter(|&node_id| !is_broken(node_id))
+        .map(|node_id| (raw_data.longitude[node_id], raw_data.latitude[node_id]))
+        .unzip();
+
+    if longitudes.is_empty() {
+        return vec![true; num_nodes];
+    }
+
+    let (min_lon, max_lon) = extended_percentile_range(&mut longitudes);
+    let (min_lat, max_lat) = extended_percentile_range(&mut latitudes);
+
+    let is_outlier = (0..num_nodes)
+        .map(|node_id| {
+            is_broken(node_id)
+                || raw_data.longitude[node_id] < min_lon
+                || raw_data.longitude[node_id] > max_lon
+                || raw_data.latitude[node_id] < min_lat
+                || raw_data.latitude[node_id] > max_lat
+        })
+        .collect::<Vec<bool>>();
+
+    println!(
+        "Found {} nodes with invalid coordinates, affecting {} edges",
+        is_outlier.iter().filter(|&&outlier| outlier).count(),
+        count_affected_edges(raw_data, &is_outlier)
+    );
+
+    is_outlier
+}
+
+/// Repair outliers by moving them to the mean position of their valid neighbors (considering both edge directions).
+/// Repaired nodes serve as valid neighbors in subsequent rounds, so chains of outliers are repaired as well.
+/// Returns the outliers which could not be repaired.
+pub fn repair_coordinate_outliers(raw_data: &mut CapacityGraphContainer, is_outlier: &Vec<bool>) -> Vec<bool> {
+    let mut is_outlier = is_outlier.clone();
+
+    // undirected adjacency, built from the edge list
+    let mut neighbors = vec![Vec::new(); is_outlier.len()];
+    for (tail, edges) in raw_data.first_out.windows(2).enumerate() {
+        for &head in &raw_data.head[edges[0] as usize..edges[1] as usize] {
+            neighbors[tail].push(head as usize);
+            neighbors[head as usize].push(tail);
+        }
+    }
+
+    let mut num_repaired = 0;
+    loop {
+        let repaired = (0..is_outlier.len())
+            .filter(|&node_id| is_outlier[node_id])
+            .filter_map(|node_id| {
+                let valid_neighbors = neighbors[node_id].iter().filter(|&&neighbor| !is_outlier[neighbor]).collect::<Vec<&usize>>();
+                if valid_neighbors.is_empty() {
+                    return None;
+                }
+
+                let lon = valid_neighbors.iter().map(|&&neighbor| raw_data.longitude[neighbor]).sum::<f32>() / valid_neighbors.len() as f32;
+                let lat = valid_neighbors.iter().map(|&&neighbor| raw_data.latitude[neighbor]).sum::<f32>() / valid_neighbors.len() as f32;
+                Some((node_id, lon, lat))
+            })
+            .collect::<Vec<(usize, f32, f32)>>();
+
+        if repaired.is_empty() {
+            break;
+        }
+
+        for (node_id, lon, lat) in repaired {
+            raw_data.longitude[node_id] = lon;
+            raw_data.latitude[node_id] = lat;
+            is_outlier[node_id] = false;
+            num_repaired += 1;
+        }
     }
+
+    println!(
+        "Repaired coordinates of {} nodes, {} nodes remain invalid (affecting {} edges)",
+        num_repaired,
+        is_outlier.iter().filter(|&&outlier| outlier).count(),
+        count_affected_edges(raw_data, &is_outlier)
+    );
+
+    is_outlier
+}
+
+/// (lower, upper) bound of the 1st to 99th percentile, extended by its own size in each direction
+fn extended_percentile_range(values: &mut Vec<f32>) -> (f32, f32) {
+    values.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let lower = values[values.len() / 100];
+    let upper = values[values.len() - 1 - values.len() / 100];
+    let extent = upper - lower;
+
+    (lower - extent, upper + extent)
+}
+
+fn count_affected_edges(raw_data: &CapacityGraphContainer, is_outlier: &Vec<bool>) -> usize {
+    raw_data
+        .first_out
+        .windows(2)
+        .enumerate()
+        .map(|(tail, edges)| {
+            raw_data.head[edges[0] as usize..edges[1] as usize]
+                .iter()
+                .filter(|&&head| is_outlier[tail] || is_outlier[head as usize])
+                .count()
+        })
+        .sum()
 }
@@ -7,6 +7,8 @@ use std::path::Path;
 
 pub mod extract_scc;
 pub mod filter_invalid_nodes_and_edges;
+pub mod remove_degenerate_edges;
+pub mod renumber_graph;
 
 pub struct CapacityGraphContainer {
     pub first_out: Vec<EdgeId>,
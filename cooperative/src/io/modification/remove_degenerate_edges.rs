@@ -0,0 +1,67 @@
+use crate::io::modification::CapacityGraphContainer;
+use rust_road_router::datastr::graph::EdgeId;
+
+/// Graph preprocessing: remove self-loops, zero-length edges and all edges flagged as invalid
+/// (e.g. duplicate reverse edges of bidirectional ways). Such edges are useless for routing
+/// and may violate assumptions made during CCH customization.
+///
+/// Nodes are kept unchanged, the returned mapping contains the new id of each original edge
+/// and can be used to remap further edge-based data via `remap_edge_data`.
+pub fn remove_degenerate_edges(raw_data: &CapacityGraphContainer, is_flagged_invalid: &Vec<bool>) -> (CapacityGraphContainer, Vec<Option<EdgeId>>) {
+    debug_assert_eq!(is_flagged_invalid.len(), raw_data.head.len());
+
+    let mut edge_mapping = vec![None; raw_data.head.len()];
+    let mut first_out = Vec::with_capacity(raw_data.first_out.len());
+    first_out.push(0);
+
+    let (mut num_self_loops, mut num_zero_length, mut num_flagged) = (0, 0, 0);
+    let mut num_remaining_edges = 0;
+
+    for (node_id, edges) in raw_data.first_out.windows(2).enumerate() {
+        for edge_id in edges[0] as usize..edges[1] as usize {
+            if raw_data.head[edge_id] as usize == node_id {
+                num_self_loops += 1;
+            } else if raw_data.geo_distance[edge_id] == 0 || raw_data.travel_time[edge_id] == 0 {
+                num_zero_length += 1;
+            } else if is_flagged_invalid[edge_id] {
+                num_flagged += 1;
+            } else {
+                edge_mapping[edge_id] = Some(num_remaining_edges);
+                num_remaining_edges += 1;
+            }
+        }
+        first_out.push(num_remaining_edges);
+    }
+
+    println!(
+        "Removed {} self-loops, {} zero-length edges and {} flagged edges, {} of {} edges remaining",
+        num_self_loops,
+        num_zero_length,
+        num_flagged,
+        num_remaining_edges,
+        raw_data.head.len()
+    );
+
+    let cleaned_data = CapacityGraphContainer {
+        first_out,
+        head: remap_edge_data(&raw_data.head, &edge_mapping),
+        geo_distance: remap_edge_data(&raw_data.geo_distance, &edge_mapping),
+        travel_time: remap_edge_data(&raw_data.travel_time, &edge_mapping),
+        max_capacity: remap_edge_data(&raw_data.max_capacity, &edge_mapping),
+        longitude: raw_data.longitude.clone(),
+        latitude: raw_data.latitude.clone(),
+    };
+
+    (cleaned_data, edge_mapping)
+}
+
+/// Keep the entries of all remaining edges. As the edge order is preserved, the entries are simply filtered.
+pub fn remap_edge_data<T: Clone>(data: &[T], edge_mapping: &Vec<Option<EdgeId>>) -> Vec<T> {
+    debug_assert_eq!(data.len(), edge_mapping.len());
+
+    data.iter()
+        .zip(edge_mapping.iter())
+        .filter(|(_, mapping)| mapping.is_some())
+        .map(|(value, _)| value.clone())
+        .collect()
+}
@@ -0,0 +1,178 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::path::Path;
+use std::str::FromStr;
+
+use rust_road_router::cli::CliErr;
+use rust_road_router::datastr::graph::{EdgeId, NodeId};
+use rust_road_router::datastr::node_order::NodeOrder;
+use rust_road_router::io::{Load, Store};
+
+use crate::graph::edge_buckets::SpeedBuckets;
+use crate::io::io_graph::{load_used_speed_profiles, store_speed_profiles};
+
+// optional companion files, one entry per node resp. per edge
+const NODE_FILES: [&str; 2] = ["longitude", "latitude"];
+const EDGE_FILES: [&str; 5] = ["geo_distance", "travel_time", "capacity", "lanes", "lower_bound"];
+// optional node orders (rank -> node id), their entries are renumbered as well
+const ORDER_FILES: [&str; 2] = ["order", "cch_perm"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenumberOrder {
+    /// the CCH order stored in the `order` file of the graph directory
+    Stored,
+    /// breadth-first order, improves memory locality of graph searches
+    Bfs,
+    /// seeded random permutation, useful as a worst-case reference
+    Random,
+}
+
+impl FromStr for RenumberOrder {
+    type Err = CliErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "STORED" | "CCH" => Ok(Self::Stored),
+            "BFS" => Ok(Self::Bfs),
+            "RANDOM" => Ok(Self::Random),
+            _ => Err(CliErr("Invalid Renumber Order [CCH/BFS/RANDOM]")),
+        }
+    }
+}
+
+/// Breadth-first order of all nodes, following outgoing edges. Unreached nodes start a new search in order of their ids.
+pub fn bfs_order(first_out: &[EdgeId], head: &[NodeId]) -> NodeOrder {
+    let num_nodes = first_out.len() - 1;
+    let mut visited = vec![false; num_nodes];
+    let mut order = Vec::with_capacity(num_nodes);
+    let mut queue = VecDeque::new();
+
+    for root in 0..num_nodes {
+        if visited[root] {
+            continue;
+        }
+
+        visited[root] = true;
+        queue.push_back(root as NodeId);
+
+        while let Some(node) = queue.pop_front() {
+            order.push(node);
+
+            for &next in &head[first_out[node as usize] as usize..first_out[node as usize + 1] as usize] {
+                if !visited[next as usize] {
+                    visited[next as usize] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+
+    NodeOrder::from_node_order(order)
+}
+
+/// Physically renumber a graph according to a node order: node `v` gets the new id `order.rank(v)`.
+/// The outgoing edges of each node keep their relative order.
+/// Returns the new `first_out` and `head` arrays and the original id of each new edge.
+pub fn renumber_topology(first_out: &[EdgeId], head: &[NodeId], order: &NodeOrder) -> (Vec<EdgeId>, Vec<NodeId>, Vec<EdgeId>) {
+    debug_assert_eq!(first_out.len(), order.len() + 1);
+
+    let mut new_first_out = Vec::with_capacity(first_out.len());
+    new_first_out.push(0);
+    let mut new_head = Vec::with_capacity(head.len());
+    let mut edge_origin = Vec::with_capacity(head.len());
+
+    for &node in order.order() {
+        for edge_id in first_out[node as usize]..first_out[node as usize + 1] {
+            new_head.push(order.rank(head[edge_id as usize]));
+            edge_origin.push(edge_id);
+        }
+        new_first_out.push(new_head.len() as EdgeId);
+    }
+
+    (new_first_out, new_head, edge_origin)
+}
+
+/// reorder node-based data, the entry of node `v` is moved to `order.rank(v)`
+pub fn permute_node_data<T: Clone>(data: &[T], order: &NodeOrder) -> Vec<T> {
+    order.order().iter().map(|&node| data[node as usize].clone()).collect()
+}
+
+/// reorder edge-based data, `edge_origin[e]` is the original id of the new edge `e`
+pub fn permute_edge_data<T: Clone>(data: &[T], edge_origin: &[EdgeId]) -> Vec<T> {
+    edge_origin.iter().map(|&edge_id| data[edge_id as usize].clone()).collect()
+}
+
+/// Renumber the graph in `graph_directory` according to `order` and write it to `out_directory`.
+/// Besides the topology, all available companion data is renumbered: coordinates, edge attributes
+/// (distances, travel times, capacities, lanes), PTV travel time functions, speed profiles (`speed_profiles`)
+/// and stored node orders. Population data is assigned by coordinates and thus follows the renumbered nodes.
+pub fn renumber_graph(graph_directory: &Path, out_directory: &Path, order: &NodeOrder) -> Result<(), Box<dyn Error>> {
+    let first_out = Vec::<EdgeId>::load_from(graph_directory.join("first_out"))?;
+    let head = Vec::<NodeId>::load_from(graph_directory.join("head"))?;
+
+    if first_out.len() != order.len() + 1 {
+        return Err(format!("Node order has {} nodes, the graph has {}", order.len(), first_out.len() - 1).into());
+    }
+
+    // 1. topology
+    let (new_first_out, new_head, edge_origin) = renumber_topology(&first_out, &head, order);
+    new_first_out.write_to(&out_directory.join("first_out"))?;
+    new_head.write_to(&out_directory.join("head"))?;
+
+    // 2. node- and edge-based companion files
+    for file_name in NODE_FILES.iter().filter(|file_name| graph_directory.join(file_name).exists()) {
+        let data = Vec::<f32>::load_from(graph_directory.join(file_name))?;
+        permute_node_data(&data, order).write_to(&out_directory.join(file_name))?;
+    }
+
+    for file_name in EDGE_FILES.iter().filter(|file_name| graph_directory.join(file_name).exists()) {
+        let data = Vec::<u32>::load_from(graph_directory.join(file_name))?;
+        permute_edge_data(&data, &edge_origin).write_to(&out_directory.join(file_name))?;
+    }
+
+    for file_name in ORDER_FILES.iter().filter(|file_name| graph_directory.join(file_name).exists()) {
+        let stored_order = Vec::<NodeId>::load_from(graph_directory.join(file_name))?;
+        let renumbered = stored_order.iter().map(|&node| order.rank(node)).collect::<Vec<NodeId>>();
+        renumbered.write_to(&out_directory.join(file_name))?;
+    }
+
+    // 3. travel time functions of PTV graphs, each edge owns a contiguous range of interpolation points
+    if graph_directory.join("first_ipp_of_arc").exists() {
+        let first_ipp_of_arc = Vec::<u32>::load_from(graph_directory.join("first_ipp_of_arc"))?;
+        let ipp_departure_time = Vec::<u32>::load_from(graph_directory.join("ipp_departure_time"))?;
+        let ipp_travel_time = Vec::<u32>::load_from(graph_directory.join("ipp_travel_time"))?;
+
+        let mut new_first_ipp_of_arc = Vec::with_capacity(first_ipp_of_arc.len());
+        new_first_ipp_of_arc.push(0);
+        let mut new_departure_time = Vec::with_capacity(ipp_departure_time.len());
+        let mut new_travel_time = Vec::with_capacity(ipp_travel_time.len());
+
+        for &edge_id in &edge_origin {
+            let range = first_ipp_of_arc[edge_id as usize] as usize..first_ipp_of_arc[edge_id as usize + 1] as usize;
+            new_departure_time.extend_from_slice(&ipp_departure_time[range.clone()]);
+            new_travel_time.extend_from_slice(&ipp_travel_time[range]);
+            new_first_ipp_of_arc.push(new_departure_time.len() as u32);
+        }
+
+        new_first_ipp_of_arc.write_to(&out_directory.join("first_ipp_of_arc"))?;
+        new_departure_time.write_to(&out_directory.join("ipp_departure_time"))?;
+        new_travel_time.write_to(&out_directory.join("ipp_travel_time"))?;
+    }
+
+    // 4. speed profiles
+    if graph_directory.join("speed_profiles").exists() {
+        let speed_profiles = load_used_speed_profiles(&graph_directory.join("speed_profiles"))?
+            .into_iter()
+            .map(|profile| match profile {
+                SpeedBuckets::Unused => Vec::new(),
+                SpeedBuckets::Used(inner) => inner,
+            })
+            .collect::<Vec<Vec<(u32, u32)>>>();
+
+        let profile_directory = out_directory.join("speed_profiles");
+        std::fs::create_dir_all(&profile_directory)?;
+        store_speed_profiles(&profile_directory, &permute_edge_data(&speed_profiles, &edge_origin))?;
+    }
+
+    Ok(())
+}
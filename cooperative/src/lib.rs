@@ -7,6 +7,11 @@ pub mod graph;
 pub mod io;
 pub mod util;
 
+// track allocations of all binaries, reported alongside the running times
+#[cfg(feature = "report-memory")]
+#[global_allocator]
+static ALLOCATOR: rust_road_router::report::memory::CountingAllocator = rust_road_router::report::memory::CountingAllocator;
+
 #[cfg(test)]
 mod tests {
     #[test]
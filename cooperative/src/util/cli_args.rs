@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::error::Error;
 use std::str::FromStr;
 
@@ -23,3 +24,37 @@ pub fn parse_arg_required<T: FromStr>(args: &mut impl Iterator<Item = String>, f
 pub fn parse_arg_optional<T: FromStr + Clone>(args: &mut impl Iterator<Item = String>, default: T) -> T {
     args.next().map(|s| T::from_str(&s).unwrap_or(default.clone())).unwrap_or(default)
 }
+
+/// Collect the remaining arguments as named options of the form `name=value`, in any order.
+/// Options not contained in `names` and repeated options are rejected.
+pub fn parse_named_args(args: impl Iterator<Item = String>, names: &[&str]) -> Result<HashMap<String, String>, Box<dyn Error>> {
+    let mut options = HashMap::new();
+
+    for arg in args {
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) if names.contains(&name) => (name.to_string(), value.to_string()),
+            _ => {
+                println!("Unknown option `{}`, expected `<name>=<value>` with a name of {:?}", arg, names);
+                return Err(Box::new(CliErr("Invalid argument!")));
+            }
+        };
+
+        if options.insert(name, value).is_some() {
+            println!("Option `{}` is given more than once", arg);
+            return Err(Box::new(CliErr("Invalid argument!")));
+        }
+    }
+
+    Ok(options)
+}
+
+/// Value of a named option (see `parse_named_args`), `default` if it isn't given
+pub fn parse_named_arg<T: FromStr>(options: &HashMap<String, String>, name: &str, default: T) -> Result<T, Box<dyn Error>> {
+    match options.get(name) {
+        Some(value) => T::from_str(value).map_err(|_| {
+            println!("Invalid argument type for `{}`", name);
+            Box::new(CliErr("Invalid argument!")) as Box<dyn Error>
+        }),
+        None => Ok(default),
+    }
+}
@@ -1,3 +1,5 @@
 pub mod cli_args;
 pub mod profile_search;
+pub mod projection;
 pub mod query_path_visualization;
+pub mod ttf_plot;
@@ -0,0 +1,147 @@
+//! Coordinate reference systems of graph coordinates and conversions to WGS84 longitude/latitude.
+//!
+//! Projected coordinates are supported for UTM zones (WGS84 ellipsoid), the conversions follow
+//! Snyder, "Map Projections - A Working Manual" (1987), which is accurate to the millimeter within a zone.
+
+use std::error::Error;
+
+const SEMI_MAJOR_AXIS: f64 = 6_378_137.0;
+const FLATTENING: f64 = 1.0 / 298.257_223_563;
+const SCALE_FACTOR: f64 = 0.9996;
+const FALSE_EASTING: f64 = 500_000.0;
+const FALSE_NORTHING_SOUTH: f64 = 10_000_000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CoordinateSystem {
+    /// longitude/latitude in degrees
+    #[default]
+    Wgs84,
+    /// easting/northing in meters
+    Utm { zone: u8, north: bool },
+}
+
+impl CoordinateSystem {
+    /// Parse a coordinate system, e.g. `EPSG:4326`, `EPSG:32632` (WGS84/UTM), `EPSG:25832` (ETRS89/UTM) or `UTM 32N`.
+    pub fn parse(value: &str) -> Result<Self, Box<dyn Error>> {
+        let value = value.trim().to_uppercase();
+        let invalid = || format!("unsupported coordinate system `{}`", value);
+
+        if let Some(code) = value.strip_prefix("EPSG:") {
+            let code = code.trim().parse::<u32>().map_err(|_| invalid())?;
+            return match code {
+                4326 => Ok(CoordinateSystem::Wgs84),
+                32601..=32660 => Ok(CoordinateSystem::Utm {
+                    zone: (code - 32600) as u8,
+                    north: true,
+                }),
+                32701..=32760 => Ok(CoordinateSystem::Utm {
+                    zone: (code - 32700) as u8,
+                    north: false,
+                }),
+                // ETRS89 deviates from WGS84 by less than a meter
+                25828..=25838 => Ok(CoordinateSystem::Utm {
+                    zone: (code - 25800) as u8,
+                    north: true,
+                }),
+                _ => Err(invalid().into()),
+            };
+        }
+
+        if let Some(zone) = value.strip_prefix("UTM") {
+            let zone = zone.trim();
+            let north = match zone.chars().last() {
+                Some('N') => true,
+                Some('S') => false,
+                _ => return Err(invalid().into()),
+            };
+            let zone = zone[..zone.len() - 1].trim().parse::<u8>().map_err(|_| invalid())?;
+            if !(1..=60).contains(&zone) {
+                return Err(invalid().into());
+            }
+            return Ok(CoordinateSystem::Utm { zone, north });
+        }
+
+        Err(invalid().into())
+    }
+
+    /// convert `(x, y)` of this coordinate system into `(longitude, latitude)`
+    pub fn to_wgs84(&self, x: f64, y: f64) -> (f64, f64) {
+        match *self {
+            CoordinateSystem::Wgs84 => (x, y),
+            CoordinateSystem::Utm { zone, north } => utm_to_wgs84(zone, north, x, y),
+        }
+    }
+
+    /// convert `(longitude, latitude)` into `(x, y)` of this coordinate system
+    pub fn from_wgs84(&self, lon: f64, lat: f64) -> (f64, f64) {
+        match *self {
+            CoordinateSystem::Wgs84 => (lon, lat),
+            CoordinateSystem::Utm { zone, north } => wgs84_to_utm(zone, north, lon, lat),
+        }
+    }
+}
+
+fn central_meridian(zone: u8) -> f64 {
+    ((zone as f64 - 1.0) * 6.0 - 180.0 + 3.0).to_radians()
+}
+
+fn meridian_arc(e2: f64, lat: f64) -> f64 {
+    let (e4, e6) = (e2 * e2, e2 * e2 * e2);
+    SEMI_MAJOR_AXIS
+        * ((1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0) * lat - (3.0 * e2 / 8.0 + 3.0 * e4 / 32.0 + 45.0 * e6 / 1024.0) * (2.0 * lat).sin()
+            + (15.0 * e4 / 256.0 + 45.0 * e6 / 1024.0) * (4.0 * lat).sin()
+            - (35.0 * e6 / 3072.0) * (6.0 * lat).sin())
+}
+
+fn wgs84_to_utm(zone: u8, north: bool, lon: f64, lat: f64) -> (f64, f64) {
+    let e2 = FLATTENING * (2.0 - FLATTENING);
+    let ep2 = e2 / (1.0 - e2);
+    let (lat, lon) = (lat.to_radians(), lon.to_radians());
+
+    let n = SEMI_MAJOR_AXIS / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+    let t = lat.tan().powi(2);
+    let c = ep2 * lat.cos().powi(2);
+    let a = lat.cos() * (lon - central_meridian(zone));
+
+    let x = SCALE_FACTOR * n * (a + (1.0 - t + c) * a.powi(3) / 6.0 + (5.0 - 18.0 * t + t * t + 72.0 * c - 58.0 * ep2) * a.powi(5) / 120.0) + FALSE_EASTING;
+    let y = SCALE_FACTOR
+        * (meridian_arc(e2, lat)
+            + n * lat.tan()
+                * (a * a / 2.0
+                    + (5.0 - t + 9.0 * c + 4.0 * c * c) * a.powi(4) / 24.0
+                    + (61.0 - 58.0 * t + t * t + 600.0 * c - 330.0 * ep2) * a.powi(6) / 720.0));
+
+    (x, if north { y } else { y + FALSE_NORTHING_SOUTH })
+}
+
+fn utm_to_wgs84(zone: u8, north: bool, x: f64, y: f64) -> (f64, f64) {
+    let e2 = FLATTENING * (2.0 - FLATTENING);
+    let ep2 = e2 / (1.0 - e2);
+    let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+    let x = x - FALSE_EASTING;
+    let y = if north { y } else { y - FALSE_NORTHING_SOUTH };
+
+    // footprint latitude
+    let mu = y / SCALE_FACTOR / (SEMI_MAJOR_AXIS * (1.0 - e2 / 4.0 - 3.0 * e2 * e2 / 64.0 - 5.0 * e2.powi(3) / 256.0));
+    let lat1 = mu
+        + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+        + (21.0 * e1 * e1 / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+        + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+        + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+
+    let n1 = SEMI_MAJOR_AXIS / (1.0 - e2 * lat1.sin().powi(2)).sqrt();
+    let t1 = lat1.tan().powi(2);
+    let c1 = ep2 * lat1.cos().powi(2);
+    let r1 = SEMI_MAJOR_AXIS * (1.0 - e2) / (1.0 - e2 * lat1.sin().powi(2)).powf(1.5);
+    let d = x / (n1 * SCALE_FACTOR);
+
+    let lat = lat1
+        - (n1 * lat1.tan() / r1)
+            * (d * d / 2.0 - (5.0 + 3.0 * t1 + 10.0 * c1 - 4.0 * c1 * c1 - 9.0 * ep2) * d.powi(4) / 24.0
+                + (61.0 + 90.0 * t1 + 298.0 * c1 + 45.0 * t1 * t1 - 252.0 * ep2 - 3.0 * c1 * c1) * d.powi(6) / 720.0);
+    let lon = central_meridian(zone)
+        + (d - (1.0 + 2.0 * t1 + c1) * d.powi(3) / 6.0 + (5.0 - 2.0 * c1 + 28.0 * t1 - 3.0 * c1 * c1 + 8.0 * ep2 + 24.0 * t1 * t1) * d.powi(5) / 120.0)
+            / lat1.cos();
+
+    (lon.to_degrees(), lat.to_degrees())
+}
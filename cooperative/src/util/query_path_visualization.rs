@@ -7,4 +7,4 @@ pub fn print_path_coords(path: &[NodeId], lat: &[f32], lon: &[f32]) {
         print!("[{},{}],", lat[node], lon[node])
     });
     println!("],");
-}
\ No newline at end of file
+}
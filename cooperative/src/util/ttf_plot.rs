@@ -0,0 +1,192 @@
+//! Render the travel time functions of single edges as SVG, e.g. to inspect why a potential is loose on a corridor.
+//!
+//! Each plot contains the current (bucket-based) travel time function, the historic profile (if any),
+//! the free-flow travel time and optionally the customized interval minima of the edge.
+
+use crate::graph::capacity_graph::CapacityGraph;
+use crate::graph::edge_buckets::SpeedBuckets;
+use crate::graph::MAX_BUCKETS;
+use conversion::speed_profile_to_tt_profile;
+use rust_road_router::datastr::graph::time_dependent::Timestamp;
+use rust_road_router::datastr::graph::{EdgeId, Weight, INFINITY};
+use std::fmt::Write;
+use std::path::Path;
+
+const WIDTH: f64 = 960.0;
+const HEIGHT: f64 = 480.0;
+const MARGIN: f64 = 60.0;
+
+/// Plot of an edge, see `edge_ttf_plot`
+pub struct TTFPlot {
+    pub edge_id: EdgeId,
+    pub travel_time: Vec<(Timestamp, Weight)>,
+    pub historic_travel_time: Option<Vec<(Timestamp, Weight)>>,
+    pub free_flow_time: Weight,
+    pub interval_minima: Option<Vec<Weight>>,
+}
+
+/// Collect the travel time functions of `edge_id`. `interval_minima` are given for equally sized intervals over the day,
+/// see `CustomizedCorridorLowerbound::orig_arc_interval_minima`.
+pub fn edge_ttf_plot(graph: &CapacityGraph, edge_id: EdgeId, interval_minima: Option<Vec<Weight>>) -> TTFPlot {
+    let edge = edge_id as usize;
+    let travel_time = graph.departure()[edge].iter().cloned().zip(graph.travel_time()[edge].iter().cloned()).collect();
+    let historic_travel_time = match graph.historic_speeds().map(|speeds| &speeds[edge]) {
+        Some(SpeedBuckets::Used(speeds)) if graph.distance()[edge] > 0 => Some(speed_profile_to_tt_profile(speeds, graph.distance()[edge])),
+        _ => None,
+    };
+
+    TTFPlot {
+        edge_id,
+        travel_time,
+        historic_travel_time,
+        free_flow_time: graph.free_flow_time()[edge],
+        interval_minima,
+    }
+}
+
+impl TTFPlot {
+    pub fn to_svg(&self) -> String {
+        let max_tt = self
+            .travel_time
+            .iter()
+            .chain(self.historic_travel_time.iter().flatten())
+            .map(|&(_, tt)| tt)
+            .chain(self.interval_minima.iter().flatten().cloned())
+            .chain(std::iter::once(self.free_flow_time))
+            .filter(|&tt| tt < INFINITY)
+            .max()
+            .unwrap_or(1)
+            .max(1);
+        // leave some headroom above the maximum
+        let y_max = max_tt as f64 * 1.1;
+
+        let x = |ts: Timestamp| MARGIN + ts as f64 / MAX_BUCKETS as f64 * (WIDTH - 2.0 * MARGIN);
+        let y = |tt: Weight| HEIGHT - MARGIN - tt.min(max_tt) as f64 / y_max * (HEIGHT - 2.0 * MARGIN);
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="sans-serif" font-size="12">"#,
+            WIDTH, HEIGHT
+        )
+        .unwrap();
+        writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+        writeln!(svg, r#"<text x="{}" y="20">edge {}</text>"#, MARGIN, self.edge_id).unwrap();
+
+        // axes with hourly ticks (labelled every 3 hours) and travel times in seconds
+        writeln!(
+            svg,
+            r#"<path d="M{:.1},{:.1} V{:.1} H{:.1}" stroke="black" fill="none"/>"#,
+            MARGIN,
+            MARGIN,
+            HEIGHT - MARGIN,
+            WIDTH - MARGIN
+        )
+        .unwrap();
+        for hour in 0..=24 {
+            let tick = x(hour * 3_600_000);
+            writeln!(
+                svg,
+                r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="black"/>"#,
+                tick,
+                HEIGHT - MARGIN,
+                tick,
+                HEIGHT - MARGIN + 4.0
+            )
+            .unwrap();
+            if hour % 3 == 0 {
+                writeln!(
+                    svg,
+                    r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}:00</text>"#,
+                    tick,
+                    HEIGHT - MARGIN + 18.0,
+                    hour
+                )
+                .unwrap();
+            }
+        }
+        for step in 0..=4 {
+            let tt = (max_tt as f64 * step as f64 / 4.0) as Weight;
+            writeln!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" text-anchor="end">{:.1}s</text>"#,
+                MARGIN - 6.0,
+                y(tt) + 4.0,
+                tt as f64 / 1000.0
+            )
+            .unwrap();
+        }
+
+        // free-flow time, interval minima, historic and current travel times
+        if self.free_flow_time < INFINITY {
+            let ff = y(self.free_flow_time);
+            writeln!(
+                svg,
+                r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="gray" stroke-dasharray="2,4"/>"#,
+                x(0),
+                ff,
+                x(MAX_BUCKETS),
+                ff
+            )
+            .unwrap();
+        }
+        if let Some(minima) = &self.interval_minima {
+            let interval_length = MAX_BUCKETS / minima.len().max(1) as u32;
+            let steps = minima
+                .iter()
+                .enumerate()
+                .flat_map(|(idx, &val)| vec![(idx as u32 * interval_length, val), ((idx as u32 + 1) * interval_length, val)])
+                .collect::<Vec<(Timestamp, Weight)>>();
+            write_polyline(&mut svg, &steps, &x, &y, "green", "");
+        }
+        if let Some(historic) = &self.historic_travel_time {
+            write_polyline(&mut svg, historic, &x, &y, "orange", r#" stroke-dasharray="6,3""#);
+        }
+        write_polyline(&mut svg, &self.travel_time, &x, &y, "blue", "");
+
+        // legend
+        let legend = [
+            ("blue", "current ttf"),
+            ("orange", "historic ttf"),
+            ("green", "interval minima"),
+            ("gray", "free-flow"),
+        ];
+        for (idx, (color, label)) in legend.iter().enumerate() {
+            let lx = WIDTH - MARGIN - 130.0;
+            let ly = MARGIN + 16.0 * idx as f64;
+            writeln!(
+                svg,
+                r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}" stroke-width="2"/>"#,
+                lx,
+                ly,
+                lx + 20.0,
+                ly,
+                color
+            )
+            .unwrap();
+            writeln!(svg, r#"<text x="{:.1}" y="{:.1}">{}</text>"#, lx + 26.0, ly + 4.0, label).unwrap();
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn store(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_svg())
+    }
+}
+
+fn write_polyline(svg: &mut String, points: &[(Timestamp, Weight)], x: &impl Fn(Timestamp) -> f64, y: &impl Fn(Weight) -> f64, color: &str, style: &str) {
+    let points = points
+        .iter()
+        .filter(|&&(_, tt)| tt < INFINITY)
+        .map(|&(ts, tt)| format!("{:.1},{:.1}", x(ts.min(MAX_BUCKETS)), y(tt)))
+        .collect::<Vec<String>>()
+        .join(" ");
+    writeln!(
+        svg,
+        r#"<polyline points="{}" stroke="{}" stroke-width="1.5" fill="none"{}/>"#,
+        points, color, style
+    )
+    .unwrap();
+}
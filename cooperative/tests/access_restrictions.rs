@@ -0,0 +1,89 @@
+use cooperative::dijkstra::potentials::multi_metric_potential::customization::CustomizedMultiMetrics;
+use cooperative::dijkstra::server::{CapacityServer, CapacityServerOps};
+use cooperative::graph::access_restrictions::AccessRestrictions;
+use cooperative::graph::capacity_graph::CapacityGraph;
+use cooperative::graph::MAX_BUCKETS;
+use cooperative::io::io_restrictions::load_restriction_zones;
+use rust_road_router::algo::a_star::ZeroPotential;
+use rust_road_router::algo::customizable_contraction_hierarchy::CCH;
+use rust_road_router::algo::TDQuery;
+use rust_road_router::datastr::node_order::NodeOrder;
+use utils::{create_graph, temp_path, triangle_edges};
+
+mod utils;
+
+/// restrictions of a zone around node 1 for vehicles of class 1 between 7:00 and 9:00
+fn restrictions_around_node_1(graph: &CapacityGraph, file_name: &str) -> AccessRestrictions {
+    let path = temp_path(file_name);
+    std::fs::write(
+        &path,
+        r#"{ "type": "FeatureCollection", "features": [{
+            "type": "Feature",
+            "geometry": { "type": "Polygon", "coordinates": [[[0.5, 0.5], [1.5, 0.5], [1.5, 1.5], [0.5, 1.5], [0.5, 0.5]]] },
+            "properties": { "vehicle_classes": [1], "time_windows": [["07:00", "09:00"]] }
+        }] }"#,
+    )
+    .unwrap();
+    let zones = load_restriction_zones(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    AccessRestrictions::new(zones, graph, &[0.0, 1.0, 2.0], &[0.0, 1.0, 2.0])
+}
+
+#[test]
+fn restriction_zones_exclude_edges_by_class_and_time() {
+    // 0 -> 1 -> 2 takes 2s, the direct edge 0 -> 2 takes 10s, node 1 lies inside the zone
+    let graph = create_graph(1, triangle_edges([10, 50, 10], [1000, 5000, 1000], [1000, 1000, 1000]));
+    let restrictions = restrictions_around_node_1(&graph, "restriction_zones.json");
+    assert_eq!(restrictions.num_restricted_edges(), 1);
+
+    let mut server = CapacityServer::new(graph, ZeroPotential());
+    server.set_access_restrictions(Some(restrictions));
+    let mut distance = |class, departure| {
+        server.set_vehicle_class(class);
+        server.query(&TDQuery { from: 0, to: 2, departure }, false).map(|result| result.distance)
+    };
+
+    assert_eq!(distance(0, 8 * 3_600_000), Some(2000));
+    assert_eq!(distance(1, 8 * 3_600_000), Some(5000));
+    assert_eq!(distance(1, 10 * 3_600_000), Some(2000));
+
+    // unreachable targets due to restrictions are no potential errors
+    server.set_vehicle_class(1);
+    assert!(server
+        .query(
+            &TDQuery {
+                from: 0,
+                to: 1,
+                departure: 8 * 3_600_000
+            },
+            false
+        )
+        .is_none());
+    assert!(server.result_valid());
+}
+
+#[test]
+fn restricted_detours_may_exceed_the_upper_bound_of_the_potential() {
+    // the potential is customized without restrictions, its upper bound from 0 to 2 is the 2s via node 1
+    let graph = create_graph(1, triangle_edges([10, 50, 10], [1000, 5000, 1000], [1000, 1000, 1000]));
+    let restrictions = restrictions_around_node_1(&graph, "restriction_zones_potential.json");
+    let cch = CCH::fix_order_and_build(&graph, NodeOrder::from_node_order(vec![0, 1, 2]));
+    let customized = CustomizedMultiMetrics::new_from_capacity(cch, &graph, &vec![(0, MAX_BUCKETS / 2), (MAX_BUCKETS / 2, MAX_BUCKETS)], 4);
+
+    let mut server = CapacityServer::new(graph, customized);
+    server.set_access_restrictions(Some(restrictions));
+    server.set_vehicle_class(1);
+
+    // the 10s detour via the direct edge is no potential error, although no bound was violated
+    let result = server.query(
+        &TDQuery {
+            from: 0,
+            to: 2,
+            departure: 8 * 3_600_000,
+        },
+        false,
+    );
+    assert_eq!(result.map(|result| result.distance), Some(5000));
+    assert!(server.result_valid());
+}
@@ -0,0 +1,31 @@
+use cooperative::dijkstra::potentials::cch_lower_upper::customization::CustomizedLowerUpper;
+use cooperative::dijkstra::potentials::cch_lower_upper::potential::CCHLowerUpperPotential;
+use cooperative::dijkstra::potentials::{ReversedPotential, TDPotential};
+use rust_road_router::algo::customizable_contraction_hierarchy::CCH;
+use rust_road_router::datastr::graph::FirstOutGraph;
+use rust_road_router::datastr::node_order::NodeOrder;
+
+#[test]
+fn backward_potentials_estimate_the_distance_from_the_source() {
+    // 0 -> 1 -> 2 -> 3 with a slow direct edge 0 -> 2
+    let graph = FirstOutGraph::new(vec![0, 2, 3, 4, 4], vec![1, 2, 2, 3], vec![10, 50, 10, 5]);
+    let cch = CCH::fix_order_and_build(&graph, NodeOrder::from_node_order(vec![0, 3, 1, 2]));
+    let customized = CustomizedLowerUpper::new(&cch, &vec![vec![10, 12], vec![50, 50], vec![10, 15], vec![5, 5]]);
+
+    let mut forward = CCHLowerUpperPotential::new_forward(&customized.cch, customized.upward.clone(), customized.downward.clone());
+    TDPotential::init(&mut forward, 0, 3, 0);
+    assert_eq!(forward.potential_bounds(0), Some((25, 32)));
+    assert_eq!(forward.potential_bounds(2), Some((5, 5)));
+
+    // a reverse search from 3 towards 0, the potentials are distances from 0
+    let mut backward = ReversedPotential(CCHLowerUpperPotential::new_backward(
+        &customized.cch,
+        customized.upward.clone(),
+        customized.downward.clone(),
+    ));
+    backward.init(0, 3, 100);
+    assert_eq!(backward.potential(3, 100), Some(25));
+    assert_eq!(backward.0.potential_bounds(2), Some((20, 27)));
+    assert_eq!(backward.0.potential_bounds(1), Some((10, 12)));
+    assert_eq!(backward.potential(0, 100), Some(0));
+}
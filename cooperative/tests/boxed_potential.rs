@@ -0,0 +1,25 @@
+use cooperative::dijkstra::potentials::TDPotential;
+use cooperative::dijkstra::server::{CapacityServer, CapacityServerOps};
+use rust_road_router::algo::a_star::ZeroPotential;
+use rust_road_router::algo::TDQuery;
+use utils::{create_graph, triangle_edges};
+
+mod utils;
+
+#[test]
+fn boxed_potential_matches_the_static_server() {
+    // 0 -> 1 -> 2 with 1 minute per edge and a direct edge 0 -> 2 with 3 minutes
+    let graph = || create_graph(1, triangle_edges([1000, 1000, 3000], [60_000, 60_000, 180_000], [1000, 1000, 1000]));
+    let query = TDQuery { from: 0, to: 2, departure: 0 };
+
+    let mut static_server = CapacityServer::new(graph(), ZeroPotential());
+    let potential: Box<dyn TDPotential> = Box::new(ZeroPotential());
+    let mut boxed_server = CapacityServer::new(graph(), potential);
+
+    for _ in 0..3 {
+        let expected = static_server.query(&query, true).unwrap();
+        let result = boxed_server.query(&query, true).unwrap();
+        assert_eq!(result.distance, expected.distance);
+        assert_eq!(result.path.edge_path, expected.path.edge_path);
+    }
+}
@@ -0,0 +1,55 @@
+use cooperative::graph::{MAX_BUCKETS, PCE_SCALE};
+use cooperative::io::io_bucket_loads::{apply_bucket_loads, store_bucket_loads, store_bucket_loads_csv};
+use utils::{congestible_triangle_graph, temp_path};
+
+mod utils;
+
+#[test]
+fn bucket_loads_can_be_transferred_to_another_graph() {
+    let mut graph = congestible_triangle_graph();
+    graph.increase_weights(&[0, 2], &[1000, 40000], 20 * PCE_SCALE);
+    graph.increase_weights(&[1], &[MAX_BUCKETS / 2], 5 * PCE_SCALE);
+
+    let directory = temp_path("bucket_loads");
+    std::fs::create_dir_all(&directory).unwrap();
+    let csv_path = directory.join("loads.csv");
+    store_bucket_loads(&directory, &graph).unwrap();
+    store_bucket_loads_csv(&csv_path, &graph).unwrap();
+
+    let mut from_binary = congestible_triangle_graph();
+    let mut from_csv = congestible_triangle_graph();
+    // already existing loads are replaced
+    from_csv.increase_weights(&[1], &[0], 50 * PCE_SCALE);
+    apply_bucket_loads(&directory, &mut from_binary).unwrap();
+    let updates = apply_bucket_loads(&csv_path, &mut from_csv).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    for restored in [&from_binary, &from_csv] {
+        assert_eq!(restored.export_bucket_loads(), graph.export_bucket_loads());
+        assert_eq!(restored.departure(), graph.departure());
+        assert_eq!(restored.travel_time(), graph.travel_time());
+    }
+    assert_eq!(updates.len(), 3);
+}
+
+#[test]
+fn invalid_bucket_loads_are_rejected() {
+    let directory = temp_path("invalid_bucket_loads");
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let mut graph = congestible_triangle_graph();
+    let path = directory.join("out_of_range.csv");
+    std::fs::write(&path, "edge_id,bucket,load\n0,4,10\n").unwrap();
+    assert!(apply_bucket_loads(&path, &mut graph).is_err());
+
+    let path = directory.join("duplicate.csv");
+    std::fs::write(&path, "edge_id,bucket,load\n0,1,10\n0,1,20\n").unwrap();
+    assert!(apply_bucket_loads(&path, &mut graph).is_err());
+
+    let path = directory.join("valid.csv");
+    std::fs::write(&path, "edge_id, bucket, load\n0, 1, 10\n").unwrap();
+    apply_bucket_loads(&path, &mut graph).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(graph.export_bucket_loads(), vec![vec![(MAX_BUCKETS / 4, 10)], vec![], vec![]]);
+}
@@ -0,0 +1,42 @@
+use cooperative::dijkstra::cached_server::CachedCapacityServer;
+use cooperative::dijkstra::server::{CapacityServer, CapacityServerOps};
+use rust_road_router::algo::a_star::ZeroPotential;
+use rust_road_router::algo::TDQuery;
+use utils::{create_graph_with_traffic_function, single_vehicle_traffic_function, triangle_edges};
+
+mod utils;
+
+#[test]
+fn cached_paths_are_reused_until_other_queries_modify_them() {
+    // 0 -> 1 -> 2 takes 72s, the direct edge 0 -> 2 takes 121.2s, each vehicle adds the free-flow time to an edge
+    let graph = create_graph_with_traffic_function(
+        1,
+        triangle_edges([1000, 3333, 1000], [36000, 120_000, 36000], [50, 50, 50]),
+        single_vehicle_traffic_function(1),
+    );
+    let mut server = CachedCapacityServer::new(CapacityServer::new(graph, ZeroPotential()), 10);
+    let to_2 = TDQuery { from: 0, to: 2, departure: 0 };
+
+    let first = server.query(&to_2, true).unwrap();
+    assert_eq!(first.path.edge_path, vec![0, 2]);
+
+    // the own load of the cached path doesn't evict it, its travel time is re-evaluated
+    let second = server.query(&to_2, true).unwrap();
+    assert_eq!(second.path.edge_path, vec![0, 2]);
+    assert!(second.distance > first.distance);
+    assert_eq!(server.cache_statistics(), (1, 1));
+
+    // another query loads the cached path, so the entry is evicted and the direct edge is faster now
+    server.query(&TDQuery { from: 0, to: 1, departure: 0 }, true).unwrap();
+    let third = server.query(&to_2, false).unwrap();
+    assert_eq!(third.path.edge_path, vec![1]);
+    assert_eq!(server.cache_statistics(), (1, 3));
+
+    assert_eq!(server.query(&to_2, false).unwrap().path.edge_path, vec![1]);
+    assert_eq!(server.cache_statistics(), (2, 3));
+
+    // the server may be modified directly, so the cache is cleared
+    server.borrow_server_mut();
+    server.query(&to_2, false).unwrap();
+    assert_eq!(server.cache_statistics(), (2, 4));
+}
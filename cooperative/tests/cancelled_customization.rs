@@ -0,0 +1,62 @@
+use cooperative::dijkstra::potentials::cch_parallelization_util::{CancellationToken, CustomizationCancelled};
+use cooperative::dijkstra::potentials::multi_metric_potential::customization::CustomizedMultiMetrics;
+use cooperative::dijkstra::server::{CapacityServer, CapacityServerOps};
+use cooperative::graph::{MAX_BUCKETS, PCE_SCALE};
+use rust_road_router::algo::customizable_contraction_hierarchy::CCH;
+use rust_road_router::algo::TDQuery;
+use rust_road_router::datastr::node_order::NodeOrder;
+use utils::congestible_triangle_graph;
+
+mod utils;
+
+#[test]
+fn cancelled_customization_keeps_the_previous_weights() {
+    let intervals = vec![(0, MAX_BUCKETS / 2), (MAX_BUCKETS / 2, MAX_BUCKETS)];
+    let empty_graph = congestible_triangle_graph();
+    let cch = CCH::fix_order_and_build(&empty_graph, NodeOrder::from_node_order(vec![0, 1, 2]));
+    let mut customized = CustomizedMultiMetrics::new_from_capacity(cch, &empty_graph, &intervals, 4);
+    let (num_metrics, upward, downward) = (customized.num_metrics, customized.upward.clone(), customized.downward.clone());
+    let (forward_bounds, backward_bounds) = (customized.forward_cch_bounds.clone(), customized.backward_cch_bounds.clone());
+
+    let mut graph = congestible_triangle_graph();
+    graph.increase_weights(&[0, 1, 2], &[0, 0, 0], 1000 * PCE_SCALE);
+
+    let cancellation = CancellationToken::new();
+    cancellation.cancel();
+    assert_eq!(
+        customized.customize_cancellable(&graph, &intervals, 4, &cancellation),
+        Err(CustomizationCancelled)
+    );
+    assert_eq!(customized.num_metrics, num_metrics);
+    assert_eq!((customized.upward, customized.downward), (upward, downward));
+    assert_eq!(customized.forward_cch_bounds, forward_bounds);
+    assert_eq!(customized.backward_cch_bounds, backward_bounds);
+}
+
+#[test]
+fn cancelled_customization_keeps_the_previous_potential() {
+    // the potential is customized on the empty graph, its upper bounds are exceeded on the congested graph
+    let intervals = vec![(0, MAX_BUCKETS / 2), (MAX_BUCKETS / 2, MAX_BUCKETS)];
+    let empty_graph = congestible_triangle_graph();
+    let cch = CCH::fix_order_and_build(&empty_graph, NodeOrder::from_node_order(vec![0, 1, 2]));
+    let customized = CustomizedMultiMetrics::new_from_capacity(cch, &empty_graph, &intervals, 4);
+
+    let mut graph = congestible_triangle_graph();
+    graph.increase_weights(&[0, 1, 2], &[0, 0, 0], 1000 * PCE_SCALE);
+    let mut server = CapacityServer::new(graph, customized);
+    let query = TDQuery { from: 0, to: 2, departure: 0 };
+    assert!(server.query(&query, false).is_none());
+    assert!(!server.result_valid());
+
+    // the cancelled customization neither replaces the potential nor resets its validity
+    let cancellation = CancellationToken::new();
+    cancellation.cancel();
+    assert_eq!(server.customize_cancellable(&intervals, 4, &cancellation), Err(CustomizationCancelled));
+    assert!(!server.result_valid());
+    assert!(server.query(&query, false).is_none());
+
+    // a customization with a fresh token covers the congested graph
+    assert_eq!(server.customize_cancellable(&intervals, 4, &CancellationToken::new()), Ok(()));
+    assert!(server.result_valid());
+    assert_eq!(server.query(&query, false).unwrap().distance, 240_000);
+}
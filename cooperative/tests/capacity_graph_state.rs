@@ -0,0 +1,45 @@
+use cooperative::graph::capacity_graph::CapacityGraph;
+use cooperative::graph::edge_buckets::SpeedBuckets;
+use cooperative::graph::speed_blend::SpeedBlend;
+use cooperative::graph::{MAX_BUCKETS, PCE_SCALE};
+use rust_road_router::io::{Deconstruct, Reconstruct};
+use utils::{congestible_triangle_graph, temp_path};
+
+mod utils;
+
+#[test]
+fn warmed_up_graph_state_survives_a_round_trip() {
+    let mut graph = congestible_triangle_graph();
+    graph.add_historic_speeds(
+        vec![
+            SpeedBuckets::Unused,
+            SpeedBuckets::Used(vec![(0, 80), (MAX_BUCKETS / 2, 40), (MAX_BUCKETS, 80)]),
+            SpeedBuckets::Unused,
+        ],
+        SpeedBlend::constant(0.5),
+    );
+    graph.set_edge_classes(vec![u32::MAX, 1, u32::MAX]);
+    graph.increase_weights(&[0, 2], &[1000, 40000], 20 * PCE_SCALE);
+
+    let directory = temp_path("capacity_graph_state");
+    std::fs::create_dir_all(&directory).unwrap();
+    graph.deconstruct_to(&directory).unwrap();
+    let mut restored = CapacityGraph::reconstruct_from(&directory).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(restored.num_buckets(), 4);
+    assert_eq!(restored.departure(), graph.departure());
+    assert_eq!(restored.travel_time(), graph.travel_time());
+    assert_eq!(restored.export_speeds(), graph.export_speeds());
+    assert_eq!(restored.export_learned_speeds(), graph.export_learned_speeds());
+    assert_eq!(restored.speed_blend(), graph.speed_blend());
+    assert_eq!(restored.edge_classes(), graph.edge_classes());
+    assert_eq!(restored.get_bucket_usage(), graph.get_bucket_usage());
+
+    // both graphs continue identically from the stored state
+    assert_eq!(
+        restored.increase_weights(&[0, 1], &[2000, 3000], PCE_SCALE),
+        graph.increase_weights(&[0, 1], &[2000, 3000], PCE_SCALE)
+    );
+    assert_eq!(restored.travel_time(), graph.travel_time());
+}
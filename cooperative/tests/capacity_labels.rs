@@ -0,0 +1,49 @@
+use cooperative::dijkstra::capacity_dijkstra_ops::{CapacityDijkstraOps, CapacityLabel, HopLabel};
+use cooperative::graph::capacity_graph::CapacityGraph;
+use rust_road_router::algo::dijkstra::{DijkstraData, DijkstraInit, DijkstraRun};
+use rust_road_router::datastr::graph::{EdgeIdT, NodeId, NodeIdT, Weight};
+use utils::{create_graph, CapacityEdge};
+
+mod utils;
+
+// runs a time-dependent search from node 0 until `target` is settled, returns its label and predecessor
+fn search<L: CapacityLabel>(graph: &CapacityGraph, target: NodeId, departure: Weight) -> (L, NodeId) {
+    let mut data = DijkstraData::<L, EdgeIdT, L>::new(5);
+    let mut ops = CapacityDijkstraOps::<L>::default();
+    let init = DijkstraInit {
+        source: NodeIdT(0),
+        initial_state: L::at_departure(departure),
+    };
+    let mut dijkstra = DijkstraRun::query(graph, &mut data, &mut ops, init);
+
+    while let Some(node) = dijkstra.next() {
+        if node == target {
+            return (dijkstra.tentative_distance(node).clone(), dijkstra.predecessor(node));
+        }
+    }
+    panic!("target {} is not reachable", target);
+}
+
+#[test]
+fn hop_labels_break_ties_in_the_arrival_time() {
+    // 0 -> 1 -> 3 -> 2 and 0 -> 4 -> 2 both take 2s, the path with three hops is found first
+    let graph = create_graph(
+        1,
+        vec![
+            CapacityEdge::new(0, 1, 10, 100, 1000),
+            CapacityEdge::new(1, 3, 10, 100, 1000),
+            CapacityEdge::new(3, 2, 10, 1800, 1000),
+            CapacityEdge::new(0, 4, 10, 1000, 1000),
+            CapacityEdge::new(4, 2, 10, 1000, 1000),
+        ],
+    );
+
+    // scalar labels keep the first path
+    let (arrival, predecessor) = search::<Weight>(&graph, 2, 5000);
+    assert_eq!((arrival, predecessor), (7000, 3));
+
+    // hop labels prefer the path with fewer edges
+    let (label, predecessor) = search::<HopLabel>(&graph, 2, 5000);
+    assert_eq!(label, HopLabel { arrival: 7000, hops: 2 });
+    assert_eq!(predecessor, 4);
+}
@@ -0,0 +1,50 @@
+use cooperative::io::io_coordinates::{load_coords, load_coords_f64};
+use cooperative::util::projection::CoordinateSystem;
+use rust_road_router::io::Store;
+use utils::temp_path;
+
+mod utils;
+
+#[test]
+fn utm_conversion() {
+    let utm = CoordinateSystem::parse("EPSG:32632").unwrap();
+    assert_eq!(utm, CoordinateSystem::Utm { zone: 32, north: true });
+    assert_eq!(CoordinateSystem::parse("utm 32n").unwrap(), utm);
+    assert!(CoordinateSystem::parse("EPSG:3857").is_err());
+
+    // the central meridian on the equator is the origin of the zone
+    let (x, y) = utm.from_wgs84(9.0, 0.0);
+    assert!((x - 500_000.0).abs() < 1e-6 && y.abs() < 1e-6);
+
+    // Karlsruhe palace, roughly E 456 1xx / N 5 429 xxx
+    let (x, y) = utm.from_wgs84(8.4044, 49.0134);
+    assert!((456_000.0..457_000.0).contains(&x) && (5_429_000.0..5_430_000.0).contains(&y));
+    let (lon, lat) = utm.to_wgs84(x, y);
+    assert!((lon - 8.4044).abs() < 1e-8 && (lat - 49.0134).abs() < 1e-8);
+
+    let south = CoordinateSystem::parse("EPSG:32756").unwrap();
+    let (x, y) = south.from_wgs84(151.2093, -33.8688);
+    let (lon, lat) = south.to_wgs84(x, y);
+    assert!((lon - 151.2093).abs() < 1e-8 && (lat + 33.8688).abs() < 1e-8);
+}
+
+#[test]
+fn load_projected_f64_coordinates() {
+    let directory = temp_path("coordinates");
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let utm = CoordinateSystem::Utm { zone: 32, north: true };
+    let (x, y) = utm.from_wgs84(8.4044, 49.0134);
+    vec![0u32, 0, 0].write_to(&directory.join("first_out")).unwrap();
+    vec![x, x + 1.0].write_to(&directory.join("longitude")).unwrap();
+    vec![y, y].write_to(&directory.join("latitude")).unwrap();
+    std::fs::write(directory.join("coordinate_system"), "EPSG:32632\n").unwrap();
+
+    let (lon, lat) = load_coords_f64(&directory).unwrap();
+    let (lon32, _) = load_coords(&directory).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert!((lon[0] - 8.4044).abs() < 1e-8 && (lat[0] - 49.0134).abs() < 1e-8);
+    assert!(lon[1] > lon[0]);
+    assert_eq!(lon32, lon.iter().map(|&lon| lon as f32).collect::<Vec<f32>>());
+}
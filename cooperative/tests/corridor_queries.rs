@@ -0,0 +1,74 @@
+use cooperative::dijkstra::potentials::cch_lower_upper::elimination_tree_server::CorridorEliminationTreeServer;
+use cooperative::dijkstra::potentials::multi_metric_potential::customization::CustomizedMultiMetrics;
+use cooperative::graph::MAX_BUCKETS;
+use rust_road_router::algo::customizable_contraction_hierarchy::{CCH, CCHT};
+use rust_road_router::datastr::graph::{UnweightedFirstOutGraph, Weight};
+use rust_road_router::datastr::node_order::NodeOrder;
+use rust_road_router::datastr::timestamped_vector::TimestampedVector;
+use utils::{create_graph, CapacityEdge};
+
+mod utils;
+
+#[test]
+fn multi_target_queries_match_single_target_queries() {
+    // cycle 0 -> 1 -> 2 -> 3 -> 0 with slow reverse edges and a chord 0 <-> 2, node 4 is not reachable
+    let edges = [
+        (0, 1, 60_000),
+        (0, 2, 200_000),
+        (1, 0, 120_000),
+        (1, 2, 60_000),
+        (2, 0, 240_000),
+        (2, 3, 60_000),
+        (3, 0, 60_000),
+        (3, 2, 90_000),
+        (4, 0, 60_000),
+    ];
+    let graph = create_graph(
+        1,
+        edges
+            .iter()
+            .map(|&(from, to, travel_time)| CapacityEdge::new(from, to, 1000, travel_time, 1000))
+            .collect(),
+    );
+    let cch = CCH::fix_order_and_build(&graph, NodeOrder::from_node_order(vec![1, 3, 4, 0, 2]));
+    let customized = CustomizedMultiMetrics::new_from_capacity(cch, &graph, &vec![(0, MAX_BUCKETS)], 4);
+
+    let cch = &customized.cch;
+    let forward_graph = UnweightedFirstOutGraph::new(cch.forward_first_out(), cch.forward_head());
+    let backward_graph = UnweightedFirstOutGraph::new(cch.backward_first_out(), cch.backward_head());
+    let mut fw_distances = TimestampedVector::<(Weight, Weight)>::new(5);
+    let mut bw_distances = TimestampedVector::<(Weight, Weight)>::new(5);
+    let targets = [0, 1, 2, 3, 4];
+
+    for from in 0..5 {
+        let expected = targets
+            .iter()
+            .map(|&to| {
+                CorridorEliminationTreeServer::query(
+                    cch,
+                    &forward_graph,
+                    &customized.forward_cch_bounds,
+                    &backward_graph,
+                    &customized.backward_cch_bounds,
+                    &mut fw_distances,
+                    &mut bw_distances,
+                    from,
+                    to,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let result = CorridorEliminationTreeServer::query_multi_target(
+            cch,
+            &forward_graph,
+            &customized.forward_cch_bounds,
+            &backward_graph,
+            &customized.backward_cch_bounds,
+            &mut fw_distances,
+            &mut bw_distances,
+            from,
+            &targets,
+        );
+        assert_eq!(result, expected, "bounds from {}", from);
+    }
+}
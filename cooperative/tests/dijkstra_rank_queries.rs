@@ -0,0 +1,27 @@
+use cooperative::experiments::queries::departure_distributions::{DepartureDistribution, UniformDeparture};
+use cooperative::experiments::queries::dijkstra_rank::{generate_dijkstra_rank_queries, rank_targets};
+use rust_road_router::algo::dijkstra::DijkstraData;
+use rust_road_router::datastr::graph::FirstOutGraph;
+
+#[test]
+fn rank_targets_are_the_exact_settled_nodes() {
+    // path 0 -> 1 -> ... -> 599, node `i` has rank `i - source`
+    let n = 600u32;
+    let graph = FirstOutGraph::new(
+        (0..n).chain(std::iter::once(n - 1)).collect::<Vec<_>>(),
+        (1..n).collect::<Vec<_>>(),
+        vec![1; n as usize - 1],
+    );
+    let mut data = DijkstraData::new(n as usize);
+
+    assert_eq!(rank_targets(&graph, &mut data, 10, 9), Some(vec![266, 522]));
+    assert_eq!(rank_targets(&graph, &mut data, 100, 9), None);
+
+    let (queries, ranks) = generate_dijkstra_rank_queries(&graph, 5, 9, UniformDeparture::new());
+    assert_eq!(ranks, vec![8, 8, 8, 8, 8, 9, 9, 9, 9, 9]);
+    for (query, &rank) in queries.iter().zip(ranks.iter()) {
+        assert_eq!(query.to - query.from, 1 << rank);
+    }
+    // the same sources are used for all rank classes
+    assert!((0..5).all(|idx| queries[idx].from == queries[idx + 5].from));
+}
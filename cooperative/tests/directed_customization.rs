@@ -0,0 +1,48 @@
+use cooperative::dijkstra::potentials::multi_metric_potential::customization::CustomizedMultiMetrics;
+use cooperative::graph::MAX_BUCKETS;
+use rust_road_router::algo::customizable_contraction_hierarchy::CCH;
+use rust_road_router::datastr::node_order::NodeOrder;
+use utils::{create_graph, CapacityEdge};
+
+mod utils;
+
+#[test]
+fn directed_customization_matches_the_undirected_one() {
+    // cycle 0 -> 1 -> 2 -> 3 -> 0 with slow reverse edges and a chord 0 <-> 2
+    let edges = [
+        (0, 1, 60_000),
+        (0, 2, 200_000),
+        (0, 3, 300_000),
+        (1, 0, 120_000),
+        (1, 2, 60_000),
+        (2, 0, 240_000),
+        (2, 1, 180_000),
+        (2, 3, 60_000),
+        (3, 0, 60_000),
+        (3, 2, 90_000),
+    ];
+    let graph = create_graph(
+        1,
+        edges
+            .iter()
+            .map(|&(from, to, travel_time)| CapacityEdge::new(from, to, 1000, travel_time, 1000))
+            .collect(),
+    );
+    let order = NodeOrder::from_node_order(vec![1, 3, 0, 2]);
+    let intervals = vec![(0, MAX_BUCKETS / 2), (MAX_BUCKETS / 2, MAX_BUCKETS)];
+
+    // the undirected customization, converted afterwards
+    let expected = CustomizedMultiMetrics::new_from_capacity(CCH::fix_order_and_build(&graph, order.clone()), &graph, &intervals, 4).into_directed();
+
+    // the directed customization on the same directed cch
+    let directed_cch = CustomizedMultiMetrics::new_from_capacity(CCH::fix_order_and_build(&graph, order), &graph, &intervals, 4)
+        .into_directed()
+        .cch;
+    let directed = CustomizedMultiMetrics::new_from_capacity_directed(directed_cch, &graph, &intervals, 4);
+
+    assert_eq!(directed.num_metrics, expected.num_metrics);
+    assert_eq!(directed.upward, expected.upward);
+    assert_eq!(directed.downward, expected.downward);
+    assert_eq!(directed.forward_cch_bounds, expected.forward_cch_bounds);
+    assert_eq!(directed.backward_cch_bounds, expected.backward_cch_bounds);
+}
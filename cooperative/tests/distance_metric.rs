@@ -0,0 +1,65 @@
+use cooperative::dijkstra::distance_metric::{CustomizedObjectives, Objective};
+use cooperative::dijkstra::server::{CapacityServer, CapacityServerOps};
+use rust_road_router::algo::a_star::ZeroPotential;
+use rust_road_router::algo::customizable_contraction_hierarchy::CCH;
+use rust_road_router::algo::TDQuery;
+use rust_road_router::datastr::node_order::NodeOrder;
+use utils::{create_graph_with_traffic_function, single_vehicle_traffic_function, triangle_edges};
+
+mod utils;
+
+#[test]
+fn queries_select_fastest_or_shortest_path() {
+    // the direct edge 0 -> 2 is a long motorway (2000m, 60s), the detour via 1 is short but slow (2 x 500m, 2 x 60s)
+    let graph = create_graph_with_traffic_function(
+        1,
+        triangle_edges([500, 2000, 500], [60_000, 60_000, 60_000], [50, 50, 50]),
+        single_vehicle_traffic_function(1),
+    );
+    let cch = CCH::fix_order_and_build(&graph, NodeOrder::from_node_order(vec![0, 1, 2]));
+    let mut distance = CustomizedObjectives::new(&cch, &graph, &[Objective::Shortest]);
+    assert_eq!(distance.static_path(&graph, Objective::Shortest, 0, 2), Some((1000, vec![0, 2])));
+
+    let mut server = CapacityServer::new(graph, ZeroPotential());
+    let query = TDQuery { from: 0, to: 2, departure: 0 };
+
+    let fastest = distance.query(&mut server, &query, Objective::Fastest, false).unwrap();
+    assert_eq!((fastest.distance, fastest.path.edge_path), (60_000, vec![1]));
+
+    let shortest = distance.query(&mut server, &query, Objective::Shortest, true).unwrap();
+    assert_eq!((shortest.distance, shortest.path.node_path), (120_000, vec![0, 1, 2]));
+    assert_eq!(shortest.path.departure, vec![0, 60_000, 120_000]);
+
+    // the shortest path was added to the graph, its vehicle doubles the travel time of both edges
+    assert_eq!(server.path_distance(&vec![0, 2], 0), 240_000);
+    assert_eq!(Objective::parse("shortest").unwrap(), Objective::Shortest);
+    assert!(Objective::parse("cheapest").is_err());
+}
+
+#[test]
+fn free_flow_objective_ignores_the_current_traffic() {
+    // the direct edge 0 -> 2 takes 30s, the detour via 1 takes 2 x 20s, each vehicle adds the free-flow time to an edge
+    let graph = create_graph_with_traffic_function(
+        1,
+        triangle_edges([500, 1000, 500], [20_000, 30_000, 20_000], [50, 50, 50]),
+        single_vehicle_traffic_function(1),
+    );
+    let cch = CCH::fix_order_and_build(&graph, NodeOrder::from_node_order(vec![0, 1, 2]));
+    let mut objectives = CustomizedObjectives::new(&cch, &graph, &[Objective::FreeFlow, Objective::Shortest]);
+    assert!(objectives.supports(Objective::Fastest) && objectives.supports(Objective::FreeFlow));
+
+    let mut server = CapacityServer::new(graph, ZeroPotential());
+    let query = TDQuery { from: 0, to: 2, departure: 0 };
+    for _ in 0..10 {
+        objectives.query(&mut server, &query, Objective::FreeFlow, true).unwrap();
+    }
+
+    // the free-flow path stays on the congested edge, its travel time is evaluated on the current traffic
+    let free_flow = objectives.query(&mut server, &query, Objective::FreeFlow, false).unwrap();
+    assert_eq!(free_flow.path.edge_path, vec![1]);
+    assert_eq!(free_flow.distance, 11 * 30_000);
+
+    let fastest = objectives.query(&mut server, &query, Objective::Fastest, false).unwrap();
+    assert_eq!((fastest.distance, fastest.path.edge_path), (40_000, vec![0, 2]));
+    assert_eq!(Objective::parse("free_flow").unwrap(), Objective::FreeFlow);
+}
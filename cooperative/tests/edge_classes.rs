@@ -0,0 +1,35 @@
+use cooperative::dijkstra::server::{CapacityServer, CapacityServerOps};
+use rust_road_router::algo::a_star::ZeroPotential;
+use rust_road_router::algo::TDQuery;
+use rust_road_router::datastr::graph::INFINITY;
+use utils::{create_graph, triangle_edges};
+
+mod utils;
+
+#[test]
+fn dedicated_lanes_are_only_used_by_their_classes() {
+    // 0 -> 1 -> 2 is a bus lane (class 1) taking 2s, the direct edge 0 -> 2 is open to all classes and takes 10s
+    let mut graph = create_graph(1, triangle_edges([10, 50, 10], [1000, 5000, 1000], [1000, 1000, 1000]));
+    graph.set_edge_classes(vec![1 << 1, u32::MAX, 1 << 1]);
+    assert!(graph.is_available(0, 1));
+    assert!(!graph.is_available(0, 0));
+
+    // class-specific travel times are infinite on unavailable edges
+    assert!(graph.class_travel_times(Some(0))[0].iter().all(|&tt| tt == INFINITY));
+    assert_eq!(graph.class_travel_times(Some(1))[0], graph.travel_time()[0]);
+    assert_eq!(graph.class_travel_times(None)[0], graph.travel_time()[0]);
+
+    let mut server = CapacityServer::new(graph, ZeroPotential());
+    let mut distance = |class| {
+        server.set_vehicle_class(class);
+        server.query(&TDQuery { from: 0, to: 2, departure: 0 }, false).map(|result| result.distance)
+    };
+
+    assert_eq!(distance(0), Some(5000));
+    assert_eq!(distance(1), Some(2000));
+
+    // targets only reachable via dedicated lanes are no potential errors
+    server.set_vehicle_class(0);
+    assert!(server.query(&TDQuery { from: 0, to: 1, departure: 0 }, false).is_none());
+    assert!(server.result_valid());
+}
@@ -0,0 +1,26 @@
+use cooperative::graph::traffic_functions::{EnergyModel, GradientSpeedModel};
+use utils::{create_graph, line_edges};
+
+mod utils;
+
+#[test]
+fn gradients_slow_down_uphill_edges() {
+    // 0 -> 1 uphill with 5%, 1 -> 0 downhill, both 1000m at 36 km/h
+    let mut graph = create_graph(1, line_edges(2, 1000, 100_000, 1000));
+    let flat_energy = graph.energy_consumption(&EnergyModel::default());
+    assert_eq!(flat_energy[0], flat_energy[1]);
+
+    graph.increase_weights(&[1], &[0], 1000);
+    let loaded_time = graph.travel_time()[1][0];
+
+    graph.set_elevation(&[100.0, 150.0], &GradientSpeedModel::default());
+    assert_eq!(graph.gradient(), Some(&vec![5.0, -5.0]));
+
+    // 10% slower uphill, moderate downhill slopes are not penalized
+    assert_eq!(graph.free_flow_speed(), &vec![32, 36]);
+    assert_eq!(graph.travel_time()[0], vec![112_500, 112_500]);
+    assert_eq!(graph.travel_time()[1][0], loaded_time);
+
+    let energy = graph.energy_consumption(&EnergyModel::default());
+    assert!(energy[0] > flat_energy[0] && energy[1] == 0);
+}
@@ -0,0 +1,52 @@
+use cooperative::dijkstra::fleet_assignment::{assign_fleet, assignment_costs, solve_assignment, FleetVehicle, TransportRequest};
+use cooperative::dijkstra::server::CapacityServer;
+use rust_road_router::algo::a_star::ZeroPotential;
+use rust_road_router::datastr::graph::INFINITY;
+use utils::{create_graph, line_edges};
+
+mod utils;
+
+#[test]
+fn hungarian_method_finds_minimum_cost_assignment() {
+    // greedy would assign row 0 to column 0 and pay 101
+    assert_eq!(solve_assignment(&vec![vec![1, 2], vec![1, 100]]), vec![Some(1), Some(0)]);
+
+    // rectangular matrices leave rows or columns unassigned, infinite costs are never assigned
+    assert_eq!(solve_assignment(&vec![vec![5, 1, 3]]), vec![Some(1)]);
+    assert_eq!(solve_assignment(&vec![vec![5], vec![1], vec![3]]), vec![None, Some(0), None]);
+    assert_eq!(solve_assignment(&vec![vec![INFINITY, 1], vec![INFINITY, 2]]), vec![Some(1), None]);
+    assert!(solve_assignment(&vec![]).is_empty());
+}
+
+#[test]
+fn fleet_assignment_routes_assigned_vehicles() {
+    // bidirectional line 0 - 1 - 2 - 3, each edge takes 1s
+    let graph = create_graph(1, line_edges(4, 10, 1000, 1000));
+    let mut server = CapacityServer::new(graph, ZeroPotential());
+
+    let vehicles = vec![FleetVehicle { node: 1, available: 0 }, FleetVehicle { node: 0, available: 0 }];
+    let requests = vec![
+        TransportRequest { from: 0, to: 3, departure: 0 },
+        TransportRequest {
+            from: 2,
+            to: 3,
+            departure: 500,
+        },
+    ];
+
+    // approach plus the waiting time of the customer
+    assert_eq!(assignment_costs(&mut server, &vehicles, &requests), vec![vec![2000, 1500], vec![0, 3500]]);
+
+    let assignments = assign_fleet(&mut server, &vehicles, &requests);
+    assert_eq!(assignments.len(), 2);
+    assert_eq!(
+        assignments
+            .iter()
+            .map(|assignment| (assignment.vehicle, assignment.request))
+            .collect::<Vec<_>>(),
+        vec![(1, 0), (0, 1)]
+    );
+    assert!(assignments[0].approach.path.edge_path.is_empty());
+    assert_eq!(assignments[0].trip.path.node_path, vec![0, 1, 2, 3]);
+    assert_eq!(*assignments[1].trip.path.departure.first().unwrap(), 1000);
+}
@@ -0,0 +1,25 @@
+use cooperative::experiments::queries::departure_distributions::{DepartureDistribution, UniformDeparture};
+use cooperative::experiments::queries::random_geometric::generate_random_td_geometric_queries;
+use utils::{create_graph, CapacityEdge};
+
+mod utils;
+
+#[test]
+fn td_geometric_queries_follow_the_travel_times() {
+    // path 0 -> 1 -> 2 -> 3 with one hour per edge and a closed edge 3 -> 0
+    let graph = create_graph(
+        1,
+        vec![
+            CapacityEdge::new(0, 1, 100_000, 3_600_000, 1000),
+            CapacityEdge::new(1, 2, 100_000, 3_600_000, 1000),
+            CapacityEdge::new(2, 3, 100_000, 3_600_000, 1000),
+            CapacityEdge::new(3, 0, 100_000, 3_600_000, 0),
+        ],
+    );
+
+    let queries = generate_random_td_geometric_queries(&graph, 50, UniformDeparture::new());
+    assert_eq!(queries.len(), 50);
+    assert!(queries.windows(2).all(|pair| pair[0].departure <= pair[1].departure));
+    // targets are reachable, node 3 is a dead end
+    assert!(queries.iter().all(|query| query.from < query.to));
+}
@@ -0,0 +1,52 @@
+use cooperative::graph::edge_buckets::SpeedBuckets;
+use cooperative::graph::speed_blend::SpeedBlend;
+use cooperative::graph::MAX_BUCKETS;
+use cooperative::io::io_historic_speeds::{resample_speeds, speed_records_to_profiles, SpeedRecord};
+use utils::{create_graph, line_edges};
+
+mod utils;
+
+#[test]
+fn resampling_preserves_travel_times() {
+    // downsampling: harmonic mean of 30 and 60 km/h is 40 km/h
+    assert_eq!(resample_speeds(&[30, 60, 50, 50], 2), vec![(0, 40), (MAX_BUCKETS / 2, 50), (MAX_BUCKETS, 40)]);
+
+    // upsampling: equal consecutive buckets are merged
+    assert_eq!(resample_speeds(&[30, 60], 4), vec![(0, 30), (MAX_BUCKETS / 2, 60), (MAX_BUCKETS, 30)]);
+}
+
+#[test]
+fn speed_records_are_validated() {
+    // two edges with 1 km, free-flow speed 100 km/h
+    let graph = create_graph(4, line_edges(2, 1000, 36000, 1000));
+    let record = |edge_id, bucket, speed| SpeedRecord { edge_id, bucket, speed };
+
+    let profiles = speed_records_to_profiles(&[record(1, 0, 50)], &graph, 2).unwrap();
+    assert!(!profiles[0].is_used());
+    match &profiles[1] {
+        SpeedBuckets::Used(speeds) => assert_eq!(speeds, &vec![(0, 50), (MAX_BUCKETS / 2, 100), (MAX_BUCKETS, 50)]),
+        SpeedBuckets::Unused => panic!("edge 1 must have a profile"),
+    }
+
+    assert!(speed_records_to_profiles(&[record(2, 0, 50)], &graph, 2).is_err());
+    assert!(speed_records_to_profiles(&[record(0, 2, 50)], &graph, 2).is_err());
+    assert!(speed_records_to_profiles(&[record(0, 0, 0)], &graph, 2).is_err());
+    assert!(speed_records_to_profiles(&[record(0, 0, 50), record(0, 0, 60)], &graph, 2).is_err());
+}
+
+#[test]
+fn historic_and_live_speeds_are_blended() {
+    let historic = vec![(0, 40), (MAX_BUCKETS / 2, 80), (MAX_BUCKETS, 40)];
+    let live = vec![(0, 100), (MAX_BUCKETS / 4, 60), (MAX_BUCKETS, 100)];
+
+    assert_eq!(
+        SpeedBlend::constant(0.5).blend(&historic, &live),
+        vec![(0, 70), (MAX_BUCKETS / 4, 50), (MAX_BUCKETS / 2, 70), (MAX_BUCKETS, 70)]
+    );
+
+    // only historic speeds in the first half of the day, only live speeds afterwards
+    assert_eq!(
+        SpeedBlend::weighted(vec![(0, 1.0), (MAX_BUCKETS / 2, 0.0)]).blend(&historic, &live),
+        vec![(0, 40), (MAX_BUCKETS / 2, 60), (MAX_BUCKETS, 40)]
+    );
+}
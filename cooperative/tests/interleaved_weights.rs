@@ -0,0 +1,33 @@
+use cooperative::dijkstra::potentials::multi_metric_potential::customization::{metric_stride, CustomizedMultiMetrics};
+use cooperative::dijkstra::potentials::multi_metric_potential::metric_reduction::MetricEntry;
+use rust_road_router::algo::customizable_contraction_hierarchy::CCH;
+use rust_road_router::datastr::graph::{FirstOutGraph, Graph, INFINITY};
+use rust_road_router::datastr::node_order::NodeOrder;
+
+#[test]
+fn weight_blocks_fit_into_cache_lines() {
+    assert_eq!(metric_stride(2), 2);
+    assert_eq!(metric_stride(3), 4);
+    assert_eq!(metric_stride(16), 16);
+    assert_eq!(metric_stride(17), 32);
+    assert_eq!(metric_stride(40), 48);
+}
+
+#[test]
+fn bounds_are_read_from_interleaved_blocks() {
+    let graph = FirstOutGraph::new(vec![0, 1, 2, 2], vec![1, 2], vec![10, 10]);
+    let cch = CCH::fix_order_and_build(&graph, NodeOrder::from_node_order(vec![0, 1, 2]));
+    let m = cch.num_arcs();
+
+    // per shortcut: lowerbound, upperbound, one interval metric and a padding entry
+    let upward = (0..m as u32).flat_map(|edge| vec![edge, 10 * edge, 5 * edge, INFINITY]).collect::<Vec<_>>();
+    let downward = (0..m as u32).flat_map(|edge| vec![edge + 1, 20 * edge, 7 * edge, INFINITY]).collect::<Vec<_>>();
+    let entries = vec![MetricEntry::new(0, 100, 0), MetricEntry::new(0, 50, 2)];
+
+    let customized = CustomizedMultiMetrics::restore(cch, upward, downward, entries, 3, graph.num_arcs());
+    assert_eq!(customized.forward_cch_bounds, (0..m as u32).map(|edge| (edge, 10 * edge)).collect::<Vec<_>>());
+    assert_eq!(
+        customized.backward_cch_bounds,
+        (0..m as u32).map(|edge| (edge + 1, 20 * edge)).collect::<Vec<_>>()
+    );
+}
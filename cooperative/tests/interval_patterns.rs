@@ -0,0 +1,54 @@
+use cooperative::dijkstra::potentials::multi_metric_potential::interval_patterns::{parse_interval_pattern, parse_time_of_day, rush_hour_pattern};
+use cooperative::graph::MAX_BUCKETS;
+use utils::temp_path;
+
+mod utils;
+
+#[test]
+fn valid_interval_patterns_are_parsed() {
+    assert_eq!(parse_interval_pattern(" rush_hour ").unwrap(), rush_hour_pattern());
+    assert_eq!(
+        parse_interval_pattern("06:00-10:00, 07:30-08:00,00:00-24:00").unwrap(),
+        vec![(21_600_000, 36_000_000), (27_000_000, 28_800_000), (0, MAX_BUCKETS)]
+    );
+    assert_eq!(parse_time_of_day(" 7:05 ").unwrap(), 25_500_000);
+
+    // files may contain comments, empty lines and several windows per line
+    let path = temp_path("interval_pattern.txt");
+    std::fs::write(&path, "# morning\n06:00-10:00,07:00-08:00\n\n16:00-19:00\n").unwrap();
+    let pattern = parse_interval_pattern(&format!("@{}", path.display()));
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(
+        pattern.unwrap(),
+        vec![(21_600_000, 36_000_000), (25_200_000, 28_800_000), (57_600_000, 68_400_000)]
+    );
+}
+
+#[test]
+fn malformed_interval_patterns_are_rejected() {
+    for spec in [
+        "",
+        "unknown",
+        "06:00",
+        "06:00-",
+        "6-10",
+        "06:00-10:00,",
+        "10:00-06:00",
+        "08:00-08:00",
+        "23:00-24:30",
+        "25:00-26:00",
+        "06:60-07:00",
+        "aa:00-10:00",
+        "-01:00-02:00",
+    ] {
+        assert!(parse_interval_pattern(spec).is_err(), "`{}` should be rejected", spec);
+    }
+
+    // missing files and files without windows
+    assert!(parse_interval_pattern(&format!("@{}", temp_path("missing_interval_pattern.txt").display())).is_err());
+    let path = temp_path("empty_interval_pattern.txt");
+    std::fs::write(&path, "# no windows\n\n").unwrap();
+    let pattern = parse_interval_pattern(&format!("@{}", path.display()));
+    std::fs::remove_file(&path).unwrap();
+    assert!(pattern.is_err());
+}
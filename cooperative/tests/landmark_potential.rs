@@ -0,0 +1,26 @@
+use cooperative::dijkstra::potentials::landmark_potential::{CCHLandmarkPotential, LandmarkWindow};
+use cooperative::dijkstra::potentials::TDPotential;
+use rust_road_router::algo::a_star::ZeroPotential;
+use utils::{create_graph, CapacityEdge};
+
+mod utils;
+
+#[test]
+fn landmarks_tighten_the_potential_within_their_window() {
+    // path 0 -> 1 -> 2, one minute per edge
+    let graph = create_graph(
+        1,
+        vec![CapacityEdge::new(0, 1, 1000, 60_000, 1000), CapacityEdge::new(1, 2, 1000, 60_000, 1000)],
+    );
+    let windows = vec![LandmarkWindow::new(&graph, 0, 3_600_000, 1)];
+    let mut potential = CCHLandmarkPotential::new(ZeroPotential(), &windows, 600_000);
+
+    potential.init(0, 2, 1000);
+    assert_eq!(potential.potential(0, 1000), Some(120_000));
+    assert_eq!(potential.potential(1, 61_000), Some(60_000));
+    assert_eq!(potential.num_landmark_improvements(), 2);
+
+    // the latest arrival exceeds the window, only the zero potential remains
+    potential.init(0, 2, 3_500_000);
+    assert_eq!(potential.potential(0, 3_500_000), Some(0));
+}
@@ -0,0 +1,24 @@
+use cooperative::dijkstra::potentials::multi_metric_potential::metric_reduction::{deduplicate_metrics, reduce_metrics, MetricEntry};
+
+#[test]
+fn nearly_identical_metrics_are_shared() {
+    // per edge: lowerbound, upperbound, then one metric per interval
+    let mut data = vec![vec![10, 50, 20, 24, 400], vec![15, 60, 30, 30, 15], vec![20, 90, 40, 41, 80]];
+    let mut entries = vec![
+        MetricEntry::new(0, 100, 0),
+        MetricEntry::new(0, 10, 2),
+        MetricEntry::new(10, 20, 3),
+        MetricEntry::new(20, 30, 4),
+    ];
+
+    let num_metrics = deduplicate_metrics(&mut data, &mut entries, 5);
+    assert_eq!(num_metrics, 4);
+    assert_eq!(entries.iter().map(|entry| entry.metric_id).collect::<Vec<_>>(), vec![0, 2, 2, 3]);
+    // the shared metric is the minimum of both, the others are untouched
+    assert_eq!(data, vec![vec![10, 50, 20, 400], vec![15, 60, 30, 15], vec![20, 90, 40, 80]]);
+
+    // the pairwise reduction can deal with entries sharing a metric
+    let num_metrics = reduce_metrics(&mut data, &mut entries, 2, false);
+    assert_eq!(num_metrics, 3);
+    assert!(entries.iter().all(|entry| entry.metric_id != 1 && entry.metric_id < 3));
+}
@@ -0,0 +1,55 @@
+use cooperative::dijkstra::server::{CapacityServer, CapacityServerOps};
+use cooperative::graph::parking::ParkingModel;
+use cooperative::io::io_parking::load_parking_zones;
+use rust_road_router::algo::a_star::ZeroPotential;
+use rust_road_router::algo::TDQuery;
+use utils::{create_graph, temp_path, triangle_edges};
+
+mod utils;
+
+#[test]
+fn parking_search_time_grows_with_occupancy() {
+    let path = temp_path("parking_zones.json");
+    std::fs::write(
+        &path,
+        r#"{ "type": "FeatureCollection", "features": [{
+            "type": "Feature",
+            "geometry": { "type": "Polygon", "coordinates": [[[1.5, 1.5], [2.5, 1.5], [2.5, 2.5], [1.5, 2.5], [1.5, 1.5]]] },
+            "properties": { "base_search_time": 60, "max_search_time": 600, "capacity": 1, "dwell_time": 60 }
+        }] }"#,
+    )
+    .unwrap();
+    let zones = load_parking_zones(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(zones[0].dwell_time, 3_600_000);
+
+    // only node 2 lies inside the zone
+    let parking = ParkingModel::new(zones, &[0.0, 1.0, 2.0], &[0.0, 1.0, 2.0]);
+    assert_eq!(parking.num_zone_nodes(), 1);
+
+    let graph = create_graph(1, triangle_edges([10, 50, 10], [1000, 5000, 1000], [1000, 1000, 1000]));
+    let mut server = CapacityServer::new(graph, ZeroPotential());
+    server.set_parking_model(Some(parking));
+
+    // planning queries don't occupy a space
+    let query = TDQuery { from: 0, to: 2, departure: 0 };
+    assert_eq!(server.query_trip(&query, 0, false).unwrap().parking_time, 60_000);
+    let first = server.query_trip(&query, 0, true).unwrap();
+    assert_eq!(first.parking_time, 60_000);
+    assert_eq!(first.door_to_door_time(), first.distance + 60_000);
+
+    // the zone is full until the first vehicle leaves again
+    let second = server.query_trip(&TDQuery { departure: 100_000, ..query }, 1, true).unwrap();
+    assert_eq!(second.parking_time, 600_000);
+    let later = server.query_trip(&TDQuery { departure: 3_000_000, ..query }, 2, false).unwrap();
+    assert_eq!(later.parking_time, 600_000);
+    let after_dwell_time = server.query_trip(&TDQuery { departure: 8_000_000, ..query }, 3, false).unwrap();
+    assert_eq!(after_dwell_time.parking_time, 60_000);
+
+    // destinations outside of all zones have no search time
+    assert_eq!(server.query_trip(&TDQuery { from: 0, to: 1, departure: 0 }, 4, false).unwrap().parking_time, 0);
+
+    let statistics = server.trip_statistics();
+    assert_eq!(statistics[1].parking_time, 600_000);
+    assert_eq!(statistics[1].door_to_door_time(), statistics[1].experienced_travel_time + 600_000);
+}
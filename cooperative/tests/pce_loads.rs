@@ -0,0 +1,26 @@
+use cooperative::dijkstra::server::{CapacityServer, CapacityServerOps};
+use cooperative::graph::PCE_SCALE;
+use rust_road_router::algo::a_star::ZeroPotential;
+use rust_road_router::algo::TDQuery;
+use utils::{create_graph_with_traffic_function, single_vehicle_traffic_function, triangle_edges};
+
+mod utils;
+
+#[test]
+fn path_distance_matches_the_query_distance_on_single_bucket_graphs() {
+    let graph = create_graph_with_traffic_function(
+        1,
+        triangle_edges([1000, 3333, 1000], [36000, 120_000, 36000], [50, 50, 50]),
+        single_vehicle_traffic_function(1),
+    );
+    let mut server = CapacityServer::new(graph, ZeroPotential());
+    let to_1 = TDQuery { from: 0, to: 1, departure: 0 };
+
+    // a car and a truck counting as two cars, each one adds the free-flow time of 36s
+    server.query_trip(&to_1, 0, true).unwrap();
+    server.query_trip_with_pce(&to_1, 1, 2 * PCE_SCALE, true).unwrap();
+
+    let result = server.query(&to_1, false).unwrap();
+    assert_eq!(result.distance, 4 * 36000);
+    assert_eq!(server.path_distance(&result.path.edge_path, 0), result.distance);
+}
@@ -0,0 +1,72 @@
+use cooperative::dijkstra::potentials::multi_metric_potential::customization::CustomizedMultiMetrics;
+use cooperative::dijkstra::potentials::multi_metric_potential::potential::MultiMetricPotential;
+use cooperative::dijkstra::potentials::TDPotential;
+use cooperative::dijkstra::server::{CapacityServer, CapacityServerOps};
+use cooperative::graph::capacity_graph::CapacityGraph;
+use cooperative::graph::MAX_BUCKETS;
+use rust_road_router::algo::a_star::ZeroPotential;
+use rust_road_router::algo::customizable_contraction_hierarchy::{CCH, CCHT};
+use rust_road_router::algo::TDQuery;
+use rust_road_router::datastr::node_order::NodeOrder;
+use utils::{create_graph, CapacityEdge};
+
+mod utils;
+
+// cycle 0 -> 1 -> 2 -> 3 -> 0 with slow reverse edges and a chord 0 <-> 2, so some shortcuts are dominated
+fn cycle_graph() -> CapacityGraph {
+    let edges = [
+        (0, 1, 60_000),
+        (0, 2, 200_000),
+        (0, 3, 300_000),
+        (1, 0, 120_000),
+        (1, 2, 60_000),
+        (2, 0, 240_000),
+        (2, 1, 180_000),
+        (2, 3, 60_000),
+        (3, 0, 60_000),
+        (3, 2, 90_000),
+    ];
+    create_graph(
+        1,
+        edges
+            .iter()
+            .map(|&(from, to, travel_time)| CapacityEdge::new(from, to, 1000, travel_time, 1000))
+            .collect(),
+    )
+}
+
+#[test]
+fn perfect_customization_keeps_potentials_and_distances() {
+    let graph = cycle_graph();
+    let order = NodeOrder::from_node_order(vec![1, 3, 0, 2]);
+    let intervals = vec![(0, MAX_BUCKETS / 2), (MAX_BUCKETS / 2, MAX_BUCKETS)];
+
+    let mut customized = CustomizedMultiMetrics::new_from_capacity(CCH::fix_order_and_build(&graph, order.clone()), &graph, &intervals, 4);
+    let mut perfect = CustomizedMultiMetrics::new_from_capacity(CCH::fix_order_and_build(&graph, order), &graph, &intervals, 4).into_perfect();
+    // at least one dominated shortcut was removed
+    assert!(perfect.cch.forward_head().len() + perfect.cch.backward_head().len() < customized.cch.forward_head().len() + customized.cch.backward_head().len());
+
+    for target in 0..4 {
+        for source in 0..4 {
+            let expected = {
+                let mut potential = MultiMetricPotential::prepare(&mut customized);
+                potential.init(source, target, 0);
+                (0..4).map(|node| potential.potential(node, 0)).collect::<Vec<_>>()
+            };
+            let mut potential = MultiMetricPotential::prepare(&mut perfect);
+            potential.init(source, target, 0);
+            assert_eq!((0..4).map(|node| potential.potential(node, 0)).collect::<Vec<_>>(), expected);
+        }
+    }
+
+    // the queries on the perfect potential match plain Dijkstra queries
+    let mut server = CapacityServer::new(cycle_graph(), perfect);
+    let mut dijkstra = CapacityServer::new(cycle_graph(), ZeroPotential());
+    for from in 0..4 {
+        for to in 0..4 {
+            let query = TDQuery { from, to, departure: 0 };
+            let expected = dijkstra.query(&query, false).map(|result| result.distance);
+            assert_eq!(server.query(&query, false).map(|result| result.distance), expected);
+        }
+    }
+}
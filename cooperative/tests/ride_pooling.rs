@@ -0,0 +1,33 @@
+use cooperative::dijkstra::ride_pooling::{cheapest_insertion, Stop, VehicleRoute};
+use cooperative::dijkstra::server::CapacityServer;
+use rust_road_router::algo::a_star::ZeroPotential;
+use utils::{create_graph, line_edges};
+
+mod utils;
+
+#[test]
+fn cheapest_insertion_respects_time_windows() {
+    // bidirectional line 0 - 1 - 2 - 3, each edge takes 1s
+    let graph = create_graph(1, line_edges(4, 10, 1000, 1000));
+    let mut server = CapacityServer::new(graph, ZeroPotential());
+    let route = VehicleRoute::new(0, 0, vec![Stop::new(3, 0, 100_000)]);
+
+    // a request along the way is served without any detour
+    let insertion = cheapest_insertion(&mut server, &route, Stop::new(1, 0, 100_000), Stop::new(2, 0, 100_000), |_| {}).unwrap();
+    assert_eq!((insertion.pickup_position, insertion.dropoff_position), (0, 1));
+    assert_eq!(insertion.service_times, vec![1000, 2000, 3000]);
+    assert_eq!(insertion.added_time, 0);
+
+    // waiting for a late pickup on the way costs more than serving it afterwards
+    let insertion = cheapest_insertion(&mut server, &route, Stop::new(1, 5000, 100_000), Stop::new(2, 0, 100_000), |_| {}).unwrap();
+    assert_eq!((insertion.pickup_position, insertion.dropoff_position), (1, 2));
+    assert_eq!(insertion.service_times, vec![3000, 5000, 6000]);
+    assert_eq!(insertion.added_time, 3000);
+
+    // neither the new nor the existing stops may miss their time windows
+    assert!(cheapest_insertion(&mut server, &route, Stop::new(1, 0, 100_000), Stop::new(2, 0, 500), |_| {}).is_none());
+    let (pickup, dropoff) = (Stop::new(1, 2000, 2500), Stop::new(2, 0, 100_000));
+    assert_eq!(cheapest_insertion(&mut server, &route, pickup, dropoff, |_| {}).unwrap().added_time, 1000);
+    let tight_route = VehicleRoute::new(0, 0, vec![Stop::new(3, 0, 3500)]);
+    assert!(cheapest_insertion(&mut server, &tight_route, pickup, dropoff, |_| {}).is_none());
+}
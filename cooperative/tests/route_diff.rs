@@ -0,0 +1,44 @@
+use cooperative::dijkstra::server::CapacityServer;
+use cooperative::graph::{Capacity, PCE_SCALE};
+use rust_road_router::algo::a_star::ZeroPotential;
+use rust_road_router::algo::TDQuery;
+use utils::{create_graph_with_traffic_function, single_vehicle_traffic_function, triangle_edges};
+
+mod utils;
+
+#[test]
+fn rerouted_trip_moves_its_load_to_the_new_path() {
+    // 0 -> 1 -> 2 takes 72s, the direct edge 0 -> 2 takes 121.2s, each vehicle adds the free-flow time to an edge
+    let graph = create_graph_with_traffic_function(
+        1,
+        triangle_edges([1000, 3333, 1000], [36000, 120_000, 36000], [50, 50, 50]),
+        single_vehicle_traffic_function(1),
+    );
+    let mut server = CapacityServer::new(graph, ZeroPotential());
+    let to_2 = TDQuery { from: 0, to: 2, departure: 0 };
+    let to_1 = TDQuery { from: 0, to: 1, departure: 0 };
+
+    let first = server.query_trip(&to_2, 0, true).unwrap();
+    assert_eq!(first.path.edge_path, vec![0, 2]);
+
+    // two more vehicles congest the edge 0 -> 1, so the direct edge becomes faster even without the own load
+    server.query_trip(&to_1, 1, true).unwrap();
+    server.query_trip(&to_1, 2, true).unwrap();
+
+    let (rerouted, diff) = server.reroute_trip(&first.path, &to_2, 0).unwrap();
+    assert_eq!(rerouted.path.edge_path, vec![1]);
+    assert_eq!(diff.shared_prefix_len, 0);
+    assert_eq!(diff.divergence_node, Some(0));
+    assert_eq!(diff.added_edges, vec![1]);
+    assert_eq!(diff.removed_edges, vec![0, 2]);
+    assert!(diff.delta_travel_time > 0);
+
+    // the withdrawn path keeps no load, the new one carries the load of the trip
+    let total_loads = server
+        .borrow_graph()
+        .export_bucket_loads()
+        .iter()
+        .map(|buckets| buckets.iter().map(|&(_, load)| load).sum::<Capacity>())
+        .collect::<Vec<Capacity>>();
+    assert_eq!(total_loads, vec![2 * PCE_SCALE, PCE_SCALE, 0]);
+}
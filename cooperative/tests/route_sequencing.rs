@@ -0,0 +1,47 @@
+use cooperative::dijkstra::route_sequencing::{nearest_neighbor_tour, sequence_stops, tour_cost, two_opt};
+use cooperative::dijkstra::server::CapacityServer;
+use rust_road_router::algo::a_star::ZeroPotential;
+use rust_road_router::datastr::graph::Weight;
+use utils::{create_graph, line_edges};
+
+mod utils;
+
+#[test]
+fn two_opt_improves_nearest_neighbor_tour() {
+    // stops on a line at the given positions, the greedy tour zigzags
+    let positions: Vec<i64> = vec![0, 1, -2, 5];
+    let costs = positions
+        .iter()
+        .map(|a| positions.iter().map(|b| (a - b).unsigned_abs() as Weight).collect())
+        .collect::<Vec<Vec<Weight>>>();
+
+    let tour = nearest_neighbor_tour(&costs, false);
+    assert_eq!(tour, vec![0, 1, 2, 3]);
+    assert_eq!(tour_cost(&costs, &tour), 11);
+
+    let tour = two_opt(&costs, tour);
+    assert_eq!(tour, vec![0, 2, 1, 3]);
+    assert_eq!(tour_cost(&costs, &tour), 9);
+
+    // round trips keep returning to the start
+    let tour = two_opt(&costs, nearest_neighbor_tour(&costs, true));
+    assert_eq!((tour.first(), tour.last()), (Some(&0), Some(&0)));
+    assert_eq!(tour_cost(&costs, &tour), 14);
+}
+
+#[test]
+fn sequenced_stops_are_routed_leg_by_leg() {
+    // bidirectional line 0 - 1 - 2 - 3, each edge takes 1s
+    let graph = create_graph(1, line_edges(4, 10, 1000, 1000));
+    let mut server = CapacityServer::new(graph, ZeroPotential());
+
+    let route = sequence_stops(&mut server, &[1, 3, 0, 2], 0, false).unwrap();
+    assert_eq!(route.order, vec![0, 2, 3, 1]);
+    assert_eq!(route.legs.len(), 3);
+    assert_eq!(route.travel_time, 4000);
+    assert_eq!(*route.legs[2].path.departure.first().unwrap(), 3000);
+
+    let round_trip = sequence_stops(&mut server, &[1, 3, 0, 2], 0, true).unwrap();
+    assert_eq!(round_trip.travel_time, 6000);
+    assert_eq!(round_trip.legs.last().unwrap().path.node_path.last(), Some(&1));
+}
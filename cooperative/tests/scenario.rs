@@ -0,0 +1,75 @@
+use cooperative::graph::weather::WeatherFactor;
+use cooperative::io::io_scenario::load_scenario;
+use utils::{create_graph, line_edges, temp_path};
+
+mod utils;
+
+#[test]
+fn scenario_events_are_applied_by_time() {
+    let path = temp_path("scenario.json");
+    std::fs::write(
+        &path,
+        r#"{ "events": [
+            { "type": "lane_closure", "edge_id": 1, "start": "07:00", "end": "09:00" },
+            { "type": "lane_closure", "edge_id": 0, "closed_lanes": 1, "start": 0, "end": 3600000 },
+            { "type": "demand_scaling", "factor": 1.5, "start": "06:00", "end": "10:00" },
+            { "type": "demand_scaling", "factor": 2.0, "start": "08:00" }
+        ] }"#,
+    )
+    .unwrap();
+    let scenario = load_scenario(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut graph = create_graph(1, line_edges(2, 1000, 36000, 1000));
+    graph.set_lanes(vec![2, 3], 1800);
+    assert!(scenario.validate(&graph).is_ok());
+
+    let closed_lanes = |ts| scenario.closed_lanes_at(&graph, ts).into_iter().collect::<Vec<_>>();
+    assert_eq!(closed_lanes(0), vec![(0, 1), (1, 0)]);
+    assert_eq!(closed_lanes(8 * 3_600_000), vec![(0, 0), (1, 2)]);
+    assert_eq!(closed_lanes(9 * 3_600_000), vec![(0, 0), (1, 0)]);
+
+    assert_eq!(scenario.demand_factor(0), 1.0);
+    assert_eq!(scenario.demand_factor(9 * 3_600_000), 3.0);
+    assert_eq!(scenario.scaled_load(10, 7 * 3_600_000), 15);
+}
+
+#[test]
+fn weather_scales_speeds_and_capacities() {
+    let path = temp_path("scenario_weather.json");
+    std::fs::write(
+        &path,
+        r#"{ "events": [
+            { "type": "weather", "speed_factor": 0.5, "start": "07:00", "end": "09:00" },
+            { "type": "weather", "capacity_factor": 0.5, "region": [0.5, 0.5, 1.5, 1.5] }
+        ] }"#,
+    )
+    .unwrap();
+    let mut scenario = load_scenario(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut graph = create_graph(1, line_edges(2, 1000, 100_000, 1000));
+    assert!(scenario.validate(&graph).is_err());
+    scenario.resolve_weather_regions(&graph, &[0.0, 1.0], &[0.0, 1.0]);
+    assert!(scenario.validate(&graph).is_ok());
+
+    let weather = |ts| scenario.weather_at(&graph, ts).into_iter().collect::<Vec<_>>();
+    assert_eq!(weather(0), vec![(0, WeatherFactor::new(1.0, 1.0)), (1, WeatherFactor::new(1.0, 0.5))]);
+    assert_eq!(
+        weather(8 * 3_600_000),
+        vec![(0, WeatherFactor::new(0.5, 1.0)), (1, WeatherFactor::new(0.5, 0.5))]
+    );
+
+    let max_capacity = graph.max_capacity()[1];
+    for (edge_id, factor) in weather(8 * 3_600_000) {
+        graph.set_weather(edge_id, factor);
+    }
+    assert_eq!(graph.travel_time()[0], vec![200_000, 200_000]);
+    assert_eq!(graph.max_capacity()[1], max_capacity / 2);
+
+    // the default factor restores the original values
+    graph.set_weather(0, WeatherFactor::default());
+    graph.set_weather(1, WeatherFactor::default());
+    assert_eq!(graph.travel_time()[0], vec![100_000, 100_000]);
+    assert_eq!(graph.max_capacity()[1], max_capacity);
+}
@@ -0,0 +1,36 @@
+use cooperative::dijkstra::server::{CapacityServer, CapacityServerOps};
+use cooperative::graph::capacity_graph::CapacityGraph;
+use cooperative::graph::traffic_functions::BPRTrafficFunction;
+use rust_road_router::algo::a_star::ZeroPotential;
+use rust_road_router::algo::TDQuery;
+
+#[test]
+fn search_space_contains_settled_nodes() {
+    // path 0 -> 1 -> 2 with 1s per edge, node 3 is unreachable
+    let graph = CapacityGraph::new(
+        1,
+        vec![0, 1, 2, 2, 2],
+        vec![1, 2],
+        vec![10, 10],
+        vec![36, 36],
+        vec![1000, 1000],
+        BPRTrafficFunction::default(),
+    );
+    let mut server = CapacityServer::new(graph, ZeroPotential());
+    let query = TDQuery {
+        from: 0,
+        to: 2,
+        departure: 5000,
+    };
+
+    server.query(&query, false);
+    assert!(server.search_space().is_none());
+
+    server.set_search_space_recording(true);
+    let distance = server.query(&query, false).map(|result| result.distance);
+    let search_space = server.search_space().unwrap();
+
+    assert_eq!(search_space.iter().map(|settled| settled.node).collect::<Vec<_>>(), vec![0, 1, 2]);
+    assert_eq!(search_space.last().map(|settled| settled.distance), distance);
+    assert!(search_space.iter().all(|settled| settled.potential == 0));
+}
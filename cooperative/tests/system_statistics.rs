@@ -0,0 +1,34 @@
+use cooperative::graph::{MAX_BUCKETS, PCE_SCALE};
+use utils::{create_graph, triangle_edges};
+
+mod utils;
+
+#[test]
+fn interval_statistics_follow_added_and_withdrawn_paths() {
+    // 0 -> 1 -> 2 with 1km and 36s per edge, 4 buckets of 6 hours each
+    let mut graph = create_graph(4, triangle_edges([1000, 2000, 1000], [36000, 72000, 36000], [100, 100, 100]));
+
+    let early = graph.path_departures(&[0, 2], 1000);
+    assert_eq!(early, vec![1000, 37000, 73000]);
+    graph.increase_weights(&[0, 2], &early, 2 * PCE_SCALE);
+
+    // the second edge of this path is entered in the next bucket
+    let late = graph.path_departures(&[0, 2], MAX_BUCKETS / 4 - 1000);
+    graph.increase_weights(&[0, 2], &late, PCE_SCALE);
+    let late_travel_time = late[2] - late[1];
+
+    let intervals = graph.interval_statistics();
+    assert_eq!(intervals.len(), 4);
+    assert_eq!((intervals[1].start, intervals[1].end), (MAX_BUCKETS / 4, MAX_BUCKETS / 2));
+    assert!((intervals[0].vehicle_km - 5.0).abs() < 1e-9);
+    assert!((intervals[1].vehicle_km - 1.0).abs() < 1e-9);
+    assert!((intervals[1].vehicle_hours - late_travel_time as f64 / 3_600_000.0).abs() < 1e-9);
+    assert!(intervals[2..]
+        .iter()
+        .all(|interval| interval.vehicle_hours == 0.0 && interval.vehicle_km == 0.0));
+
+    // withdrawing the paths exactly reverts their contribution
+    graph.decrease_weights(&[0, 2], &late, PCE_SCALE);
+    graph.decrease_weights(&[0, 2], &early, 2 * PCE_SCALE);
+    assert_eq!(graph.system_statistics().total(), (0.0, 0.0));
+}
@@ -34,7 +34,7 @@ fn test_partial_backward_profile() {
     dbg!(start_ts, end_ts);
 
     //let result = first.link(&second, Timestamp(917.278 - 4.023), Timestamp(3632.2780000000002 - 4.023));
-    let result = first.link(&second, start_ts, end_ts);
+    let result = first.link(&second, start_ts, end_ts, &mut MergeBuffers::new());
     dbg!(&result);
 }
 
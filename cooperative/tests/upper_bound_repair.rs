@@ -0,0 +1,32 @@
+use cooperative::dijkstra::potentials::multi_metric_potential::customization::CustomizedMultiMetrics;
+use cooperative::dijkstra::server::{CapacityServer, CapacityServerOps};
+use cooperative::graph::{MAX_BUCKETS, PCE_SCALE};
+use rust_road_router::algo::customizable_contraction_hierarchy::CCH;
+use rust_road_router::algo::TDQuery;
+use rust_road_router::datastr::node_order::NodeOrder;
+use utils::congestible_triangle_graph;
+
+mod utils;
+
+#[test]
+fn invalid_results_without_recorded_violations_are_fully_repaired() {
+    // the potential is customized on the empty graph, the server's graph is already congested on all edges
+    let empty_graph = congestible_triangle_graph();
+    let cch = CCH::fix_order_and_build(&empty_graph, NodeOrder::from_node_order(vec![0, 1, 2]));
+    let customized = CustomizedMultiMetrics::new_from_capacity(cch, &empty_graph, &vec![(0, MAX_BUCKETS / 2), (MAX_BUCKETS / 2, MAX_BUCKETS)], 4);
+
+    // 1000 vehicles slow down all edges to 30 km/h, far beyond the 1.5 times free-flow upper bound
+    let mut graph = congestible_triangle_graph();
+    graph.increase_weights(&[0, 1, 2], &[0, 0, 0], 1000 * PCE_SCALE);
+    let mut server = CapacityServer::new(graph, customized);
+
+    // the distance exceeds the customized upper bound, but no update of the server violated it
+    let query = TDQuery { from: 0, to: 2, departure: 0 };
+    assert!(server.query(&query, false).is_none());
+    assert!(!server.result_valid());
+
+    // nothing to repair locally, the upper bounds are fully re-customized
+    assert!(!server.repair_upper_bound());
+    assert!(server.result_valid());
+    assert_eq!(server.query(&query, false).unwrap().distance, 240_000);
+}
@@ -1,9 +1,12 @@
+// shared fixtures, included by the other integration tests via `mod utils;`
+#![allow(dead_code)]
+
 use std::cmp::max;
+use std::path::{Path, PathBuf};
 
-use cooperative::dijkstra::elimination_tree::multi_level_buckets::bucket_tree::BucketTreeEntry;
 use cooperative::graph::capacity_graph::CapacityGraph;
 use cooperative::graph::traffic_functions::BPRTrafficFunction;
-use cooperative::graph::{Capacity, MAX_BUCKETS};
+use cooperative::graph::Capacity;
 use rust_road_router::datastr::graph::{NodeId, Weight};
 
 pub struct CapacityEdge {
@@ -27,7 +30,12 @@ impl CapacityEdge {
 }
 
 /// Creates a dummy graph from a given (unordered) edge list.
-pub fn create_graph(num_buckets: u32, mut edges: Vec<CapacityEdge>) -> CapacityGraph {
+pub fn create_graph(num_buckets: u32, edges: Vec<CapacityEdge>) -> CapacityGraph {
+    create_graph_with_traffic_function(num_buckets, edges, BPRTrafficFunction::default())
+}
+
+/// Same as `create_graph`, but with a custom traffic function.
+pub fn create_graph_with_traffic_function(num_buckets: u32, mut edges: Vec<CapacityEdge>, traffic_function: BPRTrafficFunction) -> CapacityGraph {
     assert!(!edges.is_empty(), "graph must not be empty!");
 
     // bring edges in sorted order
@@ -40,7 +48,7 @@ pub fn create_graph(num_buckets: u32, mut edges: Vec<CapacityEdge>) -> CapacityG
     let mut freeflow_time = Vec::with_capacity(edges.len());
     let mut max_capacity = Vec::with_capacity(edges.len());
 
-    let mut degree = vec![0u32; max_node_id as usize];
+    let mut degree = vec![0u32; max_node_id as usize + 1];
     let mut first_out = vec![0];
 
     edges.iter().for_each(|edge| {
@@ -52,17 +60,57 @@ pub fn create_graph(num_buckets: u32, mut edges: Vec<CapacityEdge>) -> CapacityG
     });
 
     degree.iter().for_each(|&deg| first_out.push(*first_out.last().unwrap() + deg));
-    CapacityGraph::new(
-        num_buckets,
-        first_out,
-        head,
-        distance,
-        freeflow_time,
-        max_capacity,
-        BPRTrafficFunction::default(),
+    CapacityGraph::new(num_buckets, first_out, head, distance, freeflow_time, max_capacity, traffic_function)
+}
+
+/// The path 0 -> 1 -> 2 with a direct edge 0 -> 2, the edges are ordered (0, 1), (0, 2), (1, 2).
+pub fn triangle_edges(distance: [Weight; 3], travel_time: [Weight; 3], capacity: [Capacity; 3]) -> Vec<CapacityEdge> {
+    [(0, 1), (0, 2), (1, 2)]
+        .iter()
+        .enumerate()
+        .map(|(idx, &(from, to))| CapacityEdge::new(from, to, distance[idx], travel_time[idx], capacity[idx]))
+        .collect()
+}
+
+/// The triangle of `triangle_edges` with 4 buckets, low capacities and a steep traffic function, so loads change the travel times.
+pub fn congestible_triangle_graph() -> CapacityGraph {
+    create_graph_with_traffic_function(
+        4,
+        triangle_edges([1000, 2000, 1000], [36000, 72000, 36000], [100, 100, 100]),
+        BPRTrafficFunction::new(0.5, 3),
     )
 }
 
+/// A linear traffic function under which each vehicle (`PCE_SCALE`) adds the free-flow time of an edge with capacity 50,
+/// the smallest capacity which is not dropped by `CapacityGraph::new`.
+pub fn single_vehicle_traffic_function(num_buckets: u32) -> BPRTrafficFunction {
+    // capacities are scaled by 24 / num_buckets, loads are compared against them in PCE units
+    BPRTrafficFunction::new(1200.0 / num_buckets as f64, 1)
+}
+
+/// The bidirectional line 0 - 1 - ... - (num_nodes - 1), all edges share the same attributes.
+pub fn line_edges(num_nodes: NodeId, distance: Weight, travel_time: Weight, capacity: Capacity) -> Vec<CapacityEdge> {
+    (1..num_nodes)
+        .flat_map(|node| {
+            [
+                CapacityEdge::new(node - 1, node, distance, travel_time, capacity),
+                CapacityEdge::new(node, node - 1, distance, travel_time, capacity),
+            ]
+        })
+        .collect()
+}
+
+/// A path in the temp directory which is unique per test process, e.g. `loads.csv` becomes `loads_<pid>.csv`.
+pub fn temp_path(name: &str) -> PathBuf {
+    let name = Path::new(name);
+    let mut file_name = format!("{}_{}", name.file_stem().unwrap().to_str().unwrap(), std::process::id());
+    if let Some(extension) = name.extension() {
+        file_name.push('.');
+        file_name.push_str(extension.to_str().unwrap());
+    }
+    std::env::temp_dir().join(file_name)
+}
+
 #[test]
 fn build_dummy_graph() {
     let edges = vec![
@@ -74,294 +122,6 @@ fn build_dummy_graph() {
     let graph = create_graph(1, edges);
     dbg!(&graph);
 
-    assert_eq!(graph.first_out(), &[0, 1, 2, 3]);
+    assert_eq!(graph.first_out(), &[0, 1, 2, 3, 3]);
     assert_eq!(graph.head(), &[1, 2, 3]);
 }
-
-#[test]
-fn bucket_tree() {
-    let entry = BucketTreeEntry {
-        interval_start: 0,
-        interval_end: 86400000,
-        metric_index: 0,
-        children: vec![
-            BucketTreeEntry {
-                interval_start: 0,
-                interval_end: 14400000,
-                metric_index: 2,
-                children: vec![
-                    BucketTreeEntry {
-                        interval_start: 0,
-                        interval_end: 7200000,
-                        metric_index: 8,
-                        children: vec![
-                            BucketTreeEntry {
-                                interval_start: 0,
-                                interval_end: 3600000,
-                                metric_index: 20,
-                                children: vec![],
-                            },
-                            BucketTreeEntry {
-                                interval_start: 3600000,
-                                interval_end: 7200000,
-                                metric_index: 21,
-                                children: vec![],
-                            },
-                        ],
-                    },
-                    BucketTreeEntry {
-                        interval_start: 7200000,
-                        interval_end: 14400000,
-                        metric_index: 9,
-                        children: vec![
-                            BucketTreeEntry {
-                                interval_start: 7200000,
-                                interval_end: 10800000,
-                                metric_index: 22,
-                                children: vec![],
-                            },
-                            BucketTreeEntry {
-                                interval_start: 10800000,
-                                interval_end: 14400000,
-                                metric_index: 23,
-                                children: vec![],
-                            },
-                        ],
-                    },
-                ],
-            },
-            BucketTreeEntry {
-                interval_start: 14400000,
-                interval_end: 28800000,
-                metric_index: 3,
-                children: vec![
-                    BucketTreeEntry {
-                        interval_start: 14400000,
-                        interval_end: 21600000,
-                        metric_index: 10,
-                        children: vec![
-                            BucketTreeEntry {
-                                interval_start: 14400000,
-                                interval_end: 18000000,
-                                metric_index: 24,
-                                children: vec![],
-                            },
-                            BucketTreeEntry {
-                                interval_start: 18000000,
-                                interval_end: 21600000,
-                                metric_index: 25,
-                                children: vec![],
-                            },
-                        ],
-                    },
-                    BucketTreeEntry {
-                        interval_start: 21600000,
-                        interval_end: 28800000,
-                        metric_index: 11,
-                        children: vec![
-                            BucketTreeEntry {
-                                interval_start: 21600000,
-                                interval_end: 25200000,
-                                metric_index: 26,
-                                children: vec![],
-                            },
-                            BucketTreeEntry {
-                                interval_start: 25200000,
-                                interval_end: 28800000,
-                                metric_index: 27,
-                                children: vec![],
-                            },
-                        ],
-                    },
-                ],
-            },
-            BucketTreeEntry {
-                interval_start: 28800000,
-                interval_end: 43200000,
-                metric_index: 4,
-                children: vec![
-                    BucketTreeEntry {
-                        interval_start: 28800000,
-                        interval_end: 36000000,
-                        metric_index: 12,
-                        children: vec![
-                            BucketTreeEntry {
-                                interval_start: 28800000,
-                                interval_end: 32400000,
-                                metric_index: 28,
-                                children: vec![],
-                            },
-                            BucketTreeEntry {
-                                interval_start: 32400000,
-                                interval_end: 36000000,
-                                metric_index: 29,
-                                children: vec![],
-                            },
-                        ],
-                    },
-                    BucketTreeEntry {
-                        interval_start: 36000000,
-                        interval_end: 43200000,
-                        metric_index: 13,
-                        children: vec![
-                            BucketTreeEntry {
-                                interval_start: 36000000,
-                                interval_end: 39600000,
-                                metric_index: 30,
-                                children: vec![],
-                            },
-                            BucketTreeEntry {
-                                interval_start: 39600000,
-                                interval_end: 43200000,
-                                metric_index: 31,
-                                children: vec![],
-                            },
-                        ],
-                    },
-                ],
-            },
-            BucketTreeEntry {
-                interval_start: 43200000,
-                interval_end: 57600000,
-                metric_index: 5,
-                children: vec![
-                    BucketTreeEntry {
-                        interval_start: 43200000,
-                        interval_end: 50400000,
-                        metric_index: 14,
-                        children: vec![
-                            BucketTreeEntry {
-                                interval_start: 43200000,
-                                interval_end: 46800000,
-                                metric_index: 32,
-                                children: vec![],
-                            },
-                            BucketTreeEntry {
-                                interval_start: 46800000,
-                                interval_end: 50400000,
-                                metric_index: 33,
-                                children: vec![],
-                            },
-                        ],
-                    },
-                    BucketTreeEntry {
-                        interval_start: 50400000,
-                        interval_end: 57600000,
-                        metric_index: 15,
-                        children: vec![
-                            BucketTreeEntry {
-                                interval_start: 50400000,
-                                interval_end: 54000000,
-                                metric_index: 34,
-                                children: vec![],
-                            },
-                            BucketTreeEntry {
-                                interval_start: 54000000,
-                                interval_end: 57600000,
-                                metric_index: 35,
-                                children: vec![],
-                            },
-                        ],
-                    },
-                ],
-            },
-            BucketTreeEntry {
-                interval_start: 57600000,
-                interval_end: 72000000,
-                metric_index: 6,
-                children: vec![
-                    BucketTreeEntry {
-                        interval_start: 57600000,
-                        interval_end: 64800000,
-                        metric_index: 16,
-                        children: vec![
-                            BucketTreeEntry {
-                                interval_start: 57600000,
-                                interval_end: 61200000,
-                                metric_index: 36,
-                                children: vec![],
-                            },
-                            BucketTreeEntry {
-                                interval_start: 61200000,
-                                interval_end: 64800000,
-                                metric_index: 37,
-                                children: vec![],
-                            },
-                        ],
-                    },
-                    BucketTreeEntry {
-                        interval_start: 64800000,
-                        interval_end: 72000000,
-                        metric_index: 17,
-                        children: vec![
-                            BucketTreeEntry {
-                                interval_start: 64800000,
-                                interval_end: 68400000,
-                                metric_index: 38,
-                                children: vec![],
-                            },
-                            BucketTreeEntry {
-                                interval_start: 68400000,
-                                interval_end: 72000000,
-                                metric_index: 39,
-                                children: vec![],
-                            },
-                        ],
-                    },
-                ],
-            },
-            BucketTreeEntry {
-                interval_start: 72000000,
-                interval_end: 86400000,
-                metric_index: 7,
-                children: vec![
-                    BucketTreeEntry {
-                        interval_start: 72000000,
-                        interval_end: 79200000,
-                        metric_index: 18,
-                        children: vec![
-                            BucketTreeEntry {
-                                interval_start: 72000000,
-                                interval_end: 75600000,
-                                metric_index: 40,
-                                children: vec![],
-                            },
-                            BucketTreeEntry {
-                                interval_start: 75600000,
-                                interval_end: 79200000,
-                                metric_index: 41,
-                                children: vec![],
-                            },
-                        ],
-                    },
-                    BucketTreeEntry {
-                        interval_start: 79200000,
-                        interval_end: 86400000,
-                        metric_index: 19,
-                        children: vec![
-                            BucketTreeEntry {
-                                interval_start: 79200000,
-                                interval_end: 82800000,
-                                metric_index: 42,
-                                children: vec![],
-                            },
-                            BucketTreeEntry {
-                                interval_start: 82800000,
-                                interval_end: 86400000,
-                                metric_index: 43,
-                                children: vec![],
-                            },
-                        ],
-                    },
-                ],
-            },
-        ],
-    };
-
-    println!("Params: {}, {}", 85816191 % MAX_BUCKETS, 86711777 % MAX_BUCKETS);
-
-    let result = entry.find_interval(85816191 % MAX_BUCKETS, 86711777 % MAX_BUCKETS);
-    debug_assert!(result.is_some());
-    dbg!(&result);
-    //debug_assert_eq!(result.unwrap().metric_index, 20);
-}
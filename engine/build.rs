@@ -29,6 +29,14 @@ fn main() {
     }
     println!("cargo:rerun-if-env-changed=TDCCH_APPROX");
 
+    if let Ok(val) = env::var("TDCCH_EPSILON") {
+        let dest_path = Path::new(&out_dir).join("TDCCH_EPSILON");
+        let mut f = File::create(&dest_path).unwrap();
+        f.write_all(val.as_bytes()).unwrap();
+        println!("cargo:rustc-cfg=override_tdcch_epsilon");
+    }
+    println!("cargo:rerun-if-env-changed=TDCCH_EPSILON");
+
     if let Ok(val) = env::var("TRAFFIC_MAX_QUERY_TIME") {
         let dest_path = Path::new(&out_dir).join("TRAFFIC_MAX_QUERY_TIME");
         let mut f = File::create(&dest_path).unwrap();
@@ -61,6 +61,14 @@ impl CCHPotData {
         }
     }
 
+    pub fn forward_lazy_rphast_potential(&self) -> BorrowedLazyRPHASTPot {
+        LazyRPHASTPotential::new(self.customized.cch(), self.customized.forward_graph(), self.customized.backward_graph())
+    }
+
+    pub fn backward_lazy_rphast_potential(&self) -> BorrowedLazyRPHASTPot {
+        LazyRPHASTPotential::new(self.customized.cch(), self.customized.backward_graph(), self.customized.forward_graph())
+    }
+
     pub fn forward_path_potential(&self) -> CCHPotentialWithPathUnpacking {
         let n = self.customized.forward_graph().num_nodes();
 
@@ -173,6 +181,121 @@ where
     }
 }
 
+/// CCH potential with RPHAST-style evaluation instead of the elimination tree climb.
+/// The backward search space of the target is computed once during `init`.
+/// For each requested node, the not yet computed part of its upward search space is selected along the (pruned) upward arcs,
+/// sorted topologically (by descending rank) and then evaluated in a single downward sweep.
+/// Nodes which are not reachable via upward arcs are never touched.
+#[derive(Clone)]
+pub struct LazyRPHASTPotential<'a, GF, GB> {
+    cch: &'a DirectedCCH,
+    potentials: TimestampedVector<InRangeOption<Weight>>,
+    forward_cch_graph: GF,
+    backward_distances: TimestampedVector<Weight>,
+    backward_parents: Vec<NodeId>,
+    backward_cch_graph: GB,
+    selection_stack: Vec<NodeId>,
+    selected_nodes: Vec<NodeId>,
+    selected: FastClearBitVec,
+    num_pot_computations: usize,
+}
+
+pub type BorrowedLazyRPHASTPot<'a> = LazyRPHASTPotential<'a, BorrowedGraph<'a>, BorrowedGraph<'a>>;
+
+impl<'a, GF: LinkIterGraph, GB: LinkIterGraph> LazyRPHASTPotential<'a, GF, GB> {
+    pub fn new(cch: &'a DirectedCCH, forward_cch_graph: GF, backward_cch_graph: GB) -> Self {
+        let n = forward_cch_graph.num_nodes();
+
+        Self {
+            cch,
+            potentials: TimestampedVector::new(n),
+            forward_cch_graph,
+            backward_distances: TimestampedVector::new(n),
+            backward_parents: vec![n as NodeId; n],
+            backward_cch_graph,
+            selection_stack: Vec::new(),
+            selected_nodes: Vec::new(),
+            selected: FastClearBitVec::new(n),
+            num_pot_computations: 0,
+        }
+    }
+
+    pub fn num_pot_computations(&self) -> usize {
+        self.num_pot_computations
+    }
+
+    // select all nodes of the upward search space without a potential, starting at `node`
+    fn select(&mut self, node: NodeId) {
+        self.selected.clear();
+        self.selected.set(node as usize);
+        self.selection_stack.push(node);
+
+        while let Some(node) = self.selection_stack.pop() {
+            self.selected_nodes.push(node);
+
+            for link in LinkIterable::<Link>::link_iter(&self.forward_cch_graph, node) {
+                if self.potentials[link.node as usize].value().is_none() && !self.selected.get(link.node as usize) {
+                    self.selected.set(link.node as usize);
+                    self.selection_stack.push(link.node);
+                }
+            }
+        }
+    }
+
+    // evaluate the selected nodes top-down, upward arcs always point to higher ranks
+    fn sweep(&mut self) {
+        self.selected_nodes.sort_unstable_by(|a, b| b.cmp(a));
+
+        for &node in &self.selected_nodes {
+            let mut dist = self.backward_distances[node as usize];
+
+            for edge in LinkIterable::<Link>::link_iter(&self.forward_cch_graph, node) {
+                dist = std::cmp::min(dist, edge.weight + self.potentials[edge.node as usize].value().unwrap())
+            }
+
+            self.potentials[node as usize] = InRangeOption::some(dist);
+        }
+
+        self.num_pot_computations += self.selected_nodes.len();
+        self.selected_nodes.clear();
+    }
+}
+
+impl<'a, GF, GB> Potential for LazyRPHASTPotential<'a, GF, GB>
+where
+    GF: LinkIterGraph,
+    GB: LinkIterGraph,
+{
+    fn init(&mut self, target: NodeId) {
+        let target = self.cch.node_order().rank(target);
+        self.potentials.reset();
+        for _ in EliminationTreeWalk::query(
+            &self.backward_cch_graph,
+            self.cch.elimination_tree(),
+            &mut self.backward_distances,
+            &mut self.backward_parents,
+            target,
+        ) {}
+        self.num_pot_computations = 0;
+    }
+
+    fn potential(&mut self, node: NodeId) -> Option<u32> {
+        let node = self.cch.node_order().rank(node);
+
+        if self.potentials[node as usize].value().is_none() {
+            self.select(node);
+            self.sweep();
+        }
+
+        let dist = self.potentials[node as usize].value().unwrap();
+        if dist < INFINITY {
+            Some(dist)
+        } else {
+            None
+        }
+    }
+}
+
 pub struct CCHPotentialWithPathUnpacking<'a> {
     cch: &'a DirectedCCH,
     stack: Vec<NodeId>,
@@ -1,7 +1,7 @@
 //! Experimental prototype implementation of Contraction Hierarchies in rust.
 //!
 //! Not tuned for performance yet.
-//! No node ordering implemented yet, depends on getting a precalculated order.
+//! Node orders can either be precalculated or obtained from the simple greedy ordering in `ordering`.
 
 use std::marker::PhantomData;
 
@@ -9,7 +9,47 @@ use super::*;
 use crate::algo::{a_star::*, dijkstra::*};
 use crate::datastr::node_order::NodeOrder;
 
+mod ordering;
+pub use ordering::*;
 pub mod query;
+mod witness;
+use witness::WitnessSearch;
+
+/// Parameters of the witness searches, which trade preprocessing time for the quality of the hierarchy.
+///
+/// Limited witness searches may miss witnesses and insert unnecessary shortcuts, the hierarchy stays correct though.
+/// On very large or dense (e.g. turn-expanded) graphs, tight limits can reduce the preprocessing time considerably.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContractionParams {
+    /// maximum number of settled nodes per witness search
+    pub settled_limit: usize,
+    /// maximum number of edges on a witness path
+    pub hop_limit: u32,
+    /// skip the witness search for node pairs which are already connected by an edge that is at most as long as the shortcut
+    pub skip_dominated_shortcuts: bool,
+}
+
+impl ContractionParams {
+    /// witness searches limited to `settled_limit` settled nodes and witnesses with at most `hop_limit` edges
+    pub fn limited(settled_limit: usize, hop_limit: u32) -> Self {
+        Self {
+            settled_limit,
+            hop_limit,
+            skip_dominated_shortcuts: true,
+        }
+    }
+
+    fn is_exact(&self) -> bool {
+        self.settled_limit == usize::MAX && self.hop_limit == u32::MAX
+    }
+}
+
+impl Default for ContractionParams {
+    /// exact witness searches, i.e. only necessary shortcuts are inserted
+    fn default() -> Self {
+        Self::limited(usize::MAX, u32::MAX)
+    }
+}
 
 /// Struct for a Contraction Hierarchy, that is the completely preprocessed
 /// graph augmented by shortcuts and split in an upwards and downward part,
@@ -147,12 +187,12 @@ impl ContractionGraph {
     }
 
     // contract all nodes - full preprocessing
-    fn contract(&mut self) {
-        self.contract_partially(self.nodes.len())
+    fn contract(&mut self, params: &ContractionParams) {
+        self.contract_partially(self.nodes.len(), params)
     }
 
     // contract a fixed number of nodes
-    fn contract_partially(&mut self, mut contraction_count: usize) {
+    fn contract_partially(&mut self, mut contraction_count: usize, params: &ContractionParams) {
         // We utilize split borrows to make node contraction work well with rusts borrowing rules.
         // The graph representation already contains the node in order of increasing rank.
         // We iteratively split of the lowest ranked node.
@@ -162,8 +202,9 @@ impl ContractionGraph {
 
         // we start with the complete graph
         let mut graph = self.partial_graph();
-        // witness search dijkstra data
+        // witness search dijkstra data, exact searches are bidirectional, limited ones only run forward
         let mut data = (DijkstraData::new(graph.nodes.len()), DijkstraData::new(graph.nodes.len()));
+        let mut witness_search = WitnessSearch::new(graph.nodes.len());
 
         // split of the lowest node, the one that will be contracted
         while let Some((node, mut subgraph)) = graph.remove_lowest() {
@@ -172,16 +213,34 @@ impl ContractionGraph {
             } else {
                 contraction_count -= 1;
             }
+            let middle_node_id = subgraph.id_offset - 1;
             // for all pairs of neighbors
             for &(Link { node: from, weight: from_wght }, _) in &node.incoming {
-                for &(Link { node: to, weight: to_wght }, _) in &node.outgoing {
-                    // do witness search to check if we need the shortcut
-                    let (shortcut_required, new_data) = subgraph.shortcut_required(from, to, from_wght + to_wght, data);
-                    data = new_data;
-                    if shortcut_required {
-                        let middle_node_id = subgraph.id_offset - 1;
-                        // insert shortcut
-                        subgraph.insert_or_decrease(from, to, from_wght + to_wght, middle_node_id);
+                // pairs which are dominated by an existing edge don't need a witness search
+                let candidates = node
+                    .outgoing
+                    .iter()
+                    .map(|&(Link { node: to, weight: to_wght }, _)| (to, from_wght + to_wght))
+                    .filter(|&(to, weight)| from != to && !(params.skip_dominated_shortcuts && subgraph.is_dominated(from, to, weight)))
+                    .collect::<Vec<(NodeId, Weight)>>();
+
+                if params.is_exact() {
+                    for (to, weight) in candidates {
+                        // do witness search to check if we need the shortcut
+                        let (shortcut_required, new_data) = subgraph.shortcut_required(from, to, weight, data);
+                        data = new_data;
+                        if shortcut_required {
+                            // insert shortcut
+                            subgraph.insert_or_decrease(from, to, weight, middle_node_id);
+                        }
+                    }
+                } else if let Some(max_weight) = candidates.iter().map(|&(_, weight)| weight).max() {
+                    // one limited witness search for all pairs with the same tail
+                    subgraph.limited_witness_search(&mut witness_search, from, max_weight, params);
+                    for (to, weight) in candidates {
+                        if witness_search.distance(to - subgraph.id_offset) > weight {
+                            subgraph.insert_or_decrease(from, to, weight, middle_node_id);
+                        }
                     }
                 }
             }
@@ -263,6 +322,26 @@ impl<'a> PartialContractionGraph<'a> {
         out_result
     }
 
+    // an existing edge which is at most as long as the shortcut is a witness itself
+    fn is_dominated(&self, from: NodeId, to: NodeId, weight: Weight) -> bool {
+        self.nodes[(from - self.id_offset) as usize]
+            .outgoing
+            .iter()
+            .any(|&(link, _)| link.node == to && link.weight <= weight)
+    }
+
+    // forward search from `from` (local ids are offset by `id_offset`) with the limits of `params`
+    fn limited_witness_search(&self, witness_search: &mut WitnessSearch, from: NodeId, max_weight: Weight, params: &ContractionParams) {
+        let offset = self.id_offset;
+        let nodes = &self.nodes;
+        witness_search.run(from - offset, max_weight, params, move |node| {
+            nodes[node as usize]
+                .outgoing
+                .iter()
+                .map(move |&(Link { node: head, weight }, _)| Link { node: head - offset, weight })
+        });
+    }
+
     fn shortcut_required(
         &self,
         from: NodeId,
@@ -299,16 +378,31 @@ impl<'a> PartialContractionGraph<'a> {
 
 /// Create an overlay graph by contracting a fixed number of nodes
 pub fn overlay<Graph: LinkIterGraph>(graph: &Graph, order: NodeOrder, contraction_count: usize) -> (OwnedGraph, OwnedGraph) {
+    overlay_with_params(graph, order, contraction_count, &ContractionParams::default())
+}
+
+/// Create an overlay graph by contracting a fixed number of nodes with custom witness search limits
+pub fn overlay_with_params<Graph: LinkIterGraph>(
+    graph: &Graph,
+    order: NodeOrder,
+    contraction_count: usize,
+    params: &ContractionParams,
+) -> (OwnedGraph, OwnedGraph) {
     let mut graph = ContractionGraph::new(graph, order);
-    graph.contract_partially(contraction_count);
+    graph.contract_partially(contraction_count, params);
     let ch = graph.into_first_out_graphs();
     (ch.forward, ch.backward)
 }
 
 /// Perform CH Preprocessing
 pub fn contract<Graph: LinkIterGraph>(graph: &Graph, order: NodeOrder) -> ContractionHierarchy {
+    contract_with_params(graph, order, &ContractionParams::default())
+}
+
+/// Perform CH Preprocessing with custom witness search limits
+pub fn contract_with_params<Graph: LinkIterGraph>(graph: &Graph, order: NodeOrder, params: &ContractionParams) -> ContractionHierarchy {
     let mut graph = ContractionGraph::new(graph, order);
-    graph.contract();
+    graph.contract(params);
     graph.into_first_out_graphs()
 }
 
@@ -0,0 +1,183 @@
+//! Greedy bottom-up node ordering for Contraction Hierarchies.
+//!
+//! The next node to contract is the one with the lowest priority,
+//! which consists of the edge difference and the number of already contracted neighbors.
+//! Witness searches are limited to a fixed number of settled nodes by default, so the number of shortcuts is only an estimate.
+//! Priorities are updated lazily when a node is popped and for all neighbors of each contracted node.
+
+use super::witness::WitnessSearch;
+use super::*;
+use crate::datastr::index_heap::IndexdMinHeap;
+
+/// settled node limit of the witness searches in `greedy_order`
+pub const WITNESS_SEARCH_SETTLED_LIMIT: usize = 500;
+
+/// Compute a node order for the given graph by greedy contraction with the usual edge difference heuristic.
+/// The result can be passed to `contract` to build the actual hierarchy.
+pub fn greedy_order<Graph: LinkIterGraph>(graph: &Graph) -> NodeOrder {
+    greedy_order_with_params(graph, &ContractionParams::limited(WITNESS_SEARCH_SETTLED_LIMIT, u32::MAX))
+}
+
+/// Same as `greedy_order`, but with custom witness search limits.
+/// Tighter limits speed up the ordering, but the shortcut estimates get worse and so does the order.
+pub fn greedy_order_with_params<Graph: LinkIterGraph>(graph: &Graph, params: &ContractionParams) -> NodeOrder {
+    NodeOrder::from_node_order(GreedyOrdering::new(graph, *params).run())
+}
+
+struct GreedyOrdering {
+    outgoing: Vec<Vec<Link>>,
+    incoming: Vec<Vec<Link>>,
+    num_contracted_neighbors: Vec<i64>,
+    params: ContractionParams,
+    witness_search: WitnessSearch,
+}
+
+impl GreedyOrdering {
+    fn new<Graph: LinkIterGraph>(graph: &Graph, params: ContractionParams) -> Self {
+        let n = graph.num_nodes();
+        let mut outgoing = vec![Vec::new(); n];
+        let mut incoming = vec![Vec::new(); n];
+
+        // filter out loops and keep only the shortest of parallel edges
+        for tail in 0..n as NodeId {
+            for Link { node: head, weight } in graph.link_iter(tail) {
+                if head != tail && Self::insert_or_decrease(&mut outgoing[tail as usize], head, weight) {
+                    incoming[head as usize].push(Link { node: tail, weight });
+                } else if head != tail {
+                    Self::insert_or_decrease(&mut incoming[head as usize], tail, weight);
+                }
+            }
+        }
+
+        Self {
+            outgoing,
+            incoming,
+            num_contracted_neighbors: vec![0; n],
+            params,
+            witness_search: WitnessSearch::new(n),
+        }
+    }
+
+    // returns true if a new link was inserted
+    fn insert_or_decrease(links: &mut Vec<Link>, node: NodeId, weight: Weight) -> bool {
+        if let Some(link) = links.iter_mut().find(|link| link.node == node) {
+            link.weight = std::cmp::min(link.weight, weight);
+            false
+        } else {
+            links.push(Link { node, weight });
+            true
+        }
+    }
+
+    fn run(&mut self) -> Vec<NodeId> {
+        let n = self.outgoing.len();
+        let mut priorities = IndexdMinHeap::new(n);
+        for node in 0..n as NodeId {
+            let key = self.priority(node);
+            priorities.push(State { key, node });
+        }
+
+        let mut order = Vec::with_capacity(n);
+        while let Some(State { node, .. }) = priorities.pop() {
+            // lazy update: postpone the node if its current priority is worse than the next one
+            let key = self.priority(node);
+            if priorities.peek().map(|next: &State<i64>| key > next.key).unwrap_or(false) {
+                priorities.push(State { key, node });
+                continue;
+            }
+
+            self.contract_node(node, true);
+            let neighbors = self.remove_node(node);
+            order.push(node);
+
+            for neighbor in neighbors {
+                self.num_contracted_neighbors[neighbor as usize] += 1;
+                let key = self.priority(neighbor);
+                priorities.update_key(State { key, node: neighbor });
+            }
+        }
+
+        order
+    }
+
+    fn priority(&mut self, node: NodeId) -> i64 {
+        let num_shortcuts = self.contract_node(node, false) as i64;
+        let num_removed = (self.outgoing[node as usize].len() + self.incoming[node as usize].len()) as i64;
+        num_shortcuts - num_removed + self.num_contracted_neighbors[node as usize]
+    }
+
+    // count (and optionally insert) the shortcuts required to contract the given node
+    fn contract_node(&mut self, node: NodeId, insert: bool) -> usize {
+        let incoming = self.incoming[node as usize].clone();
+        let outgoing = self.outgoing[node as usize].clone();
+        let mut num_shortcuts = 0;
+
+        for &Link {
+            node: from,
+            weight: from_weight,
+        } in &incoming
+        {
+            // pairs which are dominated by an existing edge don't need a witness search
+            let candidates = outgoing
+                .iter()
+                .filter(|link| {
+                    link.node != from
+                        && !(self.params.skip_dominated_shortcuts && self.is_dominated(from, link.node, shortcut_weight(from_weight, link.weight)))
+                })
+                .copied()
+                .collect::<Vec<Link>>();
+            let max_weight = candidates.iter().map(|link| shortcut_weight(from_weight, link.weight)).max();
+
+            if let Some(max_weight) = max_weight {
+                // witness search in the remaining graph without `node`
+                let graph = &self.outgoing;
+                self.witness_search.run(from, max_weight, &self.params, move |tail| {
+                    graph[tail as usize].iter().copied().filter(move |link| link.node != node)
+                });
+
+                for &Link { node: to, weight: to_weight } in &candidates {
+                    let weight = shortcut_weight(from_weight, to_weight);
+                    if self.witness_search.distance(to) > weight {
+                        num_shortcuts += 1;
+
+                        if insert && Self::insert_or_decrease(&mut self.outgoing[from as usize], to, weight) {
+                            self.incoming[to as usize].push(Link { node: from, weight });
+                        } else if insert {
+                            Self::insert_or_decrease(&mut self.incoming[to as usize], from, weight);
+                        }
+                    }
+                }
+            }
+        }
+
+        num_shortcuts
+    }
+
+    // an existing edge which is at most as long as the shortcut is a witness itself
+    fn is_dominated(&self, from: NodeId, to: NodeId, weight: Weight) -> bool {
+        self.outgoing[from as usize].iter().any(|link| link.node == to && link.weight <= weight)
+    }
+
+    // remove all links of a contracted node, returns its remaining neighbors
+    fn remove_node(&mut self, node: NodeId) -> Vec<NodeId> {
+        let outgoing = std::mem::take(&mut self.outgoing[node as usize]);
+        let incoming = std::mem::take(&mut self.incoming[node as usize]);
+
+        for link in &outgoing {
+            self.incoming[link.node as usize].retain(|other| other.node != node);
+        }
+        for link in &incoming {
+            self.outgoing[link.node as usize].retain(|other| other.node != node);
+        }
+
+        let mut neighbors = outgoing.iter().chain(incoming.iter()).map(|link| link.node).collect::<Vec<NodeId>>();
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        neighbors
+    }
+}
+
+// weights of unreachable parts are `INFINITY`, the sum must not overflow
+fn shortcut_weight(first: Weight, second: Weight) -> Weight {
+    std::cmp::min(INFINITY, first.saturating_add(second))
+}
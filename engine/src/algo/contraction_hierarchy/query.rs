@@ -91,58 +91,56 @@ impl Server {
         }
     }
 
-    // Ugly path unpacking.
-    // Should probably switch to something similar as in CCH query, which utilizes the parent pointers in the dijkstra struct.
+    // Path unpacking: follow the parent pointers of both searches from the meeting node
+    // and recursively replace shortcuts by the two arcs over their middle node.
     fn path(&self, query: Query) -> Vec<NodeId> {
-        use std::collections::LinkedList;
-
-        let &(ref forward_middle_nodes, ref backward_middle_nodes) = self.shortcut_middle_nodes.as_ref().unwrap();
-        let mut forwad_path = LinkedList::new();
-        forwad_path.push_front(self.meeting_node);
-
-        while *forwad_path.front().unwrap() != query.from {
-            let next = self.forward_data.predecessors[*forwad_path.front().unwrap() as usize].0;
-            let mut shortcut_stack = vec![next];
-
-            while let Some(node) = shortcut_stack.pop() {
-                let next = self.forward_data.predecessors[*forwad_path.front().unwrap() as usize].0;
-                let middle = forward_middle_nodes[self.forward.edge_indices(node, next).next().unwrap().0 as usize];
-                if middle < self.forward.num_nodes() as NodeId {
-                    shortcut_stack.push(node);
-                    shortcut_stack.push(middle);
-                } else {
-                    forwad_path.push_front(node);
-                }
-            }
-        }
-
-        let mut backward_path = LinkedList::new();
-        backward_path.push_back(self.meeting_node);
+        let from = self.order.rank(query.from);
+        let to = self.order.rank(query.to);
 
-        while *backward_path.back().unwrap() != query.to {
-            let next = self.backward_data.predecessors[*backward_path.back().unwrap() as usize].0;
-            let mut shortcut_stack = vec![next];
+        let mut up_path = vec![self.meeting_node];
+        while *up_path.last().unwrap() != from {
+            up_path.push(self.forward_data.predecessors[*up_path.last().unwrap() as usize].0);
+        }
+        up_path.reverse();
 
-            while let Some(node) = shortcut_stack.pop() {
-                let next = self.backward_data.predecessors[*backward_path.back().unwrap() as usize].0;
-                let middle = backward_middle_nodes[self.backward.edge_indices(node, next).next().unwrap().0 as usize];
-                if middle < self.backward.num_nodes() as NodeId {
-                    shortcut_stack.push(node);
-                    shortcut_stack.push(middle);
-                } else {
-                    backward_path.push_back(node);
-                }
-            }
+        let mut down_path = vec![self.meeting_node];
+        while *down_path.last().unwrap() != to {
+            down_path.push(self.backward_data.predecessors[*down_path.last().unwrap() as usize].0);
         }
 
-        forwad_path.pop_back();
-        forwad_path.append(&mut backward_path);
+        let mut path = vec![from];
+        for arc in up_path.windows(2).chain(down_path.windows(2)) {
+            self.unpack_arc(arc[0], arc[1], &mut path);
+        }
 
-        for node in &mut forwad_path {
+        for node in &mut path {
             *node = self.order.node(*node);
         }
 
-        forwad_path.into_iter().collect()
+        path
+    }
+
+    // append the unpacked nodes of the arc from `tail` to `head` (excluding `tail`) to the path
+    fn unpack_arc(&self, tail: NodeId, head: NodeId, path: &mut Vec<NodeId>) {
+        let (forward_middle_nodes, backward_middle_nodes) = self.shortcut_middle_nodes.as_ref().unwrap();
+        let n = self.forward.num_nodes() as NodeId;
+
+        let mut stack = vec![(tail, head)];
+        while let Some((tail, head)) = stack.pop() {
+            // upward arcs are stored in the forward graph at their tail, downward arcs in the backward graph at their head
+            let middle = if tail < head {
+                forward_middle_nodes[self.forward.edge_indices(tail, head).next().unwrap().0 as usize]
+            } else {
+                backward_middle_nodes[self.backward.edge_indices(head, tail).next().unwrap().0 as usize]
+            };
+
+            if middle < n {
+                stack.push((middle, head));
+                stack.push((tail, middle));
+            } else {
+                path.push(head);
+            }
+        }
     }
 }
 
@@ -0,0 +1,72 @@
+//! Limited one-to-many witness searches, shared by the greedy ordering and the contraction.
+
+use super::*;
+use crate::datastr::index_heap::IndexdMinHeap;
+
+pub(super) struct WitnessSearch {
+    distances: Vec<Weight>,
+    hops: Vec<u32>,
+    touched: Vec<NodeId>,
+    queue: IndexdMinHeap<State<Weight>>,
+}
+
+impl WitnessSearch {
+    pub(super) fn new(n: usize) -> Self {
+        Self {
+            distances: vec![INFINITY; n],
+            hops: vec![0; n],
+            touched: Vec::new(),
+            queue: IndexdMinHeap::new(n),
+        }
+    }
+
+    /// Dijkstra from `source` over the links returned by `links`, limited by distance, number of settled nodes and number of hops.
+    /// The tentative distances remain available through `distance` until the next search.
+    pub(super) fn run<I: Iterator<Item = Link>>(&mut self, source: NodeId, max_weight: Weight, params: &ContractionParams, mut links: impl FnMut(NodeId) -> I) {
+        for node in self.touched.drain(..) {
+            self.distances[node as usize] = INFINITY;
+        }
+
+        self.distances[source as usize] = 0;
+        self.hops[source as usize] = 0;
+        self.touched.push(source);
+        self.queue.push(State { key: 0, node: source });
+
+        let mut num_settled = 0;
+        while let Some(State { key: distance, node }) = self.queue.pop() {
+            if distance > max_weight || num_settled >= params.settled_limit {
+                break;
+            }
+            num_settled += 1;
+
+            // longer witnesses are not considered, the node is settled nevertheless
+            let hops = self.hops[node as usize];
+            if hops >= params.hop_limit {
+                continue;
+            }
+
+            for Link { node: head, weight } in links(node) {
+                let new_distance = std::cmp::min(INFINITY, distance.saturating_add(weight));
+                if new_distance < self.distances[head as usize] {
+                    if self.distances[head as usize] == INFINITY {
+                        self.touched.push(head);
+                    }
+                    self.distances[head as usize] = new_distance;
+                    self.hops[head as usize] = hops + 1;
+
+                    if self.queue.contains_index(head as usize) {
+                        self.queue.decrease_key(State { key: new_distance, node: head });
+                    } else {
+                        self.queue.push(State { key: new_distance, node: head });
+                    }
+                }
+            }
+        }
+
+        self.queue.clear();
+    }
+
+    pub(super) fn distance(&self, node: NodeId) -> Weight {
+        self.distances[node as usize]
+    }
+}
@@ -6,9 +6,12 @@
 
 use super::*;
 use crate::{datastr::graph::first_out_graph::degrees_to_first_out, report::*};
+use rayon::prelude::*;
 use std::{
     cmp::Ordering,
+    io::Write,
     ops::{Index, IndexMut},
+    path::Path,
 };
 
 #[derive(Debug, PartialEq)]
@@ -140,10 +143,18 @@ impl<'a, Graph: LinkIterable<NodeIdT>> ContractionGraph<'a, Graph> {
     }
 
     /// Main preprocessing work - chordal completion
-    pub fn contract(mut self) -> ContractedGraph<'a, Graph> {
+    pub fn contract(self) -> ContractedGraph<'a, Graph> {
+        self.contract_with_progress(&progress::ignore_progress)
+    }
+
+    /// Chordal completion, the progress callback is invoked with the number of contracted nodes
+    pub fn contract_with_progress(mut self, progress: progress::ProgressCallback) -> ContractedGraph<'a, Graph> {
         report!("algo", "CCH Contraction");
         report_time_with_key("CCH Contraction", "contraction", || {
             let mut num_shortcut_arcs = 0;
+            let n = self.nodes.len();
+            let progress_step = std::cmp::max(n / 100, 1);
+            let mut num_contracted = 0;
             // We utilize split borrows to make node contraction work well with rusts borrowing rules.
             // The graph representation already contains the node in order of increasing rank.
             // We iteratively split of the lowest ranked node.
@@ -166,6 +177,11 @@ impl<'a, Graph: LinkIterable<NodeIdT>> ContractionGraph<'a, Graph> {
                 }
 
                 graph = subgraph;
+
+                num_contracted += 1;
+                if num_contracted % progress_step == 0 || num_contracted == n {
+                    progress(num_contracted, n);
+                }
             }
 
             report!("num_arcs_inserted", num_shortcut_arcs);
@@ -174,6 +190,156 @@ impl<'a, Graph: LinkIterable<NodeIdT>> ContractionGraph<'a, Graph> {
         ContractedGraph(self)
     }
 
+    /// Chordal completion in parallel, yields the same graph as `contract`.
+    ///
+    /// The elimination tree of the chordal supergraph is computed upfront on the original graph.
+    /// The final upward neighborhood of a node is the union of its original upward neighborhood and the
+    /// neighborhoods of its children (without the node itself), so all nodes with the same height in the elimination tree
+    /// are independent and get contracted in parallel, level by level.
+    pub fn contract_parallel(mut self) -> ContractedGraph<'a, Graph> {
+        report!("algo", "Parallel CCH Contraction");
+        report_time_with_key("CCH Contraction", "contraction", || {
+            let n = self.nodes.len();
+            let parent = self.elimination_tree();
+
+            // 1. group the nodes by their height in the elimination tree, children always have a lower height
+            let mut height = vec![0usize; n];
+            for node in 0..n {
+                if let Some(parent) = parent[node] {
+                    height[parent as usize] = std::cmp::max(height[parent as usize], height[node] + 1);
+                }
+            }
+
+            let num_levels = height.iter().max().map(|&max_height| max_height + 1).unwrap_or(0);
+            let mut levels = vec![Vec::new(); num_levels];
+            let mut children = vec![Vec::new(); n];
+            for node in 0..n {
+                levels[height[node]].push(node);
+                if let Some(parent) = parent[node] {
+                    children[parent as usize].push(node);
+                }
+            }
+
+            // 2. merge the neighborhoods of all children into their parent, level by level
+            let mut num_shortcut_arcs = 0;
+            for level in &levels {
+                let nodes = &self.nodes;
+                let merged = level
+                    .par_iter()
+                    .map(|&node| {
+                        let mut merged = Node {
+                            edges: nodes[node].edges.clone(),
+                        };
+                        for &child in &children[node] {
+                            // the parent is the lowest neighbor of each child
+                            merged.merge_neighbors(&nodes[child].edges[1..]);
+                        }
+                        (node, merged)
+                    })
+                    .collect::<Vec<_>>();
+
+                for (node, merged) in merged {
+                    num_shortcut_arcs += merged.edges.len() - self.nodes[node].edges.len();
+                    self.nodes[node] = merged;
+                }
+            }
+
+            report!("num_arcs_inserted", num_shortcut_arcs);
+        });
+
+        ContractedGraph(self)
+    }
+
+    /// Chordal completion with reduced peak memory, yields the same graph as `contract`.
+    ///
+    /// The upward neighborhood of a node is final once the node is contracted, so it is appended to `head_file`
+    /// and released immediately. Only the neighborhoods of nodes that were not yet contracted are kept in memory,
+    /// the final `head` array is read back from disk once all nodes are contracted.
+    /// This only bounds the memory of the contraction, building the CCH from the result still needs it all in memory (see `contract_low_memory`).
+    pub fn contract_low_memory(mut self, head_file: &Path) -> std::io::Result<(UnweightedOwnedGraph, NodeOrder, &'a Graph)> {
+        report!("algo", "Low Memory CCH Contraction");
+        let degrees = report_time_with_key("CCH Contraction", "contraction", || -> std::io::Result<Vec<EdgeId>> {
+            let mut writer = std::io::BufWriter::new(std::fs::File::create(head_file)?);
+            let mut degrees = Vec::with_capacity(self.nodes.len());
+            let mut num_shortcut_arcs = 0;
+
+            for node_id in 0..self.nodes.len() {
+                let edges = std::mem::take(&mut self.nodes[node_id].edges);
+
+                if let Some((&lowest_neighbor, other_neighbors)) = edges.split_first() {
+                    let lowest = &mut self.nodes[lowest_neighbor as usize];
+                    let prev_deg = lowest.edges.len();
+                    lowest.merge_neighbors(other_neighbors);
+                    num_shortcut_arcs += lowest.edges.len() - prev_deg;
+                }
+
+                writer.write_all(edges.data_bytes())?;
+                degrees.push(edges.len() as EdgeId);
+            }
+
+            writer.flush()?;
+            report!("num_arcs_inserted", num_shortcut_arcs);
+            Ok(degrees)
+        })?;
+
+        // all neighborhoods were moved to disk, release the remaining (empty) node storage at once
+        drop(std::mem::take(&mut self.nodes));
+
+        let first_out: Vec<EdgeId> = degrees_to_first_out(degrees.into_iter()).collect();
+        let head = Vec::<NodeId>::load_from(head_file)?;
+        debug_assert_eq!(*first_out.last().unwrap() as usize, head.len());
+
+        Ok((UnweightedOwnedGraph::new(first_out, head), self.node_order, self.original_graph))
+    }
+
+    // Elimination tree of the chordal supergraph, computed on the (uncontracted) upward neighborhoods.
+    // This is Liu's algorithm with path compression, so the fill-in never has to be materialized.
+    fn elimination_tree(&self) -> Vec<Option<NodeId>> {
+        let n = self.nodes.len();
+
+        // downward neighborhoods of each node
+        let mut first_down = vec![0 as EdgeId; n + 1];
+        for node in &self.nodes {
+            for &neighbor in &node.edges {
+                first_down[neighbor as usize + 1] += 1;
+            }
+        }
+        for node in 0..n {
+            first_down[node + 1] += first_down[node];
+        }
+        let mut down = vec![0 as NodeId; first_down[n] as usize];
+        let mut next_down = first_down.clone();
+        for (node, neighbors) in self.nodes.iter().enumerate() {
+            for &neighbor in &neighbors.edges {
+                down[next_down[neighbor as usize] as usize] = node as NodeId;
+                next_down[neighbor as usize] += 1;
+            }
+        }
+
+        let mut parent = vec![None; n];
+        let mut ancestor: Vec<Option<NodeId>> = vec![None; n];
+        for node in 0..n {
+            for &lower in &down[first_down[node] as usize..first_down[node + 1] as usize] {
+                // walk up to the root of the subtree containing `lower`, compress the path towards `node`
+                let mut current = lower;
+                while let Some(next) = ancestor[current as usize] {
+                    if next == node as NodeId {
+                        break;
+                    }
+                    ancestor[current as usize] = Some(node as NodeId);
+                    current = next;
+                }
+
+                if ancestor[current as usize].is_none() {
+                    ancestor[current as usize] = Some(node as NodeId);
+                    parent[current as usize] = Some(node as NodeId);
+                }
+            }
+        }
+
+        parent
+    }
+
     fn partial_graph(&mut self) -> PartialContractionGraph {
         PartialContractionGraph {
             nodes: &mut self.nodes[..],
@@ -243,3 +409,44 @@ fn adjancecy_lists_to_first_out_graph(adjancecy_lists: Vec<Node>) -> UnweightedO
 
     UnweightedOwnedGraph::new(first_out, head)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parallel_contraction_matches_sequential() {
+        // two triangles connected by a path, plus an isolated node
+        let first_out = vec![0, 2, 5, 7, 9, 12, 14, 16, 16];
+        let head = vec![1, 2, 0, 2, 3, 0, 1, 1, 4, 3, 5, 6, 4, 6, 4, 5];
+        let graph = UnweightedFirstOutGraph::new(&first_out[..], &head[..]);
+
+        for order in [vec![0, 1, 2, 3, 4, 5, 6, 7], vec![3, 0, 6, 1, 5, 7, 2, 4], vec![7, 6, 5, 4, 3, 2, 1, 0]] {
+            let node_order = NodeOrder::from_node_order(order);
+            let (sequential, _, _) = ContractionGraph::new(&graph, node_order.clone()).contract().decompose();
+            let (parallel, _, _) = ContractionGraph::new(&graph, node_order).contract_parallel().decompose();
+
+            assert_eq!(sequential.first_out(), parallel.first_out());
+            assert_eq!(sequential.head(), parallel.head());
+        }
+    }
+
+    #[test]
+    fn low_memory_contraction_matches_sequential() {
+        let first_out = vec![0, 2, 5, 7, 9, 12, 14, 16, 16];
+        let head = vec![1, 2, 0, 2, 3, 0, 1, 1, 4, 3, 5, 6, 4, 6, 4, 5];
+        let graph = UnweightedFirstOutGraph::new(&first_out[..], &head[..]);
+        let head_file = std::env::temp_dir().join(format!("cch_low_memory_contraction_{}", std::process::id()));
+
+        for order in [vec![0, 1, 2, 3, 4, 5, 6, 7], vec![3, 0, 6, 1, 5, 7, 2, 4]] {
+            let node_order = NodeOrder::from_node_order(order);
+            let (sequential, _, _) = ContractionGraph::new(&graph, node_order.clone()).contract().decompose();
+            let (low_memory, _, _) = ContractionGraph::new(&graph, node_order).contract_low_memory(&head_file).unwrap();
+
+            assert_eq!(sequential.first_out(), low_memory.first_out());
+            assert_eq!(sequential.head(), low_memory.head());
+        }
+
+        std::fs::remove_file(&head_file).unwrap();
+    }
+}
@@ -326,39 +326,39 @@ pub fn customize_perfect(mut customized: Customized<CCH, &CCH>) -> Customized<Di
         let mut backward_head = Vec::with_capacity(m);
         let mut backward_weight = Vec::with_capacity(m);
 
-        let forward_cch_edge_to_orig_arc = Vecs::from_iters(
-            cch.forward_cch_edge_to_orig_arc
-                .iter()
-                .zip(forward.weight().iter())
-                .filter(|(_, w)| **w < INFINITY)
-                .map(|(slc, _)| slc.iter().copied()),
-        );
-        let backward_cch_edge_to_orig_arc = Vecs::from_iters(
-            cch.backward_cch_edge_to_orig_arc
-                .iter()
-                .zip(backward.weight().iter())
-                .filter(|(_, w)| **w < INFINITY)
-                .map(|(slc, _)| slc.iter().copied()),
-        );
+        // the original arcs of all remaining edges, has to be filtered exactly like the edges themselves
+        let mut forward_orig_arcs = Vec::with_capacity(m);
+        let mut backward_orig_arcs = Vec::with_capacity(m);
 
         for node in 0..n as NodeId {
             let edge_ids = cch.neighbor_edge_indices_usize(node);
-            for (link, &customized_weight) in LinkIterable::<Link>::link_iter(&forward, node).zip(&upward_orig[edge_ids.clone()]) {
+            for ((link, &customized_weight), edge_id) in LinkIterable::<Link>::link_iter(&forward, node)
+                .zip(&upward_orig[edge_ids.clone()])
+                .zip(edge_ids.clone())
+            {
                 if link.weight < INFINITY && link.weight >= customized_weight {
                     forward_head.push(link.node);
                     forward_weight.push(link.weight);
+                    forward_orig_arcs.push(&cch.forward_cch_edge_to_orig_arc[edge_id]);
                 }
             }
-            for (link, &customized_weight) in LinkIterable::<Link>::link_iter(&backward, node).zip(&downward_orig[edge_ids.clone()]) {
+            for ((link, &customized_weight), edge_id) in LinkIterable::<Link>::link_iter(&backward, node)
+                .zip(&downward_orig[edge_ids.clone()])
+                .zip(edge_ids.clone())
+            {
                 if link.weight < INFINITY && link.weight >= customized_weight {
                     backward_head.push(link.node);
                     backward_weight.push(link.weight);
+                    backward_orig_arcs.push(&cch.backward_cch_edge_to_orig_arc[edge_id]);
                 }
             }
             forward_first_out.push(forward_head.len() as EdgeId);
             backward_first_out.push(backward_head.len() as EdgeId);
         }
 
+        let forward_cch_edge_to_orig_arc = Vecs::from_iters(forward_orig_arcs.into_iter().map(|arcs| arcs.iter().copied()));
+        let backward_cch_edge_to_orig_arc = Vecs::from_iters(backward_orig_arcs.into_iter().map(|arcs| arcs.iter().copied()));
+
         let forward_inverted = ReversedGraphWithEdgeIds::reversed(&UnweightedFirstOutGraph::new(&forward_first_out[..], &forward_head[..]));
         let backward_inverted = ReversedGraphWithEdgeIds::reversed(&UnweightedFirstOutGraph::new(&backward_first_out[..], &backward_head[..]));
 
@@ -23,7 +23,24 @@ pub fn customize<'a, 'b: 'a>(cch: &'a CCH, metric: &'b TDGraph) -> CustomizedGra
     CustomizedGraph::new(metric, &cch.first_out, &cch.head, upward, downward)
 }
 
+/// Run CATCHUp customization with reduced peak memory.
+///
+/// Shortcut TTFs are moved into a temporary file at `spill_file` as soon as they are final
+/// and read back whenever they are needed for a lower triangle.
+/// This trades running time for feasibility on graphs where the TTFs do not fit into memory.
+/// The file is removed afterwards.
+pub fn customize_with_spill<'a, 'b: 'a>(cch: &'a CCH, metric: &'b TDGraph, spill_file: &Path) -> std::io::Result<CustomizedGraph<'a>> {
+    let spill = TTFSpillFile::create(spill_file)?;
+    let (upward, downward) = customize_internal_with_spill(cch, metric, Some(&spill));
+    report!("num_bytes_spilled", spill.len());
+    Ok(CustomizedGraph::new(metric, &cch.first_out, &cch.head, upward, downward))
+}
+
 pub fn customize_internal<'a, 'b: 'a>(cch: &'a CCH, metric: &'b TDGraph) -> (Vec<Shortcut>, Vec<Shortcut>) {
+    customize_internal_with_spill(cch, metric, None)
+}
+
+fn customize_internal_with_spill<'a, 'b: 'a>(cch: &'a CCH, metric: &'b TDGraph, spill: Option<&TTFSpillFile>) -> (Vec<Shortcut>, Vec<Shortcut>) {
     report!("algo", "Floating TDCCH Customization");
 
     let n = (cch.first_out.len() - 1) as NodeId;
@@ -250,10 +267,10 @@ pub fn customize_internal<'a, 'b: 'a>(cch: &'a CCH, metric: &'b TDGraph) -> (Vec
             cch,
             // routines created in this function
             // we customize many cells in parallel - so iterate over triangles sequentially
-            create_customization_fn(cch, metric, SeqIter(cch)),
+            create_customization_fn(cch, metric, SeqIter(cch), spill),
             // the final separator can only be customized, once everything else is done, but it still takes up a significant amount of time
             // But we can still parallelize the processing of edges from one node within this separator.
-            create_customization_fn(cch, metric, ParIter(cch)),
+            create_customization_fn(cch, metric, ParIter(cch), spill),
         );
 
         report_time("TD-CCH Customization", || {
@@ -385,7 +402,7 @@ pub fn customize_internal<'a, 'b: 'a>(cch: &'a CCH, metric: &'b TDGraph) -> (Vec
         report!("num_performed_unnecessary_links", UNNECESSARY_LINKED.load(Ordering::Relaxed));
     }
     report!("approx", f64::from(APPROX));
-    report!("approx_threshold", APPROX_THRESHOLD);
+    report!("approx_threshold", approx_threshold());
 
     if cfg!(feature = "tdcch-postcustomization") {
         // do perfect bound based customization again, because we now have better bounds and can get rid of some additional shortcuts
@@ -469,7 +486,12 @@ pub fn customize_internal<'a, 'b: 'a>(cch: &'a CCH, metric: &'b TDGraph) -> (Vec
 
 // Encapsulates the creation of the CATCHUp main customization lambdas
 // The function signature gives us some additional control of lifetimes and stuff
-fn create_customization_fn<'s, F: 's>(cch: &'s CCH, metric: &'s TDGraph, merge_iter: F) -> impl Fn(Range<usize>, usize, &mut [Shortcut], &mut [Shortcut]) + 's
+fn create_customization_fn<'s, F: 's>(
+    cch: &'s CCH,
+    metric: &'s TDGraph,
+    merge_iter: F,
+    spill: Option<&'s TTFSpillFile>,
+) -> impl Fn(Range<usize>, usize, &mut [Shortcut], &mut [Shortcut]) + 's
 where
     for<'p> F: ForEachIter<'p, 's, Shortcut>,
 {
@@ -480,6 +502,23 @@ where
             let upward_active = &mut upward_above[0..cch.neighbor_edge_indices(current_node as NodeId).len()];
             let (downward_below, downward_above) = downward.split_at_mut(cch.first_out[current_node as usize] as usize - edge_offset);
             let downward_active = &mut downward_above[0..cch.neighbor_edge_indices(current_node as NodeId).len()];
+
+            // we need the functions of all edges in lower triangles below the current nodes edges
+            if spill.is_some() || cfg!(feature = "tdcch-compact-ttf-cache") {
+                for_each_lower_triangle_edge(cch, current_node as NodeId, |edge_id| {
+                    for shortcut in [
+                        &mut upward_below[edge_id as usize - edge_offset],
+                        &mut downward_below[edge_id as usize - edge_offset],
+                    ] {
+                        if let Some(spill) = spill {
+                            shortcut.load_spilled_cache(spill);
+                        }
+                        #[cfg(feature = "tdcch-compact-ttf-cache")]
+                        shortcut.decompress_cache();
+                    }
+                });
+            }
+
             let shortcut_graph = PartialShortcutGraph::new(metric, upward_below, downward_below, edge_offset);
 
             debug_assert_eq!(upward_active.len(), cch.degree(current_node as NodeId));
@@ -559,6 +598,26 @@ where
                 },
             );
 
+            // keep only the spilled or compressed functions until they are needed again
+            if spill.is_some() || cfg!(feature = "tdcch-compact-ttf-cache") {
+                for_each_lower_triangle_edge(cch, current_node as NodeId, |edge_id| {
+                    for shortcut in [&mut upward[edge_id as usize - edge_offset], &mut downward[edge_id as usize - edge_offset]] {
+                        shortcut.release_spilled_cache();
+                        #[cfg(feature = "tdcch-compact-ttf-cache")]
+                        shortcut.release_decompressed_cache();
+                    }
+                });
+                for edge_idx in cch.neighbor_edge_indices_usize(current_node as NodeId) {
+                    for shortcut in [&mut upward[edge_idx - edge_offset], &mut downward[edge_idx - edge_offset]] {
+                        if let Some(spill) = spill {
+                            shortcut.spill_cache(spill);
+                        }
+                        #[cfg(feature = "tdcch-compact-ttf-cache")]
+                        shortcut.compress_cache();
+                    }
+                }
+            }
+
             // free up space - we will never need the explicit functions again during customization
             for (_, Reversed(EdgeIdT(edge_id))) in cch.inverted.link_iter(current_node as NodeId) {
                 upward[edge_id as usize - edge_offset].clear_plf();
@@ -570,6 +629,22 @@ where
     }
 }
 
+// All edges which are part of a lower triangle of an edge of `node`.
+// These are the edges to `node` from below and the edges from the same lower nodes to the upper neighbors of `node`.
+fn for_each_lower_triangle_edge(cch: &CCH, node: NodeId, mut f: impl FnMut(EdgeId)) {
+    let lower_neighbors: Vec<NodeId> = cch.inverted.link_iter(node).map(|(NodeIdT(lower), _)| lower).collect();
+    for (_, Reversed(EdgeIdT(edge_id))) in cch.inverted.link_iter(node) {
+        f(edge_id);
+    }
+    for upper in cch.neighbor_iter(node) {
+        for (NodeIdT(lower), Reversed(EdgeIdT(edge_id))) in cch.inverted.link_iter(upper) {
+            if lower_neighbors.binary_search(&lower).is_ok() {
+                f(edge_id);
+            }
+        }
+    }
+}
+
 trait ForEachIter<'s, 'c, S> {
     fn for_each(
         &self,
@@ -1099,7 +1174,7 @@ pub fn customize_live<'a, 'b: 'a>(cch: &'a CCH, metric: &'b LiveGraph) {
         report!("num_performed_unnecessary_links", UNNECESSARY_LINKED.load(Ordering::Relaxed));
     }
     report!("approx", f64::from(APPROX));
-    report!("approx_threshold", APPROX_THRESHOLD);
+    report!("approx_threshold", approx_threshold());
 
     // TODO Combine bounds
 }
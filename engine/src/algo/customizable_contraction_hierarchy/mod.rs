@@ -4,10 +4,10 @@ use super::*;
 use crate::{
     datastr::node_order::NodeOrder,
     io::*,
-    report::{benchmark::*, block_reporting},
+    report::{benchmark::*, block_reporting, progress},
     util::{in_range_option::InRangeOption, *},
 };
-use std::{cmp::Ordering, ops::Range};
+use std::{cmp::Ordering, ops::Range, path::Path};
 
 mod contraction;
 use contraction::*;
@@ -19,6 +19,8 @@ use separator_decomposition::*;
 mod reorder;
 use crate::util::in_range_option::Sentinel;
 pub use reorder::*;
+mod order_refinement;
+pub use order_refinement::*;
 pub mod query;
 
 /// Execute first phase, that is metric independent preprocessing.
@@ -26,9 +28,32 @@ pub fn contract<Graph: LinkIterable<NodeIdT> + EdgeIdGraph>(graph: &Graph, node_
     CCH::new(ContractionGraph::new(graph, node_order).contract())
 }
 
+/// Same as `contract`, the progress callback is invoked with the number of contracted nodes.
+pub fn contract_with_progress<Graph: LinkIterable<NodeIdT> + EdgeIdGraph>(graph: &Graph, node_order: NodeOrder, progress: progress::ProgressCallback) -> CCH {
+    CCH::new(ContractionGraph::new(graph, node_order).contract_with_progress(progress))
+}
+
+/// Same as `contract`, but the chordal completion runs in parallel over independent subtrees of the elimination tree.
+pub fn contract_parallel<Graph: LinkIterable<NodeIdT> + EdgeIdGraph>(graph: &Graph, node_order: NodeOrder) -> CCH {
+    CCH::new(ContractionGraph::new(graph, node_order).contract_parallel())
+}
+
+/// Same as `contract`, but finished neighborhoods are moved to `head_file` during contraction to reduce the peak memory usage.
+///
+/// Only the chordal completion itself is streamed. Afterwards, the complete `head` array is read back
+/// and all other CCH structures are built in memory, which makes up the actual peak:
+/// `head` and `tail` (4 bytes per CCH arc each), the `first_idx` of both arc mappings (8 bytes per arc each,
+/// up to twice that while growing) and the inverted graph (8 bytes per arc, plus temporary adjacency lists of up to 16 bytes per arc while building it).
+/// So expect roughly 40 to 64 bytes per CCH arc, i.e. 40 to 64 GB for a billion shortcuts.
+pub fn contract_low_memory<Graph: LinkIterable<NodeIdT> + EdgeIdGraph>(graph: &Graph, node_order: NodeOrder, head_file: &Path) -> std::io::Result<CCH> {
+    let (contracted, order, orig) = ContractionGraph::new(graph, node_order).contract_low_memory(head_file)?;
+    Ok(CCH::new_from(orig, order, contracted))
+}
+
 /// A struct containing all metric independent preprocessing data of CCHs.
 /// This includes on top of the chordal supergraph (the "contracted" graph),
 /// several other structures like the elimination tree, a mapping from cch edge ids to original edge ids and the inverted graph.
+#[derive(Clone)]
 pub struct CCH {
     pub first_out: Vec<EdgeId>,
     pub head: Vec<NodeId>,
@@ -62,9 +87,18 @@ impl<'g, Graph: EdgeIdGraph> ReconstructPrepared<CCH> for CCHReconstrctor<'g, Gr
 
 impl CCH {
     pub fn fix_order_and_build(graph: &(impl LinkIterable<NodeIdT> + EdgeIdGraph), order: NodeOrder) -> Self {
+        Self::fix_order_and_build_with_progress(graph, order, &progress::ignore_progress)
+    }
+
+    /// Same as `fix_order_and_build`, the progress callback covers both contraction runs, i.e. the total is twice the number of nodes.
+    pub fn fix_order_and_build_with_progress(
+        graph: &(impl LinkIterable<NodeIdT> + EdgeIdGraph),
+        order: NodeOrder,
+        progress: progress::ProgressCallback,
+    ) -> Self {
         let cch = {
             let _blocked = block_reporting();
-            contract(graph, order)
+            contract_with_progress(graph, order, &|done, total| progress(done, 2 * total))
         };
         let order = CCHReordering {
             cch: &cch,
@@ -72,7 +106,28 @@ impl CCH {
             longitude: &[],
         }
         .reorder_for_seperator_based_customization();
-        contract(graph, order)
+        contract_with_progress(graph, order, &|done, total| progress(total + done, 2 * total))
+    }
+
+    /// Reorder the separator nodes of the top levels of `order` such that they follow a linear order based on their geographic position.
+    /// The separator tree remains unchanged, so the resulting order can be passed to `fix_order_and_build` repeatedly.
+    pub fn coordinate_aware_order(graph: &(impl LinkIterable<NodeIdT> + EdgeIdGraph), order: NodeOrder, latitude: &[f32], longitude: &[f32]) -> NodeOrder {
+        if latitude.is_empty() || longitude.is_empty() {
+            return order;
+        }
+        assert_eq!(latitude.len(), graph.num_nodes());
+        assert_eq!(longitude.len(), graph.num_nodes());
+
+        let cch = {
+            let _blocked = block_reporting();
+            contract(graph, order)
+        };
+        CCHReordering {
+            cch: &cch,
+            latitude,
+            longitude,
+        }
+        .reorder()
     }
 
     fn new<Graph: EdgeIdGraph>(contracted_graph: ContractedGraph<Graph>) -> CCH {
@@ -277,6 +332,11 @@ pub trait CCHT {
     fn forward_inverted(&self) -> &ReversedGraphWithEdgeIds;
     fn backward_inverted(&self) -> &ReversedGraphWithEdgeIds;
 
+    /// Mapping of forward (upward) arcs to the original arcs they represent
+    fn forward_cch_edge_to_orig_arc(&self) -> &Vecs<EdgeIdT>;
+    /// Mapping of backward (downward) arcs to the original arcs they represent
+    fn backward_cch_edge_to_orig_arc(&self) -> &Vecs<EdgeIdT>;
+
     /// Get elimination tree (actually forest).
     /// The tree is represented as a slice of length `n`.
     /// The entry with index `x` contains the parent node in the tree of node `x`.
@@ -355,6 +415,12 @@ impl CCHT for CCH {
     fn backward_inverted(&self) -> &ReversedGraphWithEdgeIds {
         &self.inverted
     }
+    fn forward_cch_edge_to_orig_arc(&self) -> &Vecs<EdgeIdT> {
+        &self.forward_cch_edge_to_orig_arc
+    }
+    fn backward_cch_edge_to_orig_arc(&self) -> &Vecs<EdgeIdT> {
+        &self.backward_cch_edge_to_orig_arc
+    }
 
     fn node_order(&self) -> &NodeOrder {
         &self.node_order
@@ -450,7 +516,7 @@ impl DirectedCCH {
     }
 
     fn backward(&self) -> Slcs<EdgeId, NodeId> {
-        Slcs(&self.forward_first_out, &self.forward_head)
+        Slcs(&self.backward_first_out, &self.backward_head)
     }
 
     pub fn mem_size(&self) -> usize {
@@ -496,6 +562,12 @@ impl CCHT for DirectedCCH {
     fn backward_inverted(&self) -> &ReversedGraphWithEdgeIds {
         &self.backward_inverted
     }
+    fn forward_cch_edge_to_orig_arc(&self) -> &Vecs<EdgeIdT> {
+        &self.forward_cch_edge_to_orig_arc
+    }
+    fn backward_cch_edge_to_orig_arc(&self) -> &Vecs<EdgeIdT> {
+        &self.backward_cch_edge_to_orig_arc
+    }
 
     fn node_order(&self) -> &NodeOrder {
         &self.node_order
@@ -0,0 +1,155 @@
+//! Metric-aware local refinement of CCH nested dissection orders.
+//!
+//! Swapping two consecutive nodes `u` (rank `r`) and `v` (rank `r + 1`) only changes the upward neighborhoods of these two nodes,
+//! the chordal supergraph above `r + 1` stays the same.
+//! If `u` and `v` are adjacent, the number of shortcuts changes by `|N_r(v)| - |N_r(u)|`,
+//! where `N_r(x)` denotes the neighborhood of `x` after eliminating all nodes with rank below `r`.
+//! Swaps are only performed within a separator, so the separator tree and therefore the parallel customization remain valid.
+
+use super::*;
+use crate::report::*;
+
+/// Greedily swap adjacent separator nodes as long as this reduces the number of shortcuts.
+/// If the number of shortcuts stays the same, the node whose outgoing edges are more expensive under `weights`
+/// (e.g. heavily congested junctions) is contracted first.
+/// `weights` contains one entry per original edge. Runs at most `max_rounds` rounds, each one requires a full contraction.
+/// The swaps and removed arcs of each round are reported in the `refinement_rounds` collection.
+pub fn refine_order_with_metric(graph: &(impl LinkIterable<NodeIdT> + EdgeIdGraph), order: NodeOrder, weights: &[Weight], max_rounds: usize) -> NodeOrder {
+    assert_eq!(weights.len(), graph.num_arcs());
+
+    // metric cost of each node: sum of its outgoing edge weights
+    let node_costs = (0..graph.num_nodes() as NodeId)
+        .map(|node| graph.neighbor_edge_indices_usize(node).map(|edge| weights[edge] as u64).sum::<u64>())
+        .collect::<Vec<u64>>();
+
+    let mut order = order.order().to_vec();
+    let mut rounds_ctxt = push_collection_context("refinement_rounds".to_string());
+    for round in 0..max_rounds {
+        let cch = {
+            let _blocked = block_reporting();
+            contract(graph, NodeOrder::from_node_order(order.clone()))
+        };
+
+        let swaps = OrderRefinement::new(&cch, &node_costs).find_swaps();
+
+        let _round_ctxt = rounds_ctxt.push_collection_item();
+        report!("round", round);
+        report!("num_swaps", swaps.len());
+        report!("num_removed_arcs", swaps.iter().map(|&(_, delta)| -delta).sum::<i64>());
+        report!("num_arcs", cch.num_arcs());
+
+        if swaps.is_empty() {
+            break;
+        }
+
+        for (rank, _) in swaps {
+            order.swap(rank as usize, rank as usize + 1);
+        }
+    }
+
+    NodeOrder::from_node_order(order)
+}
+
+struct OrderRefinement<'c> {
+    cch: &'c CCH,
+    node_costs: &'c [u64],
+    // id of the separator (cell) each rank belongs to
+    separator_ids: Vec<usize>,
+    is_upward_neighbor: Vec<bool>,
+    is_present: Vec<bool>,
+}
+
+impl<'c> OrderRefinement<'c> {
+    fn new(cch: &'c CCH, node_costs: &'c [u64]) -> Self {
+        let n = cch.num_nodes();
+        let mut separator_ids = vec![0; n];
+        let mut next_id = 0;
+        Self::assign_separator_ids(&cch.separators(), &mut separator_ids, &mut next_id);
+
+        Self {
+            cch,
+            node_costs,
+            separator_ids,
+            is_upward_neighbor: vec![false; n],
+            is_present: vec![false; n],
+        }
+    }
+
+    fn assign_separator_ids(separators: &SeparatorTree, separator_ids: &mut [usize], next_id: &mut usize) {
+        for &node in &separators.nodes {
+            separator_ids[node as usize] = *next_id;
+        }
+        *next_id += 1;
+
+        for child in &separators.children {
+            Self::assign_separator_ids(child, separator_ids, next_id);
+        }
+    }
+
+    fn cost(&self, rank: NodeId) -> u64 {
+        self.node_costs[self.cch.node_order().node(rank) as usize]
+    }
+
+    /// Collect non-overlapping swaps of ranks `r` and `r + 1` along with the resulting change of the number of arcs.
+    fn find_swaps(&mut self) -> Vec<(NodeId, i64)> {
+        let mut swaps = Vec::new();
+        let n = self.cch.num_nodes() as NodeId;
+
+        let mut rank = 0;
+        while rank + 1 < n {
+            let (lower, upper) = (rank, rank + 1);
+
+            if self.separator_ids[lower as usize] == self.separator_ids[upper as usize] && self.cch.neighbor_iter(lower).any(|node| node == upper) {
+                let delta = self.num_neighbors_before_elimination(upper, lower) as i64 - self.cch.neighbor_iter(lower).len() as i64;
+
+                if delta < 0 || (delta == 0 && self.cost(lower) < self.cost(upper)) {
+                    swaps.push((lower, delta));
+                    // the next pair would overlap with this swap
+                    rank += 2;
+                    continue;
+                }
+            }
+
+            rank += 1;
+        }
+
+        swaps
+    }
+
+    /// Number of neighbors of `node` right before `predecessor` (the node ranked directly below) is eliminated.
+    /// This includes `predecessor` itself and all upward neighbors which are either connected by an original edge
+    /// or by a shortcut created by a lower node other than `predecessor`.
+    fn num_neighbors_before_elimination(&mut self, node: NodeId, predecessor: NodeId) -> usize {
+        let edges = self.cch.neighbor_edge_indices_usize(node);
+
+        for (upper, edge) in self.cch.neighbor_iter(node).zip(edges) {
+            self.is_upward_neighbor[upper as usize] = true;
+            if !self.cch.forward_cch_edge_to_orig_arc[edge].is_empty() || !self.cch.backward_cch_edge_to_orig_arc[edge].is_empty() {
+                self.is_present[upper as usize] = true;
+            }
+        }
+
+        for (NodeIdT(lower), _) in self.cch.inverted.link_iter(node) {
+            if lower == predecessor {
+                continue;
+            }
+
+            for upper in self.cch.neighbor_iter(lower) {
+                if self.is_upward_neighbor[upper as usize] {
+                    self.is_present[upper as usize] = true;
+                }
+            }
+        }
+
+        let mut count = 1;
+        for upper in self.cch.neighbor_iter(node) {
+            if self.is_present[upper as usize] {
+                count += 1;
+            }
+            self.is_upward_neighbor[upper as usize] = false;
+            self.is_present[upper as usize] = false;
+        }
+
+        count
+    }
+}
@@ -145,6 +145,33 @@ impl<'a, CCH: CCHT, CCHB: std::borrow::Borrow<CCH>> Server<CCH, CCHB> {
         path
     }
 
+    /// Original edge ids along the shortest path of the last query, all shortcuts are unpacked.
+    /// Parallel original edges are resolved by their weight in `metric`, which should be the customized metric.
+    fn orig_edge_path<G: EdgeRandomAccessGraph<Link>>(&mut self, query: Query, metric: &G) -> Vec<EdgeId> {
+        let path = self.path(query);
+        let cch = self.customized.cch.borrow();
+        let order = cch.node_order();
+
+        path.windows(2)
+            .map(|arc| {
+                let (tail, head) = (order.rank(arc[0]), order.rank(arc[1]));
+
+                // upward arcs are stored at their tail in the forward graph, downward arcs at their head in the backward graph
+                let orig_arcs = if tail < head {
+                    &cch.forward_cch_edge_to_orig_arc()[find_arc(cch.forward_first_out(), cch.forward_head(), tail, head)]
+                } else {
+                    &cch.backward_cch_edge_to_orig_arc()[find_arc(cch.backward_first_out(), cch.backward_head(), head, tail)]
+                };
+
+                orig_arcs
+                    .iter()
+                    .map(|&EdgeIdT(edge_id)| edge_id)
+                    .min_by_key(|&edge_id| metric.link(edge_id).weight)
+                    .expect("unpacked arc without original edge")
+            })
+            .collect()
+    }
+
     /// Unpack path from a start node (the meeting node of the CCH query), so that parent pointers point along the unpacked path.
     fn unpack_path(
         origin: NodeId,
@@ -177,8 +204,21 @@ impl<'a, CCH: CCHT, CCHB: std::borrow::Borrow<CCH>> Server<CCH, CCHB> {
     }
 }
 
+// id of the arc from `tail` to `head`, neighbors are sorted ascending
+fn find_arc(first_out: &[EdgeId], head: &[NodeId], tail: NodeId, target: NodeId) -> usize {
+    let range = first_out[tail as usize] as usize..first_out[tail as usize + 1] as usize;
+    range.start + head[range].binary_search(&target).expect("arc not contained in the CCH")
+}
+
 pub struct PathServerWrapper<'s, CCH, CCHB>(&'s mut Server<CCH, CCHB>, Query);
 
+impl<'s, CCH: CCHT, CCHB: std::borrow::Borrow<CCH>> PathServerWrapper<'s, CCH, CCHB> {
+    /// Shortest path as original edge ids, parallel edges are resolved by their weight in the given metric.
+    pub fn orig_edge_path<G: EdgeRandomAccessGraph<Link>>(&mut self, metric: &G) -> Vec<EdgeId> {
+        Server::orig_edge_path(self.0, self.1, metric)
+    }
+}
+
 impl<'s, CCH: CCHT, CCHB: std::borrow::Borrow<CCH>> PathServer for PathServerWrapper<'s, CCH, CCHB> {
     type NodeInfo = NodeId;
     type EdgeInfo = ();
@@ -2,6 +2,19 @@
 
 use super::*;
 
+/// Quality metrics of a single cell of the separator tree
+#[derive(Debug, Clone)]
+pub struct SeparatorStatistics {
+    pub id: usize,
+    pub parent: Option<usize>,
+    pub depth: usize,
+    pub separator_size: usize,
+    pub num_nodes: usize,
+    pub num_children: usize,
+    /// Share of the largest child cell among all nodes below the separator, 0.5 is perfectly balanced for two children
+    pub balance: f64,
+}
+
 #[derive(Debug)]
 pub struct SeparatorTree {
     pub nodes: Vec<NodeId>,
@@ -35,6 +48,34 @@ impl SeparatorTree {
         }
     }
 
+    /// Collect size, depth and balance of each cell in pre-order.
+    /// The virtual root (which has an empty separator) has depth 0.
+    pub fn statistics(&self) -> Vec<SeparatorStatistics> {
+        let mut statistics = Vec::new();
+        self.collect_statistics(None, 0, &mut statistics);
+        statistics
+    }
+
+    fn collect_statistics(&self, parent: Option<usize>, depth: usize, statistics: &mut Vec<SeparatorStatistics>) {
+        let id = statistics.len();
+        let num_below = self.num_nodes - self.nodes.len();
+        let largest_child = self.children.iter().map(|child| child.num_nodes).max().unwrap_or(0);
+
+        statistics.push(SeparatorStatistics {
+            id,
+            parent,
+            depth,
+            separator_size: self.nodes.len(),
+            num_nodes: self.num_nodes,
+            num_children: self.children.len(),
+            balance: if num_below == 0 { 0.0 } else { largest_child as f64 / num_below as f64 },
+        });
+
+        for child in &self.children {
+            child.collect_statistics(Some(id), depth + 1, statistics);
+        }
+    }
+
     /// Reconstruct separator tree from a preprocessed CCH
     pub fn new(elimination_tree: &[InRangeOption<NodeId>]) -> Self {
         let mut children = vec![Vec::new(); elimination_tree.len()];
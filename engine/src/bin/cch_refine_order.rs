@@ -0,0 +1,37 @@
+// Locally refine a nested dissection order for a given metric.
+// Takes a directory as argument, which has to contain the graph (in RoutingKit format) and a nested disection order.
+// Optional arguments: the name of the order file (default: cch_perm), the name of the metric file (default: travel_time)
+// and the maximum number of refinement rounds (default: 5).
+// Writes the refined order to <order_file>_refined in the graph directory.
+
+use std::{env, error::Error, path::Path};
+
+use rust_road_router::{
+    algo::customizable_contraction_hierarchy::*,
+    cli::CliErr,
+    datastr::{graph::*, node_order::NodeOrder},
+    io::*,
+    report::*,
+};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let arg = &args.next().ok_or(CliErr("No directory arg given"))?;
+    let order_file = args.next().unwrap_or_else(|| "cch_perm".to_string());
+    let metric_file = args.next().unwrap_or_else(|| "travel_time".to_string());
+    let max_rounds = args.next().map(|rounds| rounds.parse()).transpose()?.unwrap_or(5);
+    let path = Path::new(arg);
+
+    let graph = UnweightedOwnedGraph::reconstruct_from(&path)?;
+    let weights: Vec<Weight> = Vec::load_from(path.join(&metric_file))?;
+    let order = NodeOrder::from_node_order(Vec::load_from(path.join(&order_file))?);
+
+    let num_arcs_before = contract(&graph, order.clone()).num_arcs();
+    let order = report_time("Order refinement", || refine_order_with_metric(&graph, order, &weights, max_rounds));
+    let num_arcs_after = contract(&graph, order.clone()).num_arcs();
+    println!("CCH arcs: {} before, {} after refinement", num_arcs_before, num_arcs_after);
+
+    order.order().to_vec().write_to(&path.join(format!("{}_refined", order_file)))?;
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+// Export quality metrics of the separator decomposition of a nested dissection order.
+// Takes a directory as argument, which has to contain the graph (in RoutingKit format) and a nested disection order.
+// An optional second argument gives the name of the order file (default: cch_perm).
+// Writes separator_stats.csv (one line per cell) and separator_stats.json (summary and cells) to the graph directory.
+
+use std::{env, error::Error, fs::File, io::Write, path::Path};
+
+use rust_road_router::{
+    algo::customizable_contraction_hierarchy::{separator_decomposition::SeparatorStatistics, *},
+    cli::CliErr,
+    datastr::{graph::*, node_order::NodeOrder},
+    io::*,
+    report::*,
+};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let arg = &args.next().ok_or(CliErr("No directory arg given"))?;
+    let order_file = args.next().unwrap_or_else(|| "cch_perm".to_string());
+    let path = Path::new(arg);
+
+    let graph = UnweightedOwnedGraph::reconstruct_from(&path)?;
+    let order = NodeOrder::from_node_order(Vec::load_from(path.join(&order_file))?);
+    let cch = contract(&graph, order);
+
+    let statistics = cch.separators().statistics();
+
+    // the virtual root has an empty separator, the top-level separators are its children
+    let cells = &statistics[1..];
+    let max_depth = cells.iter().map(|cell| cell.depth).max().unwrap_or(0);
+    let max_separator_size = cells.iter().map(|cell| cell.separator_size).max().unwrap_or(0);
+    let top_level_separator_size = cells.iter().filter(|cell| cell.depth == 1).map(|cell| cell.separator_size).max().unwrap_or(0);
+    let inner_cells = cells.iter().filter(|cell| cell.num_children > 1).collect::<Vec<_>>();
+    let avg_balance = inner_cells.iter().map(|cell| cell.balance).sum::<f64>() / inner_cells.len().max(1) as f64;
+    let max_balance = inner_cells.iter().map(|cell| cell.balance).fold(0.0, f64::max);
+
+    println!(
+        "{} cells, max depth: {}, top-level separator: {}, max separator: {}, balance: {} (avg), {} (max)",
+        cells.len(),
+        max_depth,
+        top_level_separator_size,
+        max_separator_size,
+        avg_balance,
+        max_balance
+    );
+
+    write_csv(&statistics, &path.join("separator_stats.csv"))?;
+
+    let json = json!({
+        "num_nodes": graph.num_nodes(),
+        "num_cch_arcs": cch.num_arcs(),
+        "num_cells": cells.len(),
+        "max_depth": max_depth,
+        "top_level_separator_size": top_level_separator_size,
+        "max_separator_size": max_separator_size,
+        "avg_balance": avg_balance,
+        "max_balance": max_balance,
+        "cells": statistics.iter().map(|cell| json!({
+            "id": cell.id,
+            "parent": cell.parent,
+            "depth": cell.depth,
+            "separator_size": cell.separator_size,
+            "num_nodes": cell.num_nodes,
+            "num_children": cell.num_children,
+            "balance": cell.balance,
+        })).collect::<Vec<_>>(),
+    });
+    serde_json::to_writer(File::create(path.join("separator_stats.json"))?, &json)?;
+
+    Ok(())
+}
+
+fn write_csv(statistics: &[SeparatorStatistics], path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut file = File::create(path)?;
+    writeln!(file, "id,parent,depth,separator_size,num_nodes,num_children,balance")?;
+
+    for cell in statistics {
+        writeln!(
+            file,
+            "{},{},{},{},{},{},{}",
+            cell.id,
+            cell.parent.map(|parent| parent.to_string()).unwrap_or_default(),
+            cell.depth,
+            cell.separator_size,
+            cell.num_nodes,
+            cell.num_children,
+            cell.balance
+        )?;
+    }
+
+    Ok(())
+}
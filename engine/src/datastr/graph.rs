@@ -168,6 +168,18 @@ pub trait EdgeRandomAccessGraph<E>: EdgeIdGraph {
     fn link(&self, edge_id: EdgeId) -> E;
 }
 
+/// Trait for graph types which allow iterating over all arcs at once,
+/// as `(tail, head, edge id, weight)` tuples ordered by edge id.
+/// Customized CCH graphs are covered through their `FirstOutGraph` views.
+pub trait EdgeIterGraph: Graph {
+    type EdgeIter<'a>: Iterator<Item = (NodeId, NodeId, EdgeId, Weight)> + 'a
+    where
+        Self: 'a;
+
+    /// Get an iterator over all arcs of the graph.
+    fn edge_iter(&self) -> Self::EdgeIter<'_>;
+}
+
 /// Build the line graph (the turn expanded graph).
 /// The callback should return the turn costs between the two links
 /// with the given ids and `None` if the turn is forbidden.
@@ -284,6 +284,25 @@ where
     }
 }
 
+impl<FirstOutContainer, HeadContainer, WeightContainer> EdgeIterGraph for FirstOutGraph<FirstOutContainer, HeadContainer, WeightContainer>
+where
+    FirstOutContainer: AsRef<[EdgeId]>,
+    HeadContainer: AsRef<[NodeId]>,
+    WeightContainer: AsRef<[Weight]>,
+{
+    type EdgeIter<'a>
+        = impl Iterator<Item = (NodeId, NodeId, EdgeId, Weight)> + 'a
+    where
+        Self: 'a;
+
+    fn edge_iter(&self) -> Self::EdgeIter<'_> {
+        (0..self.num_nodes() as NodeId).flat_map(move |node| {
+            self.neighbor_edge_indices(node)
+                .map(move |edge_id| (node, self.head()[edge_id as usize], edge_id, self.weight()[edge_id as usize]))
+        })
+    }
+}
+
 impl<FirstOutContainer, HeadContainer, WeightContainer> LinkIterable<(NodeIdT, EdgeIdT)> for FirstOutGraph<FirstOutContainer, HeadContainer, WeightContainer>
 where
     FirstOutContainer: AsRef<[EdgeId]>,
@@ -495,6 +514,7 @@ impl Reconstruct for UnweightedOwnedGraph {
     }
 }
 
+#[derive(Clone)]
 pub struct ReversedGraphWithEdgeIds {
     first_out: Vec<EdgeId>,
     head: Vec<NodeId>,
@@ -0,0 +1,124 @@
+//! Compact storage for travel time functions.
+//!
+//! Points are moved onto the `EPSILON` grid and delta encoded: the first point is stored in grid steps,
+//! all following ones as the difference to their predecessor.
+//! That halves the memory of a function compared to plain `TTFPoint`s.
+//! All algorithms still work on `TTFPoint` slices, so functions are compressed when they are stored
+//! and have to be decompressed before they can be used again.
+
+use super::*;
+use std::convert::TryFrom;
+
+const STEPS_PER_SECOND: f64 = 1.0 / EPSILON;
+
+fn grid_steps(x: f64) -> i64 {
+    (x * STEPS_PER_SECOND).round() as i64
+}
+
+fn from_grid_steps(steps: i64) -> f64 {
+    steps as f64 / STEPS_PER_SECOND
+}
+
+/// Delta encoded travel time function.
+#[derive(Debug)]
+pub struct CompactTTF {
+    first: (i64, i64),
+    // time and value difference to the previous point in grid steps
+    deltas: Box<[(u32, i32)]>,
+}
+
+impl CompactTTF {
+    /// Encode the given points.
+    /// `None` when the difference between two consecutive points does not fit into the compact representation
+    /// (with the default `EPSILON` more than roughly 71 minutes between two points)
+    /// or when two points collapse onto the same grid step.
+    /// Such functions have to be kept uncompressed, but they usually have few points anyway.
+    pub fn compress(ipps: &[TTFPoint]) -> Option<Self> {
+        let to_grid = |p: &TTFPoint| (grid_steps(f64::from(p.at)), grid_steps(f64::from(p.val)));
+        let first = to_grid(ipps.first()?);
+
+        let mut prev = first;
+        let deltas = ipps[1..]
+            .iter()
+            .map(|p| {
+                let cur = to_grid(p);
+                let delta_at = u32::try_from(cur.0 - prev.0).ok().filter(|&delta| delta > 0)?;
+                let delta_val = i32::try_from(cur.1 - prev.1).ok()?;
+                prev = cur;
+                Some((delta_at, delta_val))
+            })
+            .collect::<Option<Box<[_]>>>()?;
+
+        Some(CompactTTF { first, deltas })
+    }
+
+    /// Decode into regular points, the result deviates at most half a grid step from the compressed points.
+    pub fn decompress(&self) -> Box<[TTFPoint]> {
+        let (mut at, mut val) = self.first;
+        let to_point = |at, val| TTFPoint {
+            at: Timestamp::new(from_grid_steps(at)),
+            val: FlWeight::new(from_grid_steps(val)),
+        };
+
+        std::iter::once(to_point(at, val))
+            .chain(self.deltas.iter().map(|&(delta_at, delta_val)| {
+                at += i64::from(delta_at);
+                val += i64::from(delta_val);
+                to_point(at, val)
+            }))
+            .collect()
+    }
+
+    pub fn num_points(&self) -> usize {
+        self.deltas.len() + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip_stays_within_grid() {
+        let ipps = [
+            TTFPoint {
+                at: Timestamp::new(50000.0),
+                val: FlWeight::new(12.345_678_9),
+            },
+            TTFPoint {
+                at: Timestamp::new(51000.000_000_3),
+                val: FlWeight::new(1200.5),
+            },
+            TTFPoint {
+                at: Timestamp::new(53000.0),
+                val: FlWeight::new(12.345_678_9),
+            },
+        ];
+        let compressed = CompactTTF::compress(&ipps).unwrap();
+        assert_eq!(compressed.num_points(), 3);
+
+        let decompressed = compressed.decompress();
+        assert_eq!(decompressed.len(), 3);
+        for (original, restored) in ipps.iter().zip(decompressed.iter()) {
+            assert!(original.at.fuzzy_eq(restored.at));
+            assert!(original.val.fuzzy_eq(restored.val));
+        }
+        // equal values stay equal
+        assert_eq!(decompressed.first().unwrap().val, decompressed.last().unwrap().val);
+    }
+
+    #[test]
+    fn incompressible_functions() {
+        let point = |at: f64, val: f64| TTFPoint {
+            at: Timestamp::new(at),
+            val: FlWeight::new(val),
+        };
+        // time difference beyond the range of `u32` grid steps
+        if EPSILON * f64::from(u32::MAX) < 86400.0 {
+            assert!(CompactTTF::compress(&[point(0.0, 10.0), point(86400.0, 10.0)]).is_none());
+        }
+        // points on the same grid step
+        assert!(CompactTTF::compress(&[point(0.0, 10.0), point(0.1 * EPSILON, 10.0)]).is_none());
+        assert!(CompactTTF::compress(&[]).is_none());
+    }
+}
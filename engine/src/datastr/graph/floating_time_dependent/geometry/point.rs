@@ -91,12 +91,27 @@ pub fn intersection_point(f1: &TTFPoint, f2: &TTFPoint, g1: &TTFPoint, g2: &TTFP
         at: f1.at + frac * (f2.at - f1.at),
         val: f1.val + frac * (f2.val - f1.val),
     };
-    debug_assert!(interpolate_linear(f1, f2, result.at).fuzzy_eq(result.val));
-    debug_assert!(interpolate_linear(g1, g2, result.at).fuzzy_eq(result.val));
+    debug_assert!(fuzzy_on_line(f1, f2, &result));
+    debug_assert!(fuzzy_on_line(g1, g2, &result));
+    #[cfg(not(feature = "tdcch-fixed-point-time"))]
     debug_assert!(interpolate_linear(f1, f2, result.at).fuzzy_eq(interpolate_linear(g1, g2, result.at)));
     result
 }
 
+#[cfg(not(feature = "tdcch-fixed-point-time"))]
+fn fuzzy_on_line(p1: &TTFPoint, p2: &TTFPoint, point: &TTFPoint) -> bool {
+    interpolate_linear(p1, p2, point.at).fuzzy_eq(point.val)
+}
+
+// With fixed point times, `at` of the point may be off by up to one grid step, so the value may be off by up to the slope times that.
+#[cfg(feature = "tdcch-fixed-point-time")]
+fn fuzzy_on_line(p1: &TTFPoint, p2: &TTFPoint, point: &TTFPoint) -> bool {
+    let grid_step = FlWeight::new(EPSILON);
+    let before = interpolate_linear(p1, p2, point.at - grid_step);
+    let after = interpolate_linear(p1, p2, point.at + grid_step);
+    std::cmp::min(before, after).fuzzy_leq(point.val) && point.val.fuzzy_leq(std::cmp::max(before, after))
+}
+
 /// True when r (or p->r) lies counterclockwise of p->q
 pub fn counter_clockwise(p: &TTFPoint, q: &TTFPoint, r: &TTFPoint) -> bool {
     ccw(p, q, r) == -1
@@ -214,7 +214,7 @@ impl LiveShortcut {
                     ACTUALLY_LINKED.fetch_add(1, Relaxed);
                 }
                 // link functions
-                let linked = first_plf.link(&second_plf, live_from, live_until);
+                let linked = first_plf.link(&second_plf, live_from, live_until, buffers);
 
                 self.upper_bound = min(self.upper_bound, PartialATTF::from(&linked).static_upper_bound());
                 debug_assert!(
@@ -223,7 +223,7 @@ impl LiveShortcut {
                     self.lower_bound,
                     self.upper_bound
                 );
-                self.cache = Some(linked.into());
+                self.cache = Some(linked.into_pooled_box(&mut buffers.link_results));
                 self.sources = Sources::One(other_data);
                 return;
             }
@@ -232,7 +232,7 @@ impl LiveShortcut {
             let self_plf = self.live_ttf(shortcut_graph).unwrap();
 
             // link TTFs in triangle
-            let linked_ipps = first_plf.link(&second_plf, live_from, live_until);
+            let linked_ipps = first_plf.link(&second_plf, live_from, live_until, buffers);
             if cfg!(feature = "detailed-stats") {
                 ACTUALLY_LINKED.fetch_add(1, Relaxed);
             }
@@ -254,18 +254,19 @@ impl LiveShortcut {
                     self.lower_bound,
                     self.upper_bound
                 );
-                if cfg!(feature = "tdcch-approx") && linked_ipps.num_points() > APPROX_THRESHOLD {
+                if cfg!(feature = "tdcch-approx") && linked_ipps.num_points() > approx_threshold() {
                     let old = linked_ipps.num_points();
                     if cfg!(feature = "detailed-stats") {
                         CONSIDERED_FOR_APPROX.fetch_add(old, Relaxed);
                     }
-                    let linked_ipps = linked.approximate(buffers);
+                    let approximated = linked.approximate(buffers);
                     if cfg!(feature = "detailed-stats") {
-                        SAVED_BY_APPROX.fetch_add(old as isize - linked_ipps.num_points() as isize, Relaxed);
+                        SAVED_BY_APPROX.fetch_add(old as isize - approximated.num_points() as isize, Relaxed);
                     }
-                    self.cache = Some(linked_ipps);
+                    self.cache = Some(approximated);
+                    linked_ipps.recycle(&mut buffers.link_results);
                 } else {
-                    self.cache = Some(linked_ipps.into());
+                    self.cache = Some(linked_ipps.into_pooled_box(&mut buffers.link_results));
                 }
                 self.sources = Sources::One(other_data);
                 if cfg!(feature = "detailed-stats") {
@@ -274,6 +275,7 @@ impl LiveShortcut {
                 return;
             } else if self.upper_bound.fuzzy_lt(other_lower_bound) {
                 // current upper bound always better than linked lower bound - keep whatever we currently have
+                linked_ipps.recycle(&mut buffers.link_results);
                 return;
             }
 
@@ -294,7 +296,8 @@ impl LiveShortcut {
                 let (self_ipps, other_ipps) = other_target.storage().top_plfs();
                 PartialPiecewiseLinearFunction::new(self_ipps).merge(&PartialPiecewiseLinearFunction::new(other_ipps), start, end, &mut buffers.buffer)
             });
-            if cfg!(feature = "tdcch-approx") && merged.num_points() > APPROX_THRESHOLD {
+            linked_ipps.recycle(&mut buffers.link_results);
+            if cfg!(feature = "tdcch-approx") && merged.num_points() > approx_threshold() {
                 let old = merged.num_points();
                 if cfg!(feature = "detailed-stats") {
                     CONSIDERED_FOR_APPROX.fetch_add(old, Relaxed);
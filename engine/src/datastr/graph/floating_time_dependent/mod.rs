@@ -16,7 +16,9 @@
 use super::*;
 
 mod piecewise_linear_function;
-pub use piecewise_linear_function::{PartialPiecewiseLinearFunction, PeriodicPiecewiseLinearFunction, UpdatedPiecewiseLinearFunction, PLF};
+pub use piecewise_linear_function::{
+    BoundedApproximation, PartialPiecewiseLinearFunction, PeriodicPiecewiseLinearFunction, UpdatedPiecewiseLinearFunction, PLF,
+};
 
 mod geometry;
 pub use self::geometry::TTFPoint;
@@ -45,6 +47,12 @@ pub use self::shortcut_graph::*;
 pub mod travel_time_function;
 pub use travel_time_function::*;
 
+pub mod compact_ttf;
+pub use compact_ttf::*;
+
+pub mod spill;
+pub use spill::*;
+
 #[allow(clippy::float_cmp)]
 mod time {
     use std::{
@@ -56,18 +64,60 @@ mod time {
 
     // TODO switch to something ULP based?
     // implications for division with EPSILON like divisors?
-    /// Global epsilon for float comparisons
+    /// Global epsilon for float comparisons.
+    /// Should match the resolution of the input data, e.g. profiles converted from integer milliseconds need a larger one.
+    /// Can be overridden through the TDCCH_EPSILON env var
+    #[cfg(not(override_tdcch_epsilon))]
     pub const EPSILON: f64 = 0.000_001;
+    #[cfg(override_tdcch_epsilon)]
+    pub const EPSILON: f64 = include!(concat!(env!("OUT_DIR"), "/TDCCH_EPSILON"));
+
+    // With the `tdcch-fixed-point-time` feature, all times and weights are kept on a grid of integer multiples of `EPSILON` (microseconds by default).
+    // The values are still stored as `f64` (so the public representation stays the same), but every constructed value is rounded to the grid
+    // and comparisons are done on the integer grid steps instead of on floats with `EPSILON` tolerance.
+    // Interpolated values are rounded independently, so they may be off by one grid step, which is the only tolerance left.
+    // Ratios obtained through division are not rounded, they are no times.
+    #[cfg(feature = "tdcch-fixed-point-time")]
+    const STEPS_PER_SECOND: f64 = 1.0 / EPSILON;
 
+    /// Integer grid steps of a time value
+    #[cfg(feature = "tdcch-fixed-point-time")]
+    #[inline]
+    fn grid_steps(x: f64) -> i64 {
+        (x * STEPS_PER_SECOND).round() as i64
+    }
+
+    #[cfg(feature = "tdcch-fixed-point-time")]
+    #[inline]
+    fn snap(x: f64) -> f64 {
+        (x * STEPS_PER_SECOND).round() / STEPS_PER_SECOND
+    }
+
+    #[cfg(not(feature = "tdcch-fixed-point-time"))]
+    #[inline]
+    fn snap(x: f64) -> f64 {
+        x
+    }
+
+    #[cfg(not(feature = "tdcch-fixed-point-time"))]
     fn fuzzy_eq(x: f64, y: f64) -> bool {
         (x - y).abs() <= EPSILON
     }
+    #[cfg(feature = "tdcch-fixed-point-time")]
+    fn fuzzy_eq(x: f64, y: f64) -> bool {
+        (grid_steps(x) - grid_steps(y)).abs() <= 1
+    }
     fn fuzzy_neq(x: f64, y: f64) -> bool {
         !fuzzy_eq(x, y)
     }
+    #[cfg(not(feature = "tdcch-fixed-point-time"))]
     fn fuzzy_lt(x: f64, y: f64) -> bool {
         (x - y) < -EPSILON
     }
+    #[cfg(feature = "tdcch-fixed-point-time")]
+    fn fuzzy_lt(x: f64, y: f64) -> bool {
+        grid_steps(x) + 1 < grid_steps(y)
+    }
     fn fuzzy_leq(x: f64, y: f64) -> bool {
         !fuzzy_lt(y, x)
     }
@@ -78,7 +128,7 @@ mod time {
     pub struct FlWeight(pub f64);
 
     /// Absolute epsilon for CATCHUp approximation in seconds.
-    /// Can be overriden through the TDCCH_APPROX env var
+    /// Can be overridden through the TDCCH_APPROX env var
     #[cfg(not(override_tdcch_approx))]
     pub const APPROX: FlWeight = FlWeight(1.0);
     #[cfg(override_tdcch_approx)]
@@ -90,10 +140,10 @@ mod time {
         pub const ZERO: Self = FlWeight(0.0);
         pub const INVALID: Self = FlWeight(-1.0);
 
-        /// New Weight from `f64`
+        /// New Weight from `f64`, rounded to the `EPSILON` grid with `tdcch-fixed-point-time`
         pub fn new(t: f64) -> Self {
             debug_assert_ne!(t, NAN);
-            FlWeight(t)
+            FlWeight(snap(t))
         }
 
         /// Fuzzy comparison (based on `EPSILON`) of two weights
@@ -137,7 +187,7 @@ mod time {
 
     impl<W: Borrow<FlWeight>> AddAssign<W> for FlWeight {
         fn add_assign(&mut self, rhs: W) {
-            self.0 += rhs.borrow().0;
+            self.0 = snap(self.0 + rhs.borrow().0);
         }
     }
 
@@ -186,7 +236,8 @@ mod time {
 
         fn div(self, other: FlWeight) -> Self::Output {
             debug_assert!(fuzzy_neq(other.0, 0.0));
-            FlWeight::new(self.0 / other.0)
+            // the result is a ratio and thus not rounded
+            FlWeight(self.0 / other.0)
         }
     }
 
@@ -217,10 +268,10 @@ mod time {
         pub const NEVER: Self = Timestamp(2_147_483_647.0);
         pub const ZERO: Self = Timestamp(0.0);
 
-        /// New `Timestamp` from `f64`.
+        /// New `Timestamp` from `f64`, rounded to the `EPSILON` grid with `tdcch-fixed-point-time`
         pub fn new(t: f64) -> Self {
             debug_assert_ne!(t, NAN);
-            Timestamp(t)
+            Timestamp(snap(t))
         }
 
         /// Fuzzy equality comparison (based on `EPSILON`) of two timestamps
@@ -297,6 +348,26 @@ mod time {
             Self::ZERO
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn fuzzy_comparisons_tolerate_rounding_errors() {
+            let sum = Timestamp::new(0.1) + FlWeight::new(0.2);
+            assert!(sum.fuzzy_eq(Timestamp::new(0.3)));
+            assert!(!sum.fuzzy_lt(Timestamp::new(0.3)));
+            assert!(Timestamp::new(0.3).fuzzy_lt(Timestamp::new(0.3 + 10.0 * EPSILON)));
+
+            if cfg!(feature = "tdcch-fixed-point-time") {
+                assert_eq!(sum, Timestamp::new(0.3));
+                assert_eq!(Timestamp::new(1.0 + 0.4 * EPSILON), Timestamp::new(1.0));
+                // ratios keep their full precision
+                assert_eq!(FlWeight::new(1.0) / FlWeight::new(3.0), FlWeight(1.0 / 3.0));
+            }
+        }
+    }
 }
 pub use self::time::{FlWeight, Timestamp, APPROX, EPSILON};
 
@@ -346,7 +417,7 @@ pub fn period() -> Timestamp {
     Timestamp::new(86_400.0)
 }
 
-use std::sync::atomic::{AtomicIsize, AtomicUsize};
+use std::sync::atomic::{AtomicIsize, AtomicU64, AtomicUsize};
 
 // Stat counters for customization
 pub static NODES_CUSTOMIZED: AtomicUsize = AtomicUsize::new(0);
@@ -360,6 +431,21 @@ pub static ACTIVE_SHORTCUTS: AtomicUsize = AtomicUsize::new(0);
 pub static UNNECESSARY_LINKED: AtomicUsize = AtomicUsize::new(0);
 pub static CONSIDERED_FOR_APPROX: AtomicUsize = AtomicUsize::new(0);
 pub static SAVED_BY_APPROX: AtomicIsize = AtomicIsize::new(0);
+// bits of the largest `f64` error of any `approximate_with_error` call, non-negative floats order like their bits
+static MAX_APPROX_ERROR: AtomicU64 = AtomicU64::new(0);
+
+/// Largest error of all bounded approximations since the last reset
+pub fn max_approximation_error() -> FlWeight {
+    FlWeight::new(f64::from_bits(MAX_APPROX_ERROR.load(std::sync::atomic::Ordering::Relaxed)))
+}
+
+pub fn reset_max_approximation_error() {
+    MAX_APPROX_ERROR.store(0, std::sync::atomic::Ordering::Relaxed);
+}
+
+fn record_approximation_error(error: FlWeight) {
+    MAX_APPROX_ERROR.fetch_max(f64::from(error).max(0.0).to_bits(), std::sync::atomic::Ordering::Relaxed);
+}
 
 /// Data structure to reduce allocations during customization.
 /// Stores multiple PLFs consecutively in one `Vec`
@@ -501,10 +587,54 @@ impl PLFTarget for Vec<TTFPoint> {
     }
 }
 
+/// Pool of point vectors for temporary link results.
+/// Instead of allocating (and growing) a fresh `Vec` for every link operation, vectors are taken from the pool
+/// and handed back once the result was either discarded or copied into its final exactly sized allocation.
+#[derive(Debug, Default)]
+pub struct PLFPool {
+    free: Vec<Vec<TTFPoint>>,
+}
+
+impl PLFPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get an empty vector, possibly with capacity left from earlier use
+    pub fn take(&mut self) -> Vec<TTFPoint> {
+        self.free.pop().unwrap_or_default()
+    }
+
+    /// Return a vector to the pool, its content is discarded
+    pub fn give_back(&mut self, mut points: Vec<TTFPoint>) {
+        points.clear();
+        self.free.push(points);
+    }
+
+    /// Copy the points into an exactly sized boxed slice and return the vector to the pool.
+    /// Avoids the shrinking reallocation of `Vec::into_boxed_slice`.
+    pub fn into_boxed(&mut self, points: Vec<TTFPoint>) -> Box<[TTFPoint]> {
+        let boxed = Box::from(&points[..]);
+        self.give_back(points);
+        boxed
+    }
+
+    /// Number of vectors currently available for reuse
+    pub fn len(&self) -> usize {
+        self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.free.is_empty()
+    }
+}
+
 /// Container struct which bundles all the reusable buffers we need during the customization for merging.
 pub struct MergeBuffers {
     pub unpacking_target: ReusablePLFStorage,
     pub unpacking_tmp: ReusablePLFStorage,
+    /// vectors for link results, see `PLFPool`
+    pub link_results: PLFPool,
     buffer: Vec<TTFPoint>,
     exact_result_lower: Vec<TTFPoint>,
     exact_result_upper: Vec<TTFPoint>,
@@ -521,6 +651,7 @@ impl MergeBuffers {
         MergeBuffers {
             unpacking_target: ReusablePLFStorage::new(),
             unpacking_tmp: ReusablePLFStorage::new(),
+            link_results: PLFPool::new(),
             buffer: Vec::new(),
             exact_result_lower: Vec::new(),
             exact_result_upper: Vec::new(),
@@ -117,7 +117,7 @@ impl PartialShortcut {
                     ACTUALLY_LINKED.fetch_add(1, Relaxed);
                 }
                 // link functions
-                let linked = first_plf.link(&second_plf, self.start, self.end);
+                let linked = first_plf.link(&second_plf, self.start, self.end, buffers);
 
                 self.upper_bound = min(self.upper_bound, PartialATTF::from(&linked).static_upper_bound());
                 debug_assert!(
@@ -126,7 +126,7 @@ impl PartialShortcut {
                     self.lower_bound,
                     self.upper_bound
                 );
-                self.cache = Some(linked.into());
+                self.cache = Some(linked.into_pooled_box(&mut buffers.link_results));
                 self.sources = Sources::One(other_data);
                 return;
             }
@@ -135,7 +135,7 @@ impl PartialShortcut {
             let self_plf = self.partial_ttf(shortcut_graph, self.start, self.end).unwrap();
 
             // link TTFs in triangle
-            let linked_ipps = first_plf.link(&second_plf, self.start, self.end);
+            let linked_ipps = first_plf.link(&second_plf, self.start, self.end, buffers);
             if cfg!(feature = "detailed-stats") {
                 ACTUALLY_LINKED.fetch_add(1, Relaxed);
             }
@@ -157,18 +157,19 @@ impl PartialShortcut {
                     self.lower_bound,
                     self.upper_bound
                 );
-                if cfg!(feature = "tdcch-approx") && linked_ipps.num_points() > APPROX_THRESHOLD {
+                if cfg!(feature = "tdcch-approx") && linked_ipps.num_points() > approx_threshold() {
                     let old = linked_ipps.num_points();
                     if cfg!(feature = "detailed-stats") {
                         CONSIDERED_FOR_APPROX.fetch_add(old, Relaxed);
                     }
-                    let linked_ipps = linked.approximate(buffers);
+                    let approximated = linked.approximate(buffers);
                     if cfg!(feature = "detailed-stats") {
-                        SAVED_BY_APPROX.fetch_add(old as isize - linked_ipps.num_points() as isize, Relaxed);
+                        SAVED_BY_APPROX.fetch_add(old as isize - approximated.num_points() as isize, Relaxed);
                     }
-                    self.cache = Some(linked_ipps);
+                    self.cache = Some(approximated);
+                    linked_ipps.recycle(&mut buffers.link_results);
                 } else {
-                    self.cache = Some(linked_ipps.into());
+                    self.cache = Some(linked_ipps.into_pooled_box(&mut buffers.link_results));
                 }
                 self.sources = Sources::One(other_data);
                 if cfg!(feature = "detailed-stats") {
@@ -177,6 +178,7 @@ impl PartialShortcut {
                 return;
             } else if self.upper_bound.fuzzy_lt(other_lower_bound) {
                 // current upper bound always better than linked lower bound - keep whatever we currently have
+                linked_ipps.recycle(&mut buffers.link_results);
                 return;
             }
 
@@ -197,7 +199,8 @@ impl PartialShortcut {
                 let (self_ipps, other_ipps) = other_target.storage().top_plfs();
                 PartialPiecewiseLinearFunction::new(self_ipps).merge(&PartialPiecewiseLinearFunction::new(other_ipps), start, end, &mut buffers.buffer)
             });
-            if cfg!(feature = "tdcch-approx") && merged.num_points() > APPROX_THRESHOLD {
+            linked_ipps.recycle(&mut buffers.link_results);
+            if cfg!(feature = "tdcch-approx") && merged.num_points() > approx_threshold() {
                 let old = merged.num_points();
                 if cfg!(feature = "detailed-stats") {
                     CONSIDERED_FOR_APPROX.fetch_add(old, Relaxed);
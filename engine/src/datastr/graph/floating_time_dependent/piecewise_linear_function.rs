@@ -226,16 +226,26 @@ impl<'a> PeriodicPiecewiseLinearFunction<'a> {
     /// Link two complete and valid PLFs.
     /// The result is also a complete and valid PLF, but since PLF is just a borrow we return a `Vec<TTFPoint>`
     pub fn link(&self, other: &Self) -> Vec<TTFPoint> {
+        let mut result = Vec::with_capacity(self.ipps.len() + other.ipps.len() + 1);
+        self.link_into(other, &mut result);
+        result
+    }
+
+    /// Same as `link` but stores the result in the given (empty) vector, so its allocation can be reused.
+    pub fn link_into(&self, other: &Self, result: &mut Vec<TTFPoint>) {
+        debug_assert!(result.is_empty());
+
         if let [TTFPoint { val, .. }] = &self.ipps {
             if let [TTFPoint { val: other, .. }] = &other.ipps {
-                return vec![TTFPoint {
+                result.push(TTFPoint {
                     at: Timestamp::ZERO,
                     val: val + other,
-                }];
+                });
+                return;
             } else {
                 let zero_val = other.evaluate(val.into());
                 let (_, val_offset) = Timestamp::from(val).split_of_period();
-                let mut result = std::iter::once(TTFPoint {
+                std::iter::once(TTFPoint {
                     at: Timestamp::ZERO,
                     val: zero_val + val,
                 })
@@ -251,21 +261,19 @@ impl<'a> PeriodicPiecewiseLinearFunction<'a> {
                     at: period(),
                     val: zero_val + val,
                 }))
-                .fold(Vec::with_capacity(other.ipps.len() + 2), |mut acc, p| {
-                    append_point(&mut acc, p);
-                    acc
-                });
+                .for_each(|p| append_point(result, p));
 
                 result.last_mut().unwrap().at = period();
 
-                return result;
+                return;
             }
         }
         if let [TTFPoint { val, .. }] = &other.ipps {
-            return self.ipps.iter().map(|p| TTFPoint { at: p.at, val: p.val + val }).collect();
+            result.extend(self.ipps.iter().map(|p| TTFPoint { at: p.at, val: p.val + val }));
+            return;
         }
 
-        let mut result = Vec::with_capacity(self.ipps.len() + other.ipps.len() + 1);
+        result.reserve(self.ipps.len() + other.ipps.len() + 1);
 
         let mut f = PartialPlfLinkCursor::new(&self.ipps);
         let mut g = Cursor::starting_at_or_after(&other.ipps, Timestamp::ZERO + self.ipps[0].val);
@@ -306,16 +314,14 @@ impl<'a> PeriodicPiecewiseLinearFunction<'a> {
             x = min(x, period());
             x = max(x, Timestamp::ZERO);
 
-            append_point(&mut result, TTFPoint { at: x, val: y });
+            append_point(result, TTFPoint { at: x, val: y });
         }
 
         let zero_val = result[0].val;
-        append_point(&mut result, TTFPoint { at: period(), val: zero_val });
+        append_point(result, TTFPoint { at: period(), val: zero_val });
         result.last_mut().unwrap().at = period();
 
         debug_assert!(result.len() <= self.ipps.len() + other.ipps.len() + 1);
-
-        result
     }
 
     // Merge two complete and valid PLFs in the range between 0 and period and store the result in buffer.
@@ -419,6 +425,16 @@ impl<'a> PeriodicPiecewiseLinearFunction<'a> {
         upper.into_boxed_slice()
     }
 
+    /// Approximate with periodic lower and upper bound functions which deviate at most `epsilon` from this function.
+    /// The actual maximum error is contained in the result and recorded globally, see `max_approximation_error`.
+    pub fn approximate_with_error(&self, epsilon: FlWeight) -> BoundedApproximation {
+        BoundedApproximation::refine(self.ipps, epsilon, |tolerance, lower, upper| {
+            PartialPiecewiseLinearFunction { ipps: self.ipps }.douglas_peuker_combined_with_tolerance(tolerance, lower, upper);
+            Self::make_lower_bound_periodic(lower);
+            Self::make_upper_bound_periodic(upper);
+        })
+    }
+
     pub fn make_lower_bound_periodic(plf: &mut [TTFPoint]) {
         let wrap = min(plf.first().unwrap().val, plf.last().unwrap().val);
         plf.first_mut().unwrap().val = wrap;
@@ -454,6 +470,56 @@ impl<'a> PeriodicPiecewiseLinearFunction<'a> {
     }
 }
 
+/// Lower and upper bound functions approximating a PLF, see `approximate_with_error`.
+#[derive(Debug, Clone)]
+pub struct BoundedApproximation {
+    pub lower: Box<[TTFPoint]>,
+    pub upper: Box<[TTFPoint]>,
+    /// maximum vertical distance of either bound to the original function
+    pub max_error: FlWeight,
+}
+
+impl BoundedApproximation {
+    // Run the approximation with decreasing tolerances until the bounds are within `epsilon`.
+    // Shifting segments to bounds and making them FIFO and periodic may exceed the simplification tolerance, so start with half of it.
+    fn refine(ipps: &[TTFPoint], epsilon: FlWeight, approximate: impl Fn(FlWeight, &mut Vec<TTFPoint>, &mut Vec<TTFPoint>)) -> Self {
+        let mut lower = Vec::with_capacity(ipps.len());
+        let mut upper = Vec::with_capacity(ipps.len());
+        let mut tolerance = FlWeight::new(0.5 * f64::from(epsilon));
+
+        loop {
+            approximate(tolerance, &mut lower, &mut upper);
+            let max_error = Self::max_error(ipps, &lower, &upper);
+
+            if !epsilon.fuzzy_lt(max_error) || f64::from(tolerance) < EPSILON {
+                record_approximation_error(max_error);
+                return Self {
+                    lower: lower.into_boxed_slice(),
+                    upper: upper.into_boxed_slice(),
+                    max_error,
+                };
+            }
+
+            tolerance = FlWeight::new(0.5 * f64::from(tolerance));
+            lower.clear();
+            upper.clear();
+        }
+    }
+
+    // the distance between PLFs on the same domain is maximal at a breakpoint of one of them
+    fn max_error(ipps: &[TTFPoint], lower: &[TTFPoint], upper: &[TTFPoint]) -> FlWeight {
+        let original = PartialPiecewiseLinearFunction { ipps };
+        let lower_plf = PartialPiecewiseLinearFunction { ipps: lower };
+        let upper_plf = PartialPiecewiseLinearFunction { ipps: upper };
+
+        let at_original = ipps.iter().map(|p| max(p.val - lower_plf.eval(p.at), upper_plf.eval(p.at) - p.val));
+        let at_lower = lower.iter().map(|p| original.eval(p.at) - p.val);
+        let at_upper = upper.iter().map(|p| p.val - original.eval(p.at));
+
+        at_original.chain(at_lower).chain(at_upper).fold(FlWeight::ZERO, max)
+    }
+}
+
 /// A struct borrowing a slice of points which implements all sorts of operations and algorithms for Partial PLFs (nonperiodic).
 #[derive(Debug, Clone, Copy)]
 pub struct PartialPiecewiseLinearFunction<'a> {
@@ -623,6 +689,59 @@ impl<'a> PartialPiecewiseLinearFunction<'a> {
         return self.get_sub_plf(start, end).unwrap();
     }
 
+    /// Does this function cover exactly one period, i.e. [0, period] with matching values at both ends?
+    /// Constant functions consisting of a single point are periodic, too.
+    pub fn is_periodic(&self) -> bool {
+        let first = self.first().unwrap();
+        let last = self.last().unwrap();
+        self.len() == 1 || (first.at.fuzzy_eq(Timestamp::ZERO) && last.at.fuzzy_eq(period()) && first.val.fuzzy_eq(last.val))
+    }
+
+    fn as_periodic(&self) -> PeriodicPiecewiseLinearFunction<'a> {
+        debug_assert!(
+            self.len() == 1 || (self.first().unwrap().at.fuzzy_eq(Timestamp::ZERO) && self.last().unwrap().at.fuzzy_eq(period())),
+            "not covering a period: {:?} - {:?}",
+            self.first(),
+            self.last()
+        );
+        debug_assert!(
+            self.first().unwrap().val.fuzzy_eq(self.last().unwrap().val),
+            "values not continuous at period wrap: {:?} - {:?}",
+            self.first(),
+            self.last()
+        );
+        PeriodicPiecewiseLinearFunction { ipps: self.ipps }
+    }
+
+    /// Evaluate at an arbitrary time, also before 0 or after the period, by mapping it into [0, period).
+    /// The function has to be periodic, see `is_periodic`.
+    pub fn eval_wrapping(&self, t: Timestamp) -> FlWeight {
+        self.as_periodic().evaluate(t)
+    }
+
+    /// Copy the points covering [start, end] to target, where the range may cross (several) period boundaries.
+    /// Points beyond the period are shifted accordingly. Target may already contain points, see `PeriodicPiecewiseLinearFunction::append_range`.
+    /// The function has to be periodic, see `is_periodic`.
+    pub fn append_range_wrapping(&self, start: Timestamp, end: Timestamp, target: &mut impl PLFTarget) {
+        self.as_periodic().append_range(start, end, target)
+    }
+
+    /// Copy the points of a periodic function, constant functions are expanded to two points at 0 and period
+    pub fn to_periodic_vec(&self) -> Vec<TTFPoint> {
+        let periodic = self.as_periodic();
+        if let [point] = &periodic[..] {
+            vec![
+                TTFPoint {
+                    at: Timestamp::ZERO,
+                    val: point.val,
+                },
+                TTFPoint { at: period(), val: point.val },
+            ]
+        } else {
+            periodic.to_vec()
+        }
+    }
+
     /// Copy full slice of points to target/first.
     /// The difference here to the other copy/append methods is that we don't need the Cursor logig but can copy the entire slice.
     /// When target already covers switchover, restrict those points to the range up to start, insert a point by linear interpolation
@@ -1205,6 +1324,11 @@ impl<'a> PartialPiecewiseLinearFunction<'a> {
     // calculate approximated bound functions and make them as tight as possible
     #[cfg(not(feature = "tdcch-approx-imai-iri"))]
     fn douglas_peuker_combined(&self, result_lower: &mut Vec<TTFPoint>, result_upper: &mut Vec<TTFPoint>) {
+        self.douglas_peuker_combined_with_tolerance(APPROX, result_lower, result_upper)
+    }
+
+    // same as `douglas_peuker_combined` but segments are simplified while the points deviate at most `tolerance` from them
+    fn douglas_peuker_combined_with_tolerance(&self, tolerance: FlWeight, result_lower: &mut Vec<TTFPoint>, result_upper: &mut Vec<TTFPoint>) {
         if self.ipps.len() <= 2 {
             result_lower.extend_from_slice(self.ipps);
             result_upper.extend_from_slice(self.ipps);
@@ -1228,15 +1352,15 @@ impl<'a> PartialPiecewiseLinearFunction<'a> {
             (i_max, max_delta.abs())
         };
 
-        if delta > APPROX {
-            Self { ipps: &self.ipps[0..=i] }.douglas_peuker_combined(result_lower, result_upper);
+        if delta > tolerance {
+            Self { ipps: &self.ipps[0..=i] }.douglas_peuker_combined_with_tolerance(tolerance, result_lower, result_upper);
             let prev_min = result_lower.pop().map(|p| p.val).unwrap_or(FlWeight::ZERO);
             let prev_max = result_upper.pop().map(|p| p.val).unwrap_or(FlWeight::ZERO);
             let prev_len = result_lower.len();
             Self {
                 ipps: &self.ipps[i..self.ipps.len()],
             }
-            .douglas_peuker_combined(result_lower, result_upper);
+            .douglas_peuker_combined_with_tolerance(tolerance, result_lower, result_upper);
             result_lower[prev_len].val = min(result_lower[prev_len].val, prev_min);
             result_upper[prev_len].val = max(result_upper[prev_len].val, prev_max);
         } else {
@@ -1259,6 +1383,16 @@ impl<'a> PartialPiecewiseLinearFunction<'a> {
         }
     }
 
+    /// Approximate with lower and upper bound functions which deviate at most `epsilon` from this function.
+    /// The actual maximum error is contained in the result and recorded globally, see `max_approximation_error`.
+    pub fn approximate_with_error(&self, epsilon: FlWeight) -> BoundedApproximation {
+        BoundedApproximation::refine(self.ipps, epsilon, |tolerance, lower, upper| {
+            self.douglas_peuker_combined_with_tolerance(tolerance, lower, upper);
+            Self::fifoize_down(lower);
+            Self::fifoize_up(upper);
+        })
+    }
+
     /// Generate an approximated function which is always less or equal to the original function
     #[cfg(not(feature = "tdcch-approx-imai-iri"))]
     pub fn lower_bound_ttf(&self, buffer: &mut Vec<TTFPoint>, other_buffer: &mut Vec<TTFPoint>) -> Box<[TTFPoint]> {
@@ -1342,6 +1476,9 @@ impl<'a> PartialPiecewiseLinearFunction<'a> {
     }
 }
 
+#[cfg(test)]
+mod proptests;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1577,6 +1714,52 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_wrapping_partial_plf() {
+        run_test_with_periodicity(Timestamp::new(100.0), || {
+            let ipps = [
+                TTFPoint {
+                    at: Timestamp::ZERO,
+                    val: FlWeight::new(10.0),
+                },
+                TTFPoint {
+                    at: Timestamp::new(50.0),
+                    val: FlWeight::new(20.0),
+                },
+                TTFPoint {
+                    at: Timestamp::new(100.0),
+                    val: FlWeight::new(10.0),
+                },
+            ];
+            let plf = PartialPiecewiseLinearFunction::new(&ipps);
+            assert!(plf.is_periodic());
+            assert!(!PartialPiecewiseLinearFunction::new(&ipps[..2]).is_periodic());
+
+            assert_eq!(plf.eval_wrapping(Timestamp::new(125.0)), FlWeight::new(15.0));
+            assert_eq!(plf.eval_wrapping(Timestamp::new(-25.0)), FlWeight::new(15.0));
+
+            let mut result = Vec::new();
+            plf.append_range_wrapping(Timestamp::new(90.0), Timestamp::new(160.0), &mut result);
+            assert_eq!(
+                result.iter().map(|p| (p.at, p.val)).collect::<Vec<_>>(),
+                vec![
+                    (Timestamp::new(50.0), FlWeight::new(20.0)),
+                    (Timestamp::new(100.0), FlWeight::new(10.0)),
+                    (Timestamp::new(150.0), FlWeight::new(20.0)),
+                    (Timestamp::new(200.0), FlWeight::new(10.0)),
+                ]
+            );
+
+            let constant = [TTFPoint {
+                at: Timestamp::ZERO,
+                val: FlWeight::new(10.0),
+            }];
+            let constant = PartialPiecewiseLinearFunction::new(&constant);
+            assert_eq!(constant.eval_wrapping(Timestamp::new(250.0)), FlWeight::new(10.0));
+            assert_eq!(constant.to_periodic_vec().last().unwrap().at, Timestamp::new(100.0));
+        });
+    }
+
     #[test]
     fn test_partial_merging_with_intersection_fuzzy_on_start() {
         run_test_with_periodicity(Timestamp::new(86400.0), || {
@@ -0,0 +1,189 @@
+//! Property based tests for linking and merging PLFs on randomly generated FIFO conform travel time functions.
+//! Complements the hand picked (and mostly regression) fixtures in the regular tests.
+
+use super::*;
+use proptest::prelude::*;
+
+const PERIOD: f64 = 86_400.0;
+// minimum distance between two points, larger than the maximum value difference, so the generated functions are strictly FIFO
+const MIN_GAP: f64 = 1_000.0;
+const MIN_VAL: f64 = 1.0;
+const MAX_VAL: f64 = 900.0;
+const MAX_POINTS: usize = 30;
+// the results are exact up to floating point errors, but points closer than `EPSILON` get dropped or shifted
+const TOLERANCE: f64 = 100.0 * EPSILON;
+
+/// Periodic TTF with at least `min_points` points strictly between 0 and the period.
+/// Without any such points, the TTF is constant and consists of a single point.
+fn periodic_ttf(min_points: usize) -> impl Strategy<Value = Vec<TTFPoint>> {
+    (min_points..MAX_POINTS)
+        .prop_flat_map(|num_points| {
+            (
+                prop::collection::vec(1.0..10.0f64, num_points + 1),
+                prop::collection::vec(MIN_VAL..MAX_VAL, num_points + 1),
+            )
+        })
+        .prop_map(|(gap_weights, vals)| {
+            if vals.len() == 1 {
+                return vec![TTFPoint {
+                    at: Timestamp::ZERO,
+                    val: FlWeight::new(vals[0]),
+                }];
+            }
+
+            let total_weight: f64 = gap_weights.iter().sum();
+            let free_time = PERIOD - gap_weights.len() as f64 * MIN_GAP;
+
+            let mut at = 0.0;
+            let mut points = Vec::with_capacity(vals.len() + 1);
+            for (weight, val) in gap_weights.iter().zip(vals.iter()) {
+                points.push(TTFPoint {
+                    at: Timestamp::new(at),
+                    val: FlWeight::new(*val),
+                });
+                at += MIN_GAP + free_time * weight / total_weight;
+            }
+            points.push(TTFPoint {
+                at: Timestamp::new(PERIOD),
+                val: FlWeight::new(vals[0]),
+            });
+            points
+        })
+}
+
+fn sample_times() -> impl Strategy<Value = Vec<f64>> {
+    prop::collection::vec(0.0..PERIOD, 1..20)
+}
+
+fn eval(ipps: &[TTFPoint], t: f64) -> f64 {
+    f64::from(PeriodicPiecewiseLinearFunction::new(ipps).evaluate(Timestamp::new(t)))
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() <= TOLERANCE, "{} != {}", actual, expected);
+}
+
+fn assert_valid_periodic(ipps: &[TTFPoint]) {
+    // validates FIFO and ordering in debug builds
+    PeriodicPiecewiseLinearFunction::new(ipps);
+    assert_eq!(ipps.first().unwrap().at, Timestamp::ZERO);
+    assert!(ipps.first().unwrap().val.fuzzy_eq(ipps.last().unwrap().val));
+    assert!(ipps.len() == 1 || ipps.last().unwrap().at == Timestamp::new(PERIOD));
+}
+
+fn link(first: &[TTFPoint], second: &[TTFPoint]) -> Vec<TTFPoint> {
+    PeriodicPiecewiseLinearFunction::new(first).link(&PeriodicPiecewiseLinearFunction::new(second))
+}
+
+proptest! {
+    #[test]
+    fn linking_evaluates_second_at_arrival_of_first(f in periodic_ttf(0), g in periodic_ttf(0), times in sample_times()) {
+        run_test_with_periodicity(Timestamp::new(PERIOD), || {
+            let linked = link(&f, &g);
+            assert_valid_periodic(&linked);
+
+            for &t in &times {
+                let f_t = eval(&f, t);
+                assert_close(eval(&linked, t), f_t + eval(&g, t + f_t));
+            }
+        });
+    }
+
+    #[test]
+    fn linking_is_associative(f in periodic_ttf(0), g in periodic_ttf(0), h in periodic_ttf(0), times in sample_times()) {
+        run_test_with_periodicity(Timestamp::new(PERIOD), || {
+            let left = link(&link(&f, &g), &h);
+            let right = link(&f, &link(&g, &h));
+            assert_valid_periodic(&left);
+            assert_valid_periodic(&right);
+
+            for &t in &times {
+                assert_close(eval(&left, t), eval(&right, t));
+            }
+        });
+    }
+
+    #[test]
+    fn merging_yields_lower_envelope(f in periodic_ttf(0), g in periodic_ttf(0), times in sample_times()) {
+        run_test_with_periodicity(Timestamp::new(PERIOD), || {
+            let (merged, better) = PeriodicPiecewiseLinearFunction::new(&f).merge(&PeriodicPiecewiseLinearFunction::new(&g), &mut Vec::new());
+            assert_valid_periodic(&merged);
+            assert_eq!(better.first().unwrap().0, Timestamp::ZERO);
+
+            for &t in &times {
+                let (f_t, g_t) = (eval(&f, t), eval(&g, t));
+                let merged_t = eval(&merged, t);
+                assert_close(merged_t, f_t.min(g_t));
+
+                // the switch point before t tells which function is the minimum
+                let &(_, f_better) = better.iter().rev().find(|(at, _)| f64::from(FlWeight::from(at)) <= t).unwrap();
+                assert_close(merged_t, if f_better { f_t } else { g_t });
+            }
+        });
+    }
+
+    #[test]
+    fn evaluation_wraps_around_period(f in periodic_ttf(0), g in periodic_ttf(0), times in sample_times(), num_periods in -3i32..3) {
+        run_test_with_periodicity(Timestamp::new(PERIOD), || {
+            let linked = link(&f, &g);
+            for &t in &times {
+                let shifted = t + f64::from(num_periods) * PERIOD;
+                assert_close(eval(&f, shifted), eval(&f, t));
+                assert_close(eval(&linked, shifted), eval(&linked, t));
+            }
+        });
+    }
+
+    #[test]
+    fn inverse_evaluation_recovers_departure(f in periodic_ttf(0), times in sample_times()) {
+        run_test_with_periodicity(Timestamp::new(PERIOD), || {
+            let plf = PeriodicPiecewiseLinearFunction::new(&f);
+            for &t in &times {
+                let arrival = Timestamp::new(t + eval(&f, t));
+                assert_close(f64::from(FlWeight::from(plf.inverse_evaluate(arrival))), t);
+            }
+        });
+    }
+
+    #[test]
+    fn bounded_approximation_encloses_function(f in periodic_ttf(0), epsilon in 0.1..50.0f64, times in sample_times()) {
+        run_test_with_periodicity(Timestamp::new(PERIOD), || {
+            let approx = PeriodicPiecewiseLinearFunction::new(&f).approximate_with_error(FlWeight::new(epsilon));
+            assert_valid_periodic(&approx.lower);
+            assert_valid_periodic(&approx.upper);
+            assert!(f64::from(approx.max_error) <= epsilon + TOLERANCE);
+            assert!(approx.lower.len() <= f.len() && approx.upper.len() <= f.len());
+            assert!(!max_approximation_error().fuzzy_lt(approx.max_error));
+
+            for &t in &times {
+                let f_t = eval(&f, t);
+                assert!(eval(&approx.lower, t) <= f_t + TOLERANCE && f_t - eval(&approx.lower, t) <= epsilon + TOLERANCE);
+                assert!(eval(&approx.upper, t) >= f_t - TOLERANCE && eval(&approx.upper, t) - f_t <= epsilon + TOLERANCE);
+            }
+        });
+    }
+
+    #[test]
+    fn partial_merging_yields_lower_envelope_within_bounds(
+        f in periodic_ttf(1),
+        g in periodic_ttf(1),
+        bounds in (0.0..PERIOD - 1.0).prop_flat_map(|start| (Just(start), start + 1.0..PERIOD)),
+        times in prop::collection::vec(0.0..1.0f64, 1..20)
+    ) {
+        run_test_with_periodicity(Timestamp::new(PERIOD), || {
+            let (start, end) = (Timestamp::new(bounds.0), Timestamp::new(bounds.1));
+            let f_sub = PartialPiecewiseLinearFunction::new(&f).sub_plf(start, end);
+            let g_sub = PartialPiecewiseLinearFunction::new(&g).sub_plf(start, end);
+            assert!(!start.fuzzy_lt(f_sub.first().unwrap().at) && !f_sub.last().unwrap().at.fuzzy_lt(end));
+
+            let (merged, _) = f_sub.merge(&g_sub, start, end, &mut Vec::new());
+            let merged = PartialPiecewiseLinearFunction::new(&merged);
+
+            for &frac in &times {
+                let t = Timestamp::new(bounds.0 + frac * (bounds.1 - bounds.0));
+                let expected = f64::from(min(f_sub.eval(t), g_sub.eval(t)));
+                assert_close(f64::from(merged.eval(t)), expected);
+            }
+        });
+    }
+}
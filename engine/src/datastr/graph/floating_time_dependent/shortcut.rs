@@ -4,15 +4,31 @@ use super::shortcut_source::Sources as _;
 use super::*;
 use std::cmp::{max, min};
 use std::convert::TryFrom;
-use std::sync::atomic::Ordering::Relaxed;
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
 
 /// Number of points that a PLF is allowed to have before reduction by approximation is triggered.
-/// Can be overriden through the TDCCH_APPROX_THRESHOLD env var
+/// Can be overridden through the TDCCH_APPROX_THRESHOLD env var
 #[cfg(not(override_tdcch_approx_threshold))]
 pub const APPROX_THRESHOLD: usize = 1000;
 #[cfg(override_tdcch_approx_threshold)]
 pub const APPROX_THRESHOLD: usize = include!(concat!(env!("OUT_DIR"), "/TDCCH_APPROX_THRESHOLD"));
 
+static CURRENT_APPROX_THRESHOLD: AtomicUsize = AtomicUsize::new(APPROX_THRESHOLD);
+
+/// Number of points that a PLF is currently allowed to have before it gets approximated.
+/// Starts at `APPROX_THRESHOLD` but can be lowered at runtime to trade accuracy for memory.
+pub fn approx_threshold() -> usize {
+    CURRENT_APPROX_THRESHOLD.load(Relaxed)
+}
+
+pub fn set_approx_threshold(threshold: usize) {
+    CURRENT_APPROX_THRESHOLD.store(threshold, Relaxed);
+}
+
+pub fn reset_approx_threshold() {
+    set_approx_threshold(APPROX_THRESHOLD);
+}
+
 /// Shortcut data for a CCH edge.
 ///
 /// Here, we use Shortcut as the name for all CCH edges -- probably TDCCHEdge would be a better name.
@@ -26,6 +42,11 @@ pub const APPROX_THRESHOLD: usize = include!(concat!(env!("OUT_DIR"), "/TDCCH_AP
 pub struct Shortcut {
     pub sources: Sources,
     pub cache: Option<ATTFContainer<Box<[TTFPoint]>>>,
+    /// Delta encoded TTF while it is not used, see `compress_cache`
+    #[cfg(feature = "tdcch-compact-ttf-cache")]
+    pub compressed_cache: Option<ATTFContainer<CompactTTF>>,
+    /// Location of the TTF in the spill file while it is not used, see `spill_cache`
+    pub spilled_cache: Option<Box<ATTFContainer<SpilledTTF>>>,
     pub lower_bound: FlWeight,
     pub upper_bound: FlWeight,
     pub constant: bool,
@@ -44,6 +65,9 @@ impl Shortcut {
                 Shortcut {
                     sources: Sources::One(ShortcutSource::OriginalEdge(edge_id).into()),
                     cache: None,
+                    #[cfg(feature = "tdcch-compact-ttf-cache")]
+                    compressed_cache: None,
+                    spilled_cache: None,
                     lower_bound: original_graph.travel_time_function(edge_id).lower_bound(),
                     upper_bound: original_graph.travel_time_function(edge_id).upper_bound(),
                     constant: false,
@@ -53,6 +77,9 @@ impl Shortcut {
             None => Shortcut {
                 sources: Sources::None,
                 cache: None,
+                #[cfg(feature = "tdcch-compact-ttf-cache")]
+                compressed_cache: None,
+                spilled_cache: None,
                 lower_bound: FlWeight::INFINITY,
                 upper_bound: FlWeight::INFINITY,
                 constant: false,
@@ -69,6 +96,9 @@ impl Shortcut {
         };
         Self {
             cache: None,
+            #[cfg(feature = "tdcch-compact-ttf-cache")]
+            compressed_cache: None,
+            spilled_cache: None,
             lower_bound: bounds.0,
             upper_bound: bounds.1,
             constant: bounds.0.fuzzy_eq(bounds.1),
@@ -120,7 +150,7 @@ impl Shortcut {
                     ACTUALLY_LINKED.fetch_add(1, Relaxed);
                 }
                 // link functions
-                let linked = first_plf.link(&second_plf);
+                let linked = first_plf.link(&second_plf, buffers);
 
                 self.upper_bound = min(self.upper_bound, PeriodicATTF::from(&linked).static_upper_bound());
                 debug_assert!(
@@ -129,7 +159,7 @@ impl Shortcut {
                     self.lower_bound,
                     self.upper_bound
                 );
-                self.cache = Some(linked.into());
+                self.cache = Some(linked.into_pooled_box(&mut buffers.link_results));
                 self.sources = Sources::One(other_data);
                 return;
             }
@@ -138,7 +168,7 @@ impl Shortcut {
             let self_plf = self.periodic_ttf(shortcut_graph).unwrap();
 
             // link TTFs in triangle
-            let linked_ipps = first_plf.link(&second_plf);
+            let linked_ipps = first_plf.link(&second_plf, buffers);
             if cfg!(feature = "detailed-stats") {
                 ACTUALLY_LINKED.fetch_add(1, Relaxed);
             }
@@ -160,18 +190,19 @@ impl Shortcut {
                     self.lower_bound,
                     self.upper_bound
                 );
-                if cfg!(feature = "tdcch-approx") && linked_ipps.num_points() > APPROX_THRESHOLD {
+                if cfg!(feature = "tdcch-approx") && linked_ipps.num_points() > approx_threshold() {
                     let old = linked_ipps.num_points();
                     if cfg!(feature = "detailed-stats") {
                         CONSIDERED_FOR_APPROX.fetch_add(old, Relaxed);
                     }
-                    let linked_ipps = linked.approximate(buffers);
+                    let approximated = linked.approximate(buffers);
                     if cfg!(feature = "detailed-stats") {
-                        SAVED_BY_APPROX.fetch_add(old as isize - linked_ipps.num_points() as isize, Relaxed);
+                        SAVED_BY_APPROX.fetch_add(old as isize - approximated.num_points() as isize, Relaxed);
                     }
-                    self.cache = Some(linked_ipps);
+                    self.cache = Some(approximated);
+                    linked_ipps.recycle(&mut buffers.link_results);
                 } else {
-                    self.cache = Some(linked_ipps.into());
+                    self.cache = Some(linked_ipps.into_pooled_box(&mut buffers.link_results));
                 }
                 self.sources = Sources::One(other_data);
                 if cfg!(feature = "detailed-stats") {
@@ -180,6 +211,7 @@ impl Shortcut {
                 return;
             } else if self.upper_bound.fuzzy_lt(other_lower_bound) {
                 // current upper bound always better than linked lower bound - keep whatever we currently have
+                linked_ipps.recycle(&mut buffers.link_results);
                 return;
             }
 
@@ -200,10 +232,11 @@ impl Shortcut {
                 let (self_ipps, other_ipps) = other_target.storage().top_plfs();
                 PartialPiecewiseLinearFunction::new(self_ipps).merge(&PartialPiecewiseLinearFunction::new(other_ipps), start, end, &mut buffers.buffer)
             });
+            linked_ipps.recycle(&mut buffers.link_results);
 
             // approximate function several times to reduce number of breakpoints
             if cfg!(feature = "tdcch-approx") {
-                while merged.num_points() > APPROX_THRESHOLD {
+                while merged.num_points() > approx_threshold() {
                     let old = merged.num_points();
                     if cfg!(feature = "detailed-stats") {
                         CONSIDERED_FOR_APPROX.fetch_add(old, Relaxed);
@@ -252,6 +285,9 @@ impl Shortcut {
         if let Some(cache) = &self.cache {
             return Some(cache.into());
         }
+        #[cfg(feature = "tdcch-compact-ttf-cache")]
+        debug_assert!(self.compressed_cache.is_none(), "compressed TTF has to be decompressed before use");
+        debug_assert!(self.spilled_cache.is_none(), "spilled TTF has to be loaded before use");
 
         match self.sources {
             Sources::One(source) => match source.into() {
@@ -381,12 +417,80 @@ impl Shortcut {
     /// Should only be called once it is really not needed anymore.
     pub fn clear_plf(&mut self) {
         if cfg!(feature = "detailed-stats") {
-            IPP_COUNT.fetch_sub(self.cache.as_ref().map(ATTFContainer::<Box<[TTFPoint]>>::num_points).unwrap_or(0), Relaxed);
-            if self.cache.is_some() {
+            let num_points = self.num_cached_points();
+            IPP_COUNT.fetch_sub(num_points.unwrap_or(0), Relaxed);
+            if num_points.is_some() {
                 ACTIVE_SHORTCUTS.fetch_sub(1, Relaxed);
             }
         }
         self.cache = None;
+        self.spilled_cache = None;
+        #[cfg(feature = "tdcch-compact-ttf-cache")]
+        {
+            self.compressed_cache = None;
+        }
+    }
+
+    fn num_cached_points(&self) -> Option<usize> {
+        #[cfg(feature = "tdcch-compact-ttf-cache")]
+        if let Some(compressed) = &self.compressed_cache {
+            return Some(compressed.num_points());
+        }
+        if let Some(spilled) = &self.spilled_cache {
+            return Some(spilled.num_points());
+        }
+        self.cache.as_ref().map(ATTFContainer::<Box<[TTFPoint]>>::num_points)
+    }
+
+    /// Move the cached TTF into the spill file to save memory.
+    /// The TTF is not available until `load_spilled_cache` is called.
+    pub fn spill_cache(&mut self, file: &TTFSpillFile) {
+        if let Some(cache) = self.cache.take() {
+            self.spilled_cache = Some(Box::new(cache.spill(file).expect("failed to spill TTF")));
+        }
+    }
+
+    /// Read a spilled TTF back.
+    /// The spilled copy stays valid, so once the TTF is not needed anymore it can be dropped with `release_spilled_cache`.
+    pub fn load_spilled_cache(&mut self, file: &TTFSpillFile) {
+        if let (None, Some(spilled)) = (&self.cache, &self.spilled_cache) {
+            self.cache = Some(spilled.load(file).expect("failed to read spilled TTF"));
+        }
+    }
+
+    pub fn release_spilled_cache(&mut self) {
+        if self.spilled_cache.is_some() {
+            self.cache = None;
+        }
+    }
+
+    /// Replace the cached TTF by its delta encoded representation to save memory.
+    /// Functions which can not be compressed stay as they are.
+    /// The TTF is not available until `decompress_cache` is called.
+    #[cfg(feature = "tdcch-compact-ttf-cache")]
+    pub fn compress_cache(&mut self) {
+        if let Some(cache) = self.cache.take() {
+            match cache.compress() {
+                Ok(compressed) => self.compressed_cache = Some(compressed),
+                Err(cache) => self.cache = Some(cache),
+            }
+        }
+    }
+
+    /// Make a compressed TTF available again.
+    /// The compressed representation is kept, so once the TTF is not needed anymore it can be dropped with `release_decompressed_cache`.
+    #[cfg(feature = "tdcch-compact-ttf-cache")]
+    pub fn decompress_cache(&mut self) {
+        if let (None, Some(compressed)) = (&self.cache, &self.compressed_cache) {
+            self.cache = Some(compressed.decompress());
+        }
+    }
+
+    #[cfg(feature = "tdcch-compact-ttf-cache")]
+    pub fn release_decompressed_cache(&mut self) {
+        if self.compressed_cache.is_some() {
+            self.cache = None;
+        }
     }
 
     pub fn set_cache(&mut self, ttf: Option<ATTFContainer<Box<[TTFPoint]>>>) {
@@ -1336,7 +1336,7 @@ impl<'a> ReconstructionGraph<'a> {
 
     pub fn approximate(&mut self, shortcut_id: ShortcutId, buffers: &mut MergeBuffers) {
         if let Some(cache) = shortcut_id.get_mut_from(&mut self.incoming_cache, &mut self.outgoing_cache) {
-            if cache.num_points() > APPROX_THRESHOLD {
+            if cache.num_points() > approx_threshold() {
                 cache.approximate(buffers);
             }
         }
@@ -0,0 +1,114 @@
+//! Temporary file to move shortcut TTFs out of memory during customization.
+//!
+//! The file is append only, each spilled function is written once and read back whenever it is needed again.
+//! Reads and writes are positioned, so the file can be shared between all customization threads.
+
+use super::*;
+use crate::io::{DataBytes, DataBytesMut};
+use std::{
+    fs::{File, OpenOptions},
+    io::Result,
+    os::unix::fs::FileExt,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering::Relaxed},
+};
+
+/// Location of a spilled function in a `TTFSpillFile`
+#[derive(Debug, Clone, Copy)]
+pub struct SpilledTTF {
+    offset: u64,
+    num_points: u32,
+}
+
+impl SpilledTTF {
+    pub fn num_points(&self) -> usize {
+        self.num_points as usize
+    }
+}
+
+#[derive(Debug)]
+pub struct TTFSpillFile {
+    file: File,
+    path: PathBuf,
+    len: AtomicU64,
+}
+
+impl TTFSpillFile {
+    /// Create (or truncate) the file at the given path.
+    /// It will be removed again once the `TTFSpillFile` is dropped.
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)?;
+        Ok(TTFSpillFile {
+            file,
+            path: path.to_owned(),
+            len: AtomicU64::new(0),
+        })
+    }
+
+    pub fn write(&self, ipps: &[TTFPoint]) -> Result<SpilledTTF> {
+        let bytes = ipps.data_bytes();
+        let offset = self.len.fetch_add(bytes.len() as u64, Relaxed);
+        self.file.write_all_at(bytes, offset)?;
+        Ok(SpilledTTF {
+            offset,
+            num_points: ipps.len() as u32,
+        })
+    }
+
+    pub fn read(&self, spilled: &SpilledTTF) -> Result<Box<[TTFPoint]>> {
+        let mut ipps = vec![TTFPoint::default(); spilled.num_points()];
+        self.file.read_exact_at(ipps.data_bytes_mut(), spilled.offset)?;
+        Ok(ipps.into_boxed_slice())
+    }
+
+    /// Number of bytes written so far
+    pub fn len(&self) -> u64 {
+        self.len.load(Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Drop for TTFSpillFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spilled_functions_are_read_back() {
+        let path = std::env::temp_dir().join(format!("ttf_spill_test_{}", std::process::id()));
+        let spill = TTFSpillFile::create(&path).unwrap();
+
+        let first = [
+            TTFPoint {
+                at: Timestamp::ZERO,
+                val: FlWeight::new(5.0),
+            },
+            TTFPoint {
+                at: Timestamp::new(10.0),
+                val: FlWeight::new(7.5),
+            },
+        ];
+        let second = [TTFPoint {
+            at: Timestamp::ZERO,
+            val: FlWeight::new(42.0),
+        }];
+
+        let first_spilled = spill.write(&first).unwrap();
+        let second_spilled = spill.write(&second).unwrap();
+        assert_eq!(spill.len(), 3 * std::mem::size_of::<TTFPoint>() as u64);
+
+        assert_eq!(&spill.read(&second_spilled).unwrap()[..], &second[..]);
+        assert_eq!(&spill.read(&first_spilled).unwrap()[..], &first[..]);
+
+        drop(spill);
+        assert!(!path.exists());
+    }
+}
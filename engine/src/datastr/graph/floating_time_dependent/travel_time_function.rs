@@ -80,6 +80,78 @@ impl ATTFContainer<Vec<TTFPoint>> {
             _ => unreachable!(),
         }
     }
+
+    /// Copy into exactly sized boxed slices and return the vectors to the pool
+    pub fn into_pooled_box(self, pool: &mut PLFPool) -> ATTFContainer<Box<[TTFPoint]>> {
+        match self {
+            ATTFContainer::Exact(ipps) => ATTFContainer::Exact(pool.into_boxed(ipps)),
+            ATTFContainer::Approx(lower_ipps, upper_ipps) => ATTFContainer::Approx(pool.into_boxed(lower_ipps), pool.into_boxed(upper_ipps)),
+        }
+    }
+
+    /// Discard the functions and return the vectors to the pool
+    pub fn recycle(self, pool: &mut PLFPool) {
+        match self {
+            ATTFContainer::Exact(ipps) => pool.give_back(ipps),
+            ATTFContainer::Approx(lower_ipps, upper_ipps) => {
+                pool.give_back(lower_ipps);
+                pool.give_back(upper_ipps);
+            }
+        }
+    }
+}
+
+impl ATTFContainer<Box<[TTFPoint]>> {
+    /// Delta encode the functions, gives back the uncompressed container if that is not possible
+    pub fn compress(self) -> Result<ATTFContainer<CompactTTF>, Self> {
+        let compressed = match &self {
+            ATTFContainer::Exact(ipps) => CompactTTF::compress(ipps).map(ATTFContainer::Exact),
+            ATTFContainer::Approx(lower_ipps, upper_ipps) => CompactTTF::compress(lower_ipps)
+                .zip(CompactTTF::compress(upper_ipps))
+                .map(|(lower, upper)| ATTFContainer::Approx(lower, upper)),
+        };
+        compressed.ok_or(self)
+    }
+
+    /// Write the functions into the spill file
+    pub fn spill(&self, file: &TTFSpillFile) -> std::io::Result<ATTFContainer<SpilledTTF>> {
+        Ok(match self {
+            ATTFContainer::Exact(ipps) => ATTFContainer::Exact(file.write(ipps)?),
+            ATTFContainer::Approx(lower_ipps, upper_ipps) => ATTFContainer::Approx(file.write(lower_ipps)?, file.write(upper_ipps)?),
+        })
+    }
+}
+
+impl ATTFContainer<SpilledTTF> {
+    pub fn load(&self, file: &TTFSpillFile) -> std::io::Result<ATTFContainer<Box<[TTFPoint]>>> {
+        Ok(match self {
+            ATTFContainer::Exact(ipps) => ATTFContainer::Exact(file.read(ipps)?),
+            ATTFContainer::Approx(lower_ipps, upper_ipps) => ATTFContainer::Approx(file.read(lower_ipps)?, file.read(upper_ipps)?),
+        })
+    }
+
+    pub fn num_points(&self) -> usize {
+        match self {
+            ATTFContainer::Exact(ipps) => ipps.num_points(),
+            ATTFContainer::Approx(lower_ipps, upper_ipps) => lower_ipps.num_points() + upper_ipps.num_points(),
+        }
+    }
+}
+
+impl ATTFContainer<CompactTTF> {
+    pub fn decompress(&self) -> ATTFContainer<Box<[TTFPoint]>> {
+        match self {
+            ATTFContainer::Exact(ipps) => ATTFContainer::Exact(ipps.decompress()),
+            ATTFContainer::Approx(lower_ipps, upper_ipps) => ATTFContainer::Approx(lower_ipps.decompress(), upper_ipps.decompress()),
+        }
+    }
+
+    pub fn num_points(&self) -> usize {
+        match self {
+            ATTFContainer::Exact(ipps) => ipps.num_points(),
+            ATTFContainer::Approx(lower_ipps, upper_ipps) => lower_ipps.num_points() + upper_ipps.num_points(),
+        }
+    }
 }
 
 impl<D> TryFrom<ApproxPartialsContainer<D>> for ATTFContainer<D>
@@ -176,13 +248,16 @@ impl<'a> PeriodicATTF<'a> {
         }
     }
 
-    // Link to TTFs, creating a new function
-    pub fn link(&self, second: &Self) -> ATTFContainer<Vec<TTFPoint>> {
+    // Link to TTFs, creating a new function.
+    // The result vectors are taken from `buffers.link_results` and should be handed back with `into_pooled_box` or `recycle`.
+    pub fn link(&self, second: &Self, buffers: &mut MergeBuffers) -> ATTFContainer<Vec<TTFPoint>> {
         use PeriodicATTF::*;
 
         // if both TTFs are exact, we can link exact
         if let (Exact(first), Exact(second)) = (self, second) {
-            return ATTFContainer::Exact(first.link(second));
+            let mut result = buffers.link_results.take();
+            first.link_into(second, &mut result);
+            return ATTFContainer::Exact(result);
         }
         // else the result will be approximated anyway
 
@@ -190,7 +265,11 @@ impl<'a> PeriodicATTF<'a> {
         let (second_lower, second_upper) = second.bound_plfs();
 
         // linking two upper bounds is a valid upper bound, same for lower bounds
-        ATTFContainer::Approx(first_lower.link(&second_lower), first_upper.link(&second_upper))
+        let mut result_lower = buffers.link_results.take();
+        let mut result_upper = buffers.link_results.take();
+        first_lower.link_into(&second_lower, &mut result_lower);
+        first_upper.link_into(&second_upper, &mut result_upper);
+        ATTFContainer::Approx(result_lower, result_upper)
     }
 
     // this ones a bit ugly...
@@ -725,7 +804,7 @@ impl<'a> PartialATTF<'a> {
     }
 
     // Link to TTFs, creating a new function
-    pub fn link(&self, second: &Self, start: Timestamp, end: Timestamp) -> ATTFContainer<Vec<TTFPoint>> {
+    pub fn link(&self, second: &Self, start: Timestamp, end: Timestamp, buffers: &mut MergeBuffers) -> ATTFContainer<Vec<TTFPoint>> {
         use PartialATTF::*;
 
         // if both TTFs are exact, we can link exact
@@ -734,9 +813,9 @@ impl<'a> PartialATTF<'a> {
             let second_start = start + first.eval(start);
             let second_end = end + first.eval(end);
             let second = second.sub_plf(second_start, second_end);
-            let mut result = Vec::new();
+            let mut result = buffers.link_results.take();
             first.link(&second, start, end, &mut result);
-            return ATTFContainer::Exact(result.into());
+            return ATTFContainer::Exact(result);
         }
         // else the result will be approximated anyway
 
@@ -749,13 +828,11 @@ impl<'a> PartialATTF<'a> {
         let second_lower_end = end + first_lower.eval(end);
         let second_upper_end = end + first_upper.eval(end);
 
-        let mut result_lower = Vec::new();
-        let mut result_upper = Vec::new();
+        let mut result_lower = buffers.link_results.take();
+        let mut result_upper = buffers.link_results.take();
         first_lower.link(&second_lower.sub_plf(second_lower_start, second_lower_end), start, end, &mut result_lower);
         first_upper.link(&second_upper.sub_plf(second_upper_start, second_upper_end), start, end, &mut result_upper);
 
-        // TODO reusable buffers or something
-
         // linking two upper bounds is a valid upper bound, same for lower bounds
         ATTFContainer::Approx(result_lower, result_upper)
     }
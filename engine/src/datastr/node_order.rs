@@ -3,6 +3,8 @@
 use crate::datastr::graph::*;
 use crate::io::*;
 
+use rand::prelude::*;
+use std::path::Path;
 use std::sync::Arc;
 
 pub type Rank = NodeId;
@@ -37,7 +39,7 @@ impl NodeOrder {
             ranks[node as usize] = i as Rank;
         }
 
-        debug_assert_eq!(ranks.iter().position(|&rank| rank == n as Rank), None);
+        assert_eq!(ranks.iter().position(|&rank| rank == n as Rank), None, "node order is not a permutation");
 
         NodeOrder {
             node_order: node_order.into(),
@@ -55,7 +57,7 @@ impl NodeOrder {
             node_order[rank as usize] = node as NodeId;
         }
 
-        debug_assert_eq!(node_order.iter().position(|&node| node == n as NodeId), None);
+        assert_eq!(node_order.iter().position(|&node| node == n as NodeId), None, "ranks are not a permutation");
 
         NodeOrder {
             node_order: node_order.into(),
@@ -63,6 +65,42 @@ impl NodeOrder {
         }
     }
 
+    /// Create a random `NodeOrder` of `n` nodes, reproducible by its seed.
+    /// Useful for tests and small experiments without precomputed orders.
+    pub fn random(n: usize, seed: u64) -> NodeOrder {
+        let mut node_order = (0..n as NodeId).collect::<Vec<NodeId>>();
+        node_order.shuffle(&mut StdRng::seed_from_u64(seed));
+        Self::from_node_order(node_order)
+    }
+
+    /// Load a rank file (`rank[id]` contains the rank for node `id`) and check that it is a permutation of `0..n`.
+    pub fn from_rank_file(path: &Path, n: usize) -> std::io::Result<NodeOrder> {
+        let ranks = Vec::<Rank>::load_from(path)?;
+
+        if ranks.len() != n || !Self::is_valid_permutation(&ranks) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{} does not contain a permutation of {} nodes", path.display(), n),
+            ));
+        }
+
+        Ok(Self::from_ranks(ranks))
+    }
+
+    /// Does the given slice contain each id of `0..len` exactly once?
+    /// Applies to both node orders and rank vectors.
+    pub fn is_valid_permutation(ids: &[NodeId]) -> bool {
+        let mut seen = vec![false; ids.len()];
+
+        ids.iter().all(|&id| {
+            let valid = (id as usize) < ids.len() && !seen[id as usize];
+            if valid {
+                seen[id as usize] = true;
+            }
+            valid
+        })
+    }
+
     /// Get node order (rank -> node) as a slice
     pub fn order(&self) -> &[NodeId] {
         &self.node_order
@@ -102,6 +140,32 @@ impl Deconstruct for NodeOrder {
 
 impl Reconstruct for NodeOrder {
     fn reconstruct_with(loader: Loader) -> std::io::Result<Self> {
-        loader.load("ranks").map(Self::from_ranks)
+        let ranks: Vec<Rank> = loader.load("ranks")?;
+
+        if !Self::is_valid_permutation(&ranks) {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "ranks are not a permutation"));
+        }
+
+        Ok(Self::from_ranks(ranks))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_orders_are_valid_and_seeded() {
+        let order = NodeOrder::random(100, 42);
+        assert!(NodeOrder::is_valid_permutation(order.order()));
+        assert!(NodeOrder::is_valid_permutation(order.ranks()));
+        assert_eq!(order.order(), NodeOrder::random(100, 42).order());
+    }
+
+    #[test]
+    fn invalid_permutations() {
+        assert!(NodeOrder::is_valid_permutation(&[]));
+        assert!(!NodeOrder::is_valid_permutation(&[0, 0, 1]));
+        assert!(!NodeOrder::is_valid_permutation(&[0, 3, 1]));
     }
 }
@@ -1,6 +1,7 @@
 //! A fast resettable vector based on timestamps.
 
 use crate::{datastr::graph::*, util::in_range_option::*};
+use std::cmp::min;
 use std::ops::{Index, IndexMut};
 
 pub trait Reset: Clone {
@@ -36,52 +37,91 @@ impl<R: Reset> Reset for Option<R> {
     const DEFAULT: Self = None;
 }
 
-/// A fast resettable vector based on 32bit timestamps.
+/// Integer type of the epochs (timestamps) of a `TimestampedVector`.
+/// Epoch `0` is reserved for outdated entries, the others are used cyclically.
+pub trait Epoch: Copy + Eq + Debug {
+    const OUTDATED: Self;
+    /// number of usable epochs
+    const NUM_EPOCHS: usize;
+    fn from_usize(epoch: usize) -> Self;
+    fn as_usize(self) -> usize;
+}
+
+macro_rules! impl_epoch {
+    ($($t:ty),*) => {
+        $(
+            impl Epoch for $t {
+                const OUTDATED: Self = 0;
+                const NUM_EPOCHS: usize = <$t>::MAX as usize;
+
+                fn from_usize(epoch: usize) -> Self {
+                    epoch as $t
+                }
+
+                fn as_usize(self) -> usize {
+                    self as usize
+                }
+            }
+        )*
+    };
+}
+
+impl_epoch!(u8, u16, u32);
+
+/// A fast resettable vector based on timestamps, 32bit by default.
+/// Narrower epochs (`u16`, `u8`) reduce the memory of the timestamps, but wrap around more frequently.
+/// Instead of clearing everything on a wrap around, each reset clears one chunk of `len / NUM_EPOCHS` timestamps,
+/// so every chunk is cleared once per epoch cycle before outdated timestamps could become valid again.
 /// When only few entries are modified, a clearlist based approach may actually be preferable
 /// The elements can be modified through the index traits.
 /// Other modifications are not permitted.
 #[derive(Debug, Clone)]
-pub struct TimestampedVector<T> {
+pub struct TimestampedVector<T, E: Epoch = u32> {
     data: Vec<T>,
     // timestamp for current iteration. Up to date values will have this one
-    current: u32,
+    current: E,
     // current timestamp for each entry.
-    timestamps: Vec<u32>,
+    timestamps: Vec<E>,
     default: T,
 }
 
 impl<T: Reset> TimestampedVector<T> {
     /// Create a new `TimestampedVector` with `size` elements of the default
     pub fn new(size: usize) -> TimestampedVector<T> {
-        TimestampedVector {
-            data: vec![T::DEFAULT; size],
-            current: 0,
-            timestamps: vec![0; size],
-            default: T::DEFAULT,
-        }
+        Self::with_epochs(size)
     }
 
     pub fn new_with_default(size: usize, default: T) -> TimestampedVector<T> {
+        Self::with_epochs_and_default(size, default)
+    }
+}
+
+impl<T: Reset, E: Epoch> TimestampedVector<T, E> {
+    /// Create a new `TimestampedVector` with `size` elements of the default and epochs of type `E`
+    pub fn with_epochs(size: usize) -> TimestampedVector<T, E> {
+        Self::with_epochs_and_default(size, T::DEFAULT)
+    }
+
+    pub fn with_epochs_and_default(size: usize, default: T) -> TimestampedVector<T, E> {
         TimestampedVector {
             data: vec![default.clone(); size],
-            current: 0,
-            timestamps: vec![0; size],
+            current: E::from_usize(1),
+            timestamps: vec![E::OUTDATED; size],
             default,
         }
     }
 
     /// Reset all elements to the default.
-    /// Amortized O(1).
+    /// O(1 + len / NUM_EPOCHS).
     pub fn reset(&mut self) {
-        let (new, overflow) = self.current.overflowing_add(1);
-        self.current = new;
+        let next = self.current.as_usize() % E::NUM_EPOCHS + 1;
+        self.current = E::from_usize(next);
 
-        // we have to reset all values manually on overflow, because we now might encounter old timestamps again
-        if overflow {
-            for element in &mut self.data {
-                element.reset();
-            }
-        }
+        // clear the chunk belonging to the new epoch, its entries might still carry this epoch from the last cycle
+        let chunk_size = (self.timestamps.len() + E::NUM_EPOCHS - 1) / E::NUM_EPOCHS;
+        let chunk_start = min(self.timestamps.len(), (next - 1) * chunk_size);
+        let chunk_end = min(self.timestamps.len(), chunk_start + chunk_size);
+        self.timestamps[chunk_start..chunk_end].fill(E::OUTDATED);
     }
 
     /// Update an individual element.
@@ -103,7 +143,7 @@ impl<T: Reset> TimestampedVector<T> {
     }
 }
 
-impl<T: Reset> Index<usize> for TimestampedVector<T> {
+impl<T: Reset, E: Epoch> Index<usize> for TimestampedVector<T, E> {
     type Output = T;
 
     fn index(&self, index: usize) -> &T {
@@ -117,7 +157,7 @@ impl<T: Reset> Index<usize> for TimestampedVector<T> {
     }
 }
 
-impl<T: Reset> IndexMut<usize> for TimestampedVector<T> {
+impl<T: Reset, E: Epoch> IndexMut<usize> for TimestampedVector<T, E> {
     fn index_mut(&mut self, index: usize) -> &mut T {
         if self.timestamps[index] != self.current {
             self.timestamps[index] = self.current;
@@ -188,3 +228,25 @@ fn from_pair((w, ts): (Weight, u32)) -> u64 {
 fn to_pair(combined: u64) -> (Weight, u32) {
     unsafe { std::mem::transmute(combined) }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn narrow_epochs_survive_wrap_around() {
+        let mut vec = TimestampedVector::<Weight, u8>::with_epochs(1000);
+        vec[3] = 3;
+        vec[999] = 999;
+
+        for _ in 0..3 * u8::MAX as usize {
+            vec.reset();
+            assert_eq!(vec[3], INFINITY);
+            assert_eq!(vec[999], INFINITY);
+        }
+
+        vec[3] = 3;
+        assert_eq!(vec[3], 3);
+        assert_eq!(vec[999], INFINITY);
+    }
+}
@@ -1,5 +1,5 @@
 /// Number of dijkstra queries performed for experiments.
-/// Can be overriden through the NUM_DIJKSTRA_QUERIES env var.
+/// Can be overridden through the NUM_DIJKSTRA_QUERIES env var.
 pub fn num_dijkstra_queries() -> usize {
     std::env::var("NUM_DIJKSTRA_QUERIES").map_or(1000, |num| num.parse().unwrap())
 }
@@ -13,7 +13,7 @@ use std::{error::Error, path::Path};
 use rand::prelude::*;
 
 /// Number of queries performed for each experiment.
-/// Can be overriden through the CHPOT_NUM_QUERIES env var.
+/// Can be overridden through the CHPOT_NUM_QUERIES env var.
 pub fn num_queries() -> usize {
     std::env::var("CHPOT_NUM_QUERIES").map_or(10000, |num| num.parse().unwrap())
 }
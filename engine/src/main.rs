@@ -12,7 +12,7 @@ use rust_road_router::{
     cli::CliErr,
     datastr::{graph::*, node_order::NodeOrder},
     io::{Load, ReconstructPrepared},
-    report::benchmark::report_time,
+    report::benchmark::{num_benchmark_repetitions, report_benchmark},
 };
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -37,22 +37,23 @@ fn main() -> Result<(), Box<dyn Error>> {
     );
     let mut ch_server_with_own_ch = CHServer::new(contraction_hierarchy::contract(&graph, ch_order.clone()), ch_order);
 
+    let repetitions = num_benchmark_repetitions()?;
     for ((&from, &to), &ground_truth) in from.iter().zip(to.iter()).zip(ground_truth.iter()).take(100) {
         let ground_truth = match ground_truth {
             INFINITY => None,
             val => Some(val),
         };
 
-        report_time("simple dijkstra", || {
+        report_benchmark("simple dijkstra", repetitions, || {
             assert_eq!(simple_server.query(Query { from, to }).distance(), ground_truth);
         });
-        report_time("bidir dijkstra", || {
+        report_benchmark("bidir dijkstra", repetitions, || {
             assert_eq!(bi_dir_server.query(Query { from, to }).distance(), ground_truth);
         });
-        report_time("CH", || {
+        report_benchmark("CH", repetitions, || {
             assert_eq!(ch_server.query(Query { from, to }).distance(), ground_truth);
         });
-        report_time("own CH", || {
+        report_benchmark("own CH", repetitions, || {
             assert_eq!(ch_server_with_own_ch.query(Query { from, to }).distance(), ground_truth);
         });
     }
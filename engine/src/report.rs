@@ -319,3 +319,5 @@ pub fn enable_reporting(program: &str) -> ReportingGuard {
 
 pub mod benchmark;
 pub use benchmark::*;
+pub mod memory;
+pub mod progress;
@@ -1,8 +1,14 @@
 //! This module contains a few utilities to measure how long executing algorithms takes.
-//! It utilizes the `time` crate.
+//! All measurements are based on `std::time::Instant`.
+//! `benchmark` distinguishes the cold first run from warm repetitions, whose number is configured by `BENCHMARK_REPETITIONS`.
+//! `span` measures (possibly nested) phases of an algorithm, the times are aggregated over all threads and can be reported with `report_spans`.
 
+use super::memory::*;
 use super::*;
+use crate::cli::CliErr;
+use std::collections::BTreeMap;
 use std::sync::atomic::{compiler_fence, Ordering::SeqCst};
+use std::sync::Mutex;
 use std::time::*;
 
 /// This function will measure how long it takes to execute the given lambda,
@@ -11,12 +17,16 @@ pub fn report_time<Out, F: FnOnce() -> Out>(name: &str, f: F) -> Out {
     compiler_fence(SeqCst);
     let start = Instant::now();
     eprintln!("starting {}", name);
-    let res = f();
+    let (res, memory) = run_with_optional_memory(f);
     let t_passed = start.elapsed();
     compiler_fence(SeqCst);
     let t_passed = t_passed.as_secs_f64() * 1000.0;
     eprintln!("{} done - took: {}ms", name, t_passed);
     report!("running_time_ms", t_passed);
+    if let Some(memory) = memory {
+        eprintln!("{} memory - {}", name, memory);
+        memory.report("");
+    }
     res
 }
 
@@ -26,15 +36,29 @@ pub fn report_time_with_key<Out, F: FnOnce() -> Out>(name: &str, key: &str, f: F
     compiler_fence(SeqCst);
     let start = Instant::now();
     eprintln!("starting {}", name);
-    let res = f();
+    let (res, memory) = run_with_optional_memory(f);
     let t_passed = start.elapsed();
     compiler_fence(SeqCst);
     let t_passed = t_passed.as_secs_f64() * 1000.0;
     eprintln!("{} done - took: {}ms", name, t_passed);
     report!(format!("{}_running_time_ms", key), t_passed);
+    if let Some(memory) = memory {
+        eprintln!("{} memory - {}", name, memory);
+        memory.report(&format!("{}_", key));
+    }
     res
 }
 
+/// Run the lambda, measuring its memory usage if the `report-memory` feature is enabled
+fn run_with_optional_memory<Out, F: FnOnce() -> Out>(f: F) -> (Out, Option<MemoryMeasurement>) {
+    if cfg!(feature = "report-memory") {
+        let (res, memory) = measure_memory(f);
+        (res, Some(memory))
+    } else {
+        (f(), None)
+    }
+}
+
 /// This function will measure how long it takes to execute the given lambda,
 /// print the time and return the result of the lambda.
 pub fn silent_report_time<Out, F: FnOnce() -> Out>(f: F) -> Out {
@@ -69,6 +93,110 @@ pub fn measure<Out, F: FnOnce() -> Out>(f: F) -> (Out, Duration) {
     (res, start.elapsed())
 }
 
+/// Same as `measure`, but additionally measures the memory usage of the lambda, see `memory::measure_memory`.
+pub fn measure_with_memory<Out, F: FnOnce() -> Out>(f: F) -> (Out, Duration, MemoryMeasurement) {
+    let start = Instant::now();
+    let (res, memory) = measure_memory(f);
+    (res, start.elapsed(), memory)
+}
+
+/// Number of warm repetitions for benchmarks, configured through the `BENCHMARK_REPETITIONS` environment variable.
+/// Defaults to 0, i.e. only a single cold run is measured. Values which are not a non-negative integer are rejected.
+pub fn num_benchmark_repetitions() -> Result<usize, CliErr> {
+    match std::env::var("BENCHMARK_REPETITIONS") {
+        Ok(num) => num
+            .trim()
+            .parse()
+            .map_err(|_| CliErr("Invalid BENCHMARK_REPETITIONS, expected a non-negative integer")),
+        Err(_) => Ok(0),
+    }
+}
+
+/// Running times of a cold first run and the following warm repetitions of the same operation
+#[derive(Debug, Clone)]
+pub struct BenchmarkTimes {
+    pub cold: Duration,
+    pub warm: Vec<Duration>,
+}
+
+impl BenchmarkTimes {
+    /// Total number of runs, including the cold run
+    pub fn num_runs(&self) -> usize {
+        self.warm.len() + 1
+    }
+
+    /// Median of the warm repetitions, `None` if there were none
+    pub fn warm_median(&self) -> Option<Duration> {
+        if self.warm.is_empty() {
+            return None;
+        }
+
+        let mut warm = self.warm.clone();
+        warm.sort_unstable();
+        let mid = warm.len() / 2;
+
+        if warm.len() % 2 == 0 {
+            Some((warm[mid - 1] + warm[mid]) / 2)
+        } else {
+            Some(warm[mid])
+        }
+    }
+
+    /// The warm median if there were repetitions, the cold running time otherwise
+    pub fn representative(&self) -> Duration {
+        self.warm_median().unwrap_or(self.cold)
+    }
+
+    /// Report the representative, the cold and the median warm running time in the current reporting context
+    pub fn report(&self) {
+        report!("running_time_ms", self.representative().as_secs_f64() * 1000.0);
+        report!("cold_running_time_ms", self.cold.as_secs_f64() * 1000.0);
+        if let Some(median) = self.warm_median() {
+            report!("warm_median_running_time_ms", median.as_secs_f64() * 1000.0);
+            report!("num_warm_runs", self.warm.len());
+        }
+    }
+}
+
+impl std::fmt::Display for BenchmarkTimes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cold: {}ms", self.cold.as_secs_f64() * 1000.0)?;
+        if let Some(median) = self.warm_median() {
+            write!(f, ", warm median: {}ms ({} runs)", median.as_secs_f64() * 1000.0, self.warm.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// This function will run the given lambda once on cold caches and `repetitions` more times afterwards.
+/// Returns the result of the last run together with the running times of all runs.
+/// Results of previous runs are dropped before the next run starts.
+pub fn benchmark<Out, F: FnMut() -> Out>(repetitions: usize, mut f: F) -> (Out, BenchmarkTimes) {
+    compiler_fence(SeqCst);
+    let (mut res, cold) = measure(&mut f);
+    let mut warm = Vec::with_capacity(repetitions);
+
+    for _ in 0..repetitions {
+        drop(res);
+        let (next, time) = measure(&mut f);
+        res = next;
+        warm.push(time);
+    }
+    compiler_fence(SeqCst);
+
+    (res, BenchmarkTimes { cold, warm })
+}
+
+/// Same as `report_time`, but with a cold first run and `repetitions` warm runs.
+/// Both the cold and the median warm running time are printed and reported.
+pub fn report_benchmark<Out, F: FnMut() -> Out>(name: &str, repetitions: usize, f: F) -> Out {
+    eprintln!("starting {}", name);
+    let (res, times) = benchmark(repetitions, f);
+    eprintln!("{} done - {}", name, times);
+    times.report();
+    res
+}
+
 /// A struct to repeatedly measure the time passed since the timer was started
 #[derive(Debug)]
 pub struct Timer {
@@ -107,3 +235,154 @@ impl Timer {
         self.start.elapsed()
     }
 }
+
+/// Aggregated running times of all spans with the same path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpanTimes {
+    pub count: usize,
+    pub total: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl SpanTimes {
+    fn new(time: Duration) -> Self {
+        SpanTimes {
+            count: 1,
+            total: time,
+            min: time,
+            max: time,
+        }
+    }
+
+    fn add(&mut self, time: Duration) {
+        self.count += 1;
+        self.total += time;
+        self.min = self.min.min(time);
+        self.max = self.max.max(time);
+    }
+
+    /// Average running time of a single span
+    pub fn avg(&self) -> Duration {
+        self.total / self.count as u32
+    }
+}
+
+static SPAN_TIMES: Mutex<BTreeMap<String, SpanTimes>> = Mutex::new(BTreeMap::new());
+
+thread_local! {
+    static SPAN_STACK: RefCell<Vec<String>> = RefCell::new(Vec::new());
+}
+
+/// Guard of a running span, the passed time is recorded when it is dropped
+#[must_use]
+pub struct SpanGuard {
+    path: String,
+    start: Instant,
+}
+
+impl SpanGuard {
+    /// Return the time passed since the span was started
+    pub fn get_passed(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+impl Drop for SpanGuard {
+    fn drop(&mut self) {
+        let time = self.start.elapsed();
+        SPAN_STACK.with(|stack| stack.borrow_mut().pop());
+
+        let mut times = SPAN_TIMES.lock().unwrap();
+        match times.get_mut(&self.path) {
+            Some(entry) => entry.add(time),
+            None => {
+                times.insert(self.path.clone(), SpanTimes::new(time));
+            }
+        }
+    }
+}
+
+/// Start a named span, which ends when the returned guard is dropped.
+/// Spans started while another span of the same thread is running are nested, their path is `outer/inner`.
+/// Spans of other threads (e.g. rayon workers) start at the root, all spans with the same path are aggregated.
+pub fn span(name: &str) -> SpanGuard {
+    let path = SPAN_STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        let path = match stack.last() {
+            Some(parent) => format!("{}/{}", parent, name),
+            None => name.to_string(),
+        };
+        stack.push(path.clone());
+        path
+    });
+
+    SpanGuard { path, start: Instant::now() }
+}
+
+/// Measure the given lambda in a named span and return its result
+pub fn with_span<Out, F: FnOnce() -> Out>(name: &str, f: F) -> Out {
+    let _span = span(name);
+    f()
+}
+
+/// Snapshot of the aggregated times of all finished spans, ordered by their path
+pub fn span_times() -> BTreeMap<String, SpanTimes> {
+    SPAN_TIMES.lock().unwrap().clone()
+}
+
+/// Discard the aggregated times of all finished spans
+pub fn reset_spans() {
+    SPAN_TIMES.lock().unwrap().clear();
+}
+
+/// Report the aggregated times of all finished spans under the key `spans` in the current reporting context
+/// and print them to stderr
+pub fn report_spans() {
+    let times = span_times();
+    let mut object = Map::new();
+
+    for (path, entry) in &times {
+        eprintln!(
+            "{}: {}x, total: {}ms, avg: {}ms",
+            path,
+            entry.count,
+            entry.total.as_secs_f64() * 1000.0,
+            entry.avg().as_secs_f64() * 1000.0
+        );
+        object.insert(
+            path.clone(),
+            json!({
+                "count": entry.count,
+                "total_running_time_ms": entry.total.as_secs_f64() * 1000.0,
+                "min_running_time_ms": entry.min.as_secs_f64() * 1000.0,
+                "max_running_time_ms": entry.max.as_secs_f64() * 1000.0,
+            }),
+        );
+    }
+
+    report_silent("spans".to_string(), Value::Object(object));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_spans_are_aggregated_across_threads() {
+        let run = || {
+            let _outer = span("test_outer");
+            for _ in 0..2 {
+                with_span("inner", || ());
+            }
+        };
+        run();
+        std::thread::spawn(run).join().unwrap();
+
+        let times = span_times();
+        assert_eq!(times["test_outer"].count, 2);
+        assert_eq!(times["test_outer/inner"].count, 4);
+        assert!(times["test_outer"].total >= times["test_outer/inner"].total);
+        assert!(SPAN_STACK.with(|stack| stack.borrow().is_empty()));
+    }
+}
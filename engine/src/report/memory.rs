@@ -0,0 +1,208 @@
+//! Memory measurements to be reported alongside running times.
+//!
+//! The peak resident set size is read from `/proc/self/status` and thus only available on Linux.
+//! Allocations are only tracked, if the binary installs the `CountingAllocator`:
+//!
+//! ```ignore
+//! #[global_allocator]
+//! static ALLOCATOR: rust_road_router::report::memory::CountingAllocator = rust_road_router::report::memory::CountingAllocator;
+//! ```
+//!
+//! With the `report-memory` feature, `report_time` and `report_time_with_key` report memory measurements as well.
+
+use super::*;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+static NUM_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+// number of currently running `measure_memory` calls
+static MEASUREMENT_DEPTH: AtomicUsize = AtomicUsize::new(0);
+
+/// Global allocator which delegates to the system allocator and counts allocations and allocated bytes
+#[derive(Debug, Default)]
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    fn track_alloc(size: usize) {
+        NUM_ALLOCATIONS.fetch_add(1, Relaxed);
+        let allocated = ALLOCATED_BYTES.fetch_add(size, Relaxed) + size;
+        PEAK_ALLOCATED_BYTES.fetch_max(allocated, Relaxed);
+    }
+
+    fn track_dealloc(size: usize) {
+        ALLOCATED_BYTES.fetch_sub(size, Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::track_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::track_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::track_dealloc(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            Self::track_dealloc(layout.size());
+            Self::track_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// Whether the `CountingAllocator` is installed, i.e. has seen any allocation
+pub fn allocations_tracked() -> bool {
+    NUM_ALLOCATIONS.load(Relaxed) > 0
+}
+
+/// Currently allocated bytes, 0 if the `CountingAllocator` is not installed
+pub fn allocated_bytes() -> usize {
+    ALLOCATED_BYTES.load(Relaxed)
+}
+
+/// Peak resident set size of the process in KiB, `None` if not available on this platform
+pub fn peak_rss_kib() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find(|line| line.starts_with("VmHWM:"))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|value| value.parse().ok())
+}
+
+/// Current resident set size of the process in KiB, `None` if not available on this platform
+pub fn current_rss_kib() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find(|line| line.starts_with("VmRSS:"))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|value| value.parse().ok())
+}
+
+/// Current memory usage in bytes.
+/// The allocated bytes if the `CountingAllocator` is installed, otherwise the resident set size.
+pub fn current_memory_bytes() -> Option<usize> {
+    if allocations_tracked() {
+        Some(allocated_bytes())
+    } else {
+        current_rss_kib().map(|kib| kib * 1024)
+    }
+}
+
+/// Reset the peak resident set size of the process to the current one.
+/// Best effort, this requires Linux and might not be permitted.
+pub fn reset_peak_rss() {
+    let _ = std::fs::write("/proc/self/clear_refs", "5");
+}
+
+/// Memory usage of a measured operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryMeasurement {
+    /// peak resident set size of the process during the operation in KiB
+    pub peak_rss_kib: Option<usize>,
+    /// peak of allocated bytes during the operation minus the bytes allocated at its start
+    pub peak_allocated_bytes: Option<usize>,
+    /// bytes still allocated after the operation minus the bytes allocated at its start, may be negative
+    pub retained_bytes: Option<isize>,
+    /// number of allocations during the operation
+    pub num_allocations: Option<usize>,
+}
+
+impl MemoryMeasurement {
+    /// Report all available values in the current reporting context, keys are prefixed with `prefix`
+    pub fn report(&self, prefix: &str) {
+        if let Some(peak_rss) = self.peak_rss_kib {
+            report!(format!("{}peak_rss_kib", prefix), peak_rss);
+        }
+        if let (Some(peak), Some(retained), Some(num)) = (self.peak_allocated_bytes, self.retained_bytes, self.num_allocations) {
+            report!(format!("{}peak_allocated_bytes", prefix), peak);
+            report!(format!("{}retained_bytes", prefix), retained);
+            report!(format!("{}num_allocations", prefix), num);
+        }
+    }
+}
+
+impl std::fmt::Display for MemoryMeasurement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.peak_rss_kib {
+            Some(peak_rss) => write!(f, "peak rss: {}MiB", peak_rss / 1024)?,
+            None => write!(f, "peak rss: n/a")?,
+        }
+        if let (Some(peak), Some(num)) = (self.peak_allocated_bytes, self.num_allocations) {
+            write!(f, ", peak allocated: {}MiB, {} allocations", peak / (1024 * 1024), num)?;
+        }
+        Ok(())
+    }
+}
+
+/// This function will measure the memory usage of the given lambda
+/// and return a tuple of the result of the lambda and the measurement.
+/// The allocation peak is tracked globally, so measurements must not run concurrently.
+/// Nested measurements restore the peak of the enclosing one afterwards. The peak resident set size can't be restored,
+/// so it is only measured at the outermost level (`None` for nested measurements).
+pub fn measure_memory<Out, F: FnOnce() -> Out>(f: F) -> (Out, MemoryMeasurement) {
+    let outermost = MEASUREMENT_DEPTH.fetch_add(1, Relaxed) == 0;
+    if outermost {
+        reset_peak_rss();
+    }
+    let start_allocated = ALLOCATED_BYTES.load(Relaxed);
+    let start_num_allocations = NUM_ALLOCATIONS.load(Relaxed);
+    let outer_peak = PEAK_ALLOCATED_BYTES.swap(start_allocated, Relaxed);
+
+    let res = f();
+
+    let tracked = allocations_tracked();
+    let end_allocated = ALLOCATED_BYTES.load(Relaxed);
+    // the peak of the enclosing measurement is the maximum of its own peak and the one of this measurement
+    let peak_allocated = PEAK_ALLOCATED_BYTES.fetch_max(outer_peak, Relaxed);
+    MEASUREMENT_DEPTH.fetch_sub(1, Relaxed);
+
+    let measurement = MemoryMeasurement {
+        peak_rss_kib: peak_rss_kib().filter(|_| outermost),
+        peak_allocated_bytes: Some(peak_allocated.saturating_sub(start_allocated)).filter(|_| tracked),
+        retained_bytes: Some(end_allocated as isize - start_allocated as isize).filter(|_| tracked),
+        num_allocations: Some(NUM_ALLOCATIONS.load(Relaxed) - start_num_allocations).filter(|_| tracked),
+    };
+
+    (res, measurement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn peak_rss_is_available_on_linux() {
+        if cfg!(target_os = "linux") {
+            assert!(peak_rss_kib().unwrap() > 0);
+            assert!(current_memory_bytes().unwrap() > 0);
+        }
+    }
+
+    #[test]
+    fn untracked_allocations_are_not_reported() {
+        let (data, measurement) = measure_memory(|| vec![0u8; 1 << 20]);
+        assert_eq!(data.len(), 1 << 20);
+        assert_eq!(measurement.num_allocations, None);
+        assert_eq!(measurement.peak_allocated_bytes, None);
+    }
+}
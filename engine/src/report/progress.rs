@@ -0,0 +1,63 @@
+//! Progress reporting for long-running preprocessing steps.
+//!
+//! Preprocessing routines take a callback which receives the number of processed items and the total number of items.
+//! The callback may be invoked concurrently from several threads.
+
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+use std::time::Instant;
+
+/// Callback receiving the number of processed items and the total number of items.
+pub type ProgressCallback<'a> = &'a (dyn Fn(usize, usize) + Sync);
+
+/// Callback which ignores all progress updates.
+pub fn ignore_progress(_done: usize, _total: usize) {}
+
+/// Prints progress in fixed percentage steps together with an estimate of the remaining time.
+#[derive(Debug)]
+pub struct ProgressPrinter {
+    name: String,
+    step_percentage: usize,
+    start: Instant,
+    last_printed: AtomicUsize,
+}
+
+impl ProgressPrinter {
+    pub fn new(name: &str) -> Self {
+        Self::with_step(name, 5)
+    }
+
+    pub fn with_step(name: &str, step_percentage: usize) -> Self {
+        assert!(step_percentage > 0 && step_percentage <= 100);
+        Self {
+            name: name.to_string(),
+            step_percentage,
+            start: Instant::now(),
+            last_printed: AtomicUsize::new(0),
+        }
+    }
+
+    /// Restart the timer, e.g. when the printer is reused for a second run.
+    pub fn reset(&mut self) {
+        self.start = Instant::now();
+        self.last_printed = AtomicUsize::new(0);
+    }
+
+    pub fn update(&self, done: usize, total: usize) {
+        if total == 0 {
+            return;
+        }
+
+        let percentage = (done.min(total) * 100 / total) / self.step_percentage * self.step_percentage;
+        let last_printed = self.last_printed.load(Relaxed);
+
+        // only one thread may print each step
+        if percentage > last_printed && self.last_printed.compare_exchange(last_printed, percentage, Relaxed, Relaxed).is_ok() {
+            let elapsed = self.start.elapsed().as_secs_f64();
+            let remaining = elapsed * (total - done.min(total)) as f64 / done.max(1) as f64;
+            eprintln!(
+                "{}: {}% ({} of {}) - elapsed: {:.1}s, ETA: {:.1}s",
+                self.name, percentage, done, total, elapsed, remaining
+            );
+        }
+    }
+}
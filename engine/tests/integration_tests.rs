@@ -64,3 +64,33 @@ fn bidir_dijkstra_stopping_crit() {
 
     assert_eq!(server.query(Query { from: 0, to: 4 }).distance(), Some(12));
 }
+
+#[test]
+fn ch_with_limited_witness_searches_is_correct() {
+    use rust_road_router::algo::contraction_hierarchy::{self, query::Server as CHServer, ContractionParams};
+
+    let order = contraction_hierarchy::greedy_order(&graph());
+    let limits = [
+        ContractionParams::default(),
+        ContractionParams::limited(1, 1),
+        ContractionParams::limited(3, u32::MAX),
+    ];
+
+    for params in limits {
+        let mut dijkstra = DijkServer::<_, DefaultOps>::new(graph());
+        let mut server = CHServer::new(contraction_hierarchy::contract_with_params(&graph(), order.clone(), &params), order.clone());
+
+        for from in 0..6 {
+            for to in 0..6 {
+                assert_eq!(
+                    server.query(Query { from, to }).distance(),
+                    dijkstra.query(Query { from, to }).distance(),
+                    "{:?}: {} -> {}",
+                    params,
+                    from,
+                    to
+                );
+            }
+        }
+    }
+}